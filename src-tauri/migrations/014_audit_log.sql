@@ -0,0 +1,19 @@
+-- Migration 014: Generic audit log for entity mutations
+-- Requirement edits keep their richer snapshot trail in requirement_history;
+-- this table records a one-line summary for every entity type.
+-- No FK to projects: a project's own deletion is recorded here too.
+
+CREATE TABLE IF NOT EXISTS audit_log (
+    id          TEXT PRIMARY KEY,           -- UUIDv4
+    project_id  TEXT NOT NULL,
+    entity_type TEXT NOT NULL,              -- project | node | edge | diagram | diagram_element | document | baseline
+    entity_id   TEXT NOT NULL,
+    action      TEXT NOT NULL,              -- create | update | delete
+    actor       TEXT NOT NULL DEFAULT 'system',
+    summary     TEXT NOT NULL DEFAULT '',   -- short human-readable description
+    changed_at  TEXT NOT NULL               -- ISO-8601 UTC
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_project ON audit_log(project_id, changed_at DESC);
+CREATE INDEX IF NOT EXISTS idx_audit_log_entity  ON audit_log(entity_id, changed_at DESC);
+CREATE INDEX IF NOT EXISTS idx_audit_log_type    ON audit_log(project_id, entity_type, changed_at DESC);
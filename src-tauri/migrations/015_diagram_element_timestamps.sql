@@ -0,0 +1,10 @@
+-- Migration 015: created/modified tracking for diagram elements
+-- Existing rows inherit their diagram's timestamps.
+
+ALTER TABLE diagram_elements ADD COLUMN created_at TEXT;
+ALTER TABLE diagram_elements ADD COLUMN modified_at TEXT;
+
+UPDATE diagram_elements
+SET created_at  = (SELECT d.created_at  FROM diagrams d WHERE d.id = diagram_elements.diagram_id),
+    modified_at = (SELECT d.modified_at FROM diagrams d WHERE d.id = diagram_elements.diagram_id)
+WHERE created_at IS NULL;
@@ -0,0 +1,16 @@
+-- Block value properties (SysML ownedAttributes, e.g. "mass : kg = 12.5")
+CREATE TABLE IF NOT EXISTS block_properties (
+    id                  TEXT PRIMARY KEY,
+    block_node_id       TEXT NOT NULL REFERENCES nodes(id) ON DELETE CASCADE,
+    name                TEXT NOT NULL,
+    -- typing ValueType node; NULL when untyped or the ValueType was deleted
+    value_type_node_id  TEXT REFERENCES nodes(id) ON DELETE SET NULL,
+    type_name           TEXT NOT NULL DEFAULT '',   -- display type, e.g. "Mass"
+    value               TEXT NOT NULL DEFAULT '',   -- default value, e.g. "12.5"
+    unit                TEXT NOT NULL DEFAULT '',   -- e.g. "kg"
+    created_at          TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_block_properties_block ON block_properties(block_node_id);
+CREATE INDEX IF NOT EXISTS idx_block_properties_value_type ON block_properties(value_type_node_id)
+    WHERE value_type_node_id IS NOT NULL;
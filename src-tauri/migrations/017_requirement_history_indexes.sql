@@ -0,0 +1,5 @@
+-- Migration 017: indexes for project-wide requirement history queries
+-- ("everything that changed this week", "everything the AI changed").
+
+CREATE INDEX idx_req_history_changed_at ON requirement_history(changed_at DESC);
+CREATE INDEX idx_req_history_source ON requirement_history(project_id, change_source, changed_at DESC);
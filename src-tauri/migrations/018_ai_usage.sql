@@ -0,0 +1,17 @@
+-- Migration 018: per-call AI token usage
+-- One row per provider completion. Cache columns are only filled by
+-- providers that support prompt caching (Anthropic); NULL elsewhere.
+
+CREATE TABLE IF NOT EXISTS ai_usage (
+    id                 TEXT PRIMARY KEY,    -- UUIDv4
+    provider           TEXT NOT NULL,       -- anthropic | ollama
+    model              TEXT NOT NULL,
+    purpose            TEXT NOT NULL,       -- extract | quality_pass | allocation_pass | diagram
+    input_tokens       INTEGER,
+    output_tokens      INTEGER,
+    cache_read_tokens  INTEGER,             -- prompt tokens served from cache
+    cache_write_tokens INTEGER,             -- prompt tokens written to cache
+    created_at         TEXT NOT NULL        -- ISO-8601 UTC
+);
+
+CREATE INDEX IF NOT EXISTS idx_ai_usage_created ON ai_usage(created_at DESC);
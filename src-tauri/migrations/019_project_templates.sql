@@ -0,0 +1,12 @@
+-- Migration 019: user-saved project templates
+-- Built-in templates live in code (core::templates); only templates saved
+-- from a project are stored here.
+
+CREATE TABLE IF NOT EXISTS project_templates (
+    id          TEXT PRIMARY KEY,           -- UUIDv4
+    name        TEXT NOT NULL,
+    description TEXT NOT NULL DEFAULT '',
+    include     TEXT NOT NULL DEFAULT '[]', -- JSON array of captured parts
+    content     TEXT NOT NULL,              -- JSON ProjectTemplateContent
+    created_at  TEXT NOT NULL               -- ISO-8601 UTC
+);
@@ -0,0 +1,25 @@
+-- Migration 020: items tracked in external tools (Jira issues, generic
+-- CSV lists) and their links to model nodes
+
+CREATE TABLE IF NOT EXISTS external_items (
+    id           TEXT PRIMARY KEY,           -- UUIDv4
+    project_id   TEXT NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    source       TEXT NOT NULL DEFAULT 'generic', -- 'jira' | 'generic'
+    external_key TEXT NOT NULL,              -- e.g. "VER-123"; unique per project
+    title        TEXT NOT NULL DEFAULT '',
+    url          TEXT,
+    status       TEXT NOT NULL DEFAULT '',
+    synced_at    TEXT NOT NULL               -- ISO-8601 UTC of the last import
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_external_items_key
+    ON external_items(project_id, external_key);
+
+CREATE TABLE IF NOT EXISTS external_item_links (
+    item_id    TEXT NOT NULL REFERENCES external_items(id) ON DELETE CASCADE,
+    node_id    TEXT NOT NULL REFERENCES nodes(id) ON DELETE CASCADE,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (item_id, node_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_external_item_links_node ON external_item_links(node_id);
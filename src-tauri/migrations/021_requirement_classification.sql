@@ -0,0 +1,4 @@
+-- Migration 021: store the requirement classification assigned by the AI
+-- quality pass (a label from the project's taxonomy setting)
+
+ALTER TABLE nodes ADD COLUMN req_classification TEXT;
@@ -0,0 +1,16 @@
+-- Migration 022: reviewer decisions on extracted requirements, kept per
+-- extraction session so a long review survives restarts
+
+CREATE TABLE IF NOT EXISTS extraction_review_state (
+    session_id  TEXT PRIMARY KEY,            -- chosen by the frontend per extraction run
+    project_id  TEXT NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    document_id TEXT,                        -- source document, if any
+    source_name TEXT NOT NULL DEFAULT '',    -- document name recorded on imported nodes
+    results     TEXT NOT NULL DEFAULT '[]',  -- JSON array: raw extraction results, never rewritten
+    items       TEXT NOT NULL DEFAULT '[]',  -- JSON array: decisions + edited fields (deltas only)
+    created_at  TEXT NOT NULL,
+    updated_at  TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_extraction_review_project
+    ON extraction_review_state(project_id);
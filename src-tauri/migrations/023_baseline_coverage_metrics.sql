@@ -0,0 +1,4 @@
+-- Migration 023: cache requirement coverage metrics per baseline.
+-- NULL until coverage_trend first evaluates the snapshot.
+
+ALTER TABLE model_baselines ADD COLUMN coverage_metrics TEXT;
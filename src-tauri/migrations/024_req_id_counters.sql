@@ -0,0 +1,9 @@
+-- Migration 024: per-section sequence counters for section-based
+-- requirement ids (req_id.scheme = "section_based")
+
+CREATE TABLE IF NOT EXISTS req_id_counters (
+    project_id TEXT NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    prefix     TEXT NOT NULL,     -- the id pattern rendered without its number, e.g. "SOW-3.2.1-R{seq}"
+    last_seq   INTEGER NOT NULL,  -- last number handed out
+    PRIMARY KEY (project_id, prefix)
+);
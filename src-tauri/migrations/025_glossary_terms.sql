@@ -0,0 +1,15 @@
+-- Migration 025: project glossary of approved terminology, checked
+-- against requirement text by terminology_check
+
+CREATE TABLE IF NOT EXISTS glossary_terms (
+    id             TEXT PRIMARY KEY,
+    project_id     TEXT NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    term           TEXT NOT NULL,                -- display name, e.g. "GCS"
+    approved       TEXT NOT NULL,                -- approved form, e.g. "Ground Control Station"
+    variants       TEXT NOT NULL DEFAULT '[]',   -- JSON array of forbidden variants
+    case_sensitive INTEGER NOT NULL DEFAULT 0,
+    created_at     TEXT NOT NULL,
+    modified_at    TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_glossary_terms_project ON glossary_terms(project_id);
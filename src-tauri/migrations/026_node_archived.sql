@@ -0,0 +1,7 @@
+-- Migration 026: archived flag on nodes. Archived nodes keep their edges
+-- and history but are left out of listings, validation and diagram
+-- population; set only by archive_nodes / unarchive_nodes.
+
+ALTER TABLE nodes ADD COLUMN archived INTEGER NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS idx_nodes_archived ON nodes(project_id, archived);
@@ -0,0 +1,5 @@
+-- Migration 027: index for looking a requirement up by its req_id within a
+-- project (find_node_by_req_id, reference resolution)
+
+CREATE INDEX IF NOT EXISTS idx_nodes_project_req_id
+    ON nodes(project_id, req_id) WHERE req_id IS NOT NULL;
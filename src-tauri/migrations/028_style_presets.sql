@@ -0,0 +1,14 @@
+-- Migration 028: named diagram style presets, merged into the
+-- style_overrides of matching elements by apply_style_preset
+
+CREATE TABLE IF NOT EXISTS style_presets (
+    id           TEXT PRIMARY KEY,
+    project_id   TEXT NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    name         TEXT NOT NULL,
+    target_kinds TEXT NOT NULL DEFAULT '[]',   -- JSON array of node kinds; empty = all
+    style        TEXT NOT NULL DEFAULT '{}',   -- JSON object of allowlisted style keys
+    created_at   TEXT NOT NULL,
+    modified_at  TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_style_presets_project ON style_presets(project_id);
@@ -0,0 +1,7 @@
+-- Migration 029: heading hierarchy for document sections, filled in by
+-- parse_document_sections
+
+ALTER TABLE document_sections ADD COLUMN parent_section_id TEXT;   -- enclosing heading; NULL at top level
+ALTER TABLE document_sections ADD COLUMN section_level INTEGER NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS idx_doc_sections_parent ON document_sections(parent_section_id);
@@ -0,0 +1,13 @@
+-- Migration 030: one row per machine that has this database open, for
+-- attribution and for noticing another instance on a shared database.
+-- Refreshed by a heartbeat while the app runs; removed on clean exit.
+
+CREATE TABLE IF NOT EXISTS presence (
+    machine            TEXT PRIMARY KEY,         -- host name
+    user               TEXT NOT NULL DEFAULT '', -- configured identity; '' = none
+    read_only_fallback INTEGER NOT NULL DEFAULT 0,
+    started_at         TEXT NOT NULL,
+    last_seen          TEXT NOT NULL             -- ISO-8601 UTC
+);
+
+CREATE INDEX IF NOT EXISTS idx_presence_last_seen ON presence(last_seen);
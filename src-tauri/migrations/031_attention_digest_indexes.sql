@@ -0,0 +1,11 @@
+-- Migration 031: indexes for the attention digest's dated lookups, so
+-- attention_digest stays cheap enough to run on every launch
+
+CREATE INDEX IF NOT EXISTS idx_req_comments_open
+    ON req_comments(project_id, resolved_at, created_at);
+CREATE INDEX IF NOT EXISTS idx_review_sessions_status
+    ON review_sessions(project_id, status, created_at);
+CREATE INDEX IF NOT EXISTS idx_suspect_links_open
+    ON suspect_links(project_id, resolved_at, flagged_at);
+CREATE INDEX IF NOT EXISTS idx_nodes_req_status
+    ON nodes(project_id, kind, req_status, modified_at);
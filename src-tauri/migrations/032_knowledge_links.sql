@@ -0,0 +1,16 @@
+-- Migration 032: [[wiki-links]] from subsystem knowledge pages to nodes,
+-- recorded by resolve_knowledge_links. A renamed target flags its pages
+-- instead of rewriting their text.
+
+CREATE TABLE IF NOT EXISTS knowledge_links (
+    page_id     TEXT NOT NULL REFERENCES subsystem_knowledge(id) ON DELETE CASCADE,
+    node_id     TEXT NOT NULL REFERENCES nodes(id) ON DELETE CASCADE,
+    link_text   TEXT NOT NULL,              -- what is between the brackets
+    node_name   TEXT NOT NULL,              -- target name when resolved
+    node_req_id TEXT NOT NULL DEFAULT '',   -- target req_id when resolved
+    PRIMARY KEY (page_id, node_id, link_text)
+);
+
+CREATE INDEX IF NOT EXISTS idx_knowledge_links_node ON knowledge_links(node_id);
+
+ALTER TABLE subsystem_knowledge ADD COLUMN stale_links INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,14 @@
+-- Migration 033: layout editing sessions. Each captures the diagram's
+-- element geometry when it starts, so it can be cancelled or restored later.
+
+CREATE TABLE IF NOT EXISTS layout_sessions (
+    id          TEXT PRIMARY KEY,
+    diagram_id  TEXT NOT NULL REFERENCES diagrams(id) ON DELETE CASCADE,
+    status      TEXT NOT NULL DEFAULT 'open',  -- open | committed | cancelled
+    snapshot    TEXT NOT NULL DEFAULT '[]',    -- JSON: [ { node_id, x, y, width, height } ]
+    started_at  TEXT NOT NULL,
+    ended_at    TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_layout_sessions_diagram
+    ON layout_sessions(diagram_id, started_at);
@@ -0,0 +1,14 @@
+-- Migration 034: cached AI requirement extraction results, so re-running
+-- extraction on an unchanged document skips the model. created_at is kept
+-- for age-based eviction.
+
+CREATE TABLE IF NOT EXISTS ai_extraction_cache (
+    project_id  TEXT NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    cache_key   TEXT NOT NULL,      -- SHA-256 hex; see core::extraction::cache_key
+    result      TEXT NOT NULL,      -- JSON: { results: [...], graphrag?: {...} }
+    created_at  TEXT NOT NULL,      -- ISO-8601 UTC
+    PRIMARY KEY (project_id, cache_key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_ai_extraction_cache_created
+    ON ai_extraction_cache(created_at);
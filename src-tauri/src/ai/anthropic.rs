@@ -28,7 +28,7 @@ impl AnthropicProvider {
         self
     }
 
-    fn build_body(&self, prompt: &Prompt, stream: bool) -> Value {
+    pub(crate) fn build_body(&self, prompt: &Prompt, stream: bool) -> Value {
         let messages: Vec<Value> = prompt
             .messages
             .iter()
@@ -47,7 +47,16 @@ impl AnthropicProvider {
         });
 
         if let Some(sys) = &prompt.system {
-            body["system"] = json!(sys);
+            body["system"] = if prompt.cache_system {
+                // Block form is required to attach cache_control.
+                json!([{
+                    "type": "text",
+                    "text": sys,
+                    "cache_control": { "type": "ephemeral" },
+                }])
+            } else {
+                json!(sys)
+            };
         }
 
         if stream {
@@ -86,6 +95,10 @@ impl AIProvider for AnthropicProvider {
             model: resp["model"].as_str().unwrap_or(&self.model).to_string(),
             input_tokens: resp["usage"]["input_tokens"].as_u64().map(|v| v as u32),
             output_tokens: resp["usage"]["output_tokens"].as_u64().map(|v| v as u32),
+            cache_read_tokens: resp["usage"]["cache_read_input_tokens"].as_u64().map(|v| v as u32),
+            cache_write_tokens: resp["usage"]["cache_creation_input_tokens"]
+                .as_u64()
+                .map(|v| v as u32),
         })
     }
 
@@ -136,4 +149,8 @@ impl AIProvider for AnthropicProvider {
     fn name(&self) -> &str {
         "anthropic"
     }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
 }
@@ -1,5 +1,17 @@
+use super::ollama::OllamaProvider;
 use anyhow::{Context, Result};
 use graphrag_core::GraphRAG;
+use serde::Serialize;
+
+/// Tokens kept for the extraction instructions and the JSON answer.
+const RESERVED_TOKENS: usize = 1600;
+
+/// Assumed when Ollama doesn't report the model's context length.
+const DEFAULT_CONTEXT_TOKENS: u32 = 4096;
+
+/// Upper bound however large the window; past this the hints crowd out
+/// the document rather than help.
+const MAX_CONTEXT_CHARS: usize = 8000;
 
 /// Configuration for GraphRAG context enrichment.
 pub struct GraphRagExtractorConfig {
@@ -22,12 +34,103 @@ impl Default for GraphRagExtractorConfig {
     }
 }
 
+/// What GraphRAG contributed to an extraction. Returned as `graphrag`
+/// next to the results, and emitted on `events::GRAPHRAG_STATUS` before
+/// the graph is built, when `used` means enrichment is about to run.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GraphRagStatus {
+    pub used: bool,
+    pub entities: usize,
+    pub relationships: usize,
+    /// Why enrichment was skipped, phrased so the user can act on it.
+    pub skipped_reason: Option<String>,
+    /// Characters of graph context injected into each prompt.
+    pub context_chars: usize,
+}
+
+/// Graph context for the extraction prompt, with the size of the graph it
+/// was drawn from.
+pub struct EnrichmentContext {
+    pub text: String,
+    pub entities: usize,
+    pub relationships: usize,
+}
+
+/// Check Ollama is reachable and both models are pulled before building a
+/// graph, so a skip names the missing piece. Returns the chat model's
+/// context length when Ollama reports it.
+pub async fn probe_models(
+    cfg: &GraphRagExtractorConfig,
+) -> std::result::Result<Option<u32>, String> {
+    let chat = OllamaProvider::new(&cfg.ollama_chat_model, Some(cfg.ollama_base_url.clone()));
+    let installed = chat
+        .installed_models()
+        .await
+        .map_err(|e| format!("Ollama is not reachable at {}: {e}", cfg.ollama_base_url))?;
+    for (role, model) in [
+        ("embed", &cfg.ollama_embed_model),
+        ("chat", &cfg.ollama_chat_model),
+    ] {
+        if !is_pulled(&installed, model) {
+            return Err(format!(
+                "{role} model {model} not pulled (run: ollama pull {model})"
+            ));
+        }
+    }
+    Ok(chat.context_length().await.unwrap_or(None))
+}
+
+/// `installed` names carry a tag; a bare name means ":latest".
+fn is_pulled(installed: &[String], model: &str) -> bool {
+    let tagged = |name: &str| {
+        if name.contains(':') {
+            name.to_string()
+        } else {
+            format!("{name}:latest")
+        }
+    };
+    let wanted = tagged(model.trim());
+    installed.iter().any(|name| tagged(name) == wanted)
+}
+
+/// Characters of graph context that fit beside a `chunk_chars` chunk of
+/// document text in a `context_length`-token window, at ~3 characters
+/// per token. 0 when nothing fits.
+pub fn context_char_budget(context_length: Option<u32>, chunk_chars: usize) -> usize {
+    let tokens = context_length.unwrap_or(DEFAULT_CONTEXT_TOKENS) as usize;
+    (tokens.saturating_sub(RESERVED_TOKENS) * 3)
+        .saturating_sub(chunk_chars)
+        .min(MAX_CONTEXT_CHARS)
+}
+
+/// `context` cut to whole lines within `budget` characters, without a
+/// heading left dangling at the end.
+pub fn cap_context(context: &str, budget: usize) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+    let mut used = 0;
+    for line in context.trim().lines() {
+        let cost = line.chars().count() + 1;
+        if used + cost > budget {
+            break;
+        }
+        used += cost;
+        lines.push(line);
+    }
+    while lines
+        .last()
+        .is_some_and(|l| l.trim().is_empty() || l.trim_end().ends_with(':'))
+    {
+        lines.pop();
+    }
+    lines.join("\n")
+}
+
 /// Build a knowledge graph from text and return entity/relationship context
 /// that can be appended to the extraction prompt.
 pub async fn build_requirement_enrichment_context(
     text: &str,
     cfg: &GraphRagExtractorConfig,
-) -> Result<String> {
+) -> Result<EnrichmentContext> {
     let base_url = cfg.ollama_base_url.trim_end_matches('/');
     let (host, port) = if let Some(last_colon) = base_url.rfind(':') {
         let after_colon = &base_url[last_colon + 1..];
@@ -65,6 +168,8 @@ pub async fn build_requirement_enrichment_context(
     let kg = graphrag
         .knowledge_graph()
         .context("knowledge graph not available after build")?;
+    let entities = kg.entities().count();
+    let relationships = kg.relationships().count();
 
     let entity_lines: Vec<String> = kg
         .entities()
@@ -84,7 +189,11 @@ pub async fn build_requirement_enrichment_context(
         .collect();
 
     if entity_lines.is_empty() && rel_lines.is_empty() {
-        return Ok(String::new());
+        return Ok(EnrichmentContext {
+            text: String::new(),
+            entities,
+            relationships,
+        });
     }
 
     let mut out = String::new();
@@ -101,5 +210,9 @@ pub async fn build_requirement_enrichment_context(
         out.push_str(&rel_lines.join("\n"));
     }
 
-    Ok(out)
+    Ok(EnrichmentContext {
+        text: out,
+        entities,
+        relationships,
+    })
 }
@@ -2,5 +2,9 @@ pub mod anthropic;
 pub mod context;
 pub mod graphrag;
 pub mod ollama;
+pub mod openai;
 pub mod provider;
 pub mod suggestions;
+
+#[cfg(test)]
+mod tests;
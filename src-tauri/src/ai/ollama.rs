@@ -32,6 +32,60 @@ impl OllamaProvider {
         }
     }
 
+    /// Names of the models pulled on the server, as `/api/tags` lists them
+    /// ("nomic-embed-text:latest").
+    pub async fn installed_models(&self) -> Result<Vec<String>> {
+        let url = format!("{}/api/tags", self.base_url);
+        let resp = self
+            .client
+            .get(&url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<Value>()
+            .await?;
+        Ok(resp["models"]
+            .as_array()
+            .map(|models| {
+                models
+                    .iter()
+                    .filter_map(|m| m["name"].as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    /// Context window in tokens: the model's `num_ctx` parameter when its
+    /// Modelfile sets one, else the architecture's `context_length`.
+    /// `None` when `/api/show` reports neither.
+    pub async fn context_length(&self) -> Result<Option<u32>> {
+        let url = format!("{}/api/show", self.base_url);
+        let resp = self
+            .client
+            .post(&url)
+            .json(&json!({ "model": self.model, "name": self.model }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<Value>()
+            .await?;
+        let num_ctx = resp["parameters"].as_str().and_then(|params| {
+            params.lines().find_map(|line| {
+                let mut parts = line.split_whitespace();
+                (parts.next() == Some("num_ctx"))
+                    .then(|| parts.next()?.parse::<u32>().ok())
+                    .flatten()
+            })
+        });
+        let trained = resp["model_info"].as_object().and_then(|info| {
+            info.iter()
+                .find(|(key, _)| key.ends_with(".context_length"))
+                .and_then(|(_, v)| v.as_u64())
+                .map(|v| v as u32)
+        });
+        Ok(num_ctx.or(trained))
+    }
+
     fn build_body(&self, prompt: &Prompt, stream: bool) -> Value {
         // Ollama uses the OpenAI-compatible /api/chat endpoint
         let mut messages: Vec<Value> = Vec::new();
@@ -90,6 +144,8 @@ impl AIProvider for OllamaProvider {
             model: resp["model"].as_str().unwrap_or(&self.model).to_string(),
             input_tokens: resp["prompt_eval_count"].as_u64().map(|v| v as u32),
             output_tokens: resp["eval_count"].as_u64().map(|v| v as u32),
+            cache_read_tokens: None,
+            cache_write_tokens: None,
         })
     }
 
@@ -130,4 +186,8 @@ impl AIProvider for OllamaProvider {
     fn name(&self) -> &str {
         "ollama"
     }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
 }
@@ -0,0 +1,166 @@
+use super::provider::{AIProvider, AIResponse, Prompt, TokenStream};
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::StreamExt;
+use reqwest::Client;
+use serde_json::{json, Value};
+
+const API_URL: &str = "https://api.openai.com/v1/chat/completions";
+const DEFAULT_MODEL: &str = "gpt-4o";
+
+pub struct OpenAIProvider {
+    client: Client,
+    api_key: String,
+    model: String,
+}
+
+impl OpenAIProvider {
+    pub fn new(api_key: String) -> Self {
+        Self {
+            client: Client::new(),
+            model: DEFAULT_MODEL.to_string(),
+            api_key,
+        }
+    }
+
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    fn build_body(&self, prompt: &Prompt, stream: bool) -> Value {
+        // The system prompt is the first message rather than a separate field.
+        let mut messages: Vec<Value> = Vec::new();
+        if let Some(sys) = &prompt.system {
+            messages.push(json!({ "role": "system", "content": sys }));
+        }
+        for m in &prompt.messages {
+            messages.push(json!({
+                "role": m.role,
+                "content": m.content,
+            }));
+        }
+
+        let mut body = json!({
+            "model": self.model,
+            "messages": messages,
+        });
+
+        // `max_tokens` is deprecated, and rejected by the reasoning models.
+        if let Some(max_tokens) = prompt.max_tokens {
+            body["max_completion_tokens"] = json!(max_tokens);
+        }
+
+        if stream {
+            body["stream"] = json!(true);
+        }
+
+        body
+    }
+}
+
+#[async_trait]
+impl AIProvider for OpenAIProvider {
+    async fn complete(&self, prompt: Prompt) -> Result<AIResponse> {
+        let body = self.build_body(&prompt, false);
+
+        let resp = self
+            .client
+            .post(API_URL)
+            .bearer_auth(&self.api_key)
+            .header("content-type", "application/json")
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<Value>()
+            .await?;
+
+        let content = resp["choices"][0]["message"]["content"]
+            .as_str()
+            .unwrap_or("")
+            .to_string();
+
+        Ok(AIResponse {
+            content,
+            model: resp["model"].as_str().unwrap_or(&self.model).to_string(),
+            input_tokens: resp["usage"]["prompt_tokens"].as_u64().map(|v| v as u32),
+            output_tokens: resp["usage"]["completion_tokens"]
+                .as_u64()
+                .map(|v| v as u32),
+            // Caching is automatic; only reads are reported.
+            cache_read_tokens: resp["usage"]["prompt_tokens_details"]["cached_tokens"]
+                .as_u64()
+                .map(|v| v as u32),
+            cache_write_tokens: None,
+        })
+    }
+
+    async fn stream(&self, prompt: Prompt) -> Result<TokenStream> {
+        let body = self.build_body(&prompt, true);
+
+        let resp = self
+            .client
+            .post(API_URL)
+            .bearer_auth(&self.api_key)
+            .header("content-type", "application/json")
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        // A network chunk can end mid-line, even mid-character, so bytes are
+        // held over until their line is complete.
+        let stream = resp
+            .bytes_stream()
+            .scan(Vec::new(), |pending: &mut Vec<u8>, chunk| {
+                let tokens = match chunk {
+                    Ok(bytes) => {
+                        pending.extend_from_slice(&bytes);
+                        take_sse_tokens(pending)
+                    }
+                    Err(_) => String::new(),
+                };
+                futures::future::ready(Some(tokens))
+            })
+            .filter_map(|tokens| async move { (!tokens.is_empty()).then_some(Ok(tokens)) });
+
+        Ok(Box::pin(stream))
+    }
+
+    fn is_available(&self) -> bool {
+        !self.api_key.is_empty()
+    }
+
+    fn name(&self) -> &str {
+        "openai"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+}
+
+/// The delta text of every complete SSE line ("data: {...}") in `pending`,
+/// which keeps the trailing partial line for the next chunk.
+pub(crate) fn take_sse_tokens(pending: &mut Vec<u8>) -> String {
+    let Some(end) = pending.iter().rposition(|&b| b == b'\n') else {
+        return String::new();
+    };
+    let complete: Vec<u8> = pending.drain(..=end).collect();
+    let mut tokens = String::new();
+    for line in String::from_utf8_lossy(&complete).lines() {
+        let Some(data) = line.trim().strip_prefix("data: ") else {
+            continue;
+        };
+        if data == "[DONE]" {
+            break;
+        }
+        if let Ok(val) = serde_json::from_str::<Value>(data) {
+            if let Some(token) = val["choices"][0]["delta"]["content"].as_str() {
+                tokens.push_str(token);
+            }
+        }
+    }
+    tokens
+}
@@ -3,6 +3,7 @@ use async_trait::async_trait;
 use futures::Stream;
 use serde::{Deserialize, Serialize};
 use std::pin::Pin;
+use std::time::Duration;
 
 // ── Types ─────────────────────────────────────────────────────────────────────
 
@@ -11,6 +12,11 @@ pub struct Prompt {
     pub system: Option<String>,
     pub messages: Vec<Message>,
     pub max_tokens: Option<u32>,
+    /// Ask the provider to cache the system prompt across calls. Worth it
+    /// when the same long system prompt is sent repeatedly (chunked
+    /// extraction); ignored by providers without prompt caching.
+    #[serde(default)]
+    pub cache_system: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -32,6 +38,10 @@ pub struct AIResponse {
     pub model: String,
     pub input_tokens: Option<u32>,
     pub output_tokens: Option<u32>,
+    /// Prompt tokens read from the provider's cache.
+    pub cache_read_tokens: Option<u32>,
+    /// Prompt tokens written to the provider's cache.
+    pub cache_write_tokens: Option<u32>,
 }
 
 pub type TokenStream = Pin<Box<dyn Stream<Item = Result<String>> + Send>>;
@@ -44,6 +54,99 @@ pub trait AIProvider: Send + Sync {
     async fn stream(&self, prompt: Prompt) -> Result<TokenStream>;
     fn is_available(&self) -> bool;
     fn name(&self) -> &str;
+    /// The model requests go to, as configured.
+    fn model(&self) -> &str;
+}
+
+// ── Retry ─────────────────────────────────────────────────────────────────────
+
+/// Default for the `ai.retry.max_attempts` setting.
+pub const DEFAULT_RETRY_ATTEMPTS: u32 = 3;
+
+/// Default for the `ai.retry.base_delay_ms` setting.
+pub const DEFAULT_RETRY_BASE_DELAY_MS: u64 = 1000;
+
+/// No single wait is longer than this, however many attempts are allowed.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// How `complete_with_retry` retries a transient failure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Calls in total, the first included; 1 never retries.
+    pub max_attempts: u32,
+    /// Wait before the first retry, doubled for each one after.
+    pub base_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: DEFAULT_RETRY_ATTEMPTS,
+            base_delay: Duration::from_millis(DEFAULT_RETRY_BASE_DELAY_MS),
+        }
+    }
+}
+
+/// `provider.complete(prompt)`, retried with exponential backoff and jitter
+/// while it fails transiently (see [`is_transient`]) and attempts remain.
+/// Returns the last error once they run out.
+pub async fn complete_with_retry(
+    provider: &dyn AIProvider,
+    prompt: Prompt,
+    policy: &RetryPolicy,
+) -> Result<AIResponse> {
+    let mut attempt = 1;
+    loop {
+        match provider.complete(prompt.clone()).await {
+            Ok(response) => return Ok(response),
+            Err(e) if attempt < policy.max_attempts && is_transient(&e) => {
+                let delay = backoff(policy.base_delay, attempt);
+                eprintln!(
+                    "{} call failed ({e}); retry {attempt} of {} in {delay:?}",
+                    provider.name(),
+                    policy.max_attempts - 1
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Rate limits, overload, server and gateway errors, timeouts and refused
+/// connections. Anything else (a bad key, a bad request, a missing model)
+/// fails the same way every time.
+pub fn is_transient(err: &anyhow::Error) -> bool {
+    let Some(err) = err.downcast_ref::<reqwest::Error>() else {
+        return false;
+    };
+    if err.is_timeout() || err.is_connect() {
+        return true;
+    }
+    // 529 is Anthropic's "overloaded".
+    err.status()
+        .is_some_and(|s| matches!(s.as_u16(), 408 | 429 | 500 | 502 | 503 | 504 | 529))
+}
+
+/// Half of `base · 2^(attempt − 1)`, plus up to the other half at random so
+/// calls that failed together don't all retry together.
+fn backoff(base: Duration, attempt: u32) -> Duration {
+    use std::hash::{BuildHasher, Hasher};
+
+    let full = base
+        .saturating_mul(1 << (attempt - 1).min(16))
+        .min(MAX_RETRY_DELAY);
+    let half = full / 2;
+    let spread = half.as_millis() as u64;
+    if spread == 0 {
+        return half;
+    }
+    // A randomly keyed hasher is the standard library's only randomness.
+    let random = std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish();
+    half + Duration::from_millis(random % (spread + 1))
 }
 
 // ── No-op provider (default when nothing is configured) ───────────────────────
@@ -67,4 +170,8 @@ impl AIProvider for NullProvider {
     fn name(&self) -> &str {
         "none"
     }
+
+    fn model(&self) -> &str {
+        "none"
+    }
 }
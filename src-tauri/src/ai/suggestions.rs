@@ -91,6 +91,7 @@ pub async fn analyze_requirements(
             ),
         }],
         max_tokens: Some(2048),
+        cache_system: false,
     };
 
     let response = provider.complete(prompt).await?;
@@ -0,0 +1,76 @@
+use super::anthropic::AnthropicProvider;
+use super::openai::take_sse_tokens;
+use super::provider::{Message, Prompt, Role};
+use serde_json::json;
+
+fn delta(text: &str) -> String {
+    format!(
+        "data: {}\n\n",
+        serde_json::json!({ "choices": [{ "delta": { "content": text } }] })
+    )
+}
+
+#[test]
+fn sse_lines_split_across_chunks_keep_every_token() {
+    let body = [
+        delta("Hello"),
+        delta(" wörld"),
+        delta("!"),
+        "data: [DONE]\n\n".into(),
+    ]
+    .concat();
+    let bytes = body.as_bytes();
+    // Cut inside the second event's JSON and inside the two-byte "ö".
+    let split_line = body.find("wö").unwrap() - 10;
+    let split_char = body.find('ö').unwrap() + 1;
+    let mut pending = Vec::new();
+    let mut out = String::new();
+    for chunk in [
+        &bytes[..split_line],
+        &bytes[split_line..split_char],
+        &bytes[split_char..],
+    ] {
+        pending.extend_from_slice(chunk);
+        out.push_str(&take_sse_tokens(&mut pending));
+    }
+    assert_eq!(out, "Hello wörld!");
+    assert!(pending.is_empty());
+}
+
+#[test]
+fn a_partial_line_waits_for_the_rest() {
+    let mut pending = b"data: {\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}".to_vec();
+    assert_eq!(take_sse_tokens(&mut pending), "");
+    pending.extend_from_slice(b"\n");
+    assert_eq!(take_sse_tokens(&mut pending), "Hi");
+}
+
+fn extraction_prompt(cache_system: bool) -> Prompt {
+    Prompt {
+        system: Some("Extract requirements.".into()),
+        messages: vec![Message {
+            role: Role::User,
+            content: "The pump shall start.".into(),
+        }],
+        max_tokens: None,
+        cache_system,
+    }
+}
+
+#[test]
+fn anthropic_body_marks_the_system_prompt_for_caching_when_asked() {
+    let provider = AnthropicProvider::new("key".into());
+
+    let cached = provider.build_body(&extraction_prompt(true), false);
+    assert_eq!(
+        cached["system"],
+        json!([{
+            "type": "text",
+            "text": "Extract requirements.",
+            "cache_control": { "type": "ephemeral" },
+        }])
+    );
+
+    let plain = provider.build_body(&extraction_prompt(false), false);
+    assert_eq!(plain["system"], json!("Extract requirements."));
+}
@@ -0,0 +1,198 @@
+/// Structured command errors. Serialized to the frontend as
+/// `{code, message, detail}` so it can branch on `code` instead of parsing
+/// message text:
+///
+/// - `not_found`: a missing record, or sqlx `RowNotFound`.
+/// - `invalid_uuid`: an id argument that isn't a UUID; detail is
+///   `{field, value}`, `field` being the argument name when known.
+/// - `conflict`: UNIQUE or FOREIGN KEY constraint failures; detail is the
+///   database message.
+/// - `validation`: rejected input, or CHECK constraint failures; detail is
+///   the list of issues.
+/// - `project_locked`: another bulk operation holds the write queue.
+/// - `provider_unavailable`: no AI provider is configured.
+/// - `not_ready`: startup is still opening or migrating the database; retry
+///   after the `startup:progress` event reports `ready`.
+/// - `io`: filesystem and database I/O failures; detail is the OS message.
+/// - `internal`: anything else; detail is the raw error.
+///
+/// Store errors arrive as `anyhow::Error` and are mapped by `From`, which
+/// looks for a [`HeavyWriteBusy`], `sqlx::Error` or `std::io::Error` in the
+/// chain. A `uuid::Error` there is a stored id that doesn't parse, so it
+/// stays `internal`; `invalid_uuid` is only for arguments, via
+/// [`parse_uuid`]. Commands not yet migrated still return
+/// `String`; they can call `.to_string()` on a `CommandError` to keep
+/// their old message.
+use crate::core::store::HeavyWriteBusy;
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use std::fmt;
+
+#[derive(Debug, Clone)]
+pub enum CommandError {
+    /// What was looked up, e.g. "node 3f2a… not found".
+    NotFound(String),
+    /// The argument that failed to parse, and its name when known.
+    InvalidUuid { field: Option<String>, value: String },
+    Conflict(String),
+    Validation { issues: Vec<FieldIssue> },
+    /// The running operation's busy message.
+    ProjectLocked(String),
+    ProviderUnavailable,
+    /// Rejected by the startup gate before `AppState` exists.
+    NotReady,
+    Io(String),
+    Internal { detail: String },
+}
+
+/// One problem with submitted input. `field` is `None` when the problem is
+/// with the request as a whole.
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldIssue {
+    pub field: Option<String>,
+    /// Machine-readable kind, e.g. "too_long" or "invalid".
+    pub code: String,
+    pub message: String,
+}
+
+impl CommandError {
+    pub fn not_found(what: impl fmt::Display) -> Self {
+        CommandError::NotFound(format!("{what} not found"))
+    }
+
+    /// A validation error with a single request-level issue.
+    pub fn invalid(message: impl Into<String>) -> Self {
+        CommandError::Validation {
+            issues: vec![FieldIssue {
+                field: None,
+                code: "invalid".to_string(),
+                message: message.into(),
+            }],
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            CommandError::NotFound(_) => "not_found",
+            CommandError::InvalidUuid { .. } => "invalid_uuid",
+            CommandError::Conflict(_) => "conflict",
+            CommandError::Validation { .. } => "validation",
+            CommandError::ProjectLocked(_) => "project_locked",
+            CommandError::ProviderUnavailable => "provider_unavailable",
+            CommandError::NotReady => "not_ready",
+            CommandError::Io(_) => "io",
+            CommandError::Internal { .. } => "internal",
+        }
+    }
+}
+
+/// Parse the id argument `field`. Commands that still return `String`
+/// call `.map_err(|e| e.to_string())` on the result.
+pub fn parse_uuid(field: &str, value: &str) -> Result<uuid::Uuid, CommandError> {
+    value.trim().parse().map_err(|_| CommandError::InvalidUuid {
+        field: Some(field.to_string()),
+        value: value.to_string(),
+    })
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandError::NotFound(what) => write!(f, "{what}"),
+            CommandError::InvalidUuid { field: Some(field), value } => {
+                write!(f, "{field}: '{value}' is not a valid id")
+            }
+            CommandError::InvalidUuid { field: None, value } => {
+                write!(f, "'{value}' is not a valid id")
+            }
+            CommandError::Conflict(_) => {
+                write!(f, "This change conflicts with existing data or refers to missing data.")
+            }
+            CommandError::Validation { issues } => {
+                let messages: Vec<&str> = issues.iter().map(|i| i.message.as_str()).collect();
+                write!(f, "{}", messages.join("; "))
+            }
+            CommandError::ProjectLocked(message) => write!(f, "{message}"),
+            CommandError::ProviderUnavailable => write!(f, "No AI provider is configured."),
+            CommandError::NotReady => write!(f, "The database is still being prepared."),
+            CommandError::Io(message) => write!(f, "File or database access failed: {message}"),
+            CommandError::Internal { detail } => write!(f, "{detail}"),
+        }
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+impl Serialize for CommandError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("CommandError", 3)?;
+        s.serialize_field("code", self.code())?;
+        s.serialize_field("message", &self.to_string())?;
+        match self {
+            CommandError::InvalidUuid { field, value } => s.serialize_field(
+                "detail",
+                &serde_json::json!({ "field": field, "value": value }),
+            )?,
+            CommandError::Conflict(detail)
+            | CommandError::Io(detail)
+            | CommandError::Internal { detail } => s.serialize_field("detail", detail)?,
+            CommandError::Validation { issues } => s.serialize_field("detail", issues)?,
+            CommandError::NotFound(_)
+            | CommandError::ProjectLocked(_)
+            | CommandError::ProviderUnavailable
+            | CommandError::NotReady => s.serialize_field("detail", &None::<()>)?,
+        }
+        s.end()
+    }
+}
+
+impl From<anyhow::Error> for CommandError {
+    fn from(err: anyhow::Error) -> Self {
+        for cause in err.chain() {
+            if let Some(busy) = cause.downcast_ref::<HeavyWriteBusy>() {
+                return CommandError::ProjectLocked(busy.to_string());
+            }
+            if let Some(db) = cause.downcast_ref::<sqlx::Error>() {
+                if let Some(mapped) = from_sqlx(db) {
+                    return mapped;
+                }
+            }
+            if let Some(io) = cause.downcast_ref::<std::io::Error>() {
+                return CommandError::Io(io.to_string());
+            }
+        }
+        CommandError::Internal { detail: format!("{err:#}") }
+    }
+}
+
+impl From<sqlx::Error> for CommandError {
+    fn from(err: sqlx::Error) -> Self {
+        from_sqlx(&err).unwrap_or_else(|| CommandError::Internal { detail: err.to_string() })
+    }
+}
+
+impl From<std::io::Error> for CommandError {
+    fn from(err: std::io::Error) -> Self {
+        CommandError::Io(err.to_string())
+    }
+}
+
+impl From<serde_json::Error> for CommandError {
+    fn from(err: serde_json::Error) -> Self {
+        CommandError::Internal { detail: err.to_string() }
+    }
+}
+
+fn from_sqlx(err: &sqlx::Error) -> Option<CommandError> {
+    match err {
+        sqlx::Error::RowNotFound => Some(CommandError::not_found("record")),
+        sqlx::Error::Io(io) => Some(CommandError::Io(io.to_string())),
+        sqlx::Error::Database(db) if db.is_unique_violation() || db.is_foreign_key_violation() => {
+            Some(CommandError::Conflict(db.message().to_string()))
+        }
+        sqlx::Error::Database(db) if db.is_check_violation() => {
+            Some(CommandError::invalid(db.message().to_string()))
+        }
+        _ => None,
+    }
+}
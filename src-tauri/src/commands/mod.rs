@@ -1,6 +1,14 @@
 use crate::ai::provider::{Message, Prompt, Role};
+use crate::core::classification;
+use crate::core::digest;
+use crate::core::readiness;
+use crate::core::export::scope::{ExportPreview, ExportScope, ScopedModel};
 use crate::core::model::*;
+use crate::core::sections::SectionTree;
+use crate::core::store::USER_HEAVY_WRITE_WAIT;
 use crate::core::validation;
+use crate::diagnostics::{Diagnostics, OperationTiming};
+use crate::jobs::ExportFormat;
 use crate::AppState;
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
@@ -9,19 +17,34 @@ use std::sync::Arc;
 use tauri::{Manager, State};
 use uuid::Uuid;
 
+mod error;
+
+pub use error::{parse_uuid, CommandError, FieldIssue};
+
+/// The actor the frontend named, or "user", which the store stamps with
+/// the configured identity.
+fn actor_or_user(actor: Option<String>) -> String {
+    actor.filter(|a| !a.trim().is_empty()).unwrap_or_else(|| "user".to_string())
+}
+
 // ── Projects ──────────────────────────────────────────────────────────────────
 
 #[tauri::command]
-pub async fn list_projects(state: State<'_, AppState>) -> Result<Vec<Project>, String> {
-    state.store.list_projects().await.map_err(|e| e.to_string())
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn list_projects(
+    state: State<'_, AppState>,
+) -> Result<Vec<Project>, CommandError> {
+    state.store.list_projects().await.map_err(CommandError::from)
 }
 
 #[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
 pub async fn create_project(
     name: String,
     description: String,
+    actor: Option<String>,
     state: State<'_, AppState>,
-) -> Result<Project, String> {
+) -> Result<Project, CommandError> {
     let now = Utc::now();
     let project = Project {
         id: Uuid::new_v4(),
@@ -30,1176 +53,4825 @@ pub async fn create_project(
         created_at: now,
         modified_at: now,
     };
-    state
-        .store
-        .create_project(&project)
-        .await
-        .map_err(|e| e.to_string())?;
+    state.store.create_project(&project, &actor_or_user(actor)).await?;
     Ok(project)
 }
 
 #[tauri::command]
-pub async fn get_project(id: String, state: State<'_, AppState>) -> Result<Project, String> {
-    let uuid: Uuid = id.parse().map_err(|e: uuid::Error| e.to_string())?;
-    state
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn get_project(
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<Project, CommandError> {
+    let uuid = parse_uuid("id", &id)?;
+    // Opening a project drops cached validation for any other project.
+    state.validation_cache.lock().unwrap().retain_only(uuid);
+    let project = state
         .store
         .get_project(uuid)
-        .await
-        .map_err(|e| e.to_string())?
-        .ok_or_else(|| "project not found".to_string())
+        .await?
+        .ok_or_else(|| CommandError::not_found("project"))?;
+    // Remembered for the startup digest; a read-only session just skips it.
+    if !state.store.is_read_only() {
+        let _ = state
+            .store
+            .set_setting(LAST_PROJECT_SETTING, None, &uuid.to_string())
+            .await;
+    }
+    Ok(project)
 }
 
 #[tauri::command]
-pub async fn delete_project(id: String, state: State<'_, AppState>) -> Result<(), String> {
-    let uuid: Uuid = id.parse().map_err(|e: uuid::Error| e.to_string())?;
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn delete_project(
+    id: String,
+    actor: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), CommandError> {
+    let uuid = parse_uuid("id", &id)?;
+    state.validation_cache.lock().unwrap().invalidate(uuid);
+    state.baseline_index.lock().unwrap().invalidate_project(uuid);
     state
         .store
-        .delete_project(uuid)
+        .delete_project(uuid, &actor_or_user(actor))
         .await
-        .map_err(|e| e.to_string())
+        .map_err(CommandError::from)
 }
 
-// ── Nodes ─────────────────────────────────────────────────────────────────────
+// ── Project templates ─────────────────────────────────────────────────────────
 
+/// Capture a project's blocks, value types, diagrams and/or settings as a
+/// reusable template. `include` picks the parts; empty means all of them.
+/// Requirements and documents are never captured, nor are secret settings.
 #[tauri::command]
-pub async fn list_nodes(
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn save_project_as_template(
     project_id: String,
+    name: String,
+    include: Vec<String>,
+    description: Option<String>,
     state: State<'_, AppState>,
-) -> Result<Vec<Node>, String> {
-    let id: Uuid = project_id.parse().map_err(|e: uuid::Error| e.to_string())?;
-    state.store.list_nodes(id).await.map_err(|e| e.to_string())
-}
+) -> Result<ProjectTemplate, CommandError> {
+    use crate::core::templates;
 
-#[tauri::command]
-pub async fn upsert_node(node: Node, state: State<'_, AppState>) -> Result<(), String> {
-    let node_id = node.id;
-    let project_id = node.project_id;
-    let is_requirement = node.kind == crate::core::model::NodeKind::Requirement;
-    state
-        .store
-        .upsert_node(&node)
-        .await
-        .map_err(|e| e.to_string())?;
-    // Flag downstream links as suspect when a requirement changes
-    if is_requirement {
-        let _ = state.store.flag_suspect_links(project_id, node_id, "requirement updated").await;
+    let pid = parse_uuid("project_id", &project_id)?;
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(CommandError::invalid("template name must not be empty"));
     }
-    Ok(())
+    let parts = templates::parse_include(&include).map_err(CommandError::invalid)?;
+
+    let store = &state.store;
+    let nodes = store.list_nodes(pid).await?;
+    let edges = store.list_edges(pid).await?;
+    let properties = store.list_project_block_properties(pid).await?;
+    let diagrams = store.list_diagrams(pid).await?;
+    let elements = store.list_project_diagram_elements(pid).await?;
+    let settings: Vec<SettingEntry> = store
+        .list_settings(Some(pid), None)
+        .await?
+        .into_iter()
+        .filter(|s| !is_secret_setting(&s.key))
+        .collect();
+
+    let template = ProjectTemplate {
+        id: Uuid::new_v4(),
+        name,
+        description: description.unwrap_or_default(),
+        builtin: false,
+        include: parts.iter().map(|p| p.as_str().to_string()).collect(),
+        content: templates::capture(&parts, nodes, edges, properties, diagrams, elements, settings),
+        created_at: Utc::now(),
+    };
+    store.create_project_template(&template).await?;
+    Ok(template)
 }
 
+/// Built-in templates first, then saved ones newest first.
 #[tauri::command]
-pub async fn list_requirement_history(
-    node_id: String,
-    limit: Option<i64>,
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn list_project_templates(
     state: State<'_, AppState>,
-) -> Result<Vec<RequirementHistoryEntry>, String> {
-    let id: Uuid = node_id.parse().map_err(|e: uuid::Error| e.to_string())?;
-    let capped_limit = limit.unwrap_or(20).clamp(1, 200) as usize;
-    state
-        .store
-        .list_requirement_history(id, capped_limit)
-        .await
-        .map_err(|e| e.to_string())
+) -> Result<Vec<ProjectTemplate>, CommandError> {
+    let mut templates = crate::core::templates::builtin_templates();
+    templates.extend(state.store.list_project_templates().await?);
+    Ok(templates)
 }
 
+/// Create a project and fill it from a template with fresh ids. If any
+/// write fails the half-built project is deleted again.
 #[tauri::command]
-pub async fn delete_node(id: String, state: State<'_, AppState>) -> Result<(), String> {
-    let uuid: Uuid = id.parse().map_err(|e: uuid::Error| e.to_string())?;
-    state
-        .store
-        .delete_node(uuid)
-        .await
-        .map_err(|e| e.to_string())
-}
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn create_project_from_template(
+    template_id: String,
+    name: String,
+    description: String,
+    actor: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Project, CommandError> {
+    use crate::core::templates;
 
-// ── Edges ─────────────────────────────────────────────────────────────────────
+    let tid = parse_uuid("template_id", &template_id)?;
+    let template = match templates::builtin_template(tid) {
+        Some(template) => template,
+        None => state
+            .store
+            .get_project_template(tid)
+            .await?
+            .ok_or_else(|| CommandError::not_found("template"))?,
+    };
 
-#[tauri::command]
-pub async fn upsert_edge(edge: Edge, state: State<'_, AppState>) -> Result<(), String> {
-    state
+    let _write = state
         .store
-        .upsert_edge(&edge)
-        .await
-        .map_err(|e| e.to_string())
+        .begin_heavy_write("creating a project from a template", Some(USER_HEAVY_WRITE_WAIT))
+        .await?;
+    let now = Utc::now();
+    let project = Project {
+        id: Uuid::new_v4(),
+        name,
+        description,
+        created_at: now,
+        modified_at: now,
+    };
+    let actor = actor_or_user(actor);
+    state.store.create_project(&project, &actor).await?;
+
+    let content = templates::instantiate(&template.content, project.id);
+    if let Err(e) = state.store.write_template_content(&content, &actor).await {
+        let _ = state.store.delete_project(project.id, &actor).await;
+        return Err(e.context(format!("failed to apply template '{}'", template.name)).into());
+    }
+    Ok(project)
 }
 
+/// Create the example UAV project (see `core::example`). A failed write
+/// leaves no half-built project behind.
 #[tauri::command]
-pub async fn delete_edge(id: String, state: State<'_, AppState>) -> Result<(), String> {
-    let uuid: Uuid = id.parse().map_err(|e: uuid::Error| e.to_string())?;
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn create_example_project(
+    actor: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Project, CommandError> {
+    let example = crate::core::example::build(Utc::now());
+
+    let _write = state
+        .store
+        .begin_heavy_write("creating the example project", Some(USER_HEAVY_WRITE_WAIT))
+        .await?;
     state
         .store
-        .delete_edge(uuid)
-        .await
-        .map_err(|e| e.to_string())
+        .create_example_project(&example, &actor_or_user(actor))
+        .await?;
+    Ok(example.project)
 }
 
+// ── Nodes ─────────────────────────────────────────────────────────────────────
+
+/// The project's nodes. Archived nodes are left out unless
+/// `include_archived` is set; `created_origin` keeps only imported or only
+/// authored nodes (see `Node::created_origin`).
 #[tauri::command]
-pub async fn edges_for_node(
-    node_id: String,
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn list_nodes(
+    project_id: String,
+    include_archived: Option<bool>,
+    created_origin: Option<CreatedOrigin>,
     state: State<'_, AppState>,
-) -> Result<Vec<Edge>, String> {
-    let uuid: Uuid = node_id.parse().map_err(|e: uuid::Error| e.to_string())?;
-    state
-        .store
-        .edges_for_node(uuid)
-        .await
-        .map_err(|e| e.to_string())
+) -> Result<Vec<Node>, CommandError> {
+    let id = parse_uuid("project_id", &project_id)?;
+    let mut nodes = state.store.list_nodes(id).await?;
+    if !include_archived.unwrap_or(false) {
+        nodes.retain(|n| !n.archived);
+    }
+    if let Some(origin) = created_origin {
+        nodes.retain(|n| n.created_origin() == origin);
+    }
+    Ok(nodes)
 }
 
-// ── Diagrams ──────────────────────────────────────────────────────────────────
-
+/// Id, kind, name, req_id and status of the project's nodes, optionally of
+/// one kind, without loading their data. Archived nodes are left out unless
+/// `include_archived` is set.
 #[tauri::command]
-pub async fn list_diagrams(
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn list_node_summaries(
     project_id: String,
+    kind: Option<NodeKind>,
+    include_archived: Option<bool>,
     state: State<'_, AppState>,
-) -> Result<Vec<Diagram>, String> {
-    let id: Uuid = project_id.parse().map_err(|e: uuid::Error| e.to_string())?;
+) -> Result<Vec<NodeSummary>, CommandError> {
+    let id = parse_uuid("project_id", &project_id)?;
     state
         .store
-        .list_diagrams(id)
+        .list_node_summaries(id, kind.as_ref(), include_archived.unwrap_or(false))
         .await
-        .map_err(|e| e.to_string())
+        .map_err(CommandError::from)
 }
 
+/// Typo-tolerant ranked node search for quick-open. `limit` defaults to 50.
 #[tauri::command]
-pub async fn upsert_diagram(diagram: Diagram, state: State<'_, AppState>) -> Result<(), String> {
-    state
-        .store
-        .upsert_diagram(&diagram)
-        .await
-        .map_err(|e| e.to_string())
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn search_nodes_fuzzy(
+    project_id: String,
+    query: String,
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::core::search::FuzzyMatch>, CommandError> {
+    let id = parse_uuid("project_id", &project_id)?;
+    let nodes = state.store.list_nodes(id).await?;
+    Ok(crate::core::search::fuzzy_search(&nodes, &query, limit.unwrap_or(50)))
 }
 
+/// The project's requirement with `req_id`, if any.
 #[tauri::command]
-pub async fn diagram_elements(
-    diagram_id: String,
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn find_node_by_req_id(
+    project_id: String,
+    req_id: String,
     state: State<'_, AppState>,
-) -> Result<Vec<DiagramElement>, String> {
-    let id: Uuid = diagram_id.parse().map_err(|e: uuid::Error| e.to_string())?;
+) -> Result<Option<Node>, CommandError> {
+    let id = parse_uuid("project_id", &project_id)?;
     state
         .store
-        .diagram_elements(id)
+        .find_node_by_req_id(id, &req_id)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(CommandError::from)
 }
 
+/// The req_ids mentioned in a requirement's text, each resolved to its
+/// requirement where one exists; see `core::references`. With
+/// `create_traces`, a «traces» edge is added from the requirement to every
+/// other resolved one it doesn't trace yet.
 #[tauri::command]
-pub async fn upsert_diagram_element(
-    element: DiagramElement,
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn resolve_references(
+    node_id: String,
+    create_traces: Option<bool>,
+    app: tauri::AppHandle,
     state: State<'_, AppState>,
-) -> Result<(), String> {
-    state
+) -> Result<crate::core::references::ReferenceResolution, CommandError> {
+    use crate::core::references::{self, ReferenceResolution};
+
+    let id = parse_uuid("node_id", &node_id)?;
+    let node = state
         .store
-        .upsert_diagram_element(&element)
-        .await
-        .map_err(|e| e.to_string())
+        .get_node(id)
+        .await?
+        .ok_or_else(|| CommandError::not_found(format!("node {id}")))?;
+    let Some(text) = references::requirement_text(&node) else {
+        return Err(CommandError::invalid(format!("'{}' is not a requirement", node.name)));
+    };
+
+    let patterns = req_id_patterns(&state, node.project_id).await;
+    let own = references::own_req_id(&node);
+    let mut mentions: Vec<_> = references::find_mentions(text, &patterns)
+        .into_iter()
+        .filter(|m| Some(m.req_id.as_str()) != own)
+        .collect();
+    let mut resolved: std::collections::HashMap<String, Option<(Uuid, String)>> =
+        Default::default();
+    for mention in &mut mentions {
+        if !resolved.contains_key(&mention.req_id) {
+            let target = state.store.find_node_by_req_id(node.project_id, &mention.req_id).await?;
+            resolved.insert(mention.req_id.clone(), target.map(|t| (t.id, t.name)));
+        }
+        if let Some(Some((target_id, name))) = resolved.get(&mention.req_id) {
+            mention.node_id = Some(*target_id);
+            mention.name = Some(name.clone());
+        }
+    }
+
+    let mut created_edges: Vec<Edge> = Vec::new();
+    if create_traces.unwrap_or(false) {
+        let existing = state.store.edges_for_node(id).await?;
+        let targets: Vec<Uuid> = mentions.iter().filter_map(|m| m.node_id).collect();
+        for target in targets {
+            let traced = existing
+                .iter()
+                .chain(&created_edges)
+                .any(|e| e.kind == EdgeKind::Traces && e.source_id == id && e.target_id == target);
+            if target == id || traced {
+                continue;
+            }
+            let now = Utc::now();
+            let edge = Edge {
+                id: Uuid::new_v4(),
+                project_id: node.project_id,
+                kind: EdgeKind::Traces,
+                source_id: id,
+                target_id: target,
+                label: String::new(),
+                meta: [("change_source".to_string(), serde_json::json!("reference"))]
+                    .into_iter()
+                    .collect(),
+                created_at: now,
+                modified_at: now,
+            };
+            state.store.upsert_edge(&edge).await?;
+            created_edges.push(edge);
+        }
+        if !created_edges.is_empty() {
+            let touched = created_edges.iter().map(|e| e.id).collect();
+            let _ = revalidate_touched(&app, &state, node.project_id, touched).await;
+        }
+    }
+
+    Ok(ReferenceResolution { node_id: id, mentions, created_edges })
+}
+
+/// Patterns req_id mentions are matched against, from the `req_id.scheme`
+/// and `req_id.pattern` settings, project then global.
+async fn req_id_patterns(state: &State<'_, AppState>, project_id: Uuid) -> Vec<String> {
+    use crate::core::numbering::{self, ReqIdScheme};
+
+    let scheme = project_or_global_setting(state, numbering::SCHEME_SETTING, project_id).await;
+    let pattern = project_or_global_setting(state, numbering::PATTERN_SETTING, project_id).await;
+    crate::core::references::scheme_patterns(
+        ReqIdScheme::parse(scheme.as_deref()),
+        pattern.as_deref(),
+    )
 }
 
 #[tauri::command]
-pub async fn delete_diagram(diagram_id: String, state: State<'_, AppState>) -> Result<(), String> {
-    let id: Uuid = diagram_id.parse().map_err(|e: uuid::Error| e.to_string())?;
-    state
-        .store
-        .delete_diagram(id)
-        .await
-        .map_err(|e| e.to_string())
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn upsert_node(
+    node: Node,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), CommandError> {
+    let node_id = node.id;
+    let project_id = node.project_id;
+    let is_requirement = node.kind == crate::core::model::NodeKind::Requirement;
+    if let NodeData::Requirement(r) = &node.data {
+        check_requirement_text_length(&state, &node, r).await?;
+    }
+    state.store.upsert_node(&node).await?;
+    // Flag downstream links as suspect when a requirement changes
+    if is_requirement {
+        let _ = state.store.flag_suspect_links(project_id, node_id, "requirement updated").await;
+    }
+    let _ = revalidate_touched(&app, &state, project_id, vec![node_id]).await;
+    Ok(())
 }
 
-// -- Documents --------------------------------------------------------------
+/// Enforce `limits.req_text_chars`. Text that was already over the limit
+/// and is saved unchanged is let through (validation reports it instead),
+/// so older oversized requirements can still be edited otherwise. Fails
+/// with a "too_long" issue on the `text` field.
+async fn check_requirement_text_length(
+    state: &State<'_, AppState>,
+    node: &Node,
+    req: &RequirementData,
+) -> Result<(), CommandError> {
+    let length = req.text.as_deref().map(|t| t.chars().count()).unwrap_or(0);
+    let limit = validation_limits(state, node.project_id).await.req_text_chars;
+    if length <= limit {
+        return Ok(());
+    }
+    let stored = state.store.get_node(node.id).await?;
+    let unchanged = matches!(
+        stored.as_ref().map(|n| &n.data),
+        Some(NodeData::Requirement(prev)) if prev.text == req.text
+    );
+    if unchanged {
+        return Ok(());
+    }
+    Err(CommandError::Validation {
+        issues: vec![FieldIssue {
+            field: Some("text".to_string()),
+            code: "too_long".to_string(),
+            message: format!("text is {length} characters; the limit is {limit}"),
+        }],
+    })
+}
 
+/// Propose verification methods from requirement text using the built-in
+/// ruleset plus any rules in the project's `verification.rules` setting.
+/// Unless `dry_run`, confident proposals are written to requirements that
+/// have no method yet. Every proposal is returned, applied or not.
 #[tauri::command]
-pub async fn list_documents(
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn infer_verification_methods(
     project_id: String,
+    only_missing: bool,
+    dry_run: bool,
     state: State<'_, AppState>,
-) -> Result<Vec<Document>, String> {
-    let id: Uuid = project_id.parse().map_err(|e: uuid::Error| e.to_string())?;
-    state
+) -> Result<Vec<crate::core::verification::VerificationProposal>, String> {
+    use crate::core::verification::{self, VerificationRule, APPLY_THRESHOLD};
+
+    let pid = parse_uuid("project_id", &project_id).map_err(|e| e.to_string())?;
+
+    let mut rules = verification::default_rules();
+    if let Some(raw) = state
         .store
-        .list_documents(id)
+        .get_setting("verification.rules", Some(pid))
         .await
-        .map_err(|e| e.to_string())
-}
+        .map_err(|e| e.to_string())?
+        .filter(|raw| !raw.trim().is_empty())
+    {
+        let extra: Vec<VerificationRule> = serde_json::from_str(&raw)
+            .map_err(|e| format!("invalid verification.rules setting: {e}"))?;
+        rules.extend(extra);
+    }
 
-#[tauri::command]
-pub async fn upsert_document(doc: Document, state: State<'_, AppState>) -> Result<(), String> {
-    state
+    let nodes = state
         .store
-        .upsert_document(&doc)
+        .list_nodes_by_kind(pid, &NodeKind::Requirement)
         .await
-        .map_err(|e| e.to_string())
-}
-
-#[tauri::command]
-pub async fn delete_document(id: String, state: State<'_, AppState>) -> Result<(), String> {
-    let uuid: Uuid = id.parse().map_err(|e: uuid::Error| e.to_string())?;
-    state
+        .map_err(|e| e.to_string())?;
+    let mut proposals = verification::propose(&nodes, &rules, only_missing);
+    if dry_run {
+        return Ok(proposals);
+    }
+    let _write = state
         .store
-        .delete_document(uuid)
+        .begin_heavy_write("applying verification methods", Some(USER_HEAVY_WRITE_WAIT))
         .await
-        .map_err(|e| e.to_string())
-}
+        .map_err(|e| e.to_string())?;
 
-// -- Document sections -------------------------------------------------------
+    for proposal in &mut proposals {
+        let Some(method) = proposal.proposed.clone() else {
+            continue;
+        };
+        if proposal.current.is_some() || proposal.confidence < APPLY_THRESHOLD {
+            continue;
+        }
+        let Some(mut node) = nodes.iter().find(|n| n.id == proposal.node_id).cloned() else {
+            continue;
+        };
+        if let NodeData::Requirement(req) = &mut node.data {
+            req.verification_method = Some(method);
+        }
+        node.meta.insert("change_source".to_string(), serde_json::json!("inference"));
+        node.meta.insert("actor".to_string(), serde_json::json!("system"));
+        node.modified_at = Utc::now();
+        state
+            .store
+            .upsert_node(&node)
+            .await
+            .map_err(|e| e.to_string())?;
+        proposal.applied = true;
+    }
+
+    Ok(proposals)
+}
 
 #[tauri::command]
-pub async fn list_document_sections(
-    document_id: String,
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn list_requirement_history(
+    node_id: String,
+    limit: Option<i64>,
     state: State<'_, AppState>,
-) -> Result<Vec<DocumentSection>, String> {
-    let id: Uuid = document_id
-        .parse()
-        .map_err(|e: uuid::Error| e.to_string())?;
+) -> Result<Vec<RequirementHistoryEntry>, String> {
+    let id = parse_uuid("node_id", &node_id).map_err(|e| e.to_string())?;
+    let capped_limit = limit.unwrap_or(20).clamp(1, 200) as usize;
     state
         .store
-        .list_document_sections(id)
+        .list_requirement_history(id, capped_limit)
         .await
         .map_err(|e| e.to_string())
 }
 
+/// Requirement changes across the whole project, newest first.
+/// `source` matches the change source (manual, ai, review, revert, ...).
 #[tauri::command]
-pub async fn list_project_document_sections(
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn list_project_requirement_history(
     project_id: String,
+    since: Option<String>,
+    source: Option<String>,
+    actor: Option<String>,
+    limit: Option<i64>,
+    offset: Option<i64>,
     state: State<'_, AppState>,
-) -> Result<Vec<DocumentSection>, String> {
-    let id: Uuid = project_id.parse().map_err(|e: uuid::Error| e.to_string())?;
+) -> Result<Vec<ProjectRequirementHistoryEntry>, String> {
+    let pid = parse_uuid("project_id", &project_id).map_err(|e| e.to_string())?;
+    let since = parse_rfc3339_opt(since)?;
+    let capped_limit = limit.unwrap_or(100).clamp(1, 1000) as usize;
+    let offset = offset.unwrap_or(0).max(0) as usize;
     state
         .store
-        .list_project_document_sections(id)
+        .list_project_requirement_history(
+            pid,
+            since,
+            source.as_deref().map(str::trim).filter(|s| !s.is_empty()),
+            actor.as_deref().map(str::trim).filter(|s| !s.is_empty()),
+            capped_limit,
+            offset,
+        )
         .await
         .map_err(|e| e.to_string())
 }
 
+/// Requirement change counts per day and per source, for the history chart.
 #[tauri::command]
-pub async fn upsert_document_section(
-    section: DocumentSection,
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn requirement_history_stats(
+    project_id: String,
+    since: Option<String>,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<RequirementHistoryStats, String> {
+    let pid = parse_uuid("project_id", &project_id).map_err(|e| e.to_string())?;
+    let since = parse_rfc3339_opt(since)?;
     state
         .store
-        .upsert_document_section(&section)
+        .requirement_history_stats(pid, since)
         .await
         .map_err(|e| e.to_string())
 }
 
+/// Apply the history retention settings (`history.keep_per_node`,
+/// `history.keep_days`, `history.collapse_window_secs`) to a project. With
+/// `dry_run` nothing is written and the report says what would go.
 #[tauri::command]
-pub async fn delete_document_section(id: String, state: State<'_, AppState>) -> Result<(), String> {
-    let uuid: Uuid = id.parse().map_err(|e: uuid::Error| e.to_string())?;
-    state
-        .store
-        .delete_document_section(uuid)
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn prune_requirement_history(
+    project_id: String,
+    dry_run: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<crate::core::history::HistoryPruneReport, String> {
+    use crate::core::history::{self, HistoryPruneReport, HistoryRetention};
+
+    let pid = parse_uuid("project_id", &project_id).map_err(|e| e.to_string())?;
+    let dry_run = dry_run.unwrap_or(false);
+    let state_ref = &state;
+    let setting = move |key: &'static str| project_or_global_setting(state_ref, key, pid);
+    let mut retention = HistoryRetention::default();
+    if let Some(keep) = setting("history.keep_per_node")
         .await
-        .map_err(|e| e.to_string())
-}
+        .and_then(|v| v.trim().trim_matches('"').parse::<usize>().ok())
+    {
+        retention.keep_per_node = keep;
+    }
+    if let Some(days) = setting("history.keep_days")
+        .await
+        .and_then(|v| v.trim().trim_matches('"').parse::<i64>().ok())
+        .filter(|d| *d >= 0)
+    {
+        retention.keep_days = days;
+    }
+    if let Some(secs) = setting("history.collapse_window_secs")
+        .await
+        .and_then(|v| v.trim().trim_matches('"').parse::<i64>().ok())
+    {
+        retention.collapse_window_secs = secs.max(0);
+    }
 
-#[tauri::command]
-pub async fn delete_document_sections(
-    document_id: String,
-    state: State<'_, AppState>,
-) -> Result<(), String> {
-    let uuid: Uuid = document_id
-        .parse()
-        .map_err(|e: uuid::Error| e.to_string())?;
-    state
+    let _write = if dry_run {
+        None
+    } else {
+        let write = state
+            .store
+            .begin_heavy_write("pruning requirement history", Some(USER_HEAVY_WRITE_WAIT))
+            .await
+            .map_err(|e| e.to_string())?;
+        Some(write)
+    };
+    let entries = state
         .store
-        .delete_document_sections(uuid)
+        .list_all_requirement_history(pid)
         .await
-        .map_err(|e| e.to_string())
-}
-
-// -- Subsystem knowledge ----------------------------------------------------
+        .map_err(|e| e.to_string())?;
+    let entries_before = entries.len();
+    let plan = history::plan(entries, &retention, Utc::now());
 
-#[tauri::command]
-pub async fn list_subsystem_knowledge(
-    subsystem_id: String,
-    state: State<'_, AppState>,
-) -> Result<Vec<SubsystemKnowledgePage>, String> {
-    let id: Uuid = subsystem_id
-        .parse()
-        .map_err(|e: uuid::Error| e.to_string())?;
-    state
-        .store
-        .list_subsystem_knowledge(id)
-        .await
-        .map_err(|e| e.to_string())
+    let removed = if dry_run {
+        plan.deleted.len()
+    } else {
+        state
+            .store
+            .apply_requirement_history_prune(&plan.updated, &plan.deleted)
+            .await
+            .map_err(|e| e.to_string())? as usize
+    };
+    Ok(HistoryPruneReport {
+        project_id: pid,
+        dry_run,
+        retention,
+        entries_before,
+        removed,
+        collapsed: plan.collapsed,
+        expired: plan.expired,
+        nodes_affected: plan.nodes_affected,
+    })
+}
+
+fn parse_rfc3339_opt(value: Option<String>) -> Result<Option<chrono::DateTime<Utc>>, String> {
+    value
+        .map(|s| {
+            chrono::DateTime::parse_from_rfc3339(s.trim())
+                .map(|d| d.with_timezone(&Utc))
+                .map_err(|e| e.to_string())
+        })
+        .transpose()
 }
 
+/// Everything that refers to a node, for the delete confirmation.
 #[tauri::command]
-pub async fn upsert_subsystem_knowledge(
-    page: SubsystemKnowledgePage,
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn node_usage(
+    node_id: String,
     state: State<'_, AppState>,
-) -> Result<(), String> {
-    state
+) -> Result<NodeUsage, CommandError> {
+    let id = parse_uuid("node_id", &node_id)?;
+    let node = state
         .store
-        .upsert_subsystem_knowledge(&page)
-        .await
-        .map_err(|e| e.to_string())
+        .get_node(id)
+        .await?
+        .ok_or_else(|| CommandError::not_found(format!("node {id}")))?;
+    state.store.node_usage(&node).await.map_err(CommandError::from)
 }
 
+/// Deleting a ValueType that types block properties is refused unless
+/// `force` is set, in which case those properties become untyped.
+/// Returns what referred to the node, as `node_usage` reported it just
+/// before the delete, or `None` if there was no such node.
 #[tauri::command]
-pub async fn delete_subsystem_knowledge(
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn delete_node(
     id: String,
+    force: Option<bool>,
+    actor: Option<String>,
+    app: tauri::AppHandle,
     state: State<'_, AppState>,
-) -> Result<(), String> {
-    let uuid: Uuid = id.parse().map_err(|e: uuid::Error| e.to_string())?;
-    state
-        .store
-        .delete_subsystem_knowledge(uuid)
-        .await
-        .map_err(|e| e.to_string())
+) -> Result<Option<NodeUsage>, CommandError> {
+    let uuid = parse_uuid("id", &id)?;
+    let existing = state.store.get_node(uuid).await?;
+    if !force.unwrap_or(false) {
+        if let Some(node) = existing.as_ref().filter(|n| n.kind == NodeKind::ValueType) {
+            let refs = state.store.count_value_type_references(uuid).await?;
+            if refs > 0 {
+                return Err(CommandError::invalid(format!(
+                    "value type '{}' types {refs} block propert{}; delete with force to leave them untyped",
+                    node.name,
+                    if refs == 1 { "y" } else { "ies" }
+                )));
+            }
+        }
+    }
+    // Incident edges go with the node (ON DELETE CASCADE); note them first
+    // so their cached validation issues can be cleared too.
+    let mut touched = vec![uuid];
+    let mut usage = None;
+    if let Some(node) = existing.as_ref() {
+        let incident = state.store.edges_for_node(uuid).await?;
+        touched.extend(incident.iter().map(|e| e.id));
+        touched.extend(incident.iter().flat_map(connects_endpoints));
+        usage = Some(state.store.node_usage(node).await?);
+    }
+    state.store.delete_node(uuid, &actor_or_user(actor)).await?;
+    if let Some(node) = existing {
+        let _ = revalidate_touched(&app, &state, node.project_id, touched).await;
+    }
+    Ok(usage)
 }
 
-// -- Subsystem artifacts ----------------------------------------------------
-
+/// Split a compound requirement into one new requirement per part; see
+/// `core::split`. With `keep_parent` the parent is marked Obsolete and
+/// refined by the children, otherwise it is deleted. `propagate_links`
+/// copies «satisfies» and «verifies» edges into the parent to every child.
 #[tauri::command]
-pub async fn list_subsystem_artifacts(
-    subsystem_id: String,
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn split_requirement(
+    node_id: String,
+    parts: Vec<String>,
+    keep_parent: bool,
+    propagate_links: Option<bool>,
+    actor: Option<String>,
+    app: tauri::AppHandle,
     state: State<'_, AppState>,
-) -> Result<Vec<SubsystemArtifact>, String> {
-    let id: Uuid = subsystem_id
-        .parse()
-        .map_err(|e: uuid::Error| e.to_string())?;
-    state
+) -> Result<crate::core::split::RequirementSplit, CommandError> {
+    use crate::core::split::{self, SplitOptions};
+
+    let id = parse_uuid("node_id", &node_id)?;
+    let parent = state
         .store
-        .list_subsystem_artifacts(id)
-        .await
-        .map_err(|e| e.to_string())
+        .get_node(id)
+        .await?
+        .ok_or_else(|| CommandError::not_found(format!("node {id}")))?;
+    let parent_edges = state.store.edges_for_node(id).await?;
+    let taken_ids = state
+        .store
+        .list_node_summaries(parent.project_id, Some(&NodeKind::Requirement), true)
+        .await?
+        .into_iter()
+        .filter_map(|n| n.req_id)
+        .collect();
+
+    let options = SplitOptions {
+        keep_parent,
+        propagate_links: propagate_links.unwrap_or(false),
+        actor: actor_or_user(actor),
+        now: Utc::now(),
+    };
+    let plan = split::plan(&parent, &parts, &parent_edges, &taken_ids, &options)
+        .map_err(CommandError::invalid)?;
+    state.store.apply_requirement_split(&parent, &plan).await?;
+
+    let mut touched = vec![id];
+    touched.extend(plan.children.iter().map(|c| c.id));
+    touched.extend(plan.edges.iter().map(|e| e.id));
+    if plan.parent.is_none() {
+        touched.extend(parent_edges.iter().map(|e| e.id));
+    }
+    let _ = revalidate_touched(&app, &state, parent.project_id, touched).await;
+    Ok(plan)
 }
 
+/// Archive obsolete nodes. Their edges and history are kept, so
+/// traceability through them stays intact, but they drop out of listings,
+/// validation and diagram population. Returns the nodes that changed.
 #[tauri::command]
-pub async fn list_project_artifacts(
-    project_id: String,
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn archive_nodes(
+    ids: Vec<String>,
+    actor: Option<String>,
+    app: tauri::AppHandle,
     state: State<'_, AppState>,
-) -> Result<Vec<SubsystemArtifact>, String> {
-    let id: Uuid = project_id.parse().map_err(|e: uuid::Error| e.to_string())?;
-    state
-        .store
-        .list_project_artifacts(id)
-        .await
-        .map_err(|e| e.to_string())
+) -> Result<Vec<Node>, CommandError> {
+    set_nodes_archived(ids, true, actor, &app, &state).await
 }
 
+/// Undo `archive_nodes`. Returns the nodes that changed.
 #[tauri::command]
-pub async fn upsert_subsystem_artifact(
-    artifact: SubsystemArtifact,
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn unarchive_nodes(
+    ids: Vec<String>,
+    actor: Option<String>,
+    app: tauri::AppHandle,
     state: State<'_, AppState>,
-) -> Result<(), String> {
-    state
-        .store
-        .upsert_subsystem_artifact(&artifact)
-        .await
-        .map_err(|e| e.to_string())
+) -> Result<Vec<Node>, CommandError> {
+    set_nodes_archived(ids, false, actor, &app, &state).await
+}
+
+async fn set_nodes_archived(
+    ids: Vec<String>,
+    archived: bool,
+    actor: Option<String>,
+    app: &tauri::AppHandle,
+    state: &State<'_, AppState>,
+) -> Result<Vec<Node>, CommandError> {
+    let ids = ids.iter().map(|id| parse_uuid("ids", id)).collect::<Result<Vec<_>, _>>()?;
+    let actor = actor_or_user(actor);
+    let changed = state.store.set_nodes_archived(&ids, archived, &actor).await?;
+
+    let mut by_project: std::collections::HashMap<Uuid, Vec<Uuid>> = Default::default();
+    for node in &changed {
+        by_project.entry(node.project_id).or_default().push(node.id);
+    }
+    for (project_id, touched) in by_project {
+        if archived {
+            let _ = revalidate_touched(app, state, project_id, touched).await;
+        } else {
+            // A restored node's hierarchy edges may close a cycle again,
+            // which only a full validation sees.
+            use tauri::Emitter;
+            let Ok(issues) = full_validation(state, project_id).await else {
+                continue;
+            };
+            let delta = state.validation_cache.lock().unwrap().reconcile(project_id, &issues);
+            if !delta.is_empty() {
+                let _ = app.emit(crate::events::VALIDATION_UPDATED, &delta);
+            }
+        }
+    }
+    Ok(changed)
 }
 
+// ── Edges ─────────────────────────────────────────────────────────────────────
+
 #[tauri::command]
-pub async fn delete_subsystem_artifact(
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn upsert_edge(
+    edge: Edge,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), CommandError> {
+    check_edge_references(&state, &edge).await?;
+    // A re-pointed «connects» edge also changes its old endpoints.
+    let previous = state.store.get_edge(edge.id).await?;
+    state.store.upsert_edge(&edge).await?;
+    let mut touched = vec![edge.id];
+    touched.extend(connects_endpoints(&edge));
+    touched.extend(previous.iter().flat_map(connects_endpoints));
+    let _ = revalidate_touched(&app, &state, edge.project_id, touched).await;
+    Ok(())
+}
+
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn delete_edge(
     id: String,
+    actor: Option<String>,
+    app: tauri::AppHandle,
     state: State<'_, AppState>,
-) -> Result<(), String> {
-    let uuid: Uuid = id.parse().map_err(|e: uuid::Error| e.to_string())?;
-    state
-        .store
-        .delete_subsystem_artifact(uuid)
-        .await
-        .map_err(|e| e.to_string())
+) -> Result<(), CommandError> {
+    let uuid = parse_uuid("id", &id)?;
+    let existing = state.store.get_edge(uuid).await?;
+    state.store.delete_edge(uuid, &actor_or_user(actor)).await?;
+    if let Some(edge) = existing {
+        let mut touched = vec![uuid];
+        touched.extend(connects_endpoints(&edge));
+        let _ = revalidate_touched(&app, &state, edge.project_id, touched).await;
+    }
+    Ok(())
 }
 
-// -- Subsystem activity -----------------------------------------------------
+/// A "not_found" issue on `field`: an id in a struct from the frontend
+/// that names no record, or one in another project.
+fn missing_reference(field: &str, message: String) -> FieldIssue {
+    FieldIssue {
+        field: Some(field.to_string()),
+        code: "not_found".to_string(),
+        message,
+    }
+}
+
+fn reject_issues(issues: Vec<FieldIssue>) -> Result<(), CommandError> {
+    if issues.is_empty() {
+        Ok(())
+    } else {
+        Err(CommandError::Validation { issues })
+    }
+}
+
+/// The edge's project and both endpoints must exist, and the endpoints
+/// belong to that project, so a stale id fails here with the field named
+/// rather than as a foreign key conflict.
+async fn check_edge_references(state: &State<'_, AppState>, edge: &Edge) -> Result<(), CommandError> {
+    let mut issues = Vec::new();
+    if state.store.get_project(edge.project_id).await?.is_none() {
+        issues.push(missing_reference("project_id", format!("project {} does not exist", edge.project_id)));
+    }
+    for (field, id) in [("source_id", edge.source_id), ("target_id", edge.target_id)] {
+        match state.store.get_node(id).await? {
+            None => issues.push(missing_reference(field, format!("node {id} does not exist"))),
+            Some(node) if node.project_id != edge.project_id => issues.push(missing_reference(
+                field,
+                format!("'{}' belongs to another project", node.name),
+            )),
+            Some(_) => {}
+        }
+    }
+    reject_issues(issues)
+}
+
+/// Endpoints of a «connects» edge, whose `PORT_UNCONNECTED` issues depend on
+/// it; empty for other kinds.
+fn connects_endpoints(edge: &Edge) -> Vec<Uuid> {
+    if edge.kind == EdgeKind::Connects {
+        vec![edge.source_id, edge.target_id]
+    } else {
+        Vec::new()
+    }
+}
 
+/// Set or clear (empty text) the free-text rationale on an edge, stored in
+/// `meta.rationale` and exported as an XMI comment.
 #[tauri::command]
-pub async fn list_subsystem_activity(
-    subsystem_id: String,
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn set_edge_rationale(
+    edge_id: String,
+    text: String,
     state: State<'_, AppState>,
-) -> Result<Vec<SubsystemActivity>, String> {
-    let id: Uuid = subsystem_id
-        .parse()
-        .map_err(|e: uuid::Error| e.to_string())?;
-    state
+) -> Result<Edge, CommandError> {
+    let uuid = parse_uuid("edge_id", &edge_id)?;
+    let mut edge = state
         .store
-        .list_subsystem_activity(id)
-        .await
-        .map_err(|e| e.to_string())
+        .get_edge(uuid)
+        .await?
+        .ok_or_else(|| CommandError::not_found(format!("edge {uuid}")))?;
+    let text = text.trim();
+    if text.is_empty() {
+        edge.meta.remove("rationale");
+    } else {
+        edge.meta.insert("rationale".to_string(), serde_json::json!(text));
+    }
+    edge.modified_at = Utc::now();
+    state.store.upsert_edge(&edge).await?;
+    Ok(edge)
 }
 
 #[tauri::command]
-pub async fn add_subsystem_activity(
-    entry: SubsystemActivity,
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn edges_for_node(
+    node_id: String,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<Vec<Edge>, CommandError> {
+    let uuid = parse_uuid("node_id", &node_id)?;
     state
         .store
-        .add_subsystem_activity(&entry)
+        .edges_for_node(uuid)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(CommandError::from)
 }
 
-// -- Settings ---------------------------------------------------------------
-
+/// Project edges filtered by kind, optionally restricted to edges whose
+/// source/target nodes are of a given kind. An empty `kinds` list matches all.
 #[tauri::command]
-pub async fn get_setting(
-    key: String,
-    project_id: Option<String>,
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn list_project_edges_filtered(
+    project_id: String,
+    kinds: Vec<String>,
+    source_kind: Option<String>,
+    target_kind: Option<String>,
     state: State<'_, AppState>,
-) -> Result<Option<String>, String> {
-    let pid = match project_id {
-        Some(id) => Some(id.parse().map_err(|e: uuid::Error| e.to_string())?),
-        None => None,
+) -> Result<Vec<Edge>, CommandError> {
+    use crate::core::store::{parse_edge_kind, parse_node_kind};
+
+    let id = parse_uuid("project_id", &project_id)?;
+    let edge_kinds = kinds
+        .iter()
+        .map(|k| parse_edge_kind(k))
+        .collect::<anyhow::Result<Vec<_>>>()
+        .map_err(|e| CommandError::invalid(e.to_string()))?;
+    let node_kind = |kind: Option<String>| {
+        kind.as_deref()
+            .map(parse_node_kind)
+            .transpose()
+            .map_err(|e| CommandError::invalid(e.to_string()))
     };
+    let source_kind = node_kind(source_kind)?;
+    let target_kind = node_kind(target_kind)?;
+
     state
         .store
-        .get_setting(&key, pid)
+        .list_edges_filtered(id, &edge_kinds, source_kind.as_ref(), target_kind.as_ref())
         .await
-        .map_err(|e| e.to_string())
+        .map_err(CommandError::from)
 }
 
+// ── Diagrams ──────────────────────────────────────────────────────────────────
+
 #[tauri::command]
-pub async fn set_setting(
-    key: String,
-    value: String,
-    project_id: Option<String>,
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn list_diagrams(
+    project_id: String,
     state: State<'_, AppState>,
-) -> Result<(), String> {
-    let pid = match project_id {
-        Some(id) => Some(id.parse().map_err(|e: uuid::Error| e.to_string())?),
-        None => None,
-    };
+) -> Result<Vec<Diagram>, CommandError> {
+    let id = parse_uuid("project_id", &project_id)?;
     state
         .store
-        .set_setting(&key, pid, &value)
+        .list_diagrams(id)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(CommandError::from)
 }
 
-// ── Validation ────────────────────────────────────────────────────────────────
-
 #[tauri::command]
-pub async fn validate_model(
-    project_id: String,
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn upsert_diagram(
+    diagram: Diagram,
+    actor: Option<String>,
     state: State<'_, AppState>,
-) -> Result<Vec<validation::ValidationIssue>, String> {
-    let id: Uuid = project_id.parse().map_err(|e: uuid::Error| e.to_string())?;
-    let nodes = state
+) -> Result<(), CommandError> {
+    state
         .store
-        .list_nodes(id)
+        .upsert_diagram(&diagram, &actor_or_user(actor))
         .await
-        .map_err(|e| e.to_string())?;
-    let edges = {
-        let mut all = Vec::new();
-        for node in &nodes {
-            let mut e = state
-                .store
-                .edges_for_node(node.id)
-                .await
-                .map_err(|e| e.to_string())?;
-            all.append(&mut e);
-        }
-        all.sort_by_key(|e| e.id);
-        all.dedup_by_key(|e| e.id);
-        all
-    };
-    Ok(validation::validate(&nodes, &edges))
+        .map_err(CommandError::from)
 }
 
-// ── Export ────────────────────────────────────────────────────────────────────
-
+/// Validate and store a diagram's view filters. Returns the normalized set.
 #[tauri::command]
-pub async fn export_markdown(
-    project_id: String,
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn set_diagram_filters(
+    diagram_id: String,
+    filters: DiagramFilters,
+    actor: Option<String>,
     state: State<'_, AppState>,
-) -> Result<String, String> {
-    let id: Uuid = project_id.parse().map_err(|e: uuid::Error| e.to_string())?;
-    let project = state
-        .store
-        .get_project(id)
-        .await
-        .map_err(|e| e.to_string())?
-        .ok_or_else(|| "project not found".to_string())?;
-    let nodes = state
+) -> Result<DiagramFilters, CommandError> {
+    let id = parse_uuid("diagram_id", &diagram_id)?;
+    let mut diagram = state
         .store
-        .list_nodes(id)
-        .await
-        .map_err(|e| e.to_string())?;
-    let edges = {
-        let mut all = Vec::new();
-        for node in &nodes {
-            let mut e = state
-                .store
-                .edges_for_node(node.id)
-                .await
-                .map_err(|e| e.to_string())?;
-            all.append(&mut e);
+        .get_diagram(id)
+        .await?
+        .ok_or_else(|| CommandError::not_found(format!("diagram {id}")))?;
+
+    let mut filters = filters;
+    for kind in filters.hidden_kinds.iter_mut() {
+        *kind = kind.trim().to_lowercase();
+        kind.parse::<NodeKind>().map_err(CommandError::invalid)?;
+    }
+    filters.hidden_kinds.sort();
+    filters.hidden_kinds.dedup();
+    filters.hidden_node_ids.sort();
+    filters.hidden_node_ids.dedup();
+    for node_id in &filters.hidden_node_ids {
+        let node = state.store.get_node(*node_id).await?;
+        if node.map(|n| n.project_id) != Some(diagram.project_id) {
+            return Err(CommandError::invalid(format!(
+                "node {node_id} is not in this diagram's project"
+            )));
         }
-        all.sort_by_key(|e| e.id);
-        all.dedup_by_key(|e| e.id);
-        all
-    };
-    Ok(crate::core::export::to_markdown(&project, &nodes, &edges))
+    }
+
+    if filters.is_empty() {
+        diagram.layout_options.remove(DiagramFilters::LAYOUT_KEY);
+    } else {
+        let value = serde_json::to_value(&filters)?;
+        diagram
+            .layout_options
+            .insert(DiagramFilters::LAYOUT_KEY.to_string(), value);
+    }
+    diagram.modified_at = Utc::now();
+    state.store.upsert_diagram(&diagram, &actor_or_user(actor)).await?;
+    Ok(filters)
 }
 
+/// Render-ready IR for a diagram with its stored view filters applied.
 #[tauri::command]
-pub async fn export_json(project_id: String, state: State<'_, AppState>) -> Result<String, String> {
-    let id: Uuid = project_id.parse().map_err(|e: uuid::Error| e.to_string())?;
-    let project = state
-        .store
-        .get_project(id)
-        .await
-        .map_err(|e| e.to_string())?
-        .ok_or_else(|| "project not found".to_string())?;
-    let nodes = state
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn get_diagram_ir(
+    diagram_id: String,
+    state: State<'_, AppState>,
+) -> Result<crate::diagrams::ir::DiagramIR, CommandError> {
+    let id = parse_uuid("diagram_id", &diagram_id)?;
+    let diagram = state
         .store
-        .list_nodes(id)
-        .await
-        .map_err(|e| e.to_string())?;
-    let edges = {
-        let mut all = Vec::new();
-        for node in &nodes {
-            let mut e = state
-                .store
-                .edges_for_node(node.id)
-                .await
-                .map_err(|e| e.to_string())?;
-            all.append(&mut e);
-        }
-        all.sort_by_key(|e| e.id);
-        all.dedup_by_key(|e| e.id);
-        all
-    };
-    crate::core::export::to_native_json(&project, &nodes, &edges).map_err(|e| e.to_string())
+        .get_diagram(id)
+        .await?
+        .ok_or_else(|| CommandError::not_found(format!("diagram {id}")))?;
+
+    let nodes = state.store.list_nodes(diagram.project_id).await?;
+    // In creation order, not `list_edges`' id order: edges are painted in
+    // IR order, so a new edge draws over the ones already there.
+    let edges = state.store.list_edges_by_kind(diagram.project_id, &[]).await?;
+    let elements = state.store.diagram_elements(id).await?;
+    let routes = state.store.diagram_edge_routes(id).await?;
+    let properties = state.store.list_project_block_properties(diagram.project_id).await?;
+
+    Ok(crate::diagrams::ir::build_ir(
+        diagram.id,
+        diagram.kind.clone(),
+        diagram.name.clone(),
+        &nodes,
+        &edges,
+        &elements,
+        &routes,
+        &properties,
+        &diagram.filters(),
+        &[],
+        &[],
+        &[],
+        None,
+    ))
 }
 
+/// Drops redundant bend points from a diagram's stored edge routes and, with
+/// `orthogonal`, straightens nearly horizontal or vertical segments. Only
+/// changed routes are written back.
 #[tauri::command]
-pub async fn export_xmi(project_id: String, state: State<'_, AppState>) -> Result<String, String> {
-    let id: Uuid = project_id.parse().map_err(|e: uuid::Error| e.to_string())?;
-    let project = state
-        .store
-        .get_project(id)
-        .await
-        .map_err(|e| e.to_string())?
-        .ok_or_else(|| "project not found".to_string())?;
-    let nodes = state
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn simplify_edge_routes(
+    diagram_id: String,
+    tolerance: f64,
+    orthogonal: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::diagrams::routing::RouteSimplification>, CommandError> {
+    use crate::diagrams::routing::{simplify_route, RouteSimplification};
+
+    let id = parse_uuid("diagram_id", &diagram_id)?;
+    if !tolerance.is_finite() || tolerance < 0.0 {
+        return Err(CommandError::invalid("tolerance must be a non-negative number"));
+    }
+    state
         .store
-        .list_nodes(id)
-        .await
-        .map_err(|e| e.to_string())?;
-    let edges = {
-        let mut all = Vec::new();
-        for node in &nodes {
-            let mut e = state
-                .store
-                .edges_for_node(node.id)
-                .await
-                .map_err(|e| e.to_string())?;
-            all.append(&mut e);
+        .get_diagram(id)
+        .await?
+        .ok_or_else(|| CommandError::not_found(format!("diagram {id}")))?;
+
+    let orthogonal = orthogonal.unwrap_or(false);
+    let mut report = Vec::new();
+    let mut changed = Vec::new();
+    for mut route in state.store.diagram_edge_routes(id).await? {
+        let before = route.waypoints.len();
+        let simplified = simplify_route(&route.waypoints, tolerance, orthogonal);
+        let moved = simplified.len() != before
+            || simplified
+                .iter()
+                .zip(&route.waypoints)
+                .any(|(a, b)| a.x != b.x || a.y != b.y);
+        report.push(RouteSimplification {
+            edge_id: route.edge_id,
+            points_before: before,
+            points_after: simplified.len(),
+            points_removed: before - simplified.len(),
+        });
+        if moved {
+            route.waypoints = simplified;
+            changed.push(route);
         }
-        all.sort_by_key(|e| e.id);
-        all.dedup_by_key(|e| e.id);
-        all
-    };
-    Ok(crate::core::export::to_xmi(&project, &nodes, &edges))
+    }
+    state.store.update_edge_route_waypoints(&changed).await?;
+    Ok(report)
 }
 
-// ── AI availability ───────────────────────────────────────────────────────────
-
 #[tauri::command]
-pub async fn ai_available(state: State<'_, AppState>) -> Result<bool, String> {
-    Ok(state.ai_provider.lock().unwrap().is_available())
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn diagram_elements(
+    diagram_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<DiagramElement>, CommandError> {
+    let id = parse_uuid("diagram_id", &diagram_id)?;
+    state
+        .store
+        .diagram_elements(id)
+        .await
+        .map_err(CommandError::from)
 }
 
 #[tauri::command]
-pub async fn ai_provider_name(state: State<'_, AppState>) -> Result<String, String> {
-    Ok(state.ai_provider.lock().unwrap().name().to_string())
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn upsert_diagram_element(
+    element: DiagramElement,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), CommandError> {
+    use crate::diagrams::journal::Throttle;
+
+    // Checked before the journal takes it, so a deferred write can't fail
+    // later where nobody sees it.
+    check_element_references(&state, &element).await?;
+    let throttle = state
+        .layout_journal
+        .lock()
+        .unwrap()
+        .offer(&element, std::time::Instant::now());
+    match throttle {
+        Throttle::WriteNow => state
+            .store
+            .upsert_diagram_element(&element, "user")
+            .await
+            .map_err(CommandError::from),
+        Throttle::Deferred { flush_after, schedule } => {
+            if schedule {
+                let (diagram_id, node_id) = (element.diagram_id, element.node_id);
+                tauri::async_runtime::spawn(async move {
+                    tokio::time::sleep(flush_after).await;
+                    let state = app.state::<AppState>();
+                    let due = state.layout_journal.lock().unwrap().take_pending(
+                        diagram_id,
+                        node_id,
+                        std::time::Instant::now(),
+                    );
+                    if let Some(element) = due {
+                        if let Err(e) = state.store.upsert_diagram_element(&element, "user").await {
+                            eprintln!("warning: deferred layout write failed: {e}");
+                        }
+                    }
+                });
+            }
+            Ok(())
+        }
+    }
 }
 
-#[tauri::command]
-pub async fn ollama_status(state: State<'_, AppState>) -> Result<serde_json::Value, String> {
-    use crate::ai::ollama::OllamaProvider;
+/// The element's diagram and node must exist, in the same project.
+async fn check_element_references(
+    state: &State<'_, AppState>,
+    element: &DiagramElement,
+) -> Result<(), CommandError> {
+    let mut issues = Vec::new();
+    let diagram = state.store.get_diagram(element.diagram_id).await?;
+    if diagram.is_none() {
+        issues.push(missing_reference(
+            "diagram_id",
+            format!("diagram {} does not exist", element.diagram_id),
+        ));
+    }
+    match state.store.get_node(element.node_id).await? {
+        None => issues.push(missing_reference(
+            "node_id",
+            format!("node {} does not exist", element.node_id),
+        )),
+        Some(node) if diagram.as_ref().is_some_and(|d| d.project_id != node.project_id) => issues
+            .push(missing_reference(
+                "node_id",
+                format!("'{}' belongs to another project than the diagram", node.name),
+            )),
+        Some(_) => {}
+    }
+    reject_issues(issues)
+}
 
-    let base_url = state
+// -- Layout sessions --------------------------------------------------------
+
+/// Setting: how many ended layout sessions each diagram keeps (default 20).
+const LAYOUT_SESSION_LIMIT_SETTING: &str = "layout.session_limit";
+
+/// Starts a layout session: the diagram's element geometry is captured so
+/// the session can be cancelled, and element writes are coalesced per
+/// element until it ends. A session already open on the diagram is
+/// committed first.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn begin_layout_session(
+    diagram_id: String,
+    state: State<'_, AppState>,
+) -> Result<LayoutSession, CommandError> {
+    let id = parse_uuid("diagram_id", &diagram_id)?;
+    let diagram = state
         .store
-        .get_setting("ai.ollama.base_url", None)
+        .get_diagram(id)
+        .await?
+        .ok_or_else(|| CommandError::not_found(format!("diagram {id}")))?;
+
+    let previous = state.layout_journal.lock().unwrap().session_for(id);
+    if let Some(previous) = previous {
+        finish_layout_session(&state, id, previous, true).await?;
+    }
+    let session = state.store.begin_layout_session(id).await?;
+    state.layout_journal.lock().unwrap().open(id, session.id);
+
+    let keep = project_or_global_setting(&state, LAYOUT_SESSION_LIMIT_SETTING, diagram.project_id)
         .await
-        .unwrap_or(None)
-        .unwrap_or_else(|| "http://localhost:11434".to_string());
-    let model = state
+        .and_then(|v| v.trim().trim_matches('"').parse::<i64>().ok())
+        .filter(|v| *v >= 0)
+        .unwrap_or(20);
+    state.store.prune_layout_sessions(id, keep).await?;
+    Ok(session)
+}
+
+/// Ends a layout session. With `commit` the new positions stay; without,
+/// the geometry captured when it began is restored.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn end_layout_session(
+    session_id: String,
+    commit: bool,
+    state: State<'_, AppState>,
+) -> Result<LayoutSession, CommandError> {
+    let id = parse_uuid("session_id", &session_id)?;
+    let session = state
         .store
-        .get_setting("ai.ollama.model", None)
-        .await
-        .unwrap_or(None)
-        .unwrap_or_else(|| "qwen2.5:7b".to_string());
+        .get_layout_session(id)
+        .await?
+        .ok_or_else(|| CommandError::not_found(format!("layout session {id}")))?;
+    if session.status != LayoutSessionStatus::Open {
+        return Err(CommandError::invalid(format!("layout session is already {}", session.status)));
+    }
+    finish_layout_session(&state, session.diagram_id, id, commit).await?;
+    state
+        .store
+        .get_layout_session(id)
+        .await?
+        .ok_or_else(|| CommandError::not_found(format!("layout session {id}")))
+}
+
+/// Flushes or drops the session's held writes, restores its snapshot when
+/// cancelling, and marks it ended.
+async fn finish_layout_session(
+    state: &State<'_, AppState>,
+    diagram_id: Uuid,
+    session_id: Uuid,
+    commit: bool,
+) -> Result<(), CommandError> {
+    let held = state
+        .layout_journal
+        .lock()
+        .unwrap()
+        .close(diagram_id, session_id)
+        .unwrap_or_default();
+    if commit {
+        for element in &held {
+            state.store.upsert_diagram_element(element, "user").await?;
+        }
+        state.store.end_layout_session(session_id, LayoutSessionStatus::Committed).await?;
+    } else {
+        let snapshot = state.store.layout_session_snapshot(session_id).await?;
+        state.store.restore_element_geometry(diagram_id, &snapshot).await?;
+        state.store.end_layout_session(session_id, LayoutSessionStatus::Cancelled).await?;
+    }
+    Ok(())
+}
+
+/// The diagram's layout sessions, newest first.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn list_layout_sessions(
+    diagram_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<LayoutSession>, CommandError> {
+    let id = parse_uuid("diagram_id", &diagram_id)?;
+    Ok(state.store.list_layout_sessions(id).await?)
+}
+
+/// Puts the diagram back the way it was when the session began.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn restore_layout_session(
+    session_id: String,
+    state: State<'_, AppState>,
+) -> Result<(), CommandError> {
+    let id = parse_uuid("session_id", &session_id)?;
+    let session = state
+        .store
+        .get_layout_session(id)
+        .await?
+        .ok_or_else(|| CommandError::not_found(format!("layout session {id}")))?;
+    let snapshot = state.store.layout_session_snapshot(id).await?;
+    Ok(state.store.restore_element_geometry(session.diagram_id, &snapshot).await?)
+}
 
-    let active_provider = state
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn delete_diagram(
+    diagram_id: String,
+    actor: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), CommandError> {
+    let id = parse_uuid("diagram_id", &diagram_id)?;
+    state
         .store
-        .get_setting("ai.provider", None)
+        .delete_diagram(id, &actor_or_user(actor))
         .await
-        .unwrap_or(None)
-        .unwrap_or_default();
+        .map_err(CommandError::from)
+}
 
-    let probe = OllamaProvider::new(&model, Some(base_url.clone()));
-    let reachable = probe.check_available().await;
+// -- Style presets ----------------------------------------------------------
 
-    Ok(serde_json::json!({
-        "reachable": reachable,
-        "base_url": base_url,
-        "model": model,
-        "is_active": active_provider == "ollama",
-    }))
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn list_style_presets(
+    project_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<StylePreset>, CommandError> {
+    let pid = parse_uuid("project_id", &project_id)?;
+    Ok(state.store.list_style_presets(pid).await?)
 }
 
+/// Create or update a preset. Target kinds are normalized like diagram
+/// filters; style keys outside `StylePreset::STYLE_KEYS`, or values of the
+/// wrong type, are rejected.
 #[tauri::command]
-pub async fn set_ollama_config(
-    model: String,
-    base_url: Option<String>,
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn upsert_style_preset(
+    mut preset: StylePreset,
     state: State<'_, AppState>,
-) -> Result<(), String> {
-    use crate::ai::ollama::OllamaProvider;
+) -> Result<StylePreset, CommandError> {
+    let mut issues = Vec::new();
+    let mut issue = |field: String, code: &str, message: String| {
+        issues.push(FieldIssue { field: Some(field), code: code.to_string(), message });
+    };
+    preset.name = preset.name.trim().to_string();
+    if preset.name.is_empty() {
+        issue("name".into(), "required", "the preset name must not be empty".into());
+    }
+    for kind in preset.target_kinds.iter_mut() {
+        *kind = kind.trim().to_lowercase();
+        if let Err(message) = kind.parse::<NodeKind>() {
+            issue("target_kinds".into(), "invalid", message);
+        }
+    }
+    preset.target_kinds.sort();
+    preset.target_kinds.dedup();
+    let mut keys: Vec<&String> = preset.style.keys().collect();
+    keys.sort();
+    for key in keys {
+        let value = &preset.style[key];
+        let (valid, expected) = match key.as_str() {
+            "fill" | "stroke" | "text_color" => (
+                value.as_str().is_some_and(|v| !v.trim().is_empty()),
+                "a color string",
+            ),
+            "corner_radius" | "font_size" => {
+                (value.as_f64().is_some_and(|v| v >= 0.0), "a non-negative number")
+            }
+            _ => {
+                let allowed = StylePreset::STYLE_KEYS.join(", ");
+                let message = format!("'{key}' is not a style key; use one of {allowed}");
+                issue(format!("style.{key}"), "invalid", message);
+                continue;
+            }
+        };
+        if !valid {
+            issue(format!("style.{key}"), "invalid", format!("'{key}' must be {expected}"));
+        }
+    }
+    if !issues.is_empty() {
+        return Err(CommandError::Validation { issues });
+    }
 
-    let url = base_url.unwrap_or_else(|| "http://localhost:11434".to_string());
+    if state.store.get_project(preset.project_id).await?.is_none() {
+        return Err(CommandError::not_found(format!("project {}", preset.project_id)));
+    }
+    preset.modified_at = Utc::now();
+    state.store.upsert_style_preset(&preset).await?;
+    Ok(preset)
+}
+
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn delete_style_preset(
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<(), CommandError> {
+    let id = parse_uuid("id", &id)?;
+    if !state.store.delete_style_preset(id).await? {
+        return Err(CommandError::not_found(format!("style preset {id}")));
+    }
+    Ok(())
+}
+
+/// Merge a preset's style into the `style_overrides` of the diagram's
+/// elements whose node kind the preset targets, narrowed to `node_kinds`
+/// when given. Other override keys are kept. Returns the changed elements.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn apply_style_preset(
+    diagram_id: String,
+    preset_id: String,
+    node_kinds: Option<Vec<String>>,
+    actor: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<DiagramElement>, CommandError> {
+    let id = parse_uuid("diagram_id", &diagram_id)?;
+    let diagram = state
+        .store
+        .get_diagram(id)
+        .await?
+        .ok_or_else(|| CommandError::not_found(format!("diagram {id}")))?;
+    let preset_id = parse_uuid("preset_id", &preset_id)?;
+    let preset = state
+        .store
+        .get_style_preset(preset_id)
+        .await?
+        .filter(|p| p.project_id == diagram.project_id)
+        .ok_or_else(|| CommandError::not_found(format!("style preset {preset_id}")))?;
+    let node_kinds: Option<Vec<String>> = node_kinds
+        .map(|kinds| kinds.iter().map(|k| k.trim().to_lowercase()).collect());
+
+    let kinds: std::collections::HashMap<Uuid, NodeKind> = state
+        .store
+        .list_node_summaries(diagram.project_id, None, true)
+        .await?
+        .into_iter()
+        .map(|n| (n.id, n.kind))
+        .collect();
+    let mut changed = Vec::new();
+    for mut element in state.store.diagram_elements(id).await? {
+        let Some(kind) = kinds.get(&element.node_id) else {
+            continue;
+        };
+        let selected = match &node_kinds {
+            Some(ks) => ks.contains(&kind.to_string()),
+            None => true,
+        };
+        if !selected || !preset.applies_to(kind) {
+            continue;
+        }
+        let before = element.style_overrides.clone();
+        element
+            .style_overrides
+            .extend(preset.style.iter().map(|(k, v)| (k.clone(), v.clone())));
+        if element.style_overrides != before {
+            changed.push(element);
+        }
+    }
+
+    let styles: Vec<_> = changed.iter().map(|e| (e.id, e.style_overrides.clone())).collect();
+    if !styles.is_empty() {
+        let summary =
+            format!("applied style preset '{}' to {} element(s)", preset.name, styles.len());
+        state
+            .store
+            .set_element_styles(id, &styles, &summary, &actor_or_user(actor))
+            .await?;
+    }
+    Ok(changed)
+}
+
+/// Remove the preset style keys (`StylePreset::STYLE_KEYS`) from every
+/// element of the diagram, leaving other overrides alone. Returns the
+/// changed elements.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn clear_styles(
+    diagram_id: String,
+    actor: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<DiagramElement>, CommandError> {
+    let id = parse_uuid("diagram_id", &diagram_id)?;
+    if state.store.get_diagram(id).await?.is_none() {
+        return Err(CommandError::not_found(format!("diagram {id}")));
+    }
+    let mut changed = Vec::new();
+    for mut element in state.store.diagram_elements(id).await? {
+        let before = element.style_overrides.len();
+        element
+            .style_overrides
+            .retain(|k, _| !StylePreset::STYLE_KEYS.contains(&k.as_str()));
+        if element.style_overrides.len() != before {
+            changed.push(element);
+        }
+    }
 
+    let styles: Vec<_> = changed.iter().map(|e| (e.id, e.style_overrides.clone())).collect();
+    if !styles.is_empty() {
+        let summary = format!("cleared styles of {} element(s)", styles.len());
+        state
+            .store
+            .set_element_styles(id, &styles, &summary, &actor_or_user(actor))
+            .await?;
+    }
+    Ok(changed)
+}
+
+// -- Documents --------------------------------------------------------------
+
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn list_documents(
+    project_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<Document>, String> {
+    let id = parse_uuid("project_id", &project_id).map_err(|e| e.to_string())?;
     state
         .store
-        .set_setting("ai.ollama.model", None, &model)
+        .list_documents(id)
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn upsert_document(
+    doc: Document,
+    actor: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
     state
         .store
-        .set_setting("ai.ollama.base_url", None, &url)
+        .upsert_document(&doc, &actor_or_user(actor))
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn delete_document(
+    id: String,
+    actor: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let uuid = parse_uuid("id", &id).map_err(|e| e.to_string())?;
     state
         .store
-        .set_setting("ai.provider", None, "ollama")
+        .delete_document(uuid, &actor_or_user(actor))
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| e.to_string())
+}
 
-    let new_provider: Arc<dyn crate::ai::provider::AIProvider> =
-        Arc::new(OllamaProvider::new(model, Some(url)));
-    *state.ai_provider.lock().unwrap() = new_provider;
-    Ok(())
+// -- Document sections -------------------------------------------------------
+
+/// A document's sections in position order; with `tree`, nested under
+/// their parent headings.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn list_document_sections(
+    document_id: String,
+    tree: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<Vec<SectionTree>, String> {
+    let id = parse_uuid("document_id", &document_id).map_err(|e| e.to_string())?;
+    let sections = state
+        .store
+        .list_document_sections(id)
+        .await
+        .map_err(|e| e.to_string())?;
+    if tree.unwrap_or(false) {
+        return Ok(crate::core::sections::build_tree(sections));
+    }
+    Ok(sections
+        .into_iter()
+        .map(|section| SectionTree {
+            section,
+            children: Vec::new(),
+        })
+        .collect())
 }
 
+/// Rebuild a document's sections from its text (see `core::sections`),
+/// replacing the existing ones, and return them as a tree. The new
+/// sections get new ids, so edits made to the old ones are lost and
+/// a section id held by the caller (from a source match, say) no longer
+/// resolves.
 #[tauri::command]
-pub async fn set_anthropic_key(key: String, state: State<'_, AppState>) -> Result<(), String> {
-    use crate::ai::anthropic::AnthropicProvider;
-    use crate::ai::provider::NullProvider;
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn parse_document_sections(
+    document_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<SectionTree>, CommandError> {
+    let id = parse_uuid("document_id", &document_id)?;
+    let document = state
+        .store
+        .get_document(id)
+        .await?
+        .ok_or_else(|| CommandError::not_found(format!("document {id}")))?;
+    let sections = crate::core::sections::parse_sections(&document, Utc::now());
+    state.store.replace_document_sections(id, &sections).await?;
+    Ok(crate::core::sections::build_tree(sections))
+}
 
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn list_project_document_sections(
+    project_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<DocumentSection>, String> {
+    let id = parse_uuid("project_id", &project_id).map_err(|e| e.to_string())?;
     state
         .store
-        .set_setting("ai.anthropic.api_key", None, &key)
+        .list_project_document_sections(id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn upsert_document_section(
+    section: DocumentSection,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    check_section_references(&state, &section)
         .await
         .map_err(|e| e.to_string())?;
-    if !key.is_empty() {
-        state
-            .store
-            .set_setting("ai.provider", None, "anthropic")
-            .await
-            .map_err(|e| e.to_string())?;
+    state
+        .store
+        .upsert_document_section(&section)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// The section's document must exist in its project, and its parent, when
+/// set, must be a section of the same document.
+async fn check_section_references(
+    state: &State<'_, AppState>,
+    section: &DocumentSection,
+) -> Result<(), CommandError> {
+    let mut issues = Vec::new();
+    match state.store.get_document(section.document_id).await? {
+        None => issues.push(missing_reference(
+            "document_id",
+            format!("document {} does not exist", section.document_id),
+        )),
+        Some(doc) if doc.project_id != section.project_id => issues.push(missing_reference(
+            "document_id",
+            format!("'{}' belongs to another project", doc.name),
+        )),
+        Some(_) => {}
+    }
+    if let Some(parent_id) = section.parent_section_id {
+        let parent = state.store.get_document_section(parent_id).await?;
+        if !parent.is_some_and(|p| p.document_id == section.document_id) {
+            issues.push(missing_reference(
+                "parent_section_id",
+                format!("section {parent_id} is not in this document"),
+            ));
+        }
     }
+    reject_issues(issues)
+}
 
-    let new_provider: Arc<dyn crate::ai::provider::AIProvider> = if !key.is_empty() {
-        Arc::new(AnthropicProvider::new(key))
-    } else {
-        Arc::new(NullProvider)
+/// Write many sections of one document in a single transaction, for
+/// committing a parsed document. Every section must name the same document
+/// and project; a parent must be in the batch or already in the document;
+/// ids already used by another document's sections are rejected.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn bulk_upsert_document_sections(
+    sections: Vec<DocumentSection>,
+    state: State<'_, AppState>,
+) -> Result<SectionWriteReport, CommandError> {
+    let Some(first) = sections.first() else {
+        return Err(CommandError::invalid("no sections to write"));
     };
-    *state.ai_provider.lock().unwrap() = new_provider;
-    Ok(())
-}
+    let (document_id, project_id) = (first.document_id, first.project_id);
+    let mut issues = Vec::new();
+    let mut issue = |index: usize, field: &str, code: &str, message: String| {
+        issues.push(FieldIssue {
+            field: Some(format!("sections[{index}].{field}")),
+            code: code.to_string(),
+            message,
+        });
+    };
+    match state.store.get_document(document_id).await? {
+        None => issue(
+            0,
+            "document_id",
+            "not_found",
+            format!("document {document_id} does not exist"),
+        ),
+        Some(doc) if doc.project_id != project_id => issue(
+            0,
+            "document_id",
+            "not_found",
+            format!("'{}' belongs to another project", doc.name),
+        ),
+        Some(_) => {}
+    }
 
-// ── Requirement parser ────────────────────────────────────────────────────────
+    let existing: std::collections::HashSet<Uuid> = state
+        .store
+        .list_document_sections(document_id)
+        .await?
+        .iter()
+        .map(|s| s.id)
+        .collect();
+    let ids: Vec<Uuid> = sections.iter().map(|s| s.id).collect();
+    let elsewhere = state.store.section_documents(&ids).await?;
+    let mut batch = std::collections::HashSet::new();
+    for (i, section) in sections.iter().enumerate() {
+        if section.document_id != document_id {
+            issue(i, "document_id", "invalid", "all sections must belong to one document".into());
+        }
+        if section.project_id != project_id {
+            issue(i, "project_id", "invalid", "all sections must belong to one project".into());
+        }
+        if !batch.insert(section.id) {
+            issue(i, "id", "duplicate", format!("section {} appears more than once", section.id));
+        }
+        if elsewhere.get(&section.id).is_some_and(|doc| *doc != document_id) {
+            let message = format!("section {} belongs to another document", section.id);
+            issue(i, "id", "conflict", message);
+        }
+    }
+    for (i, section) in sections.iter().enumerate() {
+        if let Some(parent_id) = section.parent_section_id {
+            if !batch.contains(&parent_id) && !existing.contains(&parent_id) {
+                issue(
+                    i,
+                    "parent_section_id",
+                    "not_found",
+                    format!("section {parent_id} is not in this document"),
+                );
+            }
+        }
+    }
+    reject_issues(issues)?;
 
-/// Send sentences to req_parser.py via the system Python interpreter.
-/// Tries Miniconda first, then falls back to "python" / "python3" on PATH.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct RequirementParseBlock {
-    pub text: String,
-    #[serde(default)]
-    pub section_title: String,
-    #[serde(default)]
-    pub section_ref: String,
-    #[serde(default)]
-    pub section_type: String,
-    #[serde(default)]
-    pub line_index: i32,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct RequirementQualityInput {
-    #[serde(default)]
-    pub id: String,
-    pub sentence: String,
-    #[serde(default)]
-    pub name: String,
-    #[serde(default)]
-    pub confidence: String,
-    #[serde(default)]
-    pub flags: Vec<String>,
-    #[serde(default)]
-    pub classification: String,
-    #[serde(default)]
-    pub score: f64,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct RequirementQualityOutput {
-    #[serde(default)]
-    pub id: String,
-    pub sentence: String,
-    #[serde(default)]
-    pub name: String,
-    #[serde(default)]
-    pub confidence: String,
-    #[serde(default)]
-    pub classification: String,
-    #[serde(default)]
-    pub flags: Vec<String>,
-    #[serde(default)]
-    pub review_priority: String,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct RequirementAllocationInput {
-    #[serde(default)]
-    pub id: String,
-    pub sentence: String,
-    #[serde(default)]
-    pub name: String,
-    #[serde(default)]
-    pub confidence: String,
-    #[serde(default)]
-    pub flags: Vec<String>,
-    #[serde(default)]
-    pub classification: String,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AllocationSubsystemInput {
-    pub name: String,
-    #[serde(default)]
-    pub description: String,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct RequirementAllocationOutput {
-    #[serde(default)]
-    pub id: String,
-    pub sentence: String,
-    pub allocation: String,
-    #[serde(default)]
-    pub confidence: String,
-    #[serde(default)]
-    pub rationale: String,
-    #[serde(default)]
-    pub new_subsystem_name: String,
+    let _write = state
+        .store
+        .begin_heavy_write("saving document sections", Some(USER_HEAVY_WRITE_WAIT))
+        .await?;
+    let started = std::time::Instant::now();
+    let written = state.store.bulk_upsert_document_sections(&sections).await?;
+    Ok(SectionWriteReport {
+        document_id,
+        sections: written,
+        elapsed_ms: started.elapsed().as_secs_f64() * 1000.0,
+    })
 }
 
+/// Renumber a document's sections in the order given, for drag-reorder in
+/// the outline. `ordered_ids` must list every section of the document
+/// exactly once.
 #[tauri::command]
-pub async fn parse_requirements(
-    sentences: Option<Vec<String>>,
-    blocks: Option<Vec<RequirementParseBlock>>,
-    doc_type: Option<String>,
-    app: tauri::AppHandle,
-) -> Result<String, String> {
-    let payload = if let Some(blocks) = blocks {
-        serde_json::json!({
-            "blocks": blocks,
-            "sentences": sentences.unwrap_or_default(),
-            "doc_type": doc_type.unwrap_or_default(),
-        })
-    } else {
-        serde_json::json!({
-            "sentences": sentences.unwrap_or_default(),
-            "doc_type": doc_type.unwrap_or_default(),
-        })
-    };
-    let input = serde_json::to_string(&payload).map_err(|e| e.to_string())?;
-
-    // Locate req_parser.py — try several locations in order:
-    //   1. Bundled in app resource dir (production)
-    //   2. CARGO_MANIFEST_DIR-relative (dev, most reliable)
-    //   3. cwd-relative fallback
-    let script_path = {
-        let resource_dir = app
-            .path()
-            .resource_dir()
-            .unwrap_or_else(|_| std::path::PathBuf::from("."));
-        let bundled = resource_dir.join("sidecar").join("req_parser.py");
-
-        // In dev, CARGO_MANIFEST_DIR points to src-tauri/, so go one level up
-        let manifest_dir = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-        let cargo_relative = manifest_dir
-            .parent()
-            .map(|p| p.join("sidecar").join("req_parser.py"))
-            .unwrap_or_else(|| manifest_dir.join("sidecar").join("req_parser.py"));
-
-        let cwd_relative = std::path::PathBuf::from("sidecar/req_parser.py");
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn reorder_document_sections(
+    document_id: String,
+    ordered_ids: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<SectionWriteReport, CommandError> {
+    let document_id = parse_uuid("document_id", &document_id)?;
+    if state.store.get_document(document_id).await?.is_none() {
+        return Err(CommandError::not_found(format!("document {document_id}")));
+    }
+    let ordered = ordered_ids
+        .iter()
+        .map(|raw| parse_uuid("ordered_ids", raw))
+        .collect::<Result<Vec<Uuid>, _>>()?;
 
-        if bundled.exists() {
-            bundled
-        } else if cargo_relative.exists() {
-            cargo_relative
-        } else if cwd_relative.exists() {
-            cwd_relative
-        } else {
-            return Err(format!(
-                "req_parser.py not found. Looked in: {}, {}, {}",
-                bundled.display(),
-                cargo_relative.display(),
-                cwd_relative.display()
+    let existing: std::collections::HashSet<Uuid> = state
+        .store
+        .list_document_sections(document_id)
+        .await?
+        .iter()
+        .map(|s| s.id)
+        .collect();
+    let mut seen = std::collections::HashSet::new();
+    let mut issues = Vec::new();
+    for id in &ordered {
+        if !seen.insert(*id) {
+            issues.push(FieldIssue {
+                field: Some("ordered_ids".to_string()),
+                code: "duplicate".to_string(),
+                message: format!("section {id} appears more than once"),
+            });
+        } else if !existing.contains(id) {
+            issues.push(missing_reference(
+                "ordered_ids",
+                format!("section {id} is not in this document"),
             ));
         }
-    };
-
-    // Try Python interpreters in order of preference
-    let candidates = [r"C:\Users\aliso\miniconda3\python.exe", "python", "python3"];
-
-    let mut last_err = String::from("no Python interpreter found");
-    for python in &candidates {
-        match run_python_script(python, &script_path, &input).await {
-            Ok(out) if !out.trim().is_empty() => return Ok(out.trim().to_string()),
-            Ok(_) => {
-                last_err = format!("{python}: produced empty output");
-            }
-            Err(e) => {
-                last_err = format!("{python}: {e}");
-            }
-        }
-    }
-
-    Err(format!("req_parser failed: {last_err}"))
-}
-
-async fn run_python_script(
-    python: &str,
-    script: &std::path::Path,
-    input: &str,
-) -> Result<String, String> {
-    use tokio::io::AsyncWriteExt;
-
-    let mut child = tokio::process::Command::new(python)
-        .arg(script)
-        .env("PYTHONIOENCODING", "utf-8")
-        .env("PYTHONUTF8", "1")
-        .stdin(std::process::Stdio::piped())
-        .stdout(std::process::Stdio::piped())
-        .stderr(std::process::Stdio::piped())
-        .spawn()
-        .map_err(|e| format!("spawn failed: {e}"))?;
-
-    if let Some(mut stdin) = child.stdin.take() {
-        stdin
-            .write_all(format!("{input}\n").as_bytes())
-            .await
-            .map_err(|e| e.to_string())?;
     }
-
-    let out = child.wait_with_output().await.map_err(|e| e.to_string())?;
-
-    let stdout = String::from_utf8_lossy(&out.stdout).to_string();
-    if stdout.trim().is_empty() {
-        let stderr = String::from_utf8_lossy(&out.stderr).to_string();
-        return Err(format!("empty output. stderr: {}", stderr.trim()));
+    let missing = existing.iter().filter(|id| !seen.contains(id)).count();
+    if missing > 0 {
+        issues.push(FieldIssue {
+            field: Some("ordered_ids".to_string()),
+            code: "incomplete".to_string(),
+            message: format!("{missing} section(s) of this document are not listed"),
+        });
     }
-
-    Ok(stdout)
+    reject_issues(issues)?;
+
+    let started = std::time::Instant::now();
+    let reordered = state.store.reorder_document_sections(document_id, &ordered).await?;
+    Ok(SectionWriteReport {
+        document_id,
+        sections: reordered,
+        elapsed_ms: started.elapsed().as_secs_f64() * 1000.0,
+    })
 }
 
-// ── Simulation commands ───────────────────────────────────────────────────────
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn delete_document_section(id: String, state: State<'_, AppState>) -> Result<(), String> {
+    let uuid = parse_uuid("id", &id).map_err(|e| e.to_string())?;
+    state
+        .store
+        .delete_document_section(uuid)
+        .await
+        .map_err(|e| e.to_string())
+}
 
 #[tauri::command]
-pub async fn save_sim_params(
-    node_id: String,
-    params: Option<SimParams>,
-    script: Option<String>,
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn delete_document_sections(
+    document_id: String,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
-    let uuid: Uuid = node_id.parse().map_err(|e: uuid::Error| e.to_string())?;
-    let node = state
+    let uuid = parse_uuid("document_id", &document_id).map_err(|e| e.to_string())?;
+    state
         .store
-        .get_node(uuid)
+        .delete_document_sections(uuid)
         .await
-        .map_err(|e| e.to_string())?
-        .ok_or_else(|| "node not found".to_string())?;
-    let updated_data = match node.data {
-        NodeData::Block(mut b) => {
-            b.sim_params = params;
-            b.sim_script = script;
-            NodeData::Block(b)
-        }
-        _ => return Err("node is not a block".to_string()),
-    };
-    let updated = Node {
-        data: updated_data,
-        modified_at: Utc::now(),
-        ..node
-    };
-    state.store.upsert_node(&updated).await.map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())
 }
 
+/// Resolve a requirement back to the document section(s) it came from: the
+/// free-text `source` is parsed into a document name and section reference,
+/// and the name is fuzzily matched against the project's documents. That
+/// text is the only section provenance there is; edges connect nodes, never
+/// sections.
 #[tauri::command]
-pub async fn get_sim_params(
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn resolve_requirement_source(
     node_id: String,
     state: State<'_, AppState>,
-) -> Result<serde_json::Value, String> {
-    let uuid: Uuid = node_id.parse().map_err(|e: uuid::Error| e.to_string())?;
+) -> Result<Vec<crate::core::provenance::SourceMatch>, String> {
+    let uuid = parse_uuid("node_id", &node_id).map_err(|e| e.to_string())?;
     let node = state
         .store
         .get_node(uuid)
         .await
         .map_err(|e| e.to_string())?
         .ok_or_else(|| "node not found".to_string())?;
-    match node.data {
-        NodeData::Block(b) => Ok(serde_json::json!({
-            "sim_params": b.sim_params,
-            "sim_script": b.sim_script,
-        })),
-        _ => Ok(serde_json::json!({ "sim_params": null, "sim_script": null })),
+    if node.kind != NodeKind::Requirement {
+        return Err("node is not a requirement".to_string());
     }
-}
 
-#[tauri::command]
-pub async fn save_scenario(
-    scenario: SimulationScenario,
-    state: State<'_, AppState>,
-) -> Result<SimulationScenario, String> {
-    state
+    let documents = state
         .store
-        .upsert_simulation_scenario(&scenario)
+        .list_documents(node.project_id)
         .await
         .map_err(|e| e.to_string())?;
-    Ok(scenario)
-}
-
-#[tauri::command]
-pub async fn list_scenarios(
-    project_id: String,
-    state: State<'_, AppState>,
-) -> Result<Vec<SimulationScenario>, String> {
-    let uuid: Uuid = project_id.parse().map_err(|e: uuid::Error| e.to_string())?;
-    state
+    let sections = state
         .store
-        .list_simulation_scenarios(uuid)
+        .list_project_document_sections(node.project_id)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+
+    Ok(requirement_sources(&node, &documents, &sections))
+}
+
+/// Source sections of a requirement: matches for its parsed `source` text.
+fn requirement_sources(
+    node: &Node,
+    documents: &[Document],
+    sections: &[DocumentSection],
+) -> Vec<crate::core::provenance::SourceMatch> {
+    use crate::core::provenance::{match_source_text, parse_source_reference};
+
+    let source = match &node.data {
+        NodeData::Requirement(r) => r.source.clone().unwrap_or_default(),
+        _ => String::new(),
+    };
+    match_source_text(&parse_source_reference(&source), documents, sections)
 }
 
+/// Reverse of `resolve_requirement_source`: requirements whose parsed
+/// `source` text points at the given section.
 #[tauri::command]
-pub async fn run_simulation(
-    scenario_id: String,
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn requirements_for_section(
+    section_id: String,
     state: State<'_, AppState>,
-    app: tauri::AppHandle,
-) -> Result<String, String> {
-    let scenario_uuid: Uuid = scenario_id.parse().map_err(|e: uuid::Error| e.to_string())?;
+) -> Result<Vec<crate::core::provenance::SectionRequirementLink>, String> {
+    use crate::core::provenance::{
+        document_name_confidence, parse_source_reference, section_ref_confidence,
+        SectionRequirementLink,
+    };
 
-    let scenario = state
+    let uuid = parse_uuid("section_id", &section_id).map_err(|e| e.to_string())?;
+    let section = state
         .store
-        .get_simulation_scenario(scenario_uuid)
+        .get_document_section(uuid)
         .await
         .map_err(|e| e.to_string())?
-        .ok_or_else(|| "scenario not found".to_string())?;
+        .ok_or_else(|| "section not found".to_string())?;
+    let document = state
+        .store
+        .list_documents(section.project_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|d| d.id == section.document_id)
+        .ok_or_else(|| "document not found".to_string())?;
 
-    let nodes = state
+    let requirements = state
         .store
-        .list_nodes(scenario.project_id)
+        .list_nodes_by_kind(section.project_id, &NodeKind::Requirement)
         .await
         .map_err(|e| e.to_string())?;
 
-    let mut all_edges = Vec::new();
-    for node in &nodes {
-        let mut e = state
-            .store
-            .edges_for_node(node.id)
-            .await
-            .map_err(|e| e.to_string())?;
-        all_edges.append(&mut e);
-    }
-    all_edges.sort_by_key(|e| e.id);
-    all_edges.dedup_by_key(|e| e.id);
-
-    // Build block_behaviors: block_id -> { sim_params, sim_script }
-    let block_behaviors: serde_json::Map<String, serde_json::Value> = nodes
+    let mut links: Vec<SectionRequirementLink> = requirements
         .iter()
-        .filter(|n| n.kind == NodeKind::Block)
-        .filter_map(|n| {
-            if let NodeData::Block(ref b) = n.data {
-                if b.sim_params.is_some() || b.sim_script.is_some() {
-                    Some((
-                        n.id.to_string(),
-                        serde_json::json!({
-                            "sim_params": b.sim_params,
-                            "sim_script": b.sim_script,
-                        }),
-                    ))
-                } else {
-                    None
-                }
-            } else {
-                None
+        .filter_map(|node| {
+            let NodeData::Requirement(r) = &node.data else {
+                return None;
+            };
+            let reference = parse_source_reference(r.source.as_deref().unwrap_or(""));
+            let doc_conf = reference
+                .document
+                .as_deref()
+                .map(|d| document_name_confidence(d, &document.name))
+                .unwrap_or(0.0);
+            let sec_conf = reference
+                .section_ref
+                .as_deref()
+                .map(|sref| section_ref_confidence(sref, &section))
+                .unwrap_or(0.0);
+            let confidence = doc_conf * sec_conf;
+            if confidence <= 0.0 {
+                return None;
             }
+            Some(SectionRequirementLink {
+                node_id: node.id,
+                req_id: r.req_id.clone(),
+                name: node.name.clone(),
+                confidence,
+            })
         })
         .collect();
+    links.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+    Ok(links)
+}
 
-    let project = state
+// -- Block value properties -------------------------------------------------
+
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn list_block_properties(
+    block_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<BlockProperty>, String> {
+    let id = parse_uuid("block_id", &block_id).map_err(|e| e.to_string())?;
+    state
         .store
-        .get_project(scenario.project_id)
+        .list_block_properties(id)
         .await
-        .map_err(|e| e.to_string())?
-        .ok_or_else(|| "project not found".to_string())?;
+        .map_err(|e| e.to_string())
+}
 
-    let project_json_str = crate::core::export::to_native_json(&project, &nodes, &all_edges)
-        .map_err(|e| e.to_string())?;
-    let project_json: serde_json::Value =
-        serde_json::from_str(&project_json_str).unwrap_or_default();
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn list_project_block_properties(
+    project_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<BlockProperty>, String> {
+    let id = parse_uuid("project_id", &project_id).map_err(|e| e.to_string())?;
+    state
+        .store
+        .list_project_block_properties(id)
+        .await
+        .map_err(|e| e.to_string())
+}
 
-    let input_payload = serde_json::json!({
-        "project_json": project_json,
-        "scenario": {
-            "id": scenario.id,
-            "name": scenario.name,
-            "duration_ms": scenario.duration_ms,
-            "events": scenario.events,
-        },
-        "block_behaviors": block_behaviors,
-    });
-    let input = serde_json::to_string(&input_payload).map_err(|e| e.to_string())?;
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn upsert_block_property(
+    property: BlockProperty,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    state
+        .store
+        .upsert_block_property(&property)
+        .await
+        .map_err(|e| e.to_string())
+}
 
-    // Create a pending result row
-    let result_id = Uuid::new_v4();
-    let pending_result = SimulationResult {
-        id: result_id,
-        scenario_id: scenario_uuid,
-        ran_at: Utc::now(),
-        status: "running".to_string(),
-        metrics: serde_json::Value::Object(Default::default()),
-        timeline: serde_json::Value::Array(vec![]),
-        errors: serde_json::Value::Array(vec![]),
-    };
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn delete_block_property(
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let uuid = parse_uuid("id", &id).map_err(|e| e.to_string())?;
     state
         .store
-        .insert_simulation_result(&pending_result)
+        .delete_block_property(uuid)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Sum or max a named value property over the composition tree under
+/// `root_block_id`. `aggregate` is "sum" (default) or "max".
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn rollup_property(
+    project_id: String,
+    root_block_id: String,
+    property_name: String,
+    aggregate: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<crate::core::rollup::RollupResult, String> {
+    compute_rollup(&state, &project_id, &root_block_id, &property_name, aggregate.as_deref()).await
+}
+
+/// `rollup_property` flattened to CSV for saving through the export dialog.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn export_property_rollup_csv(
+    project_id: String,
+    root_block_id: String,
+    property_name: String,
+    aggregate: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let result =
+        compute_rollup(&state, &project_id, &root_block_id, &property_name, aggregate.as_deref()).await?;
+    Ok(crate::core::rollup::to_csv(&result))
+}
+
+async fn compute_rollup(
+    state: &State<'_, AppState>,
+    project_id: &str,
+    root_block_id: &str,
+    property_name: &str,
+    aggregate: Option<&str>,
+) -> Result<crate::core::rollup::RollupResult, String> {
+    use crate::core::rollup::{self, RollupAggregate};
+
+    let pid = parse_uuid("project_id", &project_id).map_err(|e| e.to_string())?;
+    let root = parse_uuid("root_block_id", &root_block_id).map_err(|e| e.to_string())?;
+    let aggregate = match aggregate {
+        None => RollupAggregate::Sum,
+        Some(a) => RollupAggregate::parse(a)
+            .ok_or_else(|| format!("unknown aggregate '{a}' (expected sum or max)"))?,
+    };
+    if property_name.trim().is_empty() {
+        return Err("property_name is required".to_string());
+    }
+
+    let nodes = state.store.list_nodes(pid).await.map_err(|e| e.to_string())?;
+    let edges = state
+        .store
+        .list_edges_by_kind(pid, &[EdgeKind::Composes])
+        .await
+        .map_err(|e| e.to_string())?;
+    let properties = state
+        .store
+        .list_project_block_properties(pid)
         .await
         .map_err(|e| e.to_string())?;
+    rollup::rollup(root, &nodes, &edges, &properties, property_name, aggregate)
+}
 
-    // Resolve simulation_engine.py (same 3-path strategy as req_parser.py)
-    let script_path = {
-        let resource_dir = app
-            .path()
-            .resource_dir()
-            .unwrap_or_else(|_| std::path::PathBuf::from("."));
-        let bundled = resource_dir.join("sidecar").join("simulation_engine.py");
-        let manifest_dir = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-        let cargo_relative = manifest_dir
-            .parent()
-            .map(|p| p.join("sidecar").join("simulation_engine.py"))
-            .unwrap_or_else(|| manifest_dir.join("sidecar").join("simulation_engine.py"));
-        let cwd_relative = std::path::PathBuf::from("sidecar/simulation_engine.py");
+// -- Glossary ---------------------------------------------------------------
 
-        if bundled.exists() {
-            bundled
-        } else if cargo_relative.exists() {
-            cargo_relative
-        } else if cwd_relative.exists() {
-            cwd_relative
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn list_glossary_terms(
+    project_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<GlossaryTerm>, CommandError> {
+    let pid = parse_uuid("project_id", &project_id)?;
+    Ok(state.store.list_glossary_terms(pid).await?)
+}
+
+/// Create or update a term. Variants are trimmed and deduplicated; empty
+/// ones and ones equal to the approved form are dropped.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn upsert_glossary_term(
+    mut term: GlossaryTerm,
+    state: State<'_, AppState>,
+) -> Result<GlossaryTerm, CommandError> {
+    let issue = |field: &str, message: &str| CommandError::Validation {
+        issues: vec![FieldIssue {
+            field: Some(field.to_string()),
+            code: "required".to_string(),
+            message: message.to_string(),
+        }],
+    };
+    term.approved = term.approved.trim().to_string();
+    if term.approved.is_empty() {
+        return Err(issue("approved", "the approved form must not be empty"));
+    }
+    term.term = term.term.trim().to_string();
+    if term.term.is_empty() {
+        term.term = term.approved.clone();
+    }
+    let same = |a: &str, b: &str| {
+        if term.case_sensitive {
+            a == b
         } else {
-            state
-                .store
-                .update_simulation_result_status(
-                    result_id,
-                    "error",
-                    serde_json::Value::Object(Default::default()),
-                    serde_json::Value::Array(vec![]),
-                    serde_json::json!(["simulation_engine.py not found"]),
-                )
-                .await
-                .ok();
-            return Ok(result_id.to_string());
+            a.to_lowercase() == b.to_lowercase()
         }
     };
+    let mut variants: Vec<String> = Vec::new();
+    for variant in term.variants.iter().map(|v| v.trim()).filter(|v| !v.is_empty()) {
+        if !same(variant, &term.approved) && !variants.iter().any(|v| same(v, variant)) {
+            variants.push(variant.to_string());
+        }
+    }
+    term.variants = variants;
 
-    let candidates = [r"C:\Users\aliso\miniconda3\python.exe", "python", "python3"];
-    let mut last_err = String::from("no Python interpreter found");
-    let mut engine_output: Option<String> = None;
+    if state.store.get_project(term.project_id).await?.is_none() {
+        return Err(CommandError::not_found(format!("project {}", term.project_id)));
+    }
+    term.modified_at = Utc::now();
+    state.store.upsert_glossary_term(&term).await?;
+    Ok(term)
+}
 
-    for python in &candidates {
-        match run_python_script(python, &script_path, &input).await {
-            Ok(out) if !out.trim().is_empty() => {
-                engine_output = Some(out.trim().to_string());
-                break;
-            }
-            Ok(_) => {
-                last_err = format!("{python}: produced empty output");
-            }
-            Err(e) => {
-                last_err = format!("{python}: {e}");
-            }
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn delete_glossary_term(
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<(), CommandError> {
+    let id = parse_uuid("id", &id)?;
+    if !state.store.delete_glossary_term(id).await? {
+        return Err(CommandError::not_found(format!("glossary term {id}")));
+    }
+    Ok(())
+}
+
+/// Forbidden variants and miscapitalized approved terms in the project's
+/// requirement text; see `core::terminology`.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn terminology_check(
+    project_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::core::terminology::TerminologyIssue>, CommandError> {
+    let pid = parse_uuid("project_id", &project_id)?;
+    let terms = state.store.list_glossary_terms(pid).await?;
+    if terms.is_empty() {
+        return Ok(Vec::new());
+    }
+    let requirements = state.store.list_nodes_by_kind(pid, &NodeKind::Requirement).await?;
+    Ok(crate::core::terminology::check(&requirements, &terms))
+}
+
+/// Apply terminology fixes through `upsert_node`, so each requirement's
+/// history records the change. A description that mirrored the old text
+/// is updated with it. All fixes are checked before anything is written;
+/// if any no longer matches the text, nothing is. Returns the updated
+/// requirements.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn apply_terminology_fixes(
+    fixes: Vec<crate::core::terminology::TerminologyFix>,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Vec<Node>, CommandError> {
+    use crate::core::terminology;
+
+    let mut by_node: Vec<(Uuid, Vec<&terminology::TerminologyFix>)> = Vec::new();
+    for fix in &fixes {
+        match by_node.iter_mut().find(|(id, _)| *id == fix.node_id) {
+            Some((_, list)) => list.push(fix),
+            None => by_node.push((fix.node_id, vec![fix])),
         }
     }
 
-    match engine_output {
-        Some(out) => match serde_json::from_str::<serde_json::Value>(&out) {
-            Ok(parsed) => {
-                let status =
-                    if parsed.get("status").and_then(|v| v.as_str()) == Some("error") {
-                        "error"
-                    } else {
-                        "complete"
-                    };
-                state
-                    .store
-                    .update_simulation_result_status(
-                        result_id,
-                        status,
-                        parsed.get("metrics").cloned().unwrap_or_default(),
-                        parsed.get("timeline").cloned().unwrap_or_default(),
-                        parsed.get("errors").cloned().unwrap_or_default(),
-                    )
-                    .await
-                    .map_err(|e| e.to_string())?;
-            }
-            Err(e) => {
-                state
-                    .store
-                    .update_simulation_result_status(
-                        result_id,
-                        "error",
-                        serde_json::Value::Object(Default::default()),
-                        serde_json::Value::Array(vec![]),
-                        serde_json::json!([format!("JSON parse error: {e}"), out]),
-                    )
-                    .await
-                    .ok();
+    let now = Utc::now();
+    let mut updated = Vec::with_capacity(by_node.len());
+    for (node_id, node_fixes) in by_node {
+        let mut node = state
+            .store
+            .get_node(node_id)
+            .await?
+            .ok_or_else(|| CommandError::not_found(format!("node {node_id}")))?;
+        let Some(text) = terminology::requirement_text(&node).map(str::to_string) else {
+            let message = format!("'{}' is a {}, not a requirement", node.name, node.kind);
+            return Err(CommandError::invalid(message));
+        };
+        let fixed = terminology::apply(&text, &node_fixes)
+            .map_err(|e| CommandError::invalid(format!("{}: {e}", node.name)))?;
+        if node.description == text {
+            node.description = fixed.clone();
+        }
+        if let NodeData::Requirement(req) = &mut node.data {
+            if req.text.as_deref().is_some_and(|t| !t.trim().is_empty()) {
+                req.text = Some(fixed);
             }
-        },
-        None => {
-            state
-                .store
-                .update_simulation_result_status(
-                    result_id,
-                    "error",
-                    serde_json::Value::Object(Default::default()),
-                    serde_json::Value::Array(vec![]),
-                    serde_json::json!([last_err]),
-                )
-                .await
-                .ok();
         }
+        node.meta.insert("change_source".to_string(), serde_json::json!("terminology"));
+        node.meta.insert("actor".to_string(), serde_json::json!("system"));
+        node.modified_at = now;
+        updated.push(node);
     }
 
-    Ok(result_id.to_string())
+    for node in &updated {
+        state.store.upsert_node(node).await?;
+    }
+    let project_id = updated.first().map(|n| n.project_id);
+    if let Some(project_id) = project_id {
+        let touched = updated.iter().map(|n| n.id).collect();
+        let _ = revalidate_touched(&app, &state, project_id, touched).await;
+    }
+    Ok(updated)
 }
 
+// -- Block interfaces -------------------------------------------------------
+
+/// A block's ports with direction, type and multiplicity, and for each port
+/// the «connects» edges touching it with the far-end port and block names.
 #[tauri::command]
-pub async fn get_simulation_result(
-    result_id: String,
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn block_interface_summary(
+    block_id: String,
     state: State<'_, AppState>,
-) -> Result<SimulationResult, String> {
-    let uuid: Uuid = result_id.parse().map_err(|e: uuid::Error| e.to_string())?;
-    state
+) -> Result<crate::core::interfaces::BlockInterfaceSummary, String> {
+    let id = parse_uuid("block_id", &block_id).map_err(|e| e.to_string())?;
+    let block = state
         .store
-        .get_simulation_result(uuid)
+        .get_node(id)
         .await
         .map_err(|e| e.to_string())?
-        .ok_or_else(|| "result not found".to_string())
+        .ok_or_else(|| format!("block {id} not found"))?;
+
+    let nodes = state
+        .store
+        .list_nodes(block.project_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    let edges = state
+        .store
+        .list_edges_by_kind(
+            block.project_id,
+            &[EdgeKind::Composes, EdgeKind::Connects, EdgeKind::Specializes],
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    crate::core::interfaces::summarize(id, &nodes, &edges)
 }
 
-// -- Local LLM (llama.cpp) ---------------------------------------------------
+/// A block's ports and block properties including those inherited along
+/// «specializes» edges, each marked with the ancestor it comes from, and
+/// any redefinitions with conflicting port directions.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn effective_block_definition(
+    block_id: String,
+    state: State<'_, AppState>,
+) -> Result<crate::core::inheritance::EffectiveBlockDefinition, CommandError> {
+    let id = parse_uuid("block_id", &block_id)?;
+    let block = state
+        .store
+        .get_node(id)
+        .await?
+        .ok_or_else(|| CommandError::not_found(format!("block {id}")))?;
+    let nodes = state.store.list_nodes(block.project_id).await?;
+    let edges = state
+        .store
+        .list_edges_by_kind(block.project_id, &[EdgeKind::Composes, EdgeKind::Specializes])
+        .await?;
+    let properties = state.store.list_project_block_properties(block.project_id).await?;
+    crate::core::inheritance::effective_definition(id, &nodes, &edges, &properties)
+        .map_err(CommandError::invalid)
+}
+
+// -- Subsystem knowledge ----------------------------------------------------
+
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn list_subsystem_knowledge(
+    subsystem_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<SubsystemKnowledgePage>, String> {
+    let id = parse_uuid("subsystem_id", &subsystem_id).map_err(|e| e.to_string())?;
+    state
+        .store
+        .list_subsystem_knowledge(id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn upsert_subsystem_knowledge(
+    page: SubsystemKnowledgePage,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    state
+        .store
+        .upsert_subsystem_knowledge(&page)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn delete_subsystem_knowledge(
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let uuid = parse_uuid("id", &id).map_err(|e| e.to_string())?;
+    state
+        .store
+        .delete_subsystem_knowledge(uuid)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// The page's `[[links]]` with their positions and targets; see
+/// `core::wikilinks`. Resolved targets are recorded for `pages_mentioning`
+/// and the page's `stale_links` flag is cleared.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn resolve_knowledge_links(
+    page_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::core::wikilinks::ResolvedLink>, CommandError> {
+    use crate::core::wikilinks;
+
+    let id = parse_uuid("page_id", &page_id)?;
+    let page = state
+        .store
+        .get_subsystem_knowledge(id)
+        .await?
+        .ok_or_else(|| CommandError::not_found(format!("knowledge page {id}")))?;
+    let subsystem = state
+        .store
+        .get_node(page.subsystem_id)
+        .await?
+        .ok_or_else(|| CommandError::not_found(format!("subsystem {}", page.subsystem_id)))?;
+    let mut nodes = state.store.list_nodes(subsystem.project_id).await?;
+    nodes.retain(|n| !n.archived);
+
+    let resolved = wikilinks::resolve_links(wikilinks::parse_links(&page.body), &nodes);
+    let records: Vec<KnowledgeLink> = resolved
+        .iter()
+        .filter_map(|r| {
+            let node = nodes.iter().find(|n| Some(n.id) == r.node_id)?;
+            Some(KnowledgeLink {
+                node_id: node.id,
+                link_text: r.link.text.clone(),
+                node_name: node.name.clone(),
+                node_req_id: crate::core::references::own_req_id(node)
+                    .unwrap_or_default()
+                    .to_string(),
+            })
+        })
+        .collect();
+    state.store.replace_knowledge_links(id, &records).await?;
+    Ok(resolved)
+}
+
+/// Knowledge pages whose resolved links point at the node.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn pages_mentioning(
+    node_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<SubsystemKnowledgePage>, CommandError> {
+    let id = parse_uuid("node_id", &node_id)?;
+    Ok(state.store.pages_mentioning(id).await?)
+}
+
+// -- Subsystem artifacts ----------------------------------------------------
+
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn list_subsystem_artifacts(
+    subsystem_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<SubsystemArtifact>, String> {
+    let id = parse_uuid("subsystem_id", &subsystem_id).map_err(|e| e.to_string())?;
+    state
+        .store
+        .list_subsystem_artifacts(id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn list_project_artifacts(
+    project_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<SubsystemArtifact>, String> {
+    let id = parse_uuid("project_id", &project_id).map_err(|e| e.to_string())?;
+    state
+        .store
+        .list_project_artifacts(id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn upsert_subsystem_artifact(
+    artifact: SubsystemArtifact,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    state
+        .store
+        .upsert_subsystem_artifact(&artifact)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn delete_subsystem_artifact(
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let uuid = parse_uuid("id", &id).map_err(|e| e.to_string())?;
+    state
+        .store
+        .delete_subsystem_artifact(uuid)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// -- Subsystem activity -----------------------------------------------------
+
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn list_subsystem_activity(
+    subsystem_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<SubsystemActivity>, String> {
+    let id = parse_uuid("subsystem_id", &subsystem_id).map_err(|e| e.to_string())?;
+    state
+        .store
+        .list_subsystem_activity(id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn add_subsystem_activity(
+    entry: SubsystemActivity,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    state
+        .store
+        .add_subsystem_activity(&entry)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// -- Settings ---------------------------------------------------------------
+
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn get_setting(
+    key: String,
+    project_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Option<String>, String> {
+    let pid = match project_id {
+        Some(id) => Some(parse_uuid("project_id", &id).map_err(|e| e.to_string())?),
+        None => None,
+    };
+    state
+        .store
+        .get_setting(&key, pid)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn set_setting(
+    key: String,
+    value: String,
+    project_id: Option<String>,
+    state: State<'_, AppState>,
+    diagnostics: State<'_, Arc<Diagnostics>>,
+) -> Result<(), String> {
+    let pid = match project_id {
+        Some(id) => Some(parse_uuid("project_id", &id).map_err(|e| e.to_string())?),
+        None => None,
+    };
+    state
+        .store
+        .set_setting(&key, pid, &value)
+        .await
+        .map_err(|e| e.to_string())?;
+    if pid.is_none() && key == crate::diagnostics::ENABLED_SETTING {
+        diagnostics.set_file_logging(crate::diagnostics::enabled_from_setting(Some(&value)));
+    }
+    Ok(())
+}
+
+/// Keys whose values never leave the backend through `list_settings`.
+/// A leading `*` matches any prefix.
+const SECRET_SETTING_PATTERNS: [&str; 5] = ["*.api_key", "*.token", "*.secret", "*.password", "*_api_key"];
+
+fn is_secret_setting(key: &str) -> bool {
+    SECRET_SETTING_PATTERNS.iter().any(|pattern| match pattern.strip_prefix('*') {
+        Some(suffix) => key.ends_with(suffix),
+        None => key == *pattern,
+    })
+}
+
+/// Settings in one scope (global when `project_id` is omitted), optionally
+/// filtered by key prefix. Secret values are masked.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn list_settings(
+    project_id: Option<String>,
+    prefix: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<SettingEntry>, String> {
+    let pid = match project_id {
+        Some(id) => Some(parse_uuid("project_id", &id).map_err(|e| e.to_string())?),
+        None => None,
+    };
+    let mut entries = state
+        .store
+        .list_settings(pid, prefix.as_deref())
+        .await
+        .map_err(|e| e.to_string())?;
+    for entry in entries.iter_mut().filter(|e| is_secret_setting(&e.key)) {
+        if !entry.value.is_empty() {
+            entry.value = "********".to_string();
+            entry.masked = true;
+        }
+    }
+    Ok(entries)
+}
+
+/// Returns whether the setting existed.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn delete_setting(
+    key: String,
+    project_id: Option<String>,
+    state: State<'_, AppState>,
+    diagnostics: State<'_, Arc<Diagnostics>>,
+) -> Result<bool, String> {
+    let pid = match project_id {
+        Some(id) => Some(parse_uuid("project_id", &id).map_err(|e| e.to_string())?),
+        None => None,
+    };
+    let deleted = state
+        .store
+        .delete_setting(&key, pid)
+        .await
+        .map_err(|e| e.to_string())?;
+    if pid.is_none() && key == crate::diagnostics::ENABLED_SETTING {
+        diagnostics.set_file_logging(false);
+    }
+    Ok(deleted)
+}
+
+/// Write several settings atomically.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn set_settings(
+    entries: Vec<SettingEntry>,
+    state: State<'_, AppState>,
+    diagnostics: State<'_, Arc<Diagnostics>>,
+) -> Result<(), String> {
+    if entries.iter().any(|e| e.key.trim().is_empty()) {
+        return Err("setting key is empty".to_string());
+    }
+    // Writing a mask back would overwrite the real secret.
+    if let Some(masked) = entries.iter().find(|e| e.masked) {
+        return Err(format!("'{}' holds a masked value; send the real value instead", masked.key));
+    }
+    state
+        .store
+        .set_settings(&entries)
+        .await
+        .map_err(|e| e.to_string())?;
+    if let Some(entry) = entries
+        .iter()
+        .rev()
+        .find(|e| e.project_id.is_none() && e.key == crate::diagnostics::ENABLED_SETTING)
+    {
+        diagnostics.set_file_logging(crate::diagnostics::enabled_from_setting(Some(&entry.value)));
+    }
+    Ok(())
+}
+
+// ── Validation ────────────────────────────────────────────────────────────────
+
+/// Full validation. Also reconciles the incremental cache and emits the
+/// difference on `events::VALIDATION_UPDATED`.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn validate_model(
+    project_id: String,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Vec<validation::ValidationIssue>, String> {
+    use tauri::Emitter;
+
+    let id = parse_uuid("project_id", &project_id).map_err(|e| e.to_string())?;
+    let issues = full_validation(&state, id).await?;
+    let delta = state.validation_cache.lock().unwrap().reconcile(id, &issues);
+    if !delta.is_empty() {
+        let _ = app.emit(crate::events::VALIDATION_UPDATED, &delta);
+    }
+    Ok(issues)
+}
+
+async fn full_validation(
+    state: &State<'_, AppState>,
+    project_id: Uuid,
+) -> Result<Vec<validation::ValidationIssue>, String> {
+    let nodes = state
+        .store
+        .list_nodes(project_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    let edges = state.store.list_edges(project_id).await.map_err(|e| e.to_string())?;
+    let limits = validation_limits(state, project_id).await;
+    let mut issues = validation::validate_with_limits(&nodes, &edges, &limits);
+    let diagrams = state
+        .store
+        .list_diagrams(project_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    let elements = state
+        .store
+        .list_project_diagram_elements(project_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    issues.extend(validation::port_direction_unspecified(
+        &nodes, &edges, &diagrams, &elements,
+    ));
+    issues.extend(validation::redefinition_conflicts(&nodes, &edges));
+    let broken_references =
+        project_or_global_setting(state, crate::core::references::VALIDATION_SETTING, project_id)
+            .await
+            .is_some_and(|v| v.trim().trim_matches('"') == "true");
+    if broken_references {
+        let patterns = req_id_patterns(state, project_id).await;
+        issues.extend(validation::broken_references(&nodes, &patterns));
+    }
+    let stale = state
+        .store
+        .count_stale_suspect_links(project_id, limits.suspect_stale_days)
+        .await
+        .map_err(|e| e.to_string())?;
+    issues.extend(validation::stale_suspect_links_issue(stale, &limits));
+    Ok(issues)
+}
+
+/// Limits from settings (`limits.req_text_chars`,
+/// `validation.suspect_stale_days`), project then global.
+async fn validation_limits(state: &State<'_, AppState>, project_id: Uuid) -> validation::ValidationLimits {
+    let mut limits = validation::ValidationLimits::default();
+    if let Some(chars) = project_or_global_setting(state, "limits.req_text_chars", project_id)
+        .await
+        .and_then(|v| v.trim().trim_matches('"').parse::<usize>().ok())
+        .filter(|v| *v > 0)
+    {
+        limits.req_text_chars = chars;
+    }
+    if let Some(days) = project_or_global_setting(state, "validation.suspect_stale_days", project_id)
+        .await
+        .and_then(|v| v.trim().trim_matches('"').parse::<i64>().ok())
+        .filter(|v| *v > 0)
+    {
+        limits.suspect_stale_days = days;
+    }
+    limits
+}
+
+/// After a bulk import: drop the project's cached issues, which may no
+/// longer match the model, and reseed the cache from a full validation.
+/// The emitted delta removes everything that was cached and adds the
+/// fresh issues.
+async fn reseed_validation(
+    app: &tauri::AppHandle,
+    state: &State<'_, AppState>,
+    project_id: Uuid,
+) -> Result<(), String> {
+    use tauri::Emitter;
+
+    let dropped = state.validation_cache.lock().unwrap().invalidate(project_id);
+    let issues = full_validation(state, project_id).await?;
+    let mut delta = state.validation_cache.lock().unwrap().reconcile(project_id, &issues);
+    delta.removed_issue_ids = dropped;
+    if !delta.is_empty() {
+        let _ = app.emit(crate::events::VALIDATION_UPDATED, &delta);
+    }
+    Ok(())
+}
+
+/// Re-validate the given node/edge ids (plus the edges incident to any
+/// touched node) and emit the change on `events::VALIDATION_UPDATED`.
+/// A project the cache hasn't seen yet gets a full validation instead.
+async fn revalidate_touched(
+    app: &tauri::AppHandle,
+    state: &State<'_, AppState>,
+    project_id: Uuid,
+    touched: Vec<Uuid>,
+) -> Result<(), String> {
+    use tauri::Emitter;
+
+    let (seeded, project_wide_reported) = {
+        let cache = state.validation_cache.lock().unwrap();
+        (
+            cache.is_seeded(project_id),
+            cache.has_any_code(project_id, &validation::FULL_VALIDATION_CODES),
+        )
+    };
+    // Hierarchy cycles and duplicate req_ids only show up in a full
+    // validation. A changed hierarchy edge can close a cycle and a changed
+    // requirement can take another's req_id; while either is reported, any
+    // edit, deletion or archiving might clear it.
+    let mut project_wide = project_wide_reported;
+    if seeded && !project_wide {
+        for id in &touched {
+            if let Some(edge) = state.store.get_edge(*id).await.map_err(|e| e.to_string())? {
+                project_wide = validation::HIERARCHY_EDGE_KINDS.contains(&edge.kind);
+            } else if let Some(node) = state.store.get_node(*id).await.map_err(|e| e.to_string())? {
+                if let NodeData::Requirement(r) = &node.data {
+                    let req_id = r.req_id.as_deref().map(str::trim).unwrap_or_default();
+                    project_wide = !req_id.is_empty()
+                        && state
+                            .store
+                            .count_requirements_with_req_id(project_id, req_id)
+                            .await
+                            .map_err(|e| e.to_string())?
+                            > 1;
+                }
+            }
+            if project_wide {
+                break;
+            }
+        }
+    }
+    let delta = if !seeded || project_wide {
+        let issues = full_validation(state, project_id).await?;
+        state.validation_cache.lock().unwrap().reconcile(project_id, &issues)
+    } else {
+        let mut touched = touched;
+        let mut nodes: Vec<Node> = Vec::new();
+        let mut edges: Vec<Edge> = Vec::new();
+        for id in touched.clone() {
+            if let Some(node) = state.store.get_node(id).await.map_err(|e| e.to_string())? {
+                for edge in state.store.edges_for_node(id).await.map_err(|e| e.to_string())? {
+                    if !edges.iter().any(|e| e.id == edge.id) {
+                        touched.push(edge.id);
+                        edges.push(edge);
+                    }
+                }
+                nodes.push(node);
+            } else if let Some(edge) = state.store.get_edge(id).await.map_err(|e| e.to_string())? {
+                if !edges.iter().any(|e| e.id == edge.id) {
+                    edges.push(edge);
+                }
+            }
+        }
+        // Port direction messages name the blocks owning «connects» ports.
+        let connected: Vec<Uuid> = edges
+            .iter()
+            .filter(|e| e.kind == EdgeKind::Connects)
+            .flat_map(|e| [e.source_id, e.target_id])
+            .collect();
+        for id in connected {
+            for edge in state.store.edges_for_node(id).await.map_err(|e| e.to_string())? {
+                let owns = edge.kind == EdgeKind::Composes && edge.target_id == id;
+                if owns && !edges.iter().any(|e| e.id == edge.id) {
+                    edges.push(edge);
+                }
+            }
+        }
+        // Edge rules need both endpoints.
+        let endpoints: Vec<Uuid> = edges.iter().flat_map(|e| [e.source_id, e.target_id]).collect();
+        for id in endpoints {
+            if !nodes.iter().any(|n| n.id == id) {
+                if let Some(node) = state.store.get_node(id).await.map_err(|e| e.to_string())? {
+                    nodes.push(node);
+                }
+            }
+        }
+        let limits = validation_limits(state, project_id).await;
+        state
+            .validation_cache
+            .lock()
+            .unwrap()
+            .revalidate(project_id, &touched, &nodes, &edges, &limits)
+    };
+
+    if !delta.is_empty() {
+        let _ = app.emit(crate::events::VALIDATION_UPDATED, &delta);
+    }
+    Ok(())
+}
+
+// ── Export ────────────────────────────────────────────────────────────────────
+
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn export_markdown(
+    project_id: String,
+    group_by: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let id = parse_uuid("project_id", &project_id).map_err(|e| e.to_string())?;
+    let group_by = group_by
+        .filter(|g| !g.trim().is_empty())
+        .map(|g| {
+            crate::core::export::MarkdownGroupBy::parse(&g).ok_or_else(|| {
+                format!("unknown group_by '{g}' (expected allocation, priority or status)")
+            })
+        })
+        .transpose()?;
+    let project = state
+        .store
+        .get_project(id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "project not found".to_string())?;
+    let nodes = state
+        .store
+        .list_nodes(id)
+        .await
+        .map_err(|e| e.to_string())?;
+    let edges = state.store.list_edges(id).await.map_err(|e| e.to_string())?;
+    let properties = state
+        .store
+        .list_project_block_properties(id)
+        .await
+        .map_err(|e| e.to_string())?;
+    let external = state
+        .store
+        .list_project_external_links(id)
+        .await
+        .map_err(|e| e.to_string())?;
+    let unrecognized = state
+        .store
+        .list_unrecognized_rows(id)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(crate::core::export::to_markdown(
+        &project,
+        &nodes,
+        &edges,
+        &properties,
+        &external,
+        &unrecognized,
+        group_by,
+    ))
+}
+
+/// The requirements table as CSV; see `core::export::to_csv`. `bom`
+/// prefixes a UTF-8 byte order mark, which Excel on Windows needs to read
+/// non-ASCII text correctly.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn export_csv(
+    project_id: String,
+    bom: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let id = parse_uuid("project_id", &project_id).map_err(|e| e.to_string())?;
+    let project = state
+        .store
+        .get_project(id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "project not found".to_string())?;
+    let nodes = state
+        .store
+        .list_nodes_by_kind(id, &NodeKind::Requirement)
+        .await
+        .map_err(|e| e.to_string())?;
+    let csv = crate::core::export::to_csv(&project, &nodes);
+    Ok(if bom.unwrap_or(false) {
+        format!("\u{feff}{csv}")
+    } else {
+        csv
+    })
+}
+
+/// Every requirement field as CSV, in the layout `import_requirements_csv`
+/// reads; see `core::export::to_requirements_csv`.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn export_requirements_csv(
+    project_id: String,
+    state: State<'_, AppState>,
+) -> Result<String, CommandError> {
+    let id = parse_uuid("project_id", &project_id)?;
+    let project = state
+        .store
+        .get_project(id)
+        .await?
+        .ok_or_else(|| CommandError::not_found(format!("project {id}")))?;
+    let nodes = state.store.list_nodes_by_kind(id, &NodeKind::Requirement).await?;
+    Ok(crate::core::export::to_requirements_csv(&project, &nodes))
+}
+
+/// Import a requirements table from CSV; see `core::import::csv`. Each
+/// requirement is saved like an edit, so it gets requirement history and,
+/// when changed, suspect links. Rows that can't be imported come back in
+/// `errors` while the rest are written.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn import_requirements_csv(
+    project_id: String,
+    csv_text: String,
+    mode: crate::core::import::csv::CsvImportMode,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<crate::core::import::csv::RequirementsCsvImportSummary, CommandError> {
+    let pid = parse_uuid("project_id", &project_id)?;
+    state
+        .store
+        .get_project(pid)
+        .await?
+        .ok_or_else(|| CommandError::not_found(format!("project {pid}")))?;
+    let text_limit = validation_limits(&state, pid).await.req_text_chars;
+
+    let write = state
+        .store
+        .begin_heavy_write("importing requirements", Some(USER_HEAVY_WRITE_WAIT))
+        .await?;
+    let nodes = state.store.list_nodes_by_kind(pid, &NodeKind::Requirement).await?;
+    let import =
+        crate::core::import::csv::requirements_from_csv(pid, &csv_text, mode, &nodes, text_limit)
+            .map_err(CommandError::invalid)?;
+    for node in &import.created {
+        state.store.upsert_node(node).await?;
+    }
+    for node in &import.updated {
+        state.store.upsert_node(node).await?;
+        let _ = state.store.flag_suspect_links(pid, node.id, "requirement updated").await;
+    }
+    drop(write);
+
+    let _ = reseed_validation(&app, &state, pid).await;
+    Ok(crate::core::import::csv::RequirementsCsvImportSummary {
+        created: import.created.len(),
+        updated: import.updated.len(),
+        unchanged: import.unchanged,
+        errors: import.errors,
+    })
+}
+
+/// Native JSON of the project, or of `scope` within it.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn export_json(
+    project_id: String,
+    scope: Option<ExportScope>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let id = parse_uuid("project_id", &project_id).map_err(|e| e.to_string())?;
+    let (project, model, unrecognized) =
+        load_export_model(&state.store, id, &scope.unwrap_or_default(), Some(ExportFormat::Json))
+            .await
+            .map_err(|e| e.to_string())?;
+    crate::core::export::to_native_json(&project, &model.nodes, &model.edges, &unrecognized)
+        .map_err(|e| e.to_string())
+}
+
+/// Read a native JSON export back (see `core::export::from_native_json`)
+/// as a new project, or into the existing project `into_project_id`.
+/// Ids already in the database are replaced with fresh ones, and each
+/// element's `created_at` in the file is kept as its original creation
+/// time; everything is written in one transaction.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn import_json(
+    project_json: String,
+    into_project_id: Option<String>,
+    actor: Option<String>,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<crate::core::export::NativeImportSummary, CommandError> {
+    let mut doc = crate::core::export::from_native_json(&project_json)
+        .map_err(|e| CommandError::invalid(e.to_string()))?;
+    let into = into_project_id
+        .as_deref()
+        .map(|id| parse_uuid("into_project_id", id))
+        .transpose()?;
+
+    let write = state
+        .store
+        .begin_heavy_write("importing a project", Some(USER_HEAVY_WRITE_WAIT))
+        .await?;
+    let taken = state.store.taken_ids(&doc.ids()).await?;
+    let (project, create) = match into {
+        Some(id) => {
+            let project = state
+                .store
+                .get_project(id)
+                .await?
+                .ok_or_else(|| CommandError::not_found(format!("project {id}")))?;
+            (project, false)
+        }
+        None => {
+            let mut project = doc.project.clone();
+            if taken.contains(&project.id) {
+                project.id = Uuid::new_v4();
+            }
+            (project, true)
+        }
+    };
+    doc.remap(project.id, &taken);
+    doc.mark_imported();
+    state
+        .store
+        .import_model(&project, create, &doc.nodes, &doc.edges, &actor_or_user(actor))
+        .await?;
+    drop(write);
+
+    if !create {
+        let _ = reseed_validation(&app, &state, project.id).await;
+    }
+    Ok(crate::core::export::NativeImportSummary {
+        project_id: project.id,
+        nodes_imported: doc.nodes.len(),
+        edges_imported: doc.edges.len(),
+        warnings: doc.warnings,
+    })
+}
+
+/// SysML XMI of the project, or of `scope` within it.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn export_xmi(
+    project_id: String,
+    scope: Option<ExportScope>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let id = parse_uuid("project_id", &project_id).map_err(|e| e.to_string())?;
+    let (project, model, _) =
+        load_export_model(&state.store, id, &scope.unwrap_or_default(), Some(ExportFormat::Xmi))
+            .await
+            .map_err(|e| e.to_string())?;
+    Ok(crate::core::export::to_xmi(
+        &project,
+        &model.nodes,
+        &model.edges,
+        &model.properties,
+    ))
+}
+
+/// ReqIF 1.2 of the project's requirements and their trace links, or of
+/// `scope` within it, for DOORS and Polarion; see `core::export::reqif`.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn export_reqif(
+    project_id: String,
+    scope: Option<ExportScope>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let id = parse_uuid("project_id", &project_id).map_err(|e| e.to_string())?;
+    let (project, model, _) =
+        load_export_model(&state.store, id, &scope.unwrap_or_default(), None)
+            .await
+            .map_err(|e| e.to_string())?;
+    Ok(crate::core::export::to_reqif(&project, &model.nodes, &model.edges))
+}
+
+/// Import requirements and trace links from a ReqIF file into a project;
+/// see `core::import`. Requirements already in the project (by req_id)
+/// and links that already exist are matched, not duplicated.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn import_reqif(
+    project_id: String,
+    xml: String,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<crate::core::import::ReqIfImportSummary, CommandError> {
+    let pid = parse_uuid("project_id", &project_id)?;
+    state
+        .store
+        .get_project(pid)
+        .await?
+        .ok_or_else(|| CommandError::not_found(format!("project {pid}")))?;
+
+    let write = state
+        .store
+        .begin_heavy_write("importing ReqIF", Some(USER_HEAVY_WRITE_WAIT))
+        .await?;
+    let nodes = state.store.list_nodes(pid).await?;
+    let edges = state.store.list_edges(pid).await?;
+    let import = crate::core::import::import_reqif(pid, &xml, &nodes, &edges)
+        .map_err(CommandError::invalid)?;
+    for node in &import.nodes {
+        state.store.upsert_node(node).await?;
+    }
+    for edge in &import.edges {
+        state.store.upsert_edge(edge).await?;
+    }
+    drop(write);
+
+    let _ = reseed_validation(&app, &state, pid).await;
+    Ok(import.summary())
+}
+
+/// What a JSON or XMI export of `scope` would write, without writing it:
+/// counts, an estimated size (see `core::export::scope`) and warnings
+/// about content the export drops or carries over unchecked.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn export_preview(
+    project_id: String,
+    format: String,
+    scope: Option<ExportScope>,
+    state: State<'_, AppState>,
+) -> Result<ExportPreview, CommandError> {
+    use crate::core::export::scope::{estimate_size, ByteCounter, ExportCounts, ExportWarning};
+
+    let id = parse_uuid("project_id", &project_id)?;
+    let format = ExportFormat::parse(&format).ok_or_else(|| {
+        CommandError::invalid(format!("unsupported export format '{format}' (expected json or xmi)"))
+    })?;
+    let (project, model, unrecognized) =
+        load_export_model(&state.store, id, &scope.unwrap_or_default(), Some(format))
+            .await
+            .map_err(|e| match e {
+                ExportLoadError::NotFound => CommandError::not_found(format!("project {id}")),
+                ExportLoadError::Store(e) => CommandError::from(e),
+            })?;
+
+    let render = |nodes: &[Node], edges: &[Edge], props: &[BlockProperty]| -> anyhow::Result<u64> {
+        let mut out = ByteCounter::default();
+        let mut progress = |_: usize, _: usize| true;
+        match format {
+            ExportFormat::Json => crate::core::export::write_native_json(
+                &mut out,
+                &project,
+                nodes,
+                edges,
+                &unrecognized,
+                &mut progress,
+            )?,
+            ExportFormat::Xmi => crate::core::export::write_xmi(
+                &mut out,
+                &project,
+                nodes,
+                edges,
+                props,
+                &mut progress,
+            )?,
+        };
+        Ok(out.0)
+    };
+    let (estimated_bytes, exact) = estimate_size(&model, &render)?;
+
+    let mut warnings = Vec::new();
+    let mut warn = |code: &'static str, count: usize, message: String| {
+        if count > 0 {
+            warnings.push(ExportWarning {
+                code,
+                message,
+                count,
+            });
+        }
+    };
+    let all_unrecognized = state.store.list_unrecognized_rows(id).await?;
+    let mut unknown_kinds: Vec<&str> =
+        all_unrecognized.iter().map(|r| r.kind.as_str()).collect();
+    unknown_kinds.sort_unstable();
+    unknown_kinds.dedup();
+    let fate = match format {
+        ExportFormat::Json => "carried over unchanged",
+        ExportFormat::Xmi => "left out",
+    };
+    warn(
+        "UNKNOWN_KINDS",
+        all_unrecognized.len(),
+        format!(
+            "{} rows of kinds this version doesn't know ({}) will be {fate}",
+            all_unrecognized.len(),
+            unknown_kinds.join(", ")
+        ),
+    );
+    let limit = validation_limits(&state, id).await.req_text_chars;
+    let over_limit = model
+        .nodes
+        .iter()
+        .filter(|n| match &n.data {
+            NodeData::Requirement(r) => r.text.as_deref().unwrap_or("").chars().count() > limit,
+            _ => false,
+        })
+        .count();
+    warn(
+        "TEXT_OVER_LIMIT",
+        over_limit,
+        format!("{over_limit} requirements have text over the {limit}-character limit"),
+    );
+    warn(
+        "DANGLING_EDGES_DROPPED",
+        model.dropped_edges,
+        format!(
+            "{} edges with an end outside the export will be dropped",
+            model.dropped_edges
+        ),
+    );
+    let diagrams = state.store.list_diagrams(id).await?.len();
+    let documents = state.store.list_documents(id).await?.len();
+    warn(
+        "NOT_EXPORTED",
+        diagrams + documents,
+        format!("{diagrams} diagrams and {documents} documents are not part of this format"),
+    );
+
+    Ok(ExportPreview {
+        format: match format {
+            ExportFormat::Json => "json",
+            ExportFormat::Xmi => "xmi",
+        }
+        .to_string(),
+        counts: ExportCounts {
+            nodes: model.nodes.len(),
+            edges: model.edges.len(),
+            block_properties: match format {
+                ExportFormat::Json => 0,
+                ExportFormat::Xmi => model.properties.len(),
+            },
+            unrecognized: unrecognized.len(),
+            diagrams: 0,
+            documents: 0,
+        },
+        estimated_bytes,
+        exact,
+        warnings,
+    })
+}
+
+enum ExportLoadError {
+    NotFound,
+    Store(anyhow::Error),
+}
+
+impl std::fmt::Display for ExportLoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportLoadError::NotFound => write!(f, "project not found"),
+            ExportLoadError::Store(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl From<anyhow::Error> for ExportLoadError {
+    fn from(e: anyhow::Error) -> Self {
+        ExportLoadError::Store(e)
+    }
+}
+
+/// The project narrowed to `scope` as `format` writes it: block properties
+/// for XMI, rows of unknown kinds for JSON, neither with no `format` (ReqIF).
+/// Shared by the JSON, XMI and ReqIF exports, the export job and
+/// `export_preview`. Timed as a query; `rows` counts nodes and edges.
+#[tracing::instrument(target = "query", skip_all, fields(project_id = %project_id, rows), err)]
+async fn load_export_model(
+    store: &crate::core::store::Store,
+    project_id: Uuid,
+    scope: &ExportScope,
+    format: Option<ExportFormat>,
+) -> Result<(Project, ScopedModel, Vec<UnrecognizedRow>), ExportLoadError> {
+    let project = store
+        .get_project(project_id)
+        .await?
+        .ok_or(ExportLoadError::NotFound)?;
+    let nodes = store.list_nodes(project_id).await?;
+    let edges = store.list_edges(project_id).await?;
+    tracing::Span::current().record("rows", nodes.len() + edges.len());
+    let properties = match format {
+        Some(ExportFormat::Xmi) => store.list_project_block_properties(project_id).await?,
+        Some(ExportFormat::Json) | None => Vec::new(),
+    };
+    let unrecognized = match format {
+        Some(ExportFormat::Json) => store.list_unrecognized_rows(project_id).await?,
+        Some(ExportFormat::Xmi) | None => Vec::new(),
+    };
+    let model = crate::core::export::scope::resolve(scope, nodes, edges, properties);
+    Ok((project, model, unrecognized))
+}
+
+/// GraphML of the project's active nodes and their edges, for network
+/// analysis tools; see `core::export::graphml`. Empty or missing kind
+/// lists keep every kind.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn export_graphml(
+    project_id: String,
+    node_kinds: Option<Vec<String>>,
+    edge_kinds: Option<Vec<String>>,
+    state: State<'_, AppState>,
+) -> Result<String, CommandError> {
+    let (nodes, edges, filter) = graph_inputs(&state, &project_id, node_kinds, edge_kinds).await?;
+    Ok(crate::core::export::graphml::to_graphml(&nodes, &edges, &filter))
+}
+
+/// Degree distribution, highest-degree nodes and weakly connected
+/// component count of the same graph `export_graphml` writes.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn graph_metrics(
+    project_id: String,
+    node_kinds: Option<Vec<String>>,
+    edge_kinds: Option<Vec<String>>,
+    state: State<'_, AppState>,
+) -> Result<crate::core::export::graphml::GraphMetrics, CommandError> {
+    let (nodes, edges, filter) = graph_inputs(&state, &project_id, node_kinds, edge_kinds).await?;
+    Ok(crate::core::export::graphml::metrics(&nodes, &edges, &filter))
+}
+
+async fn graph_inputs(
+    state: &State<'_, AppState>,
+    project_id: &str,
+    node_kinds: Option<Vec<String>>,
+    edge_kinds: Option<Vec<String>>,
+) -> Result<(Vec<Node>, Vec<Edge>, crate::core::export::graphml::GraphFilter), CommandError> {
+    use crate::core::store::{parse_edge_kind, parse_node_kind};
+
+    let id = parse_uuid("project_id", project_id)?;
+    if state.store.get_project(id).await?.is_none() {
+        return Err(CommandError::not_found(format!("project {id}")));
+    }
+    let invalid = |e: anyhow::Error| CommandError::invalid(e.to_string());
+    let filter = crate::core::export::graphml::GraphFilter {
+        node_kinds: node_kinds
+            .unwrap_or_default()
+            .iter()
+            .map(|k| parse_node_kind(k.trim()))
+            .collect::<anyhow::Result<_>>()
+            .map_err(invalid)?,
+        edge_kinds: edge_kinds
+            .unwrap_or_default()
+            .iter()
+            .map(|k| parse_edge_kind(k.trim()))
+            .collect::<anyhow::Result<_>>()
+            .map_err(invalid)?,
+    };
+    let nodes = state.store.list_nodes(id).await?;
+    let edges = state.store.list_edges(id).await?;
+    Ok((nodes, edges, filter))
+}
+
+/// JSON-LD export. `base_iri` / `extra_context` override the
+/// `export.base_iri` / `export.extra_context` settings (project, then global);
+/// with neither set the output uses `urn:uuid:` identifiers.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn export_json_ld(
+    project_id: String,
+    base_iri: Option<String>,
+    extra_context: Option<serde_json::Value>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let id = parse_uuid("project_id", &project_id).map_err(|e| e.to_string())?;
+    let project = state
+        .store
+        .get_project(id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "project not found".to_string())?;
+    let nodes = state
+        .store
+        .list_nodes(id)
+        .await
+        .map_err(|e| e.to_string())?;
+    let edges = state.store.list_edges(id).await.map_err(|e| e.to_string())?;
+
+    let base_iri = match base_iri {
+        Some(iri) => Some(iri),
+        None => project_or_global_setting(&state, "export.base_iri", id).await,
+    };
+    let extra_context = match extra_context {
+        Some(ctx) => Some(ctx),
+        None => project_or_global_setting(&state, "export.extra_context", id)
+            .await
+            .map(|raw| {
+                serde_json::from_str::<serde_json::Value>(&raw)
+                    .map_err(|e| format!("export.extra_context is not valid JSON: {e}"))
+            })
+            .transpose()?,
+    };
+    let extra_context = match extra_context {
+        Some(serde_json::Value::Object(map)) => map,
+        Some(serde_json::Value::Null) | None => serde_json::Map::new(),
+        Some(_) => return Err("extra_context must be a JSON object".to_string()),
+    };
+
+    let options = crate::core::export::JsonLdOptions {
+        base_iri,
+        extra_context,
+    };
+    crate::core::export::to_json_ld(&project, &nodes, &edges, &options).map_err(|e| e.to_string())
+}
+
+/// Verification cross-reference matrix written to `path` as "csv",
+/// "markdown" or "xlsx"; see `core::export::vcrm`.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn export_vcrm(
+    project_id: String,
+    format: String,
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<crate::core::export::vcrm::VcrmExport, CommandError> {
+    use crate::core::export::vcrm::{self, VcrmExport, VcrmFormat};
+
+    let pid = parse_uuid("project_id", &project_id)?;
+    let format = VcrmFormat::parse(&format).ok_or_else(|| {
+        CommandError::invalid(format!("unknown format '{format}' (expected csv, markdown or xlsx)"))
+    })?;
+    let dest = PathBuf::from(path.trim());
+    if dest.as_os_str().is_empty() {
+        return Err(CommandError::invalid("export path is empty"));
+    }
+    let project = state
+        .store
+        .get_project(pid)
+        .await?
+        .ok_or_else(|| CommandError::not_found(format!("project {pid}")))?;
+    let nodes = state.store.list_nodes(pid).await?;
+    let edges = state.store.list_edges_by_kind(pid, &[EdgeKind::Verifies]).await?;
+
+    let matrix = vcrm::build(&nodes, &edges);
+    match format {
+        VcrmFormat::Csv => std::fs::write(&dest, vcrm::to_csv(&matrix))?,
+        VcrmFormat::Markdown => std::fs::write(&dest, vcrm::to_markdown(&matrix, &project.name))?,
+        VcrmFormat::Xlsx => std::fs::write(&dest, vcrm::to_xlsx(&matrix))?,
+    }
+    Ok(VcrmExport { rows: matrix.rows.len(), unverified: matrix.unverified.len() })
+}
+
+/// Requirement × test case matrix as "markdown" or "csv", with uncovered
+/// requirements listed after it; see `core::export::matrix`.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn export_traceability_matrix(
+    project_id: String,
+    format: String,
+    state: State<'_, AppState>,
+) -> Result<String, CommandError> {
+    use crate::core::export::matrix::{self, MatrixFormat};
+
+    let pid = parse_uuid("project_id", &project_id)?;
+    let format = MatrixFormat::parse(&format).ok_or_else(|| {
+        CommandError::invalid(format!("unknown format '{format}' (expected csv or markdown)"))
+    })?;
+    let project = state
+        .store
+        .get_project(pid)
+        .await?
+        .ok_or_else(|| CommandError::not_found(format!("project {pid}")))?;
+    let nodes = state.store.list_nodes(pid).await?;
+    let edges = state.store.list_edges_by_kind(pid, &[EdgeKind::Verifies]).await?;
+
+    let grid = matrix::to_traceability_matrix(&nodes, &edges);
+    Ok(match format {
+        MatrixFormat::Csv => matrix::to_csv(&grid),
+        MatrixFormat::Markdown => matrix::to_markdown(&grid, &project.name),
+    })
+}
+
+/// Requirements traceability matrix as "markdown" or "csv"; see
+/// `core::export::rtm`.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn generate_rtm(
+    project_id: String,
+    format: String,
+    state: State<'_, AppState>,
+) -> Result<String, CommandError> {
+    use crate::core::export::rtm::{self, RtmFormat};
+
+    let pid = parse_uuid("project_id", &project_id)?;
+    let format = RtmFormat::parse(&format).ok_or_else(|| {
+        CommandError::invalid(format!("unknown format '{format}' (expected csv or markdown)"))
+    })?;
+    let project = state
+        .store
+        .get_project(pid)
+        .await?
+        .ok_or_else(|| CommandError::not_found(format!("project {pid}")))?;
+    let nodes = state.store.list_nodes(pid).await?;
+    let edges = state
+        .store
+        .list_edges_by_kind(
+            pid,
+            &[EdgeKind::Satisfies, EdgeKind::Verifies, EdgeKind::Refines, EdgeKind::Derives],
+        )
+        .await?;
+
+    let matrix = rtm::build(&nodes, &edges);
+    Ok(match format {
+        RtmFormat::Csv => rtm::to_csv(&matrix),
+        RtmFormat::Markdown => rtm::to_markdown(&matrix, &project.name),
+    })
+}
+
+/// Per-requirement dossier for verification closure packages, as Markdown
+/// or HTML (one page per requirement). All requirements must belong to the
+/// same project.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn export_requirement_dossier(
+    node_ids: Vec<String>,
+    format: String,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    use crate::core::export::dossier::{
+        self, DossierFormat, DossierSuspect, LinkedNode, RequirementDossier,
+    };
+
+    let format = DossierFormat::parse(&format)
+        .ok_or_else(|| format!("unknown format '{format}' (expected markdown or html)"))?;
+    let ids = node_ids
+        .iter()
+        .map(|id| parse_uuid("node_ids", id).map_err(|e| e.to_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let store = &state.store;
+    let mut requirements = Vec::new();
+    for id in ids {
+        let node = store
+            .get_node(id)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("node {id} not found"))?;
+        if node.kind != NodeKind::Requirement {
+            return Err(format!("'{}' is a {}, not a requirement", node.name, node.kind));
+        }
+        requirements.push(node);
+    }
+    let Some(project_id) = requirements.first().map(|n| n.project_id) else {
+        return Ok(dossier::render(&[], format));
+    };
+    if requirements.iter().any(|n| n.project_id != project_id) {
+        return Err("requirements must all belong to one project".to_string());
+    }
+
+    let nodes: std::collections::HashMap<Uuid, Node> = store
+        .list_nodes(project_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|n| (n.id, n))
+        .collect();
+    let documents = store.list_documents(project_id).await.map_err(|e| e.to_string())?;
+    let sections = store
+        .list_project_document_sections(project_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    let suspects = store.get_suspect_links(project_id).await.map_err(|e| e.to_string())?;
+    let project_edges = store.list_edges(project_id).await.map_err(|e| e.to_string())?;
+    let name_of = |id: Uuid| {
+        nodes.get(&id).map(|n| n.name.clone()).unwrap_or_else(|| "?".to_string())
+    };
+
+    let mut dossiers = Vec::new();
+    for node in requirements {
+        // In edges_for_node's order, which the dossier lists follow.
+        let mut edges: Vec<Edge> = project_edges
+            .iter()
+            .filter(|e| e.source_id == node.id || e.target_id == node.id)
+            .cloned()
+            .collect();
+        edges.sort_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| a.id.cmp(&b.id)));
+        let incoming = |kind: EdgeKind| -> Vec<Node> {
+            edges
+                .iter()
+                .filter(|e| e.kind == kind && e.target_id == node.id)
+                .filter_map(|e| nodes.get(&e.source_id).cloned())
+                .collect()
+        };
+        let satisfied_by = incoming(EdgeKind::Satisfies);
+        let verified_by = incoming(EdgeKind::Verifies);
+
+        // A refining requirement is the child; a derived one is the child
+        // of the requirement it derives from.
+        let mut parents = Vec::new();
+        let mut children = Vec::new();
+        let lineage = edges
+            .iter()
+            .filter(|e| matches!(e.kind, EdgeKind::Refines | EdgeKind::Derives));
+        for edge in lineage {
+            let (parent, child) = match edge.kind {
+                EdgeKind::Refines => (edge.target_id, edge.source_id),
+                _ => (edge.source_id, edge.target_id),
+            };
+            let (other, list) = if child == node.id {
+                (parent, &mut parents)
+            } else {
+                (child, &mut children)
+            };
+            if let Some(other) = nodes.get(&other).filter(|n| n.kind == NodeKind::Requirement) {
+                list.push(LinkedNode { node: other.clone(), via: edge.kind.clone() });
+            }
+        }
+
+        let open_comments = store
+            .get_req_comments(node.id)
+            .await
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .filter(|c| c.resolved_at.is_none())
+            .collect();
+        let suspect_links = suspects
+            .iter()
+            .filter(|s| s.source_node_id == node.id || s.target_node_id == node.id)
+            .map(|s| DossierSuspect {
+                other_name: name_of(if s.source_node_id == node.id {
+                    s.target_node_id
+                } else {
+                    s.source_node_id
+                }),
+                link: s.clone(),
+            })
+            .collect();
+
+        dossiers.push(RequirementDossier {
+            history_count: store
+                .count_requirement_history(node.id)
+                .await
+                .map_err(|e| e.to_string())?,
+            last_change: store
+                .list_requirement_history(node.id, 1)
+                .await
+                .map_err(|e| e.to_string())?
+                .pop(),
+            sources: requirement_sources(&node, &documents, &sections),
+            external_items: store
+                .external_items_for_node(node.id)
+                .await
+                .map_err(|e| e.to_string())?,
+            satisfied_by,
+            verified_by,
+            parents,
+            children,
+            open_comments,
+            suspect_links,
+            node,
+        });
+    }
+
+    Ok(dossier::render(&dossiers, format))
+}
+
+/// A document template found by `list_document_templates`.
+#[derive(Debug, Clone, Serialize)]
+pub struct DocumentTemplateInfo {
+    /// File name without the extension.
+    pub name: String,
+    pub path: String,
+    /// Shipped with the app rather than added by the user.
+    pub bundled: bool,
+}
+
+/// Directories holding document templates: the bundled set (falling back
+/// to the source tree in dev) and the user's `templates` folder in the app
+/// data directory.
+fn document_template_dirs(app: &tauri::AppHandle) -> Vec<(PathBuf, bool)> {
+    let bundled = app
+        .path()
+        .resource_dir()
+        .map(|dir| dir.join("resources").join("templates"))
+        .ok()
+        .filter(|dir| dir.is_dir())
+        .unwrap_or_else(|| {
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("resources").join("templates")
+        });
+    let mut dirs = vec![(bundled, true)];
+    if let Ok(data_dir) = app.path().app_data_dir() {
+        dirs.push((data_dir.join("templates"), false));
+    }
+    dirs
+}
+
+fn is_markdown_path(path: &std::path::Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("md") || e.eq_ignore_ascii_case("markdown"))
+}
+
+/// Markdown templates in the bundled and user template directories, by name.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn list_document_templates(
+    app: tauri::AppHandle,
+) -> Result<Vec<DocumentTemplateInfo>, String> {
+    let mut templates = Vec::new();
+    for (dir, bundled) in document_template_dirs(&app) {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !is_markdown_path(&path) || !path.is_file() {
+                continue;
+            }
+            templates.push(DocumentTemplateInfo {
+                name: path
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                path: path.to_string_lossy().to_string(),
+                bundled,
+            });
+        }
+    }
+    templates.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+    Ok(templates)
+}
+
+/// Fill the Markdown template at `template_path` from the project and write
+/// the result to `output_path`. Template errors name the offending line and
+/// leave `output_path` untouched.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn generate_document_from_template(
+    project_id: String,
+    template_path: String,
+    output_path: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    use crate::core::export::template::{render_markdown, TemplateData};
+
+    let id = parse_uuid("project_id", &project_id).map_err(|e| e.to_string())?;
+    if !is_markdown_path(std::path::Path::new(&template_path)) {
+        return Err("only Markdown (.md) templates are supported".to_string());
+    }
+    let source = std::fs::read_to_string(&template_path)
+        .map_err(|e| format!("can't read template {template_path}: {e}"))?;
+
+    let project = state
+        .store
+        .get_project(id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "project not found".to_string())?;
+    let nodes = state.store.list_nodes(id).await.map_err(|e| e.to_string())?;
+    let edges = state.store.list_edges(id).await.map_err(|e| e.to_string())?;
+
+    let data = TemplateData {
+        project: &project,
+        nodes: &nodes,
+        edges: &edges,
+        generated_at: Utc::now(),
+    };
+    let document =
+        render_markdown(&source, &data).map_err(|e| format!("{template_path}: {e}"))?;
+    std::fs::write(&output_path, document)
+        .map_err(|e| format!("can't write {output_path}: {e}"))
+}
+
+/// Read a setting scoped to the project, falling back to the global value.
+async fn project_or_global_setting(
+    state: &State<'_, AppState>,
+    key: &str,
+    project_id: Uuid,
+) -> Option<String> {
+    let scoped = state
+        .store
+        .get_setting(key, Some(project_id))
+        .await
+        .unwrap_or(None);
+    let value = match scoped {
+        Some(v) => Some(v),
+        None => state.store.get_setting(key, None).await.unwrap_or(None),
+    };
+    value.filter(|v| !v.trim().is_empty())
+}
+
+// ── Background export jobs ────────────────────────────────────────────────────
+
+/// Start exporting a project to `path` ("json" or "xmi") on a background
+/// task. For large models; `export_json` / `export_xmi` remain for small ones.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn start_export_job(
+    project_id: String,
+    format: String,
+    path: String,
+    scope: Option<ExportScope>,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<crate::jobs::Job, String> {
+    use crate::jobs::JobKind;
+
+    let id = parse_uuid("project_id", &project_id).map_err(|e| e.to_string())?;
+    let format =
+        ExportFormat::parse(&format).ok_or_else(|| format!("unsupported export format '{format}'"))?;
+    let dest = PathBuf::from(path.trim());
+    if dest.as_os_str().is_empty() {
+        return Err("export path is empty".to_string());
+    }
+    if let Some(parent) = dest.parent().filter(|p| !p.as_os_str().is_empty()) {
+        if !parent.is_dir() {
+            return Err(format!("directory {} does not exist", parent.display()));
+        }
+    }
+    state
+        .store
+        .get_project(id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "project not found".to_string())?;
+
+    let (job, cancel) = state.jobs.lock().unwrap().start(
+        JobKind::Export,
+        id,
+        Some(dest.to_string_lossy().to_string()),
+    );
+    let job_id = job.id;
+    let scope = scope.unwrap_or_default();
+    tauri::async_runtime::spawn(async move {
+        run_export_job(app, job_id, id, format, scope, dest, cancel).await;
+    });
+    Ok(job)
+}
+
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn get_job(job_id: String, state: State<'_, AppState>) -> Result<crate::jobs::Job, String> {
+    let id = parse_uuid("job_id", &job_id).map_err(|e| e.to_string())?;
+    state
+        .jobs
+        .lock()
+        .unwrap()
+        .get(id)
+        .ok_or_else(|| format!("job {id} not found"))
+}
+
+/// Request cancellation. The job stops at its next element, removes the
+/// partial file and completes with status `cancelled`.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn cancel_job(job_id: String, state: State<'_, AppState>) -> Result<crate::jobs::Job, String> {
+    let id = parse_uuid("job_id", &job_id).map_err(|e| e.to_string())?;
+    state.jobs.lock().unwrap().cancel(id)
+}
+
+/// Sidecar and llama-cli processes currently running for a command.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn list_background_processes(
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::processes::BackgroundProcess>, CommandError> {
+    Ok(state.processes.lock().unwrap().list())
+}
+
+/// Kill a runaway process. The command that spawned it then fails as if
+/// the process had crashed.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn kill_background_process(
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<(), CommandError> {
+    let id = parse_uuid("id", &id)?;
+    let process = state
+        .processes
+        .lock()
+        .unwrap()
+        .get(id)
+        .ok_or_else(|| CommandError::not_found(format!("process {id}")))?;
+    crate::processes::kill_pid(process.pid).map_err(|e| {
+        let message = format!("could not kill {} (pid {}): {e}", process.label, process.pid);
+        CommandError::invalid(message)
+    })
+}
+
+async fn run_export_job(
+    app: tauri::AppHandle,
+    job_id: Uuid,
+    project_id: Uuid,
+    format: crate::jobs::ExportFormat,
+    scope: ExportScope,
+    dest: PathBuf,
+    cancel: Arc<std::sync::atomic::AtomicBool>,
+) {
+    use crate::jobs::JobStatus;
+    use std::sync::atomic::Ordering;
+    use tauri::Emitter;
+
+    let result = export_to_file(&app, job_id, project_id, format, &scope, &dest, &cancel).await;
+    let (status, error) = match result {
+        Ok(true) => (JobStatus::Completed, None),
+        Ok(false) => (JobStatus::Cancelled, None),
+        Err(e) if cancel.load(Ordering::Relaxed) => (JobStatus::Cancelled, Some(e)),
+        Err(e) => (JobStatus::Failed, Some(e)),
+    };
+    let job = app
+        .state::<AppState>()
+        .jobs
+        .lock()
+        .unwrap()
+        .finish(job_id, status, error);
+    if let Some(job) = job {
+        let _ = app.emit(crate::events::JOB_COMPLETE, &job);
+    }
+}
+
+/// Where an export job writes before renaming onto `dest`: a hidden file
+/// in the same directory, so the rename stays on one filesystem, named
+/// after the job so it is never a file the user already had.
+fn partial_export_path(dest: &std::path::Path, job_id: Uuid) -> PathBuf {
+    let name = dest
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "export".to_string());
+    dest.with_file_name(format!(".{name}.{job_id}.partial"))
+}
+
+/// Load the project and stream it to `dest`. `Ok(false)` means cancelled.
+async fn export_to_file(
+    app: &tauri::AppHandle,
+    job_id: Uuid,
+    project_id: Uuid,
+    format: crate::jobs::ExportFormat,
+    scope: &ExportScope,
+    dest: &std::path::Path,
+    cancel: &Arc<std::sync::atomic::AtomicBool>,
+) -> Result<bool, String> {
+    use std::sync::atomic::Ordering;
+    use tauri::Emitter;
+
+    let store = app.state::<AppState>().store.clone();
+    let (project, model, unrecognized) = load_export_model(&store, project_id, scope, Some(format))
+        .await
+        .map_err(|e| e.to_string())?;
+    let ScopedModel {
+        nodes,
+        edges,
+        properties,
+        ..
+    } = model;
+    if cancel.load(Ordering::Relaxed) {
+        return Ok(false);
+    }
+
+    let app = app.clone();
+    let dest = dest.to_path_buf();
+    let cancel = cancel.clone();
+    // Written beside `dest` and renamed over it once complete, so a failed
+    // or cancelled export leaves a file already at `dest` as it was.
+    let partial = partial_export_path(&dest, job_id);
+    tauri::async_runtime::spawn_blocking(move || -> Result<bool, String> {
+        let write = || -> Result<bool, String> {
+            let file = std::fs::File::create(&partial).map_err(|e| e.to_string())?;
+            let mut out = std::io::BufWriter::new(file);
+
+            // Report roughly every 1% so a 60k-node model doesn't flood the IPC channel.
+            let mut last_reported = None;
+            let mut progress = |done: usize, total: usize| {
+                if cancel.load(Ordering::Relaxed) {
+                    return false;
+                }
+                let step = (total / 100).max(1);
+                let due = match last_reported {
+                    None => true,
+                    Some(last) => done == total || done >= last + step,
+                };
+                if due {
+                    last_reported = Some(done);
+                    let job = app
+                        .state::<AppState>()
+                        .jobs
+                        .lock()
+                        .unwrap()
+                        .set_progress(job_id, done, total);
+                    if let Some(job) = job {
+                        let _ = app.emit(crate::events::JOB_PROGRESS, &job);
+                    }
+                }
+                true
+            };
+
+            let finished = match format {
+                ExportFormat::Json => crate::core::export::write_native_json(
+                    &mut out,
+                    &project,
+                    &nodes,
+                    &edges,
+                    &unrecognized,
+                    &mut progress,
+                ),
+                ExportFormat::Xmi => crate::core::export::write_xmi(
+                    &mut out,
+                    &project,
+                    &nodes,
+                    &edges,
+                    &properties,
+                    &mut progress,
+                ),
+            }
+            .map_err(|e| e.to_string())?;
+            if finished {
+                use std::io::Write;
+                out.flush().map_err(|e| e.to_string())?;
+            }
+            Ok(finished)
+        };
+        match write() {
+            Ok(true) => std::fs::rename(&partial, &dest).map(|()| true).map_err(|e| {
+                let _ = std::fs::remove_file(&partial);
+                e.to_string()
+            }),
+            other => {
+                let _ = std::fs::remove_file(&partial);
+                other
+            }
+        }
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// ── AI availability ───────────────────────────────────────────────────────────
+
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn ai_available(state: State<'_, AppState>) -> Result<bool, String> {
+    Ok(state.ai_provider.lock().unwrap().is_available())
+}
+
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn ai_provider_name(state: State<'_, AppState>) -> Result<String, String> {
+    Ok(state.ai_provider.lock().unwrap().name().to_string())
+}
+
+/// The Ollama settings as written by `set_ollama_config`, with defaults
+/// for unset keys. Read in one query so a concurrent `set_ollama_config`
+/// can't be seen half-applied (new model, old base URL).
+struct OllamaSettings {
+    provider: String,
+    base_url: String,
+    model: String,
+    embed_model: String,
+}
+
+async fn ollama_settings(state: &State<'_, AppState>) -> OllamaSettings {
+    let mut values = state
+        .store
+        .get_settings(
+            &["ai.provider", "ai.ollama.base_url", "ai.ollama.model", "ai.ollama.embed_model"],
+            None,
+        )
+        .await
+        .unwrap_or_default();
+    let mut take = |key: &str, default: &str| {
+        values.remove(key).unwrap_or_else(|| default.to_string())
+    };
+    OllamaSettings {
+        provider: take("ai.provider", ""),
+        base_url: take("ai.ollama.base_url", "http://localhost:11434"),
+        model: take("ai.ollama.model", "qwen2.5:7b"),
+        embed_model: take("ai.ollama.embed_model", "nomic-embed-text"),
+    }
+}
+
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn ollama_status(state: State<'_, AppState>) -> Result<serde_json::Value, String> {
+    use crate::ai::ollama::OllamaProvider;
+
+    let settings = ollama_settings(&state).await;
+    let probe = OllamaProvider::new(&settings.model, Some(settings.base_url.clone()));
+    let reachable = probe.check_available().await;
+
+    Ok(serde_json::json!({
+        "reachable": reachable,
+        "base_url": settings.base_url,
+        "model": settings.model,
+        "is_active": settings.provider == "ollama",
+    }))
+}
+
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn set_ollama_config(
+    model: String,
+    base_url: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    use crate::ai::ollama::OllamaProvider;
+
+    let url = base_url.unwrap_or_else(|| "http://localhost:11434".to_string());
+
+    // One transaction, so readers see the old configuration or the new one.
+    let setting = |key: &str, value: &str| SettingEntry {
+        key: key.to_string(),
+        project_id: None,
+        value: value.to_string(),
+        masked: false,
+    };
+    state
+        .store
+        .set_settings(&[
+            setting("ai.ollama.model", &model),
+            setting("ai.ollama.base_url", &url),
+            setting("ai.provider", "ollama"),
+        ])
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let new_provider: Arc<dyn crate::ai::provider::AIProvider> =
+        Arc::new(OllamaProvider::new(model, Some(url)));
+    *state.ai_provider.lock().unwrap() = new_provider;
+    Ok(())
+}
+
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn set_anthropic_key(key: String, state: State<'_, AppState>) -> Result<(), String> {
+    use crate::ai::anthropic::AnthropicProvider;
+    use crate::ai::provider::NullProvider;
+
+    state
+        .store
+        .set_setting("ai.anthropic.api_key", None, &key)
+        .await
+        .map_err(|e| e.to_string())?;
+    if !key.is_empty() {
+        state
+            .store
+            .set_setting("ai.provider", None, "anthropic")
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    let new_provider: Arc<dyn crate::ai::provider::AIProvider> = if !key.is_empty() {
+        Arc::new(AnthropicProvider::new(key))
+    } else {
+        Arc::new(NullProvider)
+    };
+    *state.ai_provider.lock().unwrap() = new_provider;
+    Ok(())
+}
+
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn set_openai_key(key: String, state: State<'_, AppState>) -> Result<(), String> {
+    use crate::ai::openai::OpenAIProvider;
+    use crate::ai::provider::NullProvider;
+
+    state
+        .store
+        .set_setting("ai.openai.api_key", None, &key)
+        .await
+        .map_err(|e| e.to_string())?;
+    if !key.is_empty() {
+        state
+            .store
+            .set_setting("ai.provider", None, "openai")
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    let new_provider: Arc<dyn crate::ai::provider::AIProvider> = if !key.is_empty() {
+        Arc::new(OpenAIProvider::new(key))
+    } else {
+        Arc::new(NullProvider)
+    };
+    *state.ai_provider.lock().unwrap() = new_provider;
+    Ok(())
+}
+
+// ── Requirement parser ────────────────────────────────────────────────────────
+
+/// Send sentences to req_parser.py via the system Python interpreter.
+/// Tries Miniconda first, then falls back to "python" / "python3" on PATH.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequirementParseBlock {
+    pub text: String,
+    #[serde(default)]
+    pub section_title: String,
+    #[serde(default)]
+    pub section_ref: String,
+    #[serde(default)]
+    pub section_type: String,
+    #[serde(default)]
+    pub line_index: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequirementQualityInput {
+    #[serde(default)]
+    pub id: String,
+    pub sentence: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub confidence: String,
+    #[serde(default)]
+    pub flags: Vec<String>,
+    #[serde(default)]
+    pub classification: String,
+    #[serde(default)]
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequirementQualityOutput {
+    #[serde(default)]
+    pub id: String,
+    pub sentence: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub confidence: String,
+    #[serde(default)]
+    pub classification: String,
+    #[serde(default)]
+    pub flags: Vec<String>,
+    #[serde(default)]
+    pub review_priority: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequirementAllocationInput {
+    #[serde(default)]
+    pub id: String,
+    pub sentence: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub confidence: String,
+    #[serde(default)]
+    pub flags: Vec<String>,
+    #[serde(default)]
+    pub classification: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AllocationSubsystemInput {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequirementAllocationOutput {
+    #[serde(default)]
+    pub id: String,
+    pub sentence: String,
+    pub allocation: String,
+    #[serde(default)]
+    pub confidence: String,
+    #[serde(default)]
+    pub rationale: String,
+    #[serde(default)]
+    pub new_subsystem_name: String,
+}
+
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn parse_requirements(
+    sentences: Option<Vec<String>>,
+    blocks: Option<Vec<RequirementParseBlock>>,
+    doc_type: Option<String>,
+    app: tauri::AppHandle,
+) -> Result<String, String> {
+    let payload = if let Some(blocks) = blocks {
+        serde_json::json!({
+            "blocks": blocks,
+            "sentences": sentences.unwrap_or_default(),
+            "doc_type": doc_type.unwrap_or_default(),
+        })
+    } else {
+        serde_json::json!({
+            "sentences": sentences.unwrap_or_default(),
+            "doc_type": doc_type.unwrap_or_default(),
+        })
+    };
+    let input = serde_json::to_string(&payload).map_err(|e| e.to_string())?;
+
+    // Locate req_parser.py — try several locations in order:
+    //   1. Bundled in app resource dir (production)
+    //   2. CARGO_MANIFEST_DIR-relative (dev, most reliable)
+    //   3. cwd-relative fallback
+    let script_path = {
+        let resource_dir = app
+            .path()
+            .resource_dir()
+            .unwrap_or_else(|_| std::path::PathBuf::from("."));
+        let bundled = resource_dir.join("sidecar").join("req_parser.py");
+
+        // In dev, CARGO_MANIFEST_DIR points to src-tauri/, so go one level up
+        let manifest_dir = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let cargo_relative = manifest_dir
+            .parent()
+            .map(|p| p.join("sidecar").join("req_parser.py"))
+            .unwrap_or_else(|| manifest_dir.join("sidecar").join("req_parser.py"));
+
+        let cwd_relative = std::path::PathBuf::from("sidecar/req_parser.py");
+
+        if bundled.exists() {
+            bundled
+        } else if cargo_relative.exists() {
+            cargo_relative
+        } else if cwd_relative.exists() {
+            cwd_relative
+        } else {
+            return Err(format!(
+                "req_parser.py not found. Looked in: {}, {}, {}",
+                bundled.display(),
+                cargo_relative.display(),
+                cwd_relative.display()
+            ));
+        }
+    };
+
+    // Try Python interpreters in order of preference
+    let candidates = [r"C:\Users\aliso\miniconda3\python.exe", "python", "python3"];
+
+    let processes = &app.state::<AppState>().inner().processes;
+    let mut last_err = String::from("no Python interpreter found");
+    for python in &candidates {
+        match run_python_script(python, &script_path, &input, processes).await {
+            Ok(out) if !out.trim().is_empty() => return Ok(out.trim().to_string()),
+            Ok(_) => {
+                last_err = format!("{python}: produced empty output");
+            }
+            Err(e) => {
+                last_err = format!("{python}: {e}");
+            }
+        }
+    }
+
+    Err(format!("req_parser failed: {last_err}"))
+}
+
+async fn run_python_script(
+    python: &str,
+    script: &std::path::Path,
+    input: &str,
+    processes: &std::sync::Mutex<crate::processes::ProcessRegistry>,
+) -> Result<String, String> {
+    use crate::processes::{self, ProcessKind};
+    use tokio::io::AsyncWriteExt;
+
+    let mut child = tokio::process::Command::new(python)
+        .arg(script)
+        .env("PYTHONIOENCODING", "utf-8")
+        .env("PYTHONUTF8", "1")
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("spawn failed: {e}"))?;
+    let label = script.file_name().unwrap_or_default().to_string_lossy();
+    let _registration = processes::register(processes, ProcessKind::Sidecar, &label, child.id());
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(format!("{input}\n").as_bytes())
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    let out = child.wait_with_output().await.map_err(|e| e.to_string())?;
+
+    let stdout = String::from_utf8_lossy(&out.stdout).to_string();
+    if stdout.trim().is_empty() {
+        let stderr = String::from_utf8_lossy(&out.stderr).to_string();
+        return Err(format!("empty output. stderr: {}", stderr.trim()));
+    }
+
+    Ok(stdout)
+}
+
+// ── Simulation commands ───────────────────────────────────────────────────────
+
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn save_sim_params(
+    node_id: String,
+    params: Option<SimParams>,
+    script: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let uuid = parse_uuid("node_id", &node_id).map_err(|e| e.to_string())?;
+    let node = state
+        .store
+        .get_node(uuid)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "node not found".to_string())?;
+    let updated_data = match node.data {
+        NodeData::Block(mut b) => {
+            b.sim_params = params;
+            b.sim_script = script;
+            NodeData::Block(b)
+        }
+        _ => return Err("node is not a block".to_string()),
+    };
+    let updated = Node {
+        data: updated_data,
+        modified_at: Utc::now(),
+        ..node
+    };
+    state.store.upsert_node(&updated).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn get_sim_params(
+    node_id: String,
+    state: State<'_, AppState>,
+) -> Result<serde_json::Value, String> {
+    let uuid = parse_uuid("node_id", &node_id).map_err(|e| e.to_string())?;
+    let node = state
+        .store
+        .get_node(uuid)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "node not found".to_string())?;
+    match node.data {
+        NodeData::Block(b) => Ok(serde_json::json!({
+            "sim_params": b.sim_params,
+            "sim_script": b.sim_script,
+        })),
+        _ => Ok(serde_json::json!({ "sim_params": null, "sim_script": null })),
+    }
+}
+
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn save_scenario(
+    scenario: SimulationScenario,
+    state: State<'_, AppState>,
+) -> Result<SimulationScenario, String> {
+    state
+        .store
+        .upsert_simulation_scenario(&scenario)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(scenario)
+}
+
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn list_scenarios(
+    project_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<SimulationScenario>, String> {
+    let uuid = parse_uuid("project_id", &project_id).map_err(|e| e.to_string())?;
+    state
+        .store
+        .list_simulation_scenarios(uuid)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Expand periodic or burst `spec` patterns into events and append them to
+/// the scenario, after its existing events; see `core::scenario`. Events
+/// with the same time, block and signal type as one already there are
+/// dropped. With `dry_run` the scenario is left unchanged and the events
+/// are only returned for preview.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn generate_scenario_events(
+    scenario_id: String,
+    spec: Vec<crate::core::scenario::EventPattern>,
+    dry_run: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<crate::core::scenario::EventGeneration, CommandError> {
+    use crate::core::scenario::{self, EventGeneration};
+
+    let id = parse_uuid("scenario_id", &scenario_id)?;
+    let mut scenario = state
+        .store
+        .get_simulation_scenario(id)
+        .await?
+        .ok_or_else(|| CommandError::not_found(format!("scenario {id}")))?;
+    let block_ids: std::collections::HashSet<Uuid> = state
+        .store
+        .list_node_summaries(scenario.project_id, Some(&NodeKind::Block), false)
+        .await?
+        .into_iter()
+        .map(|n| n.id)
+        .collect();
+
+    let generated = scenario::expand(&spec, scenario.duration_ms, &block_ids).map_err(|errors| {
+        let issues = errors
+            .into_iter()
+            .map(|e| FieldIssue {
+                field: Some(format!("spec[{}].{}", e.index, e.field)),
+                code: "invalid".to_string(),
+                message: e.message,
+            })
+            .collect();
+        CommandError::Validation { issues }
+    })?;
+    let (events, duplicates) = scenario::dedup_against(&scenario.events, generated);
+
+    let dry_run = dry_run.unwrap_or(false);
+    if !dry_run && !events.is_empty() {
+        scenario.events.extend(events.iter().cloned());
+        scenario.modified_at = Utc::now();
+        state.store.upsert_simulation_scenario(&scenario).await?;
+    }
+    Ok(EventGeneration { events, duplicates, dry_run })
+}
+
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn run_simulation(
+    scenario_id: String,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<String, String> {
+    let scenario_uuid = parse_uuid("scenario_id", &scenario_id).map_err(|e| e.to_string())?;
+
+    let scenario = state
+        .store
+        .get_simulation_scenario(scenario_uuid)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "scenario not found".to_string())?;
+
+    let nodes = state
+        .store
+        .list_nodes(scenario.project_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let all_edges = state
+        .store
+        .list_edges(scenario.project_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // Build block_behaviors: block_id -> { sim_params, sim_script }
+    let block_behaviors: serde_json::Map<String, serde_json::Value> = nodes
+        .iter()
+        .filter(|n| n.kind == NodeKind::Block)
+        .filter_map(|n| {
+            if let NodeData::Block(ref b) = n.data {
+                if b.sim_params.is_some() || b.sim_script.is_some() {
+                    Some((
+                        n.id.to_string(),
+                        serde_json::json!({
+                            "sim_params": b.sim_params,
+                            "sim_script": b.sim_script,
+                        }),
+                    ))
+                } else {
+                    None
+                }
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    let project = state
+        .store
+        .get_project(scenario.project_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "project not found".to_string())?;
+
+    let project_json_str = crate::core::export::to_native_json(&project, &nodes, &all_edges, &[])
+        .map_err(|e| e.to_string())?;
+    let project_json: serde_json::Value =
+        serde_json::from_str(&project_json_str).unwrap_or_default();
+
+    let input_payload = serde_json::json!({
+        "project_json": project_json,
+        "scenario": {
+            "id": scenario.id,
+            "name": scenario.name,
+            "duration_ms": scenario.duration_ms,
+            "events": scenario.events,
+        },
+        "block_behaviors": block_behaviors,
+    });
+    let input = serde_json::to_string(&input_payload).map_err(|e| e.to_string())?;
+
+    // Create a pending result row
+    let result_id = Uuid::new_v4();
+    let pending_result = SimulationResult {
+        id: result_id,
+        scenario_id: scenario_uuid,
+        ran_at: Utc::now(),
+        status: "running".to_string(),
+        metrics: serde_json::Value::Object(Default::default()),
+        timeline: serde_json::Value::Array(vec![]),
+        errors: serde_json::Value::Array(vec![]),
+    };
+    state
+        .store
+        .insert_simulation_result(&pending_result)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // Scenario events may reference blocks deleted since the scenario was saved.
+    let block_ids: std::collections::HashSet<Uuid> = nodes
+        .iter()
+        .filter(|n| n.kind == NodeKind::Block)
+        .map(|n| n.id)
+        .collect();
+    let mut stale_blocks: Vec<Uuid> = scenario
+        .events
+        .iter()
+        .map(|ev| ev.block_id)
+        .filter(|id| !block_ids.contains(id))
+        .collect();
+    stale_blocks.sort();
+    stale_blocks.dedup();
+    if !stale_blocks.is_empty() {
+        let errors: Vec<String> = stale_blocks
+            .iter()
+            .map(|id| {
+                format!(
+                    "Scenario '{}' has events for block {id}, which no longer exists in the project",
+                    scenario.name
+                )
+            })
+            .collect();
+        state
+            .store
+            .update_simulation_result_status(
+                result_id,
+                "error",
+                serde_json::Value::Object(Default::default()),
+                serde_json::Value::Array(vec![]),
+                serde_json::json!(errors),
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+        return Ok(result_id.to_string());
+    }
+
+    // Refuse to run with miswired signal types — the engine would otherwise
+    // silently drop the mismatched flow and report zero throughput.
+    let wiring_errors: Vec<String> = validation::validate_signal_flow(&nodes, &all_edges)
+        .into_iter()
+        .map(|issue| issue.message)
+        .collect();
+    if !wiring_errors.is_empty() {
+        state
+            .store
+            .update_simulation_result_status(
+                result_id,
+                "error",
+                serde_json::Value::Object(Default::default()),
+                serde_json::Value::Array(vec![]),
+                serde_json::json!(wiring_errors),
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+        return Ok(result_id.to_string());
+    }
+
+    // Resolve simulation_engine.py (same 3-path strategy as req_parser.py)
+    let script_path = {
+        let resource_dir = app
+            .path()
+            .resource_dir()
+            .unwrap_or_else(|_| std::path::PathBuf::from("."));
+        let bundled = resource_dir.join("sidecar").join("simulation_engine.py");
+        let manifest_dir = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let cargo_relative = manifest_dir
+            .parent()
+            .map(|p| p.join("sidecar").join("simulation_engine.py"))
+            .unwrap_or_else(|| manifest_dir.join("sidecar").join("simulation_engine.py"));
+        let cwd_relative = std::path::PathBuf::from("sidecar/simulation_engine.py");
+
+        if bundled.exists() {
+            bundled
+        } else if cargo_relative.exists() {
+            cargo_relative
+        } else if cwd_relative.exists() {
+            cwd_relative
+        } else {
+            state
+                .store
+                .update_simulation_result_status(
+                    result_id,
+                    "error",
+                    serde_json::Value::Object(Default::default()),
+                    serde_json::Value::Array(vec![]),
+                    serde_json::json!(["simulation_engine.py not found"]),
+                )
+                .await
+                .ok();
+            return Ok(result_id.to_string());
+        }
+    };
+
+    let candidates = [r"C:\Users\aliso\miniconda3\python.exe", "python", "python3"];
+    let mut last_err = String::from("no Python interpreter found");
+    let mut engine_output: Option<String> = None;
+
+    for python in &candidates {
+        match run_python_script(python, &script_path, &input, &state.processes).await {
+            Ok(out) if !out.trim().is_empty() => {
+                engine_output = Some(out.trim().to_string());
+                break;
+            }
+            Ok(_) => {
+                last_err = format!("{python}: produced empty output");
+            }
+            Err(e) => {
+                last_err = format!("{python}: {e}");
+            }
+        }
+    }
+
+    match engine_output {
+        Some(out) => match serde_json::from_str::<serde_json::Value>(&out) {
+            Ok(parsed) => {
+                let status =
+                    if parsed.get("status").and_then(|v| v.as_str()) == Some("error") {
+                        "error"
+                    } else {
+                        "complete"
+                    };
+                state
+                    .store
+                    .update_simulation_result_status(
+                        result_id,
+                        status,
+                        parsed.get("metrics").cloned().unwrap_or_default(),
+                        parsed.get("timeline").cloned().unwrap_or_default(),
+                        parsed.get("errors").cloned().unwrap_or_default(),
+                    )
+                    .await
+                    .map_err(|e| e.to_string())?;
+            }
+            Err(e) => {
+                state
+                    .store
+                    .update_simulation_result_status(
+                        result_id,
+                        "error",
+                        serde_json::Value::Object(Default::default()),
+                        serde_json::Value::Array(vec![]),
+                        serde_json::json!([format!("JSON parse error: {e}"), out]),
+                    )
+                    .await
+                    .ok();
+            }
+        },
+        None => {
+            state
+                .store
+                .update_simulation_result_status(
+                    result_id,
+                    "error",
+                    serde_json::Value::Object(Default::default()),
+                    serde_json::Value::Array(vec![]),
+                    serde_json::json!([last_err]),
+                )
+                .await
+                .ok();
+        }
+    }
+
+    Ok(result_id.to_string())
+}
+
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn get_simulation_result(
+    result_id: String,
+    state: State<'_, AppState>,
+) -> Result<SimulationResult, String> {
+    let uuid = parse_uuid("result_id", &result_id).map_err(|e| e.to_string())?;
+    state
+        .store
+        .get_simulation_result(uuid)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "result not found".to_string())
+}
+
+// -- Local LLM (llama.cpp) ---------------------------------------------------
 
 fn resolve_llama_paths(app: &tauri::AppHandle) -> Result<(PathBuf, PathBuf), String> {
     let resource_dir = app
@@ -1207,1070 +4879,3268 @@ fn resolve_llama_paths(app: &tauri::AppHandle) -> Result<(PathBuf, PathBuf), Str
         .resource_dir()
         .unwrap_or_else(|_| PathBuf::from("."));
 
-    let bin = resource_dir.join("llama").join("llama-cli.exe");
-    let model = resource_dir
-        .join("models")
-        .join("Qwen2.5-0.5B-Instruct-Q4_K_M.gguf");
+    let bin = resource_dir.join("llama").join("llama-cli.exe");
+    let model = resource_dir
+        .join("models")
+        .join("Qwen2.5-0.5B-Instruct-Q4_K_M.gguf");
+
+    if bin.exists() && model.exists() {
+        return Ok((bin, model));
+    }
+
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let dev_root = manifest_dir.parent().unwrap_or(&manifest_dir);
+    let dev_bin = dev_root
+        .join("src-tauri")
+        .join("resources")
+        .join("llama")
+        .join("llama-cli.exe");
+    let dev_model = dev_root
+        .join("src-tauri")
+        .join("resources")
+        .join("models")
+        .join("Qwen2.5-0.5B-Instruct-Q4_K_M.gguf");
+
+    if dev_bin.exists() && dev_model.exists() {
+        return Ok((dev_bin, dev_model));
+    }
+
+    Err("llama-cli or model not found in resources".to_string())
+}
+
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn local_llm_available(app: tauri::AppHandle) -> Result<bool, String> {
+    Ok(resolve_llama_paths(&app).is_ok())
+}
+
+/// Result of probing the bundled llama.cpp model. Cached in `AppState`
+/// for the session once `local_llm_info` has run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocalLlmInfo {
+    pub ok: bool,
+    pub bin_path: Option<String>,
+    pub model_path: Option<String>,
+    /// Context window in tokens, as reported by the loader.
+    pub context_length: Option<u32>,
+    /// Weight format, e.g. "Q4_K - Medium".
+    pub quantization: Option<String>,
+    pub load_time_ms: u64,
+    pub error: Option<String>,
+}
+
+/// Load the local model with a one-token prompt to confirm it actually runs
+/// and read its metadata. Cached per session; pass `refresh` to re-probe.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn local_llm_info(
+    refresh: Option<bool>,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<LocalLlmInfo, String> {
+    if !refresh.unwrap_or(false) {
+        if let Some(info) = state.local_llm.lock().unwrap().clone() {
+            return Ok(info);
+        }
+    }
+
+    let info = probe_local_llm(&app).await;
+    *state.local_llm.lock().unwrap() = Some(info.clone());
+    Ok(info)
+}
+
+async fn probe_local_llm(app: &tauri::AppHandle) -> LocalLlmInfo {
+    use std::process::Stdio;
+    use std::time::{Duration, Instant};
+    use tokio::process::Command;
+    use tokio::time::timeout;
+
+    let mut info = LocalLlmInfo {
+        ok: false,
+        bin_path: None,
+        model_path: None,
+        context_length: None,
+        quantization: None,
+        load_time_ms: 0,
+        error: None,
+    };
+
+    let (bin, model) = match resolve_llama_paths(app) {
+        Ok(paths) => paths,
+        Err(e) => {
+            info.error = Some(e);
+            return info;
+        }
+    };
+    info.bin_path = Some(bin.to_string_lossy().to_string());
+    info.model_path = Some(model.to_string_lossy().to_string());
+
+    let started = Instant::now();
+    let child = Command::new(&bin)
+        .arg("-m")
+        .arg(&model)
+        .arg("--n-predict")
+        .arg("1")
+        .arg("--single-turn")
+        .arg("--no-conversation")
+        .arg("--simple-io")
+        .arg("--no-display-prompt")
+        .arg("--prompt")
+        .arg("OK")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn();
+    let child = match child {
+        Ok(child) => child,
+        Err(e) => {
+            info.error = Some(e.to_string());
+            return info;
+        }
+    };
+    let processes = &app.state::<AppState>().inner().processes;
+    let _registration = crate::processes::register(
+        processes,
+        crate::processes::ProcessKind::Llama,
+        "llama-cli probe",
+        child.id(),
+    );
+
+    let output = match timeout(Duration::from_secs(30), child.wait_with_output()).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) => {
+            info.error = Some(e.to_string());
+            return info;
+        }
+        Err(_) => {
+            info.error = Some("model did not load within 30s".to_string());
+            return info;
+        }
+    };
+    info.load_time_ms = started.elapsed().as_millis() as u64;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let (context_length, quantization) = parse_llama_metadata(&stderr);
+    info.context_length = context_length;
+    info.quantization = quantization.or_else(|| quantization_from_filename(&model));
+
+    if output.status.success() {
+        info.ok = true;
+    } else {
+        let tail: Vec<&str> = stderr.lines().rev().take(3).collect();
+        info.error = Some(format!(
+            "llama-cli exited with {}: {}",
+            output.status,
+            tail.into_iter().rev().collect::<Vec<_>>().join(" | ")
+        ));
+    }
+    info
+}
+
+/// Pull (context length, quantization) out of llama.cpp's load log.
+/// The runtime `n_ctx` wins over the trained context since that is what
+/// the prompt actually has to fit into.
+fn parse_llama_metadata(stderr: &str) -> (Option<u32>, Option<String>) {
+    let value_after = |line: &str, key: &str| -> Option<String> {
+        let rest = &line[line.find(key)? + key.len()..];
+        let rest = rest.trim_start().strip_prefix('=')?.trim();
+        Some(rest.to_string())
+    };
+    let leading_number = |s: &str| -> Option<u32> {
+        s.split(|c: char| !c.is_ascii_digit()).next()?.parse().ok()
+    };
+
+    let mut n_ctx = None;
+    let mut n_ctx_train = None;
+    let mut quantization = None;
+    for line in stderr.lines() {
+        if let Some(v) = value_after(line, "n_ctx_train") {
+            n_ctx_train = n_ctx_train.or(leading_number(&v));
+        } else if let Some(v) = value_after(line, "n_ctx") {
+            n_ctx = n_ctx.or(leading_number(&v));
+        } else if line.contains(".context_length") {
+            // llama_model_loader: - kv 4: qwen2.context_length u32 = 32768
+            if let Some(v) = line.rsplit('=').next() {
+                n_ctx_train = n_ctx_train.or(leading_number(v.trim()));
+            }
+        }
+        if quantization.is_none() {
+            quantization = value_after(line, "file type").or_else(|| value_after(line, "model ftype"));
+        }
+    }
+    (n_ctx.or(n_ctx_train), quantization)
+}
+
+fn quantization_from_filename(model: &std::path::Path) -> Option<String> {
+    let stem = model.file_stem()?.to_string_lossy().to_string();
+    stem.rsplit(['-', '.'])
+        .find(|part| {
+            let upper = part.to_ascii_uppercase();
+            (upper.starts_with('Q') || upper.starts_with("IQ"))
+                && upper.chars().any(|c| c.is_ascii_digit())
+        })
+        .map(|part| part.to_string())
+}
+
+/// How many characters of document text fit in one llama.cpp prompt.
+/// Reserves room for the instructions and the generated JSON, assuming
+/// ~3 characters per token. Falls back to 8000 before the model is probed.
+fn llm_prompt_char_budget(context_length: Option<u32>) -> usize {
+    const RESERVED_TOKENS: usize = 384 + 400;
+    match context_length {
+        Some(ctx) if ctx as usize > RESERVED_TOKENS * 2 => (ctx as usize - RESERVED_TOKENS) * 3,
+        _ => 8000,
+    }
+}
+
+fn extract_json_array(raw: &str) -> Option<String> {
+    let mut start: Option<usize> = None;
+    let mut depth: i32 = 0;
+    let mut in_string = false;
+    let mut escape = false;
+
+    for (i, ch) in raw.char_indices() {
+        if start.is_none() {
+            if ch == '[' {
+                start = Some(i);
+                depth = 1;
+            }
+            continue;
+        }
+
+        if in_string {
+            if escape {
+                escape = false;
+            } else if ch == '\\' {
+                escape = true;
+            } else if ch == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+
+        match ch {
+            '"' => in_string = true,
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    let start_idx = start?;
+                    return Some(raw[start_idx..=i].to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+fn extract_json_object(raw: &str) -> Option<String> {
+    let trimmed = raw.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+
+    let cleaned = if trimmed.starts_with("```") {
+        trimmed
+            .lines()
+            .skip(1)
+            .take_while(|line| !line.starts_with("```"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    } else {
+        trimmed.to_string()
+    };
+
+    if serde_json::from_str::<serde_json::Value>(&cleaned).is_ok() {
+        return Some(cleaned);
+    }
+
+    let start = cleaned.find('{')?;
+    let end = cleaned.rfind('}')?;
+    if end > start {
+        Some(cleaned[start..=end].to_string())
+    } else {
+        None
+    }
+}
+
+/// The project's classification taxonomy, falling back to the global
+/// setting and then to the built-in set.
+async fn classification_taxonomy(
+    state: &State<'_, AppState>,
+    project_id: Option<Uuid>,
+) -> Result<Vec<classification::ClassificationLabel>, String> {
+    let raw = match project_id {
+        Some(pid) => project_or_global_setting(state, classification::TAXONOMY_SETTING, pid).await,
+        None => state
+            .store
+            .get_setting(classification::TAXONOMY_SETTING, None)
+            .await
+            .map_err(|e| e.to_string())?
+            .filter(|v| !v.trim().is_empty()),
+    };
+    match raw {
+        Some(raw) => classification::parse_taxonomy(&raw),
+        None => Ok(classification::default_taxonomy()),
+    }
+}
+
+fn requirement_needs_quality_review(item: &RequirementQualityInput) -> bool {
+    let confidence = item.confidence.trim().to_lowercase();
+    let classification = item.classification.trim().to_lowercase();
+    let name_words = item.name.split_whitespace().count();
+    let sentence_len = item.sentence.chars().count();
+
+    if confidence != "high" {
+        return true;
+    }
+    if classification.is_empty() || classification == "unknown" {
+        return true;
+    }
+    if name_words < 3 {
+        return true;
+    }
+    if sentence_len > 260 {
+        return true;
+    }
+    item.flags.iter().any(|f| {
+        let f = f.to_lowercase();
+        f.contains("compound")
+            || f.contains("hedge")
+            || f.contains("ambig")
+            || f.contains("implicit_constraint")
+    })
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct LlmExtractProgress {
+    chunk: usize,
+    total: usize,
+    extracted: usize,
+    done: bool,
+}
+
+/// Extract requirements with the bundled llama.cpp model. Long documents
+/// are split into sentence-aligned chunks sized to the model's context
+/// (override with the `llm.chunk_chars` setting) and run one at a time.
+///
+/// Returns a JSON array by default; with `detailed` the array is wrapped as
+/// `{ "results": [...], "chunks": { ... } }`.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn llm_extract_requirements(
+    text: String,
+    detailed: Option<bool>,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    use tauri::Emitter;
+
+    let (bin, model) = resolve_llama_paths(&app)?;
+
+    let context_length = state
+        .local_llm
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|info| info.context_length);
+    let budget = llm_prompt_char_budget(context_length);
+    let chunk_chars = state
+        .store
+        .get_setting("llm.chunk_chars", None)
+        .await
+        .ok()
+        .flatten()
+        .and_then(|v| v.trim().trim_matches('"').parse::<usize>().ok())
+        .map(|v| v.clamp(500, budget))
+        .unwrap_or(budget);
+
+    let chunks = chunk_text_by_sentences(&text, chunk_chars, (chunk_chars / 10).min(400));
+    let total = chunks.len();
+    let mut merged = SentenceMerge::default();
+    let mut errors: Vec<String> = Vec::new();
+
+    for (i, chunk) in chunks.iter().enumerate() {
+        let _ = app.emit(
+            crate::events::LLM_EXTRACT_PROGRESS,
+            LlmExtractProgress { chunk: i + 1, total, extracted: merged.items.len(), done: false },
+        );
+
+        let part = (total > 1).then(|| format!("part {}/{}", i + 1, total));
+        let extraction =
+            run_llama_extraction(&bin, &model, chunk, part.as_deref(), &state.processes).await;
+        match extraction {
+            Ok(items) => merged.extend(items),
+            Err(e) => errors.push(format!("chunk {}: {e}", i + 1)),
+        }
+
+        let _ = app.emit(
+            crate::events::LLM_EXTRACT_PROGRESS,
+            LlmExtractProgress { chunk: i + 1, total, extracted: merged.items.len(), done: true },
+        );
+    }
+
+    if errors.len() == total {
+        return Err(errors.join("; "));
+    }
+
+    let results = serde_json::Value::Array(merged.items);
+    if detailed.unwrap_or(false) {
+        Ok(serde_json::json!({
+            "results": results,
+            "chunks": {
+                "total": total,
+                "succeeded": total - errors.len(),
+                "failed": errors.len(),
+                "chunk_chars": chunk_chars,
+                "context_length": context_length,
+                "errors": errors,
+            },
+        })
+        .to_string())
+    } else {
+        Ok(results.to_string())
+    }
+}
+
+/// Run llama-cli once over `text` and parse the JSON array it prints.
+async fn run_llama_extraction(
+    bin: &std::path::Path,
+    model: &std::path::Path,
+    text: &str,
+    part: Option<&str>,
+    processes: &std::sync::Mutex<crate::processes::ProcessRegistry>,
+) -> Result<Vec<serde_json::Value>, String> {
+    use crate::processes::{self, ProcessKind};
+    use std::process::Stdio;
+    use std::time::Duration;
+    use tokio::io::AsyncReadExt;
+    use tokio::process::Command;
+    use tokio::time::timeout;
+
+    let heading = match part {
+        Some(part) => format!("Document ({part}):"),
+        None => "Document:".to_string(),
+    };
+    let prompt = format!(
+        "You are a requirements extraction engine. Extract requirement statements from the document.\n\
+Return ONLY a JSON array. Each item: {{\"sentence\":\"<verbatim text>\",\"name\":\"<specific 3-7 word name derived from the requirement subject and constraint — never generic like System Requirement or Data Requirement>\",\"confidence\":\"high|medium|low\",\"flags\":[\"...\"]}}.\n\
+Name rules: derive from the actual subject + measurement/constraint in each sentence. Example good names: \"RF Link Margin 6 dB Min\", \"Boot Time Under 10s\", \"AES-256 Data Encryption\".\n\
+Keep sentences verbatim; use the source wording exactly.\n\
+{heading}\n---\n{}\n---\nJSON:",
+        text
+    );
+
+    let mut child = Command::new(bin)
+        .arg("-m")
+        .arg(model)
+        .arg("--temp")
+        .arg("0.2")
+        .arg("--top-p")
+        .arg("0.9")
+        .arg("--repeat-penalty")
+        .arg("1.1")
+        .arg("--n-predict")
+        .arg("384")
+        .arg("--single-turn")
+        .arg("--no-conversation")
+        .arg("--simple-io")
+        .arg("--no-display-prompt")
+        .arg("--color")
+        .arg("off")
+        .arg("--log-colors")
+        .arg("off")
+        .arg("--prompt")
+        .arg(prompt)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    let label = match part {
+        Some(part) => format!("llama-cli extraction ({part})"),
+        None => "llama-cli extraction".to_string(),
+    };
+    let _registration = processes::register(processes, ProcessKind::Llama, &label, child.id());
+
+    match timeout(Duration::from_secs(90), child.wait()).await {
+        Ok(res) => {
+            res.map_err(|e| e.to_string())?;
+        }
+        Err(_) => {
+            let _ = child.start_kill();
+            return Err("LLM timed out after 90s".to_string());
+        }
+    }
+
+    let mut stdout = String::new();
+    if let Some(mut out) = child.stdout.take() {
+        let mut buf = Vec::new();
+        let _ = out.read_to_end(&mut buf).await;
+        stdout = String::from_utf8_lossy(&buf).to_string();
+    }
+
+    let mut stderr = String::new();
+    if let Some(mut err) = child.stderr.take() {
+        let mut buf = Vec::new();
+        let _ = err.read_to_end(&mut buf).await;
+        stderr = String::from_utf8_lossy(&buf).to_string();
+    }
+
+    if let Some(json) = extract_json_array(&stdout) {
+        if let Ok(items) = serde_json::from_str::<Vec<serde_json::Value>>(&json) {
+            return Ok(items);
+        }
+    }
+
+    Err(format!(
+        "LLM output did not contain JSON. stdout: {}, stderr: {}",
+        stdout.chars().take(200).collect::<String>(),
+        stderr.chars().take(200).collect::<String>()
+    ))
+}
+
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn ai_quality_pass_requirements(
+    requirements: Vec<RequirementQualityInput>,
+    doc_type: Option<String>,
+    doc_name: Option<String>,
+    project_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let provider = state.ai_provider.lock().unwrap().clone();
+    if !provider.is_available() {
+        return Err("no_api_key".to_string());
+    }
+
+    let pid = project_id
+        .map(|id| parse_uuid("project_id", &id))
+        .transpose()
+        .map_err(|e| e.to_string())?;
+    let taxonomy = classification_taxonomy(&state, pid).await?;
+
+    if requirements.is_empty() {
+        return Ok(serde_json::json!({ "results": [] }).to_string());
+    }
+
+    let mut candidates: Vec<RequirementQualityInput> = requirements
+        .iter()
+        .filter(|item| requirement_needs_quality_review(item))
+        .cloned()
+        .collect();
+    if candidates.is_empty() {
+        candidates = requirements.iter().take(20).cloned().collect();
+    } else {
+        candidates.truncate(40);
+    }
+
+    let dtype = doc_type.unwrap_or_else(|| "General".to_string());
+    let dname = doc_name.unwrap_or_else(|| "document".to_string());
+    let payload = serde_json::to_string_pretty(&candidates).map_err(|e| e.to_string())?;
+
+    let prompt = Prompt {
+        system: Some(format!(
+            "You are a systems engineering requirement quality reviewer applying IEEE 29148.\n\
+Do NOT rewrite or paraphrase the requirement sentence — only improve the short name field.\n\
+\n\
+NAME RULES (most important):\n\
+- The name must uniquely identify WHAT the requirement is about — never use generic filler.\n\
+- Derive the name from the actual subject + constraint/action in the sentence.\n\
+- Format: \"<Subject> <Constraint/Property/Action>\" in Title Case, 3-7 words.\n\
+- Bad names (reject these patterns): \"System Requirement\", \"Performance Requirement\", \"Data Requirement\", \"Interface Requirement\", \"Security Requirement\", \"High Requirement\", \"Network Requirement\", or any name that could apply to dozens of requirements.\n\
+- Good examples: \"Uplink Data Rate 100 Mbps\", \"Battery Reserve 72 Hour Minimum\", \"GPS Fix Acquisition Under 30s\", \"AES-256 Payload Encryption\", \"Operator Alert Latency Under 2s\".\n\
+- If the current name is already specific and accurate, keep it unchanged.\n\
+\n\
+QUALITY FLAGS (choose all that apply): ambiguous, compound_shall, missing_measurement, missing_verification_method, hedge_word, passive_voice, implicit_subject, testable, performance, interface, safety, security.\n\
+\n\
+{classes}\
+\n\
+Return ONLY this JSON object — no markdown, no explanation:\n\
+{{\"results\":[{{\"id\":\"...\",\"sentence\":\"...\",\"name\":\"<specific descriptive name>\",\
+\"confidence\":\"high|medium|low\",\"classification\":\"{choices}\",\
+\"flags\":[\"...\"],\"review_priority\":\"high|medium|low\"}}]}}",
+            classes = classification::prompt_section(&taxonomy),
+            choices = classification::label_choices(&taxonomy),
+        )),
+        messages: vec![Message {
+            role: Role::User,
+            content: format!(
+                "Document: \"{dname}\" (type: {dtype})\n\
+Review these extracted requirements. For each, produce a specific descriptive name derived \
+from the actual subject and constraint in that requirement sentence. Return the JSON object exactly.\n\n\
+{payload}"
+            ),
+        }],
+        max_tokens: Some(2048),
+        cache_system: true,
+    };
+
+    let response = complete_logged(&state.store, provider.as_ref(), prompt, "quality_pass").await?;
+    let raw = response.content.trim().to_string();
+    let raw_json = extract_json_object(&raw).ok_or_else(|| {
+        format!(
+            "AI quality pass did not return JSON object. output: {}",
+            raw.chars().take(220).collect::<String>()
+        )
+    })?;
+
+    let parsed: serde_json::Value =
+        serde_json::from_str(&raw_json).map_err(|e| format!("Invalid JSON: {e}"))?;
+    let mut out: Vec<RequirementQualityOutput> = Vec::new();
+
+    if let Some(items) = parsed["results"].as_array() {
+        for item in items {
+            let sentence = item["sentence"].as_str().unwrap_or("").trim().to_string();
+            if sentence.is_empty() {
+                continue;
+            }
+            let confidence = match item["confidence"]
+                .as_str()
+                .unwrap_or("")
+                .to_lowercase()
+                .as_str()
+            {
+                "high" | "medium" | "low" => item["confidence"].as_str().unwrap_or("").to_string(),
+                _ => "medium".to_string(),
+            };
+            let classification = classification::normalize(
+                item["classification"].as_str().unwrap_or(""),
+                &taxonomy,
+            );
+            let review_priority = match item["review_priority"]
+                .as_str()
+                .unwrap_or("")
+                .to_lowercase()
+                .as_str()
+            {
+                "high" | "medium" | "low" => {
+                    item["review_priority"].as_str().unwrap_or("").to_string()
+                }
+                _ => "medium".to_string(),
+            };
+            let flags = item["flags"]
+                .as_array()
+                .map(|arr| {
+                    arr.iter()
+                        .filter_map(|v| v.as_str().map(|s| s.trim().to_string()))
+                        .filter(|s| !s.is_empty())
+                        .take(12)
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+
+            out.push(RequirementQualityOutput {
+                id: item["id"].as_str().unwrap_or("").trim().to_string(),
+                sentence,
+                name: item["name"].as_str().unwrap_or("").trim().to_string(),
+                confidence,
+                classification,
+                flags,
+                review_priority,
+            });
+        }
+    }
+
+    let output = serde_json::json!({ "results": out });
+    Ok(output.to_string())
+}
+
+/// Ask the AI provider how to split a compound requirement into atomic
+/// ones. Returns the proposed part texts for the user to edit before
+/// calling `split_requirement`.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn ai_propose_requirement_split(
+    node_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, String> {
+    let provider = state.ai_provider.lock().unwrap().clone();
+    if !provider.is_available() {
+        return Err("no_api_key".to_string());
+    }
+
+    let id = parse_uuid("node_id", &node_id).map_err(|e| e.to_string())?;
+    let node = state
+        .store
+        .get_node(id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("node {id} not found"))?;
+    let text = match &node.data {
+        NodeData::Requirement(r) => r.text.clone().filter(|t| !t.trim().is_empty()),
+        _ => return Err(format!("'{}' is not a requirement", node.name)),
+    }
+    .unwrap_or_else(|| node.description.clone());
+
+    let prompt = Prompt {
+        system: Some(
+            "You are a systems engineering requirement reviewer applying IEEE 29148.\n\
+Split the given compound requirement into atomic requirements: one obligation each, \
+each a complete \"shall\" sentence that names its subject and keeps every measurement, \
+condition and constraint from the original. Do not add obligations that are not in the \
+original. If it is already atomic, return it unchanged as the only part.\n\
+\n\
+Return ONLY this JSON object — no markdown, no explanation:\n\
+{\"parts\":[\"...\",\"...\"]}"
+                .to_string(),
+        ),
+        messages: vec![Message {
+            role: Role::User,
+            content: format!("Requirement \"{}\":\n{}", node.name, text.trim()),
+        }],
+        max_tokens: Some(1024),
+        cache_system: true,
+    };
+
+    let response = complete_logged(&state.store, provider.as_ref(), prompt, "split").await?;
+    let raw = response.content.trim().to_string();
+    let raw_json = extract_json_object(&raw).ok_or_else(|| {
+        format!(
+            "AI split proposal did not return JSON object. output: {}",
+            raw.chars().take(220).collect::<String>()
+        )
+    })?;
+    let parsed: serde_json::Value =
+        serde_json::from_str(&raw_json).map_err(|e| format!("Invalid JSON: {e}"))?;
+    Ok(parsed["parts"]
+        .as_array()
+        .map(|parts| {
+            parts
+                .iter()
+                .filter_map(|p| p.as_str().map(|s| s.trim().to_string()))
+                .filter(|s| !s.is_empty())
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn ai_suggest_requirement_allocations(
+    requirements: Vec<RequirementAllocationInput>,
+    subsystems: Vec<AllocationSubsystemInput>,
+    doc_type: Option<String>,
+    doc_name: Option<String>,
+    project_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let provider = state.ai_provider.lock().unwrap().clone();
+    if !provider.is_available() {
+        return Err("no_api_key".to_string());
+    }
+
+    if requirements.is_empty() {
+        return Ok(serde_json::json!({ "results": [] }).to_string());
+    }
+
+    let pid = project_id
+        .filter(|p| !p.trim().is_empty())
+        .map(|p| parse_uuid("project_id", &p))
+        .transpose()
+        .map_err(|e| e.to_string())?;
+    let aliases = block_aliases(&state, pid).await.map_err(|e| e.to_string())?;
+
+    let dtype = doc_type.unwrap_or_else(|| "General".to_string());
+    let dname = doc_name.unwrap_or_else(|| "document".to_string());
+
+    let mut candidates = requirements;
+    candidates.truncate(120);
+
+    let mut subsystem_list = subsystems;
+    subsystem_list.truncate(40);
+
+    let payload = serde_json::to_string_pretty(&candidates).map_err(|e| e.to_string())?;
+    let subsystem_payload =
+        serde_json::to_string_pretty(&subsystem_list).map_err(|e| e.to_string())?;
+
+    let prompt = Prompt {
+        system: Some(
+            "You are a systems engineer allocating requirements to physical or domain subsystems \
+in a Model-Based Systems Engineering (MBSE) architecture.\n\
+\n\
+SUBSYSTEM DEFINITION — CRITICAL:\n\
+Subsystems are physical hardware units, major domain components, or top-level engineering \
+disciplines. They are NOT software functions, features, or use-cases.\n\
+\n\
+Good subsystem examples (physical/domain level):\n\
+  FPGA, Microprocessor, Microcontroller, Power Distribution, Onboard Computer,\n\
+  Communication Module, RF Subsystem, GPS Receiver, Inertial Measurement Unit,\n\
+  Sensor Array, Propulsion System, Thermal Management, Battery Pack,\n\
+  Flight Controller, Motor Driver, Payload Interface, Data Storage,\n\
+  Ground Control Station, User Interface Terminal, Network Switch,\n\
+  Hydraulic Actuator, Structural Frame, Navigation System.\n\
+\n\
+Bad subsystem examples (these are software functions — NEVER suggest these):\n\
+  display_search_results, lock_account, notify_emergency, user_authentication,\n\
+  error_handling, login_module, alert_driver, payment_processing.\n\
+\n\
+ALLOCATION RULES:\n\
+1. Choose ONE allocation from the provided subsystem list, OR 'System Level'.\n\
+2. Use 'System Level' for cross-cutting, contractual, or project-wide requirements.\n\
+3. If no listed subsystem fits but the requirement is clearly subsystem-specific,\n\
+   keep allocation as 'System Level' AND set new_subsystem_name to a concise \n\
+   physical/domain subsystem name (e.g. 'Flight Controller', 'Power Distribution Unit').\n\
+4. NEVER set new_subsystem_name to a software function or feature name.\n\
+5. Abbreviations and their expansions name the SAME subsystem (see the alias list). \
+Never propose a new_subsystem_name that is an abbreviation, expansion or variant \
+(e.g. with 'Unit', 'Module' or 'Subsystem' added) of a listed subsystem — allocate to \
+the listed one instead.\n\
+\n\
+Return ONLY a JSON object:\n\
+{\"results\":[{\"id\":\"...\",\"sentence\":\"...\",\"allocation\":\"System Level|<exact subsystem name>\",\
+\"confidence\":\"high|medium|low\",\"rationale\":\"...\",\"new_subsystem_name\":\"optional\"}]}"
+                .to_string(),
+        ),
+        messages: vec![Message {
+            role: Role::User,
+            content: format!(
+                "Document: \"{dname}\" (type: {dtype})\n\
+Subsystems (use exact names when allocating):\n{subsystem_payload}\n\n\
+Aliases (abbreviation = expansion):\n{}\n\n\
+Requirements to allocate:\n{payload}",
+                crate::core::duplicates::aliases_prompt(&aliases)
+            ),
+        }],
+        max_tokens: Some(3072),
+        cache_system: true,
+    };
+
+    let response =
+        complete_logged(&state.store, provider.as_ref(), prompt, "allocation_pass").await?;
+    let raw = response.content.trim().to_string();
+    let raw_json = extract_json_object(&raw).ok_or_else(|| {
+        format!(
+            "AI allocation pass did not return JSON object. output: {}",
+            raw.chars().take(220).collect::<String>()
+        )
+    })?;
+
+    let parsed: serde_json::Value =
+        serde_json::from_str(&raw_json).map_err(|e| format!("Invalid JSON: {e}"))?;
+
+    let subsystem_lookup = subsystem_list
+        .iter()
+        .map(|s| (s.name.trim().to_lowercase(), s.name.trim().to_string()))
+        .collect::<std::collections::HashMap<_, _>>();
+
+    let mut out: Vec<RequirementAllocationOutput> = Vec::new();
+    if let Some(items) = parsed["results"].as_array() {
+        for item in items {
+            let sentence = item["sentence"].as_str().unwrap_or("").trim().to_string();
+            if sentence.is_empty() {
+                continue;
+            }
+
+            let confidence = match item["confidence"]
+                .as_str()
+                .unwrap_or("")
+                .to_lowercase()
+                .as_str()
+            {
+                "high" | "medium" | "low" => item["confidence"].as_str().unwrap_or("").to_string(),
+                _ => "medium".to_string(),
+            };
+
+            let allocation_raw = item["allocation"].as_str().unwrap_or("").trim();
+            let allocation_norm = allocation_raw.to_lowercase();
+            let mut allocation = if allocation_norm.is_empty()
+                || allocation_norm == "system"
+                || allocation_norm == "system-level"
+                || allocation_norm == "system level"
+            {
+                "System Level".to_string()
+            } else if let Some(exact) = subsystem_lookup.get(&allocation_norm) {
+                exact.clone()
+            } else {
+                "System Level".to_string()
+            };
+
+            let mut new_subsystem_name = item["new_subsystem_name"]
+                .as_str()
+                .unwrap_or("")
+                .trim()
+                .replace('\n', " ");
+            if new_subsystem_name.len() > 64 {
+                new_subsystem_name = new_subsystem_name.chars().take(64).collect();
+            }
+            if new_subsystem_name.len() < 3 {
+                new_subsystem_name.clear();
+            }
+            // A "new" subsystem that is a variant of a listed one is that one.
+            if !new_subsystem_name.is_empty() {
+                if let Some(existing) = crate::core::duplicates::matching_name(
+                    &new_subsystem_name,
+                    subsystem_list.iter().map(|s| s.name.trim()),
+                    &aliases,
+                    crate::core::duplicates::DEFAULT_THRESHOLD,
+                ) {
+                    if allocation == "System Level" {
+                        allocation = existing.to_string();
+                    }
+                    new_subsystem_name.clear();
+                }
+            }
+
+            out.push(RequirementAllocationOutput {
+                id: item["id"].as_str().unwrap_or("").trim().to_string(),
+                sentence,
+                allocation,
+                confidence,
+                rationale: item["rationale"].as_str().unwrap_or("").trim().to_string(),
+                new_subsystem_name,
+            });
+        }
+    }
+
+    let output = serde_json::json!({ "results": out });
+    Ok(output.to_string())
+}
+
+/// The block alias map: built-ins overlaid with `blocks.aliases`, read for
+/// the project when given, else globally.
+async fn block_aliases(
+    state: &State<'_, AppState>,
+    project_id: Option<Uuid>,
+) -> Result<crate::core::duplicates::AliasMap, CommandError> {
+    use crate::core::duplicates::{aliases_from_setting, ALIASES_SETTING};
+    let raw = match project_id {
+        Some(pid) => project_or_global_setting(state, ALIASES_SETTING, pid).await?,
+        None => state.store.get_setting(ALIASES_SETTING, None).await?,
+    };
+    aliases_from_setting(raw.as_deref()).map_err(CommandError::invalid)
+}
+
+/// Pairs of blocks whose names look like the same thing once abbreviations
+/// are expanded, best first. `threshold` defaults to 0.8.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn find_duplicate_blocks(
+    project_id: String,
+    threshold: Option<f64>,
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::core::duplicates::DuplicatePair>, CommandError> {
+    use crate::core::duplicates::{find_duplicates, DEFAULT_THRESHOLD};
+    let pid = parse_uuid("project_id", &project_id)?;
+    let threshold = threshold.unwrap_or(DEFAULT_THRESHOLD);
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err(CommandError::invalid("threshold must be between 0 and 1"));
+    }
+    let aliases = block_aliases(&state, Some(pid)).await?;
+    let blocks = state.store.list_nodes_by_kind(pid, &NodeKind::Block).await?;
+    Ok(find_duplicates(&blocks, &aliases, threshold))
+}
+
+/// Fold the blocks in `remove_ids` into `keep_id` and delete them; see
+/// `Store::merge_blocks` for what moves.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn merge_blocks(
+    keep_id: String,
+    remove_ids: Vec<String>,
+    actor: Option<String>,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<BlockMergeReport, CommandError> {
+    let keep_id = parse_uuid("keep_id", &keep_id)?;
+    let keep = state
+        .store
+        .get_node(keep_id)
+        .await?
+        .ok_or_else(|| CommandError::not_found(format!("block {keep_id}")))?;
+    if keep.kind != NodeKind::Block {
+        return Err(CommandError::invalid(format!("'{}' is not a block", keep.name)));
+    }
+    let mut removed: Vec<Node> = Vec::new();
+    for raw in &remove_ids {
+        let id = parse_uuid("remove_ids", raw)?;
+        if id == keep_id || removed.iter().any(|n| n.id == id) {
+            continue;
+        }
+        let node = state
+            .store
+            .get_node(id)
+            .await?
+            .ok_or_else(|| CommandError::not_found(format!("block {id}")))?;
+        if node.kind != NodeKind::Block {
+            return Err(CommandError::invalid(format!("'{}' is not a block", node.name)));
+        }
+        if node.project_id != keep.project_id {
+            return Err(CommandError::invalid(format!(
+                "'{}' belongs to a different project",
+                node.name
+            )));
+        }
+        removed.push(node);
+    }
+    if removed.is_empty() {
+        return Err(CommandError::invalid("no blocks to merge"));
+    }
+
+    let write = state.store.begin_heavy_write("merging blocks", Some(USER_HEAVY_WRITE_WAIT)).await?;
+    let actor = actor_or_user(actor);
+    // Edges of the removed blocks move or go; note them for revalidation.
+    let mut touched = vec![keep_id];
+    for node in &removed {
+        touched.push(node.id);
+        let incident = state.store.edges_for_node(node.id).await?;
+        touched.extend(incident.iter().map(|e| e.id));
+        touched.extend(incident.iter().flat_map(connects_endpoints));
+    }
+    let report = state.store.merge_blocks(&keep, &removed, &actor).await?;
+    touched.extend(report.requirements_reallocated.iter().copied());
+    drop(write);
+    let _ = revalidate_touched(&app, &state, keep.project_id, touched).await;
+    Ok(report)
+}
+
+// -- AI requirement extraction (Claude / Anthropic) --------------------------
 
-    if bin.exists() && model.exists() {
-        return Ok((bin, model));
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn ai_extract_requirements(
+    text: String,
+    doc_type: Option<String>,
+    doc_name: Option<String>,
+    project_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let provider = state.ai_provider.lock().unwrap().clone();
+    if !provider.is_available() {
+        return Err("no_api_key".to_string());
     }
 
-    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-    let dev_root = manifest_dir.parent().unwrap_or(&manifest_dir);
-    let dev_bin = dev_root
-        .join("src-tauri")
-        .join("resources")
-        .join("llama")
-        .join("llama-cli.exe");
-    let dev_model = dev_root
-        .join("src-tauri")
-        .join("resources")
-        .join("models")
-        .join("Qwen2.5-0.5B-Instruct-Q4_K_M.gguf");
+    let pid = project_id
+        .filter(|p| !p.trim().is_empty())
+        .map(|p| parse_uuid("project_id", &p))
+        .transpose()
+        .map_err(|e| e.to_string())?;
+    let doc_label = doc_name.unwrap_or_else(|| "document".to_string());
+    let dtype = doc_type.unwrap_or_else(|| "General".to_string());
+    let is_local = provider.name() == "ollama";
+    let merge_lists = merge_list_continuations_enabled(&state).await;
+
+    let cache_key = crate::core::extraction::cache_key(
+        &text,
+        &dtype,
+        provider.name(),
+        provider.model(),
+        if merge_lists { "merge_lists" } else { "" },
+    );
+    if let Some(cached) = cached_extraction(&state, pid, &cache_key).await {
+        let results = cached["results"].as_array().cloned().unwrap_or_default();
+        let gate = extraction_gate(&state).await;
+        return Ok(gated_extraction_output(results, &gate));
+    }
 
-    if dev_bin.exists() && dev_model.exists() {
-        return Ok((dev_bin, dev_model));
+    let extraction = if is_local {
+        run_chunked_local_extraction(
+            &state.store,
+            provider.clone(),
+            &text,
+            &doc_label,
+            &dtype,
+            None,
+            merge_lists,
+        )
+        .await
+    } else {
+        let trimmed: String = text.chars().take(60_000).collect();
+        run_single_extraction(
+            &state.store,
+            provider.clone(),
+            &trimmed,
+            &doc_label,
+            &dtype,
+            false,
+            None,
+        )
+        .await
+        .map(|items| ChunkedExtraction {
+            items: if merge_lists {
+                crate::core::extraction::merge_list_continuations(items)
+            } else {
+                items
+            },
+            failed_chunks: Vec::new(),
+        })?
+    };
+
+    if extraction.failed_chunks.is_empty() {
+        let cached = serde_json::json!({ "results": extraction.items });
+        cache_extraction(&state, pid, &cache_key, &cached).await;
+    }
+    let gate = extraction_gate(&state).await;
+    let mut output = gated_extraction_value(extraction.items, &gate);
+    with_failed_chunks(&mut output, extraction.failed_chunks);
+    Ok(output.to_string())
+}
+
+/// Report the chunks a partial extraction lost as `failed_chunks`.
+fn with_failed_chunks(output: &mut serde_json::Value, failed_chunks: Vec<String>) {
+    if !failed_chunks.is_empty() {
+        output["failed_chunks"] = serde_json::json!(failed_chunks);
     }
+}
 
-    Err("llama-cli or model not found in resources".to_string())
+/// The raw results a previous extraction stored under `cache_key`, before
+/// the gate; nothing without a project to look in.
+async fn cached_extraction(
+    state: &State<'_, AppState>,
+    project_id: Option<Uuid>,
+    cache_key: &str,
+) -> Option<serde_json::Value> {
+    let project_id = project_id?;
+    match state.store.get_extraction_cache(project_id, cache_key).await {
+        Ok(cached) => cached,
+        Err(e) => {
+            eprintln!("extraction cache warning: {e}");
+            None
+        }
+    }
 }
 
+/// Store raw extraction results for [`cached_extraction`]. A failed write
+/// never fails the extraction.
+async fn cache_extraction(
+    state: &State<'_, AppState>,
+    project_id: Option<Uuid>,
+    cache_key: &str,
+    result: &serde_json::Value,
+) {
+    let Some(project_id) = project_id else {
+        return;
+    };
+    if let Err(e) = state.store.put_extraction_cache(project_id, cache_key, result).await {
+        eprintln!("extraction cache warning: {e}");
+    }
+}
+
+/// Forget the project's cached extraction results so the next extraction
+/// calls the model again. Returns how many were dropped.
 #[tauri::command]
-pub async fn local_llm_available(app: tauri::AppHandle) -> Result<bool, String> {
-    Ok(resolve_llama_paths(&app).is_ok())
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn clear_extraction_cache(
+    project_id: String,
+    state: State<'_, AppState>,
+) -> Result<u64, CommandError> {
+    let pid = parse_uuid("project_id", &project_id)?;
+    Ok(state.store.clear_extraction_cache(pid).await?)
 }
 
-fn extract_json_array(raw: &str) -> Option<String> {
-    let mut start: Option<usize> = None;
-    let mut depth: i32 = 0;
-    let mut in_string = false;
-    let mut escape = false;
+/// `extract.merge_list_continuations` — on unless explicitly set to false.
+async fn merge_list_continuations_enabled(state: &State<'_, AppState>) -> bool {
+    state
+        .store
+        .get_setting("extract.merge_list_continuations", None)
+        .await
+        .ok()
+        .flatten()
+        .and_then(|v| v.trim().trim_matches('"').parse::<bool>().ok())
+        .unwrap_or(true)
+}
 
-    for (i, ch) in raw.char_indices() {
-        if start.is_none() {
-            if ch == '[' {
-                start = Some(i);
-                depth = 1;
-            }
+/// Read the `extract.*` settings; missing or unparsable values keep the
+/// permissive defaults.
+async fn extraction_gate(state: &State<'_, AppState>) -> crate::core::extraction::ExtractionGate {
+    use crate::core::extraction::{Confidence, ExtractionGate};
+
+    let setting = |key: &'static str| async move {
+        state
+            .store
+            .get_setting(key, None)
+            .await
+            .ok()
+            .flatten()
+            .map(|v| v.trim().trim_matches('"').to_string())
+            .filter(|v| !v.is_empty())
+    };
+    let mut gate = ExtractionGate::default();
+    if let Some(min) = setting("extract.min_confidence").await.and_then(|v| Confidence::parse(&v)) {
+        gate.min_confidence = min;
+    }
+    if let Some(require) = setting("extract.require_modal").await.and_then(|v| v.parse().ok()) {
+        gate.require_modal = require;
+    }
+    gate.max_sentence_chars = setting("extract.max_sentence_chars")
+        .await
+        .and_then(|v| v.parse::<usize>().ok())
+        .filter(|max| *max > 0);
+    gate
+}
+
+/// `{results, accepted, rejected}` — `results` mirrors `accepted` for
+/// callers that predate the gate.
+fn gated_extraction_output(
+    results: Vec<serde_json::Value>,
+    gate: &crate::core::extraction::ExtractionGate,
+) -> String {
+    gated_extraction_value(results, gate).to_string()
+}
+
+fn gated_extraction_value(
+    results: Vec<serde_json::Value>,
+    gate: &crate::core::extraction::ExtractionGate,
+) -> serde_json::Value {
+    let outcome = crate::core::extraction::apply_gate(results, gate);
+    serde_json::json!({
+        "results": outcome.accepted,
+        "accepted": outcome.accepted,
+        "rejected": outcome.rejected,
+    })
+}
+
+// -- Extraction review ------------------------------------------------------
+
+/// A save of review progress. `results` are the raw extraction results and
+/// are only read on a session's first save. Items may carry every field;
+/// only those that differ from the raw result are stored.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExtractionReviewInput {
+    pub project_id: Uuid,
+    #[serde(default)]
+    pub document_id: Option<Uuid>,
+    #[serde(default)]
+    pub source_name: Option<String>,
+    #[serde(default)]
+    pub results: Option<Vec<serde_json::Value>>,
+    pub items: Vec<ExtractionReviewItem>,
+}
+
+/// Save the reviewer's decisions for an extraction session, creating the
+/// session on first save. Returns what was stored.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn save_extraction_review_state(
+    session_id: String,
+    review: ExtractionReviewInput,
+    state: State<'_, AppState>,
+) -> Result<ExtractionReviewState, String> {
+    let now = Utc::now();
+    let existing = state
+        .store
+        .get_extraction_review_state(&session_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut saved = match existing {
+        Some(existing) if existing.project_id != review.project_id => {
+            return Err(format!("extraction session {session_id} belongs to another project"));
+        }
+        Some(existing) => existing,
+        None => ExtractionReviewState {
+            session_id: session_id.clone(),
+            project_id: review.project_id,
+            document_id: review.document_id,
+            source_name: String::new(),
+            results: review.results.ok_or_else(|| {
+                format!("first save of extraction session {session_id} must include the results")
+            })?,
+            items: Vec::new(),
+            created_at: now,
+            updated_at: now,
+        },
+    };
+    if let Some(name) = review.source_name {
+        saved.source_name = name;
+    }
+    saved.items = crate::core::extraction::review::compact(&saved.results, review.items);
+    saved.updated_at = now;
+    state
+        .store
+        .save_extraction_review_state(&saved)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(saved)
+}
+
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn get_extraction_review_state(
+    session_id: String,
+    state: State<'_, AppState>,
+) -> Result<Option<ExtractionReviewState>, String> {
+    state
+        .store
+        .get_extraction_review_state(&session_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Create requirements from the session's accepted items and mark them
+/// imported. `items`, when given, replace the saved decisions first;
+/// otherwise the saved ones are used. Returns the created nodes.
+///
+/// With `req_id.scheme = "section_based"`, an item whose section is known
+/// (an explicit `section_ref` field, or the section of the source document
+/// containing its sentence) is numbered per section; see
+/// `core::numbering`. Other items get the next "REQ-NNN".
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn commit_extraction_review(
+    session_id: String,
+    items: Option<Vec<ExtractionReviewItem>>,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Vec<Node>, String> {
+    use crate::core::extraction::review;
+    use crate::core::numbering::{self, ReqIdScheme, SectionNumbering};
+
+    let mut saved = state
+        .store
+        .get_extraction_review_state(&session_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("no saved review for extraction session {session_id}"))?;
+    if let Some(items) = items {
+        saved.items = review::compact(&saved.results, items);
+    }
+
+    let existing = state
+        .store
+        .list_node_summaries(saved.project_id, Some(&NodeKind::Requirement), true)
+        .await
+        .map_err(|e| e.to_string())?;
+    let taken: Vec<String> = existing.into_iter().filter_map(|n| n.req_id).collect();
+    let pending: Vec<usize> = saved
+        .items
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| item.decision == ReviewDecision::Accepted && item.node_id.is_none())
+        .map(|(i, _)| i)
+        .collect();
+    let mut req_ids =
+        review::next_req_ids(taken.iter().map(String::as_str), pending.len()).into_iter();
+
+    // Section-based numbering needs the source document and its sections.
+    let scheme =
+        project_or_global_setting(&state, numbering::SCHEME_SETTING, saved.project_id).await;
+    let mut sectioned = None;
+    if let (ReqIdScheme::SectionBased, Some(document_id)) =
+        (ReqIdScheme::parse(scheme.as_deref()), saved.document_id)
+    {
+        let document = state
+            .store
+            .list_documents(saved.project_id)
+            .await
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .find(|d| d.id == document_id);
+        if let Some(document) = document {
+            let sections = state
+                .store
+                .list_document_sections(document_id)
+                .await
+                .map_err(|e| e.to_string())?;
+            let pattern =
+                project_or_global_setting(&state, numbering::PATTERN_SETTING, saved.project_id)
+                    .await
+                    .unwrap_or_else(|| numbering::DEFAULT_PATTERN.to_string());
+            let counters = state
+                .store
+                .req_id_counters(saved.project_id)
+                .await
+                .map_err(|e| e.to_string())?;
+            let numbering =
+                SectionNumbering::new(&pattern, counters, taken.iter().cloned().collect());
+            sectioned = Some((numbering::doc_abbrev(&document), sections, numbering));
+        }
+    }
+
+    let now = Utc::now();
+    let mut created: Vec<(usize, Node)> = Vec::new();
+    for i in pending {
+        let fields = review::resolve(&saved.results, &saved.items[i]);
+        let section_id = sectioned.as_mut().and_then(|(abbrev, sections, numbering)| {
+            let section_ref = fields
+                .get("section_ref")
+                .and_then(serde_json::Value::as_str)
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+                .or_else(|| {
+                    let text = review::requirement_text(&fields)?;
+                    numbering::section_containing(sections, text).map(|s| s.section_ref.clone())
+                })?;
+            Some(numbering.next(abbrev, &section_ref))
+        });
+        let Some(req_id) = section_id.or_else(|| req_ids.next()) else {
+            continue;
+        };
+        let Some(node) =
+            review::requirement_node(saved.project_id, &fields, req_id, &saved.source_name, now)
+        else {
             continue;
+        };
+        if let NodeData::Requirement(r) = &node.data {
+            check_requirement_text_length(&state, &node, r).await.map_err(|e| e.to_string())?;
         }
+        created.push((i, node));
+    }
 
-        if in_string {
-            if escape {
-                escape = false;
-            } else if ch == '\\' {
-                escape = true;
-            } else if ch == '"' {
-                in_string = false;
-            }
+    for (i, node) in &created {
+        state.store.upsert_node(node).await.map_err(|e| e.to_string())?;
+        saved.items[*i].decision = ReviewDecision::Imported;
+        saved.items[*i].node_id = Some(node.id);
+    }
+    if let Some((_, _, numbering)) = sectioned {
+        state
+            .store
+            .save_req_id_counters(saved.project_id, &numbering.into_counters(), false)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    saved.updated_at = now;
+    state
+        .store
+        .save_extraction_review_state(&saved)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let nodes: Vec<Node> = created.into_iter().map(|(_, node)| node).collect();
+    let touched = nodes.iter().map(|n| n.id).collect();
+    let _ = revalidate_touched(&app, &state, saved.project_id, touched).await;
+    Ok(nodes)
+}
+
+/// Give every requirement traceable to a section of its source document a
+/// section-based id (see `core::numbering`), numbering each section from 1
+/// in creation order. Requirements without a known section keep their ids.
+/// With `dry_run` nothing is written and the changes say what would be.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn renumber_requirements_by_section(
+    project_id: String,
+    dry_run: bool,
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::core::numbering::ReqIdChange>, String> {
+    use crate::core::numbering::{self, SourceDocument};
+
+    let pid = parse_uuid("project_id", &project_id).map_err(|e| e.to_string())?;
+    let _write = if dry_run {
+        None
+    } else {
+        let write = state
+            .store
+            .begin_heavy_write("renumbering requirements", Some(USER_HEAVY_WRITE_WAIT))
+            .await
+            .map_err(|e| e.to_string())?;
+        Some(write)
+    };
+
+    let requirements = state
+        .store
+        .list_nodes_by_kind(pid, &NodeKind::Requirement)
+        .await
+        .map_err(|e| e.to_string())?;
+    let documents = state.store.list_documents(pid).await.map_err(|e| e.to_string())?;
+    let mut sections = Vec::with_capacity(documents.len());
+    for document in &documents {
+        sections.push(
+            state
+                .store
+                .list_document_sections(document.id)
+                .await
+                .map_err(|e| e.to_string())?,
+        );
+    }
+    let sources: Vec<SourceDocument> = documents
+        .iter()
+        .zip(&sections)
+        .map(|(document, sections)| SourceDocument { document, sections })
+        .collect();
+    let pattern = project_or_global_setting(&state, numbering::PATTERN_SETTING, pid)
+        .await
+        .unwrap_or_else(|| numbering::DEFAULT_PATTERN.to_string());
+    let (mut changes, counters) = numbering::plan_renumbering(&requirements, &sources, &pattern);
+    if dry_run {
+        return Ok(changes);
+    }
+
+    let now = Utc::now();
+    for change in &mut changes {
+        let Some(mut node) = requirements.iter().find(|n| n.id == change.node_id).cloned() else {
             continue;
+        };
+        if let NodeData::Requirement(req) = &mut node.data {
+            req.req_id = Some(change.to.clone());
         }
+        node.meta.insert("change_source".to_string(), serde_json::json!("renumber"));
+        node.meta.insert("actor".to_string(), serde_json::json!("system"));
+        node.modified_at = now;
+        state.store.upsert_node(&node).await.map_err(|e| e.to_string())?;
+        change.applied = true;
+    }
+    state
+        .store
+        .save_req_id_counters(pid, &counters, true)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(changes)
+}
 
-        match ch {
-            '"' => in_string = true,
-            '[' => depth += 1,
-            ']' => {
-                depth -= 1;
-                if depth == 0 {
-                    let start_idx = start?;
-                    return Some(raw[start_idx..=i].to_string());
-                }
-            }
-            _ => {}
+/// Split text into overlapping chunks, snapping boundaries to sentence endings.
+fn chunk_text_by_sentences(text: &str, chunk_chars: usize, overlap_chars: usize) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let total = chars.len();
+    if total <= chunk_chars {
+        return vec![text.to_string()];
+    }
+
+    let mut chunks = Vec::new();
+    let mut start = 0;
+
+    while start < total {
+        let raw_end = (start + chunk_chars).min(total);
+        // Snap end forward to next sentence boundary
+        let end = if raw_end < total {
+            let lookahead = (raw_end + 300).min(total);
+            chars[raw_end..lookahead]
+                .iter()
+                .position(|&c| c == '.' || c == '\n')
+                .map(|p| raw_end + p + 1)
+                .unwrap_or(raw_end)
+        } else {
+            raw_end
+        };
+
+        chunks.push(chars[start..end].iter().collect());
+        if end >= total {
+            break;
         }
+
+        // Next chunk starts overlap chars back, snapped to a sentence start
+        let raw_next = end.saturating_sub(overlap_chars);
+        let next_start = chars[raw_next..end]
+            .iter()
+            .rposition(|&c| c == '.' || c == '\n')
+            .map(|p| raw_next + p + 1)
+            .unwrap_or(raw_next);
+
+        start = if next_start > start { next_start } else { end };
     }
 
-    None
+    chunks
 }
 
-fn extract_json_object(raw: &str) -> Option<String> {
-    let trimmed = raw.trim();
-    if trimmed.is_empty() {
-        return None;
-    }
+/// Characters of document text per local extraction prompt.
+const LOCAL_EXTRACTION_CHUNK_CHARS: usize = 6_000;
 
-    let cleaned = if trimmed.starts_with("```") {
-        trimmed
-            .lines()
-            .skip(1)
-            .take_while(|line| !line.starts_with("```"))
-            .collect::<Vec<_>>()
-            .join("\n")
-    } else {
-        trimmed.to_string()
-    };
+/// Requirements merged from a chunked extraction, and a message per chunk
+/// that failed. Only a run with no failures is complete enough to cache.
+#[derive(Default)]
+struct ChunkedExtraction {
+    items: Vec<serde_json::Value>,
+    failed_chunks: Vec<String>,
+}
 
-    if serde_json::from_str::<serde_json::Value>(&cleaned).is_ok() {
-        return Some(cleaned);
+/// Run local extraction chunk-by-chunk and merge unique requirement sentences.
+async fn run_chunked_local_extraction(
+    store: &crate::core::store::Store,
+    provider: Arc<dyn crate::ai::provider::AIProvider>,
+    text: &str,
+    doc_label: &str,
+    dtype: &str,
+    enrichment_context: Option<&str>,
+    merge_lists: bool,
+) -> ChunkedExtraction {
+    let chunks = chunk_text_by_sentences(text, LOCAL_EXTRACTION_CHUNK_CHARS, 400);
+    let total = chunks.len();
+    let mut merged = SentenceMerge::default();
+    let mut failed_chunks = Vec::new();
+
+    for (i, chunk) in chunks.iter().enumerate() {
+        let label = format!("{doc_label} (part {}/{})", i + 1, total);
+        let extracted = run_single_extraction(
+            store,
+            provider.clone(),
+            chunk,
+            &label,
+            dtype,
+            true,
+            enrichment_context,
+        )
+        .await;
+
+        match extracted {
+            // Fold list continuations per chunk, before overlapping chunks
+            // are deduplicated, so fragments stay next to their stem.
+            Ok(items) if merge_lists => {
+                merged.extend(crate::core::extraction::merge_list_continuations(items))
+            }
+            Ok(items) => merged.extend(items),
+            Err(e) => failed_chunks.push(format!("part {}/{}: {e}", i + 1, total)),
+        }
     }
 
-    let start = cleaned.find('{')?;
-    let end = cleaned.rfind('}')?;
-    if end > start {
-        Some(cleaned[start..=end].to_string())
-    } else {
-        None
+    ChunkedExtraction {
+        items: merged.items,
+        failed_chunks,
     }
 }
 
-fn requirement_needs_quality_review(item: &RequirementQualityInput) -> bool {
-    let confidence = item.confidence.trim().to_lowercase();
-    let classification = item.classification.trim().to_lowercase();
-    let name_words = item.name.split_whitespace().count();
-    let sentence_len = item.sentence.chars().count();
+/// Accumulates extracted requirements across chunks, keeping the first
+/// occurrence of each sentence (chunks overlap, so repeats are expected).
+#[derive(Default)]
+struct SentenceMerge {
+    items: Vec<serde_json::Value>,
+    seen: std::collections::HashSet<String>,
+}
 
-    if confidence != "high" {
-        return true;
+impl SentenceMerge {
+    fn extend(&mut self, items: Vec<serde_json::Value>) {
+        for item in items {
+            let key = item["sentence"]
+                .as_str()
+                .unwrap_or("")
+                .trim()
+                .to_lowercase();
+            if !key.is_empty() && self.seen.insert(key) {
+                self.items.push(item);
+            }
+        }
     }
-    if classification.is_empty() || classification == "unknown" {
-        return true;
+}
+
+/// Retry settings (`ai.retry.max_attempts`, `ai.retry.base_delay_ms`),
+/// global only since several AI calls have no project.
+async fn ai_retry_policy(store: &crate::core::store::Store) -> crate::ai::provider::RetryPolicy {
+    let mut policy = crate::ai::provider::RetryPolicy::default();
+    let values = store
+        .get_settings(&["ai.retry.max_attempts", "ai.retry.base_delay_ms"], None)
+        .await
+        .unwrap_or_default();
+    let value = |key: &str| values.get(key).map(|v| v.trim().trim_matches('"').to_string());
+    if let Some(attempts) = value("ai.retry.max_attempts")
+        .and_then(|v| v.parse::<u32>().ok())
+        .filter(|v| *v > 0)
+    {
+        policy.max_attempts = attempts;
     }
-    if name_words < 3 {
-        return true;
+    if let Some(ms) = value("ai.retry.base_delay_ms").and_then(|v| v.parse::<u64>().ok()) {
+        policy.base_delay = std::time::Duration::from_millis(ms);
     }
-    if sentence_len > 260 {
-        return true;
+    policy
+}
+
+/// Send `prompt`, retrying transient failures per [`ai_retry_policy`], and
+/// log the call's token usage to `ai_usage` under `purpose`. A failed
+/// usage write never fails the AI call.
+async fn complete_logged(
+    store: &crate::core::store::Store,
+    provider: &dyn crate::ai::provider::AIProvider,
+    prompt: Prompt,
+    purpose: &str,
+) -> Result<crate::ai::provider::AIResponse, String> {
+    let policy = ai_retry_policy(store).await;
+    let response = crate::ai::provider::complete_with_retry(provider, prompt, &policy)
+        .await
+        .map_err(|e| e.to_string())?;
+    let usage = AiUsage {
+        id: Uuid::new_v4(),
+        provider: provider.name().to_string(),
+        model: response.model.clone(),
+        purpose: purpose.to_string(),
+        input_tokens: response.input_tokens,
+        output_tokens: response.output_tokens,
+        cache_read_tokens: response.cache_read_tokens,
+        cache_write_tokens: response.cache_write_tokens,
+        created_at: Utc::now(),
+    };
+    if let Err(e) = store.record_ai_usage(&usage).await {
+        eprintln!("AI usage log warning: {e}");
     }
-    item.flags.iter().any(|f| {
-        let f = f.to_lowercase();
-        f.contains("compound")
-            || f.contains("hedge")
-            || f.contains("ambig")
-            || f.contains("implicit_constraint")
-    })
+    Ok(response)
 }
 
-#[tauri::command]
-pub async fn llm_extract_requirements(
-    text: String,
-    app: tauri::AppHandle,
-) -> Result<String, String> {
-    use std::process::Stdio;
-    use std::time::Duration;
-    use tokio::io::AsyncReadExt;
-    use tokio::process::Command;
-    use tokio::time::timeout;
+/// Run extraction prompt on one chunk of text.
+async fn run_single_extraction(
+    store: &crate::core::store::Store,
+    provider: Arc<dyn crate::ai::provider::AIProvider>,
+    text: &str,
+    doc_label: &str,
+    dtype: &str,
+    is_local: bool,
+    enrichment_context: Option<&str>,
+) -> Result<Vec<serde_json::Value>, String> {
+    let naming_rules = "NAME FIELD RULES:\n\
+- Derive name from the actual subject + constraint/measurement in that sentence.\n\
+- 3-7 words, Title Case.\n\
+- Include the key metric, component, or property if present (e.g. \"RF Link Margin 6 dB Minimum\", \"Watchdog Timeout Under 500 ms\", \"User Session Idle Logout 15 Min\").\n\
+- NEVER use generic titles like \"System Requirement\", \"Performance Requirement\", \"Data Requirement\", \"Interface Requirement\", or any name that could apply to multiple requirements.\n";
 
-    let (bin, model) = resolve_llama_paths(&app)?;
+    let (system, user) = if is_local {
+        let mut sys = format!(
+            "You are a requirements extraction tool. \
+Extract every requirement from the text — technical, security, communications, \
+programmatic, and contractor obligations. \
+A requirement uses 'shall', 'must', or 'will'. \
+Copy each requirement sentence VERBATIM. Never paraphrase or invent text. \
+Return only valid JSON, no other text.\n\n\
+{naming_rules}"
+        );
+
+        if let Some(ctx) = enrichment_context.map(str::trim).filter(|v| !v.is_empty()) {
+            sys.push_str(
+                "\nUse the context below as hints to resolve entities and relationships. \
+Do not invent requirements that are not explicitly present in the excerpt.\n\n",
+            );
+            sys.push_str(ctx);
+        }
 
-    let trimmed = if text.len() > 8000 {
-        format!("{}...", &text[..8000])
+        let usr = format!(
+            "Extract every requirement from this excerpt of \"{doc_label}\" ({dtype}).\n\
+Include ALL types: technical, performance, security, comms, interface, programmatic, reporting.\n\n\
+---\n{text}\n---\n\n\
+Return ONLY this JSON (no markdown, no explanation):\n\
+{{\"results\":[{{\"sentence\":\"<verbatim text>\",\"name\":\"<specific descriptive name from subject+constraint>\",\
+\"confidence\":\"high|medium|low\",\"flags\":[]}}]}}\n\n\
+Confidence guide:\n\
+- high: clear shall/must/will with explicit subject and measurable constraint\n\
+- medium: likely requirement, implicit subject or missing measurement\n\
+- low: possible obligation, ambiguous modal or missing subject\n\
+- If no requirements found in this excerpt: {{\"results\":[]}}"
+        );
+        (sys, usr)
     } else {
-        text
+        let sys = format!(
+            "You are a precise requirements engineering assistant applying IEEE 29148.\n\
+Extract every verifiable requirement from the document.\n\
+Rules: copy sentence verbatim — no paraphrasing, split compound shalls into separate items, \
+skip headings/rationale/notes, assign confidence high|medium|low, return only valid JSON.\n\n\
+{naming_rules}"
+        );
+        let usr = format!(
+            "Document: \"{doc_label}\" (type: {dtype})\n\n\
+---\n{text}\n---\n\n\
+Return JSON with a specific descriptive name for each requirement derived from its subject and constraint:\n\
+{{\"results\":[{{\"sentence\":\"<exact verbatim text>\",\"name\":\"<specific name from subject+constraint>\",\
+\"confidence\":\"high|medium|low\",\"flags\":[\"modal:shall\",\"has_measurement\",\"missing_subject\",...]}}]}}"
+        );
+        (sys, usr)
     };
 
-    let prompt = format!(
-        "You are a requirements extraction engine. Extract requirement statements from the document.\n\
-Return ONLY a JSON array. Each item: {{\"sentence\":\"<verbatim text>\",\"name\":\"<specific 3-7 word name derived from the requirement subject and constraint — never generic like System Requirement or Data Requirement>\",\"confidence\":\"high|medium|low\",\"flags\":[\"...\"]}}.\n\
-Name rules: derive from the actual subject + measurement/constraint in each sentence. Example good names: \"RF Link Margin 6 dB Min\", \"Boot Time Under 10s\", \"AES-256 Data Encryption\".\n\
-Keep sentences verbatim; use the source wording exactly.\n\
-Document:\n---\n{}\n---\nJSON:",
-        trimmed
-    );
+    let prompt = Prompt {
+        system: Some(system),
+        messages: vec![Message {
+            role: Role::User,
+            content: user,
+        }],
+        max_tokens: Some(4096),
+        // The system prompt is identical for every chunk of a document.
+        cache_system: true,
+    };
 
-    let mut child = Command::new(bin)
-        .arg("-m")
-        .arg(model)
-        .arg("--temp")
-        .arg("0.2")
-        .arg("--top-p")
-        .arg("0.9")
-        .arg("--repeat-penalty")
-        .arg("1.1")
-        .arg("--n-predict")
-        .arg("384")
-        .arg("--single-turn")
-        .arg("--no-conversation")
-        .arg("--simple-io")
-        .arg("--no-display-prompt")
-        .arg("--color")
-        .arg("off")
-        .arg("--log-colors")
-        .arg("off")
-        .arg("--prompt")
-        .arg(prompt)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .map_err(|e| e.to_string())?;
+    let response = complete_logged(store, provider.as_ref(), prompt, "extract").await?;
+    let raw = response.content.trim().to_string();
 
-    let status = match timeout(Duration::from_secs(90), child.wait()).await {
-        Ok(res) => res.map_err(|e| e.to_string())?,
-        Err(_) => {
-            let _ = child.start_kill();
-            return Err("LLM timed out after 90s".to_string());
-        }
+    let raw = if raw.starts_with("```") {
+        raw.lines()
+            .skip(1)
+            .take_while(|l| !l.starts_with("```"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    } else {
+        raw
     };
 
-    let mut stdout = String::new();
-    if let Some(mut out) = child.stdout.take() {
-        let mut buf = Vec::new();
-        let _ = out.read_to_end(&mut buf).await;
-        stdout = String::from_utf8_lossy(&buf).to_string();
-    }
+    let parsed: serde_json::Value =
+        serde_json::from_str(&raw).map_err(|e| format!("Invalid JSON: {e}"))?;
 
-    let mut stderr = String::new();
-    if let Some(mut err) = child.stderr.take() {
-        let mut buf = Vec::new();
-        let _ = err.read_to_end(&mut buf).await;
-        stderr = String::from_utf8_lossy(&buf).to_string();
-    }
+    Ok(parsed["results"].as_array().cloned().unwrap_or_default())
+}
 
-    if let Some(json) = extract_json_array(&stdout) {
-        return Ok(json);
-    }
+// ── AI diagram generation ─────────────────────────────────────────────────────
 
-    let _ = status;
-    Err(format!(
-        "LLM output did not contain JSON. stdout: {}, stderr: {}",
-        stdout.chars().take(200).collect::<String>(),
-        stderr.chars().take(200).collect::<String>()
-    ))
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DiagramNodeInput {
+    pub id: String,
+    pub kind: String,
+    pub name: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DiagramEdgeInput {
+    pub source_id: String,
+    pub target_id: String,
+    pub kind: String,
 }
 
+/// Ask the AI to decide which nodes to place in a diagram and return their
+/// positions. Returns JSON: `{"placements":[{"node_id","x","y","width","height"}]}`
 #[tauri::command]
-pub async fn ai_quality_pass_requirements(
-    requirements: Vec<RequirementQualityInput>,
-    doc_type: Option<String>,
-    doc_name: Option<String>,
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn ai_generate_diagram(
+    diagram_kind: String,
+    diagram_name: String,
+    nodes: Vec<DiagramNodeInput>,
+    edges: Vec<DiagramEdgeInput>,
     state: State<'_, AppState>,
 ) -> Result<String, String> {
     let provider = state.ai_provider.lock().unwrap().clone();
     if !provider.is_available() {
         return Err("no_api_key".to_string());
     }
-
-    if requirements.is_empty() {
-        return Ok(serde_json::json!({ "results": [] }).to_string());
+    if nodes.is_empty() {
+        return Ok(serde_json::json!({ "placements": [] }).to_string());
     }
 
-    let mut candidates: Vec<RequirementQualityInput> = requirements
-        .iter()
-        .filter(|item| requirement_needs_quality_review(item))
-        .cloned()
-        .collect();
-    if candidates.is_empty() {
-        candidates = requirements.iter().take(20).cloned().collect();
-    } else {
-        candidates.truncate(40);
-    }
+    let nodes_json = serde_json::to_string_pretty(&nodes).map_err(|e| e.to_string())?;
+    let edges_json = serde_json::to_string_pretty(&edges).map_err(|e| e.to_string())?;
 
-    let dtype = doc_type.unwrap_or_else(|| "General".to_string());
-    let dname = doc_name.unwrap_or_else(|| "document".to_string());
-    let payload = serde_json::to_string_pretty(&candidates).map_err(|e| e.to_string())?;
+    let kind_guidance = match diagram_kind.as_str() {
+        "bdd" => "Block Definition Diagram (BDD): show blocks and their composition/specialization relationships. Place the system root block at center-top. Subsystem blocks below it in a horizontal row.",
+        "ibd" => "Internal Block Diagram (IBD): show internal structure with ports and interfaces. Use a grid layout.",
+        "usecase" => "Use Case Diagram: actors on the left, use cases in an ellipse cluster in the center.",
+        "sequence" => "Sequence Diagram: actors/blocks across the top as columns, interactions implied by order.",
+        _ => "Arrange nodes in a clear hierarchical layout with related nodes close together.",
+    };
 
     let prompt = Prompt {
-        system: Some(
-            "You are a systems engineering requirement quality reviewer applying IEEE 29148.\n\
-Do NOT rewrite or paraphrase the requirement sentence — only improve the short name field.\n\
-\n\
-NAME RULES (most important):\n\
-- The name must uniquely identify WHAT the requirement is about — never use generic filler.\n\
-- Derive the name from the actual subject + constraint/action in the sentence.\n\
-- Format: \"<Subject> <Constraint/Property/Action>\" in Title Case, 3-7 words.\n\
-- Bad names (reject these patterns): \"System Requirement\", \"Performance Requirement\", \"Data Requirement\", \"Interface Requirement\", \"Security Requirement\", \"High Requirement\", \"Network Requirement\", or any name that could apply to dozens of requirements.\n\
-- Good examples: \"Uplink Data Rate 100 Mbps\", \"Battery Reserve 72 Hour Minimum\", \"GPS Fix Acquisition Under 30s\", \"AES-256 Payload Encryption\", \"Operator Alert Latency Under 2s\".\n\
-- If the current name is already specific and accurate, keep it unchanged.\n\
+        system: Some(format!(
+            "You are an MBSE diagram layout engine. Given a set of model nodes and edges, \
+select the most relevant nodes for a {diagram_kind} diagram and assign each a canvas position.\n\
 \n\
-QUALITY FLAGS (choose all that apply): ambiguous, compound_shall, missing_measurement, missing_verification_method, hedge_word, passive_voice, implicit_subject, testable, performance, interface, safety, security.\n\
+Layout guidance: {kind_guidance}\n\
 \n\
-CLASSIFICATION: system | contractual | verification | interface | constraint | unknown.\n\
+Canvas coordinate system: origin (0,0) is top-left. X increases right, Y increases down.\n\
+Typical node width: 180, height: 90. Leave at least 40px gap between nodes.\n\
+Use a canvas of roughly 1200 x 800.\n\
 \n\
-Return ONLY this JSON object — no markdown, no explanation:\n\
-{\"results\":[{\"id\":\"...\",\"sentence\":\"...\",\"name\":\"<specific descriptive name>\",\
-\"confidence\":\"high|medium|low\",\"classification\":\"system|contractual|verification|interface|constraint|unknown\",\
-\"flags\":[\"...\"],\"review_priority\":\"high|medium|low\"}]}"
-                .to_string(),
-        ),
+Return ONLY valid JSON:\n\
+{{\"placements\":[{{\"node_id\":\"...\",\"x\":0,\"y\":0,\"width\":180,\"height\":90}}]}}\n\
+Include only nodes relevant to a {diagram_kind}. Do not invent new node IDs."
+        )),
         messages: vec![Message {
             role: Role::User,
             content: format!(
-                "Document: \"{dname}\" (type: {dtype})\n\
-Review these extracted requirements. For each, produce a specific descriptive name derived \
-from the actual subject and constraint in that requirement sentence. Return the JSON object exactly.\n\n\
-{payload}"
+                "Diagram name: \"{diagram_name}\" (kind: {diagram_kind})\n\nNodes:\n{nodes_json}\n\nEdges:\n{edges_json}\n\nReturn the diagram layout."
             ),
         }],
         max_tokens: Some(2048),
+        cache_system: false,
     };
 
-    let response = provider.complete(prompt).await.map_err(|e| e.to_string())?;
+    let response = complete_logged(&state.store, provider.as_ref(), prompt, "diagram").await?;
     let raw = response.content.trim().to_string();
-    let raw_json = extract_json_object(&raw).ok_or_else(|| {
-        format!(
-            "AI quality pass did not return JSON object. output: {}",
-            raw.chars().take(220).collect::<String>()
-        )
+    let json_str = extract_json_object(&raw).ok_or_else(|| {
+        format!("AI did not return valid JSON. Output: {}", raw.chars().take(200).collect::<String>())
     })?;
+    Ok(json_str)
+}
 
-    let parsed: serde_json::Value =
-        serde_json::from_str(&raw_json).map_err(|e| format!("Invalid JSON: {e}"))?;
-    let mut out: Vec<RequirementQualityOutput> = Vec::new();
-
-    if let Some(items) = parsed["results"].as_array() {
-        for item in items {
-            let sentence = item["sentence"].as_str().unwrap_or("").trim().to_string();
-            if sentence.is_empty() {
-                continue;
-            }
-            let confidence = match item["confidence"]
-                .as_str()
-                .unwrap_or("")
-                .to_lowercase()
-                .as_str()
-            {
-                "high" | "medium" | "low" => item["confidence"].as_str().unwrap_or("").to_string(),
-                _ => "medium".to_string(),
-            };
-            let classification = match item["classification"]
-                .as_str()
-                .unwrap_or("")
-                .to_lowercase()
-                .as_str()
-            {
-                "system" | "contractual" | "verification" | "interface" | "constraint"
-                | "unknown" => item["classification"].as_str().unwrap_or("").to_string(),
-                _ => "unknown".to_string(),
-            };
-            let review_priority = match item["review_priority"]
-                .as_str()
-                .unwrap_or("")
-                .to_lowercase()
-                .as_str()
-            {
-                "high" | "medium" | "low" => {
-                    item["review_priority"].as_str().unwrap_or("").to_string()
-                }
-                _ => "medium".to_string(),
-            };
-            let flags = item["flags"]
-                .as_array()
-                .map(|arr| {
-                    arr.iter()
-                        .filter_map(|v| v.as_str().map(|s| s.trim().to_string()))
-                        .filter(|s| !s.is_empty())
-                        .take(12)
-                        .collect::<Vec<_>>()
-                })
-                .unwrap_or_default();
+// ── Suspect links ─────────────────────────────────────────────────────────────
 
-            out.push(RequirementQualityOutput {
-                id: item["id"].as_str().unwrap_or("").trim().to_string(),
-                sentence,
-                name: item["name"].as_str().unwrap_or("").trim().to_string(),
-                confidence,
-                classification,
-                flags,
-                review_priority,
-            });
-        }
-    }
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn get_suspect_links(project_id: String, state: State<'_, AppState>) -> Result<Vec<crate::core::model::SuspectLink>, String> {
+    let uuid = parse_uuid("project_id", &project_id).map_err(|e| e.to_string())?;
+    state.store.get_suspect_links(uuid).await.map_err(|e| e.to_string())
+}
 
-    let output = serde_json::json!({ "results": out });
-    Ok(output.to_string())
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn resolve_suspect_link(id: String, resolved_by: String, state: State<'_, AppState>) -> Result<(), String> {
+    let uuid = parse_uuid("id", &id).map_err(|e| e.to_string())?;
+    state.store.resolve_suspect_link(uuid, &resolved_by).await.map_err(|e| e.to_string())
 }
 
+/// A suspect link with its edge, both endpoints, the upstream node's history
+/// since the link was last reviewed and a word diff of its text over that
+/// window.
 #[tauri::command]
-pub async fn ai_suggest_requirement_allocations(
-    requirements: Vec<RequirementAllocationInput>,
-    subsystems: Vec<AllocationSubsystemInput>,
-    doc_type: Option<String>,
-    doc_name: Option<String>,
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn suspect_link_context(
+    suspect_id: String,
     state: State<'_, AppState>,
-) -> Result<String, String> {
-    let provider = state.ai_provider.lock().unwrap().clone();
-    if !provider.is_available() {
-        return Err("no_api_key".to_string());
-    }
+) -> Result<crate::core::history::SuspectLinkContext, String> {
+    use crate::core::history::{self, word_diff};
 
-    if requirements.is_empty() {
-        return Ok(serde_json::json!({ "results": [] }).to_string());
-    }
+    let id = parse_uuid("suspect_id", &suspect_id).map_err(|e| e.to_string())?;
+    let suspect = state
+        .store
+        .get_suspect_link(id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("suspect link {id} not found"))?;
 
-    let dtype = doc_type.unwrap_or_else(|| "General".to_string());
-    let dname = doc_name.unwrap_or_else(|| "document".to_string());
+    let edge = state.store.get_edge(suspect.edge_id).await.map_err(|e| e.to_string())?;
+    let source = state
+        .store
+        .get_node(suspect.source_node_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    let target = state
+        .store
+        .get_node(suspect.target_node_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    let edge_links = state
+        .store
+        .list_edge_suspect_links(suspect.edge_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    let reviewed_at = history::last_review(&suspect, &edge_links);
+    let entries = state
+        .store
+        .list_node_requirement_history(suspect.source_node_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    let window = history::suspect_window(entries, &suspect, reviewed_at);
 
-    let mut candidates = requirements;
-    candidates.truncate(120);
+    let current_text = match source.as_ref().map(|n| &n.data) {
+        Some(NodeData::Requirement(r)) => r.text.clone().unwrap_or_default(),
+        _ => String::new(),
+    };
+    let text_before = window
+        .first()
+        .map(|e| e.prev.text.clone())
+        .unwrap_or_else(|| current_text.clone());
+    let text_after = window.last().map(|e| e.next.text.clone()).unwrap_or(current_text);
+    let text_diff = word_diff::diff_words(&text_before, &text_after);
+
+    Ok(history::SuspectLinkContext {
+        suspect,
+        edge,
+        source,
+        target,
+        reviewed_at,
+        history: window,
+        text_before,
+        text_after,
+        text_diff,
+    })
+}
 
-    let mut subsystem_list = subsystems;
-    subsystem_list.truncate(40);
+/// Unresolved suspect links by age, the `limit` oldest (default 10) and
+/// their spread over the target requirements' subsystems.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn suspect_link_summary(
+    project_id: String,
+    limit: Option<i64>,
+    state: State<'_, AppState>,
+) -> Result<SuspectLinkSummary, String> {
+    let pid = parse_uuid("project_id", &project_id).map_err(|e| e.to_string())?;
+    let limit = limit.unwrap_or(10).clamp(1, 200);
+    state
+        .store
+        .suspect_link_summary(pid, limit)
+        .await
+        .map_err(|e| e.to_string())
+}
 
-    let payload = serde_json::to_string_pretty(&candidates).map_err(|e| e.to_string())?;
-    let subsystem_payload =
-        serde_json::to_string_pretty(&subsystem_list).map_err(|e| e.to_string())?;
+// ── Inline comments ───────────────────────────────────────────────────────────
 
-    let prompt = Prompt {
-        system: Some(
-            "You are a systems engineer allocating requirements to physical or domain subsystems \
-in a Model-Based Systems Engineering (MBSE) architecture.\n\
-\n\
-SUBSYSTEM DEFINITION — CRITICAL:\n\
-Subsystems are physical hardware units, major domain components, or top-level engineering \
-disciplines. They are NOT software functions, features, or use-cases.\n\
-\n\
-Good subsystem examples (physical/domain level):\n\
-  FPGA, Microprocessor, Microcontroller, Power Distribution, Onboard Computer,\n\
-  Communication Module, RF Subsystem, GPS Receiver, Inertial Measurement Unit,\n\
-  Sensor Array, Propulsion System, Thermal Management, Battery Pack,\n\
-  Flight Controller, Motor Driver, Payload Interface, Data Storage,\n\
-  Ground Control Station, User Interface Terminal, Network Switch,\n\
-  Hydraulic Actuator, Structural Frame, Navigation System.\n\
-\n\
-Bad subsystem examples (these are software functions — NEVER suggest these):\n\
-  display_search_results, lock_account, notify_emergency, user_authentication,\n\
-  error_handling, login_module, alert_driver, payment_processing.\n\
-\n\
-ALLOCATION RULES:\n\
-1. Choose ONE allocation from the provided subsystem list, OR 'System Level'.\n\
-2. Use 'System Level' for cross-cutting, contractual, or project-wide requirements.\n\
-3. If no listed subsystem fits but the requirement is clearly subsystem-specific,\n\
-   keep allocation as 'System Level' AND set new_subsystem_name to a concise \n\
-   physical/domain subsystem name (e.g. 'Flight Controller', 'Power Distribution Unit').\n\
-4. NEVER set new_subsystem_name to a software function or feature name.\n\
-\n\
-Return ONLY a JSON object:\n\
-{\"results\":[{\"id\":\"...\",\"sentence\":\"...\",\"allocation\":\"System Level|<exact subsystem name>\",\
-\"confidence\":\"high|medium|low\",\"rationale\":\"...\",\"new_subsystem_name\":\"optional\"}]}"
-                .to_string(),
-        ),
-        messages: vec![Message {
-            role: Role::User,
-            content: format!(
-                "Document: \"{dname}\" (type: {dtype})\n\
-Subsystems (use exact names when allocating):\n{subsystem_payload}\n\n\
-Requirements to allocate:\n{payload}"
-            ),
-        }],
-        max_tokens: Some(3072),
-    };
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn add_req_comment(
+    project_id: String,
+    node_id: String,
+    parent_id: Option<String>,
+    author: String,
+    body: String,
+    state: State<'_, AppState>,
+) -> Result<crate::core::model::ReqComment, String> {
+    let project_uuid = parse_uuid("project_id", &project_id).map_err(|e| e.to_string())?;
+    let node_uuid = parse_uuid("node_id", &node_id).map_err(|e| e.to_string())?;
+    let parent_uuid = parent_id.map(|s| parse_uuid("parent_id", &s).map_err(|e| e.to_string())).transpose()?;
+    let node = state.store.get_node(node_uuid).await.map_err(|e| e.to_string())?;
+    if !node.is_some_and(|n| n.project_id == project_uuid) {
+        return Err(format!("node_id: node {node_uuid} is not in this project"));
+    }
+    if let Some(parent) = parent_uuid {
+        let thread = state.store.get_req_comments(node_uuid).await.map_err(|e| e.to_string())?;
+        if !thread.iter().any(|c| c.id == parent) {
+            return Err(format!("parent_id: comment {parent} is not on this node"));
+        }
+    }
+    state.store
+        .add_req_comment(project_uuid, node_uuid, parent_uuid, &author, &body)
+        .await
+        .map_err(|e| e.to_string())
+}
 
-    let response = provider.complete(prompt).await.map_err(|e| e.to_string())?;
-    let raw = response.content.trim().to_string();
-    let raw_json = extract_json_object(&raw).ok_or_else(|| {
-        format!(
-            "AI allocation pass did not return JSON object. output: {}",
-            raw.chars().take(220).collect::<String>()
-        )
-    })?;
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn get_req_comments(node_id: String, state: State<'_, AppState>) -> Result<Vec<crate::core::model::ReqComment>, String> {
+    let uuid = parse_uuid("node_id", &node_id).map_err(|e| e.to_string())?;
+    state.store.get_req_comments(uuid).await.map_err(|e| e.to_string())
+}
 
-    let parsed: serde_json::Value =
-        serde_json::from_str(&raw_json).map_err(|e| format!("Invalid JSON: {e}"))?;
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn get_comment_counts(project_id: String, state: State<'_, AppState>) -> Result<std::collections::HashMap<String, i64>, String> {
+    let uuid = parse_uuid("project_id", &project_id).map_err(|e| e.to_string())?;
+    state.store.get_comment_counts_for_project(uuid).await.map_err(|e| e.to_string())
+}
 
-    let subsystem_lookup = subsystem_list
-        .iter()
-        .map(|s| (s.name.trim().to_lowercase(), s.name.trim().to_string()))
-        .collect::<std::collections::HashMap<_, _>>();
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn resolve_req_comment(id: String, resolved_by: String, state: State<'_, AppState>) -> Result<(), String> {
+    let uuid = parse_uuid("id", &id).map_err(|e| e.to_string())?;
+    state.store.resolve_req_comment(uuid, &resolved_by).await.map_err(|e| e.to_string())
+}
 
-    let mut out: Vec<RequirementAllocationOutput> = Vec::new();
-    if let Some(items) = parsed["results"].as_array() {
-        for item in items {
-            let sentence = item["sentence"].as_str().unwrap_or("").trim().to_string();
-            if sentence.is_empty() {
-                continue;
-            }
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn delete_req_comment(id: String, state: State<'_, AppState>) -> Result<(), String> {
+    let uuid = parse_uuid("id", &id).map_err(|e| e.to_string())?;
+    state.store.delete_req_comment(uuid).await.map_err(|e| e.to_string())
+}
 
-            let confidence = match item["confidence"]
-                .as_str()
-                .unwrap_or("")
-                .to_lowercase()
-                .as_str()
-            {
-                "high" | "medium" | "low" => item["confidence"].as_str().unwrap_or("").to_string(),
-                _ => "medium".to_string(),
-            };
+// ── Review workflow ───────────────────────────────────────────────────────────
 
-            let allocation_raw = item["allocation"].as_str().unwrap_or("").trim();
-            let allocation_norm = allocation_raw.to_lowercase();
-            let allocation = if allocation_norm.is_empty()
-                || allocation_norm == "system"
-                || allocation_norm == "system-level"
-                || allocation_norm == "system level"
-            {
-                "System Level".to_string()
-            } else if let Some(exact) = subsystem_lookup.get(&allocation_norm) {
-                exact.clone()
-            } else {
-                "System Level".to_string()
-            };
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn create_review_session(
+    project_id: String,
+    title: String,
+    description: Option<String>,
+    node_ids: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<crate::core::model::ReviewSession, String> {
+    let project_uuid = parse_uuid("project_id", &project_id).map_err(|e| e.to_string())?;
+    let node_uuids: Vec<Uuid> = node_ids.iter()
+        .map(|s| parse_uuid("node_ids", s).map_err(|e| e.to_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+    state.store.create_review_session(project_uuid, &title, description.as_deref(), node_uuids).await.map_err(|e| e.to_string())
+}
 
-            let mut new_subsystem_name = item["new_subsystem_name"]
-                .as_str()
-                .unwrap_or("")
-                .trim()
-                .replace('\n', " ");
-            if new_subsystem_name.len() > 64 {
-                new_subsystem_name = new_subsystem_name.chars().take(64).collect();
-            }
-            if new_subsystem_name.len() < 3 {
-                new_subsystem_name.clear();
-            }
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn list_review_sessions(project_id: String, state: State<'_, AppState>) -> Result<Vec<crate::core::model::ReviewSession>, String> {
+    let uuid = parse_uuid("project_id", &project_id).map_err(|e| e.to_string())?;
+    state.store.list_review_sessions(uuid).await.map_err(|e| e.to_string())
+}
 
-            out.push(RequirementAllocationOutput {
-                id: item["id"].as_str().unwrap_or("").trim().to_string(),
-                sentence,
-                allocation,
-                confidence,
-                rationale: item["rationale"].as_str().unwrap_or("").trim().to_string(),
-                new_subsystem_name,
-            });
-        }
-    }
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn set_review_verdict(
+    item_id: String,
+    verdict: String,
+    verdict_by: String,
+    note: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let uuid = parse_uuid("item_id", &item_id).map_err(|e| e.to_string())?;
+    state.store.set_review_verdict(uuid, &verdict, &verdict_by, note.as_deref()).await.map_err(|e| e.to_string())
+}
 
-    let output = serde_json::json!({ "results": out });
-    Ok(output.to_string())
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn close_review_session(session_id: String, status: String, state: State<'_, AppState>) -> Result<(), String> {
+    let uuid = parse_uuid("session_id", &session_id).map_err(|e| e.to_string())?;
+    state.store.close_review_session(uuid, &status).await.map_err(|e| e.to_string())
 }
 
-// -- AI requirement extraction (Claude / Anthropic) --------------------------
+// ── Model baselines ───────────────────────────────────────────────────────────
 
+/// Pass/fail per readiness criterion for `profile` ("baseline" or
+/// "delivery"), with the offending counts and a few sample items.
 #[tauri::command]
-pub async fn ai_extract_requirements(
-    text: String,
-    doc_type: Option<String>,
-    doc_name: Option<String>,
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn readiness_check(
+    project_id: String,
+    profile: String,
     state: State<'_, AppState>,
-) -> Result<String, String> {
-    let provider = state.ai_provider.lock().unwrap().clone();
-    if !provider.is_available() {
-        return Err("no_api_key".to_string());
-    }
+) -> Result<readiness::ReadinessReport, CommandError> {
+    let pid = parse_uuid("project_id", &project_id)?;
+    let profile = readiness::ReadinessProfile::parse(&profile).ok_or_else(|| {
+        CommandError::invalid(format!("unknown readiness profile '{profile}'"))
+    })?;
+    run_readiness_check(&state, pid, profile).await
+}
 
-    let doc_label = doc_name.unwrap_or_else(|| "document".to_string());
-    let dtype = doc_type.unwrap_or_else(|| "General".to_string());
-    let is_local = provider.name() == "ollama";
+/// Built from the queries behind validation, the suspect link summary,
+/// the attention digest and coverage metrics.
+async fn run_readiness_check(
+    state: &State<'_, AppState>,
+    project_id: Uuid,
+    profile: readiness::ReadinessProfile,
+) -> Result<readiness::ReadinessReport, CommandError> {
+    use readiness::{Criterion, CriterionResult, Measurement, ReadinessSample};
+
+    const SAMPLES: usize = 5;
+    let keys = readiness::ReadinessConfig::setting_keys(profile);
+    let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+    let mut values = state.store.get_settings(&keys, None).await?;
+    values.extend(state.store.get_settings(&keys, Some(project_id)).await?);
+    let config = readiness::ReadinessConfig::from_lookup(profile, |key| {
+        values.get(key).filter(|v| !v.trim().is_empty()).cloned()
+    });
 
-    let all_results: Vec<serde_json::Value> = if is_local {
-        run_chunked_local_extraction(provider.clone(), &text, &doc_label, &dtype, None).await
-    } else {
-        let trimmed: String = text.chars().take(60_000).collect();
-        run_single_extraction(provider.clone(), &trimmed, &doc_label, &dtype, false, None)
-            .await
-            .map_err(|e| e.to_string())?
+    let from_digest = |bucket: DigestBucket| Measurement {
+        value: bucket.count as f64,
+        samples: bucket
+            .samples
+            .into_iter()
+            .map(|s| ReadinessSample { node_id: s.node_id, label: s.label })
+            .collect(),
     };
-
-    let output = serde_json::json!({ "results": all_results });
-    Ok(output.to_string())
+    let mut results = Vec::with_capacity(config.criteria.len());
+    for (criterion, threshold) in config.criteria {
+        let measurement = match criterion {
+            Criterion::ValidationErrors => {
+                let issues = full_validation(state, project_id)
+                    .await
+                    .map_err(|detail| CommandError::Internal { detail })?;
+                let errors: Vec<_> = issues
+                    .into_iter()
+                    .filter(|i| matches!(i.severity, validation::IssueSeverity::Error))
+                    .collect();
+                Measurement {
+                    value: errors.len() as f64,
+                    samples: errors
+                        .into_iter()
+                        .take(SAMPLES)
+                        .map(|i| ReadinessSample { node_id: i.node_id, label: i.message })
+                        .collect(),
+                }
+            }
+            Criterion::SuspectLinks => {
+                let summary = state.store.suspect_link_summary(project_id, SAMPLES as i64).await?;
+                Measurement {
+                    value: summary.unresolved as f64,
+                    samples: summary
+                        .oldest
+                        .into_iter()
+                        .map(|s| ReadinessSample {
+                            node_id: Some(s.link.target_node_id),
+                            label: format!("{} → {}", s.source_name, s.target_name),
+                        })
+                        .collect(),
+                }
+            }
+            // Drafts are the only status that is neither approved nor
+            // waived (obsolete).
+            Criterion::UnapprovedRequirements => from_digest(
+                state.store.digest_stale_drafts(project_id, Utc::now(), SAMPLES).await?,
+            ),
+            Criterion::OpenReviews => from_digest(
+                state.store.digest_open_reviews(project_id, Utc::now(), SAMPLES).await?,
+            ),
+            Criterion::VerifiedCoverage => {
+                let metrics = state.store.coverage_metrics(project_id).await?;
+                // An empty project has nothing left to verify.
+                let value = if metrics.total_requirements == 0 {
+                    100.0
+                } else {
+                    metrics.verified_pct
+                };
+                Measurement { value, samples: Vec::new() }
+            }
+        };
+        results.push(CriterionResult::judge(criterion, threshold, measurement));
+    }
+    Ok(readiness::ReadinessReport::new(profile, results))
 }
 
-/// Split text into overlapping chunks, snapping boundaries to sentence endings.
-fn chunk_text_by_sentences(text: &str, chunk_chars: usize, overlap_chars: usize) -> Vec<String> {
-    let chars: Vec<char> = text.chars().collect();
-    let total = chars.len();
-    if total <= chunk_chars {
-        return vec![text.to_string()];
+/// With `enforce_readiness`, refuses to snapshot a project that fails the
+/// "baseline" readiness check and names the failed criteria.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn create_baseline(
+    project_id: String,
+    name: String,
+    description: Option<String>,
+    created_by: Option<String>,
+    enforce_readiness: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<ModelBaseline, String> {
+    let pid = parse_uuid("project_id", &project_id).map_err(|e| e.to_string())?;
+    if enforce_readiness.unwrap_or(false) {
+        let report = run_readiness_check(&state, pid, readiness::ReadinessProfile::Baseline)
+            .await
+            .map_err(|e| e.to_string())?;
+        if !report.passed {
+            return Err(format!("Readiness check failed: {}", report.failure_summary()));
+        }
     }
+    // Hold the queue while reading too, so the snapshot never sees half of
+    // another bulk write.
+    let _write = state
+        .store
+        .begin_heavy_write("creating a baseline", Some(USER_HEAVY_WRITE_WAIT))
+        .await
+        .map_err(|e| e.to_string())?;
 
-    let mut chunks = Vec::new();
-    let mut start = 0;
-
-    while start < total {
-        let raw_end = (start + chunk_chars).min(total);
-        // Snap end forward to next sentence boundary
-        let end = if raw_end < total {
-            let lookahead = (raw_end + 300).min(total);
-            chars[raw_end..lookahead]
-                .iter()
-                .position(|&c| c == '.' || c == '\n')
-                .map(|p| raw_end + p + 1)
-                .unwrap_or(raw_end)
-        } else {
-            raw_end
-        };
+    // Collect the full model state into a JSON snapshot, ordered by id so
+    // two baselines of an unchanged model are identical.
+    let mut nodes = state.store.list_nodes(pid).await.map_err(|e| e.to_string())?;
+    nodes.sort_by_key(|n| n.id);
+    let edges = state.store.list_edges(pid).await.map_err(|e| e.to_string())?;
 
-        chunks.push(chars[start..end].iter().collect());
-        if end >= total {
-            break;
-        }
+    let mut diagrams = state.store.list_diagrams(pid).await.map_err(|e| e.to_string())?;
+    diagrams.sort_by_key(|d| d.id);
+    let mut diagram_elements = state
+        .store
+        .list_project_diagram_elements(pid)
+        .await
+        .map_err(|e| e.to_string())?;
+    diagram_elements.sort_by_key(|e| e.id);
 
-        // Next chunk starts overlap chars back, snapped to a sentence start
-        let raw_next = end.saturating_sub(overlap_chars);
-        let next_start = chars[raw_next..end]
-            .iter()
-            .rposition(|&c| c == '.' || c == '\n')
-            .map(|p| raw_next + p + 1)
-            .unwrap_or(raw_next);
+    let snapshot = serde_json::json!({
+        "nodes": nodes,
+        "edges": edges,
+        "diagrams": diagrams,
+        "diagram_elements": diagram_elements,
+    });
 
-        start = if next_start > start { next_start } else { end };
-    }
+    let baseline = ModelBaseline {
+        id: Uuid::new_v4(),
+        project_id: pid,
+        name,
+        description: description.unwrap_or_default(),
+        created_by: created_by.unwrap_or_else(|| "User".to_string()),
+        created_at: Utc::now(),
+        snapshot,
+    };
 
-    chunks
+    state.store.create_baseline(&baseline).await.map_err(|e| e.to_string())?;
+    Ok(baseline)
 }
 
-/// Run local extraction chunk-by-chunk and merge unique requirement sentences.
-async fn run_chunked_local_extraction(
-    provider: Arc<dyn crate::ai::provider::AIProvider>,
-    text: &str,
-    doc_label: &str,
-    dtype: &str,
-    enrichment_context: Option<&str>,
-) -> Vec<serde_json::Value> {
-    let chunks = chunk_text_by_sentences(text, 6_000, 400);
-    let total = chunks.len();
-    let mut merged: Vec<serde_json::Value> = Vec::new();
-    let mut seen = std::collections::HashSet::<String>::new();
-
-    for (i, chunk) in chunks.iter().enumerate() {
-        let label = format!("{doc_label} (part {}/{})", i + 1, total);
-        let extracted = run_single_extraction(
-            provider.clone(),
-            chunk,
-            &label,
-            dtype,
-            true,
-            enrichment_context,
-        )
-        .await;
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn list_baselines(
+    project_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<ModelBaseline>, String> {
+    let pid = parse_uuid("project_id", &project_id).map_err(|e| e.to_string())?;
+    state.store.list_baselines(pid).await.map_err(|e| e.to_string())
+}
 
-        if let Ok(items) = extracted {
-            for item in items {
-                let key = item["sentence"]
-                    .as_str()
-                    .unwrap_or("")
-                    .trim()
-                    .to_lowercase();
-                if !key.is_empty() && seen.insert(key) {
-                    merged.push(item);
-                }
-            }
-        }
-    }
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn get_baseline(
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<ModelBaseline, String> {
+    let uuid = parse_uuid("id", &id).map_err(|e| e.to_string())?;
+    state
+        .store
+        .get_baseline(uuid)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "baseline not found".to_string())
+}
 
-    merged
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn delete_baseline(
+    id: String,
+    actor: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let uuid = parse_uuid("id", &id).map_err(|e| e.to_string())?;
+    state.baseline_index.lock().unwrap().invalidate(uuid);
+    let actor = actor_or_user(actor);
+    state.store.delete_baseline(uuid, &actor).await.map_err(|e| e.to_string())
 }
 
-/// Run extraction prompt on one chunk of text.
-async fn run_single_extraction(
-    provider: Arc<dyn crate::ai::provider::AIProvider>,
-    text: &str,
-    doc_label: &str,
-    dtype: &str,
-    is_local: bool,
-    enrichment_context: Option<&str>,
-) -> Result<Vec<serde_json::Value>, String> {
-    let naming_rules = "NAME FIELD RULES:\n\
-- Derive name from the actual subject + constraint/measurement in that sentence.\n\
-- 3-7 words, Title Case.\n\
-- Include the key metric, component, or property if present (e.g. \"RF Link Margin 6 dB Minimum\", \"Watchdog Timeout Under 500 ms\", \"User Session Idle Logout 15 Min\").\n\
-- NEVER use generic titles like \"System Requirement\", \"Performance Requirement\", \"Data Requirement\", \"Interface Requirement\", or any name that could apply to multiple requirements.\n";
+/// A requirement as it was in a baseline, its current state and the field
+/// changes between the two. `comparison` tells a requirement created after
+/// the baseline apart from one deleted since.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn requirement_at_baseline(
+    node_id: String,
+    baseline_id: String,
+    state: State<'_, AppState>,
+) -> Result<RequirementAtBaseline, String> {
+    use crate::core::baseline::BaselineIndex;
+    use crate::core::store::requirement_snapshot_from_node;
 
-    let (system, user) = if is_local {
-        let mut sys = format!(
-            "You are a requirements extraction tool. \
-Extract every requirement from the text — technical, security, communications, \
-programmatic, and contractor obligations. \
-A requirement uses 'shall', 'must', or 'will'. \
-Copy each requirement sentence VERBATIM. Never paraphrase or invent text. \
-Return only valid JSON, no other text.\n\n\
-{naming_rules}"
-        );
+    let nid = parse_uuid("node_id", &node_id).map_err(|e| e.to_string())?;
+    let bid = parse_uuid("baseline_id", &baseline_id).map_err(|e| e.to_string())?;
 
-        if let Some(ctx) = enrichment_context.map(str::trim).filter(|v| !v.is_empty()) {
-            sys.push_str(
-                "\nUse the context below as hints to resolve entities and relationships. \
-Do not invent requirements that are not explicitly present in the excerpt.\n\n",
-            );
-            sys.push_str(ctx);
+    let cached = state.baseline_index.lock().unwrap().get(bid);
+    let index = match cached {
+        Some(index) => index,
+        None => {
+            let baseline = state
+                .store
+                .get_baseline(bid)
+                .await
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| "baseline not found".to_string())?;
+            let index = BaselineIndex::build(baseline);
+            state.baseline_index.lock().unwrap().insert(index)
         }
-
-        let usr = format!(
-            "Extract every requirement from this excerpt of \"{doc_label}\" ({dtype}).\n\
-Include ALL types: technical, performance, security, comms, interface, programmatic, reporting.\n\n\
----\n{text}\n---\n\n\
-Return ONLY this JSON (no markdown, no explanation):\n\
-{{\"results\":[{{\"sentence\":\"<verbatim text>\",\"name\":\"<specific descriptive name from subject+constraint>\",\
-\"confidence\":\"high|medium|low\",\"flags\":[]}}]}}\n\n\
-Confidence guide:\n\
-- high: clear shall/must/will with explicit subject and measurable constraint\n\
-- medium: likely requirement, implicit subject or missing measurement\n\
-- low: possible obligation, ambiguous modal or missing subject\n\
-- If no requirements found in this excerpt: {{\"results\":[]}}"
-        );
-        (sys, usr)
-    } else {
-        let sys = format!(
-            "You are a precise requirements engineering assistant applying IEEE 29148.\n\
-Extract every verifiable requirement from the document.\n\
-Rules: copy sentence verbatim — no paraphrasing, split compound shalls into separate items, \
-skip headings/rationale/notes, assign confidence high|medium|low, return only valid JSON.\n\n\
-{naming_rules}"
-        );
-        let usr = format!(
-            "Document: \"{doc_label}\" (type: {dtype})\n\n\
----\n{text}\n---\n\n\
-Return JSON with a specific descriptive name for each requirement derived from its subject and constraint:\n\
-{{\"results\":[{{\"sentence\":\"<exact verbatim text>\",\"name\":\"<specific name from subject+constraint>\",\
-\"confidence\":\"high|medium|low\",\"flags\":[\"modal:shall\",\"has_measurement\",\"missing_subject\",...]}}]}}"
-        );
-        (sys, usr)
     };
 
-    let prompt = Prompt {
-        system: Some(system),
-        messages: vec![Message {
-            role: Role::User,
-            content: user,
-        }],
-        max_tokens: Some(4096),
+    let then = index.node(nid)?;
+    let now = state.store.get_node(nid).await.map_err(|e| e.to_string())?;
+    for node in then.iter().chain(now.iter()) {
+        if node.kind != NodeKind::Requirement {
+            return Err(format!("'{}' is a {}, not a requirement", node.name, node.kind));
+        }
+    }
+
+    let baseline = then.as_ref().and_then(requirement_snapshot_from_node);
+    let current = now.as_ref().and_then(requirement_snapshot_from_node);
+    let comparison = match (&baseline, &current) {
+        (Some(_), Some(_)) => BaselineComparison::Present,
+        (None, Some(_)) => BaselineComparison::MissingInBaseline,
+        (Some(_), None) => BaselineComparison::DeletedSinceBaseline,
+        (None, None) => {
+            return Err(format!(
+                "requirement {nid} is neither in baseline '{}' nor in the model",
+                index.name
+            ))
+        }
     };
+    let empty = RequirementSnapshot::default();
+    let changes = baseline
+        .as_ref()
+        .unwrap_or(&empty)
+        .diff(current.as_ref().unwrap_or(&empty));
+
+    Ok(RequirementAtBaseline {
+        node_id: nid,
+        baseline_id: bid,
+        baseline_name: index.name.clone(),
+        baseline_created_at: index.created_at,
+        comparison,
+        baseline,
+        current,
+        changes,
+    })
+}
 
-    let response = provider.complete(prompt).await.map_err(|e| e.to_string())?;
-    let raw = response.content.trim().to_string();
+/// How a diagram's layout changed since a baseline: elements added,
+/// removed, moved more than `threshold_px` (default 2) or resized, and
+/// edges whose endpoints changed. With `include_svg` the report carries an
+/// annotated SVG of the current layout.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn diff_diagram_against_baseline(
+    diagram_id: String,
+    baseline_id: String,
+    threshold_px: Option<f64>,
+    include_svg: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<crate::core::baseline::diagram_diff::DiagramBaselineDiff, CommandError> {
+    use crate::core::baseline::diagram_diff::{self, DiagramBaselineDiff};
 
-    let raw = if raw.starts_with("```") {
-        raw.lines()
-            .skip(1)
-            .take_while(|l| !l.starts_with("```"))
-            .collect::<Vec<_>>()
-            .join("\n")
-    } else {
-        raw
+    let did = parse_uuid("diagram_id", &diagram_id)?;
+    let bid = parse_uuid("baseline_id", &baseline_id)?;
+    let diagram = state
+        .store
+        .get_diagram(did)
+        .await?
+        .ok_or_else(|| CommandError::not_found(format!("diagram {did}")))?;
+    let baseline = state
+        .store
+        .get_baseline(bid)
+        .await?
+        .ok_or_else(|| CommandError::not_found(format!("baseline {bid}")))?;
+    if baseline.project_id != diagram.project_id {
+        return Err(CommandError::invalid(format!(
+            "baseline '{}' belongs to another project",
+            baseline.name
+        )));
+    }
+
+    let before = diagram_diff::baseline_elements(&baseline.snapshot, did)
+        .map_err(|detail| CommandError::Internal { detail })?;
+    let after = state.store.diagram_elements(did).await?;
+    let edges_before = diagram_diff::baseline_edges(&baseline.snapshot);
+    let edges_after = state.store.list_edges(diagram.project_id).await?;
+    let mut names = diagram_diff::baseline_node_names(&baseline.snapshot);
+    let nodes = state
+        .store
+        .list_node_summaries(diagram.project_id, None, true)
+        .await?;
+    names.extend(nodes.into_iter().map(|n| (n.id, n.name)));
+
+    let threshold_px = threshold_px
+        .filter(|t| t.is_finite() && *t >= 0.0)
+        .unwrap_or(diagram_diff::DEFAULT_MOVE_THRESHOLD_PX);
+    let changes = diagram_diff::diff(
+        before.as_deref(),
+        &after,
+        &edges_before,
+        &edges_after,
+        &names,
+        threshold_px,
+    );
+    let mut report = DiagramBaselineDiff {
+        diagram_id: did,
+        baseline_id: bid,
+        baseline_name: baseline.name,
+        changes,
+        svg: None,
     };
+    if include_svg.unwrap_or(false) {
+        report.svg = Some(diagram_diff::annotated_svg(&report, &after, &edges_after, &names));
+    }
+    Ok(report)
+}
 
-    let parsed: serde_json::Value =
-        serde_json::from_str(&raw).map_err(|e| format!("Invalid JSON: {e}"))?;
+/// Percent of requirements satisfied, verified, approved, with a
+/// verification method and allocated, for the live model.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn coverage_metrics(
+    project_id: String,
+    state: State<'_, AppState>,
+) -> Result<CoverageMetrics, CommandError> {
+    let pid = parse_uuid("project_id", &project_id)?;
+    Ok(state.store.coverage_metrics(pid).await?)
+}
 
-    Ok(parsed["results"].as_array().cloned().unwrap_or_default())
+/// The same metrics for every baseline, oldest first, followed by a point
+/// for the live model (`baseline_id: null`) so the chart ends at today.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn coverage_trend(
+    project_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<CoveragePoint>, CommandError> {
+    let pid = parse_uuid("project_id", &project_id)?;
+    let mut points = state.store.baseline_coverage(pid).await?;
+    points.push(CoveragePoint {
+        baseline_id: None,
+        name: "Current".to_string(),
+        at: Utc::now(),
+        metrics: state.store.coverage_metrics(pid).await?,
+    });
+    Ok(points)
 }
 
-// ── AI diagram generation ─────────────────────────────────────────────────────
+/// Counts and percentages of requirements satisfied, verified and
+/// allocated, with Obsolete ones left out, plus the blocks and test cases
+/// tracing to nothing; see `core::metrics`.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn model_coverage(
+    project_id: String,
+    state: State<'_, AppState>,
+) -> Result<crate::core::metrics::ModelCoverage, CommandError> {
+    let pid = parse_uuid("project_id", &project_id)?;
+    let nodes = state.store.list_nodes(pid).await?;
+    let edges = state.store.list_edges(pid).await?;
+    Ok(crate::core::metrics::model_coverage(&nodes, &edges))
+}
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct DiagramNodeInput {
-    pub id: String,
-    pub kind: String,
-    pub name: String,
-    pub description: Option<String>,
+/// The tree of nodes `node_id` impacts ("downstream", the default) or
+/// depends on ("upstream") through «derives», «refines», «satisfies»,
+/// «verifies» and «allocates» edges, at most `max_depth` (default 10)
+/// edges away; see `core::trace`.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn impact_analysis(
+    node_id: String,
+    max_depth: Option<u32>,
+    direction: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<crate::core::trace::ImpactNode, CommandError> {
+    use crate::core::trace::{self, TraceDirection};
+
+    let direction = match direction.as_deref() {
+        Some(raw) => TraceDirection::parse(raw).ok_or_else(|| {
+            CommandError::invalid(format!(
+                "unknown direction '{raw}' (expected downstream or upstream)"
+            ))
+        })?,
+        None => TraceDirection::Downstream,
+    };
+    let id = parse_uuid("node_id", &node_id)?;
+    let node = state
+        .store
+        .get_node(id)
+        .await?
+        .ok_or_else(|| CommandError::not_found(format!("node {id}")))?;
+    let nodes = state.store.list_nodes(node.project_id).await?;
+    let edges = state.store.list_edges(node.project_id).await?;
+    let max_depth = max_depth.map_or(trace::DEFAULT_MAX_DEPTH, |d| d as usize);
+    trace::impact_tree(id, &nodes, &edges, direction, max_depth)
+        .ok_or_else(|| CommandError::not_found(format!("node {id}")))
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct DiagramEdgeInput {
-    pub source_id: String,
-    pub target_id: String,
-    pub kind: String,
+// ── Portfolio ─────────────────────────────────────────────────────────────────
+
+/// Health of every project in the database, by name; see `ProjectHealth`.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn portfolio_summary(
+    state: State<'_, AppState>,
+) -> Result<Vec<ProjectHealth>, CommandError> {
+    Ok(state.store.portfolio_summary().await?)
 }
 
-/// Ask the AI to decide which nodes to place in a diagram and return their
-/// positions. Returns JSON: `{"placements":[{"node_id","x","y","width","height"}]}`
+/// `portfolio_summary` written to `path` as CSV. Returns the number of
+/// project rows.
 #[tauri::command]
-pub async fn ai_generate_diagram(
-    diagram_kind: String,
-    diagram_name: String,
-    nodes: Vec<DiagramNodeInput>,
-    edges: Vec<DiagramEdgeInput>,
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn export_portfolio_csv(
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<usize, CommandError> {
+    let dest = PathBuf::from(path.trim());
+    if dest.as_os_str().is_empty() {
+        return Err(CommandError::invalid("export path is empty"));
+    }
+    let rows = state.store.portfolio_summary().await?;
+    std::fs::write(&dest, crate::core::portfolio::to_csv(&rows))?;
+    Ok(rows.len())
+}
+
+// ── GraphRAG requirement extraction (Ollama + knowledge graph) ───────────────
+
+/// Extract requirements using a hybrid path:
+/// 1) build GraphRAG entity/relationship context from the document
+/// 2) run the normal `run_single_extraction` prompt over chunks, enriched by
+///    that context
+///
+/// Ollama is probed first; when it is unreachable or a model isn't pulled,
+/// or the graph can't be built, extraction still runs without enrichment.
+/// The output carries `graphrag` (a `GraphRagStatus`) saying whether the
+/// context was used and if not why, and the same status is emitted on
+/// `events::GRAPHRAG_STATUS` before the graph is built. The context is cut
+/// to what fits the chat model's context window beside a chunk.
+///
+/// Frontend calls this when the user has Ollama selected as provider AND
+/// the "Use GraphRAG" toggle is enabled in the requirements-extractor page.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn graphrag_extract_requirements(
+    text: String,
+    doc_type: Option<String>,
+    doc_name: Option<String>,
+    project_id: Option<String>,
+    app: tauri::AppHandle,
     state: State<'_, AppState>,
 ) -> Result<String, String> {
+    use crate::ai::graphrag::{
+        build_requirement_enrichment_context, cap_context, context_char_budget, probe_models,
+        GraphRagExtractorConfig, GraphRagStatus,
+    };
+    use tauri::Emitter;
+
+    let doc_label = doc_name.unwrap_or_else(|| "document".to_string());
+    let dtype = doc_type.unwrap_or_else(|| "General".to_string());
     let provider = state.ai_provider.lock().unwrap().clone();
     if !provider.is_available() {
         return Err("no_api_key".to_string());
     }
-    if nodes.is_empty() {
-        return Ok(serde_json::json!({ "placements": [] }).to_string());
+
+    let pid = project_id
+        .filter(|p| !p.trim().is_empty())
+        .map(|p| parse_uuid("project_id", &p))
+        .transpose()
+        .map_err(|e| e.to_string())?;
+    let settings = ollama_settings(&state).await;
+    let cfg = GraphRagExtractorConfig {
+        ollama_base_url: settings.base_url,
+        ollama_chat_model: settings.model,
+        ollama_embed_model: settings.embed_model,
+        ..Default::default()
+    };
+    let merge_lists = merge_list_continuations_enabled(&state).await;
+
+    let cache_key = crate::core::extraction::cache_key(
+        &text,
+        &dtype,
+        provider.name(),
+        provider.model(),
+        &format!(
+            "graphrag:{}:{}{}",
+            cfg.ollama_chat_model,
+            cfg.ollama_embed_model,
+            if merge_lists { ":merge_lists" } else { "" }
+        ),
+    );
+    if let Some(cached) = cached_extraction(&state, pid, &cache_key).await {
+        let results = cached["results"].as_array().cloned().unwrap_or_default();
+        // The status of the pass that filled the cache.
+        let _ = app.emit(crate::events::GRAPHRAG_STATUS, &cached["graphrag"]);
+        let gate = extraction_gate(&state).await;
+        let mut output = gated_extraction_value(results, &gate);
+        output["graphrag"] = cached["graphrag"].clone();
+        return Ok(output.to_string());
     }
 
-    let nodes_json = serde_json::to_string_pretty(&nodes).map_err(|e| e.to_string())?;
-    let edges_json = serde_json::to_string_pretty(&edges).map_err(|e| e.to_string())?;
+    // Cap input the same way ai_extract_requirements does for local models.
+    let capped: String = text.chars().take(60_000).collect();
 
-    let kind_guidance = match diagram_kind.as_str() {
-        "bdd" => "Block Definition Diagram (BDD): show blocks and their composition/specialization relationships. Place the system root block at center-top. Subsystem blocks below it in a horizontal row.",
-        "ibd" => "Internal Block Diagram (IBD): show internal structure with ports and interfaces. Use a grid layout.",
-        "usecase" => "Use Case Diagram: actors on the left, use cases in an ellipse cluster in the center.",
-        "sequence" => "Sequence Diagram: actors/blocks across the top as columns, interactions implied by order.",
-        _ => "Arrange nodes in a clear hierarchical layout with related nodes close together.",
+    // Probe Ollama so a skip says what is missing, and size the context to
+    // the chat model's window.
+    let mut status = GraphRagStatus::default();
+    let budget = match probe_models(&cfg).await {
+        Ok(context_length) => {
+            let budget = context_char_budget(context_length, LOCAL_EXTRACTION_CHUNK_CHARS);
+            if budget == 0 {
+                status.skipped_reason = Some(format!(
+                    "the context window of {} leaves no room for graph context",
+                    cfg.ollama_chat_model
+                ));
+            }
+            budget
+        }
+        Err(reason) => {
+            status.skipped_reason = Some(reason);
+            0
+        }
     };
+    status.used = status.skipped_reason.is_none();
+    let _ = app.emit(crate::events::GRAPHRAG_STATUS, &status);
 
-    let prompt = Prompt {
-        system: Some(format!(
-            "You are an MBSE diagram layout engine. Given a set of model nodes and edges, \
-select the most relevant nodes for a {diagram_kind} diagram and assign each a canvas position.\n\
-\n\
-Layout guidance: {kind_guidance}\n\
-\n\
-Canvas coordinate system: origin (0,0) is top-left. X increases right, Y increases down.\n\
-Typical node width: 180, height: 90. Leave at least 40px gap between nodes.\n\
-Use a canvas of roughly 1200 x 800.\n\
-\n\
-Return ONLY valid JSON:\n\
-{{\"placements\":[{{\"node_id\":\"...\",\"x\":0,\"y\":0,\"width\":180,\"height\":90}}]}}\n\
-Include only nodes relevant to a {diagram_kind}. Do not invent new node IDs."
-        )),
-        messages: vec![Message {
-            role: Role::User,
-            content: format!(
-                "Diagram name: \"{diagram_name}\" (kind: {diagram_kind})\n\nNodes:\n{nodes_json}\n\nEdges:\n{edges_json}\n\nReturn the diagram layout."
-            ),
-        }],
-        max_tokens: Some(2048),
-    };
+    // Build graph context first, then run the same extraction prompt used by
+    // run_single_extraction with this context injected.
+    let mut graph_context = String::new();
+    if status.used {
+        match build_requirement_enrichment_context(&capped, &cfg).await {
+            Ok(enrichment) => {
+                status.entities = enrichment.entities;
+                status.relationships = enrichment.relationships;
+                graph_context = cap_context(&enrichment.text, budget);
+                if graph_context.is_empty() {
+                    status.skipped_reason =
+                        Some("the document graph has no entities or relationships".to_string());
+                }
+            }
+            Err(e) => {
+                status.skipped_reason = Some(format!("building the document graph failed: {e}"));
+            }
+        }
+        status.used = status.skipped_reason.is_none();
+        status.context_chars = graph_context.chars().count();
+    }
 
-    let response = provider.complete(prompt).await.map_err(|e| e.to_string())?;
-    let raw = response.content.trim().to_string();
-    let json_str = extract_json_object(&raw).ok_or_else(|| {
-        format!("AI did not return valid JSON. Output: {}", raw.chars().take(200).collect::<String>())
-    })?;
-    Ok(json_str)
+    let extraction = run_chunked_local_extraction(
+        &state.store,
+        provider,
+        &capped,
+        &doc_label,
+        &dtype,
+        Some(graph_context.as_str()).filter(|ctx| !ctx.is_empty()),
+        merge_lists,
+    )
+    .await;
+
+    // A skipped enrichment ("model not pulled", Ollama down) is worth
+    // retrying once the user fixes it, so only a full pass is cached.
+    let complete = status.used && extraction.failed_chunks.is_empty();
+    let status = serde_json::to_value(&status).map_err(|e| e.to_string())?;
+    if complete {
+        let cached = serde_json::json!({ "results": extraction.items, "graphrag": status });
+        cache_extraction(&state, pid, &cache_key, &cached).await;
+    }
+    let gate = extraction_gate(&state).await;
+    let mut output = gated_extraction_value(extraction.items, &gate);
+    output["graphrag"] = status;
+    with_failed_chunks(&mut output, extraction.failed_chunks);
+    Ok(output.to_string())
+}
+
+// ── External items ────────────────────────────────────────────────────────────
+
+/// Import external items (e.g. a Jira CSV export) into a project. Rows whose
+/// key already exists update that item; rows without a key are skipped and
+/// reported.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn import_external_items_csv(
+    project_id: String,
+    csv: String,
+    mapping: crate::core::external::ExternalItemMapping,
+    state: State<'_, AppState>,
+) -> Result<crate::core::external::ExternalImportSummary, String> {
+    let pid = parse_uuid("project_id", &project_id).map_err(|e| e.to_string())?;
+    state
+        .store
+        .get_project(pid)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("project {pid} not found"))?;
+
+    let (items, skipped) = crate::core::external::items_from_csv(pid, &csv, &mapping)?;
+    let _write = state
+        .store
+        .begin_heavy_write("importing external items", Some(USER_HEAVY_WRITE_WAIT))
+        .await
+        .map_err(|e| e.to_string())?;
+    let (created, updated) = state
+        .store
+        .upsert_external_items(&items)
+        .await
+        .map_err(|e| e.to_string())?;
+    // Like every bulk import, start the project's validation cache over;
+    // the next edit or validation reseeds it.
+    state.validation_cache.lock().unwrap().invalidate(pid);
+    Ok(crate::core::external::ExternalImportSummary {
+        created,
+        updated,
+        skipped,
+    })
 }
 
-// ── Suspect links ─────────────────────────────────────────────────────────────
-
+/// External items in a project, optionally only those with `status`.
 #[tauri::command]
-pub async fn get_suspect_links(project_id: String, state: State<'_, AppState>) -> Result<Vec<crate::core::model::SuspectLink>, String> {
-    let uuid: Uuid = project_id.parse().map_err(|e: uuid::Error| e.to_string())?;
-    state.store.get_suspect_links(uuid).await.map_err(|e| e.to_string())
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn list_external_items(
+    project_id: String,
+    status: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<ExternalItem>, String> {
+    let pid = parse_uuid("project_id", &project_id).map_err(|e| e.to_string())?;
+    let status = status.as_deref().map(str::trim).filter(|s| !s.is_empty());
+    state
+        .store
+        .list_external_items(pid, status)
+        .await
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn resolve_suspect_link(id: String, resolved_by: String, state: State<'_, AppState>) -> Result<(), String> {
-    let uuid: Uuid = id.parse().map_err(|e: uuid::Error| e.to_string())?;
-    state.store.resolve_suspect_link(uuid, &resolved_by).await.map_err(|e| e.to_string())
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn link_external_item(
+    item_id: String,
+    node_id: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let item_id = parse_uuid("item_id", &item_id).map_err(|e| e.to_string())?;
+    let node_id = parse_uuid("node_id", &node_id).map_err(|e| e.to_string())?;
+    let item = state
+        .store
+        .get_external_item(item_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "external item not found".to_string())?;
+    let node = state
+        .store
+        .get_node(node_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "node not found".to_string())?;
+    if item.project_id != node.project_id {
+        return Err("external item and node belong to different projects".to_string());
+    }
+    state
+        .store
+        .link_external_item(item_id, node_id)
+        .await
+        .map_err(|e| e.to_string())
 }
 
-// ── Inline comments ───────────────────────────────────────────────────────────
-
 #[tauri::command]
-pub async fn add_req_comment(
-    project_id: String,
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn unlink_external_item(
+    item_id: String,
     node_id: String,
-    parent_id: Option<String>,
-    author: String,
-    body: String,
     state: State<'_, AppState>,
-) -> Result<crate::core::model::ReqComment, String> {
-    let project_uuid: Uuid = project_id.parse().map_err(|e: uuid::Error| e.to_string())?;
-    let node_uuid: Uuid = node_id.parse().map_err(|e: uuid::Error| e.to_string())?;
-    let parent_uuid = parent_id.map(|s| s.parse::<Uuid>().map_err(|e: uuid::Error| e.to_string())).transpose()?;
-    state.store
-        .add_req_comment(project_uuid, node_uuid, parent_uuid, &author, &body)
+) -> Result<(), String> {
+    let item_id = parse_uuid("item_id", &item_id).map_err(|e| e.to_string())?;
+    let node_id = parse_uuid("node_id", &node_id).map_err(|e| e.to_string())?;
+    state
+        .store
+        .unlink_external_item(item_id, node_id)
         .await
         .map_err(|e| e.to_string())
 }
 
-#[tauri::command]
-pub async fn get_req_comments(node_id: String, state: State<'_, AppState>) -> Result<Vec<crate::core::model::ReqComment>, String> {
-    let uuid: Uuid = node_id.parse().map_err(|e: uuid::Error| e.to_string())?;
-    state.store.get_req_comments(uuid).await.map_err(|e| e.to_string())
-}
+// ── Command palette ───────────────────────────────────────────────────────────
 
-#[tauri::command]
-pub async fn get_comment_counts(project_id: String, state: State<'_, AppState>) -> Result<std::collections::HashMap<String, i64>, String> {
-    let uuid: Uuid = project_id.parse().map_err(|e: uuid::Error| e.to_string())?;
-    state.store.get_comment_counts_for_project(uuid).await.map_err(|e| e.to_string())
+/// Most entries `palette_index` returns. An entry with a typical name is
+/// around 120 bytes of JSON, so a full index stays near 2.4 MB.
+const PALETTE_LIMIT: usize = 20_000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaletteIndex {
+    pub entries: Vec<PaletteEntry>,
+    /// More targets exist than `PALETTE_LIMIT`; the rest are left out.
+    pub truncated: bool,
+    /// When the index was built. The frontend keeps it until the next
+    /// `model:changed` event.
+    pub palette_updated_at: chrono::DateTime<Utc>,
 }
 
+/// Every jump target in a project for the Ctrl-K palette: ids, names and a
+/// short sublabel, nothing else.
 #[tauri::command]
-pub async fn resolve_req_comment(id: String, resolved_by: String, state: State<'_, AppState>) -> Result<(), String> {
-    let uuid: Uuid = id.parse().map_err(|e: uuid::Error| e.to_string())?;
-    state.store.resolve_req_comment(uuid, &resolved_by).await.map_err(|e| e.to_string())
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn palette_index(
+    project_id: String,
+    state: State<'_, AppState>,
+) -> Result<PaletteIndex, CommandError> {
+    let pid = parse_uuid("project_id", &project_id)?;
+    let palette_updated_at = Utc::now();
+    let mut entries = state.store.palette_entries(pid, PALETTE_LIMIT + 1).await?;
+    let truncated = entries.len() > PALETTE_LIMIT;
+    entries.truncate(PALETTE_LIMIT);
+    Ok(PaletteIndex { entries, truncated, palette_updated_at })
 }
 
-#[tauri::command]
-pub async fn delete_req_comment(id: String, state: State<'_, AppState>) -> Result<(), String> {
-    let uuid: Uuid = id.parse().map_err(|e: uuid::Error| e.to_string())?;
-    state.store.delete_req_comment(uuid).await.map_err(|e| e.to_string())
+// ── Attention digest ──────────────────────────────────────────────────────────
+
+/// Global setting holding the id of the project opened last.
+pub(crate) const LAST_PROJECT_SETTING: &str = "app.last_project_id";
+
+/// `digest.*` settings for a project, project values over global ones.
+pub(crate) async fn digest_settings(
+    store: &crate::core::store::Store,
+    project_id: Uuid,
+) -> anyhow::Result<digest::DigestSettings> {
+    let mut values = store.get_settings(&digest::DigestSettings::KEYS, None).await?;
+    values.extend(
+        store
+            .get_settings(&digest::DigestSettings::KEYS, Some(project_id))
+            .await?,
+    );
+    Ok(digest::DigestSettings::from_lookup(|key| {
+        values.get(key).filter(|v| !v.trim().is_empty()).cloned()
+    }))
 }
 
-// ── Review workflow ───────────────────────────────────────────────────────────
+pub(crate) async fn compute_attention_digest(
+    store: &crate::core::store::Store,
+    project_id: Uuid,
+    settings: &digest::DigestSettings,
+) -> anyhow::Result<Vec<digest::AttentionItem>> {
+    let now = Utc::now();
+    let days_ago = |days: i64| now - chrono::Duration::days(days);
+    let samples = digest::SAMPLE_ITEMS;
+    let buckets = digest::DigestBuckets {
+        stale_comments: store
+            .digest_open_comments(project_id, days_ago(settings.comment_age_days), samples)
+            .await?,
+        open_reviews: store
+            .digest_open_reviews(project_id, days_ago(settings.review_age_days), samples)
+            .await?,
+        suspect_links: store.digest_suspect_links(project_id, samples).await?,
+        stale_drafts: store
+            .digest_stale_drafts(project_id, days_ago(settings.draft_age_days), samples)
+            .await?,
+    };
+    Ok(digest::prioritize(buckets, settings, now))
+}
 
+/// Overdue comments, reviews, suspect links and drafts, most overdue first.
 #[tauri::command]
-pub async fn create_review_session(
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn attention_digest(
     project_id: String,
-    title: String,
-    description: Option<String>,
-    node_ids: Vec<String>,
     state: State<'_, AppState>,
-) -> Result<crate::core::model::ReviewSession, String> {
-    let project_uuid: Uuid = project_id.parse().map_err(|e: uuid::Error| e.to_string())?;
-    let node_uuids: Vec<Uuid> = node_ids.iter()
-        .map(|s| s.parse::<Uuid>().map_err(|e: uuid::Error| e.to_string()))
-        .collect::<Result<Vec<_>, _>>()?;
-    state.store.create_review_session(project_uuid, &title, description.as_deref(), node_uuids).await.map_err(|e| e.to_string())
+) -> Result<Vec<digest::AttentionItem>, CommandError> {
+    let pid = parse_uuid("project_id", &project_id)?;
+    let settings = digest_settings(&state.store, pid).await?;
+    Ok(compute_attention_digest(&state.store, pid, &settings).await?)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestReady {
+    pub project_id: Uuid,
+    pub items: Vec<digest::AttentionItem>,
+}
+
+/// Startup digest for the project opened last, emitted once the window has
+/// had a moment to register its listeners. Nothing is emitted when
+/// `digest.enabled` is off or nothing is overdue.
+pub(crate) async fn emit_startup_digest(
+    app: tauri::AppHandle,
+    store: crate::core::store::Store,
+) {
+    use tauri::Emitter;
+
+    tokio::time::sleep(std::time::Duration::from_secs(3)).await;
+    let Some(pid) = store
+        .get_setting(LAST_PROJECT_SETTING, None)
+        .await
+        .unwrap_or(None)
+        .and_then(|v| Uuid::parse_str(v.trim().trim_matches('"')).ok())
+    else {
+        return;
+    };
+    if !matches!(store.get_project(pid).await, Ok(Some(_))) {
+        return;
+    }
+    let result = match digest_settings(&store, pid).await {
+        Ok(settings) if !settings.enabled => return,
+        Ok(settings) => compute_attention_digest(&store, pid, &settings).await,
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(items) if items.is_empty() => {}
+        Ok(items) => {
+            let _ = app.emit(crate::events::DIGEST_READY, DigestReady { project_id: pid, items });
+        }
+        Err(e) => eprintln!("warning: attention digest failed: {e}"),
+    }
 }
 
+// ── Startup ───────────────────────────────────────────────────────────────────
+
+/// Where deferred startup has got to. Answered before `AppState` exists,
+/// for a window that opened after the `startup:*` events fired.
 #[tauri::command]
-pub async fn list_review_sessions(project_id: String, state: State<'_, AppState>) -> Result<Vec<crate::core::model::ReviewSession>, String> {
-    let uuid: Uuid = project_id.parse().map_err(|e: uuid::Error| e.to_string())?;
-    state.store.list_review_sessions(uuid).await.map_err(|e| e.to_string())
+pub fn startup_status(
+    state: State<'_, crate::startup::StartupState>,
+) -> crate::startup::StartupStatus {
+    let status = state.0.lock().unwrap();
+    status.clone()
 }
 
+// ── Diagnostics ───────────────────────────────────────────────────────────────
+
+/// The latest `limit` command timings, newest first. Kept in memory since
+/// launch (up to 1000 operations); see `crate::diagnostics`.
 #[tauri::command]
-pub async fn set_review_verdict(
-    item_id: String,
-    verdict: String,
-    verdict_by: String,
-    note: Option<String>,
-    state: State<'_, AppState>,
-) -> Result<(), String> {
-    let uuid: Uuid = item_id.parse().map_err(|e: uuid::Error| e.to_string())?;
-    state.store.set_review_verdict(uuid, &verdict, &verdict_by, note.as_deref()).await.map_err(|e| e.to_string())
+pub fn get_recent_command_timings(
+    limit: usize,
+    diagnostics: State<'_, Arc<Diagnostics>>,
+) -> Vec<OperationTiming> {
+    diagnostics.recent_commands(limit)
 }
 
+/// Commands and store queries that took at least `threshold_ms`, slowest
+/// first.
 #[tauri::command]
-pub async fn close_review_session(session_id: String, status: String, state: State<'_, AppState>) -> Result<(), String> {
-    let uuid: Uuid = session_id.parse().map_err(|e: uuid::Error| e.to_string())?;
-    state.store.close_review_session(uuid, &status).await.map_err(|e| e.to_string())
+pub fn get_slow_operations(
+    threshold_ms: f64,
+    diagnostics: State<'_, Arc<Diagnostics>>,
+) -> Result<Vec<OperationTiming>, CommandError> {
+    if !threshold_ms.is_finite() || threshold_ms < 0.0 {
+        return Err(CommandError::invalid("threshold_ms must be zero or more"));
+    }
+    Ok(diagnostics.slow_operations(threshold_ms))
 }
 
-// ── Model baselines ───────────────────────────────────────────────────────────
+// ── Presence and identity ─────────────────────────────────────────────────────
 
+/// Machines with a heartbeat in the last two minutes, this one first.
 #[tauri::command]
-pub async fn create_baseline(
-    project_id: String,
-    name: String,
-    description: Option<String>,
-    created_by: Option<String>,
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn who_is_active(
     state: State<'_, AppState>,
-) -> Result<ModelBaseline, String> {
-    let pid: Uuid = project_id.parse().map_err(|e: uuid::Error| e.to_string())?;
-
-    // Collect the full model state into a JSON snapshot
-    let nodes = state.store.list_nodes(pid).await.map_err(|e| e.to_string())?;
-    let edges = {
-        let mut all = Vec::new();
-        for node in &nodes {
-            let mut e = state
-                .store
-                .edges_for_node(node.id)
-                .await
-                .map_err(|e| e.to_string())?;
-            all.append(&mut e);
-        }
-        all.sort_by_key(|e| e.id);
-        all.dedup_by_key(|e| e.id);
-        all
-    };
+) -> Result<Vec<crate::presence::ActiveInstance>, CommandError> {
+    let rows = state.store.list_presence().await?;
+    Ok(crate::presence::active_instances(&rows, &state.presence.machine, Utc::now()))
+}
 
-    let snapshot = serde_json::json!({
-        "nodes": nodes,
-        "edges": edges,
-    });
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdentityInfo {
+    pub machine: String,
+    /// Stamped onto audit, history and comment rows; `None` leaves them
+    /// as the caller wrote them.
+    pub identity: Option<String>,
+    /// Reopen read-only at startup when another machine is active.
+    pub read_only_fallback: bool,
+    /// This session runs read-only.
+    pub read_only: bool,
+}
 
-    let baseline = ModelBaseline {
-        id: Uuid::new_v4(),
-        project_id: pid,
-        name,
-        description: description.unwrap_or_default(),
-        created_by: created_by.unwrap_or_else(|| "User".to_string()),
-        created_at: Utc::now(),
-        snapshot,
-    };
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn get_identity(state: State<'_, AppState>) -> Result<IdentityInfo, CommandError> {
+    let own = state.store.get_presence(&state.presence.machine).await?;
+    Ok(IdentityInfo {
+        machine: state.presence.machine.clone(),
+        identity: state.store.identity(),
+        read_only_fallback: own.is_some_and(|p| p.read_only_fallback),
+        read_only: state.presence.read_only,
+    })
+}
 
-    state.store.create_baseline(&baseline).await.map_err(|e| e.to_string())?;
-    Ok(baseline)
+/// Set this machine's identity; blank clears it.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn set_identity(
+    identity: String,
+    state: State<'_, AppState>,
+) -> Result<IdentityInfo, CommandError> {
+    if identity.chars().count() > 100 {
+        return Err(CommandError::invalid("identity must be at most 100 characters"));
+    }
+    state
+        .store
+        .set_presence_user(&state.presence.machine, &identity)
+        .await?;
+    get_identity(state).await
 }
 
+/// Opt in or out of the read-only fallback; takes effect on next start.
 #[tauri::command]
-pub async fn list_baselines(
-    project_id: String,
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn set_read_only_fallback(
+    enabled: bool,
     state: State<'_, AppState>,
-) -> Result<Vec<ModelBaseline>, String> {
-    let pid: Uuid = project_id.parse().map_err(|e: uuid::Error| e.to_string())?;
-    state.store.list_baselines(pid).await.map_err(|e| e.to_string())
+) -> Result<IdentityInfo, CommandError> {
+    state
+        .store
+        .set_presence_read_only_fallback(&state.presence.machine, enabled)
+        .await?;
+    get_identity(state).await
 }
 
+// ── Audit log ─────────────────────────────────────────────────────────────────
+
 #[tauri::command]
-pub async fn get_baseline(
-    id: String,
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn list_audit_log(
+    project_id: String,
+    entity_type: Option<String>,
+    entity_id: Option<String>,
+    since: Option<String>,
+    limit: Option<i64>,
     state: State<'_, AppState>,
-) -> Result<ModelBaseline, String> {
-    let uuid: Uuid = id.parse().map_err(|e: uuid::Error| e.to_string())?;
+) -> Result<Vec<AuditEntry>, String> {
+    let pid = parse_uuid("project_id", &project_id).map_err(|e| e.to_string())?;
+    let eid = entity_id
+        .map(|s| parse_uuid("entity_id", &s).map_err(|e| e.to_string()))
+        .transpose()?;
+    let since = since
+        .map(|s| {
+            chrono::DateTime::parse_from_rfc3339(&s)
+                .map(|d| d.with_timezone(&Utc))
+                .map_err(|e| e.to_string())
+        })
+        .transpose()?;
+    let capped_limit = limit.unwrap_or(100).clamp(1, 1000) as usize;
     state
         .store
-        .get_baseline(uuid)
+        .list_audit_log(pid, entity_type.as_deref(), eid, since, capped_limit)
         .await
-        .map_err(|e| e.to_string())?
-        .ok_or_else(|| "baseline not found".to_string())
+        .map_err(|e| e.to_string())
 }
 
+/// Delete audit entries older than `keep_days`. Returns the number removed.
 #[tauri::command]
-pub async fn delete_baseline(id: String, state: State<'_, AppState>) -> Result<(), String> {
-    let uuid: Uuid = id.parse().map_err(|e: uuid::Error| e.to_string())?;
-    state.store.delete_baseline(uuid).await.map_err(|e| e.to_string())
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn prune_audit_log(
+    project_id: String,
+    keep_days: i64,
+    state: State<'_, AppState>,
+) -> Result<u64, String> {
+    let pid = parse_uuid("project_id", &project_id).map_err(|e| e.to_string())?;
+    state
+        .store
+        .prune_audit_log(pid, keep_days)
+        .await
+        .map_err(|e| e.to_string())
 }
 
-// ── GraphRAG requirement extraction (Ollama + knowledge graph) ───────────────
-
-/// Extract requirements using a hybrid path:
-/// 1) build GraphRAG entity/relationship context from the document
-/// 2) run the normal `run_single_extraction` prompt over chunks, enriched by
-///    that context
+/// Chronological activity trail for a project, as pretty-printed JSON.
 ///
-/// If GraphRAG initialisation fails (e.g. embedding model not pulled yet),
-/// extraction still runs without enrichment.
-///
-/// Frontend calls this when the user has Ollama selected as provider AND
-/// the "Use GraphRAG" toggle is enabled in the requirements-extractor page.
+/// Merges the recorded audit log with events derived from entity
+/// timestamps (diagram elements, comments, reviews, and any nodes/edges
+/// that predate the audit log) so exports cover the full history.
 #[tauri::command]
-pub async fn graphrag_extract_requirements(
-    text: String,
-    doc_type: Option<String>,
-    doc_name: Option<String>,
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn export_audit_log(
+    project_id: String,
     state: State<'_, AppState>,
 ) -> Result<String, String> {
-    use crate::ai::graphrag::{build_requirement_enrichment_context, GraphRagExtractorConfig};
+    let pid = parse_uuid("project_id", &project_id).map_err(|e| e.to_string())?;
+    let store = &state.store;
 
-    let doc_label = doc_name.unwrap_or_else(|| "document".to_string());
-    let dtype = doc_type.unwrap_or_else(|| "General".to_string());
-    let provider = state.ai_provider.lock().unwrap().clone();
-    if !provider.is_available() {
-        return Err("no_api_key".to_string());
+    let mut events = store
+        .list_audit_log(pid, None, None, None, i64::MAX as usize)
+        .await
+        .map_err(|e| e.to_string())?;
+    let audited: std::collections::HashSet<Uuid> = events.iter().map(|e| e.entity_id).collect();
+
+    let derived = |entity_type: &str, entity_id: Uuid, action: &str, actor: &str, summary: String, at| AuditEntry {
+        id: Uuid::new_v4(),
+        project_id: pid,
+        entity_type: entity_type.to_string(),
+        entity_id,
+        action: action.to_string(),
+        actor: actor.to_string(),
+        summary,
+        changed_at: at,
+    };
+
+    let nodes = store.list_nodes(pid).await.map_err(|e| e.to_string())?;
+    let node_names: std::collections::HashMap<Uuid, &str> =
+        nodes.iter().map(|n| (n.id, n.name.as_str())).collect();
+    let name_of = |id: &Uuid| node_names.get(id).copied().unwrap_or("(deleted)").to_string();
+
+    for n in nodes.iter().filter(|n| !audited.contains(&n.id)) {
+        events.push(derived("node", n.id, "create", "", format!("created '{}'", n.name), n.created_at));
+        if n.modified_at > n.created_at {
+            events.push(derived("node", n.id, "update", "", format!("updated '{}'", n.name), n.modified_at));
+        }
     }
 
-    // Read Ollama settings from the store (same keys as set_ollama_config).
-    let base_url = state
-        .store
-        .get_setting("ai.ollama.base_url", None)
-        .await
-        .unwrap_or(None)
-        .unwrap_or_else(|| "http://localhost:11434".to_string());
+    let edges = store.list_edges(pid).await.map_err(|e| e.to_string())?;
+    for e in edges.iter().filter(|e| !audited.contains(&e.id)) {
+        let summary = format!("linked '{}' «{}» '{}'", name_of(&e.source_id), e.kind, name_of(&e.target_id));
+        events.push(derived("edge", e.id, "create", "", summary, e.created_at));
+    }
 
-    let chat_model = state
-        .store
-        .get_setting("ai.ollama.model", None)
-        .await
-        .unwrap_or(None)
-        .unwrap_or_else(|| "qwen2.5:7b".to_string());
+    for el in store.list_project_diagram_elements(pid).await.map_err(|e| e.to_string())? {
+        let name = name_of(&el.node_id);
+        events.push(derived("diagram_element", el.id, "create", "", format!("placed '{name}' on diagram"), el.created_at));
+        if el.modified_at > el.created_at {
+            events.push(derived("diagram_element", el.id, "update", "", format!("moved '{name}'"), el.modified_at));
+        }
+    }
+
+    for c in store.list_project_req_comments(pid).await.map_err(|e| e.to_string())? {
+        let target = name_of(&c.node_id);
+        let verb = if c.parent_id.is_some() { "replied on" } else { "commented on" };
+        events.push(derived("comment", c.id, "create", &c.author, format!("{verb} '{target}'"), c.created_at));
+        if c.updated_at > c.created_at {
+            events.push(derived("comment", c.id, "update", &c.author, format!("edited comment on '{target}'"), c.updated_at));
+        }
+        if let Some(at) = c.resolved_at {
+            let by = c.resolved_by.as_deref().unwrap_or("");
+            events.push(derived("comment", c.id, "resolve", by, format!("resolved comment on '{target}'"), at));
+        }
+    }
+
+    for s in store.list_review_sessions(pid).await.map_err(|e| e.to_string())? {
+        events.push(derived("review", s.id, "create", &s.created_by, format!("opened review '{}'", s.title), s.created_at));
+        for item in &s.items {
+            if let (Some(verdict), Some(at)) = (&item.verdict, item.verdict_at) {
+                let by = item.verdict_by.as_deref().unwrap_or("");
+                let summary = format!("{verdict} '{}' in review '{}'", name_of(&item.node_id), s.title);
+                events.push(derived("review_item", item.id, "verdict", by, summary, at));
+            }
+        }
+        if let Some(at) = s.closed_at {
+            events.push(derived("review", s.id, "close", &s.created_by, format!("closed review '{}'", s.title), at));
+        }
+    }
+
+    events.sort_by_key(|e| e.changed_at);
+    serde_json::to_string_pretty(&events).map_err(|e| e.to_string())
+}
+
+// ── App / database info ───────────────────────────────────────────────────────
+
+/// Diagnostics for support requests ("what schema is your DB on?").
+#[derive(Debug, Clone, Serialize)]
+pub struct AppInfo {
+    pub app_version: String,
+    pub db_path: String,
+    pub db_size_bytes: u64,
+    pub wal_size_bytes: u64,
+    pub journal_mode: String,
+    pub migrations: Vec<MigrationStatus>,
+    pub pending_migrations: usize,
+    pub project_count: i64,
+    pub node_count: i64,
+    pub edge_count: i64,
+    pub ai_provider: String,
+    /// Set when a newer app version has used this database.
+    pub version_warning: Option<String>,
+}
 
-    let embed_model = state
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn app_info(state: State<'_, AppState>) -> Result<AppInfo, String> {
+    let store = &state.store;
+    let migrations = store.migration_status().await.map_err(|e| e.to_string())?;
+    let (project_count, node_count, edge_count) =
+        store.model_totals().await.map_err(|e| e.to_string())?;
+    let file_size = |path: &str| std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+
+    Ok(AppInfo {
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        db_path: store.db_path().to_string(),
+        db_size_bytes: file_size(store.db_path()),
+        wal_size_bytes: file_size(&format!("{}-wal", store.db_path())),
+        journal_mode: store.journal_mode().await.map_err(|e| e.to_string())?,
+        pending_migrations: migrations.iter().filter(|m| !m.applied).count(),
+        migrations,
+        project_count,
+        node_count,
+        edge_count,
+        ai_provider: state.ai_provider.lock().unwrap().name().to_string(),
+        version_warning: store.version_warning().map(str::to_string),
+    })
+}
+
+/// Rows in a project that this build can't interpret (unknown node, edge or
+/// diagram kinds, usually from a newer app version). Listings skip them.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, fields(project_id = %project_id), err)]
+pub async fn data_health(
+    project_id: String,
+    state: State<'_, AppState>,
+) -> Result<DataHealthReport, String> {
+    let pid = parse_uuid("project_id", &project_id).map_err(|e| e.to_string())?;
+    let unrecognized = state
         .store
-        .get_setting("ai.ollama.embed_model", None)
+        .list_unrecognized_rows(pid)
         .await
-        .unwrap_or(None)
-        .unwrap_or_else(|| "nomic-embed-text".to_string());
+        .map_err(|e| e.to_string())?;
+    Ok(DataHealthReport { project_id: pid, unrecognized })
+}
 
-    let cfg = GraphRagExtractorConfig {
-        ollama_base_url: base_url,
-        ollama_chat_model: chat_model,
-        ollama_embed_model: embed_model,
-        ..Default::default()
-    };
+// ── Database maintenance ──────────────────────────────────────────────────────
 
-    // Cap input the same way ai_extract_requirements does for local models.
-    let capped: String = text.chars().take(60_000).collect();
+#[derive(Debug, Clone, Serialize)]
+struct DbMaintenanceProgress {
+    action: String,
+    step: usize,
+    total: usize,
+    done: bool,
+}
 
-    // Build graph context first, then run the same extraction prompt used by
-    // run_single_extraction with this context injected.
-    let graph_context = match build_requirement_enrichment_context(&capped, &cfg).await {
-        Ok(ctx) => ctx,
-        Err(e) => {
-            eprintln!("GraphRAG context build warning (continuing without): {e}");
-            String::new()
-        }
-    };
+/// Run housekeeping `actions` in order: "integrity_check", "vacuum",
+/// "orphan_scan", "orphan_cleanup". Progress is reported on
+/// `events::DB_MAINTENANCE_PROGRESS` as each action starts and finishes.
+#[tauri::command]
+#[tracing::instrument(target = "command", skip_all, err)]
+pub async fn db_maintenance(
+    actions: Vec<String>,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<DbMaintenanceReport, String> {
+    const KNOWN: [&str; 4] = ["integrity_check", "vacuum", "orphan_scan", "orphan_cleanup"];
+    if let Some(bad) = actions.iter().find(|a| !KNOWN.contains(&a.as_str())) {
+        return Err(format!("unknown maintenance action '{bad}'"));
+    }
 
-    let graph_context = graph_context.trim().to_string();
-    let results = if graph_context.is_empty() {
-        run_chunked_local_extraction(provider, &capped, &doc_label, &dtype, None).await
-    } else {
-        run_chunked_local_extraction(
-            provider,
-            &capped,
-            &doc_label,
-            &dtype,
-            Some(graph_context.as_str()),
-        )
-        .await
+    // VACUUM and the full-table scans can take a while on large databases;
+    // keep them off the async worker threads.
+    let store = state.store.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        tauri::async_runtime::block_on(run_db_maintenance(&store, &actions, &app))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())
+}
+
+async fn run_db_maintenance(
+    store: &crate::core::store::Store,
+    actions: &[String],
+    app: &tauri::AppHandle,
+) -> anyhow::Result<DbMaintenanceReport> {
+    use tauri::Emitter;
+
+    let mut report = DbMaintenanceReport::default();
+    let total = actions.len();
+    let progress = |step: usize, action: &str, done: bool| {
+        let _ = app.emit(
+            crate::events::DB_MAINTENANCE_PROGRESS,
+            DbMaintenanceProgress { action: action.to_string(), step, total, done },
+        );
     };
 
-    let output = serde_json::json!({ "results": results });
-    Ok(output.to_string())
+    for (i, action) in actions.iter().enumerate() {
+        progress(i + 1, action, false);
+        match action.as_str() {
+            "integrity_check" => report.integrity = Some(store.integrity_check().await?),
+            "vacuum" => {
+                report.size_before_bytes = Some(store.database_size_bytes().await?);
+                store.vacuum().await?;
+                report.size_after_bytes = Some(store.database_size_bytes().await?);
+            }
+            "orphan_scan" => report.orphans = Some(store.scan_orphans().await?),
+            "orphan_cleanup" => report.removed = Some(store.cleanup_orphans().await?),
+            _ => unreachable!("actions are validated before dispatch"),
+        }
+        progress(i + 1, action, true);
+    }
+
+    Ok(report)
 }
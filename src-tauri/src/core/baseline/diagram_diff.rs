@@ -0,0 +1,361 @@
+/// Layout changes on one diagram since a baseline, for change boards that
+/// sign off on pictures rather than model data.
+///
+/// Elements are matched by the node they show, so an element removed and
+/// placed again counts as moved, not removed and added. Edges are checked
+/// when either end, before or after, is on the diagram. The annotated SVG
+/// draws the current layout with changes outlined: added green, moved
+/// orange (with a dashed ghost at the old position), resized blue, removed
+/// as grey ghosts where they used to be.
+use crate::core::export::xml_escape;
+use crate::core::model::{DiagramElement, Edge, Point};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use uuid::Uuid;
+
+/// Movement below this many pixels is treated as jitter.
+pub const DEFAULT_MOVE_THRESHOLD_PX: f64 = 2.0;
+
+/// Width/height changes below this are float noise.
+const SIZE_EPSILON_PX: f64 = 0.5;
+
+const SVG_MARGIN: f64 = 24.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlacedElement {
+    pub node_id: Uuid,
+    pub name: String,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ElementMove {
+    pub node_id: Uuid,
+    pub name: String,
+    pub from: Point,
+    pub to: Point,
+    pub distance: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Size {
+    pub width: f64,
+    pub height: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ElementResize {
+    pub node_id: Uuid,
+    pub name: String,
+    pub from: Size,
+    pub to: Size,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EdgeEndpointChange {
+    pub edge_id: Uuid,
+    pub kind: String,
+    pub label: String,
+    pub from_source: Uuid,
+    pub from_target: Uuid,
+    pub to_source: Uuid,
+    pub to_target: Uuid,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiagramBaselineDiff {
+    pub diagram_id: Uuid,
+    pub baseline_id: Uuid,
+    pub baseline_name: String,
+    #[serde(flatten)]
+    pub changes: LayoutChanges,
+    /// Annotated SVG of the current layout, when requested.
+    #[serde(default)]
+    pub svg: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LayoutChanges {
+    /// False when the baseline was taken before diagrams were captured;
+    /// every element then shows as added.
+    pub layout_in_baseline: bool,
+    pub threshold_px: f64,
+    pub added: Vec<PlacedElement>,
+    pub removed: Vec<PlacedElement>,
+    pub moved: Vec<ElementMove>,
+    pub resized: Vec<ElementResize>,
+    pub edges_rewired: Vec<EdgeEndpointChange>,
+    pub unchanged: usize,
+}
+
+/// The diagram's elements as captured in a baseline snapshot, or `None`
+/// if the snapshot predates diagram capture.
+pub fn baseline_elements(
+    snapshot: &Value,
+    diagram_id: Uuid,
+) -> Result<Option<Vec<DiagramElement>>, String> {
+    let Some(Value::Array(elements)) = snapshot.get("diagram_elements") else {
+        return Ok(None);
+    };
+    let wanted = diagram_id.to_string();
+    elements
+        .iter()
+        .filter(|e| e.get("diagram_id").and_then(Value::as_str) == Some(wanted.as_str()))
+        .map(|e| {
+            serde_json::from_value(e.clone())
+                .map_err(|err| format!("baseline diagram element could not be read: {err}"))
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(Some)
+}
+
+/// The edges captured in a baseline snapshot. Edges that no longer parse
+/// (e.g. a kind this build doesn't know) are skipped.
+pub fn baseline_edges(snapshot: &Value) -> Vec<Edge> {
+    match snapshot.get("edges") {
+        Some(Value::Array(edges)) => edges
+            .iter()
+            .filter_map(|e| serde_json::from_value(e.clone()).ok())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Node names captured in a baseline snapshot, read without building nodes.
+pub fn baseline_node_names(snapshot: &Value) -> HashMap<Uuid, String> {
+    match snapshot.get("nodes") {
+        Some(Value::Array(nodes)) => nodes
+            .iter()
+            .filter_map(|n| {
+                let id = n.get("id")?.as_str()?.parse().ok()?;
+                Some((id, n.get("name")?.as_str()?.to_string()))
+            })
+            .collect(),
+        _ => HashMap::new(),
+    }
+}
+
+/// Compare the diagram's elements and the edges touching them. `names`
+/// should cover both current and baseline nodes; missing names show as
+/// the node id.
+pub fn diff(
+    before: Option<&[DiagramElement]>,
+    after: &[DiagramElement],
+    edges_before: &[Edge],
+    edges_after: &[Edge],
+    names: &HashMap<Uuid, String>,
+    threshold_px: f64,
+) -> LayoutChanges {
+    let name = |id: Uuid| names.get(&id).cloned().unwrap_or_else(|| id.to_string());
+    let placed = |e: &DiagramElement| PlacedElement {
+        node_id: e.node_id,
+        name: name(e.node_id),
+        x: e.x,
+        y: e.y,
+        width: e.width,
+        height: e.height,
+    };
+    let before_list = before.unwrap_or(&[]);
+    let old: HashMap<Uuid, &DiagramElement> = before_list.iter().map(|e| (e.node_id, e)).collect();
+    let new: HashMap<Uuid, &DiagramElement> = after.iter().map(|e| (e.node_id, e)).collect();
+
+    let mut report = LayoutChanges {
+        layout_in_baseline: before.is_some(),
+        threshold_px,
+        ..LayoutChanges::default()
+    };
+
+    for element in after {
+        let Some(prev) = old.get(&element.node_id) else {
+            report.added.push(placed(element));
+            continue;
+        };
+        let distance = (element.x - prev.x).hypot(element.y - prev.y);
+        let moved = distance > threshold_px;
+        let resized = (element.width - prev.width).abs() > SIZE_EPSILON_PX
+            || (element.height - prev.height).abs() > SIZE_EPSILON_PX;
+        if moved {
+            report.moved.push(ElementMove {
+                node_id: element.node_id,
+                name: name(element.node_id),
+                from: Point { x: prev.x, y: prev.y },
+                to: Point { x: element.x, y: element.y },
+                distance,
+            });
+        }
+        if resized {
+            report.resized.push(ElementResize {
+                node_id: element.node_id,
+                name: name(element.node_id),
+                from: Size { width: prev.width, height: prev.height },
+                to: Size { width: element.width, height: element.height },
+            });
+        }
+        if !moved && !resized {
+            report.unchanged += 1;
+        }
+    }
+    report.removed = before_list
+        .iter()
+        .filter(|e| !new.contains_key(&e.node_id))
+        .map(placed)
+        .collect();
+
+    let on_diagram = |id: &Uuid| old.contains_key(id) || new.contains_key(id);
+    let prev_edges: HashMap<Uuid, &Edge> = edges_before.iter().map(|e| (e.id, e)).collect();
+    for edge in edges_after {
+        let Some(prev) = prev_edges.get(&edge.id) else {
+            continue;
+        };
+        let touches = [prev.source_id, prev.target_id, edge.source_id, edge.target_id]
+            .iter()
+            .any(on_diagram);
+        if touches && (prev.source_id != edge.source_id || prev.target_id != edge.target_id) {
+            report.edges_rewired.push(EdgeEndpointChange {
+                edge_id: edge.id,
+                kind: edge.kind.to_string(),
+                label: edge.label.clone(),
+                from_source: prev.source_id,
+                from_target: prev.target_id,
+                to_source: edge.source_id,
+                to_target: edge.target_id,
+            });
+        }
+    }
+    report
+}
+
+/// Draw the current layout with the changes marked. Edges are straight
+/// lines between element centres; rewired ones are drawn in red.
+pub fn annotated_svg(
+    report: &DiagramBaselineDiff,
+    after: &[DiagramElement],
+    edges: &[Edge],
+    names: &HashMap<Uuid, String>,
+) -> String {
+    let changes = &report.changes;
+    let added: HashSet<Uuid> = changes.added.iter().map(|e| e.node_id).collect();
+    let moved: HashSet<Uuid> = changes.moved.iter().map(|m| m.node_id).collect();
+    let resized: HashSet<Uuid> = changes.resized.iter().map(|r| r.node_id).collect();
+    let rewired: HashSet<Uuid> = changes.edges_rewired.iter().map(|e| e.edge_id).collect();
+    let by_node: HashMap<Uuid, &DiagramElement> = after.iter().map(|e| (e.node_id, e)).collect();
+
+    // Canvas bounds cover current elements, ghosts and old positions.
+    let mut boxes: Vec<Rect> = after.iter().map(|e| Rect(e.x, e.y, e.width, e.height)).collect();
+    boxes.extend(changes.removed.iter().map(|e| Rect(e.x, e.y, e.width, e.height)));
+    boxes.extend(changes.moved.iter().filter_map(|m| {
+        by_node.get(&m.node_id).map(|e| Rect(m.from.x, m.from.y, e.width, e.height))
+    }));
+    let (min_x, min_y, max_x, max_y) = if boxes.is_empty() {
+        (0.0, 0.0, 0.0, 0.0)
+    } else {
+        (
+            boxes.iter().map(|b| b.0).fold(f64::INFINITY, f64::min),
+            boxes.iter().map(|b| b.1).fold(f64::INFINITY, f64::min),
+            boxes.iter().map(|b| b.0 + b.2).fold(f64::NEG_INFINITY, f64::max),
+            boxes.iter().map(|b| b.1 + b.3).fold(f64::NEG_INFINITY, f64::max),
+        )
+    };
+    let shift = |r: Rect| Rect(r.0 + SVG_MARGIN - min_x, r.1 + SVG_MARGIN - min_y, r.2, r.3);
+    let width = max_x - min_x + 2.0 * SVG_MARGIN;
+    let height = max_y - min_y + 2.0 * SVG_MARGIN;
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width:.0}\" height=\"{height:.0}\" \
+viewBox=\"0 0 {width:.0} {height:.0}\" font-family=\"sans-serif\" font-size=\"12\">"
+    );
+    let _ = writeln!(svg, "<title>Changes since {}</title>", xml_escape(&report.baseline_name));
+
+    for edge in edges {
+        let (Some(s), Some(t)) = (by_node.get(&edge.source_id), by_node.get(&edge.target_id))
+        else {
+            continue;
+        };
+        let stroke = if rewired.contains(&edge.id) { "#d32f2f" } else { "#9e9e9e" };
+        let (a, b) = (shift(Rect::of(s)).centre(), shift(Rect::of(t)).centre());
+        line(&mut svg, a, b, stroke, false);
+    }
+    for ghost in &changes.removed {
+        let r = shift(Rect(ghost.x, ghost.y, ghost.width, ghost.height));
+        boxed(&mut svg, r, &ghost.name, "#9e9e9e", true, 0.4);
+    }
+    for m in &changes.moved {
+        let Some(e) = by_node.get(&m.node_id) else {
+            continue;
+        };
+        let old = shift(Rect(m.from.x, m.from.y, e.width, e.height));
+        boxed(&mut svg, old, "", MOVED, true, 0.5);
+        line(&mut svg, old.centre(), shift(Rect::of(e)).centre(), MOVED, true);
+    }
+    for e in after {
+        let stroke = if added.contains(&e.node_id) {
+            "#2e7d32"
+        } else if moved.contains(&e.node_id) {
+            MOVED
+        } else if resized.contains(&e.node_id) {
+            "#1565c0"
+        } else {
+            UNCHANGED
+        };
+        let name = names.get(&e.node_id).cloned().unwrap_or_else(|| e.node_id.to_string());
+        boxed(&mut svg, shift(Rect::of(e)), &name, stroke, false, 1.0);
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+const MOVED: &str = "#ef6c00";
+const UNCHANGED: &str = "#424242";
+
+/// x, y, width, height.
+#[derive(Clone, Copy)]
+struct Rect(f64, f64, f64, f64);
+
+impl Rect {
+    fn of(e: &DiagramElement) -> Self {
+        Rect(e.x, e.y, e.width, e.height)
+    }
+
+    fn centre(self) -> (f64, f64) {
+        (self.0 + self.2 / 2.0, self.1 + self.3 / 2.0)
+    }
+}
+
+/// A labelled box; an empty `text` draws no label.
+fn boxed(svg: &mut String, r: Rect, text: &str, stroke: &str, dashed: bool, opacity: f64) {
+    let Rect(x, y, w, h) = r;
+    let dash = if dashed { r#" stroke-dasharray="6 4""# } else { "" };
+    let stroke_width = if stroke == UNCHANGED { 1.0 } else { 2.5 };
+    let _ = writeln!(
+        svg,
+        "<rect x=\"{x:.1}\" y=\"{y:.1}\" width=\"{w:.1}\" height=\"{h:.1}\" rx=\"4\" fill=\"white\" \
+fill-opacity=\"{opacity}\" stroke=\"{stroke}\" stroke-width=\"{stroke_width}\" \
+stroke-opacity=\"{opacity}\"{dash}/>"
+    );
+    if !text.is_empty() {
+        let (cx, cy) = r.centre();
+        let _ = writeln!(
+            svg,
+            "<text x=\"{cx:.1}\" y=\"{cy:.1}\" text-anchor=\"middle\" dominant-baseline=\"middle\" \
+fill-opacity=\"{opacity}\">{}</text>",
+            xml_escape(text)
+        );
+    }
+}
+
+fn line(svg: &mut String, (x1, y1): (f64, f64), (x2, y2): (f64, f64), stroke: &str, dashed: bool) {
+    let dash = if dashed { r#" stroke-dasharray="4 3""# } else { "" };
+    let _ = writeln!(
+        svg,
+        "<line x1=\"{x1:.1}\" y1=\"{y1:.1}\" x2=\"{x2:.1}\" y2=\"{y2:.1}\" stroke=\"{stroke}\" \
+stroke-width=\"1.5\"{dash}/>"
+    );
+}
+
@@ -0,0 +1,158 @@
+/// Lookups into baseline snapshots without deserializing the whole model.
+///
+/// A snapshot is `{ "nodes": [...], "edges": [...], "diagrams": [...],
+/// "diagram_elements": [...] }` as written by `create_baseline`; baselines
+/// taken before diagrams were captured only have nodes and edges.
+/// Baselines never change once taken, so the node objects are indexed by
+/// id the first time one is asked for and the index is kept in `AppState`
+/// for later lookups. Only the requested node is turned into a `Node`.
+use crate::core::model::{CoverageMetrics, ModelBaseline, Node};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use uuid::Uuid;
+
+pub mod diagram_diff;
+
+/// Indexed baselines kept in memory; the least recently used is dropped.
+const MAX_CACHED_BASELINES: usize = 4;
+
+#[derive(Debug)]
+pub struct BaselineIndex {
+    pub baseline_id: Uuid,
+    pub project_id: Uuid,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    nodes: HashMap<Uuid, Value>,
+}
+
+impl BaselineIndex {
+    /// Take the node objects out of the snapshot, keyed by their `id`.
+    /// Entries without a parseable id are skipped.
+    pub fn build(baseline: ModelBaseline) -> Self {
+        let mut snapshot = baseline.snapshot;
+        let nodes = match snapshot.get_mut("nodes").map(Value::take) {
+            Some(Value::Array(nodes)) => nodes
+                .into_iter()
+                .filter_map(|node| {
+                    let id = node.get("id")?.as_str()?.parse().ok()?;
+                    Some((id, node))
+                })
+                .collect(),
+            _ => HashMap::new(),
+        };
+        Self {
+            baseline_id: baseline.id,
+            project_id: baseline.project_id,
+            name: baseline.name,
+            created_at: baseline.created_at,
+            nodes,
+        }
+    }
+
+    /// The node as it was in the baseline.
+    pub fn node(&self, node_id: Uuid) -> Result<Option<Node>, String> {
+        self.nodes
+            .get(&node_id)
+            .map(|raw| {
+                serde_json::from_value(raw.clone())
+                    .map_err(|e| format!("baseline node {node_id} could not be read: {e}"))
+            })
+            .transpose()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct BaselineIndexCache {
+    /// Most recently used last.
+    entries: Vec<Arc<BaselineIndex>>,
+}
+
+impl BaselineIndexCache {
+    pub fn get(&mut self, baseline_id: Uuid) -> Option<Arc<BaselineIndex>> {
+        let pos = self.entries.iter().position(|e| e.baseline_id == baseline_id)?;
+        let entry = self.entries.remove(pos);
+        self.entries.push(entry.clone());
+        Some(entry)
+    }
+
+    pub fn insert(&mut self, index: BaselineIndex) -> Arc<BaselineIndex> {
+        self.invalidate(index.baseline_id);
+        if self.entries.len() >= MAX_CACHED_BASELINES {
+            self.entries.remove(0);
+        }
+        let index = Arc::new(index);
+        self.entries.push(index.clone());
+        index
+    }
+
+    pub fn invalidate(&mut self, baseline_id: Uuid) {
+        self.entries.retain(|e| e.baseline_id != baseline_id);
+    }
+
+    /// Drop every baseline of a deleted project.
+    pub fn invalidate_project(&mut self, project_id: Uuid) {
+        self.entries.retain(|e| e.project_id != project_id);
+    }
+}
+
+/// Requirement coverage as of `snapshot`, counted the same way as the
+/// store's `coverage_metrics` counts the live model: archived nodes and
+/// Obsolete requirements are left out, and edges from archived nodes
+/// don't count. Reads the JSON directly, so nodes this build can't
+/// deserialize still count.
+pub fn coverage_metrics(snapshot: &Value) -> CoverageMetrics {
+    let list = |key: &str| {
+        snapshot
+            .get(key)
+            .and_then(Value::as_array)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    };
+    let archived = |node: &Value| node.get("archived").and_then(Value::as_bool) == Some(true);
+    let live: HashSet<&str> = list("nodes")
+        .iter()
+        .filter(|n| !archived(n))
+        .filter_map(|n| n.get("id").and_then(Value::as_str))
+        .collect();
+    let targets = |kind: &str| -> HashSet<&str> {
+        list("edges")
+            .iter()
+            .filter(|e| e.get("kind").and_then(Value::as_str) == Some(kind))
+            .filter(|e| {
+                e.get("source_id")
+                    .and_then(Value::as_str)
+                    .is_some_and(|s| live.contains(s))
+            })
+            .filter_map(|e| e.get("target_id").and_then(Value::as_str))
+            .collect()
+    };
+    let satisfied_ids = targets("satisfies");
+    let verified_ids = targets("verifies");
+
+    let (mut total, mut satisfied, mut verified) = (0, 0, 0);
+    let (mut approved, mut with_method, mut allocated) = (0, 0, 0);
+    for node in list("nodes") {
+        if node.get("kind").and_then(Value::as_str) != Some("requirement") || archived(node) {
+            continue;
+        }
+        let data = node.get("data");
+        let field = |key: &str| data.and_then(|d| d.get(key));
+        if field("status").and_then(Value::as_str) == Some("obsolete") {
+            continue;
+        }
+        total += 1;
+        let id = node.get("id").and_then(Value::as_str).unwrap_or_default();
+        satisfied += i64::from(satisfied_ids.contains(id));
+        verified += i64::from(verified_ids.contains(id));
+        approved += i64::from(field("status").and_then(Value::as_str) == Some("approved"));
+        with_method += i64::from(field("verification_method").is_some_and(|v| !v.is_null()));
+        allocated += i64::from(
+            field("allocations")
+                .and_then(Value::as_array)
+                .is_some_and(|a| !a.is_empty()),
+        );
+    }
+    CoverageMetrics::from_counts(total, satisfied, verified, approved, with_method, allocated)
+}
@@ -0,0 +1,95 @@
+/// Requirement classification taxonomy: the labels the AI quality pass may
+/// assign. Projects override the default set with the
+/// `requirements.classification_taxonomy` setting, a JSON array of
+/// `{"label": ..., "description": ...}` in the order the prompt lists them.
+///
+/// "unknown" is always accepted and never needs listing; any returned label
+/// outside the configured set is mapped to it.
+use serde::{Deserialize, Serialize};
+
+/// Settings key holding the project's taxonomy.
+pub const TAXONOMY_SETTING: &str = "requirements.classification_taxonomy";
+
+pub const UNKNOWN: &str = "unknown";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ClassificationLabel {
+    pub label: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+/// The set the quality pass used before taxonomies were configurable.
+pub fn default_taxonomy() -> Vec<ClassificationLabel> {
+    [
+        ("system", "Behaviour or capability of the system as a whole."),
+        ("contractual", "Obligation from the contract or statement of work."),
+        ("verification", "How or when compliance is to be demonstrated."),
+        ("interface", "Exchange with another system, subsystem or user."),
+        ("constraint", "Limit on design or implementation choices."),
+    ]
+    .into_iter()
+    .map(|(label, description)| ClassificationLabel {
+        label: label.to_string(),
+        description: description.to_string(),
+    })
+    .collect()
+}
+
+/// Parse the setting value. Labels are trimmed and lowercased; blanks,
+/// duplicates and "unknown" are dropped. An empty result is an error so a
+/// bad setting doesn't silently classify everything as unknown.
+pub fn parse_taxonomy(raw: &str) -> Result<Vec<ClassificationLabel>, String> {
+    let entries: Vec<ClassificationLabel> = serde_json::from_str(raw)
+        .map_err(|e| format!("invalid {TAXONOMY_SETTING} setting: {e}"))?;
+    let mut taxonomy: Vec<ClassificationLabel> = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let label = entry.label.trim().to_lowercase();
+        if label.is_empty() || label == UNKNOWN || taxonomy.iter().any(|l| l.label == label) {
+            continue;
+        }
+        taxonomy.push(ClassificationLabel {
+            label,
+            description: entry.description.trim().to_string(),
+        });
+    }
+    if taxonomy.is_empty() {
+        return Err(format!("{TAXONOMY_SETTING} lists no classification labels"));
+    }
+    Ok(taxonomy)
+}
+
+/// The labels joined for the prompt's JSON example, ending with "unknown".
+pub fn label_choices(taxonomy: &[ClassificationLabel]) -> String {
+    taxonomy
+        .iter()
+        .map(|l| l.label.as_str())
+        .chain([UNKNOWN])
+        .collect::<Vec<_>>()
+        .join("|")
+}
+
+/// The CLASSIFICATION block of the quality-pass system prompt.
+pub fn prompt_section(taxonomy: &[ClassificationLabel]) -> String {
+    let mut out = String::from("CLASSIFICATION (choose exactly one):\n");
+    for l in taxonomy {
+        if l.description.is_empty() {
+            out.push_str(&format!("- {}\n", l.label));
+        } else {
+            out.push_str(&format!("- {}: {}\n", l.label, l.description));
+        }
+    }
+    out.push_str(&format!("- {UNKNOWN}: none of the above fits.\n"));
+    out
+}
+
+/// The configured label matching `raw` (ignoring case and whitespace), or
+/// "unknown".
+pub fn normalize(raw: &str, taxonomy: &[ClassificationLabel]) -> String {
+    let raw = raw.trim().to_lowercase();
+    taxonomy
+        .iter()
+        .find(|l| l.label == raw)
+        .map(|l| l.label.clone())
+        .unwrap_or_else(|| UNKNOWN.to_string())
+}
@@ -0,0 +1,154 @@
+/// The attention digest: open work nobody has come back to, gathered
+/// from a few dated queries into one prioritized list.
+///
+/// Rules, each with a `digest.*` setting (project, then global):
+///
+/// - `stale_comments`: unresolved comments older than
+///   `digest.comment_age_days` (7);
+/// - `open_reviews`: review sessions open longer than
+///   `digest.review_age_days` (30);
+/// - `suspect_links`: unresolved suspect links, once there are at least
+///   `digest.suspect_link_count` (10);
+/// - `stale_drafts`: draft requirements untouched for
+///   `digest.draft_age_days` (90).
+///
+/// `digest.enabled` ("false" turns it off) only gates the startup event;
+/// the command always answers. Items are ordered by how far past their
+/// threshold they are, worst first.
+use crate::core::model::{DigestBucket, DigestSample};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+pub const ENABLED_SETTING: &str = "digest.enabled";
+
+/// Sample items returned per kind.
+pub const SAMPLE_ITEMS: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DigestSettings {
+    pub enabled: bool,
+    pub comment_age_days: i64,
+    pub review_age_days: i64,
+    pub suspect_link_count: i64,
+    pub draft_age_days: i64,
+}
+
+impl Default for DigestSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            comment_age_days: 7,
+            review_age_days: 30,
+            suspect_link_count: 10,
+            draft_age_days: 90,
+        }
+    }
+}
+
+impl DigestSettings {
+    /// Every `digest.*` key this module reads.
+    pub const KEYS: [&'static str; 5] = [
+        ENABLED_SETTING,
+        "digest.comment_age_days",
+        "digest.review_age_days",
+        "digest.suspect_link_count",
+        "digest.draft_age_days",
+    ];
+
+    /// Settings from `lookup`; missing, unparsable or negative values keep
+    /// their defaults.
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let mut settings = Self::default();
+        let number = |key: &str| {
+            lookup(key)
+                .and_then(|v| v.trim().trim_matches('"').parse::<i64>().ok())
+                .filter(|v| *v >= 0)
+        };
+        if let Some(v) = number("digest.comment_age_days") {
+            settings.comment_age_days = v;
+        }
+        if let Some(v) = number("digest.review_age_days") {
+            settings.review_age_days = v;
+        }
+        if let Some(v) = number("digest.suspect_link_count") {
+            settings.suspect_link_count = v;
+        }
+        if let Some(v) = number("digest.draft_age_days") {
+            settings.draft_age_days = v;
+        }
+        if let Some(v) = lookup(ENABLED_SETTING) {
+            settings.enabled = v.trim().trim_matches('"') != "false";
+        }
+        settings
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AttentionKind {
+    StaleComments,
+    OpenReviews,
+    SuspectLinks,
+    StaleDrafts,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttentionItem {
+    pub kind: AttentionKind,
+    pub count: i64,
+    /// Age of the oldest item, in whole days.
+    pub worst_age_days: i64,
+    /// The oldest few, oldest first.
+    pub sample_items: Vec<DigestSample>,
+}
+
+/// The digest's buckets, one per rule, as queried with the settings'
+/// cutoffs.
+pub struct DigestBuckets {
+    pub stale_comments: DigestBucket,
+    pub open_reviews: DigestBucket,
+    pub suspect_links: DigestBucket,
+    pub stale_drafts: DigestBucket,
+}
+
+/// Non-empty rules as attention items, most overdue first.
+pub fn prioritize(
+    buckets: DigestBuckets,
+    settings: &DigestSettings,
+    now: DateTime<Utc>,
+) -> Vec<AttentionItem> {
+    let age_days = |bucket: &DigestBucket| {
+        bucket
+            .oldest
+            .map_or(0, |oldest| (now - oldest).num_days().max(0))
+    };
+    // How far past its threshold a rule is; 1.0 is just due.
+    let overdue = |value: i64, threshold: i64| value as f64 / threshold.max(1) as f64;
+
+    let mut ranked: Vec<(f64, AttentionItem)> = Vec::new();
+    let mut push = |kind, bucket: DigestBucket, score: f64| {
+        if bucket.count > 0 {
+            let item = AttentionItem {
+                kind,
+                count: bucket.count,
+                worst_age_days: age_days(&bucket),
+                sample_items: bucket.samples,
+            };
+            ranked.push((score, item));
+        }
+    };
+
+    let score = overdue(age_days(&buckets.stale_comments), settings.comment_age_days);
+    push(AttentionKind::StaleComments, buckets.stale_comments, score);
+    let score = overdue(age_days(&buckets.open_reviews), settings.review_age_days);
+    push(AttentionKind::OpenReviews, buckets.open_reviews, score);
+    if buckets.suspect_links.count >= settings.suspect_link_count {
+        let score = overdue(buckets.suspect_links.count, settings.suspect_link_count);
+        push(AttentionKind::SuspectLinks, buckets.suspect_links, score);
+    }
+    let score = overdue(age_days(&buckets.stale_drafts), settings.draft_age_days);
+    push(AttentionKind::StaleDrafts, buckets.stale_drafts, score);
+
+    ranked.sort_by(|a, b| b.0.total_cmp(&a.0).then(b.1.count.cmp(&a.1.count)));
+    ranked.into_iter().map(|(_, item)| item).collect()
+}
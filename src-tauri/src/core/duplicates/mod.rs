@@ -0,0 +1,232 @@
+/// Near-duplicate blocks, typically subsystems the AI allocation pass
+/// re-invented under a variant name ("Power Distribution Unit" next to
+/// "Power Distribution", "PDU" next to either).
+///
+/// Names are compared as token sets after lowercasing, expanding
+/// abbreviations through an alias map and dropping filler words such as
+/// "unit" or "subsystem". An abbreviation matching the initials of the
+/// other name counts even when it isn't in the map. Projects extend the
+/// built-in map through the `blocks.aliases` setting, a JSON object of
+/// abbreviation → expansion.
+use crate::core::model::{Node, NodeKind};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use uuid::Uuid;
+
+/// Setting holding extra aliases, project-scoped or global.
+pub const ALIASES_SETTING: &str = "blocks.aliases";
+
+/// Pairs scoring below this are not reported unless a threshold is given.
+pub const DEFAULT_THRESHOLD: f64 = 0.8;
+
+/// Score for an abbreviation that spells the other name's initials.
+const INITIALS_SCORE: f64 = 0.9;
+
+/// Words that qualify a subsystem name without telling two apart.
+const FILLER_WORDS: &[&str] = &[
+    "the",
+    "of",
+    "and",
+    "for",
+    "a",
+    "an",
+    "unit",
+    "module",
+    "subsystem",
+    "system",
+    "assembly",
+];
+
+/// Abbreviation (lowercase) → expansion.
+pub type AliasMap = BTreeMap<String, String>;
+
+/// Common avionics and spacecraft abbreviations.
+pub fn default_aliases() -> AliasMap {
+    [
+        ("pdu", "Power Distribution Unit"),
+        ("eps", "Electrical Power System"),
+        ("bms", "Battery Management System"),
+        ("obc", "Onboard Computer"),
+        ("imu", "Inertial Measurement Unit"),
+        ("gps", "Global Positioning System"),
+        ("adcs", "Attitude Determination and Control System"),
+        ("gnc", "Guidance Navigation and Control"),
+        ("tcs", "Thermal Control System"),
+        ("mcu", "Microcontroller"),
+        ("cpu", "Central Processing Unit"),
+        ("fpga", "Field Programmable Gate Array"),
+        ("rf", "Radio Frequency"),
+        ("comms", "Communication"),
+        ("hmi", "Human Machine Interface"),
+        ("ui", "User Interface"),
+        ("gcs", "Ground Control Station"),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v.to_string()))
+    .collect()
+}
+
+/// The built-in map overlaid with `raw`, the `blocks.aliases` setting.
+/// Keys are matched case-insensitively; an empty expansion removes a
+/// built-in alias.
+pub fn aliases_from_setting(raw: Option<&str>) -> Result<AliasMap, String> {
+    let mut aliases = default_aliases();
+    let Some(raw) = raw.map(str::trim).filter(|r| !r.is_empty()) else {
+        return Ok(aliases);
+    };
+    let extra: BTreeMap<String, String> =
+        serde_json::from_str(raw).map_err(|e| format!("invalid {ALIASES_SETTING} setting: {e}"))?;
+    for (abbreviation, expansion) in extra {
+        let key = abbreviation.trim().to_lowercase();
+        if expansion.trim().is_empty() {
+            aliases.remove(&key);
+        } else if !key.is_empty() {
+            aliases.insert(key, expansion.trim().to_string());
+        }
+    }
+    Ok(aliases)
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateReason {
+    /// Same words once aliases are expanded and filler dropped.
+    SameTokens,
+    /// One name is the other's initials.
+    Initials,
+    /// Overlapping but not identical words.
+    Similar,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicatePair {
+    pub a_id: Uuid,
+    pub a_name: String,
+    pub b_id: Uuid,
+    pub b_name: String,
+    /// 0..=1.
+    pub score: f64,
+    pub reason: DuplicateReason,
+}
+
+/// Lowercase words of `name`, split on anything not alphanumeric.
+fn words(name: &str) -> Vec<String> {
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Words with aliases expanded (one level) and filler dropped.
+pub fn normalized_tokens(name: &str, aliases: &AliasMap) -> BTreeSet<String> {
+    let mut tokens = BTreeSet::new();
+    for word in words(name) {
+        match aliases.get(&word) {
+            Some(expansion) => tokens.extend(words(expansion)),
+            None => {
+                tokens.insert(word);
+            }
+        }
+    }
+    tokens.retain(|t| !FILLER_WORDS.contains(&t.as_str()));
+    tokens
+}
+
+/// How alike two names are, and why. Token overlap averages Jaccard with
+/// containment, so a name that only adds a word scores well without two
+/// one-word names sharing nothing scoring at all.
+pub fn name_similarity(a: &str, b: &str, aliases: &AliasMap) -> (f64, DuplicateReason) {
+    let (x, y) = (normalized_tokens(a, aliases), normalized_tokens(b, aliases));
+    if !x.is_empty() && x == y {
+        return (1.0, DuplicateReason::SameTokens);
+    }
+    if is_initials(a, b) || is_initials(b, a) {
+        return (INITIALS_SCORE, DuplicateReason::Initials);
+    }
+    if x.is_empty() || y.is_empty() {
+        return (0.0, DuplicateReason::Similar);
+    }
+    let common = x.intersection(&y).count() as f64;
+    let jaccard = common / x.union(&y).count() as f64;
+    let containment = common / x.len().min(y.len()) as f64;
+    ((jaccard + containment) / 2.0, DuplicateReason::Similar)
+}
+
+/// Whether `short` is a single word spelling the initials of `long`'s
+/// words, with or without filler ("PDU", "PD" for "Power Distribution Unit").
+fn is_initials(short: &str, long: &str) -> bool {
+    let short_words = words(short);
+    let [abbreviation] = short_words.as_slice() else {
+        return false;
+    };
+    let long_words = words(long);
+    if abbreviation.len() < 2 || long_words.len() < 2 {
+        return false;
+    }
+    let all = initials(long_words.iter());
+    let content = initials(
+        long_words
+            .iter()
+            .filter(|w| !FILLER_WORDS.contains(&w.as_str())),
+    );
+    *abbreviation == all || *abbreviation == content
+}
+
+fn initials<'a>(words: impl Iterator<Item = &'a String>) -> String {
+    words.filter_map(|w| w.chars().next()).collect()
+}
+
+/// Pairs of unarchived blocks scoring at least `threshold`, best first.
+pub fn find_duplicates(nodes: &[Node], aliases: &AliasMap, threshold: f64) -> Vec<DuplicatePair> {
+    let blocks: Vec<&Node> = nodes
+        .iter()
+        .filter(|n| n.kind == NodeKind::Block && !n.archived && !n.name.trim().is_empty())
+        .collect();
+    let mut pairs = Vec::new();
+    for (i, a) in blocks.iter().enumerate() {
+        for b in &blocks[i + 1..] {
+            let (score, reason) = name_similarity(&a.name, &b.name, aliases);
+            if score >= threshold {
+                pairs.push(DuplicatePair {
+                    a_id: a.id,
+                    a_name: a.name.clone(),
+                    b_id: b.id,
+                    b_name: b.name.clone(),
+                    score,
+                    reason,
+                });
+            }
+        }
+    }
+    pairs.sort_by(|x, y| {
+        y.score
+            .total_cmp(&x.score)
+            .then_with(|| x.a_name.to_lowercase().cmp(&y.a_name.to_lowercase()))
+    });
+    pairs
+}
+
+/// Aliases as prompt text, one "ABBR = Expansion" per line, for the AI
+/// allocation pass.
+pub fn aliases_prompt(aliases: &AliasMap) -> String {
+    aliases
+        .iter()
+        .map(|(k, v)| format!("{} = {v}", k.to_uppercase()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The existing subsystem name `proposed` duplicates, if any.
+pub fn matching_name<'a>(
+    proposed: &str,
+    existing: impl IntoIterator<Item = &'a str>,
+    aliases: &AliasMap,
+    threshold: f64,
+) -> Option<&'a str> {
+    existing
+        .into_iter()
+        .map(|name| (name, name_similarity(proposed, name, aliases).0))
+        .filter(|(_, score)| *score >= threshold)
+        .max_by(|x, y| x.1.total_cmp(&y.1))
+        .map(|(name, _)| name)
+}
@@ -0,0 +1,499 @@
+/// The example project offered to new users: a small survey UAV model that
+/// touches every part of the app. Five blocks, ten requirements with req_ids
+/// and verification methods, one test case per requirement, «composes»,
+/// «satisfies», «verifies» and «connects» edges, a BDD and a state machine
+/// with placed elements, a statement of work whose sections the
+/// requirements cite as their source, and a saved simulation scenario.
+///
+/// `build` is a pure factory; `Store::create_example_project` writes the
+/// result through the ordinary store calls, so creating it exercises the
+/// whole stack. Tests get it from `test_support::seed_example_project`.
+use crate::core::model::{
+    BlockData, Diagram, DiagramElement, DiagramKind, Document, DocumentSection, Edge, EdgeKind,
+    Node, NodeData, NodeKind, Project, ProjectTemplateContent, RequirementData,
+    RequirementPriority, RequirementStatus, SectionType, SimParams, SimulationScenario,
+    SimulationScenarioEvent, StateData, TestCaseData, VerificationMethod,
+};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Global setting; "false" stops the project list offering the example
+/// when there are no projects yet.
+pub const OFFER_SETTING: &str = "onboarding.offer_example";
+
+pub const PROJECT_NAME: &str = "Example: Survey UAV";
+
+const DOCUMENT_NAME: &str = "UAV Statement of Work";
+
+/// A built example, ready to be written.
+pub struct ExampleProject {
+    pub project: Project,
+    /// Nodes, edges, diagrams and diagram elements.
+    pub content: ProjectTemplateContent,
+    pub document: Document,
+    pub sections: Vec<DocumentSection>,
+    pub scenario: SimulationScenario,
+}
+
+/// (section_ref, title) of the statement of work, in order.
+const SECTIONS: &[(&str, &str)] = &[
+    ("3.1", "Endurance"),
+    ("3.2", "Mass and thrust"),
+    ("3.3", "Flight performance"),
+    ("3.4", "Safety behaviour"),
+    ("3.5", "Communications"),
+    ("3.6", "Environment"),
+];
+
+struct ExampleRequirement {
+    name: &'static str,
+    text: &'static str,
+    section: &'static str,
+    /// Index into the subsystem blocks.
+    block: usize,
+    method: VerificationMethod,
+    test: &'static str,
+    expected: &'static str,
+}
+
+const AIRFRAME: usize = 0;
+const PROPULSION: usize = 1;
+const FLIGHT_CONTROLLER: usize = 2;
+const BATTERY: usize = 3;
+
+const REQUIREMENTS: &[ExampleRequirement] = &[
+    ExampleRequirement {
+        name: "Endurance",
+        text: "The UAV shall remain airborne for at least 45 minutes with the standard payload.",
+        section: "3.1",
+        block: BATTERY,
+        method: VerificationMethod::Test,
+        test: "Endurance flight",
+        expected: "Flight time of 45 minutes or more before the low battery landing.",
+    },
+    ExampleRequirement {
+        name: "Maximum takeoff mass",
+        text: "The UAV shall have a maximum takeoff mass of no more than 4.5 kg.",
+        section: "3.2",
+        block: AIRFRAME,
+        method: VerificationMethod::Inspection,
+        test: "Weigh-in",
+        expected: "Ready-to-fly mass with payload of 4.5 kg or less.",
+    },
+    ExampleRequirement {
+        name: "Thrust margin",
+        text: "The propulsion system shall provide a thrust-to-weight ratio of at least 2:1 at \
+               maximum takeoff mass.",
+        section: "3.2",
+        block: PROPULSION,
+        method: VerificationMethod::Analysis,
+        test: "Thrust margin analysis",
+        expected: "Static thrust from the motor data of at least twice the takeoff weight.",
+    },
+    ExampleRequirement {
+        name: "Wind tolerance",
+        text: "The UAV shall maintain stable flight in sustained winds of up to 10 m/s.",
+        section: "3.3",
+        block: FLIGHT_CONTROLLER,
+        method: VerificationMethod::Test,
+        test: "Wind flight test",
+        expected: "Attitude stays within 10 degrees of level throughout the flight.",
+    },
+    ExampleRequirement {
+        name: "Cruise speed",
+        text: "The UAV shall cruise at 15 m/s or faster in still air.",
+        section: "3.3",
+        block: PROPULSION,
+        method: VerificationMethod::Test,
+        test: "Cruise speed run",
+        expected: "Average ground speed of 15 m/s or more over both legs.",
+    },
+    ExampleRequirement {
+        name: "Position hold",
+        text: "The flight controller shall hold position within 1 m horizontally in calm air.",
+        section: "3.3",
+        block: FLIGHT_CONTROLLER,
+        method: VerificationMethod::Test,
+        test: "Position hold test",
+        expected: "Logged horizontal drift stays within 1 m for two minutes.",
+    },
+    ExampleRequirement {
+        name: "Return to home",
+        text: "The UAV shall return to its launch point automatically when the command link is \
+               lost for more than 5 seconds.",
+        section: "3.4",
+        block: FLIGHT_CONTROLLER,
+        method: VerificationMethod::Demonstration,
+        test: "Link loss demonstration",
+        expected: "The UAV turns for home within 6 seconds of the link being cut.",
+    },
+    ExampleRequirement {
+        name: "Low battery landing",
+        text: "The UAV shall initiate a landing when the remaining battery charge falls below \
+               20 percent.",
+        section: "3.4",
+        block: BATTERY,
+        method: VerificationMethod::Demonstration,
+        test: "Low battery demonstration",
+        expected: "Landing starts when the reported charge crosses 20 percent.",
+    },
+    ExampleRequirement {
+        name: "Command link range",
+        text: "The UAV shall receive commands from the ground station at ranges of up to 2 km.",
+        section: "3.5",
+        block: FLIGHT_CONTROLLER,
+        method: VerificationMethod::Test,
+        test: "Range test",
+        expected: "Commands are acknowledged at every waypoint out to 2 km.",
+    },
+    ExampleRequirement {
+        name: "Operating temperature",
+        text: "The UAV shall operate in ambient temperatures from -10 °C to 40 °C.",
+        section: "3.6",
+        block: AIRFRAME,
+        method: VerificationMethod::Analysis,
+        test: "Thermal analysis",
+        expected: "Component temperatures stay within rating across the ambient range.",
+    },
+];
+
+/// Build the example with fresh ids.
+pub fn build(now: DateTime<Utc>) -> ExampleProject {
+    let project = Project {
+        id: Uuid::new_v4(),
+        name: PROJECT_NAME.to_string(),
+        description: "A small survey drone showing blocks, requirements, test cases, \
+                      traceability, diagrams, a source document and a simulation scenario."
+            .to_string(),
+        created_at: now,
+        modified_at: now,
+    };
+    let project_id = project.id;
+
+    let meta = || {
+        let mut meta: HashMap<String, Value> = HashMap::new();
+        meta.insert("actor".to_string(), Value::from("system"));
+        meta.insert("change_source".to_string(), Value::from("example"));
+        meta
+    };
+    let node = |kind: NodeKind, name: &str, description: &str, data: NodeData| Node {
+        id: Uuid::new_v4(),
+        project_id,
+        kind,
+        name: name.to_string(),
+        description: description.to_string(),
+        data,
+        meta: meta(),
+        archived: false,
+        created_at: now,
+        modified_at: now,
+    };
+    let edge = |kind: EdgeKind, source_id: Uuid, target_id: Uuid, label: &str| Edge {
+        id: Uuid::new_v4(),
+        project_id,
+        kind,
+        source_id,
+        target_id,
+        label: label.to_string(),
+        meta: HashMap::new(),
+        created_at: now,
+        modified_at: now,
+    };
+    let block = |name: &str, description: &str, sim_params: Option<SimParams>| {
+        let data = BlockData {
+            sim_params,
+            ..BlockData::default()
+        };
+        node(NodeKind::Block, name, description, NodeData::Block(data))
+    };
+
+    // ── Structure ────────────────────────────────────────────────────────────
+    let mut uav = block(
+        "Survey UAV",
+        "Battery-powered quadcopter for aerial survey.",
+        None,
+    );
+    uav.meta
+        .insert("system_root".to_string(), Value::Bool(true));
+    let subsystems = vec![
+        block(
+            "Airframe",
+            "Frame, arms, landing gear and payload mount.",
+            None,
+        ),
+        block(
+            "Propulsion System",
+            "Motors, speed controllers and propellers.",
+            Some(SimParams {
+                processing_time_ms: Some(20.0),
+                input_signal_type: Some("thrust_cmd".to_string()),
+                ..SimParams::default()
+            }),
+        ),
+        block(
+            "Flight Controller",
+            "Autopilot running attitude, navigation and failsafe logic.",
+            Some(SimParams {
+                processing_time_ms: Some(5.0),
+                input_signal_type: Some("power".to_string()),
+                output_signal_type: Some("thrust_cmd".to_string()),
+                ..SimParams::default()
+            }),
+        ),
+        block(
+            "Battery Pack",
+            "6S lithium-polymer pack with charge monitoring.",
+            Some(SimParams {
+                output_signal_type: Some("power".to_string()),
+                ..SimParams::default()
+            }),
+        ),
+    ];
+
+    let mut edges: Vec<Edge> = subsystems
+        .iter()
+        .map(|part| edge(EdgeKind::Composes, uav.id, part.id, ""))
+        .collect();
+    edges.push(edge(
+        EdgeKind::Connects,
+        subsystems[BATTERY].id,
+        subsystems[FLIGHT_CONTROLLER].id,
+        "power",
+    ));
+    edges.push(edge(
+        EdgeKind::Connects,
+        subsystems[FLIGHT_CONTROLLER].id,
+        subsystems[PROPULSION].id,
+        "thrust_cmd",
+    ));
+
+    // ── Requirements and test cases ──────────────────────────────────────────
+    let mut requirements = Vec::new();
+    let mut test_cases = Vec::new();
+    for (i, r) in REQUIREMENTS.iter().enumerate() {
+        let n = i + 1;
+        let subsystem = &subsystems[r.block];
+        let requirement = node(
+            NodeKind::Requirement,
+            r.name,
+            r.text,
+            NodeData::Requirement(RequirementData {
+                req_id: Some(format!("REQ-{n:03}")),
+                text: Some(r.text.to_string()),
+                rationale: None,
+                priority: RequirementPriority::Shall,
+                status: RequirementStatus::Approved,
+                source: Some(format!("{DOCUMENT_NAME} §{}", r.section)),
+                allocations: Some(vec![subsystem.name.clone()]),
+                verification_method: Some(r.method.clone()),
+                classification: None,
+            }),
+        );
+        let test_case = node(
+            NodeKind::TestCase,
+            &format!("TC-{n:03} {}", r.test),
+            "",
+            NodeData::TestCase(TestCaseData {
+                procedure: Some(format!("Check that: {}", r.text)),
+                expected: Some(r.expected.to_string()),
+                ..TestCaseData::default()
+            }),
+        );
+        edges.push(edge(EdgeKind::Satisfies, subsystem.id, requirement.id, ""));
+        edges.push(edge(EdgeKind::Verifies, test_case.id, requirement.id, ""));
+        requirements.push(requirement);
+        test_cases.push(test_case);
+    }
+
+    // ── Flight modes ─────────────────────────────────────────────────────────
+    let state = |name: &str, pseudo_kind: Option<&str>, do_activity: Option<&str>| {
+        let data = StateData {
+            pseudo_kind: pseudo_kind.map(str::to_string),
+            do_activity: do_activity.map(str::to_string),
+            ..StateData::default()
+        };
+        node(NodeKind::State, name, "", NodeData::State(data))
+    };
+    let states = vec![
+        state("Initial", Some("initial"), None),
+        state("Idle", None, None),
+        state("Armed", None, Some("spin motors at idle")),
+        state("Flying", None, Some("follow mission")),
+        state("Returning Home", None, Some("fly to launch point")),
+        state("Landing", None, Some("descend at 1 m/s")),
+        state("Final", Some("final"), None),
+    ];
+    let transitions = [
+        (0, 1, ""),
+        (1, 2, "arm"),
+        (2, 3, "takeoff"),
+        (3, 4, "link lost / low battery"),
+        (3, 5, "land"),
+        (4, 5, "over launch point"),
+        (5, 1, "touchdown"),
+        (1, 6, "power off"),
+    ];
+    for (from, to, label) in transitions {
+        edges.push(edge(
+            EdgeKind::Transition,
+            states[from].id,
+            states[to].id,
+            label,
+        ));
+    }
+
+    // ── Diagrams ─────────────────────────────────────────────────────────────
+    let diagram = |kind: DiagramKind, name: &str, description: &str| Diagram {
+        id: Uuid::new_v4(),
+        project_id,
+        kind,
+        name: name.to_string(),
+        description: description.to_string(),
+        layout_options: HashMap::new(),
+        created_at: now,
+        modified_at: now,
+    };
+    let bdd = diagram(
+        DiagramKind::Bdd,
+        "UAV Structure",
+        "Decomposition of the survey UAV.",
+    );
+    let machine = diagram(
+        DiagramKind::StateMachine,
+        "Flight Modes",
+        "Flight mode logic.",
+    );
+    let element =
+        |diagram_id: Uuid, node_id: Uuid, x: f64, y: f64, size: (f64, f64)| DiagramElement {
+            id: Uuid::new_v4(),
+            diagram_id,
+            node_id,
+            x,
+            y,
+            width: size.0,
+            height: size.1,
+            collapsed: false,
+            style_overrides: HashMap::new(),
+            created_at: now,
+            modified_at: now,
+        };
+
+    // Subsystems in one row under the root, which sits centred above them.
+    let mut diagram_elements = vec![element(bdd.id, uav.id, 400.0, 40.0, (180.0, 90.0))];
+    diagram_elements.extend(subsystems.iter().enumerate().map(|(i, part)| {
+        element(
+            bdd.id,
+            part.id,
+            40.0 + 240.0 * i as f64,
+            240.0,
+            (180.0, 90.0),
+        )
+    }));
+    // Flight modes left to right, with the two ways down to Landing stacked.
+    let positions = [
+        (40.0, 220.0),
+        (140.0, 200.0),
+        (360.0, 200.0),
+        (580.0, 200.0),
+        (800.0, 60.0),
+        (800.0, 340.0),
+        (190.0, 420.0),
+    ];
+    diagram_elements.extend(states.iter().zip(positions).map(|(s, (x, y))| {
+        let pseudo = matches!(&s.data, NodeData::State(d) if d.pseudo_kind.is_some());
+        let size = if pseudo { (30.0, 30.0) } else { (160.0, 72.0) };
+        element(machine.id, s.id, x, y, size)
+    }));
+
+    // ── Source document ──────────────────────────────────────────────────────
+    let document_id = Uuid::new_v4();
+    let sections: Vec<DocumentSection> = SECTIONS
+        .iter()
+        .enumerate()
+        .map(|(position, (section_ref, title))| DocumentSection {
+            id: Uuid::new_v4(),
+            document_id,
+            project_id,
+            section_ref: section_ref.to_string(),
+            section_type: SectionType::SowSection,
+            title: title.to_string(),
+            body: REQUIREMENTS
+                .iter()
+                .filter(|r| r.section == *section_ref)
+                .map(|r| r.text)
+                .collect::<Vec<_>>()
+                .join("\n"),
+            part_number: None,
+            quantity: None,
+            unit: None,
+            position: position as i64,
+            created_at: now,
+            parent_section_id: None,
+            section_level: 0,
+        })
+        .collect();
+    let mut text = String::from("3 Requirements\n");
+    for section in &sections {
+        text.push_str(&format!(
+            "\n{} {}\n{}\n",
+            section.section_ref, section.title, section.body
+        ));
+    }
+    let document = Document {
+        id: document_id,
+        project_id,
+        name: DOCUMENT_NAME.to_string(),
+        doc_type: "SOW".to_string(),
+        size: text.len() as i64,
+        added_at: now,
+        text,
+        source_base64: None,
+        source_mime: None,
+    };
+
+    // ── Simulation ───────────────────────────────────────────────────────────
+    let event =
+        |time_ms: f64, block: &Node, signal_type: &str, value: f64| SimulationScenarioEvent {
+            time_ms,
+            block_id: block.id,
+            signal_type: signal_type.to_string(),
+            value: Value::from(value),
+        };
+    let scenario = SimulationScenario {
+        id: Uuid::new_v4(),
+        project_id,
+        name: "Takeoff and climb".to_string(),
+        description: "Battery power up, then a climb and a throttle back to cruise.".to_string(),
+        duration_ms: 10_000,
+        events: vec![
+            event(0.0, &subsystems[BATTERY], "power", 22.2),
+            event(1_000.0, &subsystems[FLIGHT_CONTROLLER], "thrust_cmd", 0.6),
+            event(6_000.0, &subsystems[FLIGHT_CONTROLLER], "thrust_cmd", 0.4),
+        ],
+        created_at: now,
+        modified_at: now,
+    };
+
+    let mut nodes = vec![uav];
+    nodes.extend(subsystems);
+    nodes.extend(requirements);
+    nodes.extend(test_cases);
+    nodes.extend(states);
+
+    ExampleProject {
+        project,
+        content: ProjectTemplateContent {
+            nodes,
+            edges,
+            block_properties: Vec::new(),
+            diagrams: vec![bdd, machine],
+            diagram_elements,
+            settings: Vec::new(),
+        },
+        document,
+        sections,
+        scenario,
+    }
+}
@@ -0,0 +1,323 @@
+/// Requirement dossiers for verification closure packages: one page per
+/// requirement with everything known about it. The commands layer gathers
+/// a [`RequirementDossier`] per requirement; this module only renders.
+///
+/// Every linkage section is always printed — an empty one says "None" —
+/// because auditors check for the absence explicitly. In HTML, each
+/// requirement's page carries the anchor the Markdown export uses, and a
+/// parent or child with a page of its own links to it.
+use super::{requirement_anchors, xml_escape};
+use crate::core::model::{
+    EdgeKind, ExternalItem, Node, NodeData, ReqComment, RequirementData,
+    RequirementHistoryEntry, SuspectLink,
+};
+use crate::core::provenance::SourceMatch;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DossierFormat {
+    Markdown,
+    Html,
+}
+
+impl DossierFormat {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "markdown" | "md" => Some(Self::Markdown),
+            "html" => Some(Self::Html),
+            _ => None,
+        }
+    }
+}
+
+/// A related requirement and the edge kind that relates it.
+#[derive(Debug, Clone)]
+pub struct LinkedNode {
+    pub node: Node,
+    pub via: EdgeKind,
+}
+
+/// An unresolved suspect link touching the requirement.
+#[derive(Debug, Clone)]
+pub struct DossierSuspect {
+    pub link: SuspectLink,
+    /// Name of the node at the other end of the link.
+    pub other_name: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct RequirementDossier {
+    pub node: Node,
+    pub history_count: i64,
+    pub last_change: Option<RequirementHistoryEntry>,
+    /// Blocks with a «satisfies» edge to the requirement.
+    pub satisfied_by: Vec<Node>,
+    /// Test cases with a «verifies» edge to the requirement.
+    pub verified_by: Vec<Node>,
+    /// Requirements this one refines or is derived from.
+    pub parents: Vec<LinkedNode>,
+    /// Requirements refining or derived from this one.
+    pub children: Vec<LinkedNode>,
+    pub open_comments: Vec<ReqComment>,
+    pub suspect_links: Vec<DossierSuspect>,
+    pub sources: Vec<SourceMatch>,
+    /// Linked items from other tools, e.g. Jira findings.
+    pub external_items: Vec<ExternalItem>,
+}
+
+enum Body {
+    Fields(Vec<(&'static str, String)>),
+    /// Rendered as "None" when empty. An entry naming a requirement carries
+    /// its id, so the HTML can link to that requirement's page.
+    List(Vec<(String, Option<Uuid>)>),
+}
+
+fn list(items: Vec<String>) -> Body {
+    Body::List(items.into_iter().map(|item| (item, None)).collect())
+}
+
+/// A titled part of one requirement's page.
+type Section = (&'static str, Body);
+
+pub fn render(dossiers: &[RequirementDossier], format: DossierFormat) -> String {
+    match format {
+        DossierFormat::Markdown => to_markdown(dossiers),
+        DossierFormat::Html => to_html(dossiers),
+    }
+}
+
+fn heading(node: &Node) -> String {
+    match &node.data {
+        NodeData::Requirement(r) => match r.req_id.as_deref().filter(|id| !id.is_empty()) {
+            Some(id) => format!("{id} — {}", node.name),
+            None => node.name.clone(),
+        },
+        _ => node.name.clone(),
+    }
+}
+
+fn or_dash(s: Option<&str>) -> String {
+    match s.map(str::trim).filter(|s| !s.is_empty()) {
+        Some(s) => s.to_string(),
+        None => "-".to_string(),
+    }
+}
+
+fn sections(d: &RequirementDossier) -> Vec<Section> {
+    let mut fields = vec![("Name", d.node.name.clone())];
+    if let NodeData::Requirement(r) = &d.node.data {
+        let allocations = r.allocations.as_deref().unwrap_or_default().join(", ");
+        fields.extend([
+            ("ID", or_dash(r.req_id.as_deref())),
+            ("Text", or_dash(r.text.as_deref())),
+            ("Rationale", or_dash(r.rationale.as_deref())),
+            ("Priority", format!("{:?}", r.priority)),
+            ("Status", format!("{:?}", r.status)),
+            (
+                "Verification",
+                r.verification_method
+                    .as_ref()
+                    .map(|v| format!("{v:?}"))
+                    .unwrap_or_else(|| "-".to_string()),
+            ),
+            ("Source", or_dash(r.source.as_deref())),
+            ("Allocations", or_dash(Some(&allocations))),
+        ]);
+    }
+    fields.push(("Description", or_dash(Some(&d.node.description))));
+
+    let history = vec![
+        ("Changes recorded", d.history_count.to_string()),
+        (
+            "Last change",
+            d.last_change
+                .as_ref()
+                .map(|h| {
+                    format!("{} by {} ({})", h.ts.format("%Y-%m-%d %H:%M UTC"), h.actor, h.source)
+                })
+                .unwrap_or_else(|| "-".to_string()),
+        ),
+    ];
+
+    let names = |nodes: &[Node]| nodes.iter().map(heading).collect::<Vec<_>>();
+    let linked = |links: &[LinkedNode]| {
+        let items = links
+            .iter()
+            .map(|l| (format!("{} («{}»)", heading(&l.node), l.via), Some(l.node.id)))
+            .collect();
+        Body::List(items)
+    };
+    let tests = d
+        .verified_by
+        .iter()
+        .map(|tc| match &tc.data {
+            NodeData::TestCase(t) => format!("{} — latest result: {:?}", tc.name, t.status),
+            _ => tc.name.clone(),
+        })
+        .collect();
+    let comments = d
+        .open_comments
+        .iter()
+        .map(|c| {
+            format!("{} ({}): {}", c.author, c.created_at.format("%Y-%m-%d"), c.body.trim())
+        })
+        .collect();
+    let suspects = d
+        .suspect_links
+        .iter()
+        .map(|s| {
+            let direction = if s.link.source_node_id == d.node.id { "to" } else { "from" };
+            format!(
+                "Suspect since {} — link {} {}: {}",
+                s.link.flagged_at.format("%Y-%m-%d"),
+                direction,
+                s.other_name,
+                s.link.flagged_reason
+            )
+        })
+        .collect();
+    let sources = d
+        .sources
+        .iter()
+        .map(|s| {
+            let title = [s.section_ref.trim(), s.section_title.trim()]
+                .iter()
+                .filter(|p| !p.is_empty())
+                .copied()
+                .collect::<Vec<_>>()
+                .join(" ");
+            let body = s.section_body.trim();
+            if body.is_empty() {
+                format!("{} {}", s.document_name, title)
+            } else {
+                format!("{} {}: {}", s.document_name, title, body)
+            }
+        })
+        .collect();
+    let external = d
+        .external_items
+        .iter()
+        .map(|item| {
+            let mut line = item.external_key.clone();
+            for part in [&item.title, &item.status] {
+                if !part.trim().is_empty() {
+                    line.push_str(&format!(" — {}", part.trim()));
+                }
+            }
+            match &item.url {
+                Some(url) => format!("{line} ({url})"),
+                None => line,
+            }
+        })
+        .collect();
+
+    vec![
+        ("Requirement", Body::Fields(fields)),
+        ("History", Body::Fields(history)),
+        ("Satisfied by", list(names(&d.satisfied_by))),
+        ("Verified by", list(tests)),
+        ("Parent requirements", linked(&d.parents)),
+        ("Child requirements", linked(&d.children)),
+        ("Open comments", list(comments)),
+        ("Suspect links", list(suspects)),
+        ("Source document", list(sources)),
+        ("External items", list(external)),
+    ]
+}
+
+pub fn to_markdown(dossiers: &[RequirementDossier]) -> String {
+    let mut out = String::from("# Requirement dossier\n\n");
+    for (i, d) in dossiers.iter().enumerate() {
+        if i > 0 {
+            out.push_str("---\n\n");
+        }
+        out.push_str(&format!("## {}\n\n", heading(&d.node)));
+        for (title, body) in sections(d) {
+            out.push_str(&format!("### {title}\n\n"));
+            match body {
+                Body::Fields(fields) => {
+                    out.push_str("| Field | Value |\n|---|---|\n");
+                    for (name, value) in fields {
+                        out.push_str(&format!("| {name} | {} |\n", super::md_cell(&value)));
+                    }
+                }
+                Body::List(items) if items.is_empty() => out.push_str("None\n"),
+                Body::List(items) => {
+                    for (item, _) in items {
+                        out.push_str(&format!("- {}\n", item.replace('\n', " ")));
+                    }
+                }
+            }
+            out.push('\n');
+        }
+    }
+    out
+}
+
+pub fn to_html(dossiers: &[RequirementDossier]) -> String {
+    let reqs: Vec<(&Node, &RequirementData)> = dossiers
+        .iter()
+        .filter_map(|d| match &d.node.data {
+            NodeData::Requirement(r) => Some((&d.node, r)),
+            _ => None,
+        })
+        .collect();
+    let anchors = requirement_anchors(&reqs);
+    let mut out = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>Requirement dossier</title>\n<style>\n\
+         body { font-family: sans-serif; font-size: 11pt; }\n\
+         table { border-collapse: collapse; width: 100%; }\n\
+         th, td { border: 1px solid #999; padding: 4px 6px; }\n\
+         th, td { text-align: left; vertical-align: top; }\n\
+         th { width: 20%; }\n\
+         .none { font-style: italic; }\n\
+         .page-break { break-after: page; page-break-after: always; }\n\
+         </style>\n</head>\n<body>\n",
+    );
+    for (i, d) in dossiers.iter().enumerate() {
+        if i > 0 {
+            out.push_str("<div class=\"page-break\"></div>\n");
+        }
+        match anchors.get(&d.node.id) {
+            Some(anchor) => out.push_str(&format!("<section id=\"{anchor}\">\n")),
+            None => out.push_str("<section>\n"),
+        }
+        out.push_str(&format!("<h1>{}</h1>\n", xml_escape(&heading(&d.node))));
+        for (title, body) in sections(d) {
+            out.push_str(&format!("<h2>{}</h2>\n", xml_escape(title)));
+            match body {
+                Body::Fields(fields) => {
+                    out.push_str("<table>\n");
+                    for (name, value) in fields {
+                        out.push_str(&format!(
+                            "<tr><th>{}</th><td>{}</td></tr>\n",
+                            xml_escape(name),
+                            xml_escape(&value).replace('\n', "<br>")
+                        ));
+                    }
+                    out.push_str("</table>\n");
+                }
+                Body::List(items) if items.is_empty() => {
+                    out.push_str("<p class=\"none\">None</p>\n");
+                }
+                Body::List(items) => {
+                    out.push_str("<ul>\n");
+                    for (item, node_id) in items {
+                        let item = xml_escape(&item);
+                        match node_id.and_then(|id| anchors.get(&id)) {
+                            Some(anchor) => out.push_str(&format!(
+                                "<li><a href=\"#{anchor}\">{item}</a></li>\n"
+                            )),
+                            None => out.push_str(&format!("<li>{item}</li>\n")),
+                        }
+                    }
+                    out.push_str("</ul>\n");
+                }
+            }
+        }
+        out.push_str("</section>\n");
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
@@ -0,0 +1,213 @@
+/// GraphML export of the model for network analysis tools such as Gephi,
+/// and a few built-in graph metrics for users without one.
+///
+/// Nodes carry kind, name, req_id and status as typed `<data>` keys; edges
+/// carry kind and label. Nodes are ordered by kind then id and edges by
+/// kind then id, so re-exporting an unchanged model gives identical output.
+/// Archived nodes are left out, and so is any edge whose source or target
+/// is not in the exported node set.
+use super::xml_escape;
+use crate::core::model::{Edge, EdgeKind, Node, NodeData, NodeKind, RequirementStatus};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use uuid::Uuid;
+
+/// How many nodes `metrics` lists in `top_nodes`.
+pub const TOP_NODES: usize = 10;
+
+/// Kinds to keep; an empty list keeps every kind.
+#[derive(Debug, Clone, Default)]
+pub struct GraphFilter {
+    pub node_kinds: Vec<NodeKind>,
+    pub edge_kinds: Vec<EdgeKind>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DegreeCount {
+    pub degree: usize,
+    pub nodes: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeDegree {
+    pub node_id: Uuid,
+    pub kind: NodeKind,
+    pub name: String,
+    pub req_id: Option<String>,
+    pub degree: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphMetrics {
+    pub node_count: usize,
+    pub edge_count: usize,
+    /// Nodes per degree, by degree. Edge direction is ignored and a
+    /// self-loop counts twice.
+    pub degree_distribution: Vec<DegreeCount>,
+    /// The `TOP_NODES` highest-degree nodes, ties broken by name.
+    pub top_nodes: Vec<NodeDegree>,
+    /// Components with edge direction ignored; an isolated node is one.
+    pub weakly_connected_components: usize,
+}
+
+/// The nodes and edges `filter` keeps, in export order.
+fn select<'a>(
+    nodes: &'a [Node],
+    edges: &'a [Edge],
+    filter: &GraphFilter,
+) -> (Vec<&'a Node>, Vec<&'a Edge>) {
+    let mut nodes: Vec<&Node> = nodes
+        .iter()
+        .filter(|n| !n.archived)
+        .filter(|n| filter.node_kinds.is_empty() || filter.node_kinds.contains(&n.kind))
+        .collect();
+    nodes.sort_by_key(|n| (n.kind.to_string(), n.id));
+    let ids: HashSet<Uuid> = nodes.iter().map(|n| n.id).collect();
+    let mut edges: Vec<&Edge> = edges
+        .iter()
+        .filter(|e| filter.edge_kinds.is_empty() || filter.edge_kinds.contains(&e.kind))
+        .filter(|e| ids.contains(&e.source_id) && ids.contains(&e.target_id))
+        .collect();
+    edges.sort_by_key(|e| (e.kind.to_string(), e.id));
+    (nodes, edges)
+}
+
+fn req_id(node: &Node) -> Option<&str> {
+    match &node.data {
+        NodeData::Requirement(r) => r.req_id.as_deref(),
+        _ => None,
+    }
+}
+
+fn status(node: &Node) -> Option<&'static str> {
+    match &node.data {
+        NodeData::Requirement(r) => Some(match r.status {
+            RequirementStatus::Draft => "draft",
+            RequirementStatus::Approved => "approved",
+            RequirementStatus::Obsolete => "obsolete",
+        }),
+        _ => None,
+    }
+}
+
+pub fn to_graphml(nodes: &[Node], edges: &[Edge], filter: &GraphFilter) -> String {
+    let (nodes, edges) = select(nodes, edges, filter);
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str(
+        "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\" \
+         xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" \
+         xsi:schemaLocation=\"http://graphml.graphdrawing.org/xmlns \
+         http://graphml.graphdrawing.org/xmlns/1.0/graphml.xsd\">\n",
+    );
+    for (id, domain, name) in [
+        ("n_kind", "node", "kind"),
+        ("n_name", "node", "name"),
+        ("n_req_id", "node", "req_id"),
+        ("n_status", "node", "status"),
+        ("n_created_at", "node", "created_at"),
+        ("n_original_created_at", "node", "original_created_at"),
+        ("e_kind", "edge", "kind"),
+        ("e_label", "edge", "label"),
+    ] {
+        out.push_str(&format!(
+            "  <key id=\"{id}\" for=\"{domain}\" attr.name=\"{name}\" attr.type=\"string\"/>\n"
+        ));
+    }
+    out.push_str("  <graph id=\"G\" edgedefault=\"directed\">\n");
+
+    let data = |key: &str, value: &str| {
+        format!("      <data key=\"{key}\">{}</data>\n", xml_escape(value))
+    };
+    for node in &nodes {
+        out.push_str(&format!("    <node id=\"{}\">\n", node.id));
+        out.push_str(&data("n_kind", &node.kind.to_string()));
+        out.push_str(&data("n_name", &node.name));
+        if let Some(req_id) = req_id(node) {
+            out.push_str(&data("n_req_id", req_id));
+        }
+        if let Some(status) = status(node) {
+            out.push_str(&data("n_status", status));
+        }
+        out.push_str(&data("n_created_at", &node.created_at.to_rfc3339()));
+        if let Some(original) = node.original_created_at() {
+            out.push_str(&data("n_original_created_at", &original.to_rfc3339()));
+        }
+        out.push_str("    </node>\n");
+    }
+    for edge in &edges {
+        out.push_str(&format!(
+            "    <edge id=\"{}\" source=\"{}\" target=\"{}\">\n",
+            edge.id, edge.source_id, edge.target_id
+        ));
+        out.push_str(&data("e_kind", &edge.kind.to_string()));
+        if !edge.label.is_empty() {
+            out.push_str(&data("e_label", &edge.label));
+        }
+        out.push_str("    </edge>\n");
+    }
+    out.push_str("  </graph>\n</graphml>\n");
+    out
+}
+
+pub fn metrics(nodes: &[Node], edges: &[Edge], filter: &GraphFilter) -> GraphMetrics {
+    let (nodes, edges) = select(nodes, edges, filter);
+    let index: HashMap<Uuid, usize> = nodes.iter().enumerate().map(|(i, n)| (n.id, i)).collect();
+
+    let mut degree = vec![0usize; nodes.len()];
+    let mut parent: Vec<usize> = (0..nodes.len()).collect();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+    for edge in &edges {
+        let (s, t) = (index[&edge.source_id], index[&edge.target_id]);
+        degree[s] += 1;
+        degree[t] += 1;
+        let (rs, rt) = (root(&mut parent, s), root(&mut parent, t));
+        if rs != rt {
+            parent[rs] = rt;
+        }
+    }
+    let weakly_connected_components = (0..nodes.len())
+        .filter(|&i| root(&mut parent, i) == i)
+        .count();
+
+    let mut distribution: BTreeMap<usize, usize> = BTreeMap::new();
+    for &d in &degree {
+        *distribution.entry(d).or_default() += 1;
+    }
+
+    let mut ranked: Vec<usize> = (0..nodes.len()).collect();
+    ranked.sort_by(|&a, &b| {
+        degree[b]
+            .cmp(&degree[a])
+            .then_with(|| nodes[a].name.cmp(&nodes[b].name))
+            .then_with(|| nodes[a].id.cmp(&nodes[b].id))
+    });
+    let top_nodes = ranked
+        .into_iter()
+        .take(TOP_NODES)
+        .map(|i| NodeDegree {
+            node_id: nodes[i].id,
+            kind: nodes[i].kind.clone(),
+            name: nodes[i].name.clone(),
+            req_id: req_id(nodes[i]).map(str::to_string),
+            degree: degree[i],
+        })
+        .collect();
+
+    GraphMetrics {
+        node_count: nodes.len(),
+        edge_count: edges.len(),
+        degree_distribution: distribution
+            .into_iter()
+            .map(|(degree, nodes)| DegreeCount { degree, nodes })
+            .collect(),
+        top_nodes,
+        weakly_connected_components,
+    }
+}
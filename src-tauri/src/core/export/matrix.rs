@@ -0,0 +1,186 @@
+/// Requirement-to-test case traceability matrix: one row per requirement,
+/// one column per test case, a cell marked where a «verifies» edge joins
+/// the two. Requirements in req_id order as in the VCRM, test cases by
+/// name. Requirements no test case verifies are listed again after the
+/// grid as "Uncovered".
+use super::md_cell;
+use super::vcrm::{natural_cmp, requirement_order};
+use crate::core::model::{Edge, EdgeKind, Node, NodeData};
+use crate::core::rollup::csv_field;
+use serde::Serialize;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatrixFormat {
+    Csv,
+    Markdown,
+}
+
+impl MatrixFormat {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "csv" => Some(Self::Csv),
+            "markdown" | "md" => Some(Self::Markdown),
+            _ => None,
+        }
+    }
+}
+
+/// A row or column heading.
+#[derive(Debug, Clone, Serialize)]
+pub struct MatrixNode {
+    pub id: Uuid,
+    /// The requirement's req_id; always `None` for a test case.
+    pub req_id: Option<String>,
+    pub name: String,
+}
+
+impl MatrixNode {
+    /// "REQ-001 Bus voltage", or just one of the two when the other is
+    /// missing or the same.
+    fn label(&self) -> String {
+        match self
+            .req_id
+            .as_deref()
+            .map(str::trim)
+            .filter(|r| !r.is_empty())
+        {
+            Some(req_id) if req_id != self.name.trim() => format!("{req_id} {}", self.name),
+            Some(req_id) => req_id.to_string(),
+            None => self.name.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TraceabilityMatrix {
+    pub requirements: Vec<MatrixNode>,
+    pub test_cases: Vec<MatrixNode>,
+    /// `cells[r][t]`: test case `t` verifies requirement `r`.
+    pub cells: Vec<Vec<bool>>,
+}
+
+impl TraceabilityMatrix {
+    /// Requirements with no marked cell, in row order.
+    pub fn uncovered(&self) -> Vec<&MatrixNode> {
+        self.requirements
+            .iter()
+            .zip(&self.cells)
+            .filter(|(_, row)| !row.contains(&true))
+            .map(|(req, _)| req)
+            .collect()
+    }
+}
+
+/// Build the grid from every live requirement and test case in `nodes`. A
+/// «verifies» edge counts in either direction, as in the VCRM.
+pub fn to_traceability_matrix(nodes: &[Node], edges: &[Edge]) -> TraceabilityMatrix {
+    let live = nodes.iter().filter(|n| !n.archived);
+    let mut requirements: Vec<&Node> = live
+        .clone()
+        .filter(|n| matches!(n.data, NodeData::Requirement(_)))
+        .collect();
+    requirements.sort_by(|a, b| requirement_order(a, b));
+    let mut test_cases: Vec<&Node> = live
+        .filter(|n| matches!(n.data, NodeData::TestCase(_)))
+        .collect();
+    test_cases.sort_by(|a, b| natural_cmp(&a.name, &b.name).then_with(|| a.id.cmp(&b.id)));
+
+    let verified: HashSet<(Uuid, Uuid)> = edges
+        .iter()
+        .filter(|e| e.kind == EdgeKind::Verifies)
+        .flat_map(|e| [(e.source_id, e.target_id), (e.target_id, e.source_id)])
+        .collect();
+    let mut matrix = TraceabilityMatrix::default();
+    for req in &requirements {
+        let NodeData::Requirement(r) = &req.data else {
+            continue;
+        };
+        matrix.requirements.push(MatrixNode {
+            id: req.id,
+            req_id: r.req_id.clone(),
+            name: req.name.clone(),
+        });
+        matrix.cells.push(
+            test_cases
+                .iter()
+                .map(|tc| verified.contains(&(tc.id, req.id)))
+                .collect(),
+        );
+    }
+    matrix.test_cases = test_cases
+        .iter()
+        .map(|n| MatrixNode {
+            id: n.id,
+            req_id: None,
+            name: n.name.clone(),
+        })
+        .collect();
+    matrix
+}
+
+const MARK: &str = "X";
+
+/// The grid under a "req_id,requirement,<test case>…" header, then a blank
+/// line and an "uncovered" block, as the VCRM CSV ends.
+pub fn to_csv(matrix: &TraceabilityMatrix) -> String {
+    let line = |fields: Vec<String>| {
+        fields
+            .iter()
+            .map(|f| csv_field(f))
+            .collect::<Vec<_>>()
+            .join(",")
+            + "\n"
+    };
+    let mut header = vec!["req_id".to_string(), "requirement".to_string()];
+    header.extend(matrix.test_cases.iter().map(|t| t.name.clone()));
+    let mut out = line(header);
+    for (req, row) in matrix.requirements.iter().zip(&matrix.cells) {
+        let mut fields = vec![req.req_id.clone().unwrap_or_default(), req.name.clone()];
+        fields.extend(
+            row.iter()
+                .map(|&hit| if hit { MARK.to_string() } else { String::new() }),
+        );
+        out.push_str(&line(fields));
+    }
+    out.push_str("\nuncovered\nreq_id,requirement\n");
+    for req in matrix.uncovered() {
+        out.push_str(&line(vec![
+            req.req_id.clone().unwrap_or_default(),
+            req.name.clone(),
+        ]));
+    }
+    out
+}
+
+pub fn to_markdown(matrix: &TraceabilityMatrix, project_name: &str) -> String {
+    let mut out = format!("# Traceability Matrix — {}\n\n", md_cell(project_name));
+    if matrix.requirements.is_empty() {
+        out.push_str("No requirements.\n");
+        return out;
+    }
+    let mut header = vec!["Requirement".to_string()];
+    header.extend(matrix.test_cases.iter().map(|t| md_cell(&t.name)));
+    out.push_str(&format!("| {} |\n", header.join(" | ")));
+    out.push_str(&format!("|{}\n", "---|".repeat(header.len())));
+    for (req, row) in matrix.requirements.iter().zip(&matrix.cells) {
+        let mut fields = vec![md_cell(&req.label())];
+        fields.extend(
+            row.iter()
+                .map(|&hit| if hit { MARK.to_string() } else { String::new() }),
+        );
+        out.push_str(&format!("| {} |\n", fields.join(" | ")));
+    }
+
+    out.push_str("\n## Uncovered\n\n");
+    let uncovered = matrix.uncovered();
+    if uncovered.is_empty() {
+        out.push_str("None; every requirement has a verifying test case.\n");
+        return out;
+    }
+    for req in uncovered {
+        out.push_str(&format!("- {}\n", md_cell(&req.label())));
+    }
+    out
+}
@@ -1,46 +1,142 @@
-use crate::core::model::{Edge, Node, Project};
+use crate::core::model::{
+    BlockProperty, Edge, LinkedExternalItem, Node, NodeData, Project, RequirementData,
+    RequirementPriority, RequirementStatus, UnrecognizedRow, ORIGINAL_CREATED_AT_META,
+};
+use crate::core::rollup::csv_field;
 use anyhow::Result;
 use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use uuid::Uuid;
+
+pub mod dossier;
+pub mod graphml;
+pub mod matrix;
+pub mod reqif;
+pub mod rtm;
+pub mod scope;
+pub mod template;
+pub mod vcrm;
+pub mod xlsx;
+
+pub use matrix::to_traceability_matrix;
+pub use reqif::to_reqif;
 
 // ── JSON-LD ───────────────────────────────────────────────────────────────────
 
-pub fn to_json_ld(project: &Project, nodes: &[Node], edges: &[Edge]) -> Result<String> {
-    let node_values: Vec<Value> = nodes
-        .iter()
-        .map(|n| {
-            json!({
-                "@id": format!("urn:uuid:{}", n.id),
-                "@type": format!("sysml:{}", n.kind),
-                "name": n.name,
-                "description": n.description,
-                "data": serde_json::to_value(&n.data).unwrap_or(Value::Null),
-            })
-        })
-        .collect();
+/// Identifier and context configuration for `to_json_ld`.
+/// The default reproduces the original output: `urn:uuid:` identifiers and the
+/// OMG SysML / schema.org context.
+#[derive(Debug, Clone, Default)]
+pub struct JsonLdOptions {
+    /// Base IRI for element identifiers, e.g. `https://mycorp.example/model/`.
+    /// `None` keeps `urn:uuid:` identifiers.
+    pub base_iri: Option<String>,
+    /// Additional `@context` term mappings, merged over the built-in ones.
+    pub extra_context: serde_json::Map<String, Value>,
+}
+
+impl JsonLdOptions {
+    fn iri(&self, id: Uuid) -> String {
+        match self.base_iri.as_deref().map(str::trim).filter(|b| !b.is_empty()) {
+            Some(base) if base.ends_with('/') || base.ends_with('#') => format!("{base}{id}"),
+            Some(base) => format!("{base}/{id}"),
+            None => format!("urn:uuid:{id}"),
+        }
+    }
+}
+
+pub fn to_json_ld(
+    project: &Project,
+    nodes: &[Node],
+    edges: &[Edge],
+    options: &JsonLdOptions,
+) -> Result<String> {
+    let node_value = |n: &Node| {
+        let mut value = json!({
+            "@id": options.iri(n.id),
+            "@type": format!("sysml:{}", n.kind),
+            "name": n.name,
+            "description": n.description,
+        });
+        match &n.data {
+            NodeData::Requirement(r) => {
+                let fields = [
+                    ("req_id", r.req_id.as_ref().map(|s| json!(s))),
+                    ("text", r.text.as_ref().map(|s| json!(s))),
+                    ("rationale", r.rationale.as_ref().map(|s| json!(s))),
+                    ("priority", serde_json::to_value(&r.priority).ok()),
+                    ("status", serde_json::to_value(&r.status).ok()),
+                    (
+                        "verification_method",
+                        r.verification_method
+                            .as_ref()
+                            .and_then(|v| serde_json::to_value(v).ok()),
+                    ),
+                    ("allocations", r.allocations.as_ref().map(|a| json!(a))),
+                    ("classification", r.classification.as_ref().map(|s| json!(s))),
+                    // "source" is the relationship endpoint term.
+                    ("requirement_source", r.source.as_ref().map(|s| json!(s))),
+                ];
+                for (term, field) in fields {
+                    if let Some(field) = field {
+                        value[term] = field;
+                    }
+                }
+            }
+            data => {
+                value["data"] = serde_json::to_value(data).unwrap_or(Value::Null);
+            }
+        }
+        value
+    };
+    let node_values: Vec<Value> = nodes.iter().filter(|n| !n.archived).map(node_value).collect();
+    // Archived nodes stay resolvable as relationship endpoints, apart from
+    // the active model.
+    let archived_values: Vec<Value> = nodes.iter().filter(|n| n.archived).map(node_value).collect();
 
     let edge_values: Vec<Value> = edges
         .iter()
         .map(|e| {
             json!({
-                "@id": format!("urn:uuid:{}", e.id),
+                "@id": options.iri(e.id),
                 "@type": format!("sysml:{}", e.kind),
-                "source": format!("urn:uuid:{}", e.source_id),
-                "target": format!("urn:uuid:{}", e.target_id),
+                "source": options.iri(e.source_id),
+                "target": options.iri(e.target_id),
                 "label": e.label,
             })
         })
         .collect();
 
-    let doc = json!({
-        "@context": {
-            "sysml": "https://www.omg.org/spec/SysML/20230201/",
-            "name": "http://schema.org/name",
-            "description": "http://schema.org/description",
-            "source": { "@type": "@id" },
-            "target": { "@type": "@id" },
-        },
+    let mut context = json!({
+        "sysml": "https://www.omg.org/spec/SysML/20230201/",
+        "name": "http://schema.org/name",
+        "description": "http://schema.org/description",
+        "source": { "@type": "@id" },
+        "target": { "@type": "@id" },
+        "req_id": "sysml:declaredShortName",
+        "text": "sysml:text",
+        "rationale": "sysml:rationale",
+        "priority": "sysml:priority",
+        "status": "sysml:status",
+        "verification_method": "sysml:verificationMethod",
+        "allocations": { "@id": "sysml:allocatedTo", "@container": "@set" },
+        "classification": "sysml:classification",
+        "requirement_source": "http://purl.org/dc/terms/source",
+        // Kind-specific fields of everything but requirements, kept as a
+        // JSON literal.
+        "data": { "@id": "sysml:data", "@type": "@json" },
+    });
+    if let Some(ctx) = context.as_object_mut() {
+        for (term, mapping) in &options.extra_context {
+            ctx.insert(term.clone(), mapping.clone());
+        }
+    }
+
+    let mut doc = json!({
+        "@context": context,
         "@graph": {
-            "@id": format!("urn:uuid:{}", project.id),
+            "@id": options.iri(project.id),
             "@type": "sysml:Model",
             "name": project.name,
             "description": project.description,
@@ -48,13 +144,45 @@ pub fn to_json_ld(project: &Project, nodes: &[Node], edges: &[Edge]) -> Result<S
             "relationships": edge_values,
         }
     });
+    if !archived_values.is_empty() {
+        doc["@graph"]["archivedElements"] = Value::Array(archived_values);
+    }
 
     Ok(serde_json::to_string_pretty(&doc)?)
 }
 
 // ── Markdown ──────────────────────────────────────────────────────────────────
 
-pub fn to_markdown(project: &Project, nodes: &[Node], edges: &[Edge]) -> String {
+/// How `to_markdown` splits the requirements table into sections.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkdownGroupBy {
+    /// One section per subsystem allocation; multi-allocated requirements
+    /// appear in each of their sections.
+    Allocation,
+    Priority,
+    Status,
+}
+
+impl MarkdownGroupBy {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "allocation" | "allocations" => Some(Self::Allocation),
+            "priority" => Some(Self::Priority),
+            "status" => Some(Self::Status),
+            _ => None,
+        }
+    }
+}
+
+pub fn to_markdown(
+    project: &Project,
+    nodes: &[Node],
+    edges: &[Edge],
+    properties: &[BlockProperty],
+    external: &[LinkedExternalItem],
+    unrecognized: &[UnrecognizedRow],
+    group_by: Option<MarkdownGroupBy>,
+) -> String {
     let mut out = String::new();
 
     out.push_str(&format!("# {}\n\n", project.name));
@@ -63,160 +191,1001 @@ pub fn to_markdown(project: &Project, nodes: &[Node], edges: &[Edge]) -> String
         out.push_str(&format!("{}\n\n", project.description));
     }
 
-    // Requirements table
-    let reqs: Vec<_> = nodes
+    // Requirements table(s); archived ones go to the appendix
+    let all_reqs: Vec<(&Node, &RequirementData)> = nodes
         .iter()
-        .filter(|n| matches!(n.kind, crate::core::model::NodeKind::Requirement))
+        .filter_map(|n| match &n.data {
+            NodeData::Requirement(r) => Some((n, r)),
+            _ => None,
+        })
         .collect();
 
+    let anchors = requirement_anchors(&all_reqs);
+    let (archived_reqs, reqs): (Vec<_>, Vec<_>) =
+        all_reqs.into_iter().partition(|(n, _)| n.archived);
+    // A requirement can appear in several groups; only its first row carries the anchor.
+    let mut anchored = std::collections::HashSet::new();
+    // Full text of requirements truncated in the table, keyed by footnote label.
+    let mut footnotes: Vec<(String, String)> = Vec::new();
+
     if !reqs.is_empty() {
-        out.push_str("## Requirements\n\n");
-        out.push_str("| ID | Name | Text | Priority | Status | Verification |\n");
-        out.push_str("|---|---|---|---|---|---|\n");
+        match group_by {
+            None => {
+                out.push_str("## Requirements\n\n");
+                push_requirements_table(&mut out, &reqs, &anchors, &mut anchored, &mut footnotes);
+            }
+            Some(group_by) => {
+                for (group, members) in group_requirements(&reqs, group_by) {
+                    out.push_str(&format!("## {group}\n\n"));
+                    push_requirements_table(&mut out, &members, &anchors, &mut anchored, &mut footnotes);
+                }
+            }
+        }
+    }
+
+    // Structure section — blocks that own value properties
+    let blocks_with_props: Vec<&Node> = nodes
+        .iter()
+        .filter(|n| matches!(n.kind, crate::core::model::NodeKind::Block) && !n.archived)
+        .filter(|n| properties.iter().any(|p| p.block_node_id == n.id))
+        .collect();
 
-        for node in &reqs {
-            if let crate::core::model::NodeData::Requirement(r) = &node.data {
+    if !blocks_with_props.is_empty() {
+        out.push_str("## Structure\n\n");
+        for block in blocks_with_props {
+            out.push_str(&format!("### {}\n\n", block.name));
+            out.push_str("| Property | Type | Value | Unit |\n");
+            out.push_str("|---|---|---|---|\n");
+            for prop in properties.iter().filter(|p| p.block_node_id == block.id) {
+                let type_name = prop
+                    .value_type_node_id
+                    .and_then(|id| nodes.iter().find(|n| n.id == id))
+                    .map(|n| n.name.as_str())
+                    .unwrap_or(prop.type_name.as_str());
+                let cell = |s: &str| if s.is_empty() { "-".to_string() } else { md_cell(s) };
                 out.push_str(&format!(
-                    "| {} | {} | {} | {:?} | {:?} | {} |\n",
-                    r.req_id.as_deref().unwrap_or("-"),
-                    node.name,
-                    r.text.as_deref().unwrap_or("").replace('|', "\\|"),
-                    r.priority,
-                    r.status,
-                    r.verification_method
-                        .as_ref()
-                        .map(|v| format!("{v:?}"))
-                        .unwrap_or_else(|| "-".to_string()),
+                    "| {} | {} | {} | {} |\n",
+                    cell(&prop.name),
+                    cell(type_name),
+                    cell(&prop.value),
+                    cell(&prop.unit),
                 ));
             }
+            out.push('\n');
         }
-        out.push('\n');
     }
 
     // Traceability section
     if !edges.is_empty() {
         out.push_str("## Traceability\n\n");
-        out.push_str("| Relationship | Source | Target |\n");
-        out.push_str("|---|---|---|\n");
+        out.push_str("| Relationship | Source | Target | Label |\n");
+        out.push_str("|---|---|---|---|\n");
 
-        for edge in edges {
-            let src_name = nodes
+        let node_ref = |id: Uuid| -> String {
+            let name = nodes
                 .iter()
-                .find(|n| n.id == edge.source_id)
+                .find(|n| n.id == id)
                 .map(|n| n.name.as_str())
                 .unwrap_or("?");
-            let tgt_name = nodes
+            match anchors.get(&id) {
+                Some(anchor) => format!("[{name}](#{anchor})"),
+                None => name.to_string(),
+            }
+        };
+
+        for edge in edges {
+            let label = edge.label.trim();
+            out.push_str(&format!(
+                "| «{}» | {} | {} | {} |\n",
+                edge.kind,
+                node_ref(edge.source_id),
+                node_ref(edge.target_id),
+                if label.is_empty() { "-".to_string() } else { md_cell(label) },
+            ));
+        }
+        out.push('\n');
+    }
+
+    // External items (e.g. Jira findings) linked to model elements
+    if !external.is_empty() {
+        out.push_str("## External items\n\n");
+        out.push_str("| Element | Key | Title | Status |\n");
+        out.push_str("|---|---|---|---|\n");
+        for link in external {
+            let item = &link.item;
+            let name = nodes
                 .iter()
-                .find(|n| n.id == edge.target_id)
+                .find(|n| n.id == link.node_id)
                 .map(|n| n.name.as_str())
                 .unwrap_or("?");
-
+            let element = match anchors.get(&link.node_id) {
+                Some(anchor) => format!("[{}](#{anchor})", md_cell(name)),
+                None => md_cell(name),
+            };
+            let key = match item.url.as_deref() {
+                Some(url) => format!("[{}]({url})", md_cell(&item.external_key)),
+                None => md_cell(&item.external_key),
+            };
+            let cell = |s: &str| if s.trim().is_empty() { "-".to_string() } else { md_cell(s) };
             out.push_str(&format!(
-                "| «{}» | {} | {} |\n",
-                edge.kind, src_name, tgt_name
+                "| {element} | {key} | {} | {} |\n",
+                cell(&item.title),
+                cell(&item.status),
             ));
         }
         out.push('\n');
     }
 
+    // Archived elements, kept because links and history still refer to them
+    let archived_other: Vec<&Node> = nodes
+        .iter()
+        .filter(|n| n.archived && !matches!(n.data, NodeData::Requirement(_)))
+        .collect();
+    if !archived_reqs.is_empty() || !archived_other.is_empty() {
+        out.push_str("## Appendix: Archived elements\n\n");
+        out.push_str("Obsolete elements kept for traceability.\n\n");
+        if !archived_reqs.is_empty() {
+            push_requirements_table(
+                &mut out,
+                &archived_reqs,
+                &anchors,
+                &mut anchored,
+                &mut footnotes,
+            );
+        }
+        if !archived_other.is_empty() {
+            out.push_str("| Element | Kind | Description |\n");
+            out.push_str("|---|---|---|\n");
+            for node in archived_other {
+                let description = node.description.trim();
+                out.push_str(&format!(
+                    "| {} | {} | {} |\n",
+                    md_cell(&node.name),
+                    node.kind,
+                    if description.is_empty() { "-".to_string() } else { md_cell(description) },
+                ));
+            }
+            out.push('\n');
+        }
+    }
+
+    // Rows this build can't interpret, passed through as stored
+    if !unrecognized.is_empty() {
+        out.push_str("## Unrecognized rows\n\n");
+        out.push_str("Written by a newer version of the app; included as stored.\n\n");
+        for row in unrecognized {
+            out.push_str(&format!("- `{}` {} (kind `{}`)\n\n", row.table, row.id, row.kind));
+            let raw = serde_json::to_string_pretty(&row.row).unwrap_or_default();
+            out.push_str(&format!("```json\n{raw}\n```\n\n"));
+        }
+    }
+
+    if !footnotes.is_empty() {
+        for (label, text) in &footnotes {
+            out.push_str(&format!("[^{label}]: {}\n", md_cell(text)));
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Requirement text longer than this is cut short in tables, with the
+/// full text moved to a footnote.
+const MARKDOWN_TEXT_CELL_CHARS: usize = 400;
+
+/// Make `s` safe inside a table cell: pipes escaped, line breaks as `<br>`.
+fn md_cell(s: &str) -> String {
+    s.trim()
+        .replace("\r\n", "\n")
+        .replace('\r', "\n")
+        .replace('|', "\\|")
+        .replace('\n', "<br>")
+}
+
+/// Stable in-document anchor per requirement: `req-<req_id>`, or
+/// `req-<uuid>` when the req_id is missing or shared with another requirement.
+fn requirement_anchors(reqs: &[(&Node, &RequirementData)]) -> HashMap<Uuid, String> {
+    let slug = |req_id: &str| -> String {
+        req_id
+            .trim()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '-' })
+            .collect()
+    };
+
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for (_, r) in reqs {
+        if let Some(id) = r.req_id.as_deref().map(slug).filter(|s| !s.is_empty()) {
+            *seen.entry(id).or_default() += 1;
+        }
+    }
+
+    reqs.iter()
+        .map(|(node, r)| {
+            let anchor = match r.req_id.as_deref().map(slug).filter(|s| !s.is_empty()) {
+                Some(id) if seen.get(&id) == Some(&1) => format!("req-{id}"),
+                _ => format!("req-{}", node.id),
+            };
+            (node.id, anchor)
+        })
+        .collect()
+}
+
+fn push_requirements_table(
+    out: &mut String,
+    reqs: &[(&Node, &RequirementData)],
+    anchors: &HashMap<Uuid, String>,
+    anchored: &mut std::collections::HashSet<Uuid>,
+    footnotes: &mut Vec<(String, String)>,
+) {
+    out.push_str("| ID | Name | Text | Priority | Status | Verification |\n");
+    out.push_str("|---|---|---|---|---|---|\n");
+
+    for (node, r) in reqs {
+        let anchor = match anchors.get(&node.id) {
+            Some(anchor) if anchored.insert(node.id) => format!("<a id=\"{anchor}\"></a>"),
+            _ => String::new(),
+        };
+        out.push_str(&format!(
+            "| {}{} | {} | {} | {:?} | {:?} | {} |\n",
+            anchor,
+            r.req_id.as_deref().unwrap_or("-"),
+            md_cell(&node.name),
+            text_cell(node, r, anchors, footnotes),
+            r.priority,
+            r.status,
+            r.verification_method
+                .as_ref()
+                .map(|v| format!("{v:?}"))
+                .unwrap_or_else(|| "-".to_string()),
+        ));
+    }
+    out.push('\n');
+}
+
+/// The requirement's text for a table cell, truncated with a footnote
+/// reference when it is longer than [`MARKDOWN_TEXT_CELL_CHARS`].
+fn text_cell(
+    node: &Node,
+    r: &RequirementData,
+    anchors: &HashMap<Uuid, String>,
+    footnotes: &mut Vec<(String, String)>,
+) -> String {
+    let text = r.text.as_deref().unwrap_or("").trim();
+    if text.chars().count() <= MARKDOWN_TEXT_CELL_CHARS {
+        return md_cell(text);
+    }
+    let label = anchors
+        .get(&node.id)
+        .cloned()
+        .unwrap_or_else(|| format!("req-{}", node.id));
+    if !footnotes.iter().any(|(l, _)| *l == label) {
+        footnotes.push((label.clone(), text.to_string()));
+    }
+    let head: String = text.chars().take(MARKDOWN_TEXT_CELL_CHARS).collect();
+    format!("{}…[^{label}]", md_cell(head.trim_end()))
+}
+
+/// Bucket requirements into ordered, non-empty groups. Allocations sort
+/// alphabetically with "Unallocated" last; priority and status follow
+/// their enum order.
+fn group_requirements<'a>(
+    reqs: &[(&'a Node, &'a RequirementData)],
+    group_by: MarkdownGroupBy,
+) -> Vec<(String, Vec<(&'a Node, &'a RequirementData)>)> {
+    const UNALLOCATED: &str = "Unallocated";
+
+    let keys_for = |r: &RequirementData| -> Vec<(usize, String)> {
+        match group_by {
+            MarkdownGroupBy::Allocation => {
+                let mut tags: Vec<String> = r
+                    .allocations
+                    .iter()
+                    .flatten()
+                    .map(|a| a.trim().to_string())
+                    .filter(|a| !a.is_empty())
+                    .collect();
+                tags.sort_by_key(|a| a.to_lowercase());
+                tags.dedup_by_key(|a| a.to_lowercase());
+                if tags.is_empty() {
+                    vec![(1, UNALLOCATED.to_string())]
+                } else {
+                    tags.into_iter().map(|t| (0, t)).collect()
+                }
+            }
+            MarkdownGroupBy::Priority => {
+                let rank = match r.priority {
+                    RequirementPriority::Shall => 0,
+                    RequirementPriority::Should => 1,
+                    RequirementPriority::May => 2,
+                };
+                vec![(rank, format!("{:?}", r.priority))]
+            }
+            MarkdownGroupBy::Status => {
+                let rank = match r.status {
+                    RequirementStatus::Draft => 0,
+                    RequirementStatus::Approved => 1,
+                    RequirementStatus::Obsolete => 2,
+                };
+                vec![(rank, format!("{:?}", r.status))]
+            }
+        }
+    };
+
+    let mut groups: Vec<((usize, String), Vec<(&'a Node, &'a RequirementData)>)> = Vec::new();
+    for &(node, r) in reqs {
+        for key in keys_for(r) {
+            match groups
+                .iter_mut()
+                .find(|(k, _)| k.0 == key.0 && k.1.eq_ignore_ascii_case(&key.1))
+            {
+                Some((_, members)) => members.push((node, r)),
+                None => groups.push((key, vec![(node, r)])),
+            }
+        }
+    }
+    groups.sort_by(|(a, _), (b, _)| a.0.cmp(&b.0).then_with(|| a.1.to_lowercase().cmp(&b.1.to_lowercase())));
+    groups.into_iter().map(|((_, label), members)| (label, members)).collect()
+}
+
+// ── CSV (requirements table) ──────────────────────────────────────────────────
+
+const CSV_HEADER: [&str; 9] = [
+    "ID",
+    "Name",
+    "Text",
+    "Priority",
+    "Status",
+    "Verification",
+    "Allocations",
+    "Source",
+    "Classification",
+];
+
+/// The requirements table as CSV, one row per requirement of `project` in
+/// model order, for opening in a spreadsheet. Archived requirements are
+/// left out. Fields holding a comma, quote or line break are quoted per
+/// RFC 4180; allocations are joined with "; ".
+pub fn to_csv(project: &Project, nodes: &[Node]) -> String {
+    requirement_rows(project, nodes, &CSV_HEADER, |node, r| {
+        vec![
+            r.req_id.clone().unwrap_or_default(),
+            node.name.clone(),
+            r.text.clone().unwrap_or_default(),
+            format!("{:?}", r.priority),
+            format!("{:?}", r.status),
+            r.verification_method
+                .as_ref()
+                .map(|v| format!("{v:?}"))
+                .unwrap_or_default(),
+            r.allocations.as_deref().unwrap_or_default().join("; "),
+            r.source.clone().unwrap_or_default(),
+            r.classification.clone().unwrap_or_default(),
+        ]
+    })
+}
+
+const REQUIREMENTS_CSV_HEADER: [&str; 12] = [
+    "req_id",
+    "name",
+    "text",
+    "rationale",
+    "priority",
+    "status",
+    "verification_method",
+    "allocations",
+    "source",
+    "classification",
+    "original_created_at",
+    "created_at",
+];
+
+/// Every requirement field as CSV, with the field names as headers and
+/// enum values as stored ("shall", "draft", "test"), so the table can be
+/// edited and read back by `core::import::csv`. Otherwise as [`to_csv`].
+///
+/// Both creation times close the row: the source's, for an imported
+/// requirement, then the time it was created here. The import reads the
+/// first of them, so a requirement read back keeps the source's time and
+/// one authored here gains none.
+pub fn to_requirements_csv(project: &Project, nodes: &[Node]) -> String {
+    requirement_rows(project, nodes, &REQUIREMENTS_CSV_HEADER, |node, r| {
+        vec![
+            r.req_id.clone().unwrap_or_default(),
+            node.name.clone(),
+            r.text.clone().unwrap_or_default(),
+            r.rationale.clone().unwrap_or_default(),
+            format!("{:?}", r.priority).to_lowercase(),
+            format!("{:?}", r.status).to_lowercase(),
+            r.verification_method
+                .as_ref()
+                .map(|v| format!("{v:?}").to_lowercase())
+                .unwrap_or_default(),
+            r.allocations.as_deref().unwrap_or_default().join("; "),
+            r.source.clone().unwrap_or_default(),
+            r.classification.clone().unwrap_or_default(),
+            node.original_created_at()
+                .map(|t| t.to_rfc3339())
+                .unwrap_or_default(),
+            node.created_at.to_rfc3339(),
+        ]
+    })
+}
+
+/// The header, then one RFC 4180 row per unarchived requirement of
+/// `project` with the cells `row` gives it. Shared by the two CSV writers.
+fn requirement_rows(
+    project: &Project,
+    nodes: &[Node],
+    header: &[&str],
+    row: impl Fn(&Node, &RequirementData) -> Vec<String>,
+) -> String {
+    let mut out = header.join(",") + "\n";
+    for node in nodes
+        .iter()
+        .filter(|n| n.project_id == project.id && !n.archived)
+    {
+        let NodeData::Requirement(r) = &node.data else {
+            continue;
+        };
+        let fields = row(node, r);
+        out.push_str(&fields.iter().map(|f| csv_field(f)).collect::<Vec<_>>().join(","));
+        out.push('\n');
+    }
     out
 }
 
 // ── Native JSON (round-trip) ──────────────────────────────────────────────────
 
-pub fn to_native_json(project: &Project, nodes: &[Node], edges: &[Edge]) -> Result<String> {
-    let doc = json!({
-        "version": 1,
+/// The "version" the native JSON writers stamp, and the newest
+/// [`from_native_json`] reads.
+pub const NATIVE_JSON_VERSION: u64 = 1;
+
+/// `unrecognized` rows are written under an "unrecognized" key, present
+/// only when there are any, so a newer version can read them back.
+pub fn to_native_json(
+    project: &Project,
+    nodes: &[Node],
+    edges: &[Edge],
+    unrecognized: &[UnrecognizedRow],
+) -> Result<String> {
+    let mut doc = json!({
+        "version": NATIVE_JSON_VERSION,
         "project": project,
         "nodes": nodes,
         "edges": edges,
     });
+    if !unrecognized.is_empty() {
+        doc["unrecognized"] = serde_json::to_value(unrecognized)?;
+    }
     Ok(serde_json::to_string_pretty(&doc)?)
 }
 
+/// Streaming form of [`to_native_json`]: the same document, written one
+/// element at a time so large models never sit in memory as a single string.
+/// `progress` works as in [`write_xmi`].
+pub fn write_native_json<W: Write>(
+    out: &mut W,
+    project: &Project,
+    nodes: &[Node],
+    edges: &[Edge],
+    unrecognized: &[UnrecognizedRow],
+    progress: &mut dyn FnMut(usize, usize) -> bool,
+) -> Result<bool> {
+    let total = nodes.len() + edges.len() + unrecognized.len();
+    let mut done = 0;
+
+    write!(out, "{{\n  \"version\": {NATIVE_JSON_VERSION},\n  \"project\": ")?;
+    write_indented(out, project, "  ")?;
+    out.write_all(b",\n  \"nodes\": ")?;
+    if !write_json_array(out, nodes, &mut done, total, progress)? {
+        return Ok(false);
+    }
+    out.write_all(b",\n  \"edges\": ")?;
+    if !write_json_array(out, edges, &mut done, total, progress)? {
+        return Ok(false);
+    }
+    if !unrecognized.is_empty() {
+        out.write_all(b",\n  \"unrecognized\": ")?;
+        if !write_json_array(out, unrecognized, &mut done, total, progress)? {
+            return Ok(false);
+        }
+    }
+    out.write_all(b"\n}")?;
+    out.flush()?;
+    progress(total, total);
+    Ok(true)
+}
+
+fn write_json_array<W: Write, T: serde::Serialize>(
+    out: &mut W,
+    items: &[T],
+    done: &mut usize,
+    total: usize,
+    progress: &mut dyn FnMut(usize, usize) -> bool,
+) -> Result<bool> {
+    if items.is_empty() {
+        out.write_all(b"[]")?;
+        return Ok(true);
+    }
+    out.write_all(b"[")?;
+    for (i, item) in items.iter().enumerate() {
+        if !progress(*done, total) {
+            return Ok(false);
+        }
+        out.write_all(if i == 0 { "\n    " } else { ",\n    " }.as_bytes())?;
+        write_indented(out, item, "    ")?;
+        *done += 1;
+    }
+    out.write_all(b"\n  ]")?;
+    Ok(true)
+}
+
+/// Pretty-print `value` as a nested member of the document, continuing each
+/// line after the first at `indent` (matching `serde_json::to_string_pretty`).
+/// Going through `Value` sorts object keys — including `HashMap` fields such
+/// as `meta` — exactly as `to_native_json` does, so output is reproducible.
+fn write_indented<W: Write, T: serde::Serialize>(out: &mut W, value: &T, indent: &str) -> Result<()> {
+    let pretty = serde_json::to_string_pretty(&serde_json::to_value(value)?)?;
+    let mut lines = pretty.lines();
+    if let Some(first) = lines.next() {
+        out.write_all(first.as_bytes())?;
+    }
+    for line in lines {
+        write!(out, "\n{indent}{line}")?;
+    }
+    Ok(())
+}
+
+/// A native JSON document read back by [`from_native_json`], ids as in the
+/// file until [`NativeDocument::remap`].
+#[derive(Debug, Clone)]
+pub struct NativeDocument {
+    pub project: Project,
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+    /// What was left out of the document, and why.
+    pub warnings: Vec<String>,
+}
+
+/// What `import_json` wrote.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct NativeImportSummary {
+    pub project_id: Uuid,
+    pub nodes_imported: usize,
+    pub edges_imported: usize,
+    pub warnings: Vec<String>,
+}
+
+/// Parse a document written by [`to_native_json`] or [`write_native_json`].
+///
+/// The version must be present and no newer than [`NATIVE_JSON_VERSION`].
+/// A node or edge that doesn't deserialize (a kind this version doesn't
+/// know), repeats an earlier id, or is an edge with an end missing from the
+/// file's nodes is skipped with a warning. `meta` maps are kept as written.
+///
+/// A project with no id (a hand-assembled document) gets a fresh one;
+/// missing timestamps become the time of reading, a missing description
+/// an empty one.
+pub fn from_native_json(text: &str) -> Result<NativeDocument> {
+    let mut doc: Value = serde_json::from_str(text)?;
+    let version = match doc.get("version") {
+        Some(v) => v
+            .as_u64()
+            .ok_or_else(|| anyhow::anyhow!("\"version\" must be a positive integer, not {v}"))?,
+        None => anyhow::bail!("not a native JSON export: there is no \"version\""),
+    };
+    if version == 0 || version > NATIVE_JSON_VERSION {
+        anyhow::bail!(
+            "unsupported native JSON version {version} (this app reads up to {NATIVE_JSON_VERSION})"
+        );
+    }
+    let mut raw_project = doc["project"].take();
+    if let Value::Object(fields) = &mut raw_project {
+        let now = Value::from(chrono::Utc::now().to_rfc3339());
+        fields
+            .entry("id")
+            .or_insert_with(|| Value::from(Uuid::new_v4().to_string()));
+        fields.entry("description").or_insert_with(|| Value::from(""));
+        fields.entry("created_at").or_insert_with(|| now.clone());
+        fields.entry("modified_at").or_insert(now);
+    }
+    let project: Project = serde_json::from_value(raw_project)
+        .map_err(|e| anyhow::anyhow!("invalid \"project\": {e}"))?;
+
+    let mut warnings = Vec::new();
+    let mut elements = |key: &str| match doc[key].take() {
+        Value::Array(items) => items,
+        Value::Null => Vec::new(),
+        _ => {
+            warnings.push(format!("\"{key}\" is not a list; nothing read from it"));
+            Vec::new()
+        }
+    };
+    let raw_nodes = elements("nodes");
+    let raw_edges = elements("edges");
+
+    let mut nodes: Vec<Node> = Vec::new();
+    let mut node_ids: HashSet<Uuid> = HashSet::new();
+    for (i, raw) in raw_nodes.into_iter().enumerate() {
+        match serde_json::from_value::<Node>(raw) {
+            Ok(node) if !node_ids.insert(node.id) => {
+                warnings.push(format!("node {} skipped: its id is repeated", node.id));
+            }
+            Ok(node) => nodes.push(node),
+            Err(e) => warnings.push(format!("node {} skipped: {e}", i + 1)),
+        }
+    }
+
+    let mut edges: Vec<Edge> = Vec::new();
+    let mut edge_ids: HashSet<Uuid> = HashSet::new();
+    for (i, raw) in raw_edges.into_iter().enumerate() {
+        let edge = match serde_json::from_value::<Edge>(raw) {
+            Ok(edge) => edge,
+            Err(e) => {
+                warnings.push(format!("edge {} skipped: {e}", i + 1));
+                continue;
+            }
+        };
+        let missing = [edge.source_id, edge.target_id]
+            .into_iter()
+            .find(|id| !node_ids.contains(id));
+        if let Some(missing) = missing {
+            warnings.push(format!(
+                "edge {} skipped: node {missing} is not in the file",
+                edge.id
+            ));
+        } else if !edge_ids.insert(edge.id) {
+            warnings.push(format!("edge {} skipped: its id is repeated", edge.id));
+        } else {
+            edges.push(edge);
+        }
+    }
+
+    if let Some(rows) = doc.get("unrecognized").and_then(Value::as_array) {
+        if !rows.is_empty() {
+            warnings.push(format!(
+                "{} rows written by a newer version were not imported",
+                rows.len()
+            ));
+        }
+    }
+
+    Ok(NativeDocument {
+        project,
+        nodes,
+        edges,
+        warnings,
+    })
+}
+
+impl NativeDocument {
+    /// The project, node and edge ids the document would write.
+    pub fn ids(&self) -> Vec<Uuid> {
+        std::iter::once(self.project.id)
+            .chain(self.nodes.iter().map(|n| n.id))
+            .chain(self.edges.iter().map(|e| e.id))
+            .collect()
+    }
+
+    /// Record each node's and edge's `created_at` in the file as its
+    /// `ORIGINAL_CREATED_AT_META`, unless an earlier import already set
+    /// one. The timestamps themselves are kept, so the copy lists in the
+    /// same order as the original.
+    pub fn mark_imported(&mut self) {
+        let nodes = self.nodes.iter_mut().map(|n| (&mut n.meta, n.created_at));
+        let edges = self.edges.iter_mut().map(|e| (&mut e.meta, e.created_at));
+        for (meta, created_at) in nodes.chain(edges) {
+            meta.entry(ORIGINAL_CREATED_AT_META.to_string())
+                .or_insert_with(|| Value::from(created_at.to_rfc3339()));
+        }
+    }
+
+    /// Move the model into `project_id`, giving every node and edge whose
+    /// id is in `taken` a fresh one. Edge ends and port type references
+    /// follow their node.
+    pub fn remap(&mut self, project_id: Uuid, taken: &HashSet<Uuid>) {
+        let mut fresh: HashMap<Uuid, Uuid> = HashMap::new();
+        for node in &mut self.nodes {
+            if taken.contains(&node.id) {
+                let id = Uuid::new_v4();
+                fresh.insert(node.id, id);
+                node.id = id;
+            }
+            node.project_id = project_id;
+        }
+        let follow = |id: Uuid| fresh.get(&id).copied().unwrap_or(id);
+        for node in &mut self.nodes {
+            if let NodeData::Port(port) = &mut node.data {
+                port.type_ref = port.type_ref.map(follow);
+            }
+        }
+        for edge in &mut self.edges {
+            if taken.contains(&edge.id) {
+                edge.id = Uuid::new_v4();
+            }
+            edge.project_id = project_id;
+            edge.source_id = follow(edge.source_id);
+            edge.target_id = follow(edge.target_id);
+        }
+    }
+}
+
 // ── SysML XMI (OMG SysML 1.6 / UML 2.5 subset) ──────────────────────────────
 //
 // Produces a valid XMI 2.1 document with SysML 1.6 stereotypes.
 // Subset implemented: Model, Package, Block, Requirement, Port, ValueType,
 // ConstraintBlock, State, and all edge kinds as Dependencies / Associations.
+//
+// Composition is emitted as structure rather than as loose relationships:
+// a block→block «composes» edge becomes a part property (composite
+// ownedAttribute) of the parent, with an Association whose other end it
+// owns, and a block→port «composes» edge nests the port in the block as an
+// owned uml:Port. Ports without an owning block stay top-level.
 
-pub fn to_xmi(project: &Project, nodes: &[Node], edges: &[Edge]) -> String {
-    let mut out = String::with_capacity(8192);
+pub fn to_xmi(
+    project: &Project,
+    nodes: &[Node],
+    edges: &[Edge],
+    properties: &[BlockProperty],
+) -> String {
+    let mut out = Vec::with_capacity(8192);
+    // Writing into a Vec can't fail and nothing cancels it.
+    let _ = write_xmi(&mut out, project, nodes, edges, properties, &mut |_, _| true);
+    String::from_utf8_lossy(&out).into_owned()
+}
 
-    out.push_str(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
-    out.push('\n');
-    out.push_str(r#"<xmi:XMI xmi:version="2.1""#);
-    out.push('\n');
-    out.push_str(r#"  xmlns:xmi="http://schema.omg.org/spec/XMI/2.1""#);
-    out.push('\n');
-    out.push_str(r#"  xmlns:uml="http://www.eclipse.org/uml2/5.0.0/UML""#);
-    out.push('\n');
-    out.push_str(r#"  xmlns:SysML="http://www.eclipse.org/papyrus/2/SysML/1.6""#);
-    out.push('\n');
-    out.push_str(r#"  xmlns:Blocks="http://www.eclipse.org/papyrus/2/SysML/1.6/Blocks""#);
-    out.push('\n');
-    out.push_str(r#"  xmlns:Requirements="http://www.eclipse.org/papyrus/2/SysML/1.6/Requirements">"#);
-    out.push('\n');
+/// Streaming form of [`to_xmi`] for large models. `progress` is called with
+/// `(elements written, total)` between elements; returning `false` stops the
+/// export early and the function returns `Ok(false)`.
+pub fn write_xmi<W: Write>(
+    out: &mut W,
+    project: &Project,
+    nodes: &[Node],
+    edges: &[Edge],
+    properties: &[BlockProperty],
+    progress: &mut dyn FnMut(usize, usize) -> bool,
+) -> Result<bool> {
+    let node_ids: HashSet<Uuid> = nodes.iter().map(|n| n.id).collect();
+    let mut properties_by_block: HashMap<Uuid, Vec<&BlockProperty>> = HashMap::new();
+    for prop in properties {
+        properties_by_block.entry(prop.block_node_id).or_default().push(prop);
+    }
+    let structure = CompositionStructure::new(nodes, edges);
+
+    let mut buf = String::with_capacity(8192);
+
+    buf.push_str(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    buf.push('\n');
+    buf.push_str(r#"<xmi:XMI xmi:version="2.1""#);
+    buf.push('\n');
+    buf.push_str(r#"  xmlns:xmi="http://schema.omg.org/spec/XMI/2.1""#);
+    buf.push('\n');
+    buf.push_str(r#"  xmlns:uml="http://www.eclipse.org/uml2/5.0.0/UML""#);
+    buf.push('\n');
+    buf.push_str(r#"  xmlns:SysML="http://www.eclipse.org/papyrus/2/SysML/1.6""#);
+    buf.push('\n');
+    buf.push_str(r#"  xmlns:Blocks="http://www.eclipse.org/papyrus/2/SysML/1.6/Blocks""#);
+    buf.push('\n');
+    buf.push_str(r#"  xmlns:Requirements="http://www.eclipse.org/papyrus/2/SysML/1.6/Requirements">"#);
+    buf.push('\n');
 
     // ── UML Model element ────────────────────────────────────────────────────
     let model_id = format!("_{}", project.id.to_string().replace('-', ""));
-    out.push_str(&format!(
+    buf.push_str(&format!(
         r#"  <uml:Model xmi:id="{}" name="{}">"#,
         model_id,
         xml_escape(&project.name)
     ));
-    out.push('\n');
+    buf.push('\n');
 
     // ── Package containing all elements ──────────────────────────────────────
-    out.push_str(&format!(
+    buf.push_str(&format!(
         r#"    <packagedElement xmi:type="uml:Package" xmi:id="{}_pkg" name="{}">"#,
         model_id,
         xml_escape(&project.name)
     ));
-    out.push('\n');
+    buf.push('\n');
 
     // Emit each node as a packagedElement
+    let total = nodes.len() + edges.len();
+    let mut done = 0;
     for node in nodes {
+        out.write_all(buf.as_bytes())?;
+        buf.clear();
+        if !progress(done, total) {
+            return Ok(false);
+        }
+        done += 1;
+
+        // Owned ports are written inside their block.
+        if structure.port_owner.contains_key(&node.id) {
+            continue;
+        }
+
         let nid = format!("_{}", node.id.to_string().replace('-', ""));
         let (uml_type, extra_attrs) = node_uml_type(node);
 
-        out.push_str(&format!(
-            r#"      <packagedElement xmi:type="{}" xmi:id="{}" name="{}"{}/>"#,
+        let owned = properties_by_block.get(&node.id).cloned().unwrap_or_default();
+        let parts = structure.parts.get(&node.id).map(Vec::as_slice).unwrap_or_default();
+        let ports = structure.ports.get(&node.id).map(Vec::as_slice).unwrap_or_default();
+        if owned.is_empty() && parts.is_empty() && ports.is_empty() {
+            buf.push_str(&format!(
+                r#"      <packagedElement xmi:type="{}" xmi:id="{}" name="{}"{}/>"#,
+                uml_type,
+                nid,
+                xml_escape(&node.name),
+                extra_attrs,
+            ));
+            buf.push('\n');
+            continue;
+        }
+
+        // Block value properties become ownedAttributes typed by their ValueType.
+        buf.push_str(&format!(
+            r#"      <packagedElement xmi:type="{}" xmi:id="{}" name="{}"{}>"#,
             uml_type,
             nid,
             xml_escape(&node.name),
             extra_attrs,
         ));
-        out.push('\n');
+        buf.push('\n');
+        for prop in owned {
+            let pid = format!("_{}", prop.id.to_string().replace('-', ""));
+            let type_attr = prop
+                .value_type_node_id
+                .filter(|vt| node_ids.contains(vt))
+                .map(|vt| format!(r#" type="_{}""#, vt.to_string().replace('-', "")))
+                .unwrap_or_default();
+            if prop.value.is_empty() {
+                buf.push_str(&format!(
+                    r#"        <ownedAttribute xmi:type="uml:Property" xmi:id="{}" name="{}" aggregation="composite"{}/>"#,
+                    pid,
+                    xml_escape(&prop.name),
+                    type_attr,
+                ));
+                buf.push('\n');
+            } else {
+                buf.push_str(&format!(
+                    r#"        <ownedAttribute xmi:type="uml:Property" xmi:id="{}" name="{}" aggregation="composite"{}>"#,
+                    pid,
+                    xml_escape(&prop.name),
+                    type_attr,
+                ));
+                buf.push('\n');
+                buf.push_str(&format!(
+                    r#"          <defaultValue xmi:type="uml:LiteralString" xmi:id="{}_dv" value="{}"/>"#,
+                    pid,
+                    xml_escape(&prop.value),
+                ));
+                buf.push('\n');
+                buf.push_str("        </ownedAttribute>\n");
+            }
+        }
+        for (edge, part) in parts {
+            let eid = format!("_{}", edge.id.to_string().replace('-', ""));
+            let name = if edge.label.is_empty() { &part.name } else { &edge.label };
+            buf.push_str(&format!(
+                r#"        <ownedAttribute xmi:type="uml:Property" xmi:id="{}_part" name="{}" type="_{}" aggregation="composite" association="{}"/>"#,
+                eid,
+                xml_escape(name),
+                part.id.to_string().replace('-', ""),
+                eid,
+            ));
+            buf.push('\n');
+        }
+        for port in ports {
+            let pid = format!("_{}", port.id.to_string().replace('-', ""));
+            let type_attr = match &port.data {
+                NodeData::Port(p) => p
+                    .type_ref
+                    .filter(|t| node_ids.contains(t))
+                    .map(|t| format!(r#" type="_{}""#, t.to_string().replace('-', "")))
+                    .unwrap_or_default(),
+                _ => String::new(),
+            };
+            buf.push_str(&format!(
+                r#"        <ownedAttribute xmi:type="uml:Port" xmi:id="{}" name="{}" aggregation="composite"{}/>"#,
+                pid,
+                xml_escape(&port.name),
+                type_attr,
+            ));
+            buf.push('\n');
+        }
+        buf.push_str("      </packagedElement>\n");
     }
 
     // Emit edges as UML relationships
     for edge in edges {
+        out.write_all(buf.as_bytes())?;
+        buf.clear();
+        if !progress(done, total) {
+            return Ok(false);
+        }
+        done += 1;
+
+        // Port nesting is the relationship; nothing else to write.
+        if structure.nesting_edges.contains(&edge.id) {
+            continue;
+        }
+
         let eid = format!("_{}", edge.id.to_string().replace('-', ""));
         let src = format!("_{}", edge.source_id.to_string().replace('-', ""));
         let tgt = format!("_{}", edge.target_id.to_string().replace('-', ""));
-        let (rel_type, extra) = edge_uml_type(edge);
 
-        out.push_str(&format!(
-            r#"      <packagedElement xmi:type="{}" xmi:id="{}" client="{}" supplier="{}"{}/>"#,
-            rel_type, eid, src, tgt, extra,
-        ));
-        out.push('\n');
+        // A part property's Association: the part end is owned by the
+        // parent class, the owner end by the Association itself.
+        if structure.part_edges.contains(&edge.id) {
+            let (_, name_attr) = edge_uml_type(edge);
+            let owner_name = structure
+                .names
+                .get(&edge.source_id)
+                .map(|n| xml_escape(n))
+                .unwrap_or_default();
+            buf.push_str(&format!(
+                r#"      <packagedElement xmi:type="uml:Association" xmi:id="{}"{} memberEnd="{}_part {}_owner">"#,
+                eid, name_attr, eid, eid,
+            ));
+            buf.push('\n');
+            buf.push_str(&format!(
+                r#"        <ownedEnd xmi:type="uml:Property" xmi:id="{}_owner" name="{}" type="{}" association="{}"/>"#,
+                eid, owner_name, src, eid,
+            ));
+            buf.push('\n');
+            if let Some(rationale) = edge_rationale(edge) {
+                buf.push_str(&format!(
+                    r#"        <ownedComment xmi:type="uml:Comment" xmi:id="{}_rationale" body="{}" annotatedElement="{}"/>"#,
+                    eid,
+                    xml_escape(rationale),
+                    eid,
+                ));
+                buf.push('\n');
+            }
+            buf.push_str("      </packagedElement>\n");
+            continue;
+        }
+
+        let (rel_type, extra) = edge_uml_type(edge);
+        match edge_rationale(edge) {
+            None => {
+                buf.push_str(&format!(
+                    r#"      <packagedElement xmi:type="{}" xmi:id="{}" client="{}" supplier="{}"{}/>"#,
+                    rel_type, eid, src, tgt, extra,
+                ));
+                buf.push('\n');
+            }
+            Some(rationale) => {
+                buf.push_str(&format!(
+                    r#"      <packagedElement xmi:type="{}" xmi:id="{}" client="{}" supplier="{}"{}>"#,
+                    rel_type, eid, src, tgt, extra,
+                ));
+                buf.push('\n');
+                buf.push_str(&format!(
+                    r#"        <ownedComment xmi:type="uml:Comment" xmi:id="{}_rationale" body="{}" annotatedElement="{}"/>"#,
+                    eid,
+                    xml_escape(rationale),
+                    eid,
+                ));
+                buf.push('\n');
+                buf.push_str("      </packagedElement>\n");
+            }
+        }
     }
 
-    out.push_str("    </packagedElement>\n");
-    out.push_str("  </uml:Model>\n");
+    buf.push_str("    </packagedElement>\n");
+    buf.push_str("  </uml:Model>\n");
 
     // ── SysML stereotype applications ─────────────────────────────────────────
     for node in nodes {
         let nid = format!("_{}", node.id.to_string().replace('-', ""));
         match node.kind {
             crate::core::model::NodeKind::Block => {
-                out.push_str(&format!(
+                buf.push_str(&format!(
                     r#"  <Blocks:Block xmi:id="{}_st" base_Class="{}"/>"#,
                     nid, nid
                 ));
-                out.push('\n');
+                buf.push('\n');
             }
             crate::core::model::NodeKind::Requirement => {
                 let req_text = if let crate::core::model::NodeData::Requirement(r) = &node.data {
@@ -224,34 +1193,36 @@ pub fn to_xmi(project: &Project, nodes: &[Node], edges: &[Edge]) -> String {
                 } else {
                     String::new()
                 };
-                out.push_str(&format!(
+                buf.push_str(&format!(
                     r#"  <Requirements:Requirement xmi:id="{}_st" base_Class="{}" text="{}"/>"#,
                     nid,
                     nid,
                     xml_escape(&req_text)
                 ));
-                out.push('\n');
+                buf.push('\n');
             }
             crate::core::model::NodeKind::ValueType => {
-                out.push_str(&format!(
+                buf.push_str(&format!(
                     r#"  <Blocks:ValueType xmi:id="{}_st" base_DataType="{}"/>"#,
                     nid, nid
                 ));
-                out.push('\n');
+                buf.push('\n');
             }
             crate::core::model::NodeKind::ConstraintBlock => {
-                out.push_str(&format!(
+                buf.push_str(&format!(
                     r#"  <Blocks:ConstraintBlock xmi:id="{}_st" base_Class="{}"/>"#,
                     nid, nid
                 ));
-                out.push('\n');
+                buf.push('\n');
             }
             _ => {}
         }
     }
 
-    out.push_str("</xmi:XMI>\n");
-    out
+    buf.push_str("</xmi:XMI>\n");
+    out.write_all(buf.as_bytes())?;
+    progress(total, total);
+    Ok(true)
 }
 
 fn node_uml_type(node: &Node) -> (&'static str, String) {
@@ -287,9 +1258,9 @@ fn edge_uml_type(edge: &Edge) -> (&'static str, String) {
         }
         EdgeKind::Verifies => ("uml:Dependency", name_attr),
         EdgeKind::Connects => ("uml:AssociationClass", name_attr),
-        EdgeKind::Composes => {
-            ("uml:Association", format!(r#"{} aggregation="composite""#, name_attr))
-        }
+        // Block composition is written as part properties; this covers
+        // «composes» edges between other kinds.
+        EdgeKind::Composes => ("uml:Dependency", name_attr),
         EdgeKind::Specializes => ("uml:Generalization", name_attr),
         EdgeKind::Blocks => ("uml:Dependency", name_attr),
         EdgeKind::Transition => ("uml:Transition", name_attr),
@@ -297,10 +1268,86 @@ fn edge_uml_type(edge: &Edge) -> (&'static str, String) {
     }
 }
 
-fn xml_escape(s: &str) -> String {
-    s.replace('&', "&amp;")
-        .replace('<', "&lt;")
-        .replace('>', "&gt;")
-        .replace('"', "&quot;")
-        .replace('\'', "&apos;")
+/// Which «composes» edges `write_xmi` turns into owned structure.
+struct CompositionStructure<'a> {
+    /// Block → (edge, part block), in edge order.
+    parts: HashMap<Uuid, Vec<(&'a Edge, &'a Node)>>,
+    /// Block → ports nested in it, in edge order.
+    ports: HashMap<Uuid, Vec<&'a Node>>,
+    /// Port → its owning block. A port composed by several blocks belongs
+    /// to the first; the other edges are written as plain relationships.
+    port_owner: HashMap<Uuid, Uuid>,
+    part_edges: HashSet<Uuid>,
+    nesting_edges: HashSet<Uuid>,
+    names: HashMap<Uuid, &'a str>,
+}
+
+impl<'a> CompositionStructure<'a> {
+    fn new(nodes: &'a [Node], edges: &'a [Edge]) -> Self {
+        use crate::core::model::{EdgeKind, NodeKind};
+        let by_id: HashMap<Uuid, &Node> = nodes.iter().map(|n| (n.id, n)).collect();
+        let mut structure = CompositionStructure {
+            parts: HashMap::new(),
+            ports: HashMap::new(),
+            port_owner: HashMap::new(),
+            part_edges: HashSet::new(),
+            nesting_edges: HashSet::new(),
+            names: nodes.iter().map(|n| (n.id, n.name.as_str())).collect(),
+        };
+        for edge in edges.iter().filter(|e| e.kind == EdgeKind::Composes) {
+            let owner = by_id.get(&edge.source_id);
+            let child = by_id.get(&edge.target_id);
+            let (Some(owner), Some(child)) = (owner, child) else {
+                continue;
+            };
+            if owner.kind != NodeKind::Block || owner.id == child.id {
+                continue;
+            }
+            match child.kind {
+                NodeKind::Block => {
+                    structure.parts.entry(owner.id).or_default().push((edge, *child));
+                    structure.part_edges.insert(edge.id);
+                }
+                NodeKind::Port if !structure.port_owner.contains_key(&child.id) => {
+                    structure.port_owner.insert(child.id, owner.id);
+                    structure.ports.entry(owner.id).or_default().push(*child);
+                    structure.nesting_edges.insert(edge.id);
+                }
+                _ => {}
+            }
+        }
+        structure
+    }
 }
+
+/// Free-text `meta.rationale` on an edge, if set.
+fn edge_rationale(edge: &Edge) -> Option<&str> {
+    edge.meta
+        .get("rationale")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+}
+
+/// Escape text for XML (and HTML) content and attribute values, dropping
+/// the control characters XML 1.0 can't carry. The one escaper every
+/// writer in the crate uses.
+pub(crate) fn xml_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            '\t' | '\n' | '\r' => out.push(c),
+            c if (c as u32) < 0x20 => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,509 @@
+/// ReqIF 1.2 export of the project's requirements, for hand-off to DOORS,
+/// Polarion and other requirements tools.
+///
+/// Each requirement becomes a SPEC-OBJECT carrying req_id, text, rationale,
+/// verification method, allocations, priority and status, and when it was
+/// created: here (CreatedAt) and, for an imported one, in its source
+/// (OriginalCreatedAt, written first so a re-import picks it). «satisfies»,
+/// «verifies», «refines», «derives» and «traces» edges become
+/// SPEC-RELATIONs. A relation end that is not a requirement (the block
+/// satisfying it, the test case verifying it) is written as a SPEC-OBJECT
+/// of a second "Model Element" type with its kind, so every relation
+/// points at an object in the file.
+///
+/// The one SPECIFICATION holds the requirements as the derive tree: a
+/// requirement sits under the one it refines or derives from, siblings in
+/// req_id order. One with several parents stays under the first.
+///
+/// Child elements follow the order of the ReqIF 1.2 schema (VALUES before
+/// TYPE, TARGET before SOURCE, CHILDREN before TYPE), which ReqIF Studio
+/// enforces.
+///
+/// The priority and status enumerations list only the values some
+/// requirement uses. Archived nodes are left out, as are edges to them.
+use super::vcrm::natural_cmp;
+use super::xml_escape;
+use crate::core::model::{
+    Edge, EdgeKind, Node, NodeData, Project, RequirementData, RequirementPriority,
+    RequirementStatus, VerificationMethod,
+};
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+const NAMESPACE: &str = "http://www.omg.org/spec/ReqIF/20110401/reqif.xsd";
+const TOOL_ID: &str = "SystemProduct";
+
+const DT_STRING: &str = "_dt-string";
+const DT_PRIORITY: &str = "_dt-priority";
+const DT_STATUS: &str = "_dt-status";
+const TYPE_REQUIREMENT: &str = "_type-requirement";
+const TYPE_ELEMENT: &str = "_type-model-element";
+const TYPE_SPECIFICATION: &str = "_type-specification";
+const ATTR_KIND: &str = "_attr-kind";
+const ATTR_PRIORITY: &str = "_attr-priority";
+const ATTR_STATUS: &str = "_attr-status";
+
+/// String attributes of a requirement, as (identifier, long name).
+const REQUIREMENT_STRINGS: [(&str, &str); 7] = [
+    ("_attr-req-id", "ReqID"),
+    ("_attr-text", "Text"),
+    ("_attr-rationale", "Rationale"),
+    ("_attr-verification-method", "VerificationMethod"),
+    ("_attr-allocations", "Allocations"),
+    ("_attr-original-created-at", "OriginalCreatedAt"),
+    ("_attr-created-at", "CreatedAt"),
+];
+
+/// Edge kinds written as SPEC-RELATIONs.
+const RELATION_KINDS: [EdgeKind; 5] = [
+    EdgeKind::Satisfies,
+    EdgeKind::Verifies,
+    EdgeKind::Refines,
+    EdgeKind::Derives,
+    EdgeKind::Traces,
+];
+
+fn priority_label(p: &RequirementPriority) -> &'static str {
+    match p {
+        RequirementPriority::Shall => "shall",
+        RequirementPriority::Should => "should",
+        RequirementPriority::May => "may",
+    }
+}
+
+fn verification_label(v: &VerificationMethod) -> &'static str {
+    match v {
+        VerificationMethod::Analysis => "analysis",
+        VerificationMethod::Test => "test",
+        VerificationMethod::Inspection => "inspection",
+        VerificationMethod::Demonstration => "demonstration",
+    }
+}
+
+fn status_label(s: &RequirementStatus) -> &'static str {
+    match s {
+        RequirementStatus::Draft => "draft",
+        RequirementStatus::Approved => "approved",
+        RequirementStatus::Obsolete => "obsolete",
+    }
+}
+
+/// An attribute value; newlines are kept as character references, which
+/// attribute-value normalization would otherwise turn into spaces.
+fn attr(s: &str) -> String {
+    xml_escape(s).replace('\r', "&#13;").replace('\n', "&#10;")
+}
+
+fn object_id(id: Uuid) -> String {
+    format!("_{id}")
+}
+
+fn timestamp(t: &DateTime<Utc>) -> String {
+    t.to_rfc3339()
+}
+
+pub fn to_reqif(project: &Project, nodes: &[Node], edges: &[Edge]) -> String {
+    let now = timestamp(&Utc::now());
+    let active: HashMap<Uuid, &Node> = nodes
+        .iter()
+        .filter(|n| !n.archived)
+        .map(|n| (n.id, n))
+        .collect();
+
+    let mut requirements: Vec<(&Node, &RequirementData)> = nodes
+        .iter()
+        .filter(|n| !n.archived)
+        .filter_map(|n| match &n.data {
+            NodeData::Requirement(r) => Some((n, r)),
+            _ => None,
+        })
+        .collect();
+    requirements.sort_by(|(a, ra), (b, rb)| {
+        let key = |r: &RequirementData| r.req_id.clone().unwrap_or_default();
+        natural_cmp(&key(ra), &key(rb))
+            .then_with(|| natural_cmp(&a.name, &b.name))
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    let requirement_ids: HashSet<Uuid> = requirements.iter().map(|(n, _)| n.id).collect();
+
+    let mut relations: Vec<&Edge> = edges
+        .iter()
+        .filter(|e| RELATION_KINDS.contains(&e.kind))
+        .filter(|e| active.contains_key(&e.source_id) && active.contains_key(&e.target_id))
+        .filter(|e| {
+            requirement_ids.contains(&e.source_id) || requirement_ids.contains(&e.target_id)
+        })
+        .collect();
+    relations.sort_by(|a, b| {
+        a.kind
+            .to_string()
+            .cmp(&b.kind.to_string())
+            .then_with(|| a.id.cmp(&b.id))
+    });
+
+    // Relation ends that aren't requirements, in first-seen order.
+    let mut elements: Vec<&Node> = Vec::new();
+    for edge in &relations {
+        for id in [edge.source_id, edge.target_id] {
+            if !requirement_ids.contains(&id) && !elements.iter().any(|n| n.id == id) {
+                elements.push(active[&id]);
+            }
+        }
+    }
+
+    let priorities: Vec<RequirementPriority> = [
+        RequirementPriority::Shall,
+        RequirementPriority::Should,
+        RequirementPriority::May,
+    ]
+    .into_iter()
+    .filter(|p| requirements.iter().any(|(_, r)| r.priority == *p))
+    .collect();
+    let statuses: Vec<RequirementStatus> = [
+        RequirementStatus::Draft,
+        RequirementStatus::Approved,
+        RequirementStatus::Obsolete,
+    ]
+    .into_iter()
+    .filter(|s| requirements.iter().any(|(_, r)| r.status == *s))
+    .collect();
+    let priority_labels: Vec<&str> = priorities.iter().map(priority_label).collect();
+    let status_labels: Vec<&str> = statuses.iter().map(status_label).collect();
+
+    let mut out = String::with_capacity(8192);
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str(&format!("<REQ-IF xmlns=\"{NAMESPACE}\">\n"));
+    out.push_str("  <THE-HEADER>\n");
+    out.push_str(&format!(
+        "    <REQ-IF-HEADER IDENTIFIER=\"_header-{}\">\n",
+        project.id
+    ));
+    out.push_str(&format!("      <CREATION-TIME>{now}</CREATION-TIME>\n"));
+    out.push_str(&format!(
+        "      <REQ-IF-TOOL-ID>{TOOL_ID}</REQ-IF-TOOL-ID>\n"
+    ));
+    out.push_str("      <REQ-IF-VERSION>1.0</REQ-IF-VERSION>\n");
+    out.push_str(&format!(
+        "      <SOURCE-TOOL-ID>{TOOL_ID}</SOURCE-TOOL-ID>\n"
+    ));
+    out.push_str(&format!(
+        "      <TITLE>{}</TITLE>\n",
+        xml_escape(&project.name)
+    ));
+    out.push_str("    </REQ-IF-HEADER>\n");
+    out.push_str("  </THE-HEADER>\n");
+    out.push_str("  <CORE-CONTENT>\n");
+    out.push_str("    <REQ-IF-CONTENT>\n");
+
+    // ── Datatypes ────────────────────────────────────────────────────────────
+    out.push_str("      <DATATYPES>\n");
+    out.push_str(&format!(
+        "        <DATATYPE-DEFINITION-STRING IDENTIFIER=\"{DT_STRING}\" LAST-CHANGE=\"{now}\" \
+         LONG-NAME=\"String\" MAX-LENGTH=\"65535\"/>\n"
+    ));
+    push_enum_datatype(&mut out, DT_PRIORITY, "Priority", &priority_labels, &now);
+    push_enum_datatype(&mut out, DT_STATUS, "Status", &status_labels, &now);
+    out.push_str("      </DATATYPES>\n");
+
+    // ── Spec types ───────────────────────────────────────────────────────────
+    out.push_str("      <SPEC-TYPES>\n");
+    out.push_str(&format!(
+        "        <SPEC-OBJECT-TYPE IDENTIFIER=\"{TYPE_REQUIREMENT}\" LAST-CHANGE=\"{now}\" \
+         LONG-NAME=\"Requirement\">\n"
+    ));
+    out.push_str("          <SPEC-ATTRIBUTES>\n");
+    for (id, name) in REQUIREMENT_STRINGS {
+        push_string_attribute_definition(&mut out, id, name, &now);
+    }
+    if !priority_labels.is_empty() {
+        push_enum_attribute_definition(&mut out, ATTR_PRIORITY, "Priority", DT_PRIORITY, &now);
+    }
+    if !status_labels.is_empty() {
+        push_enum_attribute_definition(&mut out, ATTR_STATUS, "Status", DT_STATUS, &now);
+    }
+    out.push_str("          </SPEC-ATTRIBUTES>\n");
+    out.push_str("        </SPEC-OBJECT-TYPE>\n");
+    if !elements.is_empty() {
+        out.push_str(&format!(
+            "        <SPEC-OBJECT-TYPE IDENTIFIER=\"{TYPE_ELEMENT}\" LAST-CHANGE=\"{now}\" \
+             LONG-NAME=\"Model Element\">\n"
+        ));
+        out.push_str("          <SPEC-ATTRIBUTES>\n");
+        push_string_attribute_definition(&mut out, ATTR_KIND, "Kind", &now);
+        out.push_str("          </SPEC-ATTRIBUTES>\n");
+        out.push_str("        </SPEC-OBJECT-TYPE>\n");
+    }
+    for kind in RELATION_KINDS
+        .iter()
+        .filter(|k| relations.iter().any(|e| e.kind == **k))
+    {
+        out.push_str(&format!(
+            "        <SPEC-RELATION-TYPE IDENTIFIER=\"_reltype-{kind}\" LAST-CHANGE=\"{now}\" \
+             LONG-NAME=\"{kind}\"/>\n"
+        ));
+    }
+    out.push_str(&format!(
+        "        <SPECIFICATION-TYPE IDENTIFIER=\"{TYPE_SPECIFICATION}\" LAST-CHANGE=\"{now}\" \
+         LONG-NAME=\"Specification\"/>\n"
+    ));
+    out.push_str("      </SPEC-TYPES>\n");
+
+    // ── Spec objects ─────────────────────────────────────────────────────────
+    out.push_str("      <SPEC-OBJECTS>\n");
+    for (node, r) in &requirements {
+        open_spec_object(&mut out, node);
+        let allocations = r.allocations.as_ref().map(|a| a.join("; "));
+        let original_created = node.original_created_at().map(|t| timestamp(&t));
+        let created = timestamp(&node.created_at);
+        let strings = [
+            r.req_id.as_deref(),
+            r.text.as_deref(),
+            r.rationale.as_deref(),
+            r.verification_method.as_ref().map(verification_label),
+            allocations.as_deref(),
+            original_created.as_deref(),
+            Some(created.as_str()),
+        ];
+        for ((id, _), value) in REQUIREMENT_STRINGS.iter().zip(strings) {
+            if let Some(value) = value.filter(|v| !v.trim().is_empty()) {
+                push_string_value(&mut out, id, value);
+            }
+        }
+        push_enum_value(
+            &mut out,
+            ATTR_PRIORITY,
+            DT_PRIORITY,
+            priority_label(&r.priority),
+        );
+        push_enum_value(&mut out, ATTR_STATUS, DT_STATUS, status_label(&r.status));
+        close_spec_object(&mut out, TYPE_REQUIREMENT);
+    }
+    for node in &elements {
+        open_spec_object(&mut out, node);
+        push_string_value(&mut out, ATTR_KIND, &node.kind.to_string());
+        close_spec_object(&mut out, TYPE_ELEMENT);
+    }
+    out.push_str("      </SPEC-OBJECTS>\n");
+
+    // ── Spec relations ───────────────────────────────────────────────────────
+    out.push_str("      <SPEC-RELATIONS>\n");
+    for edge in &relations {
+        let long_name = if edge.label.trim().is_empty() {
+            String::new()
+        } else {
+            format!(" LONG-NAME=\"{}\"", attr(&edge.label))
+        };
+        out.push_str(&format!(
+            "        <SPEC-RELATION IDENTIFIER=\"{}\" LAST-CHANGE=\"{}\"{long_name}>\n",
+            object_id(edge.id),
+            timestamp(&edge.modified_at)
+        ));
+        out.push_str(&format!(
+            "          <TARGET><SPEC-OBJECT-REF>{}</SPEC-OBJECT-REF></TARGET>\n",
+            object_id(edge.target_id)
+        ));
+        out.push_str(&format!(
+            "          <SOURCE><SPEC-OBJECT-REF>{}</SPEC-OBJECT-REF></SOURCE>\n",
+            object_id(edge.source_id)
+        ));
+        out.push_str(&format!(
+            "          <TYPE><SPEC-RELATION-TYPE-REF>_reltype-{}</SPEC-RELATION-TYPE-REF></TYPE>\n",
+            edge.kind
+        ));
+        out.push_str("        </SPEC-RELATION>\n");
+    }
+    out.push_str("      </SPEC-RELATIONS>\n");
+
+    // ── Specification ────────────────────────────────────────────────────────
+    let parents = derive_parents(&relations, &requirement_ids);
+    let mut roots: Vec<&Node> = Vec::new();
+    let mut children: HashMap<Uuid, Vec<&Node>> = HashMap::new();
+    for (node, _) in &requirements {
+        match parents.get(&node.id) {
+            Some(parent) => children.entry(*parent).or_default().push(node),
+            None => roots.push(node),
+        }
+    }
+    out.push_str("      <SPECIFICATIONS>\n");
+    out.push_str(&format!(
+        "        <SPECIFICATION IDENTIFIER=\"_spec-{}\" LAST-CHANGE=\"{now}\" LONG-NAME=\"{}\">\n",
+        project.id,
+        attr(&project.name)
+    ));
+    if !roots.is_empty() {
+        out.push_str("          <CHILDREN>\n");
+        for node in &roots {
+            push_hierarchy(&mut out, node, &children, 6, &now);
+        }
+        out.push_str("          </CHILDREN>\n");
+    }
+    out.push_str(&format!(
+        "          <TYPE><SPECIFICATION-TYPE-REF>{TYPE_SPECIFICATION}</SPECIFICATION-TYPE-REF></TYPE>\n"
+    ));
+    out.push_str("        </SPECIFICATION>\n");
+    out.push_str("      </SPECIFICATIONS>\n");
+
+    out.push_str("    </REQ-IF-CONTENT>\n");
+    out.push_str("  </CORE-CONTENT>\n");
+    out.push_str("</REQ-IF>\n");
+    out
+}
+
+/// An enumeration datatype with one ENUM-VALUE per label, identified as
+/// `{datatype}-{label}`. Nothing is written for an empty list.
+fn push_enum_datatype(out: &mut String, id: &str, name: &str, labels: &[&str], now: &str) {
+    if labels.is_empty() {
+        return;
+    }
+    out.push_str(&format!(
+        "        <DATATYPE-DEFINITION-ENUMERATION IDENTIFIER=\"{id}\" LAST-CHANGE=\"{now}\" \
+         LONG-NAME=\"{name}\">\n"
+    ));
+    out.push_str("          <SPECIFIED-VALUES>\n");
+    for (key, label) in labels.iter().enumerate() {
+        out.push_str(&format!(
+            "            <ENUM-VALUE IDENTIFIER=\"{id}-{label}\" LAST-CHANGE=\"{now}\" \
+             LONG-NAME=\"{label}\">\n"
+        ));
+        out.push_str(&format!(
+            "              <PROPERTIES><EMBEDDED-VALUE KEY=\"{key}\" \
+             OTHER-CONTENT=\"{label}\"/></PROPERTIES>\n"
+        ));
+        out.push_str("            </ENUM-VALUE>\n");
+    }
+    out.push_str("          </SPECIFIED-VALUES>\n");
+    out.push_str("        </DATATYPE-DEFINITION-ENUMERATION>\n");
+}
+
+fn push_string_attribute_definition(out: &mut String, id: &str, name: &str, now: &str) {
+    out.push_str(&format!(
+        "            <ATTRIBUTE-DEFINITION-STRING IDENTIFIER=\"{id}\" LAST-CHANGE=\"{now}\" \
+         LONG-NAME=\"{name}\">\n"
+    ));
+    out.push_str(&format!(
+        "              <TYPE><DATATYPE-DEFINITION-STRING-REF>{DT_STRING}\
+         </DATATYPE-DEFINITION-STRING-REF></TYPE>\n"
+    ));
+    out.push_str("            </ATTRIBUTE-DEFINITION-STRING>\n");
+}
+
+fn push_enum_attribute_definition(
+    out: &mut String,
+    id: &str,
+    name: &str,
+    datatype: &str,
+    now: &str,
+) {
+    out.push_str(&format!(
+        "            <ATTRIBUTE-DEFINITION-ENUMERATION IDENTIFIER=\"{id}\" LAST-CHANGE=\"{now}\" \
+         LONG-NAME=\"{name}\" MULTI-VALUED=\"false\">\n"
+    ));
+    out.push_str(&format!(
+        "              <TYPE><DATATYPE-DEFINITION-ENUMERATION-REF>{datatype}\
+         </DATATYPE-DEFINITION-ENUMERATION-REF></TYPE>\n"
+    ));
+    out.push_str("            </ATTRIBUTE-DEFINITION-ENUMERATION>\n");
+}
+
+fn open_spec_object(out: &mut String, node: &Node) {
+    out.push_str(&format!(
+        "        <SPEC-OBJECT IDENTIFIER=\"{}\" LAST-CHANGE=\"{}\" LONG-NAME=\"{}\">\n",
+        object_id(node.id),
+        timestamp(&node.modified_at),
+        attr(&node.name)
+    ));
+    out.push_str("          <VALUES>\n");
+}
+
+fn close_spec_object(out: &mut String, spec_type: &str) {
+    out.push_str("          </VALUES>\n");
+    out.push_str(&format!(
+        "          <TYPE><SPEC-OBJECT-TYPE-REF>{spec_type}</SPEC-OBJECT-TYPE-REF></TYPE>\n"
+    ));
+    out.push_str("        </SPEC-OBJECT>\n");
+}
+
+fn push_string_value(out: &mut String, definition: &str, value: &str) {
+    out.push_str(&format!(
+        "            <ATTRIBUTE-VALUE-STRING THE-VALUE=\"{}\">\n",
+        attr(value)
+    ));
+    out.push_str(&format!(
+        "              <DEFINITION><ATTRIBUTE-DEFINITION-STRING-REF>{definition}\
+         </ATTRIBUTE-DEFINITION-STRING-REF></DEFINITION>\n"
+    ));
+    out.push_str("            </ATTRIBUTE-VALUE-STRING>\n");
+}
+
+fn push_enum_value(out: &mut String, definition: &str, datatype: &str, label: &str) {
+    out.push_str("            <ATTRIBUTE-VALUE-ENUMERATION>\n");
+    out.push_str(&format!(
+        "              <DEFINITION><ATTRIBUTE-DEFINITION-ENUMERATION-REF>{definition}\
+         </ATTRIBUTE-DEFINITION-ENUMERATION-REF></DEFINITION>\n"
+    ));
+    out.push_str(&format!(
+        "              <VALUES><ENUM-VALUE-REF>{datatype}-{label}</ENUM-VALUE-REF></VALUES>\n"
+    ));
+    out.push_str("            </ATTRIBUTE-VALUE-ENUMERATION>\n");
+}
+
+/// The requirement each requirement sits under in the specification: the
+/// target of its «refines» edge or the source of the «derives» edge to it.
+/// The first such edge in `relations` wins; one that would close a cycle
+/// is ignored.
+fn derive_parents(relations: &[&Edge], requirement_ids: &HashSet<Uuid>) -> HashMap<Uuid, Uuid> {
+    let mut parents: HashMap<Uuid, Uuid> = HashMap::new();
+    for edge in relations {
+        let (parent, child) = match edge.kind {
+            EdgeKind::Refines => (edge.target_id, edge.source_id),
+            EdgeKind::Derives => (edge.source_id, edge.target_id),
+            _ => continue,
+        };
+        if !requirement_ids.contains(&parent)
+            || !requirement_ids.contains(&child)
+            || parents.contains_key(&child)
+        {
+            continue;
+        }
+        let mut ancestor = Some(parent);
+        while let Some(id) = ancestor {
+            if id == child {
+                break;
+            }
+            ancestor = parents.get(&id).copied();
+        }
+        if ancestor.is_none() {
+            parents.insert(child, parent);
+        }
+    }
+    parents
+}
+
+/// A SPEC-HIERARCHY for `node` with its children nested inside, indented
+/// by `depth` levels.
+fn push_hierarchy(
+    out: &mut String,
+    node: &Node,
+    children: &HashMap<Uuid, Vec<&Node>>,
+    depth: usize,
+    now: &str,
+) {
+    let pad = "  ".repeat(depth);
+    out.push_str(&format!(
+        "{pad}<SPEC-HIERARCHY IDENTIFIER=\"_hier-{}\" LAST-CHANGE=\"{now}\">\n",
+        node.id
+    ));
+    if let Some(kids) = children.get(&node.id) {
+        out.push_str(&format!("{pad}  <CHILDREN>\n"));
+        for child in kids {
+            push_hierarchy(out, child, children, depth + 2, now);
+        }
+        out.push_str(&format!("{pad}  </CHILDREN>\n"));
+    }
+    out.push_str(&format!(
+        "{pad}  <OBJECT><SPEC-OBJECT-REF>{}</SPEC-OBJECT-REF></OBJECT>\n",
+        object_id(node.id)
+    ));
+    out.push_str(&format!("{pad}</SPEC-HIERARCHY>\n"));
+}
@@ -0,0 +1,289 @@
+/// Requirements traceability matrix (RTM) for design reviews: one row per
+/// requirement in req_id order, with the blocks that «satisfy» it, the test
+/// cases that «verify» it, the requirements above it and its allocations.
+///
+/// Parents are every requirement up the «refines» / «derives» chain,
+/// nearest first: a requirement refining SYS-2, which derives from SYS-1,
+/// lists "SYS-2; SYS-1". A requirement with no satisfying block or no
+/// verifying test case shows "(none)" there and is counted in the summary
+/// after the table.
+use super::md_cell;
+use super::vcrm::{natural_cmp, requirement_order};
+use crate::core::model::{Edge, EdgeKind, Node, NodeData, NodeKind};
+use crate::core::rollup::csv_field;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RtmFormat {
+    Csv,
+    Markdown,
+}
+
+impl RtmFormat {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "csv" => Some(Self::Csv),
+            "markdown" | "md" => Some(Self::Markdown),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RtmRow {
+    pub requirement_id: Uuid,
+    pub req_id: Option<String>,
+    pub name: String,
+    /// Block names, sorted.
+    pub satisfied_by: Vec<String>,
+    /// Test case names, sorted.
+    pub verified_by: Vec<String>,
+    /// Ancestor requirements (req_id, else name), nearest first.
+    pub parents: Vec<String>,
+    pub allocations: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Rtm {
+    pub rows: Vec<RtmRow>,
+}
+
+impl Rtm {
+    /// Requirements no block satisfies.
+    pub fn unsatisfied(&self) -> usize {
+        self.rows
+            .iter()
+            .filter(|r| r.satisfied_by.is_empty())
+            .count()
+    }
+
+    /// Requirements no test case verifies.
+    pub fn unverified(&self) -> usize {
+        self.rows
+            .iter()
+            .filter(|r| r.verified_by.is_empty())
+            .count()
+    }
+}
+
+/// Trace every live requirement in `nodes`. «satisfies» and «verifies»
+/// count in either direction as long as the other end is a block or test
+/// case; a parent link counts only between two requirements.
+pub fn build(nodes: &[Node], edges: &[Edge]) -> Rtm {
+    let live: HashMap<Uuid, &Node> = nodes
+        .iter()
+        .filter(|n| !n.archived)
+        .map(|n| (n.id, n))
+        .collect();
+    let is_requirement = |id: &Uuid| {
+        live.get(id)
+            .is_some_and(|n| n.kind == NodeKind::Requirement)
+    };
+
+    let mut satisfied: HashMap<Uuid, Vec<&Node>> = HashMap::new();
+    let mut verified: HashMap<Uuid, Vec<&Node>> = HashMap::new();
+    let mut parents: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+    for edge in edges {
+        let (Some(source), Some(target)) = (live.get(&edge.source_id), live.get(&edge.target_id))
+        else {
+            continue;
+        };
+        let ends = |kind: NodeKind| match (source.kind == kind, target.kind == kind) {
+            (true, false) if is_requirement(&target.id) => Some((target.id, *source)),
+            (false, true) if is_requirement(&source.id) => Some((source.id, *target)),
+            _ => None,
+        };
+        match edge.kind {
+            EdgeKind::Satisfies => {
+                if let Some((req, block)) = ends(NodeKind::Block) {
+                    satisfied.entry(req).or_default().push(block);
+                }
+            }
+            EdgeKind::Verifies => {
+                if let Some((req, test)) = ends(NodeKind::TestCase) {
+                    verified.entry(req).or_default().push(test);
+                }
+            }
+            EdgeKind::Refines | EdgeKind::Derives => {
+                let (parent, child) = if edge.kind == EdgeKind::Refines {
+                    (target.id, source.id)
+                } else {
+                    (source.id, target.id)
+                };
+                if is_requirement(&parent) && is_requirement(&child) && parent != child {
+                    parents.entry(child).or_default().push(parent);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut requirements: Vec<&Node> = live
+        .values()
+        .filter(|n| n.kind == NodeKind::Requirement)
+        .copied()
+        .collect();
+    requirements.sort_by(|a, b| requirement_order(a, b));
+    for list in parents.values_mut() {
+        list.sort_by(|a, b| requirement_order(live[a], live[b]));
+        list.dedup();
+    }
+
+    let names = |nodes: Option<&Vec<&Node>>| {
+        let mut names: Vec<String> = nodes
+            .into_iter()
+            .flatten()
+            .map(|n| n.name.clone())
+            .collect();
+        names.sort_by(|a, b| natural_cmp(a, b));
+        names.dedup();
+        names
+    };
+    let mut rtm = Rtm::default();
+    for node in requirements {
+        let NodeData::Requirement(r) = &node.data else {
+            continue;
+        };
+        rtm.rows.push(RtmRow {
+            requirement_id: node.id,
+            req_id: r.req_id.clone(),
+            name: node.name.clone(),
+            satisfied_by: names(satisfied.get(&node.id)),
+            verified_by: names(verified.get(&node.id)),
+            parents: ancestors(node.id, &parents)
+                .into_iter()
+                .map(|id| requirement_label(live[&id]))
+                .collect(),
+            allocations: r.allocations.clone().unwrap_or_default(),
+        });
+    }
+    rtm
+}
+
+/// Breadth-first up the parent links, so nearer requirements come first.
+/// `id` itself never appears, even when a cycle leads back to it.
+fn ancestors(id: Uuid, parents: &HashMap<Uuid, Vec<Uuid>>) -> Vec<Uuid> {
+    let mut seen: HashSet<Uuid> = HashSet::from([id]);
+    let mut found = Vec::new();
+    let mut frontier = vec![id];
+    while !frontier.is_empty() {
+        let mut next = Vec::new();
+        for child in frontier {
+            for parent in parents.get(&child).into_iter().flatten() {
+                if seen.insert(*parent) {
+                    found.push(*parent);
+                    next.push(*parent);
+                }
+            }
+        }
+        frontier = next;
+    }
+    found
+}
+
+fn requirement_label(node: &Node) -> String {
+    match &node.data {
+        NodeData::Requirement(r) => r
+            .req_id
+            .as_deref()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .unwrap_or(&node.name)
+            .to_string(),
+        _ => node.name.clone(),
+    }
+}
+
+const NONE: &str = "(none)";
+
+const HEADER: [&str; 6] = [
+    "req_id",
+    "requirement",
+    "satisfied_by",
+    "verified_by",
+    "parents",
+    "allocations",
+];
+
+fn row_fields(row: &RtmRow) -> [String; 6] {
+    let flagged = |list: &[String]| {
+        if list.is_empty() {
+            NONE.to_string()
+        } else {
+            list.join("; ")
+        }
+    };
+    [
+        row.req_id.clone().unwrap_or_default(),
+        row.name.clone(),
+        flagged(&row.satisfied_by),
+        flagged(&row.verified_by),
+        row.parents.join("; "),
+        row.allocations.join("; "),
+    ]
+}
+
+/// The matrix, then a blank line and a "summary" block of counts.
+pub fn to_csv(rtm: &Rtm) -> String {
+    let mut out = HEADER.join(",") + "\n";
+    for row in &rtm.rows {
+        let fields = row_fields(row);
+        out.push_str(
+            &fields
+                .iter()
+                .map(|f| csv_field(f))
+                .collect::<Vec<_>>()
+                .join(","),
+        );
+        out.push('\n');
+    }
+    out.push_str(&format!(
+        "\nsummary\nrequirements,{}\nno satisfying block,{}\nno verifying test case,{}\n",
+        rtm.rows.len(),
+        rtm.unsatisfied(),
+        rtm.unverified()
+    ));
+    out
+}
+
+pub fn to_markdown(rtm: &Rtm, project_name: &str) -> String {
+    let cell = |s: &str| {
+        if s.is_empty() {
+            "-".to_string()
+        } else if s == NONE {
+            format!("**{NONE}**")
+        } else {
+            md_cell(s)
+        }
+    };
+    let mut out = format!(
+        "# Requirements Traceability Matrix — {}\n\n",
+        md_cell(project_name)
+    );
+    out.push_str(
+        "| Req ID | Requirement | Satisfied By | Verified By | Parent Requirements \
+         | Allocations |\n",
+    );
+    out.push_str("|---|---|---|---|---|---|\n");
+    for row in &rtm.rows {
+        let fields = row_fields(row);
+        out.push_str(&format!(
+            "| {} |\n",
+            fields
+                .iter()
+                .map(|f| cell(f))
+                .collect::<Vec<_>>()
+                .join(" | ")
+        ));
+    }
+    out.push_str(&format!(
+        "\n**Summary:** {} requirements; {} with no satisfying block; \
+         {} with no verifying test case.\n",
+        rtm.rows.len(),
+        rtm.unsatisfied(),
+        rtm.unverified()
+    ));
+    out
+}
@@ -0,0 +1,177 @@
+/// Which part of a project the JSON and XMI exports write, and a cheap
+/// preview of such an export. The exporters and `export_preview` both go
+/// through [`resolve`], so the preview counts exactly what gets written.
+///
+/// The size estimate renders a sample of at most [`SAMPLE_SIZE`] nodes and
+/// as many edges with the real exporter into a byte counter and
+/// extrapolates per-node and per-edge costs to the whole scope. Models
+/// small enough to render whole are measured exactly.
+use crate::core::model::{BlockProperty, Edge, Node, NodeKind};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io::Write;
+use uuid::Uuid;
+
+/// Nodes (and, separately, edges) rendered for the size estimate.
+pub const SAMPLE_SIZE: usize = 300;
+
+/// The default scope is the whole project.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExportScope {
+    /// Node kinds to include; empty includes every kind.
+    #[serde(default)]
+    pub node_kinds: Vec<NodeKind>,
+    #[serde(default)]
+    pub exclude_archived: bool,
+}
+
+/// A project narrowed to an [`ExportScope`], edges in id order.
+#[derive(Debug, Clone, Default)]
+pub struct ScopedModel {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+    /// Only those of blocks in scope.
+    pub properties: Vec<BlockProperty>,
+    /// Edges left out because an end is outside the scope or missing.
+    pub dropped_edges: usize,
+}
+
+pub fn resolve(
+    scope: &ExportScope,
+    nodes: Vec<Node>,
+    mut edges: Vec<Edge>,
+    properties: Vec<BlockProperty>,
+) -> ScopedModel {
+    let nodes: Vec<Node> = nodes
+        .into_iter()
+        .filter(|n| scope.node_kinds.is_empty() || scope.node_kinds.contains(&n.kind))
+        .filter(|n| !(scope.exclude_archived && n.archived))
+        .collect();
+    let ids: HashSet<Uuid> = nodes.iter().map(|n| n.id).collect();
+    let total_edges = edges.len();
+    edges.retain(|e| ids.contains(&e.source_id) && ids.contains(&e.target_id));
+    edges.sort_by_key(|e| e.id);
+    let properties = properties
+        .into_iter()
+        .filter(|p| ids.contains(&p.block_node_id))
+        .collect();
+    ScopedModel {
+        dropped_edges: total_edges - edges.len(),
+        nodes,
+        edges,
+        properties,
+    }
+}
+
+/// What an export writes. JSON and XMI write no diagrams or documents,
+/// so those counts are 0 for them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExportCounts {
+    pub nodes: usize,
+    pub edges: usize,
+    pub block_properties: usize,
+    /// Rows of kinds this version doesn't know that the export writes.
+    pub unrecognized: usize,
+    pub diagrams: usize,
+    pub documents: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportWarning {
+    /// UNKNOWN_KINDS, TEXT_OVER_LIMIT, DANGLING_EDGES_DROPPED or NOT_EXPORTED.
+    pub code: &'static str,
+    pub message: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportPreview {
+    pub format: String,
+    pub counts: ExportCounts,
+    pub estimated_bytes: u64,
+    /// The whole scope was rendered, so `estimated_bytes` is the real size.
+    pub exact: bool,
+    pub warnings: Vec<ExportWarning>,
+}
+
+/// An output that only counts what is written to it.
+#[derive(Debug, Default)]
+pub struct ByteCounter(pub u64);
+
+impl Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Estimated output size of `model`. `render` writes an export of the
+/// given nodes, edges and properties and returns its size in bytes.
+/// Returns the estimate and whether it is exact.
+pub fn estimate_size(
+    model: &ScopedModel,
+    render: &dyn Fn(&[Node], &[Edge], &[BlockProperty]) -> anyhow::Result<u64>,
+) -> anyhow::Result<(u64, bool)> {
+    if model.nodes.len() <= SAMPLE_SIZE && model.edges.len() <= SAMPLE_SIZE {
+        let size = render(&model.nodes, &model.edges, &model.properties)?;
+        return Ok((size, true));
+    }
+
+    let base = render(&[], &[], &[])?;
+
+    let sample_nodes: Vec<Node> = spread(&model.nodes).into_iter().cloned().collect();
+    let with_nodes = render(&sample_nodes, &[], &properties_of(model, &sample_nodes))?;
+    let per_node = per_item(with_nodes, base, sample_nodes.len());
+
+    // Edges need their ends present to be written at all.
+    let sample_edges: Vec<Edge> = spread(&model.edges).into_iter().cloned().collect();
+    let ends: HashSet<Uuid> = sample_edges
+        .iter()
+        .flat_map(|e| [e.source_id, e.target_id])
+        .collect();
+    let end_nodes: Vec<Node> = model
+        .nodes
+        .iter()
+        .filter(|n| ends.contains(&n.id))
+        .cloned()
+        .collect();
+    let end_properties = properties_of(model, &end_nodes);
+    let without_edges = render(&end_nodes, &[], &end_properties)?;
+    let with_edges = render(&end_nodes, &sample_edges, &end_properties)?;
+    let per_edge = per_item(with_edges, without_edges, sample_edges.len());
+
+    let estimate =
+        base as f64 + per_node * model.nodes.len() as f64 + per_edge * model.edges.len() as f64;
+    Ok((estimate.round() as u64, false))
+}
+
+/// At most `SAMPLE_SIZE` items, evenly spread over `items`.
+fn spread<T>(items: &[T]) -> Vec<&T> {
+    if items.len() <= SAMPLE_SIZE {
+        return items.iter().collect();
+    }
+    (0..SAMPLE_SIZE)
+        .map(|i| &items[i * items.len() / SAMPLE_SIZE])
+        .collect()
+}
+
+fn properties_of(model: &ScopedModel, nodes: &[Node]) -> Vec<BlockProperty> {
+    let ids: HashSet<Uuid> = nodes.iter().map(|n| n.id).collect();
+    model
+        .properties
+        .iter()
+        .filter(|p| ids.contains(&p.block_node_id))
+        .cloned()
+        .collect()
+}
+
+fn per_item(with: u64, without: u64, items: usize) -> f64 {
+    if items == 0 {
+        return 0.0;
+    }
+    with.saturating_sub(without) as f64 / items as f64
+}
@@ -0,0 +1,578 @@
+/// Document generation from user templates, e.g. a company SRS skeleton
+/// whose placeholders are filled from the model. A template is parsed and
+/// checked up front, so an unknown placeholder fails with its line number
+/// before anything is written, then rendered through a [`DocumentWriter`].
+/// Only [`MarkdownWriter`] exists so far; the parsed form knows nothing
+/// about the output format.
+///
+/// Syntax:
+/// - `{{project.name}}`, `{{project.description}}`, `{{date}}`: values.
+/// - `{{#requirements status="approved"}}...{{/requirements}}`: the body
+///   once per requirement, filtered by `status`, `priority`, `verification`,
+///   `allocation` or `classification`. A filter value may list
+///   alternatives: `status="draft,approved"`. Inside, `{{req.id}}`,
+///   `{{req.text}}` and the other [`REQ_FIELDS`]. Archived requirements
+///   are left out.
+/// - `{{#archived}}...{{/archived}}`: the same for archived requirements,
+///   for an appendix of obsolete requirements kept for traceability.
+/// - `{{#traceability kind="satisfies"}}...{{/traceability}}`: the body once
+///   per edge, filtered by `kind`. Inside a requirements section only the
+///   requirement's own edges are listed. Inside, `{{link.source}}` and the
+///   other [`LINK_FIELDS`].
+///
+/// A section tag alone on its line removes the whole line, so section
+/// markers don't leave blank lines behind.
+use crate::core::model::{Edge, Node, NodeData, Project, RequirementData};
+use crate::core::store::parse_edge_kind;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+pub const PROJECT_FIELDS: &[&str] = &["name", "description"];
+
+pub const REQ_FIELDS: &[&str] = &[
+    "id",
+    "name",
+    "text",
+    "rationale",
+    "priority",
+    "status",
+    "verification",
+    "source",
+    "allocations",
+    "classification",
+    "description",
+];
+
+pub const LINK_FIELDS: &[&str] =
+    &["kind", "label", "source", "target", "source_kind", "target_kind"];
+
+/// A template problem, with the 1-based line it was found on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+fn error(line: usize, message: impl Into<String>) -> TemplateError {
+    TemplateError { line, message: message.into() }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SectionKind {
+    Requirements,
+    Archived,
+    Traceability,
+}
+
+impl SectionKind {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "requirements" => Some(Self::Requirements),
+            "archived" => Some(Self::Archived),
+            "traceability" => Some(Self::Traceability),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Requirements => "requirements",
+            Self::Archived => "archived",
+            Self::Traceability => "traceability",
+        }
+    }
+
+    fn lists_requirements(self) -> bool {
+        matches!(self, Self::Requirements | Self::Archived)
+    }
+
+    fn filter_keys(self) -> &'static [&'static str] {
+        match self {
+            Self::Requirements | Self::Archived => {
+                &["status", "priority", "verification", "allocation", "classification"]
+            }
+            Self::Traceability => &["kind"],
+        }
+    }
+}
+
+/// Section filter: every key must match one of its (lowercased) values.
+#[derive(Debug, Clone, Default)]
+struct Filter(Vec<(String, Vec<String>)>);
+
+impl Filter {
+    fn matches(&self, actual: impl Fn(&str) -> Vec<String>) -> bool {
+        self.0.iter().all(|(key, allowed)| {
+            actual(key).iter().any(|v| allowed.contains(&v.to_lowercase()))
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Part {
+    Text(String),
+    Value(String),
+    Section {
+        kind: SectionKind,
+        filter: Filter,
+        body: Vec<Part>,
+    },
+}
+
+/// A parsed, checked template.
+#[derive(Debug, Clone)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+/// The model data a template is rendered from: the same project, nodes and
+/// edges the exporters use.
+pub struct TemplateData<'a> {
+    pub project: &'a Project,
+    pub nodes: &'a [Node],
+    pub edges: &'a [Edge],
+    /// Rendered by `{{date}}`.
+    pub generated_at: DateTime<Utc>,
+}
+
+/// Output format of a rendered template.
+pub trait DocumentWriter {
+    /// Template text, copied as written.
+    fn text(&mut self, text: &str);
+    /// A model value; the writer escapes it for its format.
+    fn value(&mut self, value: &str);
+}
+
+/// Writes Markdown. Values placed in a table row (a line starting with
+/// `|`) are escaped as table cells; elsewhere they are written as is.
+#[derive(Debug, Default)]
+pub struct MarkdownWriter {
+    out: String,
+}
+
+impl MarkdownWriter {
+    pub fn finish(self) -> String {
+        self.out
+    }
+}
+
+impl DocumentWriter for MarkdownWriter {
+    fn text(&mut self, text: &str) {
+        self.out.push_str(text);
+    }
+
+    fn value(&mut self, value: &str) {
+        let line_start = self.out.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        if self.out[line_start..].trim_start().starts_with('|') {
+            let cell = super::md_cell(value);
+            self.out.push_str(if cell.is_empty() { "-" } else { &cell });
+        } else {
+            self.out.push_str(&value.replace("\r\n", "\n"));
+        }
+    }
+}
+
+/// Parse `source` and render it as Markdown.
+pub fn render_markdown(source: &str, data: &TemplateData) -> Result<String, TemplateError> {
+    let template = Template::parse(source)?;
+    let mut writer = MarkdownWriter::default();
+    template.render(data, &mut writer);
+    Ok(writer.finish())
+}
+
+// ── Parsing ───────────────────────────────────────────────────────────────────
+
+enum Token {
+    Text(String),
+    Tag { body: String, line: usize },
+}
+
+struct OpenSection {
+    kind: SectionKind,
+    filter: Filter,
+    line: usize,
+    body: Vec<Part>,
+}
+
+impl Template {
+    pub fn parse(source: &str) -> Result<Self, TemplateError> {
+        let mut root: Vec<Part> = Vec::new();
+        let mut stack: Vec<OpenSection> = Vec::new();
+
+        for token in lex(source)? {
+            let (body, line) = match token {
+                Token::Text(text) => {
+                    if !text.is_empty() {
+                        current(&mut stack, &mut root).push(Part::Text(text));
+                    }
+                    continue;
+                }
+                Token::Tag { body, line } => (body, line),
+            };
+
+            if let Some(open) = body.strip_prefix('#') {
+                let (name, args) = split_tag(open, line)?;
+                let kind = SectionKind::parse(name)
+                    .ok_or_else(|| error(line, format!("unknown section '{{{{#{name}}}}}'")))?;
+                let nested = stack.iter().any(|s| s.kind == kind)
+                    || (kind.lists_requirements() && !stack.is_empty());
+                if nested {
+                    let outer = stack.last().map(|s| s.kind.name()).unwrap_or_default();
+                    return Err(error(
+                        line,
+                        format!("'{{{{#{name}}}}}' can't be placed inside '{{{{#{outer}}}}}'"),
+                    ));
+                }
+                let filter = parse_filter(kind, args, line)?;
+                stack.push(OpenSection { kind, filter, line, body: Vec::new() });
+            } else if let Some(close) = body.strip_prefix('/') {
+                let name = close.trim();
+                match stack.pop() {
+                    Some(open) if open.kind.name() == name => {
+                        current(&mut stack, &mut root).push(Part::Section {
+                            kind: open.kind,
+                            filter: open.filter,
+                            body: open.body,
+                        });
+                    }
+                    Some(open) => {
+                        return Err(error(
+                            line,
+                            format!(
+                                "'{{{{/{name}}}}}' doesn't close '{{{{#{}}}}}' opened on line {}",
+                                open.kind.name(),
+                                open.line
+                            ),
+                        ));
+                    }
+                    None => {
+                        return Err(error(
+                            line,
+                            format!("'{{{{/{name}}}}}' has no matching '{{{{#{name}}}}}'"),
+                        ));
+                    }
+                }
+            } else {
+                check_value(&body, &stack, line)?;
+                current(&mut stack, &mut root).push(Part::Value(body));
+            }
+        }
+
+        if let Some(open) = stack.last() {
+            return Err(error(
+                open.line,
+                format!("'{{{{#{}}}}}' is never closed", open.kind.name()),
+            ));
+        }
+        Ok(Self { parts: root })
+    }
+
+    pub fn render(&self, data: &TemplateData, out: &mut dyn DocumentWriter) {
+        let renderer = Renderer {
+            data,
+            by_id: data.nodes.iter().map(|n| (n.id, n)).collect(),
+        };
+        renderer.parts(&self.parts, &Scope::default(), out);
+    }
+}
+
+fn current<'a>(stack: &'a mut [OpenSection], root: &'a mut Vec<Part>) -> &'a mut Vec<Part> {
+    match stack.last_mut() {
+        Some(open) => &mut open.body,
+        None => root,
+    }
+}
+
+fn lex(source: &str) -> Result<Vec<Token>, TemplateError> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+    let mut line = 1;
+    while let Some(start) = rest.find("{{") {
+        let (text, after) = rest.split_at(start);
+        line += text.matches('\n').count();
+        let end = after
+            .find("}}")
+            .ok_or_else(|| error(line, "'{{' has no closing '}}'"))?;
+        let body = &after[2..end];
+        if !text.is_empty() {
+            tokens.push(Token::Text(text.to_string()));
+        }
+        tokens.push(Token::Tag { body: body.trim().to_string(), line });
+        line += body.matches('\n').count();
+        rest = &after[end + 2..];
+    }
+    if !rest.is_empty() {
+        tokens.push(Token::Text(rest.to_string()));
+    }
+    trim_standalone(&mut tokens);
+    Ok(tokens)
+}
+
+/// Drop the surrounding whitespace and newline of section tags that sit
+/// alone on their line.
+fn trim_standalone(tokens: &mut [Token]) {
+    let is_section =
+        |t: &Token| matches!(t, Token::Tag { body, .. } if body.starts_with(['#', '/']));
+
+    // (tag index, bytes of the previous text to keep, bytes of the next text to drop)
+    let mut cuts = Vec::new();
+    for i in 0..tokens.len() {
+        if !is_section(&tokens[i]) {
+            continue;
+        }
+        let keep = match i.checked_sub(1).map(|j| &tokens[j]) {
+            None => Some(0),
+            Some(Token::Text(t)) => match t.rfind('\n') {
+                Some(p) if t[p + 1..].trim().is_empty() => Some(p + 1),
+                None if i == 1 && t.trim().is_empty() => Some(0),
+                _ => None,
+            },
+            Some(Token::Tag { .. }) => None,
+        };
+        let drop = match tokens.get(i + 1) {
+            None => Some(0),
+            Some(Token::Text(t)) => match t.find('\n') {
+                Some(p) if t[..p].trim().is_empty() => Some(p + 1),
+                None if i + 2 == tokens.len() && t.trim().is_empty() => Some(t.len()),
+                _ => None,
+            },
+            Some(Token::Tag { .. }) => None,
+        };
+        if let (Some(keep), Some(drop)) = (keep, drop) {
+            cuts.push((i, keep, drop));
+        }
+    }
+
+    // Truncate before draining: text between two standalone tags is cut
+    // from both ends, and the drop never reaches past the kept part.
+    for &(i, keep, _) in &cuts {
+        if let Some(Token::Text(t)) = i.checked_sub(1).and_then(|j| tokens.get_mut(j)) {
+            t.truncate(keep);
+        }
+    }
+    for &(i, _, drop) in &cuts {
+        if let Some(Token::Text(t)) = tokens.get_mut(i + 1) {
+            t.drain(..drop.min(t.len()));
+        }
+    }
+}
+
+/// Split `name key="value" ...` into the name and its arguments.
+fn split_tag(tag: &str, line: usize) -> Result<(&str, Vec<(String, String)>), TemplateError> {
+    let tag = tag.trim();
+    let (name, mut rest) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+    let bad = || error(line, format!("expected key=\"value\" arguments in '{{{{#{tag}}}}}'"));
+
+    let mut args = Vec::new();
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            break;
+        }
+        let (key, after) = rest.split_once("=\"").ok_or_else(bad)?;
+        if key.is_empty() || key.contains(char::is_whitespace) {
+            return Err(bad());
+        }
+        let (value, after) = after.split_once('"').ok_or_else(bad)?;
+        args.push((key.to_string(), value.to_string()));
+        rest = after;
+    }
+    Ok((name, args))
+}
+
+fn parse_filter(
+    kind: SectionKind,
+    args: Vec<(String, String)>,
+    line: usize,
+) -> Result<Filter, TemplateError> {
+    let mut filter = Filter::default();
+    for (key, value) in args {
+        if !kind.filter_keys().contains(&key.as_str()) {
+            return Err(error(
+                line,
+                format!(
+                    "unknown argument '{key}' for '{{{{#{}}}}}'; expected one of: {}",
+                    kind.name(),
+                    kind.filter_keys().join(", ")
+                ),
+            ));
+        }
+        let values: Vec<String> = value
+            .split(',')
+            .map(|v| v.trim().to_lowercase())
+            .filter(|v| !v.is_empty())
+            .collect();
+        if kind == SectionKind::Traceability && key == "kind" {
+            if let Some(bad) = values.iter().find(|v| parse_edge_kind(v).is_err()) {
+                return Err(error(line, format!("unknown edge kind '{bad}'")));
+            }
+        }
+        filter.0.push((key, values));
+    }
+    Ok(filter)
+}
+
+fn check_value(path: &str, stack: &[OpenSection], line: usize) -> Result<(), TemplateError> {
+    let inside = |kind: SectionKind| stack.iter().any(|s| s.kind == kind);
+    let in_requirements = stack.iter().any(|s| s.kind.lists_requirements());
+    let scoped = |kind: SectionKind| {
+        if inside(kind) {
+            Ok(())
+        } else {
+            Err(error(
+                line,
+                format!("'{{{{{path}}}}}' is only available inside '{{{{#{}}}}}'", kind.name()),
+            ))
+        }
+    };
+    match path.split_once('.') {
+        Some(("project", field)) if PROJECT_FIELDS.contains(&field) => Ok(()),
+        Some(("req", field)) if REQ_FIELDS.contains(&field) && in_requirements => Ok(()),
+        Some(("req", field)) if REQ_FIELDS.contains(&field) => scoped(SectionKind::Requirements),
+        Some(("link", field)) if LINK_FIELDS.contains(&field) => scoped(SectionKind::Traceability),
+        None if path == "date" => Ok(()),
+        _ => Err(error(line, format!("unknown placeholder '{{{{{path}}}}}'"))),
+    }
+}
+
+// ── Rendering ─────────────────────────────────────────────────────────────────
+
+#[derive(Default, Clone, Copy)]
+struct Scope<'a> {
+    req: Option<(&'a Node, &'a RequirementData)>,
+    link: Option<&'a Edge>,
+}
+
+struct Renderer<'a> {
+    data: &'a TemplateData<'a>,
+    by_id: HashMap<Uuid, &'a Node>,
+}
+
+impl<'a> Renderer<'a> {
+    fn parts(&self, parts: &'a [Part], scope: &Scope<'a>, out: &mut dyn DocumentWriter) {
+        for part in parts {
+            match part {
+                Part::Text(text) => out.text(text),
+                Part::Value(path) => out.value(&self.value(path, scope)),
+                Part::Section { kind, filter, body } if kind.lists_requirements() => {
+                    let archived = *kind == SectionKind::Archived;
+                    for node in self.data.nodes.iter().filter(|n| n.archived == archived) {
+                        let NodeData::Requirement(r) = &node.data else {
+                            continue;
+                        };
+                        if filter.matches(|key| req_filter_values(r, key)) {
+                            let scope = Scope { req: Some((node, r)), ..*scope };
+                            self.parts(body, &scope, out);
+                        }
+                    }
+                }
+                Part::Section { filter, body, .. } => {
+                    let own = scope.req.map(|(node, _)| node.id);
+                    for edge in self.data.edges {
+                        let touches = match own {
+                            Some(id) => edge.source_id == id || edge.target_id == id,
+                            None => true,
+                        };
+                        if touches && filter.matches(|_| vec![edge.kind.to_string()]) {
+                            let scope = Scope { link: Some(edge), ..*scope };
+                            self.parts(body, &scope, out);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn value(&self, path: &str, scope: &Scope) -> String {
+        let (prefix, field) = path.split_once('.').unwrap_or(("", path));
+        match prefix {
+            "project" => match field {
+                "name" => self.data.project.name.clone(),
+                "description" => self.data.project.description.clone(),
+                _ => String::new(),
+            },
+            "req" => scope
+                .req
+                .map(|(node, r)| req_field(node, r, field))
+                .unwrap_or_default(),
+            "link" => scope.link.map(|e| self.link_field(e, field)).unwrap_or_default(),
+            _ => self.data.generated_at.format("%Y-%m-%d").to_string(),
+        }
+    }
+
+    fn link_field(&self, edge: &Edge, field: &str) -> String {
+        let node = |id: Uuid| self.by_id.get(&id).copied();
+        let name = |id: Uuid| match node(id) {
+            Some(n) => display_name(n),
+            None => "?".to_string(),
+        };
+        let kind = |id: Uuid| node(id).map(|n| n.kind.to_string()).unwrap_or_default();
+        match field {
+            "kind" => edge.kind.to_string(),
+            "label" => edge.label.clone(),
+            "source" => name(edge.source_id),
+            "target" => name(edge.target_id),
+            "source_kind" => kind(edge.source_id),
+            "target_kind" => kind(edge.target_id),
+            _ => String::new(),
+        }
+    }
+}
+
+/// "REQ-001 — Name" for requirements with an id, else the name.
+fn display_name(node: &Node) -> String {
+    match &node.data {
+        NodeData::Requirement(r) => match r.req_id.as_deref().filter(|id| !id.is_empty()) {
+            Some(id) => format!("{id} — {}", node.name),
+            None => node.name.clone(),
+        },
+        _ => node.name.clone(),
+    }
+}
+
+/// The serde name of a model enum, e.g. "approved".
+fn enum_str<T: Serialize>(value: &T) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+fn req_field(node: &Node, r: &RequirementData, field: &str) -> String {
+    let opt = |s: &Option<String>| s.clone().unwrap_or_default();
+    match field {
+        "id" => opt(&r.req_id),
+        "name" => node.name.clone(),
+        "text" => opt(&r.text),
+        "rationale" => opt(&r.rationale),
+        "priority" => enum_str(&r.priority),
+        "status" => enum_str(&r.status),
+        "verification" => r.verification_method.as_ref().map(enum_str).unwrap_or_default(),
+        "source" => opt(&r.source),
+        "allocations" => r.allocations.as_deref().unwrap_or_default().join(", "),
+        "classification" => opt(&r.classification),
+        "description" => node.description.clone(),
+        _ => String::new(),
+    }
+}
+
+fn req_filter_values(r: &RequirementData, key: &str) -> Vec<String> {
+    match key {
+        "status" => vec![enum_str(&r.status)],
+        "priority" => vec![enum_str(&r.priority)],
+        "verification" => vec![r.verification_method.as_ref().map(enum_str).unwrap_or_default()],
+        "allocation" => r.allocations.clone().unwrap_or_default(),
+        "classification" => vec![r.classification.clone().unwrap_or_default()],
+        _ => Vec::new(),
+    }
+}
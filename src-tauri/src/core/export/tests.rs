@@ -0,0 +1,512 @@
+use super::*;
+use crate::core::model::VerificationMethod;
+use crate::test_support as fixtures;
+
+#[test]
+fn csv_quotes_commas_quotes_and_line_breaks() {
+    let project = fixtures::project("CSV");
+    let mut req = fixtures::requirement(
+        project.id,
+        "REQ-001",
+        "The bus shall supply 28 V, \"nominal\".\nSee ICD.",
+    );
+    if let NodeData::Requirement(r) = &mut req.data {
+        r.verification_method = Some(VerificationMethod::Test);
+        r.allocations = Some(vec!["Avionics".into(), "Power".into()]);
+        r.source = Some("SOW §3.2".into());
+        r.classification = Some("interface".into());
+    }
+    let mut archived = fixtures::requirement(project.id, "REQ-002", "Gone");
+    archived.archived = true;
+    let block = fixtures::block(project.id, "Power Distribution");
+    let other = fixtures::requirement(Uuid::new_v4(), "REQ-900", "Elsewhere");
+
+    let csv = to_csv(&project, &[req, archived, block, other]);
+    assert_eq!(
+        csv,
+        "ID,Name,Text,Priority,Status,Verification,Allocations,Source,Classification\n\
+         REQ-001,REQ-001,\"The bus shall supply 28 V, \"\"nominal\"\".\nSee ICD.\",\
+         Shall,Draft,Test,Avionics; Power,SOW §3.2,interface\n"
+    );
+}
+
+#[test]
+fn reqif_nests_the_derive_tree_under_one_specification() {
+    let project = fixtures::project("ReqIF");
+    let top = fixtures::requirement(project.id, "REQ-001", "Supply 28 V.");
+    let refined = fixtures::requirement(project.id, "REQ-002", "Supply 5 V.");
+    let derived = fixtures::requirement(project.id, "REQ-003", "Regulate to 1%.");
+    let mut standalone = fixtures::requirement(project.id, "REQ-004", "Log faults.");
+    if let NodeData::Requirement(r) = &mut standalone.data {
+        r.verification_method = Some(VerificationMethod::Test);
+        r.allocations = Some(vec!["Avionics".into(), "Power".into()]);
+    }
+    let block = fixtures::block(project.id, "Power Distribution");
+    let test = fixtures::test_case(project.id, "Fault log test");
+    let edges = vec![
+        fixtures::edge(project.id, EdgeKind::Refines, refined.id, top.id),
+        fixtures::edge(project.id, EdgeKind::Derives, refined.id, derived.id),
+        fixtures::edge(project.id, EdgeKind::Satisfies, block.id, top.id),
+        fixtures::edge(project.id, EdgeKind::Verifies, test.id, standalone.id),
+        // A second parent; REQ-003 stays under the requirement it derives from.
+        fixtures::edge(project.id, EdgeKind::Refines, derived.id, standalone.id),
+    ];
+    let nodes = [
+        standalone.clone(),
+        derived.clone(),
+        refined.clone(),
+        top.clone(),
+        block,
+        test,
+    ];
+    let xml = to_reqif(&project, &nodes, &edges);
+    let root = crate::core::import::xml::parse(&xml).unwrap();
+    let content = root.find("REQ-IF-CONTENT").unwrap();
+
+    let objects = content.find_all("SPEC-OBJECT");
+    assert_eq!(
+        objects.len(),
+        6,
+        "four requirements and two linked elements"
+    );
+    for object in &objects {
+        let order: Vec<&str> = object.elements().map(|e| e.local_name()).collect();
+        assert_eq!(order, ["VALUES", "TYPE"]);
+    }
+    let values = |node: &Node| -> Vec<(String, String)> {
+        let object = objects
+            .iter()
+            .find(|o| o.attribute("IDENTIFIER") == Some(format!("_{}", node.id).as_str()))
+            .unwrap();
+        object
+            .find_all("ATTRIBUTE-VALUE-STRING")
+            .iter()
+            .map(|v| {
+                let definition = v.find("ATTRIBUTE-DEFINITION-STRING-REF").unwrap().text();
+                (definition, v.attribute("THE-VALUE").unwrap().to_string())
+            })
+            .collect()
+    };
+    let standalone_values = values(&standalone);
+    assert!(
+        standalone_values.contains(&("_attr-verification-method".to_string(), "test".to_string()))
+    );
+    assert!(standalone_values.contains(&(
+        "_attr-allocations".to_string(),
+        "Avionics; Power".to_string()
+    )));
+
+    let relations = content.find_all("SPEC-RELATION");
+    assert_eq!(relations.len(), 5);
+    for relation in &relations {
+        let order: Vec<&str> = relation.elements().map(|e| e.local_name()).collect();
+        assert_eq!(order, ["TARGET", "SOURCE", "TYPE"]);
+    }
+    assert!(relations
+        .iter()
+        .any(|r| { r.find("SPEC-RELATION-TYPE-REF").unwrap().text() == "_reltype-derives" }));
+
+    let specifications = content.find_all("SPECIFICATION");
+    assert_eq!(specifications.len(), 1);
+    let object_of = |h: &crate::core::import::xml::Element| {
+        h.child("OBJECT")
+            .unwrap()
+            .child("SPEC-OBJECT-REF")
+            .unwrap()
+            .text()
+    };
+    let nested = |h: &crate::core::import::xml::Element| -> Vec<String> {
+        h.child("CHILDREN")
+            .map(|c| c.elements().map(object_of).collect())
+            .unwrap_or_default()
+    };
+    let spec = specifications[0];
+    assert_eq!(
+        nested(spec),
+        [format!("_{}", top.id), format!("_{}", standalone.id)]
+    );
+    let top_hierarchy = spec.child("CHILDREN").unwrap().elements().next().unwrap();
+    assert_eq!(nested(top_hierarchy), [format!("_{}", refined.id)]);
+    let refined_hierarchy = top_hierarchy
+        .child("CHILDREN")
+        .unwrap()
+        .elements()
+        .next()
+        .unwrap();
+    assert_eq!(nested(refined_hierarchy), [format!("_{}", derived.id)]);
+    let derived_hierarchy = refined_hierarchy
+        .child("CHILDREN")
+        .unwrap()
+        .elements()
+        .next()
+        .unwrap();
+    assert!(derived_hierarchy.child("CHILDREN").is_none());
+}
+
+#[test]
+fn reqif_and_requirements_csv_write_both_creation_times() {
+    let project = fixtures::project("Dates");
+    let authored = fixtures::requirement(project.id, "REQ-001", "Written here.");
+    let mut imported = fixtures::requirement(project.id, "REQ-002", "Read from DOORS.");
+    imported.meta.insert(
+        ORIGINAL_CREATED_AT_META.into(),
+        json!("2019-03-01T08:00:00+00:00"),
+    );
+    let nodes = [authored.clone(), imported.clone()];
+
+    let xml = to_reqif(&project, &nodes, &[]);
+    let root = crate::core::import::xml::parse(&xml).unwrap();
+    let dates = |node: &Node| -> Vec<(String, String)> {
+        let id = format!("_{}", node.id);
+        root.find_all("SPEC-OBJECT")
+            .iter()
+            .find(|o| o.attribute("IDENTIFIER") == Some(id.as_str()))
+            .unwrap()
+            .find_all("ATTRIBUTE-VALUE-STRING")
+            .iter()
+            .map(|v| {
+                let definition = v.find("ATTRIBUTE-DEFINITION-STRING-REF").unwrap().text();
+                (definition, v.attribute("THE-VALUE").unwrap().to_string())
+            })
+            .filter(|(definition, _)| definition.ends_with("created-at"))
+            .collect()
+    };
+    assert_eq!(
+        dates(&authored),
+        [(
+            "_attr-created-at".to_string(),
+            authored.created_at.to_rfc3339()
+        )]
+    );
+    assert_eq!(
+        dates(&imported),
+        [
+            (
+                "_attr-original-created-at".to_string(),
+                "2019-03-01T08:00:00+00:00".to_string()
+            ),
+            (
+                "_attr-created-at".to_string(),
+                imported.created_at.to_rfc3339()
+            ),
+        ]
+    );
+
+    let csv = to_requirements_csv(&project, &nodes);
+    let lines: Vec<&str> = csv.lines().collect();
+    assert!(
+        lines[0].ends_with(",original_created_at,created_at"),
+        "{}",
+        lines[0]
+    );
+    assert!(lines[1].ends_with(&format!(",,{}", authored.created_at.to_rfc3339())));
+    assert!(lines[2].ends_with(&format!(
+        ",2019-03-01T08:00:00+00:00,{}",
+        imported.created_at.to_rfc3339()
+    )));
+}
+
+#[test]
+fn traceability_matrix_marks_verifies_and_lists_uncovered() {
+    use super::matrix;
+
+    let project = fixtures::project("Matrix");
+    let covered = fixtures::requirement(project.id, "REQ-2", "Supply 28 V.");
+    let uncovered = fixtures::requirement(project.id, "REQ-10", "Log faults.");
+    let tc_a = fixtures::test_case(project.id, "TC-1 Bus voltage");
+    let tc_b = fixtures::test_case(project.id, "TC-2 Brownout");
+    let edges = vec![
+        fixtures::edge(project.id, EdgeKind::Verifies, tc_a.id, covered.id),
+        // Either direction counts.
+        fixtures::edge(project.id, EdgeKind::Verifies, covered.id, tc_b.id),
+        fixtures::edge(project.id, EdgeKind::Traces, tc_a.id, uncovered.id),
+    ];
+    let grid =
+        matrix::to_traceability_matrix(&[uncovered.clone(), tc_b, covered.clone(), tc_a], &edges);
+    assert_eq!(grid.cells, [[true, true], [false, false]]);
+    assert_eq!(grid.uncovered()[0].id, uncovered.id);
+
+    assert_eq!(
+        matrix::to_csv(&grid),
+        "req_id,requirement,TC-1 Bus voltage,TC-2 Brownout\n\
+         REQ-2,REQ-2,X,X\n\
+         REQ-10,REQ-10,,\n\
+         \nuncovered\nreq_id,requirement\n\
+         REQ-10,REQ-10\n"
+    );
+    let markdown = matrix::to_markdown(&grid, "Matrix");
+    assert!(
+        markdown.contains("| Requirement | TC-1 Bus voltage | TC-2 Brownout |\n|---|---|---|\n")
+    );
+    assert!(markdown.contains("| REQ-2 | X | X |\n| REQ-10 |  |  |\n"));
+    assert!(markdown.ends_with("## Uncovered\n\n- REQ-10\n"));
+}
+
+#[test]
+fn rtm_follows_multi_level_derive_chains_and_flags_gaps() {
+    use super::rtm;
+
+    let project = fixtures::project("RTM");
+    let system = fixtures::requirement(project.id, "SYS-1", "Provide power.");
+    let subsystem = fixtures::requirement(project.id, "SUB-1", "Supply 28 V.");
+    let mut component = fixtures::requirement(project.id, "CMP-1", "Regulate to 1%.");
+    if let NodeData::Requirement(r) = &mut component.data {
+        r.allocations = Some(vec!["PSU".into(), "FPGA".into()]);
+    }
+    let block = fixtures::block(project.id, "Regulator");
+    let test = fixtures::test_case(project.id, "TC-7 Load step");
+    let edges = vec![
+        // SYS-1 → SUB-1 → CMP-1, and CMP-1 → SYS-1 closes a cycle.
+        fixtures::edge(project.id, EdgeKind::Derives, system.id, subsystem.id),
+        fixtures::edge(project.id, EdgeKind::Refines, component.id, subsystem.id),
+        fixtures::edge(project.id, EdgeKind::Derives, component.id, system.id),
+        fixtures::edge(project.id, EdgeKind::Satisfies, block.id, component.id),
+        fixtures::edge(project.id, EdgeKind::Verifies, test.id, component.id),
+    ];
+    let matrix = rtm::build(
+        &[
+            component.clone(),
+            subsystem.clone(),
+            system.clone(),
+            block,
+            test,
+        ],
+        &edges,
+    );
+
+    let row = |req_id: &str| {
+        matrix
+            .rows
+            .iter()
+            .find(|r| r.req_id.as_deref() == Some(req_id))
+            .unwrap()
+    };
+    assert_eq!(
+        matrix
+            .rows
+            .iter()
+            .map(|r| r.req_id.clone().unwrap())
+            .collect::<Vec<_>>(),
+        ["CMP-1", "SUB-1", "SYS-1"]
+    );
+    assert_eq!(row("CMP-1").parents, ["SUB-1", "SYS-1"]);
+    // The cycle back through CMP-1 doesn't list a requirement as its own parent.
+    assert_eq!(row("SUB-1").parents, ["SYS-1", "CMP-1"]);
+    assert_eq!(row("SYS-1").parents, ["CMP-1", "SUB-1"]);
+    assert_eq!(row("CMP-1").satisfied_by, ["Regulator"]);
+    assert_eq!(row("CMP-1").verified_by, ["TC-7 Load step"]);
+    assert_eq!(matrix.unsatisfied(), 2);
+    assert_eq!(matrix.unverified(), 2);
+
+    let csv = rtm::to_csv(&matrix);
+    assert!(csv.starts_with(
+        "req_id,requirement,satisfied_by,verified_by,parents,allocations\n\
+         CMP-1,CMP-1,Regulator,TC-7 Load step,SUB-1; SYS-1,PSU; FPGA\n\
+         SUB-1,SUB-1,(none),(none),SYS-1; CMP-1,\n"
+    ));
+    assert!(csv
+        .ends_with("\nsummary\nrequirements,3\nno satisfying block,2\nno verifying test case,2\n"));
+    let markdown = rtm::to_markdown(&matrix, "RTM");
+    assert!(markdown.contains("| SYS-1 | SYS-1 | **(none)** | **(none)** | CMP-1; SUB-1 | - |\n"));
+    assert!(markdown.ends_with(
+        "**Summary:** 3 requirements; 2 with no satisfying block; \
+         2 with no verifying test case.\n"
+    ));
+}
+
+#[test]
+fn json_ld_maps_requirement_fields_and_keeps_every_element() {
+    let project = fixtures::project("JSON-LD");
+    let mut req = fixtures::requirement(project.id, "REQ-001", "Supply 28 V.");
+    if let NodeData::Requirement(r) = &mut req.data {
+        r.allocations = Some(vec!["Power".into(), "Avionics".into()]);
+        r.verification_method = Some(VerificationMethod::Test);
+        r.source = Some("SOW §3.2".into());
+    }
+    let mut archived = fixtures::requirement(project.id, "REQ-002", "Gone");
+    archived.archived = true;
+    let block = fixtures::block(project.id, "Power Distribution");
+    let edges = vec![
+        fixtures::edge(project.id, EdgeKind::Satisfies, block.id, req.id),
+        fixtures::edge(project.id, EdgeKind::Refines, archived.id, req.id),
+    ];
+    let nodes = [req.clone(), archived, block];
+
+    let out = to_json_ld(&project, &nodes, &edges, &JsonLdOptions::default()).unwrap();
+    let doc: Value = serde_json::from_str(&out).unwrap();
+    let graph = &doc["@graph"];
+    let count = |key: &str| graph[key].as_array().map_or(0, Vec::len);
+    assert_eq!(
+        count("elements") + count("archivedElements") + count("relationships"),
+        nodes.len() + edges.len()
+    );
+
+    let element = graph["elements"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|e| e["@id"] == format!("urn:uuid:{}", req.id))
+        .unwrap();
+    assert!(element.get("data").is_none());
+    assert_eq!(element["req_id"], "REQ-001");
+    assert_eq!(element["text"], "Supply 28 V.");
+    assert_eq!(element["allocations"], json!(["Power", "Avionics"]));
+    assert_eq!(element["requirement_source"], "SOW §3.2");
+    assert!(element["verification_method"].is_string());
+
+    let context = &doc["@context"];
+    for term in [
+        "req_id",
+        "text",
+        "verification_method",
+        "requirement_source",
+    ] {
+        assert!(!context[term].is_null(), "{term} is not in the @context");
+    }
+    assert_eq!(context["allocations"]["@container"], "@set");
+}
+
+#[test]
+fn xml_escape_escapes_markup_and_drops_control_characters() {
+    assert_eq!(
+        xml_escape("<a href=\"x\">R&D's\u{1}\ttab</a>"),
+        "&lt;a href=&quot;x&quot;&gt;R&amp;D&apos;s\ttab&lt;/a&gt;"
+    );
+}
+
+#[test]
+fn export_size_estimate_is_within_2x_of_the_real_size() {
+    use scope::{estimate_size, resolve, ByteCounter, ExportScope};
+
+    // Larger than the sample, with uneven text, so the estimate extrapolates.
+    let project = fixtures::project("Estimate");
+    let mut nodes = Vec::new();
+    let mut edges = Vec::new();
+    for i in 0..1_200 {
+        let block = fixtures::block(project.id, &format!("Block {i}"));
+        let text = "The unit shall respond within 10 ms. ".repeat(1 + i % 7);
+        let req = fixtures::requirement(project.id, &format!("REQ-{i:04}"), &text);
+        edges.push(fixtures::edge(
+            project.id,
+            EdgeKind::Satisfies,
+            block.id,
+            req.id,
+        ));
+        if i > 0 && i % 3 == 0 {
+            edges.push(fixtures::edge(
+                project.id,
+                EdgeKind::Derives,
+                nodes[1].id,
+                req.id,
+            ));
+        }
+        nodes.extend([block, req]);
+    }
+    let model = resolve(&ExportScope::default(), nodes, edges, Vec::new());
+
+    let json = |nodes: &[Node], edges: &[Edge], _: &[BlockProperty]| -> Result<u64> {
+        let mut out = ByteCounter::default();
+        write_native_json(&mut out, &project, nodes, edges, &[], &mut |_, _| true)?;
+        Ok(out.0)
+    };
+    let xmi = |nodes: &[Node], edges: &[Edge], props: &[BlockProperty]| -> Result<u64> {
+        let mut out = ByteCounter::default();
+        write_xmi(&mut out, &project, nodes, edges, props, &mut |_, _| true)?;
+        Ok(out.0)
+    };
+    type Render<'a> = &'a dyn Fn(&[Node], &[Edge], &[BlockProperty]) -> Result<u64>;
+    let renderers: [(&str, Render); 2] = [("json", &json), ("xmi", &xmi)];
+    for (format, render) in renderers {
+        let actual = render(&model.nodes, &model.edges, &model.properties).unwrap();
+        let (estimate, exact) = estimate_size(&model, render).unwrap();
+        assert!(!exact);
+        assert!(
+            estimate <= actual * 2 && actual <= estimate * 2,
+            "{format}: estimated {estimate} bytes, wrote {actual}"
+        );
+    }
+}
+
+/// REQ-001, a requirement without a req_id derived from it, and a block
+/// satisfying REQ-001.
+fn anchor_fixture() -> (Project, Vec<Node>, Vec<Edge>) {
+    let project = fixtures::project("Anchors");
+    let top = fixtures::requirement(project.id, "REQ-001", "Supply 28 V.");
+    let mut unnumbered = fixtures::requirement(project.id, "", "Regulate to 1%.");
+    unnumbered.name = "Unnumbered".into();
+    if let NodeData::Requirement(r) = &mut unnumbered.data {
+        r.req_id = None;
+    }
+    let block = fixtures::block(project.id, "Power");
+    let edges = vec![
+        fixtures::edge(project.id, EdgeKind::Satisfies, block.id, top.id),
+        fixtures::edge(project.id, EdgeKind::Derives, top.id, unnumbered.id),
+    ];
+    (project, vec![top, unnumbered, block], edges)
+}
+
+#[test]
+fn markdown_anchors_requirements_and_links_them_from_traceability() {
+    let (project, nodes, edges) = anchor_fixture();
+    let fallback = format!("req-{}", nodes[1].id);
+
+    let markdown = to_markdown(&project, &nodes, &edges, &[], &[], &[], None);
+    assert_eq!(
+        markdown,
+        format!(
+            "# Anchors\n\n\
+             ## Requirements\n\n\
+             | ID | Name | Text | Priority | Status | Verification |\n\
+             |---|---|---|---|---|---|\n\
+             | <a id=\"req-REQ-001\"></a>REQ-001 | REQ-001 | Supply 28 V. | Shall | Draft | - |\n\
+             | <a id=\"{fallback}\"></a>- | Unnumbered | Regulate to 1%. | Shall | Draft | - |\n\n\
+             ## Traceability\n\n\
+             | Relationship | Source | Target | Label |\n\
+             |---|---|---|---|\n\
+             | «satisfies» | Power | [REQ-001](#req-REQ-001) | - |\n\
+             | «derives» | [REQ-001](#req-REQ-001) | [Unnumbered](#{fallback}) | - |\n\n"
+        )
+    );
+}
+
+#[test]
+fn dossier_html_anchors_each_page_and_links_related_pages() {
+    use dossier::{to_html, LinkedNode, RequirementDossier};
+
+    let (project, nodes, _) = anchor_fixture();
+    let outside = fixtures::requirement(project.id, "REQ-900", "Elsewhere.");
+    let dossier = |node: &Node| RequirementDossier {
+        node: node.clone(),
+        history_count: 0,
+        last_change: None,
+        satisfied_by: Vec::new(),
+        verified_by: Vec::new(),
+        parents: Vec::new(),
+        children: Vec::new(),
+        open_comments: Vec::new(),
+        suspect_links: Vec::new(),
+        sources: Vec::new(),
+        external_items: Vec::new(),
+    };
+    let mut top = dossier(&nodes[0]);
+    top.parents.push(LinkedNode {
+        node: outside,
+        via: EdgeKind::Refines,
+    });
+    top.children.push(LinkedNode {
+        node: nodes[1].clone(),
+        via: EdgeKind::Derives,
+    });
+    let fallback = format!("req-{}", nodes[1].id);
+
+    let html = to_html(&[top, dossier(&nodes[1])]);
+    for expected in [
+        "<section id=\"req-REQ-001\">\n<h1>REQ-001 — REQ-001</h1>\n".to_string(),
+        format!("<section id=\"{fallback}\">\n<h1>Unnumbered</h1>\n"),
+        format!("<li><a href=\"#{fallback}\">Unnumbered («derives»)</a></li>\n"),
+        // No page of its own, so no link.
+        "<li>REQ-900 — REQ-900 («refines»)</li>\n".to_string(),
+    ] {
+        assert!(html.contains(&expected), "missing {expected:?} in\n{html}");
+    }
+}
@@ -0,0 +1,324 @@
+/// Verification cross-reference matrix (VCRM): every requirement with its
+/// verification method and level, the test cases that «verify» it and
+/// their current status, one row per requirement–test case pair. Ordered
+/// by req_id in natural order (REQ-2 before REQ-10); requirements no test
+/// case verifies are listed separately at the end.
+///
+/// Evidence is the test case's own status; there is no record of
+/// individual test runs to take a latest result from.
+use super::md_cell;
+use super::xlsx::{write_workbook, Sheet};
+use crate::core::model::{Edge, EdgeKind, Node, NodeData, TestStatus, VerificationMethod};
+use crate::core::rollup::csv_field;
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VcrmFormat {
+    Csv,
+    Markdown,
+    Xlsx,
+}
+
+impl VcrmFormat {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "csv" => Some(Self::Csv),
+            "markdown" | "md" => Some(Self::Markdown),
+            "xlsx" => Some(Self::Xlsx),
+            _ => None,
+        }
+    }
+}
+
+/// One requirement–test case pair.
+#[derive(Debug, Clone, Serialize)]
+pub struct VcrmRow {
+    pub requirement_id: Uuid,
+    pub req_id: Option<String>,
+    pub name: String,
+    pub method: Option<VerificationMethod>,
+    pub level: Option<String>,
+    pub test_case_id: Uuid,
+    pub test_case: String,
+    pub evidence: TestStatus,
+}
+
+/// A requirement with no «verifies» link at all.
+#[derive(Debug, Clone, Serialize)]
+pub struct UnverifiedRequirement {
+    pub requirement_id: Uuid,
+    pub req_id: Option<String>,
+    pub name: String,
+    pub method: Option<VerificationMethod>,
+    pub level: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Vcrm {
+    pub rows: Vec<VcrmRow>,
+    pub unverified: Vec<UnverifiedRequirement>,
+}
+
+/// What `export_vcrm` wrote.
+#[derive(Debug, Clone, Serialize)]
+pub struct VcrmExport {
+    pub rows: usize,
+    pub unverified: usize,
+}
+
+/// Join requirements to the test cases verifying them. A «verifies» edge
+/// counts in either direction as long as one end is a test case and the
+/// other a requirement.
+pub fn build(nodes: &[Node], edges: &[Edge]) -> Vcrm {
+    let by_id: HashMap<Uuid, &Node> = nodes.iter().map(|n| (n.id, n)).collect();
+    let mut verifying: HashMap<Uuid, Vec<&Node>> = HashMap::new();
+    for edge in edges.iter().filter(|e| e.kind == EdgeKind::Verifies) {
+        let (Some(source), Some(target)) = (by_id.get(&edge.source_id), by_id.get(&edge.target_id))
+        else {
+            continue;
+        };
+        let (requirement, test_case) = match (&source.data, &target.data) {
+            (NodeData::TestCase(_), NodeData::Requirement(_)) => (target, source),
+            (NodeData::Requirement(_), NodeData::TestCase(_)) => (source, target),
+            _ => continue,
+        };
+        let cases = verifying.entry(requirement.id).or_default();
+        if !cases.iter().any(|c| c.id == test_case.id) {
+            cases.push(test_case);
+        }
+    }
+
+    let mut requirements: Vec<&Node> = nodes
+        .iter()
+        .filter(|n| matches!(n.data, NodeData::Requirement(_)))
+        .collect();
+    requirements.sort_by(|a, b| requirement_order(a, b));
+
+    let mut vcrm = Vcrm::default();
+    for node in requirements {
+        let NodeData::Requirement(r) = &node.data else {
+            continue;
+        };
+        let level = node.verification_level().map(str::to_string);
+        let Some(cases) = verifying.get_mut(&node.id) else {
+            vcrm.unverified.push(UnverifiedRequirement {
+                requirement_id: node.id,
+                req_id: r.req_id.clone(),
+                name: node.name.clone(),
+                method: r.verification_method.clone(),
+                level,
+            });
+            continue;
+        };
+        cases.sort_by(|a, b| natural_cmp(&a.name, &b.name));
+        for case in cases.iter() {
+            let NodeData::TestCase(tc) = &case.data else {
+                continue;
+            };
+            vcrm.rows.push(VcrmRow {
+                requirement_id: node.id,
+                req_id: r.req_id.clone(),
+                name: node.name.clone(),
+                method: r.verification_method.clone(),
+                level: level.clone(),
+                test_case_id: case.id,
+                test_case: case.name.clone(),
+                evidence: tc.status.clone(),
+            });
+        }
+    }
+    vcrm
+}
+
+/// By req_id in natural order; requirements without one go last, by name.
+pub(super) fn requirement_order(a: &Node, b: &Node) -> Ordering {
+    let req_id = |n: &Node| match &n.data {
+        NodeData::Requirement(r) => r.req_id.as_deref().map(str::trim).filter(|s| !s.is_empty()),
+        _ => None,
+    };
+    match (req_id(a), req_id(b)) {
+        (Some(x), Some(y)) => natural_cmp(x, y),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+    .then_with(|| natural_cmp(&a.name, &b.name))
+    .then_with(|| a.id.cmp(&b.id))
+}
+
+/// Compare with digit runs taken as numbers, so "REQ-2" < "REQ-10" and
+/// "SYS-1.2" < "SYS-1.10". Letters compare case-insensitively; exact
+/// ties fall back to plain string order.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut x = a.chars().peekable();
+    let mut y = b.chars().peekable();
+    loop {
+        match (x.peek().copied(), y.peek().copied()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(c), Some(d)) if c.is_ascii_digit() && d.is_ascii_digit() => {
+                let m = digit_run(&mut x);
+                let n = digit_run(&mut y);
+                let (m, n) = (m.trim_start_matches('0'), n.trim_start_matches('0'));
+                let ord = m.len().cmp(&n.len()).then_with(|| m.cmp(n));
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+            }
+            (Some(c), Some(d)) => {
+                let ord = c.to_lowercase().cmp(d.to_lowercase());
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+                x.next();
+                y.next();
+            }
+        }
+    }
+}
+
+fn digit_run(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> String {
+    let mut run = String::new();
+    while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+        run.push(c);
+    }
+    run
+}
+
+fn method_label(method: &Option<VerificationMethod>) -> String {
+    method
+        .as_ref()
+        .map(|m| format!("{m:?}"))
+        .unwrap_or_default()
+}
+
+fn evidence_label(status: &TestStatus) -> &'static str {
+    match status {
+        TestStatus::NotRun => "not_run",
+        TestStatus::Pass => "pass",
+        TestStatus::Fail => "fail",
+    }
+}
+
+const HEADER: [&str; 6] = [
+    "req_id",
+    "requirement",
+    "method",
+    "level",
+    "test_case",
+    "evidence",
+];
+
+fn row_fields(row: &VcrmRow) -> [String; 6] {
+    [
+        row.req_id.clone().unwrap_or_default(),
+        row.name.clone(),
+        method_label(&row.method),
+        row.level.clone().unwrap_or_default(),
+        row.test_case.clone(),
+        evidence_label(&row.evidence).to_string(),
+    ]
+}
+
+fn unverified_fields(req: &UnverifiedRequirement) -> [String; 4] {
+    [
+        req.req_id.clone().unwrap_or_default(),
+        req.name.clone(),
+        method_label(&req.method),
+        req.level.clone().unwrap_or_default(),
+    ]
+}
+
+/// The matrix, then a blank line and an "unverified" block with the
+/// requirement columns only.
+pub fn to_csv(vcrm: &Vcrm) -> String {
+    let line = |fields: &[String]| {
+        fields
+            .iter()
+            .map(|f| csv_field(f))
+            .collect::<Vec<_>>()
+            .join(",")
+            + "\n"
+    };
+    let mut out = HEADER.join(",") + "\n";
+    for row in &vcrm.rows {
+        out.push_str(&line(&row_fields(row)[..]));
+    }
+    out.push_str("\nunverified\nreq_id,requirement,method,level\n");
+    for req in &vcrm.unverified {
+        out.push_str(&line(&unverified_fields(req)[..]));
+    }
+    out
+}
+
+pub fn to_markdown(vcrm: &Vcrm, project_name: &str) -> String {
+    let cell = |s: &str| {
+        if s.is_empty() {
+            "-".to_string()
+        } else {
+            md_cell(s)
+        }
+    };
+    let mut out = format!(
+        "# Verification Cross-Reference Matrix — {}\n\n",
+        md_cell(project_name)
+    );
+    out.push_str("| Req ID | Requirement | Method | Level | Test Case | Evidence |\n");
+    out.push_str("|---|---|---|---|---|---|\n");
+    for row in &vcrm.rows {
+        let fields = row_fields(row);
+        out.push_str(&format!(
+            "| {} |\n",
+            fields
+                .iter()
+                .map(|f| cell(f))
+                .collect::<Vec<_>>()
+                .join(" | ")
+        ));
+    }
+    out.push_str("\n## Requirements without verification\n\n");
+    if vcrm.unverified.is_empty() {
+        out.push_str("None.\n");
+        return out;
+    }
+    out.push_str("| Req ID | Requirement | Method | Level |\n|---|---|---|---|\n");
+    for req in &vcrm.unverified {
+        let fields = unverified_fields(req);
+        out.push_str(&format!(
+            "| {} |\n",
+            fields
+                .iter()
+                .map(|f| cell(f))
+                .collect::<Vec<_>>()
+                .join(" | ")
+        ));
+    }
+    out
+}
+
+/// Two sheets: "VCRM" and "Unverified".
+pub fn to_xlsx(vcrm: &Vcrm) -> Vec<u8> {
+    let header = |cols: &[&str]| cols.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+    let mut matrix = vec![header(&HEADER)];
+    matrix.extend(vcrm.rows.iter().map(|r| row_fields(r).to_vec()));
+    let mut unverified = vec![header(&HEADER[..4])];
+    unverified.extend(
+        vcrm.unverified
+            .iter()
+            .map(|r| unverified_fields(r).to_vec()),
+    );
+    write_workbook(&[
+        Sheet {
+            name: "VCRM".to_string(),
+            rows: matrix,
+        },
+        Sheet {
+            name: "Unverified".to_string(),
+            rows: unverified,
+        },
+    ])
+}
@@ -0,0 +1,240 @@
+/// Minimal XLSX writer: text-only worksheets of inline strings, packed into
+/// an uncompressed ZIP. Enough for tabular exports that open in Excel and
+/// LibreOffice without pulling in a spreadsheet crate; there are no styles,
+/// formulas or shared strings.
+use super::xml_escape;
+use std::fmt::Write as _;
+
+/// One worksheet. The first row is usually the header.
+pub struct Sheet {
+    /// Shown on the tab; Excel limits this to 31 characters.
+    pub name: String,
+    pub rows: Vec<Vec<String>>,
+}
+
+/// The workbook as `.xlsx` bytes.
+pub fn write_workbook(sheets: &[Sheet]) -> Vec<u8> {
+    let mut zip = ZipWriter::default();
+    zip.add(
+        "[Content_Types].xml",
+        content_types(sheets.len()).as_bytes(),
+    );
+    zip.add(
+        "_rels/.rels",
+        concat!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+            r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
+            r#"<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/>"#,
+            r#"</Relationships>"#,
+        )
+        .as_bytes(),
+    );
+    zip.add("xl/workbook.xml", workbook(sheets).as_bytes());
+    zip.add(
+        "xl/_rels/workbook.xml.rels",
+        workbook_rels(sheets.len()).as_bytes(),
+    );
+    for (i, sheet) in sheets.iter().enumerate() {
+        zip.add(
+            &format!("xl/worksheets/sheet{}.xml", i + 1),
+            worksheet(sheet).as_bytes(),
+        );
+    }
+    zip.finish()
+}
+
+fn content_types(sheet_count: usize) -> String {
+    let mut out = String::from(concat!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+        r#"<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">"#,
+        r#"<Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>"#,
+        r#"<Default Extension="xml" ContentType="application/xml"/>"#,
+        r#"<Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/>"#,
+    ));
+    for i in 1..=sheet_count {
+        let _ = write!(
+            out,
+            r#"<Override PartName="/xl/worksheets/sheet{i}.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/>"#
+        );
+    }
+    out.push_str("</Types>");
+    out
+}
+
+fn workbook(sheets: &[Sheet]) -> String {
+    let mut out = String::from(concat!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+        r#"<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" "#,
+        r#"xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets>"#,
+    ));
+    for (i, sheet) in sheets.iter().enumerate() {
+        let _ = write!(
+            out,
+            r#"<sheet name="{}" sheetId="{}" r:id="rId{}"/>"#,
+            xml_escape(&sheet_name(&sheet.name)),
+            i + 1,
+            i + 1
+        );
+    }
+    out.push_str("</sheets></workbook>");
+    out
+}
+
+fn workbook_rels(sheet_count: usize) -> String {
+    let mut out = String::from(concat!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+        r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
+    ));
+    for i in 1..=sheet_count {
+        let _ = write!(
+            out,
+            r#"<Relationship Id="rId{i}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet{i}.xml"/>"#
+        );
+    }
+    out.push_str("</Relationships>");
+    out
+}
+
+fn worksheet(sheet: &Sheet) -> String {
+    let mut out = String::from(concat!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+        r#"<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheetData>"#,
+    ));
+    for (r, row) in sheet.rows.iter().enumerate() {
+        let _ = write!(out, r#"<row r="{}">"#, r + 1);
+        for (c, value) in row.iter().enumerate() {
+            if value.is_empty() {
+                continue;
+            }
+            let _ = write!(
+                out,
+                r#"<c r="{}{}" t="inlineStr"><is><t xml:space="preserve">{}</t></is></c>"#,
+                column_name(c),
+                r + 1,
+                xml_escape(value)
+            );
+        }
+        out.push_str("</row>");
+    }
+    out.push_str("</sheetData></worksheet>");
+    out
+}
+
+/// 0 → "A", 25 → "Z", 26 → "AA".
+fn column_name(index: usize) -> String {
+    let mut n = index + 1;
+    let mut name = Vec::new();
+    while n > 0 {
+        let rem = (n - 1) % 26;
+        name.push(b'A' + rem as u8);
+        n = (n - 1) / 26;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap_or_default()
+}
+
+/// Excel rejects tab names over 31 characters or containing `[]:*?/\`.
+fn sheet_name(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .filter(|c| !matches!(c, '[' | ']' | ':' | '*' | '?' | '/' | '\\'))
+        .take(31)
+        .collect();
+    if cleaned.trim().is_empty() {
+        "Sheet".to_string()
+    } else {
+        cleaned
+    }
+}
+
+
+// ── ZIP (stored, no compression) ──────────────────────────────────────────────
+
+#[derive(Default)]
+struct ZipWriter {
+    out: Vec<u8>,
+    central: Vec<u8>,
+    entries: u16,
+}
+
+/// 1980-01-01 00:00, the earliest DOS date; keeps output reproducible.
+const DOS_DATE: u16 = 0x21;
+
+impl ZipWriter {
+    fn add(&mut self, name: &str, data: &[u8]) {
+        let offset = self.out.len() as u32;
+        let crc = crc32(data);
+        let size = data.len() as u32;
+        let name = name.as_bytes();
+
+        push_u32(&mut self.out, 0x0403_4b50);
+        push_u16(&mut self.out, 20); // version needed
+        push_u16(&mut self.out, 0x0800); // UTF-8 names
+        push_u16(&mut self.out, 0); // stored
+        push_u16(&mut self.out, 0); // time
+        push_u16(&mut self.out, DOS_DATE);
+        push_u32(&mut self.out, crc);
+        push_u32(&mut self.out, size);
+        push_u32(&mut self.out, size);
+        push_u16(&mut self.out, name.len() as u16);
+        push_u16(&mut self.out, 0); // extra
+        self.out.extend_from_slice(name);
+        self.out.extend_from_slice(data);
+
+        push_u32(&mut self.central, 0x0201_4b50);
+        push_u16(&mut self.central, 20); // version made by
+        push_u16(&mut self.central, 20); // version needed
+        push_u16(&mut self.central, 0x0800);
+        push_u16(&mut self.central, 0);
+        push_u16(&mut self.central, 0);
+        push_u16(&mut self.central, DOS_DATE);
+        push_u32(&mut self.central, crc);
+        push_u32(&mut self.central, size);
+        push_u32(&mut self.central, size);
+        push_u16(&mut self.central, name.len() as u16);
+        push_u16(&mut self.central, 0); // extra
+        push_u16(&mut self.central, 0); // comment
+        push_u16(&mut self.central, 0); // disk
+        push_u16(&mut self.central, 0); // internal attributes
+        push_u32(&mut self.central, 0); // external attributes
+        push_u32(&mut self.central, offset);
+        self.central.extend_from_slice(name);
+        self.entries += 1;
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let central_offset = self.out.len() as u32;
+        let central_size = self.central.len() as u32;
+        self.out.append(&mut self.central);
+        push_u32(&mut self.out, 0x0605_4b50);
+        push_u16(&mut self.out, 0); // this disk
+        push_u16(&mut self.out, 0); // central directory disk
+        push_u16(&mut self.out, self.entries);
+        push_u16(&mut self.out, self.entries);
+        push_u32(&mut self.out, central_size);
+        push_u32(&mut self.out, central_offset);
+        push_u16(&mut self.out, 0); // comment
+        self.out
+    }
+}
+
+fn push_u16(out: &mut Vec<u8>, v: u16) {
+    out.extend_from_slice(&v.to_le_bytes());
+}
+
+fn push_u32(out: &mut Vec<u8>, v: u32) {
+    out.extend_from_slice(&v.to_le_bytes());
+}
+
+/// CRC-32 (IEEE), as ZIP requires.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
@@ -0,0 +1,166 @@
+/// CSV import of external items (Jira issue exports, generic tracker lists).
+/// Pure functions; the commands layer does the store upsert.
+///
+/// The CSV must have a header row. The mapping names the header of each
+/// column to read; its defaults match Jira's CSV export ("Issue key",
+/// "Summary", "Status").
+use crate::core::model::{ExternalItem, ExternalSource};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Which CSV columns hold which item fields. Header matching ignores case
+/// and surrounding whitespace.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExternalItemMapping {
+    #[serde(default)]
+    pub source: ExternalSource,
+    #[serde(default = "default_key_column")]
+    pub key_column: String,
+    #[serde(default = "default_title_column")]
+    pub title_column: String,
+    #[serde(default = "default_status_column")]
+    pub status_column: Option<String>,
+    #[serde(default)]
+    pub url_column: Option<String>,
+    /// Builds the URL when there is no URL column, e.g.
+    /// `https://example.atlassian.net/browse/{key}`.
+    #[serde(default)]
+    pub url_template: Option<String>,
+}
+
+fn default_key_column() -> String {
+    "Issue key".to_string()
+}
+
+fn default_title_column() -> String {
+    "Summary".to_string()
+}
+
+fn default_status_column() -> Option<String> {
+    Some("Status".to_string())
+}
+
+/// A data row that couldn't be imported.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkippedRow {
+    /// 1-based record number in the CSV, counting the header as record 1.
+    pub row: usize,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExternalImportSummary {
+    pub created: usize,
+    pub updated: usize,
+    pub skipped: Vec<SkippedRow>,
+}
+
+/// Items for every data row with a key. `id` is fresh; the store keeps the
+/// existing id when the key is already known. A key repeated within the
+/// file keeps its last row.
+pub fn items_from_csv(
+    project_id: Uuid,
+    csv: &str,
+    mapping: &ExternalItemMapping,
+) -> Result<(Vec<ExternalItem>, Vec<SkippedRow>), String> {
+    let mut rows = parse_csv(csv)?.into_iter();
+    let header = rows.next().ok_or("the CSV is empty")?;
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|h| h.trim().eq_ignore_ascii_case(name.trim()))
+            .ok_or_else(|| format!("column '{name}' not found in the CSV header"))
+    };
+    let key_col = column(&mapping.key_column)?;
+    let title_col = column(&mapping.title_column)?;
+    let status_col = mapping.status_column.as_deref().map(column).transpose()?;
+    let url_col = mapping.url_column.as_deref().map(column).transpose()?;
+
+    let now = Utc::now();
+    let mut items: Vec<ExternalItem> = Vec::new();
+    let mut skipped = Vec::new();
+    for (i, row) in rows.enumerate() {
+        let cell = |col: usize| row.get(col).map(|s| s.trim()).unwrap_or_default();
+        if row.iter().all(|c| c.trim().is_empty()) {
+            continue;
+        }
+        let key = cell(key_col);
+        if key.is_empty() {
+            skipped.push(SkippedRow {
+                row: i + 2,
+                reason: format!("no value in '{}'", mapping.key_column),
+            });
+            continue;
+        }
+        let url = url_col
+            .map(cell)
+            .filter(|u| !u.is_empty())
+            .map(str::to_string)
+            .or_else(|| {
+                mapping
+                    .url_template
+                    .as_deref()
+                    .filter(|t| !t.trim().is_empty())
+                    .map(|t| t.trim().replace("{key}", key))
+            });
+        let item = ExternalItem {
+            id: Uuid::new_v4(),
+            project_id,
+            source: mapping.source,
+            external_key: key.to_string(),
+            title: cell(title_col).to_string(),
+            url,
+            status: status_col.map(cell).unwrap_or_default().to_string(),
+            synced_at: now,
+        };
+        match items.iter_mut().find(|it| it.external_key == item.external_key) {
+            Some(existing) => *existing = item,
+            None => items.push(item),
+        }
+    }
+    Ok((items, skipped))
+}
+
+/// Split RFC 4180 CSV into rows of fields: quoted fields may contain commas,
+/// doubled quotes and line breaks. A leading BOM is ignored.
+pub fn parse_csv(text: &str) -> Result<Vec<Vec<String>>, String> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => in_quotes = true,
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' | '\r' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err("unterminated quoted field in the CSV".to_string());
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    Ok(rows)
+}
@@ -0,0 +1,301 @@
+/// Rule-based gate over LLM requirement extraction results.
+///
+/// The model is good at finding obligation sentences but also returns
+/// section headings, rationale and notes. Every result gets flags computed
+/// here from the sentence alone, regardless of what the model claimed, and
+/// the project's `extract.*` settings decide which results are demoted or
+/// rejected outright.
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub mod review;
+
+pub const FLAG_NO_MODAL_VERB: &str = "no_modal_verb";
+pub const FLAG_LOOKS_LIKE_HEADING: &str = "looks_like_heading";
+pub const FLAG_CONTAINS_CROSS_REFERENCE: &str = "contains_cross_reference";
+
+const MODAL_VERBS: [&str; 4] = ["shall", "must", "will", "should"];
+
+const CROSS_REFERENCE_PHRASES: [&str; 8] = [
+    "see section",
+    "see table",
+    "see figure",
+    "refer to",
+    "as defined in",
+    "as specified in",
+    "in accordance with",
+    "per section",
+];
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Confidence {
+    Low,
+    Medium,
+    High,
+}
+
+impl Confidence {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "low" => Some(Self::Low),
+            "medium" => Some(Self::Medium),
+            "high" => Some(Self::High),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+        }
+    }
+
+    fn demoted(self) -> Self {
+        match self {
+            Self::High => Self::Medium,
+            _ => Self::Low,
+        }
+    }
+}
+
+/// Settings-driven thresholds. The default accepts everything and only
+/// demotes suspicious results.
+#[derive(Debug, Clone)]
+pub struct ExtractionGate {
+    /// `extract.min_confidence` — results below this (after demotion) are rejected.
+    pub min_confidence: Confidence,
+    /// `extract.require_modal` — reject sentences without shall/must/will/should.
+    pub require_modal: bool,
+    /// `extract.max_sentence_chars` — reject longer sentences; `None` = no limit.
+    pub max_sentence_chars: Option<usize>,
+}
+
+impl Default for ExtractionGate {
+    fn default() -> Self {
+        Self {
+            min_confidence: Confidence::Low,
+            require_modal: false,
+            max_sentence_chars: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GateOutcome {
+    pub accepted: Vec<Value>,
+    /// Rejected items, each with a `rejection_reason` field added.
+    pub rejected: Vec<Value>,
+}
+
+/// Flags derived from the sentence text alone.
+pub fn rule_flags(sentence: &str) -> Vec<&'static str> {
+    let mut flags = Vec::new();
+    let lower = sentence.to_lowercase();
+
+    if !has_modal_verb(&lower) {
+        flags.push(FLAG_NO_MODAL_VERB);
+    }
+    if looks_like_heading(sentence) {
+        flags.push(FLAG_LOOKS_LIKE_HEADING);
+    }
+    if CROSS_REFERENCE_PHRASES.iter().any(|p| lower.contains(p)) || has_section_number_reference(&lower) {
+        flags.push(FLAG_CONTAINS_CROSS_REFERENCE);
+    }
+    flags
+}
+
+/// Annotate each item with `rule_flags`, demote suspicious ones and split
+/// them into accepted and rejected.
+///
+/// Headings and sentences without a modal verb drop one confidence level
+/// (recorded in `demoted_from`); rejection happens when the gate's limits
+/// are exceeded.
+pub fn apply_gate(items: Vec<Value>, gate: &ExtractionGate) -> GateOutcome {
+    let mut outcome = GateOutcome::default();
+    for mut item in items {
+        let sentence = item["sentence"].as_str().unwrap_or("").trim().to_string();
+        let flags = rule_flags(&sentence);
+        let original = item["confidence"]
+            .as_str()
+            .and_then(Confidence::parse)
+            .unwrap_or(Confidence::Low);
+
+        let mut confidence = original;
+        if flags.contains(&FLAG_LOOKS_LIKE_HEADING) || flags.contains(&FLAG_NO_MODAL_VERB) {
+            confidence = confidence.demoted();
+        }
+
+        let chars = sentence.chars().count();
+        let reason = if sentence.is_empty() {
+            Some("empty sentence".to_string())
+        } else if gate.require_modal && flags.contains(&FLAG_NO_MODAL_VERB) {
+            Some("no modal verb (shall/must/will/should)".to_string())
+        } else if let Some(max) = gate.max_sentence_chars.filter(|max| chars > *max) {
+            Some(format!("sentence is {chars} characters; limit is {max}"))
+        } else if confidence < gate.min_confidence {
+            Some(format!(
+                "confidence {} is below the minimum {}",
+                confidence.as_str(),
+                gate.min_confidence.as_str()
+            ))
+        } else {
+            None
+        };
+
+        if let Some(obj) = item.as_object_mut() {
+            obj.insert("rule_flags".to_string(), serde_json::json!(flags));
+            if confidence != original {
+                obj.insert("confidence".to_string(), Value::from(confidence.as_str()));
+                obj.insert("demoted_from".to_string(), Value::from(original.as_str()));
+            }
+            if let Some(reason) = &reason {
+                obj.insert("rejection_reason".to_string(), Value::from(reason.as_str()));
+            }
+        }
+        match reason {
+            Some(_) => outcome.rejected.push(item),
+            None => outcome.accepted.push(item),
+        }
+    }
+    outcome
+}
+
+/// Fold list items back into the sentence that introduces them.
+///
+/// "The system shall support the following modes:" followed by "a) idle",
+/// "b) survey", "c) safe" comes back from the model as four items. Each
+/// stem ending in a colon absorbs the items right after it that start with
+/// a list marker and have no modal verb of their own; the merged item keeps
+/// the stem's other fields, joins the verbatim texts with spaces and records
+/// how many items it was built from in `merged_from`.
+///
+/// Items must be in document order and from the same chunk — fragments are
+/// only recognised by adjacency.
+pub fn merge_list_continuations(items: Vec<Value>) -> Vec<Value> {
+    let mut out: Vec<Value> = Vec::with_capacity(items.len());
+    let mut items = items.into_iter().peekable();
+    while let Some(mut stem) = items.next() {
+        let stem_sentence = sentence_of(&stem);
+        if !stem_sentence.ends_with(':') {
+            out.push(stem);
+            continue;
+        }
+
+        let mut combined = stem_sentence;
+        let mut merged = 1;
+        while let Some(next) = items.peek() {
+            let fragment = sentence_of(next);
+            if !is_list_fragment(&fragment) {
+                break;
+            }
+            combined.push(' ');
+            combined.push_str(&fragment);
+            merged += 1;
+            items.next();
+        }
+
+        if merged > 1 {
+            if let Some(obj) = stem.as_object_mut() {
+                obj.insert("sentence".to_string(), Value::from(combined));
+                obj.insert("merged_from".to_string(), Value::from(merged));
+            }
+        }
+        out.push(stem);
+    }
+    out
+}
+
+fn sentence_of(item: &Value) -> String {
+    item["sentence"].as_str().unwrap_or("").trim().to_string()
+}
+
+fn has_modal_verb(lower: &str) -> bool {
+    lower
+        .split(|c: char| !c.is_alphanumeric())
+        .any(|w| MODAL_VERBS.contains(&w))
+}
+
+fn is_list_fragment(sentence: &str) -> bool {
+    starts_with_list_marker(sentence) && !has_modal_verb(&sentence.to_lowercase())
+}
+
+/// "a) ", "(b) ", "3. ", "iv) " — a short letter, number or roman numeral
+/// closed by a parenthesis or period.
+pub(crate) fn starts_with_list_marker(sentence: &str) -> bool {
+    let s = sentence.trim_start();
+    let rest = s.strip_prefix('(').unwrap_or(s);
+    let token_len = rest
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .unwrap_or(rest.len());
+    let (token, after) = rest.split_at(token_len);
+    let is_marker = match token.len() {
+        0 => false,
+        1 => true,
+        len => {
+            (len <= 3 && token.chars().all(|c| c.is_ascii_digit()))
+                || (len <= 4 && token.chars().all(|c| matches!(c, 'i' | 'v' | 'x')))
+        }
+    };
+    let mut after = after.chars();
+    is_marker
+        && matches!(after.next(), Some(')' | '.'))
+        && !matches!(after.next(), Some(c) if !c.is_whitespace())
+}
+
+/// Short, title-cased and unpunctuated: "3.2 Thermal Management".
+fn looks_like_heading(sentence: &str) -> bool {
+    let s = sentence.trim();
+    if s.is_empty() || s.ends_with(['.', ';', ':', '?', '!']) {
+        return false;
+    }
+    let words: Vec<&str> = s
+        .split_whitespace()
+        .filter(|w| w.chars().any(char::is_alphabetic))
+        .collect();
+    if words.is_empty() || words.len() > 8 {
+        return false;
+    }
+    let capitalized = words
+        .iter()
+        .filter(|w| w.chars().find(|c| c.is_alphabetic()).is_some_and(char::is_uppercase))
+        .count();
+    // Short function words ("of", "and") are usually lowercase in titles.
+    capitalized * 4 >= words.len() * 3
+}
+
+/// "section 3.2", "paragraph 4.1.2", "table 5", "§ 7".
+fn has_section_number_reference(lower: &str) -> bool {
+    if lower.contains('§') {
+        return true;
+    }
+    let words: Vec<&str> = lower.split_whitespace().collect();
+    words.windows(2).any(|pair| {
+        matches!(pair[0], "section" | "paragraph" | "table" | "figure" | "appendix" | "annex")
+            && pair[1].starts_with(|c: char| c.is_ascii_digit())
+    })
+}
+
+/// SHA-256 hex of everything that decides an extraction's raw results:
+/// the text, document type, provider and model, plus `variant` for the
+/// options that change the pass itself (GraphRAG enrichment, list merging).
+/// Each part is length-prefixed so no two inputs hash the same bytes.
+pub fn cache_key(text: &str, doc_type: &str, provider: &str, model: &str, variant: &str) -> String {
+    use sha2::{Digest, Sha256};
+
+    let mut hasher = Sha256::new();
+    for part in [text, doc_type, provider, model, variant] {
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part.as_bytes());
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests;
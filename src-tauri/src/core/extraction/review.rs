@@ -0,0 +1,167 @@
+/// Persisted review of an extraction run. The raw results are stored once;
+/// each save keeps only the reviewer's decision and the fields that differ
+/// from the raw result, so a 300-item review stays small.
+///
+/// On commit, accepted items are resolved (raw result plus edits) and turned
+/// into Draft requirement nodes the same way the import panel builds them.
+use crate::core::model::{
+    ExtractionReviewItem, Node, NodeData, NodeKind, RequirementData, RequirementPriority,
+    RequirementStatus, ReviewDecision, ORIGINAL_CREATED_AT_META,
+};
+use chrono::{DateTime, Utc};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use uuid::Uuid;
+
+/// Reduce `items` to deltas against `results`: edits equal to the raw field
+/// are dropped, and pending items with no edits are dropped entirely. When
+/// an index appears twice the later item wins. The result is ordered by
+/// index.
+pub fn compact(results: &[Value], items: Vec<ExtractionReviewItem>) -> Vec<ExtractionReviewItem> {
+    let mut by_index: BTreeMap<usize, ExtractionReviewItem> = BTreeMap::new();
+    for mut item in items {
+        if let Some(Value::Object(raw)) = results.get(item.index) {
+            item.edits.retain(|key, value| raw.get(key) != Some(value));
+        }
+        by_index.insert(item.index, item);
+    }
+    by_index
+        .into_values()
+        .filter(|item| {
+            item.decision != ReviewDecision::Pending
+                || !item.edits.is_empty()
+                || item.node_id.is_some()
+        })
+        .collect()
+}
+
+/// The item's fields as the reviewer sees them: the raw result with the
+/// edits applied on top.
+pub fn resolve(results: &[Value], item: &ExtractionReviewItem) -> Map<String, Value> {
+    let mut fields = match results.get(item.index) {
+        Some(Value::Object(raw)) => raw.clone(),
+        _ => Map::new(),
+    };
+    fields.extend(item.edits.iter().map(|(k, v)| (k.clone(), v.clone())));
+    fields
+}
+
+/// `count` fresh "REQ-NNN" ids following the highest trailing number among
+/// `existing`.
+pub fn next_req_ids<'a>(existing: impl IntoIterator<Item = &'a str>, count: usize) -> Vec<String> {
+    let max = existing
+        .into_iter()
+        .filter_map(|id| {
+            let digits = id.len() - id.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+            id[id.len() - digits..].parse::<u64>().ok()
+        })
+        .max()
+        .unwrap_or(0);
+    (1..=count as u64)
+        .map(|n| format!("REQ-{:03}", max + n))
+        .collect()
+}
+
+/// The requirement sentence of an item's resolved fields: `text` (the
+/// edited sentence) when set, otherwise `sentence`.
+pub fn requirement_text(fields: &Map<String, Value>) -> Option<&str> {
+    let field = |key: &str| {
+        fields
+            .get(key)
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+    };
+    field("text").or_else(|| field("sentence"))
+}
+
+/// A Draft requirement from an item's resolved fields, or `None` when it
+/// has no sentence (see [`requirement_text`]). `allocation` becomes the
+/// only allocation. Items flagged "ai" are attributed to the AI, others to
+/// the import. An RFC 3339 `original_created_at` field is kept in meta.
+pub fn requirement_node(
+    project_id: Uuid,
+    fields: &Map<String, Value>,
+    req_id: String,
+    source_name: &str,
+    now: DateTime<Utc>,
+) -> Option<Node> {
+    let field = |key: &str| {
+        fields
+            .get(key)
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+    };
+    let text = requirement_text(fields)?.to_string();
+    let name = field("name")
+        .map(str::to_string)
+        .unwrap_or_else(|| crate::core::split::derive_name(&text));
+
+    let lower = text.to_lowercase();
+    let strong = lower
+        .split(|c: char| !c.is_alphanumeric())
+        .any(|w| w == "shall" || w == "must");
+    let priority = if strong {
+        RequirementPriority::Shall
+    } else {
+        RequirementPriority::Should
+    };
+
+    let ai_generated = fields
+        .get("flags")
+        .and_then(Value::as_array)
+        .is_some_and(|flags| flags.iter().any(|f| f.as_str() == Some("ai")));
+    let (actor, change_source) = if ai_generated {
+        ("ai", "ai")
+    } else {
+        ("system", "import")
+    };
+    let source = if source_name.is_empty() {
+        "document"
+    } else {
+        source_name
+    };
+
+    let mut meta: HashMap<String, Value> = HashMap::new();
+    meta.insert("source_doc".to_string(), Value::from(source_name));
+    meta.insert("actor".to_string(), Value::from(actor));
+    meta.insert("change_source".to_string(), Value::from(change_source));
+    // The source's own date, when the extractor found one.
+    if let Some(original) = field(ORIGINAL_CREATED_AT_META)
+        .and_then(|raw| DateTime::parse_from_rfc3339(raw).ok())
+    {
+        meta.insert(
+            ORIGINAL_CREATED_AT_META.to_string(),
+            Value::from(original.with_timezone(&Utc).to_rfc3339()),
+        );
+    }
+
+    Some(Node {
+        id: Uuid::new_v4(),
+        project_id,
+        kind: NodeKind::Requirement,
+        name,
+        description: text.clone(),
+        data: NodeData::Requirement(RequirementData {
+            req_id: Some(req_id),
+            text: Some(text),
+            rationale: Some(format!("Extracted from: {source}")),
+            priority,
+            status: RequirementStatus::Draft,
+            source: Some(source_name.to_string()),
+            allocations: Some(
+                field("allocation")
+                    .map(str::to_string)
+                    .into_iter()
+                    .collect(),
+            ),
+            verification_method: None,
+            classification: field("classification").map(str::to_string),
+        }),
+        meta,
+        archived: false,
+        created_at: now,
+        modified_at: now,
+    })
+}
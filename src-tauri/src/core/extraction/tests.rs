@@ -0,0 +1,39 @@
+use super::*;
+use serde_json::json;
+
+#[test]
+fn three_bullet_shall_statement_merges_into_its_stem() {
+    let items = vec![
+        json!({ "sentence": "The system shall support the following modes:", "section": "3.1" }),
+        json!({ "sentence": "a) idle;", "section": "3.1" }),
+        json!({ "sentence": "(b) survey;", "section": "3.1" }),
+        json!({ "sentence": "c) safe.", "section": "3.1" }),
+        json!({ "sentence": "The system shall log every mode change.", "section": "3.2" }),
+    ];
+
+    let merged = merge_list_continuations(items);
+    assert_eq!(
+        merged,
+        [
+            json!({
+                "sentence": "The system shall support the following modes: \
+                             a) idle; (b) survey; c) safe.",
+                "section": "3.1",
+                "merged_from": 4,
+            }),
+            json!({ "sentence": "The system shall log every mode change.", "section": "3.2" }),
+        ]
+    );
+}
+
+#[test]
+fn a_bullet_with_its_own_modal_verb_stays_separate() {
+    let items = vec![
+        json!({ "sentence": "The pump controller:" }),
+        json!({ "sentence": "a) shall start the pump within 2 s;" }),
+        json!({ "sentence": "b) Version 2.0 of the interface" }),
+    ];
+
+    let merged = merge_list_continuations(items.clone());
+    assert_eq!(merged, items);
+}
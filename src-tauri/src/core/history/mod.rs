@@ -0,0 +1,204 @@
+/// Requirement history retention: which entries to drop or fold together
+/// so the history table stops growing without bound. Pure planning over
+/// loaded entries; the commands layer reads settings and applies the plan.
+///
+/// Per node, the oldest entry (usually the original import) is never
+/// touched, and the newest `keep_per_node` entries are always kept. Other
+/// entries are deleted once they are also older than `keep_days`. Deleting
+/// leaves a gap in the prev/next chain, which the history view shows as
+/// separate changes.
+///
+/// Collapsing runs first: consecutive entries by the same actor and change
+/// source, each within `collapse_window_secs` of the one before, become one
+/// entry with the first one's id and `prev` snapshot and the last one's
+/// `next` snapshot and timestamp.
+use crate::core::model::{Edge, Node, RequirementHistoryEntry, SuspectLink};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use uuid::Uuid;
+
+pub mod word_diff;
+
+use word_diff::DiffSpan;
+
+/// Default for the `history.keep_per_node` setting.
+pub const DEFAULT_KEEP_PER_NODE: usize = 50;
+
+/// Default for the `history.keep_days` setting.
+pub const DEFAULT_KEEP_DAYS: i64 = 180;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HistoryRetention {
+    pub keep_per_node: usize,
+    pub keep_days: i64,
+    /// 0 disables collapsing.
+    pub collapse_window_secs: i64,
+}
+
+impl Default for HistoryRetention {
+    fn default() -> Self {
+        Self {
+            keep_per_node: DEFAULT_KEEP_PER_NODE,
+            keep_days: DEFAULT_KEEP_DAYS,
+            collapse_window_secs: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct HistoryPrunePlan {
+    /// Surviving entries of collapsed runs, with their new `next` and `ts`.
+    pub updated: Vec<RequirementHistoryEntry>,
+    /// Every entry to delete, collapsed or expired.
+    pub deleted: Vec<Uuid>,
+    /// Entries folded into an earlier one.
+    pub collapsed: usize,
+    /// Entries dropped by the count and age limits.
+    pub expired: usize,
+    pub nodes_affected: usize,
+}
+
+/// Plan the pruning of `entries`, which may span many nodes in any order.
+pub fn plan(
+    entries: Vec<RequirementHistoryEntry>,
+    retention: &HistoryRetention,
+    now: DateTime<Utc>,
+) -> HistoryPrunePlan {
+    let mut by_node: BTreeMap<Uuid, Vec<RequirementHistoryEntry>> = BTreeMap::new();
+    for entry in entries {
+        by_node.entry(entry.node_id).or_default().push(entry);
+    }
+
+    let cutoff = now - Duration::days(retention.keep_days.max(0));
+    let mut plan = HistoryPrunePlan::default();
+    for (_, mut history) in by_node {
+        history.sort_by_key(|e| (e.ts, e.id));
+        let before = plan.deleted.len();
+
+        let (history, absorbed) = collapse(history, retention.collapse_window_secs);
+        for ids in absorbed.values() {
+            plan.collapsed += ids.len();
+            plan.deleted.extend(ids);
+        }
+
+        // Index 0 is the oldest entry; from `protected_from` on are the newest.
+        let protected_from = history.len().saturating_sub(retention.keep_per_node);
+        for (i, entry) in history.into_iter().enumerate() {
+            if i > 0 && i < protected_from && entry.ts < cutoff {
+                plan.expired += 1;
+                plan.deleted.push(entry.id);
+            } else if absorbed.contains_key(&entry.id) {
+                plan.updated.push(entry);
+            }
+        }
+
+        if plan.deleted.len() > before {
+            plan.nodes_affected += 1;
+        }
+    }
+    plan
+}
+
+/// Collapse one node's entries (oldest first). Returns the remaining
+/// entries, with survivors rewritten, and the ids folded into each survivor.
+fn collapse(
+    history: Vec<RequirementHistoryEntry>,
+    window_secs: i64,
+) -> (Vec<RequirementHistoryEntry>, HashMap<Uuid, Vec<Uuid>>) {
+    let mut absorbed: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+    if window_secs <= 0 {
+        return (history, absorbed);
+    }
+    let window = Duration::seconds(window_secs);
+
+    let mut kept: Vec<RequirementHistoryEntry> = Vec::with_capacity(history.len());
+    for entry in history {
+        // The oldest entry never absorbs later ones.
+        let can_absorb = kept.len() > 1;
+        match kept.last_mut().filter(|_| can_absorb) {
+            Some(last)
+                if last.actor == entry.actor
+                    && last.source == entry.source
+                    && entry.ts - last.ts <= window =>
+            {
+                absorbed.entry(last.id).or_default().push(entry.id);
+                last.next = entry.next;
+                last.ts = entry.ts;
+            }
+            _ => kept.push(entry),
+        }
+    }
+    (kept, absorbed)
+}
+
+/// Result of `prune_requirement_history`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryPruneReport {
+    pub project_id: Uuid,
+    pub dry_run: bool,
+    pub retention: HistoryRetention,
+    pub entries_before: usize,
+    /// Rows removed, or that would be removed in a dry run.
+    pub removed: usize,
+    pub collapsed: usize,
+    pub expired: usize,
+    pub nodes_affected: usize,
+}
+
+/// Everything needed to review one suspect link: the edge, its endpoints,
+/// and how the upstream (source) node's text changed in the review window.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuspectLinkContext {
+    pub suspect: SuspectLink,
+    /// `None` if the edge has since been deleted.
+    pub edge: Option<Edge>,
+    pub source: Option<Node>,
+    pub target: Option<Node>,
+    /// When the link was last reviewed: the previous resolution of a
+    /// suspect on the same edge. `None` if it never was.
+    pub reviewed_at: Option<DateTime<Utc>>,
+    /// The source's history entries in the window, oldest first.
+    pub history: Vec<RequirementHistoryEntry>,
+    pub text_before: String,
+    pub text_after: String,
+    pub text_diff: Vec<DiffSpan>,
+}
+
+/// The previous resolution of a suspect on `link`'s edge, from all suspect
+/// links ever recorded for that edge.
+pub fn last_review(link: &SuspectLink, edge_links: &[SuspectLink]) -> Option<DateTime<Utc>> {
+    edge_links
+        .iter()
+        .filter(|l| l.id != link.id)
+        .filter_map(|l| l.resolved_at)
+        .filter(|at| *at <= link.flagged_at)
+        .max()
+}
+
+/// The source history entries to review for `link`, oldest first: those
+/// after `reviewed_at` or, if the link was never reviewed, from the change
+/// that raised the flag (the last entry at or before `flagged_at`). The
+/// window ends when the link is resolved.
+pub fn suspect_window(
+    mut history: Vec<RequirementHistoryEntry>,
+    link: &SuspectLink,
+    reviewed_at: Option<DateTime<Utc>>,
+) -> Vec<RequirementHistoryEntry> {
+    history.sort_by_key(|e| (e.ts, e.id));
+    let start = match reviewed_at {
+        Some(at) => history.iter().position(|e| e.ts > at).unwrap_or(history.len()),
+        None => history
+            .iter()
+            .rposition(|e| e.ts <= link.flagged_at)
+            .unwrap_or(0),
+    };
+    history
+        .into_iter()
+        .skip(start)
+        .take_while(|e| !link.resolved_at.is_some_and(|at| e.ts > at))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,173 @@
+use super::word_diff::{diff_words, DiffOp};
+use super::*;
+use crate::core::model::RequirementSnapshot;
+
+/// The diff as (op, text) pairs, after checking that it rebuilds both
+/// sides.
+fn spans(old: &str, new: &str) -> Vec<(DiffOp, String)> {
+    let diff = diff_words(old, new);
+    let side = |skip: DiffOp| -> String {
+        diff.iter()
+            .filter(|s| s.op != skip)
+            .map(|s| s.text.as_str())
+            .collect()
+    };
+    assert_eq!(side(DiffOp::Insert), old);
+    assert_eq!(side(DiffOp::Delete), new);
+    diff.into_iter().map(|s| (s.op, s.text)).collect()
+}
+
+#[test]
+fn word_diff_keeps_unicode_words_whole() {
+    assert_eq!(
+        spans(
+            "Der Sensor misst die Größe auf ±0,5 °C.",
+            "Der Sensor misst die Grösse auf ±0,2 °C."
+        ),
+        [
+            (DiffOp::Equal, "Der Sensor misst die ".to_string()),
+            (DiffOp::Delete, "Größe".to_string()),
+            (DiffOp::Insert, "Grösse".to_string()),
+            (DiffOp::Equal, " auf ±0,".to_string()),
+            (DiffOp::Delete, "5".to_string()),
+            (DiffOp::Insert, "2".to_string()),
+            (DiffOp::Equal, " °C.".to_string()),
+        ]
+    );
+    assert_eq!(
+        spans("系统应当记录故障", "系统应当记录故障。"),
+        [
+            (DiffOp::Equal, "系统应当记录故障".to_string()),
+            (DiffOp::Insert, "。".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn word_diff_reports_punctuation_only_changes() {
+    assert_eq!(
+        spans("The pump shall start.", "The pump shall start!"),
+        [
+            (DiffOp::Equal, "The pump shall start".to_string()),
+            (DiffOp::Delete, ".".to_string()),
+            (DiffOp::Insert, "!".to_string()),
+        ]
+    );
+    assert_eq!(
+        spans(
+            "If power fails the unit shall reset",
+            "If power fails, the unit shall reset"
+        ),
+        [
+            (DiffOp::Equal, "If power fails".to_string()),
+            (DiffOp::Insert, ",".to_string()),
+            (DiffOp::Equal, " the unit shall reset".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn word_diff_of_equal_text_is_one_equal_span() {
+    assert_eq!(
+        spans("No change.", "No change."),
+        [(DiffOp::Equal, "No change.".to_string())]
+    );
+    assert!(spans("", "").is_empty());
+}
+
+fn snapshot(text: &str) -> RequirementSnapshot {
+    RequirementSnapshot {
+        text: text.to_string(),
+        ..Default::default()
+    }
+}
+
+/// A change of `node`'s text from `before` to `after`, `secs` seconds
+/// after `start`.
+fn change(
+    node: Uuid,
+    start: DateTime<Utc>,
+    secs: i64,
+    actor: &str,
+    before: &str,
+    after: &str,
+) -> RequirementHistoryEntry {
+    RequirementHistoryEntry {
+        id: Uuid::new_v4(),
+        project_id: Uuid::nil(),
+        node_id: node,
+        ts: start + Duration::seconds(secs),
+        actor: actor.to_string(),
+        source: "ui".to_string(),
+        prev: snapshot(before),
+        next: snapshot(after),
+    }
+}
+
+#[test]
+fn collapsing_stitches_a_run_into_its_first_entry() {
+    let node = Uuid::new_v4();
+    let start = Utc::now() - Duration::days(1);
+    let history = vec![
+        change(node, start, 0, "alice", "", "v1"),
+        change(node, start, 10, "alice", "v1", "v2"),
+        change(node, start, 40, "alice", "v2", "v3"),
+        change(node, start, 70, "alice", "v3", "v4"),
+        change(node, start, 80, "bob", "v4", "v5"),
+        change(node, start, 500, "bob", "v5", "v6"),
+    ];
+    let retention = HistoryRetention {
+        collapse_window_secs: 60,
+        ..Default::default()
+    };
+
+    let plan = plan(history.clone(), &retention, Utc::now());
+    // The creation never absorbs; bob's first change starts a new run,
+    // and his second is outside the window.
+    assert_eq!(plan.deleted, [history[2].id, history[3].id]);
+    assert_eq!(
+        (plan.collapsed, plan.expired, plan.nodes_affected),
+        (2, 0, 1)
+    );
+    assert_eq!(plan.updated.len(), 1);
+    let survivor = &plan.updated[0];
+    assert_eq!(survivor.id, history[1].id);
+    assert_eq!(
+        (survivor.prev.text.as_str(), survivor.next.text.as_str()),
+        ("v1", "v4")
+    );
+    assert_eq!(survivor.ts, history[3].ts);
+}
+
+#[test]
+fn retention_keeps_the_oldest_and_newest_entries() {
+    let (node, untouched) = (Uuid::new_v4(), Uuid::new_v4());
+    let now = Utc::now();
+    let day = 86_400;
+    let start = now - Duration::days(400);
+    let history = vec![
+        change(node, start, 0, "import", "", "v1"),
+        change(node, start, 10 * day, "alice", "v1", "v2"),
+        change(node, start, 20 * day, "alice", "v2", "v3"),
+        change(node, start, 30 * day, "alice", "v3", "v4"),
+        change(node, start, 390 * day, "alice", "v4", "v5"),
+        change(node, start, 395 * day, "alice", "v5", "v6"),
+        change(untouched, start, 0, "import", "", "only"),
+        change(untouched, start, day, "alice", "only", "old"),
+    ];
+    let retention = HistoryRetention {
+        keep_per_node: 3,
+        keep_days: 180,
+        collapse_window_secs: 0,
+    };
+
+    let plan = plan(history.clone(), &retention, now);
+    // v4 is old but among the newest three; the other node has nothing
+    // between its oldest entry and its newest three.
+    assert_eq!(plan.deleted, [history[1].id, history[2].id]);
+    assert_eq!(
+        (plan.collapsed, plan.expired, plan.nodes_affected),
+        (0, 2, 1)
+    );
+    assert!(plan.updated.is_empty());
+}
@@ -0,0 +1,139 @@
+/// Word-level text diff for showing how a requirement's text changed.
+///
+/// Text is split into tokens (runs of letters/digits, runs of whitespace,
+/// and single punctuation characters), the tokens are aligned by longest
+/// common subsequence, and adjacent tokens with the same op are merged into
+/// spans. Joining every span's text in order gives back the old text when
+/// inserts are skipped, and the new text when deletes are skipped.
+use serde::{Deserialize, Serialize};
+
+/// Above this many token pairs the LCS table is skipped and the whole text
+/// is reported as replaced.
+const MAX_LCS_CELLS: usize = 4_000_000;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffOp {
+    Equal,
+    Insert,
+    Delete,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DiffSpan {
+    pub op: DiffOp,
+    pub text: String,
+}
+
+pub fn diff_words(old: &str, new: &str) -> Vec<DiffSpan> {
+    let a = tokenize(old);
+    let b = tokenize(new);
+
+    // Common prefix and suffix don't need the table.
+    let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (a_mid, b_mid) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    let mut spans = Vec::new();
+    for t in &a[..prefix] {
+        push(&mut spans, DiffOp::Equal, t);
+    }
+    for (op, t) in align(a_mid, b_mid) {
+        push(&mut spans, op, t);
+    }
+    for t in &a[a.len() - suffix..] {
+        push(&mut spans, DiffOp::Equal, t);
+    }
+    spans
+}
+
+fn tokenize(text: &str) -> Vec<&str> {
+    #[derive(PartialEq)]
+    enum Class {
+        Word,
+        Space,
+        Punct,
+    }
+    let class = |c: char| {
+        if c.is_alphanumeric() || c == '_' {
+            Class::Word
+        } else if c.is_whitespace() {
+            Class::Space
+        } else {
+            Class::Punct
+        }
+    };
+
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut current: Option<Class> = None;
+    for (i, c) in text.char_indices() {
+        let cls = class(c);
+        let extends = current.as_ref() == Some(&cls) && cls != Class::Punct;
+        if !extends && i > start {
+            tokens.push(&text[start..i]);
+            start = i;
+        }
+        current = Some(cls);
+    }
+    if start < text.len() {
+        tokens.push(&text[start..]);
+    }
+    tokens
+}
+
+/// LCS alignment of the differing middle, deletes before inserts within a
+/// changed run.
+fn align<'a>(a: &[&'a str], b: &[&'a str]) -> Vec<(DiffOp, &'a str)> {
+    if a.is_empty() || b.is_empty() || a.len() * b.len() > MAX_LCS_CELLS {
+        return a
+            .iter()
+            .map(|t| (DiffOp::Delete, *t))
+            .chain(b.iter().map(|t| (DiffOp::Insert, *t)))
+            .collect();
+    }
+
+    // lcs[i][j] = LCS length of a[i..] and b[j..].
+    let width = b.len() + 1;
+    let mut lcs = vec![0u32; (a.len() + 1) * width];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i * width + j] = if a[i] == b[j] {
+                lcs[(i + 1) * width + j + 1] + 1
+            } else {
+                lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut out = Vec::with_capacity(a.len() + b.len());
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            out.push((DiffOp::Equal, a[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[(i + 1) * width + j] >= lcs[i * width + j + 1] {
+            out.push((DiffOp::Delete, a[i]));
+            i += 1;
+        } else {
+            out.push((DiffOp::Insert, b[j]));
+            j += 1;
+        }
+    }
+    out.extend(a[i..].iter().map(|t| (DiffOp::Delete, *t)));
+    out.extend(b[j..].iter().map(|t| (DiffOp::Insert, *t)));
+    out
+}
+
+fn push(spans: &mut Vec<DiffSpan>, op: DiffOp, text: &str) {
+    match spans.last_mut() {
+        Some(last) if last.op == op => last.text.push_str(text),
+        _ => spans.push(DiffSpan { op, text: text.to_string() }),
+    }
+}
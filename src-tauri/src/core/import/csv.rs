@@ -0,0 +1,314 @@
+/// CSV import of the requirements table: the columns
+/// [`to_requirements_csv`](crate::core::export::to_requirements_csv) or
+/// `to_csv` write, or any header the ReqIF import knows (ForeignID,
+/// Description, Verification, …). Pure functions; the commands layer
+/// writes the nodes.
+///
+/// The header decides which fields a row sets: a column that isn't there
+/// leaves the field alone when a requirement is updated, while an empty
+/// cell clears it. Problems with a row are reported against it and the
+/// other rows still import. A Created column sets the requirement's
+/// [`ORIGINAL_CREATED_AT_META`].
+use super::{
+    field_for, parse_priority, parse_status, parse_timestamp, parse_verification_method, Field,
+};
+use crate::core::external::{parse_csv, SkippedRow};
+use crate::core::model::{Node, NodeData, NodeKind, RequirementData, ORIGINAL_CREATED_AT_META};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CsvImportMode {
+    /// Every row is a new requirement; a req_id already in the project is
+    /// an error for that row.
+    Create,
+    /// A row whose req_id is in the project updates that requirement;
+    /// the others are created. Every row needs a req_id.
+    UpsertByReqId,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RequirementsCsvImportSummary {
+    pub created: usize,
+    pub updated: usize,
+    /// Rows matching a requirement that already had those values.
+    pub unchanged: usize,
+    pub errors: Vec<SkippedRow>,
+}
+
+/// The requirements to write: new ones in `created`, changed copies of
+/// existing ones in `updated`.
+#[derive(Debug, Clone, Default)]
+pub struct RequirementsCsvImport {
+    pub created: Vec<Node>,
+    pub updated: Vec<Node>,
+    pub unchanged: usize,
+    pub errors: Vec<SkippedRow>,
+}
+
+/// Read `csv` against the requirements already in `nodes`. Fails only when
+/// the file can't be used at all: it doesn't parse, has no header, or the
+/// header lacks a Name or Text column (or an ID column, for
+/// [`CsvImportMode::UpsertByReqId`]).
+///
+/// Text longer than `text_limit` characters is a row error, unless it is
+/// an existing requirement's text left unchanged, as when editing.
+pub fn requirements_from_csv(
+    project_id: Uuid,
+    csv: &str,
+    mode: CsvImportMode,
+    nodes: &[Node],
+    text_limit: usize,
+) -> Result<RequirementsCsvImport, String> {
+    let mut rows = parse_csv(csv)?.into_iter();
+    let header = rows.next().ok_or("the CSV is empty")?;
+    let mut columns: HashMap<Field, usize> = HashMap::new();
+    for (i, name) in header.iter().enumerate() {
+        if let Some(field) = field_for(name) {
+            columns.entry(field).or_insert(i);
+        }
+    }
+    if !columns.contains_key(&Field::Name) && !columns.contains_key(&Field::Text) {
+        return Err("the CSV header has neither a Name nor a Text column".to_string());
+    }
+    if mode == CsvImportMode::UpsertByReqId && !columns.contains_key(&Field::ReqId) {
+        return Err("the CSV header has no ID column to match requirements by".to_string());
+    }
+
+    let existing: HashMap<&str, &Node> = nodes
+        .iter()
+        .filter(|n| n.project_id == project_id && !n.archived)
+        .filter_map(|n| match &n.data {
+            NodeData::Requirement(r) => r.req_id.as_deref().map(|id| (id, n)),
+            _ => None,
+        })
+        .collect();
+
+    let now = Utc::now();
+    let mut import = RequirementsCsvImport::default();
+    // req_id → (updated?, index) of the node a row already wrote.
+    let mut written: HashMap<String, (bool, usize)> = HashMap::new();
+    for (i, row) in rows.enumerate() {
+        let record = i + 2;
+        if row.iter().all(|c| c.trim().is_empty()) {
+            continue;
+        }
+        let mut error = |reason: String| {
+            import.errors.push(SkippedRow {
+                row: record,
+                reason,
+            })
+        };
+
+        // None when the header has no such column; Some(None) when the
+        // cell is empty or missing from a short row.
+        let cell = |field: Field| -> Option<Option<String>> {
+            let col = *columns.get(&field)?;
+            Some(
+                row.get(col)
+                    .map(|s| s.trim())
+                    .filter(|s| !s.is_empty())
+                    .map(str::to_string),
+            )
+        };
+        let req_id = cell(Field::ReqId).flatten();
+        if mode == CsvImportMode::UpsertByReqId && req_id.is_none() {
+            error("no req_id to match on".to_string());
+            continue;
+        }
+
+        let priority = match cell(Field::Priority).flatten() {
+            Some(raw) => match parse_priority(&raw) {
+                Some(p) => Some(p),
+                None => {
+                    error(format!("'{raw}' is not a priority"));
+                    continue;
+                }
+            },
+            None => None,
+        };
+        let status = match cell(Field::Status).flatten() {
+            Some(raw) => match parse_status(&raw) {
+                Some(s) => Some(s),
+                None => {
+                    error(format!("'{raw}' is not a status"));
+                    continue;
+                }
+            },
+            None => None,
+        };
+        let verification = match cell(Field::VerificationMethod) {
+            Some(Some(raw)) => match parse_verification_method(&raw) {
+                Some(v) => Some(Some(v)),
+                None => {
+                    error(format!("'{raw}' is not a verification method"));
+                    continue;
+                }
+            },
+            other => other.map(|_| None),
+        };
+        let created = match cell(Field::CreatedAt).flatten() {
+            Some(raw) => match parse_timestamp(&raw) {
+                Some(t) => Some(t),
+                None => {
+                    error(format!("'{raw}' is not a date"));
+                    continue;
+                }
+            },
+            None => None,
+        };
+        let allocations = cell(Field::Allocations).map(|a| {
+            a.map(|a| {
+                a.split(';')
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect::<Vec<_>>()
+            })
+            .filter(|a| !a.is_empty())
+        });
+
+        let earlier = req_id.as_ref().and_then(|r| written.get(r).copied());
+        let target = match (
+            mode,
+            earlier,
+            req_id.as_deref().and_then(|r| existing.get(r)),
+        ) {
+            (CsvImportMode::Create, Some(_), _) => {
+                error(format!(
+                    "{} repeats an earlier row",
+                    req_id.unwrap_or_default()
+                ));
+                continue;
+            }
+            (CsvImportMode::Create, None, Some(_)) => {
+                error(format!(
+                    "{} is already in the project",
+                    req_id.unwrap_or_default()
+                ));
+                continue;
+            }
+            (_, Some((true, i)), _) => Some(import.updated[i].clone()),
+            (_, Some((false, i)), _) => Some(import.created[i].clone()),
+            (_, None, Some(node)) => Some((*node).clone()),
+            (_, None, None) => None,
+        };
+
+        let text = cell(Field::Text);
+        let name = cell(Field::Name).flatten();
+        let mut node = match target {
+            Some(node) => node,
+            None => {
+                let name = name
+                    .clone()
+                    .or_else(|| req_id.clone())
+                    .or_else(|| text.clone().flatten().map(|t| t.chars().take(80).collect()));
+                let Some(name) = name else {
+                    error("no name, req_id or text".to_string());
+                    continue;
+                };
+                Node {
+                    id: Uuid::new_v4(),
+                    project_id,
+                    kind: NodeKind::Requirement,
+                    name,
+                    description: text.clone().flatten().unwrap_or_default(),
+                    data: NodeData::Requirement(RequirementData {
+                        req_id: req_id.clone(),
+                        ..Default::default()
+                    }),
+                    meta: HashMap::new(),
+                    archived: false,
+                    created_at: now,
+                    modified_at: now,
+                }
+            }
+        };
+        let NodeData::Requirement(r) = &mut node.data else {
+            continue;
+        };
+        if let Some(name) = name {
+            node.name = name;
+        }
+        if let Some(text) = text {
+            r.text = text;
+        }
+        if let Some(rationale) = cell(Field::Rationale) {
+            r.rationale = rationale;
+        }
+        if let Some(priority) = priority {
+            r.priority = priority;
+        }
+        if let Some(status) = status {
+            r.status = status;
+        }
+        if let Some(verification) = verification {
+            r.verification_method = verification;
+        }
+        if let Some(allocations) = allocations {
+            r.allocations = allocations;
+        }
+        if let Some(source) = cell(Field::Source) {
+            r.source = source;
+        }
+        if let Some(classification) = cell(Field::Classification) {
+            r.classification = classification;
+        }
+        let length = r.text.as_deref().map_or(0, |t| t.chars().count());
+        let previous = req_id.as_deref().and_then(|id| existing.get(id));
+        let text_unchanged = previous
+            .is_some_and(|n| matches!(&n.data, NodeData::Requirement(prev) if prev.text == r.text));
+        if length > text_limit && !text_unchanged {
+            error(format!(
+                "text is {length} characters; the limit is {text_limit}"
+            ));
+            continue;
+        }
+        node.meta.insert("actor".to_string(), Value::from("system"));
+        node.meta
+            .insert("change_source".to_string(), Value::from("import"));
+        if let Some(created) = created {
+            node.meta.insert(
+                ORIGINAL_CREATED_AT_META.to_string(),
+                Value::from(created.to_rfc3339()),
+            );
+        }
+        node.modified_at = now;
+
+        let slot = match earlier {
+            Some((true, i)) => {
+                import.updated[i] = node;
+                (true, i)
+            }
+            Some((false, i)) => {
+                import.created[i] = node;
+                (false, i)
+            }
+            None if previous.is_some_and(|prev| same_values(prev, &node)) => {
+                import.unchanged += 1;
+                continue;
+            }
+            None if previous.is_some() => {
+                import.updated.push(node);
+                (true, import.updated.len() - 1)
+            }
+            None => {
+                import.created.push(node);
+                (false, import.created.len() - 1)
+            }
+        };
+        if let Some(req_id) = req_id {
+            written.insert(req_id, slot);
+        }
+    }
+    Ok(import)
+}
+
+/// Whether `node` has the name and requirement fields `prev` has.
+fn same_values(prev: &Node, node: &Node) -> bool {
+    prev.name == node.name
+        && serde_json::to_value(&prev.data).ok() == serde_json::to_value(&node.data).ok()
+}
@@ -0,0 +1,537 @@
+/// ReqIF import: requirements and trace links from DOORS, Polarion and
+/// other requirements tools. Pure functions; the commands layer does the
+/// store upsert.
+///
+/// Every SPEC-OBJECT becomes a requirement. Its attribute values are read
+/// by the long name of their definition, ignoring case, punctuation and a
+/// "ReqIF." prefix: ReqID / ForeignID, Text / Description, Name /
+/// ChapterName, Rationale, Priority, Status, Source and VerificationMethod
+/// fill [`RequirementData`]; anything else (or a value that doesn't parse)
+/// is kept under [`REQIF_ATTRIBUTES_META`] in the node's meta. A Created
+/// attribute, or else the object's LAST-CHANGE, is recorded as its
+/// [`ORIGINAL_CREATED_AT_META`].
+///
+/// SPEC-RELATIONs become edges of the kind their relation type name is
+/// closest to (see [`closest_edge_kind`]). Objects of our own export's
+/// "Model Element" type are not imported; they link to the existing node
+/// of that kind and name, if there is one.
+///
+/// Importing the same file twice creates nothing new: a requirement whose
+/// req_id is already in the project is matched rather than created, and an
+/// edge that already exists is not repeated.
+pub mod csv;
+pub mod xml;
+
+use crate::core::model::{
+    Edge, EdgeKind, Node, NodeData, NodeKind, RequirementData, RequirementPriority,
+    RequirementStatus, VerificationMethod, ORIGINAL_CREATED_AT_META,
+};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+use xml::Element;
+
+/// Node meta key: attribute values with no requirement field, by long name.
+pub const REQIF_ATTRIBUTES_META: &str = "reqif_attributes";
+/// Node and edge meta key: the IDENTIFIER the object or relation had.
+pub const REQIF_IDENTIFIER_META: &str = "reqif_identifier";
+
+/// What an import wrote, for the UI to report.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReqIfImportSummary {
+    pub nodes_created: usize,
+    pub edges_created: usize,
+    /// Objects that matched an existing node instead of creating one.
+    pub matched: usize,
+    /// Objects and relations left out, with the reason.
+    pub skipped: Vec<String>,
+}
+
+/// The nodes and edges to write.
+#[derive(Debug, Clone, Default)]
+pub struct ReqIfImport {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+    pub matched: usize,
+    pub skipped: Vec<String>,
+}
+
+impl ReqIfImport {
+    pub fn summary(&self) -> ReqIfImportSummary {
+        ReqIfImportSummary {
+            nodes_created: self.nodes.len(),
+            edges_created: self.edges.len(),
+            matched: self.matched,
+            skipped: self.skipped.clone(),
+        }
+    }
+}
+
+/// The requirement field an attribute's long name maps to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Field {
+    ReqId,
+    Text,
+    Name,
+    Rationale,
+    Priority,
+    Status,
+    Source,
+    VerificationMethod,
+    Allocations,
+    Classification,
+    CreatedAt,
+    Kind,
+}
+
+fn field_for(long_name: &str) -> Option<Field> {
+    let key: String = long_name
+        .trim()
+        .to_lowercase()
+        .trim_start_matches("reqif.")
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect();
+    Some(match key.as_str() {
+        "reqid" | "id" | "identifier" | "foreignid" | "requirementid" | "key" => Field::ReqId,
+        "text" | "description" | "requirementtext" | "objecttext" => Field::Text,
+        "name" | "title" | "chaptername" | "heading" => Field::Name,
+        "rationale" => Field::Rationale,
+        "priority" => Field::Priority,
+        "status" => Field::Status,
+        "source" => Field::Source,
+        "verificationmethod" | "verification" => Field::VerificationMethod,
+        "allocations" | "allocatedto" => Field::Allocations,
+        "classification" => Field::Classification,
+        "createdat" | "created" | "createdon" | "creationdate" | "originalcreatedat" => {
+            Field::CreatedAt
+        }
+        "kind" => Field::Kind,
+        _ => return None,
+    })
+}
+
+fn parse_priority(value: &str) -> Option<RequirementPriority> {
+    match value.trim().to_lowercase().as_str() {
+        "shall" | "must" | "mandatory" | "essential" | "high" => Some(RequirementPriority::Shall),
+        "should" | "desirable" | "medium" => Some(RequirementPriority::Should),
+        "may" | "optional" | "low" => Some(RequirementPriority::May),
+        _ => None,
+    }
+}
+
+fn parse_status(value: &str) -> Option<RequirementStatus> {
+    match value.trim().to_lowercase().as_str() {
+        "draft" | "new" | "proposed" | "open" | "in work" => Some(RequirementStatus::Draft),
+        "approved" | "accepted" | "released" | "baselined" => Some(RequirementStatus::Approved),
+        "obsolete" | "rejected" | "deleted" | "deprecated" => Some(RequirementStatus::Obsolete),
+        _ => None,
+    }
+}
+
+fn parse_verification_method(value: &str) -> Option<VerificationMethod> {
+    match value.trim().to_lowercase().as_str() {
+        "analysis" => Some(VerificationMethod::Analysis),
+        "test" => Some(VerificationMethod::Test),
+        "inspection" => Some(VerificationMethod::Inspection),
+        "demonstration" => Some(VerificationMethod::Demonstration),
+        _ => None,
+    }
+}
+
+/// An RFC 3339 time, an xs:dateTime without a zone (taken as UTC), or a
+/// bare date (midnight UTC).
+fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(t) = DateTime::parse_from_rfc3339(value) {
+        return Some(t.with_timezone(&Utc));
+    }
+    if let Ok(t) = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f") {
+        return Some(t.and_utc());
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|t| t.and_utc())
+}
+
+/// The edge kind a relation type name means: the kind itself when the name
+/// is one ("satisfies", "Verifies"), else the first kind whose stem it
+/// contains ("is satisfied by", "Derived From"), else «traces».
+pub fn closest_edge_kind(type_name: &str) -> EdgeKind {
+    let key = type_name.trim().to_lowercase().replace([' ', '-'], "_");
+    if let Ok(kind) = key.parse() {
+        return kind;
+    }
+    const STEMS: [(&str, EdgeKind); 10] = [
+        ("satisf", EdgeKind::Satisfies),
+        ("verif", EdgeKind::Verifies),
+        ("validat", EdgeKind::Verifies),
+        ("test", EdgeKind::Verifies),
+        ("refine", EdgeKind::Refines),
+        ("deriv", EdgeKind::Derives),
+        ("alloc", EdgeKind::Allocates),
+        ("realiz", EdgeKind::Realizes),
+        ("realis", EdgeKind::Realizes),
+        ("speciali", EdgeKind::Specializes),
+    ];
+    STEMS
+        .into_iter()
+        .find(|(stem, _)| key.contains(stem))
+        .map(|(_, kind)| kind)
+        .unwrap_or(EdgeKind::Traces)
+}
+
+/// Text of the first element inside `element`'s child `name`: the ref in
+/// `<TYPE><SPEC-OBJECT-TYPE-REF>…</SPEC-OBJECT-TYPE-REF></TYPE>`.
+fn reference(element: &Element, name: &str) -> Option<String> {
+    let target = element.child(name)?.elements().next()?.text();
+    (!target.is_empty()).then_some(target)
+}
+
+fn long_name(element: &Element) -> Option<String> {
+    element
+        .attribute("LONG-NAME")
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+        .map(str::to_string)
+}
+
+/// One SPEC-OBJECT's values, as (attribute long name, value) in file order.
+fn object_values(
+    object: &Element,
+    definitions: &HashMap<String, String>,
+    enum_labels: &HashMap<String, String>,
+) -> Vec<(String, String)> {
+    let Some(values) = object.child("VALUES") else {
+        return Vec::new();
+    };
+    values
+        .elements()
+        .filter_map(|value| {
+            let definition = reference(value, "DEFINITION")?;
+            let name = definitions.get(&definition).cloned().unwrap_or(definition);
+            let text = match value.local_name() {
+                "ATTRIBUTE-VALUE-XHTML" => value.child("THE-VALUE")?.text(),
+                "ATTRIBUTE-VALUE-ENUMERATION" => value
+                    .child("VALUES")?
+                    .elements()
+                    .map(|r| {
+                        let id = r.text();
+                        enum_labels.get(&id).cloned().unwrap_or(id)
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                _ => value.attribute("THE-VALUE")?.trim().to_string(),
+            };
+            Some((name, text))
+        })
+        .collect()
+}
+
+/// An object's values sorted into requirement fields; `extra` holds the
+/// attributes with no field, and values that didn't parse.
+#[derive(Default)]
+struct ObjectFields {
+    values: HashMap<Field, String>,
+    extra: serde_json::Map<String, Value>,
+}
+
+impl ObjectFields {
+    fn new(values: Vec<(String, String)>) -> Self {
+        let mut fields = ObjectFields::default();
+        for (name, value) in values {
+            if value.is_empty() {
+                continue;
+            }
+            match field_for(&name) {
+                // The first attribute for a field wins; a second one
+                // ("Description" next to "Text") is kept as is.
+                Some(field) if !fields.values.contains_key(&field) => {
+                    fields.values.insert(field, value);
+                }
+                _ => {
+                    fields.extra.insert(name, Value::from(value));
+                }
+            }
+        }
+        fields
+    }
+
+    fn take(&mut self, field: Field) -> Option<String> {
+        self.values.remove(&field)
+    }
+
+    /// The value for `field` parsed by `parse`; one that doesn't parse
+    /// moves to `extra` under `label`.
+    fn parse<T>(&mut self, field: Field, label: &str, parse: fn(&str) -> Option<T>) -> Option<T> {
+        let raw = self.take(field)?;
+        let parsed = parse(&raw);
+        if parsed.is_none() {
+            self.extra.insert(label.to_string(), Value::from(raw));
+        }
+        parsed
+    }
+}
+
+/// Requirements and edges for a ReqIF document. `nodes` and `edges` are
+/// the project's current model, used to match what already exists.
+pub fn import_reqif(
+    project_id: Uuid,
+    text: &str,
+    nodes: &[Node],
+    edges: &[Edge],
+) -> Result<ReqIfImport, String> {
+    let root = xml::parse(text)?;
+    if root.local_name() != "REQ-IF" {
+        return Err(format!(
+            "not a ReqIF file: the root element is <{}>",
+            root.name
+        ));
+    }
+    let content = root
+        .find("REQ-IF-CONTENT")
+        .ok_or("the ReqIF file has no REQ-IF-CONTENT")?;
+    let source_name = root
+        .find("THE-HEADER")
+        .and_then(|h| h.find("TITLE"))
+        .map(|t| t.text())
+        .filter(|t| !t.is_empty())
+        .unwrap_or_else(|| "ReqIF import".to_string());
+
+    // Enumeration value labels, attribute definition names and type names,
+    // all by IDENTIFIER.
+    let mut enum_labels: HashMap<String, String> = HashMap::new();
+    for value in content.find_all("ENUM-VALUE") {
+        let Some(id) = value.attribute("IDENTIFIER") else {
+            continue;
+        };
+        let label = long_name(value)
+            .or_else(|| {
+                value
+                    .find("EMBEDDED-VALUE")
+                    .and_then(|e| e.attribute("OTHER-CONTENT"))
+                    .map(str::to_string)
+            })
+            .unwrap_or_else(|| id.to_string());
+        enum_labels.insert(id.to_string(), label);
+    }
+    let mut definitions: HashMap<String, String> = HashMap::new();
+    let mut type_names: HashMap<String, String> = HashMap::new();
+    if let Some(spec_types) = content.child("SPEC-TYPES") {
+        for spec_type in spec_types.elements() {
+            if let (Some(id), Some(name)) =
+                (spec_type.attribute("IDENTIFIER"), long_name(spec_type))
+            {
+                type_names.insert(id.to_string(), name);
+            }
+            let Some(attributes) = spec_type.child("SPEC-ATTRIBUTES") else {
+                continue;
+            };
+            for definition in attributes.elements() {
+                if let (Some(id), Some(name)) =
+                    (definition.attribute("IDENTIFIER"), long_name(definition))
+                {
+                    definitions.insert(id.to_string(), name);
+                }
+            }
+        }
+    }
+
+    let now = Utc::now();
+    let import_meta = |identifier: &str| {
+        let mut meta: HashMap<String, Value> = HashMap::new();
+        meta.insert("source_doc".to_string(), Value::from(source_name.clone()));
+        meta.insert("actor".to_string(), Value::from("system"));
+        meta.insert("change_source".to_string(), Value::from("import"));
+        meta.insert(REQIF_IDENTIFIER_META.to_string(), Value::from(identifier));
+        meta
+    };
+    let existing_requirements: HashMap<String, Uuid> = nodes
+        .iter()
+        .filter(|n| !n.archived)
+        .filter_map(|n| match &n.data {
+            NodeData::Requirement(r) => Some((r.req_id.clone()?.trim().to_string(), n.id)),
+            _ => None,
+        })
+        .collect();
+
+    let mut import = ReqIfImport::default();
+    // SPEC-OBJECT identifier → the node it was imported as or matched to.
+    let mut resolved: HashMap<String, Uuid> = HashMap::new();
+    let mut imported_req_ids: HashMap<String, Uuid> = HashMap::new();
+    let objects = content
+        .child("SPEC-OBJECTS")
+        .map(|o| o.find_all("SPEC-OBJECT"))
+        .unwrap_or_default();
+    for object in objects {
+        let Some(identifier) = object.attribute("IDENTIFIER") else {
+            import
+                .skipped
+                .push("a SPEC-OBJECT has no IDENTIFIER".to_string());
+            continue;
+        };
+        let type_name = reference(object, "TYPE")
+            .and_then(|t| type_names.get(&t).cloned())
+            .unwrap_or_default();
+        let mut fields = ObjectFields::new(object_values(object, &definitions, &enum_labels));
+
+        // A relation end written by our own export.
+        let element_kind = fields
+            .values
+            .get(&Field::Kind)
+            .and_then(|k| k.parse::<NodeKind>().ok())
+            .filter(|k| *k != NodeKind::Requirement);
+        if let Some(kind) = element_kind.filter(|_| type_name.eq_ignore_ascii_case("Model Element"))
+        {
+            let name = long_name(object).unwrap_or_default();
+            match nodes
+                .iter()
+                .find(|n| !n.archived && n.kind == kind && n.name.eq_ignore_ascii_case(&name))
+            {
+                Some(node) => {
+                    resolved.insert(identifier.to_string(), node.id);
+                    import.matched += 1;
+                }
+                None => import
+                    .skipped
+                    .push(format!("{kind} '{name}' is not in the project")),
+            }
+            continue;
+        }
+
+        let req_id = fields.take(Field::ReqId);
+        if let Some(id) = req_id.as_deref().and_then(|r| {
+            existing_requirements
+                .get(r)
+                .or_else(|| imported_req_ids.get(r))
+        }) {
+            resolved.insert(identifier.to_string(), *id);
+            import.matched += 1;
+            continue;
+        }
+        let text = fields.take(Field::Text);
+        let name = fields
+            .take(Field::Name)
+            .or_else(|| long_name(object))
+            .or_else(|| req_id.clone())
+            .or_else(|| text.as_deref().map(|t| t.chars().take(80).collect()));
+        let Some(name) = name else {
+            import.skipped.push(format!(
+                "SPEC-OBJECT {identifier} has no name, identifier or text"
+            ));
+            continue;
+        };
+        let data = RequirementData {
+            req_id: req_id.clone(),
+            text: text.clone(),
+            rationale: fields.take(Field::Rationale),
+            priority: fields
+                .parse(Field::Priority, "Priority", parse_priority)
+                .unwrap_or_default(),
+            status: fields
+                .parse(Field::Status, "Status", parse_status)
+                .unwrap_or_default(),
+            source: fields.take(Field::Source).or(Some(source_name.clone())),
+            allocations: fields.take(Field::Allocations).map(|a| {
+                a.split(';')
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect()
+            }),
+            verification_method: fields.parse(
+                Field::VerificationMethod,
+                "VerificationMethod",
+                parse_verification_method,
+            ),
+            classification: fields.take(Field::Classification),
+        };
+        if let Some(kind) = fields.take(Field::Kind) {
+            fields.extra.insert("Kind".to_string(), Value::from(kind));
+        }
+        let created = fields
+            .parse(Field::CreatedAt, "Created", parse_timestamp)
+            .or_else(|| object.attribute("LAST-CHANGE").and_then(parse_timestamp));
+
+        let mut meta = import_meta(identifier);
+        if let Some(created) = created {
+            meta.insert(
+                ORIGINAL_CREATED_AT_META.to_string(),
+                Value::from(created.to_rfc3339()),
+            );
+        }
+        if !fields.extra.is_empty() {
+            meta.insert(
+                REQIF_ATTRIBUTES_META.to_string(),
+                Value::Object(fields.extra),
+            );
+        }
+        let node = Node {
+            id: Uuid::new_v4(),
+            project_id,
+            kind: NodeKind::Requirement,
+            name,
+            description: text.unwrap_or_default(),
+            data: NodeData::Requirement(data),
+            meta,
+            archived: false,
+            created_at: now,
+            modified_at: now,
+        };
+        resolved.insert(identifier.to_string(), node.id);
+        if let Some(req_id) = req_id {
+            imported_req_ids.insert(req_id, node.id);
+        }
+        import.nodes.push(node);
+    }
+
+    let mut linked: HashSet<(EdgeKind, Uuid, Uuid)> = edges
+        .iter()
+        .map(|e| (e.kind.clone(), e.source_id, e.target_id))
+        .collect();
+    let relations = content
+        .child("SPEC-RELATIONS")
+        .map(|r| r.find_all("SPEC-RELATION"))
+        .unwrap_or_default();
+    for relation in relations {
+        let identifier = relation.attribute("IDENTIFIER").unwrap_or_default();
+        let end = |name: &str| {
+            let id = reference(relation, name)?;
+            resolved.get(&id).copied()
+        };
+        let (Some(source_id), Some(target_id)) = (end("SOURCE"), end("TARGET")) else {
+            import.skipped.push(format!(
+                "SPEC-RELATION {identifier} links an object that was not imported"
+            ));
+            continue;
+        };
+        if source_id == target_id {
+            continue;
+        }
+        let type_name = reference(relation, "TYPE")
+            .map(|t| type_names.get(&t).cloned().unwrap_or(t))
+            .unwrap_or_default();
+        let kind = closest_edge_kind(&type_name);
+        if !linked.insert((kind.clone(), source_id, target_id)) {
+            continue;
+        }
+        import.edges.push(Edge {
+            id: Uuid::new_v4(),
+            project_id,
+            kind,
+            source_id,
+            target_id,
+            label: long_name(relation).unwrap_or_default(),
+            meta: import_meta(identifier),
+            created_at: now,
+            modified_at: now,
+        });
+    }
+
+    Ok(import)
+}
+
+#[cfg(test)]
+mod tests;
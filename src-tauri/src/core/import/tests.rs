@@ -0,0 +1,425 @@
+use super::*;
+use crate::core::export::to_reqif;
+use crate::test_support as fixtures;
+
+#[test]
+fn round_trips_our_own_export() {
+    let project = fixtures::project("Round trip");
+    let block = fixtures::block(project.id, "Power Distribution");
+    let mut req = fixtures::requirement(project.id, "REQ-001", "Supply 28 V.\nAt all times.");
+    let mut refined = fixtures::requirement(project.id, "REQ-002", "Supply 5 V & 3.3 V.");
+    if let NodeData::Requirement(r) = &mut req.data {
+        r.rationale = Some("Bus voltage".into());
+        r.status = RequirementStatus::Approved;
+    }
+    if let NodeData::Requirement(r) = &mut refined.data {
+        r.priority = RequirementPriority::May;
+    }
+    let edges = vec![
+        fixtures::edge(project.id, EdgeKind::Satisfies, block.id, req.id),
+        fixtures::edge(project.id, EdgeKind::Refines, refined.id, req.id),
+    ];
+    let exported = to_reqif(
+        &project,
+        &[block.clone(), req.clone(), refined.clone()],
+        &edges,
+    );
+
+    // Into a project that only has the block.
+    let target = fixtures::project("Target");
+    let mut existing = block.clone();
+    existing.project_id = target.id;
+    let import = import_reqif(target.id, &exported, &[existing.clone()], &[]).unwrap();
+    assert_eq!(import.nodes.len(), 2);
+    assert_eq!(import.matched, 1, "the block is matched by kind and name");
+    assert!(import.skipped.is_empty(), "{:?}", import.skipped);
+
+    let by_req_id = |id: &str| {
+        import
+            .nodes
+            .iter()
+            .find(
+                |n| matches!(&n.data, NodeData::Requirement(r) if r.req_id.as_deref() == Some(id)),
+            )
+            .unwrap()
+    };
+    let (new_req, new_refined) = (by_req_id("REQ-001"), by_req_id("REQ-002"));
+    let NodeData::Requirement(r) = &new_req.data else {
+        unreachable!()
+    };
+    assert_eq!(r.text.as_deref(), Some("Supply 28 V.\nAt all times."));
+    assert_eq!(r.rationale.as_deref(), Some("Bus voltage"));
+    assert_eq!(r.priority, RequirementPriority::Shall);
+    assert_eq!(r.status, RequirementStatus::Approved);
+    assert!(!new_req.meta.contains_key(REQIF_ATTRIBUTES_META));
+    let NodeData::Requirement(r) = &new_refined.data else {
+        unreachable!()
+    };
+    assert_eq!(r.text.as_deref(), Some("Supply 5 V & 3.3 V."));
+    assert_eq!(r.priority, RequirementPriority::May);
+
+    let mut links: Vec<(EdgeKind, Uuid, Uuid)> = import
+        .edges
+        .iter()
+        .map(|e| (e.kind.clone(), e.source_id, e.target_id))
+        .collect();
+    links.sort_by_key(|(kind, ..)| kind.to_string());
+    assert_eq!(
+        links,
+        vec![
+            (EdgeKind::Refines, new_refined.id, new_req.id),
+            (EdgeKind::Satisfies, existing.id, new_req.id),
+        ]
+    );
+
+    // A second import of the same file matches everything.
+    let mut after: Vec<Node> = vec![existing];
+    after.extend(import.nodes.iter().cloned());
+    let again = import_reqif(target.id, &exported, &after, &import.edges).unwrap();
+    assert!(again.nodes.is_empty() && again.edges.is_empty());
+    assert_eq!(again.matched, 3);
+}
+
+#[test]
+fn reads_foreign_tool_conventions() {
+    let file = r#"<?xml version="1.0" encoding="UTF-8"?>
+<REQ-IF xmlns="http://www.omg.org/spec/ReqIF/20110401/reqif.xsd"
+    xmlns:xhtml="http://www.w3.org/1999/xhtml">
+  <THE-HEADER><REQ-IF-HEADER IDENTIFIER="h"><TITLE>Vendor Spec</TITLE></REQ-IF-HEADER></THE-HEADER>
+  <CORE-CONTENT><REQ-IF-CONTENT>
+<DATATYPES>
+  <DATATYPE-DEFINITION-ENUMERATION IDENTIFIER="dt-prio" LONG-NAME="Prio">
+    <SPECIFIED-VALUES>
+      <ENUM-VALUE IDENTIFIER="ev-high" LONG-NAME="High"/>
+    </SPECIFIED-VALUES>
+  </DATATYPE-DEFINITION-ENUMERATION>
+</DATATYPES>
+<SPEC-TYPES>
+  <SPEC-OBJECT-TYPE IDENTIFIER="t-req" LONG-NAME="Requirement">
+    <SPEC-ATTRIBUTES>
+      <ATTRIBUTE-DEFINITION-STRING IDENTIFIER="a-id" LONG-NAME="ReqIF.ForeignID"/>
+      <ATTRIBUTE-DEFINITION-XHTML IDENTIFIER="a-text" LONG-NAME="ReqIF.Text"/>
+      <ATTRIBUTE-DEFINITION-ENUMERATION IDENTIFIER="a-prio" LONG-NAME="Priority"/>
+      <ATTRIBUTE-DEFINITION-STRING IDENTIFIER="a-owner" LONG-NAME="Owner"/>
+    </SPEC-ATTRIBUTES>
+  </SPEC-OBJECT-TYPE>
+  <SPEC-RELATION-TYPE IDENTIFIER="rt" LONG-NAME="is derived from"/>
+</SPEC-TYPES>
+<SPEC-OBJECTS>
+  <SPEC-OBJECT IDENTIFIER="o1">
+    <TYPE><SPEC-OBJECT-TYPE-REF>t-req</SPEC-OBJECT-TYPE-REF></TYPE>
+    <VALUES>
+      <ATTRIBUTE-VALUE-STRING THE-VALUE="SYS-1">
+        <DEFINITION><ATTRIBUTE-DEFINITION-STRING-REF>a-id</ATTRIBUTE-DEFINITION-STRING-REF></DEFINITION>
+      </ATTRIBUTE-VALUE-STRING>
+      <ATTRIBUTE-VALUE-XHTML>
+        <DEFINITION><ATTRIBUTE-DEFINITION-XHTML-REF>a-text</ATTRIBUTE-DEFINITION-XHTML-REF></DEFINITION>
+        <THE-VALUE><xhtml:div><xhtml:p>The pump shall</xhtml:p><xhtml:p>deliver &lt;5 bar.</xhtml:p></xhtml:div></THE-VALUE>
+      </ATTRIBUTE-VALUE-XHTML>
+      <ATTRIBUTE-VALUE-ENUMERATION>
+        <DEFINITION><ATTRIBUTE-DEFINITION-ENUMERATION-REF>a-prio</ATTRIBUTE-DEFINITION-ENUMERATION-REF></DEFINITION>
+        <VALUES><ENUM-VALUE-REF>ev-high</ENUM-VALUE-REF></VALUES>
+      </ATTRIBUTE-VALUE-ENUMERATION>
+      <ATTRIBUTE-VALUE-STRING THE-VALUE="J. Doe">
+        <DEFINITION><ATTRIBUTE-DEFINITION-STRING-REF>a-owner</ATTRIBUTE-DEFINITION-STRING-REF></DEFINITION>
+      </ATTRIBUTE-VALUE-STRING>
+    </VALUES>
+  </SPEC-OBJECT>
+  <SPEC-OBJECT IDENTIFIER="o2">
+    <TYPE><SPEC-OBJECT-TYPE-REF>t-req</SPEC-OBJECT-TYPE-REF></TYPE>
+    <VALUES>
+      <ATTRIBUTE-VALUE-STRING THE-VALUE="SYS-2">
+        <DEFINITION><ATTRIBUTE-DEFINITION-STRING-REF>a-id</ATTRIBUTE-DEFINITION-STRING-REF></DEFINITION>
+      </ATTRIBUTE-VALUE-STRING>
+    </VALUES>
+  </SPEC-OBJECT>
+</SPEC-OBJECTS>
+<SPEC-RELATIONS>
+  <SPEC-RELATION IDENTIFIER="r1">
+    <TYPE><SPEC-RELATION-TYPE-REF>rt</SPEC-RELATION-TYPE-REF></TYPE>
+    <SOURCE><SPEC-OBJECT-REF>o2</SPEC-OBJECT-REF></SOURCE>
+    <TARGET><SPEC-OBJECT-REF>o1</SPEC-OBJECT-REF></TARGET>
+  </SPEC-RELATION>
+  <SPEC-RELATION IDENTIFIER="r2">
+    <TYPE><SPEC-RELATION-TYPE-REF>rt</SPEC-RELATION-TYPE-REF></TYPE>
+    <SOURCE><SPEC-OBJECT-REF>o2</SPEC-OBJECT-REF></SOURCE>
+    <TARGET><SPEC-OBJECT-REF>missing</SPEC-OBJECT-REF></TARGET>
+  </SPEC-RELATION>
+</SPEC-RELATIONS>
+  </REQ-IF-CONTENT></CORE-CONTENT>
+</REQ-IF>"#;
+    let project_id = Uuid::new_v4();
+    let import = import_reqif(project_id, file, &[], &[]).unwrap();
+
+    assert_eq!(import.nodes.len(), 2);
+    let first = &import.nodes[0];
+    assert_eq!(first.name, "SYS-1");
+    let NodeData::Requirement(r) = &first.data else {
+        unreachable!()
+    };
+    assert_eq!(r.req_id.as_deref(), Some("SYS-1"));
+    assert_eq!(r.text.as_deref(), Some("The pump shall\ndeliver <5 bar."));
+    assert_eq!(r.priority, RequirementPriority::Shall);
+    assert_eq!(r.source.as_deref(), Some("Vendor Spec"));
+    assert_eq!(
+        first.meta[REQIF_ATTRIBUTES_META],
+        serde_json::json!({ "Owner": "J. Doe" })
+    );
+    assert_eq!(first.meta[REQIF_IDENTIFIER_META], "o1");
+
+    assert_eq!(import.edges.len(), 1);
+    assert_eq!(import.edges[0].kind, EdgeKind::Derives);
+    assert_eq!(import.edges[0].source_id, import.nodes[1].id);
+    assert_eq!(import.skipped.len(), 1);
+}
+
+#[test]
+fn rejects_what_is_not_reqif() {
+    assert!(import_reqif(Uuid::new_v4(), "<html></html>", &[], &[]).is_err());
+    assert!(import_reqif(Uuid::new_v4(), "<REQ-IF><CORE-CONTENT>", &[], &[]).is_err());
+}
+
+#[test]
+fn closest_edge_kinds() {
+    assert_eq!(closest_edge_kind("Satisfies"), EdgeKind::Satisfies);
+    assert_eq!(closest_edge_kind("is verified by"), EdgeKind::Verifies);
+    assert_eq!(
+        closest_edge_kind("binding connector"),
+        EdgeKind::BindingConnector
+    );
+    assert_eq!(closest_edge_kind("Link"), EdgeKind::Traces);
+}
+
+#[test]
+fn csv_upsert_updates_by_req_id_and_reports_bad_rows() {
+    use super::csv::{requirements_from_csv, CsvImportMode};
+
+    let project = fixtures::project("CSV");
+    let mut existing = fixtures::requirement(project.id, "REQ-001", "Supply 28 V.");
+    if let NodeData::Requirement(r) = &mut existing.data {
+        r.rationale = Some("Bus voltage".into());
+        r.source = Some("SOW".into());
+    }
+    let same = fixtures::requirement(project.id, "REQ-002", "Log faults.");
+    let csv = "ID,Name,Text,Priority,Status,Verification,Allocations\n\
+               REQ-001,Bus supply,\"Supply 28 V, nominal.\",May,Approved,Test,Power; Avionics\n\
+               REQ-002,REQ-002,Log faults.,Shall,Draft,,\n\
+               REQ-003,,Limit inrush.,Should,draft,analysis,\n\
+               ,No ID,Anything,,,,\n\
+               REQ-004,Bad,Anything,Urgent,,,\n";
+    let import = requirements_from_csv(
+        project.id,
+        csv,
+        CsvImportMode::UpsertByReqId,
+        &[existing.clone(), same],
+        1000,
+    )
+    .unwrap();
+
+    assert_eq!(import.updated.len(), 1);
+    assert_eq!(import.unchanged, 1);
+    let updated = &import.updated[0];
+    assert_eq!(updated.id, existing.id);
+    assert_eq!(updated.name, "Bus supply");
+    let NodeData::Requirement(r) = &updated.data else {
+        unreachable!()
+    };
+    assert_eq!(r.text.as_deref(), Some("Supply 28 V, nominal."));
+    assert_eq!(r.priority, RequirementPriority::May);
+    assert_eq!(r.status, RequirementStatus::Approved);
+    assert_eq!(r.verification_method, Some(VerificationMethod::Test));
+    assert_eq!(
+        r.allocations.as_deref(),
+        Some(&["Power".to_string(), "Avionics".to_string()][..])
+    );
+    // Not in the header, so left alone.
+    assert_eq!(r.rationale.as_deref(), Some("Bus voltage"));
+    assert_eq!(r.source.as_deref(), Some("SOW"));
+    assert_eq!(updated.meta["change_source"], "import");
+
+    assert_eq!(import.created.len(), 1);
+    assert_eq!(import.created[0].name, "REQ-003");
+
+    let errors: Vec<(usize, &str)> = import
+        .errors
+        .iter()
+        .map(|e| (e.row, e.reason.as_str()))
+        .collect();
+    assert_eq!(
+        errors,
+        [
+            (5, "no req_id to match on"),
+            (6, "'Urgent' is not a priority")
+        ]
+    );
+}
+
+#[test]
+fn csv_create_rejects_known_req_ids_and_overlong_text() {
+    use super::csv::{requirements_from_csv, CsvImportMode};
+
+    let project = fixtures::project("CSV");
+    let existing = fixtures::requirement(project.id, "REQ-001", "Supply 28 V.");
+    let csv = "req_id,text\nREQ-001,Again\nREQ-002,Short\nREQ-002,Repeat\nREQ-003,Far too long\n";
+    let import =
+        requirements_from_csv(project.id, csv, CsvImportMode::Create, &[existing], 10).unwrap();
+    assert_eq!(import.created.len(), 1);
+    assert_eq!(import.created[0].name, "REQ-002");
+    let rows: Vec<usize> = import.errors.iter().map(|e| e.row).collect();
+    assert_eq!(rows, [2, 4, 5]);
+
+    let missing = requirements_from_csv(
+        project.id,
+        "req_id,priority\n",
+        CsvImportMode::Create,
+        &[],
+        10,
+    );
+    assert!(missing.is_err());
+}
+
+#[test]
+fn csv_reads_back_the_requirements_csv_export() {
+    use super::csv::{requirements_from_csv, CsvImportMode};
+    use crate::core::export::to_requirements_csv;
+
+    let project = fixtures::project("CSV");
+    let mut req = fixtures::requirement(project.id, "REQ-001", "Line one,\n\"two\"");
+    if let NodeData::Requirement(r) = &mut req.data {
+        r.rationale = Some("Because".into());
+        r.verification_method = Some(VerificationMethod::Inspection);
+        r.allocations = Some(vec!["FPGA".into(), "MCU".into()]);
+        r.classification = Some("interface".into());
+    }
+    let csv = to_requirements_csv(&project, &[req.clone()]);
+    let import = requirements_from_csv(
+        project.id,
+        &csv,
+        CsvImportMode::UpsertByReqId,
+        &[req.clone()],
+        1000,
+    )
+    .unwrap();
+    assert!(import.errors.is_empty(), "{:?}", import.errors);
+    assert!(import.created.is_empty() && import.updated.is_empty());
+    assert_eq!(import.unchanged, 1);
+
+    let mut unclassified = req;
+    if let NodeData::Requirement(r) = &mut unclassified.data {
+        r.classification = None;
+    }
+    let import = requirements_from_csv(
+        project.id,
+        &csv,
+        CsvImportMode::UpsertByReqId,
+        &[unclassified],
+        1000,
+    )
+    .unwrap();
+    let NodeData::Requirement(r) = &import.updated[0].data else {
+        panic!("not a requirement");
+    };
+    assert_eq!(r.classification.as_deref(), Some("interface"));
+}
+
+#[test]
+fn exports_keep_the_original_creation_time_through_a_reimport() {
+    use super::csv::{requirements_from_csv, CsvImportMode};
+    use crate::core::export::to_requirements_csv;
+    use crate::core::model::original_created_at;
+
+    let project = fixtures::project("Dates");
+    let authored = fixtures::requirement(project.id, "REQ-001", "Written here.");
+    let mut imported = fixtures::requirement(project.id, "REQ-002", "Read from DOORS.");
+    imported.meta.insert(
+        ORIGINAL_CREATED_AT_META.into(),
+        Value::from("2019-03-01T08:00:00+00:00"),
+    );
+    let nodes = [authored.clone(), imported.clone()];
+    let source_time = Some("2019-03-01T08:00:00Z".parse().unwrap());
+
+    let reqif = import_reqif(Uuid::new_v4(), &to_reqif(&project, &nodes, &[]), &[], &[]).unwrap();
+    let by_name = |nodes: &[Node], name: &str| nodes.iter().find(|n| n.name == name).cloned();
+    let copy = by_name(&reqif.nodes, "REQ-001").unwrap();
+    assert_eq!(original_created_at(&copy.meta), Some(authored.created_at));
+    let copy = by_name(&reqif.nodes, "REQ-002").unwrap();
+    assert_eq!(original_created_at(&copy.meta), source_time);
+
+    let csv = to_requirements_csv(&project, &nodes);
+    let target = fixtures::project("Target");
+    let import = requirements_from_csv(target.id, &csv, CsvImportMode::Create, &[], 1000).unwrap();
+    assert!(import.errors.is_empty(), "{:?}", import.errors);
+    let copy = by_name(&import.created, "REQ-001").unwrap();
+    assert!(!copy.meta.contains_key(ORIGINAL_CREATED_AT_META));
+    let copy = by_name(&import.created, "REQ-002").unwrap();
+    assert_eq!(original_created_at(&copy.meta), source_time);
+}
+
+#[test]
+fn reqif_records_last_change_as_the_original_creation_time() {
+    let file = r#"<REQ-IF>
+  <THE-HEADER><REQ-IF-HEADER IDENTIFIER="h"><TITLE>Dated</TITLE></REQ-IF-HEADER></THE-HEADER>
+  <CORE-CONTENT><REQ-IF-CONTENT>
+<SPEC-TYPES>
+  <SPEC-OBJECT-TYPE IDENTIFIER="t" LONG-NAME="Requirement">
+    <SPEC-ATTRIBUTES>
+      <ATTRIBUTE-DEFINITION-STRING IDENTIFIER="a-id" LONG-NAME="ReqIF.ForeignID"/>
+    </SPEC-ATTRIBUTES>
+  </SPEC-OBJECT-TYPE>
+</SPEC-TYPES>
+<SPEC-OBJECTS>
+  <SPEC-OBJECT IDENTIFIER="o1" LAST-CHANGE="2021-03-04T05:06:07+01:00">
+    <TYPE><SPEC-OBJECT-TYPE-REF>t</SPEC-OBJECT-TYPE-REF></TYPE>
+    <VALUES>
+      <ATTRIBUTE-VALUE-STRING THE-VALUE="SYS-1">
+        <DEFINITION><ATTRIBUTE-DEFINITION-STRING-REF>a-id</ATTRIBUTE-DEFINITION-STRING-REF></DEFINITION>
+      </ATTRIBUTE-VALUE-STRING>
+    </VALUES>
+  </SPEC-OBJECT>
+  <SPEC-OBJECT IDENTIFIER="o2" LAST-CHANGE="yesterday">
+    <TYPE><SPEC-OBJECT-TYPE-REF>t</SPEC-OBJECT-TYPE-REF></TYPE>
+    <VALUES>
+      <ATTRIBUTE-VALUE-STRING THE-VALUE="SYS-2">
+        <DEFINITION><ATTRIBUTE-DEFINITION-STRING-REF>a-id</ATTRIBUTE-DEFINITION-STRING-REF></DEFINITION>
+      </ATTRIBUTE-VALUE-STRING>
+    </VALUES>
+  </SPEC-OBJECT>
+</SPEC-OBJECTS>
+  </REQ-IF-CONTENT></CORE-CONTENT>
+</REQ-IF>"#;
+    let import = import_reqif(Uuid::new_v4(), file, &[], &[]).unwrap();
+
+    assert_eq!(import.nodes.len(), 2);
+    assert_eq!(
+        crate::core::model::original_created_at(&import.nodes[0].meta),
+        Some("2021-03-04T04:06:07Z".parse().unwrap())
+    );
+    assert!(!import.nodes[1].meta.contains_key(ORIGINAL_CREATED_AT_META));
+}
+
+#[test]
+fn csv_records_a_created_column_as_the_original_creation_time() {
+    use super::csv::{requirements_from_csv, CsvImportMode};
+
+    let project = fixtures::project("CSV");
+    let csv = "req_id,text,created\n\
+               REQ-001,Dated,2020-07-01\n\
+               REQ-002,Undated,\n\
+               REQ-003,Bad date,July\n";
+    let import = requirements_from_csv(project.id, csv, CsvImportMode::Create, &[], 1000).unwrap();
+
+    assert_eq!(import.created.len(), 2);
+    assert_eq!(
+        crate::core::model::original_created_at(&import.created[0].meta),
+        Some("2020-07-01T00:00:00Z".parse().unwrap())
+    );
+    assert!(!import.created[1]
+        .meta
+        .contains_key(ORIGINAL_CREATED_AT_META));
+    let errors: Vec<(usize, &str)> = import
+        .errors
+        .iter()
+        .map(|e| (e.row, e.reason.as_str()))
+        .collect();
+    assert_eq!(errors, [(4, "'July' is not a date")]);
+}
@@ -0,0 +1,318 @@
+/// A small non-validating XML reader: enough of XML 1.0 for ReqIF files
+/// (elements, attributes, text, CDATA, comments, processing instructions
+/// and a skipped DOCTYPE), read into a tree.
+///
+/// Names keep their prefix; [`Element::local_name`] drops it, since ReqIF
+/// files differ in how they bind the ReqIF and XHTML namespaces.
+#[derive(Debug, Clone, Default)]
+pub struct Element {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Content>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Content {
+    Element(Element),
+    Text(String),
+}
+
+impl Element {
+    pub fn local_name(&self) -> &str {
+        local(&self.name)
+    }
+
+    /// The attribute named `name`, compared without prefixes.
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| local(key) == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|c| match c {
+            Content::Element(e) => Some(e),
+            Content::Text(_) => None,
+        })
+    }
+
+    /// The first child element with local name `name`.
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.elements().find(|e| e.local_name() == name)
+    }
+
+    /// Every element below this one with local name `name`, in document
+    /// order. Does not look inside a match.
+    pub fn find_all<'a>(&'a self, name: &str) -> Vec<&'a Element> {
+        let mut found = Vec::new();
+        let mut stack: Vec<&Element> = self.elements().collect();
+        stack.reverse();
+        while let Some(e) = stack.pop() {
+            if e.local_name() == name {
+                found.push(e);
+            } else {
+                stack.extend(e.elements().collect::<Vec<_>>().into_iter().rev());
+            }
+        }
+        found
+    }
+
+    /// The first element below this one with local name `name`.
+    pub fn find(&self, name: &str) -> Option<&Element> {
+        self.find_all(name).into_iter().next()
+    }
+
+    /// The concatenated text of this element and everything below it.
+    /// Block-level XHTML elements (`p`, `div`, `li`, …) and `br` start a new
+    /// line; runs of whitespace inside a line collapse to one space.
+    pub fn text(&self) -> String {
+        let mut raw = String::new();
+        collect_text(self, &mut raw);
+        raw.lines()
+            .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+const BLOCK_ELEMENTS: [&str; 12] = [
+    "p", "div", "br", "li", "ul", "ol", "tr", "table", "h1", "h2", "h3", "pre",
+];
+
+fn collect_text(element: &Element, out: &mut String) {
+    for child in &element.children {
+        match child {
+            Content::Text(t) => out.push_str(&t.replace('\n', " ")),
+            Content::Element(e) => {
+                let block = BLOCK_ELEMENTS.contains(&e.local_name());
+                if block {
+                    out.push('\n');
+                }
+                collect_text(e, out);
+                if block {
+                    out.push('\n');
+                }
+            }
+        }
+    }
+}
+
+fn local(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+/// Parse `text` into its root element.
+pub fn parse(text: &str) -> Result<Element, String> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut reader = Reader { src: text, pos: 0 };
+    let mut stack: Vec<Element> = Vec::new();
+    let mut root: Option<Element> = None;
+
+    while reader.pos < reader.src.len() {
+        let rest = reader.rest();
+        if rest.starts_with("<?") {
+            reader.skip_past("?>")?;
+        } else if rest.starts_with("<!--") {
+            reader.skip_past("-->")?;
+        } else if rest.starts_with("<![CDATA[") {
+            reader.pos += "<![CDATA[".len();
+            let data = reader.take_until("]]>")?;
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(Content::Text(data.to_string())),
+                None => return Err(reader.error("CDATA outside the root element")),
+            }
+        } else if rest.starts_with("<!") {
+            reader.skip_doctype()?;
+        } else if rest.starts_with("</") {
+            reader.pos += 2;
+            let name = reader.take_until(">")?.trim();
+            let element = stack
+                .pop()
+                .ok_or_else(|| reader.error(&format!("unexpected </{name}>")))?;
+            if element.name != name {
+                return Err(reader.error(&format!("</{name}> does not close <{}>", element.name)));
+            }
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(Content::Element(element)),
+                None => root = Some(element),
+            }
+        } else if rest.starts_with('<') {
+            if root.is_some() {
+                return Err(reader.error("content after the root element"));
+            }
+            reader.pos += 1;
+            let (element, empty) = reader.start_tag()?;
+            if !empty {
+                stack.push(element);
+            } else {
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(Content::Element(element)),
+                    None => root = Some(element),
+                }
+            }
+        } else {
+            let end = rest.find('<').unwrap_or(rest.len());
+            let raw = &rest[..end];
+            reader.pos += end;
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(Content::Text(unescape(raw)?)),
+                None if raw.trim().is_empty() => {}
+                None => return Err(reader.error("text outside the root element")),
+            }
+        }
+    }
+
+    if let Some(open) = stack.last() {
+        return Err(format!("<{}> is never closed", open.name));
+    }
+    root.ok_or_else(|| "no root element".to_string())
+}
+
+struct Reader<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    /// "… at line N".
+    fn error(&self, message: &str) -> String {
+        let line = self.src[..self.pos].matches('\n').count() + 1;
+        format!("{message} at line {line}")
+    }
+
+    fn take_until(&mut self, end: &str) -> Result<&'a str, String> {
+        let rest = self.rest();
+        let i = rest
+            .find(end)
+            .ok_or_else(|| self.error(&format!("missing '{end}'")))?;
+        self.pos += i + end.len();
+        Ok(&rest[..i])
+    }
+
+    fn skip_past(&mut self, end: &str) -> Result<(), String> {
+        self.take_until(end).map(|_| ())
+    }
+
+    /// Skip `<!DOCTYPE …>`, including an internal subset in brackets.
+    fn skip_doctype(&mut self) -> Result<(), String> {
+        let mut depth = 0usize;
+        for (i, c) in self.rest().char_indices() {
+            match c {
+                '[' => depth += 1,
+                ']' => depth = depth.saturating_sub(1),
+                '>' if depth == 0 => {
+                    self.pos += i + 1;
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+        Err(self.error("unterminated declaration"))
+    }
+
+    /// Read a start tag after its '<'; the flag is set for `<x/>`.
+    fn start_tag(&mut self) -> Result<(Element, bool), String> {
+        let name = self.name()?;
+        let mut element = Element {
+            name,
+            ..Default::default()
+        };
+        loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.starts_with("/>") {
+                self.pos += 2;
+                return Ok((element, true));
+            }
+            if rest.starts_with('>') {
+                self.pos += 1;
+                return Ok((element, false));
+            }
+            if rest.is_empty() {
+                return Err(self.error(&format!("unterminated <{}>", element.name)));
+            }
+            let key = self.name()?;
+            self.skip_whitespace();
+            if !self.rest().starts_with('=') {
+                return Err(self.error(&format!("attribute {key} has no value")));
+            }
+            self.pos += 1;
+            self.skip_whitespace();
+            let quote = match self.rest().chars().next() {
+                Some(q @ ('"' | '\'')) => q,
+                _ => return Err(self.error(&format!("attribute {key} is not quoted"))),
+            };
+            self.pos += 1;
+            let raw = self.take_until(&quote.to_string())?;
+            // Attribute-value normalization: literal whitespace becomes a
+            // space, character references survive.
+            let value = unescape(&raw.replace(['\t', '\r', '\n'], " "))?;
+            element.attributes.push((key, value));
+        }
+    }
+
+    fn name(&mut self) -> Result<String, String> {
+        let rest = self.rest();
+        let end = rest
+            .find(|c: char| c.is_whitespace() || matches!(c, '>' | '/' | '='))
+            .unwrap_or(rest.len());
+        if end == 0 {
+            return Err(self.error("expected a name"));
+        }
+        self.pos += end;
+        Ok(rest[..end].to_string())
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+}
+
+/// Replace the predefined entities and character references in `raw`.
+pub fn unescape(raw: &str) -> Result<String, String> {
+    if !raw.contains('&') {
+        return Ok(raw.to_string());
+    }
+    let mut out = String::with_capacity(raw.len());
+    let mut rest = raw;
+    while let Some(i) = rest.find('&') {
+        out.push_str(&rest[..i]);
+        let after = &rest[i + 1..];
+        let end = after
+            .find(';')
+            .ok_or_else(|| format!("unterminated entity in '{raw}'"))?;
+        let entity = &after[..end];
+        let c = match entity {
+            "lt" => '<',
+            "gt" => '>',
+            "amp" => '&',
+            "quot" => '"',
+            "apos" => '\'',
+            _ => {
+                let code = if let Some(hex) = entity
+                    .strip_prefix("#x")
+                    .or_else(|| entity.strip_prefix("#X"))
+                {
+                    u32::from_str_radix(hex, 16).ok()
+                } else if let Some(dec) = entity.strip_prefix('#') {
+                    dec.parse().ok()
+                } else {
+                    None
+                };
+                code.and_then(char::from_u32)
+                    .ok_or_else(|| format!("unknown entity '&{entity};'"))?
+            }
+        };
+        out.push(c);
+        rest = &after[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
@@ -0,0 +1,212 @@
+/// Block definition inheritance along «specializes» edges (source = the
+/// specific block, target = the general one). A block's effective
+/// definition is its own ports (the Port nodes it «composes») and block
+/// properties plus those of every ancestor, where a local item — or one
+/// from a nearer ancestor — redefines an inherited item of the same name
+/// (compared case-insensitively). Pure functions; the commands layer does
+/// the store lookups.
+///
+/// Ancestors are walked breadth-first, nearest first, and each block is
+/// visited once, so specialization cycles terminate. A redefined port may
+/// narrow an inherited InOut port to In or Out; any other change of
+/// direction is a conflict.
+use crate::core::model::{BlockProperty, Edge, EdgeKind, Node, NodeData, NodeKind, PortDirection};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EffectivePort {
+    pub port_id: Uuid,
+    pub name: String,
+    pub direction: PortDirection,
+    pub type_name: Option<String>,
+    pub multiplicity: Option<String>,
+    /// The ancestor block the port is inherited from; `None` when local.
+    pub inherited_from: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EffectiveProperty {
+    pub property: BlockProperty,
+    /// The ancestor block the property is inherited from; `None` when local.
+    pub inherited_from: Option<Uuid>,
+}
+
+/// A redefining port whose direction is incompatible with the port it
+/// redefines.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedefinitionConflict {
+    /// The block whose definition holds both ports.
+    pub block_id: Uuid,
+    pub port_name: String,
+    pub port_id: Uuid,
+    pub direction: PortDirection,
+    pub redefined_port_id: Uuid,
+    pub redefined_direction: PortDirection,
+    /// The ancestor owning the redefined port.
+    pub ancestor_id: Uuid,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EffectiveBlockDefinition {
+    pub block_id: Uuid,
+    pub block_name: String,
+    /// Ancestor block ids, nearest first.
+    pub ancestors: Vec<Uuid>,
+    /// Sorted by name.
+    pub ports: Vec<EffectivePort>,
+    /// Sorted by name.
+    pub properties: Vec<EffectiveProperty>,
+    pub conflicts: Vec<RedefinitionConflict>,
+}
+
+/// `block_id`'s ancestors along «specializes» edges, nearest first.
+pub fn ancestors(block_id: Uuid, nodes: &[Node], edges: &[Edge]) -> Vec<Uuid> {
+    let blocks: HashSet<Uuid> = nodes
+        .iter()
+        .filter(|n| n.kind == NodeKind::Block && !n.archived)
+        .map(|n| n.id)
+        .collect();
+    let mut parents: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+    for edge in edges.iter().filter(|e| e.kind == EdgeKind::Specializes) {
+        if blocks.contains(&edge.target_id) {
+            parents
+                .entry(edge.source_id)
+                .or_default()
+                .push(edge.target_id);
+        }
+    }
+    for list in parents.values_mut() {
+        list.sort();
+    }
+
+    let mut seen = HashSet::from([block_id]);
+    let mut queue = VecDeque::from([block_id]);
+    let mut order = Vec::new();
+    while let Some(id) = queue.pop_front() {
+        for &parent in parents.get(&id).into_iter().flatten() {
+            if seen.insert(parent) {
+                order.push(parent);
+                queue.push_back(parent);
+            }
+        }
+    }
+    order
+}
+
+/// The Port nodes `block_id` «composes».
+fn own_ports<'a>(block_id: Uuid, by_id: &HashMap<Uuid, &'a Node>, edges: &[Edge]) -> Vec<&'a Node> {
+    let mut ports: Vec<&Node> = edges
+        .iter()
+        .filter(|e| e.kind == EdgeKind::Composes && e.source_id == block_id)
+        .filter_map(|e| by_id.get(&e.target_id).copied())
+        .filter(|n| n.kind == NodeKind::Port && !n.archived)
+        .collect();
+    ports.sort_by_key(|p| (p.name.to_lowercase(), p.id));
+    ports.dedup_by_key(|p| p.id);
+    ports
+}
+
+fn port_direction(port: &Node) -> PortDirection {
+    match &port.data {
+        NodeData::Port(p) => p.direction.clone(),
+        _ => PortDirection::InOut,
+    }
+}
+
+/// Whether a port of direction `redefining` may redefine one of
+/// `redefined`.
+fn compatible(redefining: &PortDirection, redefined: &PortDirection) -> bool {
+    redefining == redefined || *redefined == PortDirection::InOut
+}
+
+/// The flattened definition of `block_id`. `edges` needs the «specializes»
+/// edges among blocks and the «composes» edges from them; `properties` the
+/// block properties of the block and its ancestors.
+pub fn effective_definition(
+    block_id: Uuid,
+    nodes: &[Node],
+    edges: &[Edge],
+    properties: &[BlockProperty],
+) -> Result<EffectiveBlockDefinition, String> {
+    let by_id: HashMap<Uuid, &Node> = nodes.iter().map(|n| (n.id, n)).collect();
+    let block = by_id
+        .get(&block_id)
+        .ok_or_else(|| format!("block {block_id} not found"))?;
+    if block.kind != NodeKind::Block {
+        return Err(format!("'{}' is a {}, not a block", block.name, block.kind));
+    }
+    let ancestors = ancestors(block_id, nodes, edges);
+
+    let mut ports: Vec<EffectivePort> = Vec::new();
+    let mut port_owner: HashMap<String, (Uuid, Uuid, PortDirection)> = HashMap::new();
+    let mut conflicts = Vec::new();
+    let mut props: Vec<EffectiveProperty> = Vec::new();
+    let mut prop_names: HashSet<String> = HashSet::new();
+    for owner in std::iter::once(block_id).chain(ancestors.iter().copied()) {
+        let inherited_from = (owner != block_id).then_some(owner);
+        for port in own_ports(owner, &by_id, edges) {
+            let key = port.name.trim().to_lowercase();
+            let direction = port_direction(port);
+            match port_owner.get(&key) {
+                // Two ports of one block sharing a name; not a redefinition.
+                Some((_, defined_by, _)) if *defined_by == owner => {}
+                Some((redefining_id, _, redefining_dir)) => {
+                    if !compatible(redefining_dir, &direction) {
+                        conflicts.push(RedefinitionConflict {
+                            block_id,
+                            port_name: port.name.clone(),
+                            port_id: *redefining_id,
+                            direction: redefining_dir.clone(),
+                            redefined_port_id: port.id,
+                            redefined_direction: direction,
+                            ancestor_id: owner,
+                        });
+                    }
+                    continue;
+                }
+                None => {
+                    port_owner.insert(key, (port.id, owner, direction.clone()));
+                }
+            }
+            let data = match &port.data {
+                NodeData::Port(p) => p.clone(),
+                _ => Default::default(),
+            };
+            ports.push(EffectivePort {
+                port_id: port.id,
+                name: port.name.clone(),
+                direction,
+                type_name: data.type_name,
+                multiplicity: data.multiplicity,
+                inherited_from,
+            });
+        }
+
+        let mut own_props: Vec<&BlockProperty> = properties
+            .iter()
+            .filter(|p| p.block_node_id == owner)
+            .collect();
+        own_props.sort_by_key(|p| (p.name.to_lowercase(), p.id));
+        for prop in own_props {
+            if prop_names.insert(prop.name.trim().to_lowercase()) {
+                props.push(EffectiveProperty {
+                    property: prop.clone(),
+                    inherited_from,
+                });
+            }
+        }
+    }
+    ports.sort_by_key(|p| (p.name.to_lowercase(), p.port_id));
+    props.sort_by_key(|p| (p.property.name.to_lowercase(), p.property.id));
+
+    Ok(EffectiveBlockDefinition {
+        block_id,
+        block_name: block.name.clone(),
+        ancestors,
+        ports,
+        properties: props,
+        conflicts,
+    })
+}
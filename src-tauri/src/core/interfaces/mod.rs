@@ -0,0 +1,121 @@
+/// Block interface summaries for the IBD inspector: a block's ports and
+/// what each one connects to. Pure functions over nodes/edges; the commands
+/// layer does the store lookups.
+///
+/// A block owns the Port nodes it «composes» and inherits those of the
+/// blocks it «specializes»; see `core::inheritance`. A port's connections
+/// are the «connects» edges touching it in either direction; the far end is
+/// usually another port, whose owning block is found the same way, but may
+/// also be a block wired directly.
+use crate::core::model::{Edge, EdgeKind, Node, NodeKind, PortDirection};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortConnection {
+    pub edge_id: Uuid,
+    /// The edge label, which names the interface or item flowing.
+    pub label: String,
+    /// Far-end port, when the edge ends on a port.
+    pub far_port_id: Option<Uuid>,
+    pub far_port_name: Option<String>,
+    /// Block owning the far-end port, or the far end itself if it's a block.
+    pub far_block_id: Option<Uuid>,
+    pub far_block_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortInterface {
+    pub port_id: Uuid,
+    pub name: String,
+    pub direction: PortDirection,
+    pub type_name: Option<String>,
+    pub multiplicity: Option<String>,
+    pub connections: Vec<PortConnection>,
+    /// No «connects» edge touches the port.
+    pub unconnected: bool,
+    /// The ancestor block the port is inherited from; `None` when local.
+    #[serde(default)]
+    pub inherited_from: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockInterfaceSummary {
+    pub block_id: Uuid,
+    pub block_name: String,
+    pub ports: Vec<PortInterface>,
+    pub unconnected_count: usize,
+}
+
+/// Summarise `block_id`'s ports, inherited ones included. `edges` needs the
+/// «specializes» edges among blocks, the «composes» edges from the block,
+/// its ancestors and far-end blocks, and the «connects» edges of its ports;
+/// other kinds are ignored.
+pub fn summarize(
+    block_id: Uuid,
+    nodes: &[Node],
+    edges: &[Edge],
+) -> Result<BlockInterfaceSummary, String> {
+    let by_id: HashMap<Uuid, &Node> = nodes.iter().map(|n| (n.id, n)).collect();
+    let definition = crate::core::inheritance::effective_definition(block_id, nodes, edges, &[])?;
+
+    let owner_of = |port_id: Uuid| {
+        edges
+            .iter()
+            .filter(|e| e.kind == EdgeKind::Composes && e.target_id == port_id)
+            .filter_map(|e| by_id.get(&e.source_id))
+            .find(|n| n.kind == NodeKind::Block)
+    };
+
+    let ports: Vec<PortInterface> = definition
+        .ports
+        .into_iter()
+        .map(|port| {
+            let connections: Vec<PortConnection> = edges
+                .iter()
+                .filter(|e| e.kind == EdgeKind::Connects)
+                .filter_map(|e| {
+                    let far = if e.source_id == port.port_id {
+                        e.target_id
+                    } else if e.target_id == port.port_id {
+                        e.source_id
+                    } else {
+                        return None;
+                    };
+                    let far = by_id.get(&far);
+                    let far_port = far.filter(|n| n.kind == NodeKind::Port);
+                    let far_block = match far_port {
+                        Some(p) => owner_of(p.id),
+                        None => far.filter(|n| n.kind == NodeKind::Block),
+                    };
+                    Some(PortConnection {
+                        edge_id: e.id,
+                        label: e.label.clone(),
+                        far_port_id: far_port.map(|p| p.id),
+                        far_port_name: far_port.map(|p| p.name.clone()),
+                        far_block_id: far_block.map(|b| b.id),
+                        far_block_name: far_block.map(|b| b.name.clone()),
+                    })
+                })
+                .collect();
+            PortInterface {
+                port_id: port.port_id,
+                name: port.name,
+                direction: port.direction,
+                type_name: port.type_name,
+                multiplicity: port.multiplicity,
+                unconnected: connections.is_empty(),
+                connections,
+                inherited_from: port.inherited_from,
+            }
+        })
+        .collect();
+
+    Ok(BlockInterfaceSummary {
+        block_id,
+        block_name: definition.block_name,
+        unconnected_count: ports.iter().filter(|p| p.unconnected).count(),
+        ports,
+    })
+}
@@ -0,0 +1,100 @@
+/// Model coverage: how far the requirements are satisfied, verified and
+/// allocated, and how many blocks and test cases trace to nothing.
+///
+/// Counts the node and edge lists in memory, so the command reads them
+/// with one query each. Requirements marked Obsolete are left out of every
+/// denominator, and archived nodes are left out entirely; an edge from an
+/// archived node no longer counts. The store's `coverage_metrics` and
+/// `core::baseline::coverage_metrics` count the same way.
+use crate::core::model::{Edge, EdgeKind, Node, NodeData, NodeKind, RequirementStatus};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+/// Percentages are 0–100 of `total_requirements`, and 0 when there are
+/// none.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ModelCoverage {
+    /// Live requirements not marked Obsolete.
+    pub total_requirements: usize,
+    /// With at least one «satisfies» edge targeting them.
+    pub satisfied: usize,
+    pub satisfied_pct: f64,
+    /// With at least one «verifies» edge targeting them.
+    pub verified: usize,
+    pub verified_pct: f64,
+    /// With at least one non-blank allocation.
+    pub allocated: usize,
+    pub allocated_pct: f64,
+    /// Blocks that satisfy none of the counted requirements.
+    pub blocks_satisfying_nothing: usize,
+    /// Test cases that verify none of the counted requirements.
+    pub test_cases_verifying_nothing: usize,
+}
+
+pub fn model_coverage(nodes: &[Node], edges: &[Edge]) -> ModelCoverage {
+    let live: HashSet<Uuid> = nodes.iter().filter(|n| !n.archived).map(|n| n.id).collect();
+    let requirements: Vec<&Node> = nodes
+        .iter()
+        .filter(|n| !n.archived)
+        .filter(|n| {
+            matches!(&n.data, NodeData::Requirement(r) if r.status != RequirementStatus::Obsolete)
+        })
+        .collect();
+    let counted: HashSet<Uuid> = requirements.iter().map(|n| n.id).collect();
+
+    // (source, target) of each live edge of `kind` into a counted requirement.
+    let links = |kind: EdgeKind| -> Vec<(Uuid, Uuid)> {
+        edges
+            .iter()
+            .filter(|e| e.kind == kind && live.contains(&e.source_id))
+            .filter(|e| counted.contains(&e.target_id))
+            .map(|e| (e.source_id, e.target_id))
+            .collect()
+    };
+    let satisfies = links(EdgeKind::Satisfies);
+    let verifies = links(EdgeKind::Verifies);
+    let satisfied: HashSet<Uuid> = satisfies.iter().map(|(_, req)| *req).collect();
+    let verified: HashSet<Uuid> = verifies.iter().map(|(_, req)| *req).collect();
+    let satisfying: HashSet<Uuid> = satisfies.iter().map(|(block, _)| *block).collect();
+    let verifying: HashSet<Uuid> = verifies.iter().map(|(test, _)| *test).collect();
+
+    let allocated = requirements
+        .iter()
+        .filter(|n| match &n.data {
+            NodeData::Requirement(r) => {
+                r.allocations.iter().flatten().any(|a| !a.trim().is_empty())
+            }
+            _ => false,
+        })
+        .count();
+    let unlinked = |kind: NodeKind, linked: &HashSet<Uuid>| {
+        nodes
+            .iter()
+            .filter(|n| n.kind == kind && !n.archived && !linked.contains(&n.id))
+            .count()
+    };
+
+    let total = requirements.len();
+    let pct = |count: usize| {
+        if total == 0 {
+            0.0
+        } else {
+            count as f64 * 100.0 / total as f64
+        }
+    };
+    ModelCoverage {
+        total_requirements: total,
+        satisfied: satisfied.len(),
+        satisfied_pct: pct(satisfied.len()),
+        verified: verified.len(),
+        verified_pct: pct(verified.len()),
+        allocated,
+        allocated_pct: pct(allocated),
+        blocks_satisfying_nothing: unlinked(NodeKind::Block, &satisfying),
+        test_cases_verifying_nothing: unlinked(NodeKind::TestCase, &verifying),
+    }
+}
+
+#[cfg(test)]
+mod tests;
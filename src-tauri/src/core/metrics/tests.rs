@@ -0,0 +1,82 @@
+use super::*;
+use crate::test_support as fixtures;
+
+fn with_status(mut node: Node, status: RequirementStatus) -> Node {
+    if let NodeData::Requirement(r) = &mut node.data {
+        r.status = status;
+    }
+    node
+}
+
+#[test]
+fn coverage_counts_live_requirements_and_unlinked_blocks_and_tests() {
+    let project = fixtures::project("Coverage");
+    let mut done = fixtures::requirement(project.id, "REQ-001", "Fully traced");
+    if let NodeData::Requirement(r) = &mut done.data {
+        r.allocations = Some(vec!["Power".into()]);
+    }
+    let satisfied_only = fixtures::requirement(project.id, "REQ-002", "Satisfied");
+    let mut bare = fixtures::requirement(project.id, "REQ-003", "Nothing yet");
+    if let NodeData::Requirement(r) = &mut bare.data {
+        r.allocations = Some(vec!["  ".into()]);
+    }
+    let obsolete = with_status(
+        fixtures::requirement(project.id, "REQ-004", "Superseded"),
+        RequirementStatus::Obsolete,
+    );
+    let regulator = fixtures::block(project.id, "Regulator");
+    let idle = fixtures::block(project.id, "Spare");
+    let legacy = fixtures::block(project.id, "Legacy");
+    let mut retired = fixtures::block(project.id, "Retired");
+    retired.archived = true;
+    let load_test = fixtures::test_case(project.id, "Load test");
+    let orphan_test = fixtures::test_case(project.id, "Orphan test");
+    let edges = vec![
+        fixtures::edge(project.id, EdgeKind::Satisfies, regulator.id, done.id),
+        fixtures::edge(
+            project.id,
+            EdgeKind::Satisfies,
+            regulator.id,
+            satisfied_only.id,
+        ),
+        fixtures::edge(project.id, EdgeKind::Verifies, load_test.id, done.id),
+        // Neither an obsolete requirement nor an archived block counts.
+        fixtures::edge(project.id, EdgeKind::Satisfies, legacy.id, obsolete.id),
+        fixtures::edge(project.id, EdgeKind::Satisfies, retired.id, bare.id),
+    ];
+
+    let coverage = model_coverage(
+        &[
+            done,
+            satisfied_only,
+            bare,
+            obsolete,
+            regulator,
+            idle,
+            legacy,
+            retired,
+            load_test,
+            orphan_test,
+        ],
+        &edges,
+    );
+    assert_eq!(coverage.total_requirements, 3);
+    assert_eq!(coverage.satisfied, 2);
+    assert!((coverage.satisfied_pct - 200.0 / 3.0).abs() < 1e-9);
+    assert_eq!(coverage.verified, 1);
+    assert!((coverage.verified_pct - 100.0 / 3.0).abs() < 1e-9);
+    assert_eq!(coverage.allocated, 1);
+    assert_eq!(coverage.blocks_satisfying_nothing, 2);
+    assert_eq!(coverage.test_cases_verifying_nothing, 1);
+}
+
+#[test]
+fn coverage_of_an_empty_model_is_zero() {
+    let project = fixtures::project("Empty");
+    let obsolete = with_status(
+        fixtures::requirement(project.id, "REQ-001", "Gone"),
+        RequirementStatus::Obsolete,
+    );
+
+    assert_eq!(model_coverage(&[obsolete], &[]), ModelCoverage::default());
+}
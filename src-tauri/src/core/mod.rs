@@ -1,4 +1,31 @@
+pub mod baseline;
+pub mod classification;
+pub mod digest;
+pub mod duplicates;
+pub mod example;
 pub mod export;
+pub mod external;
+pub mod extraction;
+pub mod history;
+pub mod import;
+pub mod inheritance;
+pub mod interfaces;
+pub mod metrics;
 pub mod model;
+pub mod numbering;
+pub mod portfolio;
+pub mod provenance;
+pub mod readiness;
+pub mod references;
+pub mod rollup;
+pub mod scenario;
+pub mod search;
+pub mod sections;
+pub mod split;
 pub mod store;
+pub mod templates;
+pub mod terminology;
+pub mod trace;
 pub mod validation;
+pub mod verification;
+pub mod wikilinks;
@@ -21,11 +21,75 @@ pub struct Node {
     /// Nothing queryable should live here.
     pub meta: HashMap<String, Value>,
 
+    /// Obsolete but kept for traceability: edges and history stay, but the
+    /// node is left out of listings, validation and diagram population.
+    /// Only changed through `archive_nodes` / `unarchive_nodes`.
+    #[serde(default)]
+    pub archived: bool,
+
     pub created_at: DateTime<Utc>,
     pub modified_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+/// Meta key importers set to the source's creation time (RFC 3339) when it
+/// has one, so `created_at` can stay the time the row was written here.
+pub const ORIGINAL_CREATED_AT_META: &str = "original_created_at";
+
+/// Meta key for a requirement's verification level ("system", "subsystem",
+/// "component", …), as listed in the VCRM. Free text; projects pick their
+/// own levels.
+pub const VERIFICATION_LEVEL_META: &str = "verification_level";
+
+/// Whether a node came in from a source or was created in the app.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CreatedOrigin {
+    Imported,
+    Authored,
+}
+
+impl Node {
+    /// The source's creation time, when an importer carried one.
+    pub fn original_created_at(&self) -> Option<DateTime<Utc>> {
+        original_created_at(&self.meta)
+    }
+
+    /// When the node originated: the source's creation time, else `created_at`.
+    pub fn origin_created_at(&self) -> DateTime<Utc> {
+        self.original_created_at().unwrap_or(self.created_at)
+    }
+
+    /// `VERIFICATION_LEVEL_META`, when set to a non-empty string.
+    pub fn verification_level(&self) -> Option<&str> {
+        let level = self.meta.get(VERIFICATION_LEVEL_META)?.as_str()?.trim();
+        (!level.is_empty()).then_some(level)
+    }
+
+    /// Imported when it carries an original creation time, names a source
+    /// document, or was written by an import.
+    pub fn created_origin(&self) -> CreatedOrigin {
+        let imported = self.meta.contains_key(ORIGINAL_CREATED_AT_META)
+            || self.meta.contains_key("source_doc")
+            || self.meta.get("change_source").and_then(Value::as_str) == Some("import");
+        if imported {
+            CreatedOrigin::Imported
+        } else {
+            CreatedOrigin::Authored
+        }
+    }
+}
+
+/// `ORIGINAL_CREATED_AT_META` from a node's or edge's meta, if it parses.
+pub fn original_created_at(meta: &HashMap<String, Value>) -> Option<DateTime<Utc>> {
+    let raw = meta.get(ORIGINAL_CREATED_AT_META)?.as_str()?;
+    DateTime::parse_from_rfc3339(raw.trim())
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+/// Deserialized through `FromStr`, so an unknown kind is rejected with the
+/// list of valid ones.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq, Hash)]
 #[serde(rename_all = "snake_case")]
 pub enum NodeKind {
     Requirement,
@@ -64,6 +128,51 @@ impl std::fmt::Display for NodeKind {
     }
 }
 
+impl NodeKind {
+    pub const ALL: [NodeKind; 13] = [
+        NodeKind::Requirement,
+        NodeKind::Block,
+        NodeKind::Interface,
+        NodeKind::Port,
+        NodeKind::UseCase,
+        NodeKind::Actor,
+        NodeKind::TestCase,
+        NodeKind::Stakeholder,
+        NodeKind::Function,
+        NodeKind::External,
+        NodeKind::ValueType,
+        NodeKind::ConstraintBlock,
+        NodeKind::State,
+    ];
+}
+
+impl std::str::FromStr for NodeKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        NodeKind::ALL
+            .into_iter()
+            .find(|kind| kind.to_string() == s)
+            .ok_or_else(|| unknown_kind("node", s, &NodeKind::ALL))
+    }
+}
+
+impl<'de> Deserialize<'de> for NodeKind {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// "unknown edge kind 'x'; expected one of: satisfies, refines, …"
+fn unknown_kind<K: std::fmt::Display>(what: &str, value: &str, all: &[K]) -> String {
+    let valid: Vec<String> = all.iter().map(|k| k.to_string()).collect();
+    format!(
+        "unknown {what} kind '{value}'; expected one of: {}",
+        valid.join(", ")
+    )
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "kind", rename_all = "snake_case")]
 pub enum NodeData {
@@ -82,6 +191,18 @@ pub enum NodeData {
     State(StateData),
 }
 
+/// The columns pickers and name lookups need, read without touching `data`
+/// or `meta`. `req_id` and `status` are set for requirements only.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeSummary {
+    pub id: Uuid,
+    pub kind: NodeKind,
+    pub name: String,
+    pub req_id: Option<String>,
+    pub status: Option<RequirementStatus>,
+    pub archived: bool,
+}
+
 // ── Kind-specific data structs ────────────────────────────────────────────────
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -96,6 +217,9 @@ pub struct RequirementData {
     /// Subsystem allocation tags (e.g. ["FPGA", "Microcontroller"])
     pub allocations: Option<Vec<String>>,
     pub verification_method: Option<VerificationMethod>,
+    /// One of the project's taxonomy labels, or "unknown"; see
+    /// `core::classification`.
+    pub classification: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
@@ -107,11 +231,56 @@ pub struct RequirementSnapshot {
     pub priority: String,
     pub status: String,
     pub verification_method: String,
+    /// Absent from entries recorded before classifications were stored.
+    #[serde(default)]
+    pub classification: String,
     pub source: String,
     pub allocations: Vec<String>,
     pub description: String,
 }
 
+impl RequirementSnapshot {
+    /// Fields that differ from `self` to `next`, in declaration order.
+    /// Compare against `RequirementSnapshot::default()` to diff a creation
+    /// or deletion, the same way history entries record them.
+    pub fn diff(&self, next: &RequirementSnapshot) -> Vec<FieldChange> {
+        let mut changes = Vec::new();
+        let mut field = |name: &str, before: serde_json::Value, after: serde_json::Value| {
+            if before != after {
+                changes.push(FieldChange { field: name.to_string(), before, after });
+            }
+        };
+        field("req_id", self.req_id.clone().into(), next.req_id.clone().into());
+        field("name", self.name.clone().into(), next.name.clone().into());
+        field("text", self.text.clone().into(), next.text.clone().into());
+        field("rationale", self.rationale.clone().into(), next.rationale.clone().into());
+        field("priority", self.priority.clone().into(), next.priority.clone().into());
+        field("status", self.status.clone().into(), next.status.clone().into());
+        field(
+            "verification_method",
+            self.verification_method.clone().into(),
+            next.verification_method.clone().into(),
+        );
+        field(
+            "classification",
+            self.classification.clone().into(),
+            next.classification.clone().into(),
+        );
+        field("source", self.source.clone().into(), next.source.clone().into());
+        field("allocations", self.allocations.clone().into(), next.allocations.clone().into());
+        field("description", self.description.clone().into(), next.description.clone().into());
+        changes
+    }
+}
+
+/// One field of a [`RequirementSnapshot::diff`].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: serde_json::Value,
+    pub after: serde_json::Value,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RequirementHistoryEntry {
     pub id: Uuid,
@@ -124,6 +293,31 @@ pub struct RequirementHistoryEntry {
     pub next: RequirementSnapshot,
 }
 
+/// A history entry from the project-wide listing, with the requirement's
+/// current req_id and name for display.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectRequirementHistoryEntry {
+    #[serde(flatten)]
+    pub entry: RequirementHistoryEntry,
+    pub req_id: Option<String>,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryCount {
+    /// The series key: a UTC day (`YYYY-MM-DD`), change source, subsystem, …
+    pub key: String,
+    pub count: i64,
+}
+
+/// Change counts for the history chart.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RequirementHistoryStats {
+    pub total: i64,
+    pub by_day: Vec<HistoryCount>,
+    pub by_source: Vec<HistoryCount>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum RequirementPriority {
@@ -300,7 +494,8 @@ pub struct Edge {
     pub modified_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+/// Deserialized through `FromStr`, like [`NodeKind`].
+#[derive(Debug, Clone, Serialize, PartialEq, Eq, Hash)]
 #[serde(rename_all = "snake_case")]
 pub enum EdgeKind {
     /// Block satisfies a Requirement
@@ -352,6 +547,42 @@ impl std::fmt::Display for EdgeKind {
     }
 }
 
+impl EdgeKind {
+    pub const ALL: [EdgeKind; 13] = [
+        EdgeKind::Satisfies,
+        EdgeKind::Refines,
+        EdgeKind::Allocates,
+        EdgeKind::Realizes,
+        EdgeKind::Traces,
+        EdgeKind::Verifies,
+        EdgeKind::Connects,
+        EdgeKind::Composes,
+        EdgeKind::Specializes,
+        EdgeKind::Derives,
+        EdgeKind::Blocks,
+        EdgeKind::Transition,
+        EdgeKind::BindingConnector,
+    ];
+}
+
+impl std::str::FromStr for EdgeKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        EdgeKind::ALL
+            .into_iter()
+            .find(|kind| kind.to_string() == s)
+            .ok_or_else(|| unknown_kind("edge", s, &EdgeKind::ALL))
+    }
+}
+
+impl<'de> Deserialize<'de> for EdgeKind {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
 // ── Diagram types ─────────────────────────────────────────────────────────────
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -377,6 +608,56 @@ pub enum DiagramKind {
     Parametric,
 }
 
+/// View filters persisted under `layout_options.filters`. Hidden nodes stay
+/// placed on the diagram; they're just left out of the IR and of auto-layout.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DiagramFilters {
+    /// Node kinds (`NodeKind` display names, e.g. "requirement") to hide.
+    #[serde(default)]
+    pub hidden_kinds: Vec<String>,
+    #[serde(default)]
+    pub hidden_node_ids: Vec<Uuid>,
+    #[serde(default = "default_true")]
+    pub show_edge_labels: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for DiagramFilters {
+    fn default() -> Self {
+        Self {
+            hidden_kinds: Vec::new(),
+            hidden_node_ids: Vec::new(),
+            show_edge_labels: true,
+        }
+    }
+}
+
+impl DiagramFilters {
+    pub const LAYOUT_KEY: &'static str = "filters";
+
+    pub fn is_hidden(&self, node: &Node) -> bool {
+        self.hidden_node_ids.contains(&node.id)
+            || self.hidden_kinds.iter().any(|k| *k == node.kind.to_string())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl Diagram {
+    /// The diagram's view filters; malformed or missing entries read as none.
+    pub fn filters(&self) -> DiagramFilters {
+        self.layout_options
+            .get(DiagramFilters::LAYOUT_KEY)
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+            .unwrap_or_default()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DiagramElement {
     pub id: Uuid,
@@ -388,6 +669,95 @@ pub struct DiagramElement {
     pub height: f64,
     pub collapsed: bool,
     pub style_overrides: HashMap<String, Value>,
+    /// Set by the store; the frontend may omit these.
+    #[serde(default = "Utc::now")]
+    pub created_at: DateTime<Utc>,
+    #[serde(default = "Utc::now")]
+    pub modified_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LayoutSessionStatus {
+    Open,
+    Committed,
+    Cancelled,
+}
+
+impl std::fmt::Display for LayoutSessionStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LayoutSessionStatus::Open => write!(f, "open"),
+            LayoutSessionStatus::Committed => write!(f, "committed"),
+            LayoutSessionStatus::Cancelled => write!(f, "cancelled"),
+        }
+    }
+}
+
+impl std::str::FromStr for LayoutSessionStatus {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "open" => Ok(LayoutSessionStatus::Open),
+            "committed" => Ok(LayoutSessionStatus::Committed),
+            "cancelled" => Ok(LayoutSessionStatus::Cancelled),
+            other => anyhow::bail!("unknown layout session status: {other}"),
+        }
+    }
+}
+
+/// A layout editing session. Its snapshot (element geometry when it
+/// started) is read separately by `layout_session_snapshot`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LayoutSession {
+    pub id: Uuid,
+    pub diagram_id: Uuid,
+    pub status: LayoutSessionStatus,
+    /// Elements in the snapshot.
+    pub element_count: i64,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+}
+
+/// One element's position and size in a layout session snapshot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ElementGeometry {
+    pub node_id: Uuid,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// A named set of element styles, merged into the `style_overrides` of a
+/// diagram's elements by `apply_style_preset`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StylePreset {
+    pub id: Uuid,
+    pub project_id: Uuid,
+    pub name: String,
+    /// Node kinds (`NodeKind` display names) the preset applies to; empty
+    /// means every kind.
+    #[serde(default)]
+    pub target_kinds: Vec<String>,
+    /// Only `STYLE_KEYS`, so presets can't write anything else the canvas
+    /// reads from `style_overrides`.
+    #[serde(default)]
+    pub style: HashMap<String, Value>,
+    #[serde(default = "Utc::now")]
+    pub created_at: DateTime<Utc>,
+    #[serde(default = "Utc::now")]
+    pub modified_at: DateTime<Utc>,
+}
+
+impl StylePreset {
+    /// Colors are strings; `corner_radius` and `font_size` are numbers.
+    pub const STYLE_KEYS: [&'static str; 5] =
+        ["fill", "stroke", "text_color", "corner_radius", "font_size"];
+
+    pub fn applies_to(&self, kind: &NodeKind) -> bool {
+        self.target_kinds.is_empty() || self.target_kinds.iter().any(|k| *k == kind.to_string())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -415,6 +785,38 @@ pub struct Project {
     pub modified_at: DateTime<Utc>,
 }
 
+/// A reusable project skeleton. Built-in templates are defined in code
+/// (`core::templates`) and are not stored in the database.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectTemplate {
+    pub id: Uuid,
+    pub name: String,
+    pub description: String,
+    pub builtin: bool,
+    /// Parts captured: "blocks", "value_types", "diagrams", "settings".
+    pub include: Vec<String>,
+    pub content: ProjectTemplateContent,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Model elements captured by a template. Ids are the source project's and
+/// are remapped when a project is created from the template.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectTemplateContent {
+    #[serde(default)]
+    pub nodes: Vec<Node>,
+    #[serde(default)]
+    pub edges: Vec<Edge>,
+    #[serde(default)]
+    pub block_properties: Vec<BlockProperty>,
+    #[serde(default)]
+    pub diagrams: Vec<Diagram>,
+    #[serde(default)]
+    pub diagram_elements: Vec<DiagramElement>,
+    #[serde(default)]
+    pub settings: Vec<SettingEntry>,
+}
+
 // -- Documents + subsystem content -----------------------------------------
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -442,6 +844,20 @@ pub struct SubsystemKnowledgePage {
     pub body_format: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// A `[[link]]` target was renamed since the links were last resolved.
+    /// Set by node writes; cleared by `resolve_knowledge_links`.
+    #[serde(default)]
+    pub stale_links: bool,
+}
+
+/// A resolved `[[link]]` from a knowledge page, with the target's name and
+/// req_id at the time so a later rename can be noticed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnowledgeLink {
+    pub node_id: Uuid,
+    pub link_text: String,
+    pub node_name: String,
+    pub node_req_id: String,
 }
 
 fn default_subsystem_knowledge_body_format() -> String {
@@ -467,6 +883,31 @@ pub struct SubsystemActivity {
     pub created_at: DateTime<Utc>,
 }
 
+/// What `merge_blocks` moved onto the kept block. Rows that would have
+/// duplicated one the kept block already had (an edge of the same kind
+/// between the same nodes, a second element on the same diagram) are
+/// dropped instead of moved and counted separately.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BlockMergeReport {
+    pub kept_id: Uuid,
+    pub kept_name: String,
+    /// Names of the deleted blocks, by id.
+    pub removed: Vec<(Uuid, String)>,
+    pub edges_moved: u64,
+    /// Duplicates of the kept block's edges, or edges between merged blocks.
+    pub edges_dropped: u64,
+    pub diagram_elements_moved: u64,
+    pub diagram_elements_dropped: u64,
+    /// Requirements whose allocation tags named a removed block.
+    pub requirements_reallocated: Vec<Uuid>,
+    pub knowledge_pages_moved: u64,
+    pub knowledge_links_moved: u64,
+    pub artifacts_moved: u64,
+    pub activity_moved: u64,
+    pub block_properties_moved: u64,
+    pub external_links_moved: u64,
+}
+
 // ── Document sections ─────────────────────────────────────────────────────────
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -535,6 +976,94 @@ pub struct DocumentSection {
     pub unit: Option<String>,
     pub position: i64,
     pub created_at: DateTime<Utc>,
+    /// The heading this section sits under; `None` at the top level.
+    #[serde(default)]
+    pub parent_section_id: Option<Uuid>,
+    /// Heading depth: a heading's own ("3.1.2" and "###" are 3), the
+    /// enclosing heading's for other sections, 0 before the first heading.
+    #[serde(default)]
+    pub section_level: i64,
+}
+
+/// What `bulk_upsert_document_sections` or `reorder_document_sections`
+/// wrote, and how long the store took to write it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SectionWriteReport {
+    pub document_id: Uuid,
+    /// Rows inserted, updated or repositioned.
+    pub sections: u64,
+    pub elapsed_ms: f64,
+}
+
+// ── Data health ───────────────────────────────────────────────────────────────
+
+/// A stored node, edge or diagram row whose kind this build doesn't know,
+/// usually written by a newer app version. Listings skip such rows and
+/// exports pass them through unchanged.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnrecognizedRow {
+    /// "nodes", "edges" or "diagrams".
+    pub table: String,
+    pub id: String,
+    pub kind: String,
+    /// Every column of the row, by column name.
+    pub row: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataHealthReport {
+    pub project_id: Uuid,
+    pub unrecognized: Vec<UnrecognizedRow>,
+}
+
+// ── Node usage ────────────────────────────────────────────────────────────────
+
+/// A record referring to a node, e.g. a diagram it appears on.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageRef {
+    pub id: Uuid,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageEdge {
+    pub edge_id: Uuid,
+    pub kind: EdgeKind,
+    pub other_id: Uuid,
+    pub other_name: String,
+    /// The node is the edge's source.
+    pub outgoing: bool,
+}
+
+/// Everything that refers to a node, shown before deleting it.
+///
+/// Deleting the node also deletes its edges, diagram appearances,
+/// knowledge pages, artifacts, properties and external item links.
+/// Comments, review items and suspect links are left for the orphan
+/// cleanup. Allocation tags and scenario events keep the stale reference.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeUsage {
+    pub node_id: Uuid,
+    pub edges: Vec<UsageEdge>,
+    pub edges_by_kind: Vec<HistoryCount>,
+    pub diagrams: Vec<UsageRef>,
+    /// Requirements carrying the node's name as an allocation tag.
+    pub allocated_requirements: Vec<UsageRef>,
+    pub comments: i64,
+    pub open_comments: i64,
+    /// Review sessions with an item for the node.
+    pub review_sessions: Vec<UsageRef>,
+    /// Unresolved suspect links from or to the node.
+    pub suspect_links: Vec<Uuid>,
+    pub knowledge_pages: Vec<UsageRef>,
+    pub artifacts: Vec<UsageRef>,
+    /// Simulation scenarios with events referencing the node.
+    pub sim_scenarios: Vec<UsageRef>,
+    /// Value properties owned by the node (a block).
+    pub properties: i64,
+    /// Block properties typed by the node (a value type).
+    pub typed_properties: i64,
+    pub external_links: i64,
 }
 
 // ── Suspect links ─────────────────────────────────────────────────────────────
@@ -552,6 +1081,35 @@ pub struct SuspectLink {
     pub resolved_by: Option<String>,
 }
 
+/// Unresolved suspect links by how long ago they were flagged.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SuspectAgeBuckets {
+    pub under_7_days: i64,
+    pub from_7_to_30_days: i64,
+    pub over_30_days: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgedSuspectLink {
+    #[serde(flatten)]
+    pub link: SuspectLink,
+    pub source_name: String,
+    pub target_name: String,
+    /// Whole days since the link was flagged.
+    pub age_days: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuspectLinkSummary {
+    pub project_id: Uuid,
+    pub unresolved: i64,
+    pub by_age: SuspectAgeBuckets,
+    /// Longest-unresolved first.
+    pub oldest: Vec<AgedSuspectLink>,
+    /// Keyed by the target requirement's allocation; "Unallocated" when it has none.
+    pub by_subsystem: Vec<HistoryCount>,
+}
+
 // ── Review workflow ───────────────────────────────────────────────────────────
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -633,7 +1191,9 @@ pub struct ReqComment {
 // ── Model baselines ───────────────────────────────────────────────────────────
 
 /// A named snapshot of the full model state at a point in time.
-/// `snapshot` is a JSON object: `{ "nodes": [...], "edges": [...] }`
+/// `snapshot` is a JSON object: `{ "nodes": [...], "edges": [...],
+/// "diagrams": [...], "diagram_elements": [...] }`. Older baselines lack
+/// the diagram keys.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModelBaseline {
     pub id: Uuid,
@@ -644,3 +1204,373 @@ pub struct ModelBaseline {
     pub created_at: chrono::DateTime<Utc>,
     pub snapshot: serde_json::Value,
 }
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BaselineComparison {
+    /// Present in both; `changes` may be empty.
+    Present,
+    /// Exists now but not in the baseline (created afterwards).
+    MissingInBaseline,
+    /// In the baseline but no longer in the model.
+    DeletedSinceBaseline,
+}
+
+/// A requirement as captured by a baseline next to its current state.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequirementAtBaseline {
+    pub node_id: Uuid,
+    pub baseline_id: Uuid,
+    pub baseline_name: String,
+    pub baseline_created_at: chrono::DateTime<Utc>,
+    pub comparison: BaselineComparison,
+    pub baseline: Option<RequirementSnapshot>,
+    pub current: Option<RequirementSnapshot>,
+    /// Baseline → current; an absent side diffs as an empty snapshot.
+    pub changes: Vec<FieldChange>,
+}
+
+// ── Coverage metrics ──────────────────────────────────────────────────────────
+
+/// Requirement coverage at one point in time. Percentages are 0–100 of
+/// `total_requirements`, and 0 when there are none.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct CoverageMetrics {
+    pub total_requirements: i64,
+    /// With at least one incoming «satisfies» edge.
+    pub satisfied_pct: f64,
+    /// With at least one incoming «verifies» edge.
+    pub verified_pct: f64,
+    pub approved_pct: f64,
+    pub verification_method_pct: f64,
+    /// With at least one allocation.
+    pub allocated_pct: f64,
+}
+
+impl CoverageMetrics {
+    pub fn from_counts(
+        total: i64,
+        satisfied: i64,
+        verified: i64,
+        approved: i64,
+        with_verification_method: i64,
+        allocated: i64,
+    ) -> Self {
+        let pct = |count: i64| {
+            if total == 0 {
+                0.0
+            } else {
+                count as f64 * 100.0 / total as f64
+            }
+        };
+        CoverageMetrics {
+            total_requirements: total,
+            satisfied_pct: pct(satisfied),
+            verified_pct: pct(verified),
+            approved_pct: pct(approved),
+            verification_method_pct: pct(with_verification_method),
+            allocated_pct: pct(allocated),
+        }
+    }
+}
+
+/// One point of `coverage_trend`: a baseline, or the current model when
+/// `baseline_id` is `None`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoveragePoint {
+    pub baseline_id: Option<Uuid>,
+    pub name: String,
+    pub at: DateTime<Utc>,
+    pub metrics: CoverageMetrics,
+}
+
+// ── Portfolio ─────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RequirementStatusCounts {
+    pub draft: i64,
+    pub approved: i64,
+    pub obsolete: i64,
+}
+
+/// One project's row in `portfolio_summary`. A project with no content has
+/// zero counts and no dates.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectHealth {
+    pub project_id: Uuid,
+    pub name: String,
+    pub requirements: RequirementStatusCounts,
+    pub coverage: CoverageMetrics,
+    pub unresolved_suspect_links: i64,
+    pub open_reviews: i64,
+    pub last_baseline_at: Option<DateTime<Utc>>,
+    /// Newest audit entry, or the project's own modification time.
+    pub last_activity_at: DateTime<Utc>,
+}
+
+// ── Audit log ─────────────────────────────────────────────────────────────────
+
+/// One entry in the generic mutation trail. `summary` is a short human string,
+/// e.g. "renamed 'Power Dist' → 'Power Distribution Unit'".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub id: Uuid,
+    pub project_id: Uuid,
+    pub entity_type: String,
+    pub entity_id: Uuid,
+    pub action: String,
+    pub actor: String,
+    pub summary: String,
+    pub changed_at: DateTime<Utc>,
+}
+
+// ── Attention digest ──────────────────────────────────────────────────────────
+
+/// One overdue item named in the attention digest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestSample {
+    /// The comment, review session, suspect link or requirement.
+    pub id: Uuid,
+    /// Requirement the item concerns, when it isn't one itself.
+    pub node_id: Option<Uuid>,
+    pub label: String,
+    pub since: DateTime<Utc>,
+}
+
+/// Items matching one digest rule: how many, the oldest date and the
+/// oldest few.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DigestBucket {
+    pub count: i64,
+    pub oldest: Option<DateTime<Utc>>,
+    pub samples: Vec<DigestSample>,
+}
+
+// ── Command palette ───────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PaletteEntityType {
+    Node,
+    Diagram,
+    Document,
+    Scenario,
+    ReviewSession,
+}
+
+/// One jump target in the command palette, names only.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaletteEntry {
+    pub id: Uuid,
+    pub entity_type: PaletteEntityType,
+    pub label: String,
+    /// Node req_id or kind, diagram kind, document type or review status.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sublabel: Option<String>,
+}
+
+// ── Presence ──────────────────────────────────────────────────────────────────
+
+/// A machine with the database open; see `crate::presence`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Presence {
+    pub machine: String,
+    /// The machine's configured identity; empty when none is set.
+    pub user: String,
+    pub read_only_fallback: bool,
+    pub started_at: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+// ── AI usage ──────────────────────────────────────────────────────────────────
+
+/// Token counts for one AI completion, as stored in `ai_usage`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AiUsage {
+    pub id: Uuid,
+    pub provider: String,
+    pub model: String,
+    /// What the call was for, e.g. "extract" or "quality_pass".
+    pub purpose: String,
+    pub input_tokens: Option<u32>,
+    pub output_tokens: Option<u32>,
+    pub cache_read_tokens: Option<u32>,
+    pub cache_write_tokens: Option<u32>,
+    pub created_at: DateTime<Utc>,
+}
+
+// ── Database maintenance ──────────────────────────────────────────────────────
+
+/// Rows in `table` whose `column` points at a missing `parent_table` row.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrphanCount {
+    pub table: String,
+    pub column: String,
+    pub parent_table: String,
+    pub count: i64,
+}
+
+/// Result of `db_maintenance`. Each field is only populated when the
+/// corresponding action was requested.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DbMaintenanceReport {
+    /// `PRAGMA integrity_check` output — `["ok"]` for a healthy database.
+    pub integrity: Option<Vec<String>>,
+    pub size_before_bytes: Option<i64>,
+    pub size_after_bytes: Option<i64>,
+    pub orphans: Option<Vec<OrphanCount>>,
+    pub removed: Option<Vec<OrphanCount>>,
+}
+
+/// A stored setting. `project_id` is `None` for global settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettingEntry {
+    pub key: String,
+    #[serde(default)]
+    pub project_id: Option<Uuid>,
+    pub value: String,
+    /// True when `value` has been replaced by a mask (secret keys).
+    #[serde(default)]
+    pub masked: bool,
+}
+
+/// One schema migration as reported by `app_info`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub applied: bool,
+    pub installed_on: Option<String>,
+    /// False for migrations recorded in the database that this build doesn't
+    /// ship — i.e. the database was upgraded by a newer app version.
+    pub known: bool,
+}
+
+// ── Block value properties ────────────────────────────────────────────────────
+
+/// A value property owned by a Block, e.g. `mass : Mass = 12.5 kg`.
+/// `value_type_node_id` optionally types it by a ValueType node; `type_name`
+/// is kept alongside so the property still reads sensibly if that goes away.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockProperty {
+    pub id: Uuid,
+    pub block_node_id: Uuid,
+    pub name: String,
+    #[serde(default)]
+    pub value_type_node_id: Option<Uuid>,
+    #[serde(default)]
+    pub type_name: String,
+    #[serde(default)]
+    pub value: String,
+    #[serde(default)]
+    pub unit: String,
+    #[serde(default = "Utc::now")]
+    pub created_at: DateTime<Utc>,
+}
+
+// ── External items ────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExternalSource {
+    Jira,
+    #[default]
+    Generic,
+}
+
+/// An item tracked in another tool — typically a Jira verification finding —
+/// imported from CSV so it can be traced to model nodes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExternalItem {
+    pub id: Uuid,
+    pub project_id: Uuid,
+    pub source: ExternalSource,
+    /// The other tool's identifier, e.g. "VER-123". Unique per project;
+    /// re-imports update the item with the same key.
+    pub external_key: String,
+    pub title: String,
+    pub url: Option<String>,
+    pub status: String,
+    pub synced_at: DateTime<Utc>,
+}
+
+/// An external item together with one node it is linked to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkedExternalItem {
+    pub node_id: Uuid,
+    #[serde(flatten)]
+    pub item: ExternalItem,
+}
+
+// ── Extraction review ─────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewDecision {
+    #[default]
+    Pending,
+    Accepted,
+    Rejected,
+    /// Accepted and already created as a requirement node.
+    Imported,
+}
+
+/// The reviewer's decision on one extracted item. `index` points into the
+/// session's raw results; an index past the end is an item the reviewer
+/// added by hand. `edits` holds only the fields that differ from the raw
+/// result (for hand-added items, every field).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtractionReviewItem {
+    pub index: usize,
+    #[serde(default)]
+    pub decision: ReviewDecision,
+    #[serde(default)]
+    pub edits: serde_json::Map<String, Value>,
+    /// Node created from this item on commit.
+    #[serde(default)]
+    pub node_id: Option<Uuid>,
+}
+
+/// Saved progress through reviewing one extraction run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtractionReviewState {
+    pub session_id: String,
+    pub project_id: Uuid,
+    #[serde(default)]
+    pub document_id: Option<Uuid>,
+    #[serde(default)]
+    pub source_name: String,
+    /// Extraction results as returned, stored once on the first save.
+    #[serde(default)]
+    pub results: Vec<Value>,
+    /// Items that have a decision or edits, ordered by index.
+    #[serde(default)]
+    pub items: Vec<ExtractionReviewItem>,
+    #[serde(default = "Utc::now")]
+    pub created_at: DateTime<Utc>,
+    #[serde(default = "Utc::now")]
+    pub updated_at: DateTime<Utc>,
+}
+
+// ── Glossary ──────────────────────────────────────────────────────────────────
+
+/// An approved term and the variants requirement text must not use. With
+/// `case_sensitive`, variants only match with the given capitalization and
+/// the approved form must be written exactly; otherwise both are matched
+/// in any case.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlossaryTerm {
+    pub id: Uuid,
+    pub project_id: Uuid,
+    /// Display name, e.g. "GCS".
+    pub term: String,
+    /// e.g. "Ground Control Station".
+    pub approved: String,
+    /// e.g. ["GCS ground station", "ground control center"].
+    #[serde(default)]
+    pub variants: Vec<String>,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    pub created_at: DateTime<Utc>,
+    pub modified_at: DateTime<Utc>,
+}
@@ -0,0 +1,203 @@
+/// Requirement id schemes, chosen by the `req_id.scheme` setting.
+///
+/// `simple` (the default) numbers requirements "REQ-001", "REQ-002", ...
+/// after the highest number in use. `section_based` gives requirements
+/// committed from an extraction review an id built from `req_id.pattern`,
+/// e.g. "SOW-3.2.1-R01", with one sequence per document section. The last
+/// number used per section is kept in `req_id_counters`, so later imports
+/// into the same section continue where earlier ones stopped. Requirements
+/// whose section can't be found, and manually created ones, keep the
+/// simple scheme.
+use crate::core::model::{Document, DocumentSection, Node, NodeData};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+pub const SCHEME_SETTING: &str = "req_id.scheme";
+pub const PATTERN_SETTING: &str = "req_id.pattern";
+
+/// Placeholders: `{doc_abbrev}`, `{section_ref}` and `{seq}` (the
+/// per-section number, at least two digits).
+pub const DEFAULT_PATTERN: &str = "{doc_abbrev}-{section_ref}-R{seq}";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReqIdScheme {
+    #[default]
+    Simple,
+    SectionBased,
+}
+
+impl ReqIdScheme {
+    /// Unknown or missing values are `Simple`.
+    pub fn parse(value: Option<&str>) -> Self {
+        match value.map(str::trim) {
+            Some("section_based") => ReqIdScheme::SectionBased,
+            _ => ReqIdScheme::Simple,
+        }
+    }
+}
+
+/// `{doc_abbrev}` for a document: its type upper-cased without spaces or
+/// punctuation ("SOW", "TESTPLAN"), or "DOC" for General and Other.
+pub fn doc_abbrev(document: &Document) -> String {
+    let abbrev: String = document
+        .doc_type
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_uppercase();
+    match abbrev.as_str() {
+        "" | "GENERAL" | "OTHER" => "DOC".to_string(),
+        _ => abbrev,
+    }
+}
+
+/// The section whose body contains `text`, ignoring case and runs of
+/// whitespace. When several do, the one with the longest `section_ref`
+/// (the most specific) wins.
+pub fn section_containing<'a>(
+    sections: &'a [DocumentSection],
+    text: &str,
+) -> Option<&'a DocumentSection> {
+    let needle = collapse(text);
+    if needle.is_empty() {
+        return None;
+    }
+    sections
+        .iter()
+        .filter(|s| !s.section_ref.trim().is_empty() && collapse(&s.body).contains(&needle))
+        .max_by_key(|s| s.section_ref.trim().len())
+}
+
+fn collapse(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Hands out section-based ids. Counters are keyed by the pattern rendered
+/// without its sequence ("SOW-3.2.1-R{seq}"), so two documents with the
+/// same abbreviation and section share a sequence and never collide.
+pub struct SectionNumbering {
+    pattern: String,
+    counters: HashMap<String, i64>,
+    taken: HashSet<String>,
+}
+
+impl SectionNumbering {
+    /// `counters` are the stored last-used numbers; `taken` the req_ids in
+    /// use. A pattern without `{seq}` gets "-{seq}" appended, since every
+    /// id would otherwise be the same.
+    pub fn new(pattern: &str, counters: HashMap<String, i64>, taken: HashSet<String>) -> Self {
+        let pattern = match pattern.trim() {
+            "" => DEFAULT_PATTERN.to_string(),
+            p if p.contains("{seq}") => p.to_string(),
+            p => format!("{p}-{{seq}}"),
+        };
+        Self {
+            pattern,
+            counters,
+            taken,
+        }
+    }
+
+    /// The next free id in `section_ref` of a document tagged `doc_abbrev`.
+    pub fn next(&mut self, doc_abbrev: &str, section_ref: &str) -> String {
+        let section_ref = section_ref.trim();
+        let key = render(&self.pattern, doc_abbrev, section_ref, None);
+        let counter = self.counters.entry(key).or_insert(0);
+        loop {
+            *counter += 1;
+            let id = render(&self.pattern, doc_abbrev, section_ref, Some(*counter));
+            if self.taken.insert(id.clone()) {
+                return id;
+            }
+        }
+    }
+
+    pub fn into_counters(self) -> HashMap<String, i64> {
+        self.counters
+    }
+}
+
+fn render(pattern: &str, doc_abbrev: &str, section_ref: &str, seq: Option<i64>) -> String {
+    let seq = seq
+        .map(|n| format!("{n:02}"))
+        .unwrap_or_else(|| "{seq}".to_string());
+    pattern
+        .replace("{doc_abbrev}", doc_abbrev)
+        .replace("{section_ref}", section_ref)
+        .replace("{seq}", &seq)
+}
+
+/// One requirement's id before and after renumbering.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReqIdChange {
+    pub node_id: Uuid,
+    pub name: String,
+    pub from: Option<String>,
+    pub to: String,
+    /// False for dry runs.
+    pub applied: bool,
+}
+
+/// A source document with its sections.
+pub struct SourceDocument<'a> {
+    pub document: &'a Document,
+    pub sections: &'a [DocumentSection],
+}
+
+/// Plan section-based ids for `requirements`, numbered in the given order
+/// from fresh counters. A requirement is renumbered when its source
+/// (`source`, or `meta.source_doc`) names one of `documents` and its text
+/// is found in one of that document's sections; the rest keep their ids
+/// and stay reserved. Returns the changes (requirements whose id stays the
+/// same are left out) and the counters to store afterwards.
+pub fn plan_renumbering(
+    requirements: &[Node],
+    documents: &[SourceDocument<'_>],
+    pattern: &str,
+) -> (Vec<ReqIdChange>, HashMap<String, i64>) {
+    let by_name: HashMap<&str, &SourceDocument<'_>> = documents
+        .iter()
+        .map(|d| (d.document.name.as_str(), d))
+        .collect();
+
+    let mut placed = Vec::new();
+    let mut kept = HashSet::new();
+    for node in requirements {
+        let NodeData::Requirement(req) = &node.data else {
+            continue;
+        };
+        let source = req
+            .source
+            .as_deref()
+            .or_else(|| node.meta.get("source_doc").and_then(|v| v.as_str()))
+            .and_then(|name| by_name.get(name.trim()));
+        let text = req.text.as_deref().unwrap_or(&node.description);
+        let section = source.and_then(|doc| {
+            section_containing(doc.sections, text).map(|s| (doc_abbrev(doc.document), s))
+        });
+        match section {
+            Some((abbrev, section)) => placed.push((node, req, abbrev, section)),
+            None => kept.extend(req.req_id.clone()),
+        }
+    }
+
+    let mut numbering = SectionNumbering::new(pattern, HashMap::new(), kept);
+    let changes = placed
+        .into_iter()
+        .filter_map(|(node, req, abbrev, section)| {
+            let to = numbering.next(&abbrev, &section.section_ref);
+            (req.req_id.as_deref() != Some(to.as_str())).then(|| ReqIdChange {
+                node_id: node.id,
+                name: node.name.clone(),
+                from: req.req_id.clone(),
+                to,
+                applied: false,
+            })
+        })
+        .collect();
+    (changes, numbering.into_counters())
+}
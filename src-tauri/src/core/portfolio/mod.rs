@@ -0,0 +1,35 @@
+/// Cross-project health table for the multi-program view. The numbers come
+/// from `Store::portfolio_summary`; this only flattens them for export.
+use crate::core::model::ProjectHealth;
+use crate::core::rollup::csv_field;
+
+/// One row per project, percentages to one decimal place, dates as RFC 3339.
+pub fn to_csv(rows: &[ProjectHealth]) -> String {
+    let mut out = String::from(
+        "project,requirements,draft,approved,obsolete,satisfied_pct,verified_pct,\
+         approved_pct,verification_method_pct,allocated_pct,unresolved_suspect_links,\
+         open_reviews,last_baseline_at,last_activity_at\n",
+    );
+    let pct = |v: f64| format!("{v:.1}");
+    for row in rows {
+        let fields = [
+            row.name.clone(),
+            row.coverage.total_requirements.to_string(),
+            row.requirements.draft.to_string(),
+            row.requirements.approved.to_string(),
+            row.requirements.obsolete.to_string(),
+            pct(row.coverage.satisfied_pct),
+            pct(row.coverage.verified_pct),
+            pct(row.coverage.approved_pct),
+            pct(row.coverage.verification_method_pct),
+            pct(row.coverage.allocated_pct),
+            row.unresolved_suspect_links.to_string(),
+            row.open_reviews.to_string(),
+            row.last_baseline_at.map(|t| t.to_rfc3339()).unwrap_or_default(),
+            row.last_activity_at.to_rfc3339(),
+        ];
+        out.push_str(&fields.iter().map(|f| csv_field(f)).collect::<Vec<_>>().join(","));
+        out.push('\n');
+    }
+    out
+}
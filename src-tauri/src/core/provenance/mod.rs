@@ -0,0 +1,267 @@
+/// Requirement provenance — maps a requirement's free-text `source`
+/// (e.g. "SOW_v3.pdf §3.2.1") back to the Document / DocumentSection rows
+/// it came from. Pure functions; the commands layer does the store lookups.
+use crate::core::model::{Document, DocumentSection};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A document section resolved from a requirement's provenance.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceMatch {
+    pub document_id: Uuid,
+    pub document_name: String,
+    pub section_id: Option<Uuid>,
+    pub section_ref: String,
+    pub section_title: String,
+    pub section_body: String,
+    /// 1.0 for an exact document and section match; lower for fuzzy ones.
+    pub confidence: f64,
+}
+
+/// A requirement that points at a given section.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SectionRequirementLink {
+    pub node_id: Uuid,
+    pub req_id: Option<String>,
+    pub name: String,
+    pub confidence: f64,
+}
+
+/// The two halves of a source string.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceReference {
+    pub document: Option<String>,
+    pub section_ref: Option<String>,
+}
+
+/// Split a source string into a document name and a section reference.
+/// Recognises "§3.2.1", "Section 3.2", "Sec. 3.2", "para 4.1" and a bare
+/// trailing dotted number ("SOW 3.2.1").
+pub fn parse_source_reference(source: &str) -> SourceReference {
+    let source = source.trim();
+    if source.is_empty() {
+        return SourceReference::default();
+    }
+
+    let markers = ["§", "section ", "sec. ", "sec ", "para. ", "para ", "paragraph "];
+    let marker_pos = markers
+        .iter()
+        .filter_map(|m| find_ignore_ascii_case(source, m).map(|pos| (pos, m.len())))
+        .min_by_key(|(pos, _)| *pos);
+
+    let (doc_part, ref_part) = match marker_pos {
+        Some((pos, len)) => (&source[..pos], Some(&source[pos + len..])),
+        None => match source.rsplit_once(char::is_whitespace) {
+            Some((head, tail)) if looks_like_section_ref(tail) => (head, Some(tail)),
+            _ => (source, None),
+        },
+    };
+
+    let document = doc_part
+        .trim()
+        .trim_end_matches([',', ';', ':', '-'])
+        .trim()
+        .to_string();
+    let section_ref = ref_part
+        .map(|r| {
+            r.trim()
+                .split(|c: char| c.is_whitespace() || c == ',' || c == ';')
+                .next()
+                .unwrap_or("")
+                .trim_end_matches('.')
+                .to_string()
+        })
+        .filter(|r| !r.is_empty());
+
+    SourceReference {
+        document: Some(document).filter(|d| !d.is_empty()),
+        section_ref,
+    }
+}
+
+/// Byte offset of the first match of `marker` in `haystack`, ignoring
+/// ASCII case. Offsets are into `haystack` itself, so they are safe to
+/// slice with; `to_lowercase` can change byte lengths ("İ").
+fn find_ignore_ascii_case(haystack: &str, marker: &str) -> Option<usize> {
+    haystack.char_indices().map(|(i, _)| i).find(|&i| {
+        haystack[i..]
+            .get(..marker.len())
+            .is_some_and(|w| w.eq_ignore_ascii_case(marker))
+    })
+}
+
+fn looks_like_section_ref(s: &str) -> bool {
+    let s = s.trim_end_matches('.');
+    !s.is_empty()
+        && s.chars().next().is_some_and(|c| c.is_ascii_digit())
+        && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '.')
+}
+
+/// Normalise a document name for comparison: lowercase, drop the file
+/// extension and any trailing version marker ("_v3", " rev B", "(2)").
+pub fn normalize_document_name(name: &str) -> String {
+    let mut n = name.trim().to_lowercase();
+
+    if let Some((stem, ext)) = n.rsplit_once('.') {
+        if !ext.is_empty() && ext.len() <= 5 && ext.chars().all(|c| c.is_ascii_alphanumeric()) {
+            n = stem.to_string();
+        }
+    }
+
+    // Strip "(2)" style copy suffixes.
+    if n.ends_with(')') {
+        if let Some(open) = n.rfind('(') {
+            n.truncate(open);
+        }
+    }
+
+    // Strip trailing version / revision tokens.
+    loop {
+        let trimmed = n.trim_end_matches([' ', '_', '-', '.']).to_string();
+        let split_at = trimmed
+            .rfind([' ', '_', '-'])
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        let token = &trimmed[split_at..];
+        let rev_code = token.strip_prefix("rev").map(|c| c.trim_start_matches('.'));
+        let is_version = (token.starts_with('v') && token[1..].chars().all(|c| c.is_ascii_digit() || c == '.') && token.len() > 1)
+            || rev_code.is_some_and(|code| code.is_empty() || is_revision_code(code))
+            || token == "final"
+            || token == "draft";
+        // "rev B", "rev 2": the code is its own token after a bare "rev".
+        let before = trimmed[..split_at].trim_end_matches([' ', '_', '-', '.']);
+        let rev_at = before.rfind([' ', '_', '-']).map(|i| i + 1).unwrap_or(0);
+        if rev_at > 0 && &before[rev_at..] == "rev" && is_revision_code(token) {
+            n = before[..rev_at].to_string();
+        } else if split_at > 0 && is_version {
+            n = trimmed[..split_at].to_string();
+        } else {
+            n = trimmed;
+            break;
+        }
+    }
+
+    n.chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A revision code after "rev": up to three digits ("2", "12"), or a letter
+/// with up to two digits ("b", "a1"). Words like "review" or "revenue"
+/// don't qualify.
+fn is_revision_code(code: &str) -> bool {
+    let mut chars = code.chars();
+    let rest = match chars.next() {
+        Some(c) if c.is_ascii_digit() => code,
+        Some(c) if c.is_ascii_alphabetic() => chars.as_str(),
+        _ => return false,
+    };
+    code.len() <= 3 && rest.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Confidence (0..=1) that `candidate` is the document named in a source string.
+pub fn document_name_confidence(source_doc: &str, candidate: &str) -> f64 {
+    if source_doc.trim().eq_ignore_ascii_case(candidate.trim()) {
+        return 1.0;
+    }
+    let a = normalize_document_name(source_doc);
+    let b = normalize_document_name(candidate);
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    if a == b {
+        return 0.85;
+    }
+    if a.contains(&b) || b.contains(&a) {
+        return 0.6;
+    }
+    0.0
+}
+
+/// Confidence (0..=1) that `section` is the one a parsed `section_ref` names.
+pub fn section_ref_confidence(section_ref: &str, section: &DocumentSection) -> f64 {
+    let wanted = section_ref.trim().trim_end_matches('.');
+    let have = section.section_ref.trim().trim_end_matches('.');
+    if wanted.is_empty() || have.is_empty() {
+        return 0.0;
+    }
+    if wanted.eq_ignore_ascii_case(have) {
+        1.0
+    } else if have.starts_with(&format!("{wanted}.")) {
+        // Source cites a parent heading; the section is one of its children.
+        0.5
+    } else {
+        0.0
+    }
+}
+
+/// Resolve a parsed source string against the project's documents and
+/// sections. Results are sorted by descending confidence.
+pub fn match_source_text(
+    reference: &SourceReference,
+    documents: &[Document],
+    sections: &[DocumentSection],
+) -> Vec<SourceMatch> {
+    let mut matches = Vec::new();
+
+    let Some(doc_name) = reference.document.as_deref() else {
+        return matches;
+    };
+
+    for doc in documents {
+        let doc_conf = document_name_confidence(doc_name, &doc.name);
+        if doc_conf <= 0.0 {
+            continue;
+        }
+
+        let doc_sections = sections.iter().filter(|s| s.document_id == doc.id);
+        let mut found_section = false;
+        if let Some(section_ref) = reference.section_ref.as_deref() {
+            for section in doc_sections {
+                let sec_conf = section_ref_confidence(section_ref, section);
+                if sec_conf > 0.0 {
+                    found_section = true;
+                    matches.push(section_match(doc, section, doc_conf * sec_conf));
+                }
+            }
+        }
+
+        if !found_section {
+            // Document matched but no section did — still useful to open the doc.
+            matches.push(SourceMatch {
+                document_id: doc.id,
+                document_name: doc.name.clone(),
+                section_id: None,
+                section_ref: reference.section_ref.clone().unwrap_or_default(),
+                section_title: String::new(),
+                section_body: String::new(),
+                confidence: doc_conf * 0.4,
+            });
+        }
+    }
+
+    matches.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+    matches
+}
+
+pub fn section_match(
+    doc: &Document,
+    section: &DocumentSection,
+    confidence: f64,
+) -> SourceMatch {
+    SourceMatch {
+        document_id: doc.id,
+        document_name: doc.name.clone(),
+        section_id: Some(section.id),
+        section_ref: section.section_ref.clone(),
+        section_title: section.title.clone(),
+        section_body: section.body.clone(),
+        confidence,
+    }
+}
+
+#[cfg(test)]
+mod tests;
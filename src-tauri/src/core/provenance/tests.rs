@@ -0,0 +1,55 @@
+use super::*;
+
+fn reference(document: Option<&str>, section_ref: Option<&str>) -> SourceReference {
+    SourceReference {
+        document: document.map(str::to_string),
+        section_ref: section_ref.map(str::to_string),
+    }
+}
+
+#[test]
+fn parses_section_markers_in_any_case() {
+    assert_eq!(
+        parse_source_reference("SOW_v3.pdf §3.2.1"),
+        reference(Some("SOW_v3.pdf"), Some("3.2.1"))
+    );
+    assert_eq!(
+        parse_source_reference("Interface Spec, SECTION 4.1."),
+        reference(Some("Interface Spec"), Some("4.1"))
+    );
+    assert_eq!(
+        parse_source_reference("SOW 3.2.1"),
+        reference(Some("SOW"), Some("3.2.1"))
+    );
+    assert_eq!(parse_source_reference("  "), SourceReference::default());
+}
+
+#[test]
+fn text_whose_lowercase_changes_length_does_not_panic() {
+    // "İ" lowercases to three bytes, shifting every offset after it.
+    assert_eq!(
+        parse_source_reference("İstanbul Şartnamesi Section 2.4"),
+        reference(Some("İstanbul Şartnamesi"), Some("2.4"))
+    );
+    assert_eq!(
+        parse_source_reference("İİİ sec 1"),
+        reference(Some("İİİ"), Some("1"))
+    );
+}
+
+#[test]
+fn only_real_revision_markers_are_stripped_from_document_names() {
+    assert_eq!(
+        normalize_document_name("Critical Design Review.pdf"),
+        "critical design review"
+    );
+    assert_eq!(
+        normalize_document_name("Revenue Plan - Revision"),
+        "revenue plan revision"
+    );
+    assert!(document_name_confidence("Critical Design Review.pdf", "Critical Design") < 0.85);
+    assert_eq!(normalize_document_name("SOW rev B.docx"), "sow");
+    assert_eq!(normalize_document_name("SOW_revB"), "sow");
+    assert_eq!(normalize_document_name("SOW rev 12"), "sow");
+    assert_eq!(normalize_document_name("SOW rev"), "sow");
+}
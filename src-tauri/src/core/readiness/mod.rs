@@ -0,0 +1,240 @@
+/// Readiness gates: the checklist run before cutting a baseline or a
+/// delivery, as pass/fail criteria with the offending counts.
+///
+/// Each profile has its criteria list and thresholds in settings (project,
+/// then global):
+///
+/// - `readiness.<profile>.criteria`: comma-separated criterion keys;
+/// - `readiness.<profile>.<criterion>`: the threshold, a maximum count for
+///   every criterion except `verified_coverage`, which is a minimum
+///   percentage.
+///
+/// Both profiles default to zero validation errors, suspect links,
+/// unapproved requirements and open reviews; `delivery` also wants every
+/// requirement verified. Obsolete requirements count as waived.
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadinessProfile {
+    Baseline,
+    Delivery,
+}
+
+impl ReadinessProfile {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim() {
+            "baseline" => Some(Self::Baseline),
+            "delivery" => Some(Self::Delivery),
+            _ => None,
+        }
+    }
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::Baseline => "baseline",
+            Self::Delivery => "delivery",
+        }
+    }
+
+    pub fn default_criteria(self) -> &'static [Criterion] {
+        match self {
+            Self::Baseline => &Criterion::CHECKLIST,
+            Self::Delivery => &Criterion::ALL,
+        }
+    }
+
+    /// Setting key holding this profile's criteria list.
+    pub fn criteria_setting(self) -> String {
+        format!("readiness.{}.criteria", self.key())
+    }
+
+    /// Setting key holding one criterion's threshold for this profile.
+    pub fn threshold_setting(self, criterion: Criterion) -> String {
+        format!("readiness.{}.{}", self.key(), criterion.key())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Criterion {
+    ValidationErrors,
+    SuspectLinks,
+    UnapprovedRequirements,
+    OpenReviews,
+    VerifiedCoverage,
+}
+
+impl Criterion {
+    /// The manual pre-baseline checklist.
+    pub const CHECKLIST: [Criterion; 4] = [
+        Criterion::ValidationErrors,
+        Criterion::SuspectLinks,
+        Criterion::UnapprovedRequirements,
+        Criterion::OpenReviews,
+    ];
+
+    pub const ALL: [Criterion; 5] = [
+        Criterion::ValidationErrors,
+        Criterion::SuspectLinks,
+        Criterion::UnapprovedRequirements,
+        Criterion::OpenReviews,
+        Criterion::VerifiedCoverage,
+    ];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::ValidationErrors => "validation_errors",
+            Self::SuspectLinks => "suspect_links",
+            Self::UnapprovedRequirements => "unapproved_requirements",
+            Self::OpenReviews => "open_reviews",
+            Self::VerifiedCoverage => "verified_coverage",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.key() == s.trim())
+    }
+
+    /// `verified_coverage` must reach its threshold; the others must not
+    /// exceed theirs.
+    pub fn is_minimum(self) -> bool {
+        matches!(self, Self::VerifiedCoverage)
+    }
+
+    pub fn default_threshold(self) -> f64 {
+        match self {
+            Self::VerifiedCoverage => 100.0,
+            _ => 0.0,
+        }
+    }
+}
+
+/// A profile's criteria with their thresholds, in evaluation order.
+#[derive(Debug, Clone)]
+pub struct ReadinessConfig {
+    pub profile: ReadinessProfile,
+    pub criteria: Vec<(Criterion, f64)>,
+}
+
+impl ReadinessConfig {
+    /// Every setting key `from_lookup` may read for `profile`.
+    pub fn setting_keys(profile: ReadinessProfile) -> Vec<String> {
+        std::iter::once(profile.criteria_setting())
+            .chain(
+                Criterion::ALL
+                    .into_iter()
+                    .map(|c| profile.threshold_setting(c)),
+            )
+            .collect()
+    }
+
+    /// Unknown criterion keys are ignored; an empty or unusable list falls
+    /// back to the profile's defaults, as do unparsable thresholds.
+    pub fn from_lookup(profile: ReadinessProfile, lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let unquote = |v: String| v.trim().trim_matches('"').to_string();
+        let mut criteria: Vec<Criterion> = Vec::new();
+        if let Some(list) = lookup(&profile.criteria_setting()).map(unquote) {
+            for criterion in list.split(',').filter_map(Criterion::parse) {
+                if !criteria.contains(&criterion) {
+                    criteria.push(criterion);
+                }
+            }
+        }
+        if criteria.is_empty() {
+            criteria = profile.default_criteria().to_vec();
+        }
+        let criteria = criteria
+            .into_iter()
+            .map(|c| {
+                let threshold = lookup(&profile.threshold_setting(c))
+                    .map(unquote)
+                    .and_then(|v| v.parse::<f64>().ok())
+                    .filter(|v| v.is_finite() && *v >= 0.0)
+                    .unwrap_or_else(|| c.default_threshold());
+                (c, threshold)
+            })
+            .collect();
+        Self { profile, criteria }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadinessSample {
+    pub node_id: Option<Uuid>,
+    pub label: String,
+}
+
+/// What a criterion measured: a count, or a percentage for coverage.
+#[derive(Debug, Clone, Default)]
+pub struct Measurement {
+    pub value: f64,
+    pub samples: Vec<ReadinessSample>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CriterionResult {
+    pub criterion: Criterion,
+    pub passed: bool,
+    pub value: f64,
+    pub threshold: f64,
+    /// A few offending items; empty when passed.
+    pub samples: Vec<ReadinessSample>,
+}
+
+impl CriterionResult {
+    pub fn judge(criterion: Criterion, threshold: f64, measurement: Measurement) -> Self {
+        let passed = if criterion.is_minimum() {
+            measurement.value >= threshold
+        } else {
+            measurement.value <= threshold
+        };
+        Self {
+            criterion,
+            passed,
+            value: measurement.value,
+            threshold,
+            samples: if passed {
+                Vec::new()
+            } else {
+                measurement.samples
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadinessReport {
+    pub profile: ReadinessProfile,
+    pub passed: bool,
+    pub criteria: Vec<CriterionResult>,
+}
+
+impl ReadinessReport {
+    pub fn new(profile: ReadinessProfile, criteria: Vec<CriterionResult>) -> Self {
+        Self {
+            profile,
+            passed: criteria.iter().all(|c| c.passed),
+            criteria,
+        }
+    }
+
+    /// "suspect_links: 3 (max 0); verified_coverage: 82.5 (min 100)".
+    pub fn failure_summary(&self) -> String {
+        self.criteria
+            .iter()
+            .filter(|c| !c.passed)
+            .map(|c| {
+                let bound = if c.criterion.is_minimum() {
+                    "min"
+                } else {
+                    "max"
+                };
+                let value = (c.value * 10.0).round() / 10.0;
+                format!("{}: {value} ({bound} {})", c.criterion.key(), c.threshold)
+            })
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
@@ -0,0 +1,173 @@
+/// Cross-references to other requirements inside requirement text, e.g.
+/// "see REQ-014". A mention is any word shaped like the project's req_id
+/// scheme: "REQ-{seq}" always, plus the `req_id.pattern` of a section-based
+/// project ("SOW-3.2.1-R01"). Finding mentions is pure; the commands layer
+/// resolves them through the store's req_id index. Offsets and lengths
+/// count characters, as in `core::terminology`.
+use crate::core::model::{Edge, Node, NodeData};
+use crate::core::numbering::{ReqIdScheme, DEFAULT_PATTERN};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Project or global setting; "true" adds `REQ_BROKEN_REFERENCE` issues to
+/// full validation.
+pub const VALIDATION_SETTING: &str = "validation.broken_references";
+
+/// Shape of ids the simple scheme hands out.
+pub const SIMPLE_PATTERN: &str = "REQ-{seq}";
+
+/// A req_id mentioned in requirement text.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReqIdMention {
+    /// Character offset into the requirement text.
+    pub offset: usize,
+    /// Length of `req_id` in characters.
+    pub length: usize,
+    pub req_id: String,
+    /// The requirement with this req_id; `None` for a broken reference.
+    pub node_id: Option<Uuid>,
+    pub name: Option<String>,
+}
+
+/// Result of `resolve_references`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReferenceResolution {
+    pub node_id: Uuid,
+    pub mentions: Vec<ReqIdMention>,
+    /// «traces» edges created for resolved mentions, when asked for.
+    pub created_edges: Vec<Edge>,
+}
+
+/// The patterns mentions are matched against for `scheme`.
+pub fn scheme_patterns(scheme: ReqIdScheme, pattern: Option<&str>) -> Vec<String> {
+    let mut patterns = vec![SIMPLE_PATTERN.to_string()];
+    if scheme == ReqIdScheme::SectionBased {
+        let pattern = pattern.map(str::trim).filter(|p| !p.is_empty());
+        patterns.push(pattern.unwrap_or(DEFAULT_PATTERN).to_string());
+    }
+    patterns
+}
+
+/// The text references are looked for in: the requirement text, or the
+/// description when the text is empty. `None` for other kinds.
+pub fn requirement_text(node: &Node) -> Option<&str> {
+    crate::core::terminology::requirement_text(node)
+}
+
+/// The node's own req_id, which is never reported as a mention of itself.
+pub fn own_req_id(node: &Node) -> Option<&str> {
+    match &node.data {
+        NodeData::Requirement(r) => r.req_id.as_deref().map(str::trim),
+        _ => None,
+    }
+}
+
+/// Every word of `text` that matches one of `patterns`, in order, all
+/// unresolved. A word is a run of letters, digits, '-', '.' and '_', with
+/// trailing punctuation ("REQ-014.") left off.
+pub fn find_mentions(text: &str, patterns: &[String]) -> Vec<ReqIdMention> {
+    let templates: Vec<Vec<Segment>> = patterns.iter().map(|p| parse_pattern(p)).collect();
+    let chars: Vec<char> = text.chars().collect();
+    let is_token = |c: char| c.is_alphanumeric() || matches!(c, '-' | '.' | '_');
+
+    let mut mentions = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if !is_token(chars[i]) {
+            i += 1;
+            continue;
+        }
+        let run_end = i + chars[i..].iter().take_while(|&&c| is_token(c)).count();
+        let (mut start, mut end) = (i, run_end);
+        while start < end && !chars[start].is_alphanumeric() {
+            start += 1;
+        }
+        while end > start && !chars[end - 1].is_alphanumeric() {
+            end -= 1;
+        }
+        let word = &chars[start..end];
+        if !word.is_empty() && templates.iter().any(|t| matches_pattern(t, word)) {
+            mentions.push(ReqIdMention {
+                offset: start,
+                length: end - start,
+                req_id: word.iter().collect(),
+                node_id: None,
+                name: None,
+            });
+        }
+        i = run_end;
+    }
+    mentions
+}
+
+enum Segment {
+    Literal(Vec<char>),
+    /// `{doc_abbrev}`: upper-case letters and digits.
+    Abbrev,
+    /// `{section_ref}`: digits separated by single dots.
+    SectionRef,
+    /// `{seq}`: digits.
+    Seq,
+}
+
+fn parse_pattern(pattern: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut literal = String::new();
+    let mut rest = pattern.trim();
+    while !rest.is_empty() {
+        let placeholder = if let Some(after) = rest.strip_prefix("{doc_abbrev}") {
+            Some((Segment::Abbrev, after))
+        } else if let Some(after) = rest.strip_prefix("{section_ref}") {
+            Some((Segment::SectionRef, after))
+        } else {
+            rest.strip_prefix("{seq}")
+                .map(|after| (Segment::Seq, after))
+        };
+        match placeholder {
+            Some((segment, after)) => {
+                if !literal.is_empty() {
+                    segments.push(Segment::Literal(literal.chars().collect()));
+                    literal.clear();
+                }
+                segments.push(segment);
+                rest = after;
+            }
+            None => {
+                let c = rest.chars().next().unwrap_or_default();
+                literal.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    if !literal.is_empty() {
+        segments.push(Segment::Literal(literal.chars().collect()));
+    }
+    segments
+}
+
+/// Whether all of `word` matches `segments`, trying every split between
+/// neighbouring placeholders.
+fn matches_pattern(segments: &[Segment], word: &[char]) -> bool {
+    let Some((first, rest)) = segments.split_first() else {
+        return word.is_empty();
+    };
+    if let Segment::Literal(lit) = first {
+        return word.starts_with(lit) && matches_pattern(rest, &word[lit.len()..]);
+    }
+    (1..=word.len()).any(|n| fits(first, &word[..n]) && matches_pattern(rest, &word[n..]))
+}
+
+fn fits(segment: &Segment, part: &[char]) -> bool {
+    match segment {
+        Segment::Literal(lit) => part == lit.as_slice(),
+        Segment::Abbrev => part
+            .iter()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit()),
+        Segment::Seq => part.iter().all(char::is_ascii_digit),
+        Segment::SectionRef => {
+            let s: String = part.iter().collect();
+            s.split('.')
+                .all(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+        }
+    }
+}
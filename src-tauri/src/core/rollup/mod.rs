@@ -0,0 +1,308 @@
+/// Value-property rollups (mass, power, cost budgets) over block
+/// composition trees. Pure functions over nodes/edges/properties; the
+/// commands layer does the store lookups.
+///
+/// The tree follows «composes» edges from whole (source) to part (target).
+/// For `Sum`, each part's subtotal is multiplied by its multiplicity — taken
+/// from the edge's `meta.multiplicity` if present, otherwise the part
+/// block's own multiplicity — so a "4" on a wheel block counts four wheels.
+/// `Max` reports the largest single-instance value and ignores multiplicity.
+use crate::core::model::{BlockProperty, Edge, EdgeKind, Node, NodeData, NodeKind};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RollupAggregate {
+    Sum,
+    Max,
+}
+
+impl RollupAggregate {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "sum" => Some(Self::Sum),
+            "max" => Some(Self::Max),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RollupIssueKind {
+    /// A leaf block has no property of that name.
+    Missing,
+    /// The property value isn't a number (e.g. "TBD").
+    NonNumericValue,
+    /// Multiplicity like "0..*" that can't be multiplied; counted as 1.
+    NonNumericMultiplicity,
+    /// The property's unit differs from the first unit seen.
+    UnitMismatch,
+    /// The block was reached again through its own subtree; not descended.
+    Cycle,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RollupIssue {
+    pub block_id: Uuid,
+    pub block_name: String,
+    pub kind: RollupIssueKind,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RollupNode {
+    pub block_id: Uuid,
+    pub block_name: String,
+    pub multiplicity: f64,
+    /// Multiplicity as written, when one was given.
+    pub multiplicity_raw: Option<String>,
+    /// The block's own value, if it has a numeric one.
+    pub own_value: Option<f64>,
+    /// The property value as stored, numeric or not.
+    pub raw_value: Option<String>,
+    pub unit: String,
+    /// Own value aggregated with all parts, for one instance of this block.
+    pub subtotal: f64,
+    /// What this block adds to its parent (`subtotal × multiplicity` for sums).
+    pub contribution: f64,
+    pub missing: bool,
+    pub children: Vec<RollupNode>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RollupResult {
+    pub property_name: String,
+    pub aggregate: RollupAggregate,
+    /// Unit of the first property encountered; empty if none had one.
+    pub unit: String,
+    pub total: f64,
+    pub root: RollupNode,
+    pub issues: Vec<RollupIssue>,
+}
+
+pub fn rollup(
+    root_id: Uuid,
+    nodes: &[Node],
+    edges: &[Edge],
+    properties: &[BlockProperty],
+    property_name: &str,
+    aggregate: RollupAggregate,
+) -> Result<RollupResult, String> {
+    let root = nodes
+        .iter()
+        .find(|n| n.id == root_id)
+        .ok_or_else(|| format!("block {root_id} not found"))?;
+    if root.kind != NodeKind::Block {
+        return Err(format!("'{}' is a {}, not a block", root.name, root.kind));
+    }
+
+    let mut walk = Walk {
+        nodes,
+        edges,
+        properties,
+        property_name: property_name.trim(),
+        aggregate,
+        unit: None,
+        issues: Vec::new(),
+        path: HashSet::new(),
+    };
+    let root_node = walk.visit(root, None);
+
+    Ok(RollupResult {
+        property_name: property_name.trim().to_string(),
+        aggregate,
+        unit: walk.unit.unwrap_or_default(),
+        total: root_node.subtotal,
+        root: root_node,
+        issues: walk.issues,
+    })
+}
+
+/// Flatten a rollup into CSV, one row per block in depth-first order.
+pub fn to_csv(result: &RollupResult) -> String {
+    let mut out = String::from(
+        "depth,path,block,multiplicity,value,unit,subtotal,contribution,status\n",
+    );
+    fn row(out: &mut String, node: &RollupNode, depth: usize, parent_path: &str, unit: &str) {
+        let path = if parent_path.is_empty() {
+            node.block_name.clone()
+        } else {
+            format!("{parent_path}/{}", node.block_name)
+        };
+        let status = if node.missing {
+            "missing"
+        } else if node.own_value.is_none() {
+            "non_numeric"
+        } else {
+            "ok"
+        };
+        let fields = [
+            depth.to_string(),
+            path.clone(),
+            node.block_name.clone(),
+            node.multiplicity_raw.clone().unwrap_or_else(|| "1".to_string()),
+            node.raw_value.clone().unwrap_or_default(),
+            if node.unit.is_empty() { unit.to_string() } else { node.unit.clone() },
+            node.subtotal.to_string(),
+            node.contribution.to_string(),
+            status.to_string(),
+        ];
+        out.push_str(&fields.iter().map(|f| csv_field(f)).collect::<Vec<_>>().join(","));
+        out.push('\n');
+        for child in &node.children {
+            row(out, child, depth + 1, &path, unit);
+        }
+    }
+    row(&mut out, &result.root, 0, "", &result.unit);
+    out
+}
+
+pub(crate) fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+struct Walk<'a> {
+    nodes: &'a [Node],
+    edges: &'a [Edge],
+    properties: &'a [BlockProperty],
+    property_name: &'a str,
+    aggregate: RollupAggregate,
+    unit: Option<String>,
+    issues: Vec<RollupIssue>,
+    path: HashSet<Uuid>,
+}
+
+impl Walk<'_> {
+    fn visit(&mut self, block: &Node, via: Option<&Edge>) -> RollupNode {
+        let (nodes, edges, property_name) = (self.nodes, self.edges, self.property_name);
+        let (multiplicity, multiplicity_raw) = self.multiplicity(block, via);
+        let prop = self
+            .properties
+            .iter()
+            .find(|p| p.block_node_id == block.id && p.name.trim().eq_ignore_ascii_case(property_name));
+
+        let mut own_value = None;
+        let mut unit = String::new();
+        if let Some(p) = prop {
+            unit = p.unit.trim().to_string();
+            match parse_number(&p.value) {
+                Some(v) => own_value = Some(v),
+                None => self.issue(block, RollupIssueKind::NonNumericValue, format!("value '{}' is not numeric", p.value)),
+            }
+            if !unit.is_empty() {
+                match &self.unit {
+                    None => self.unit = Some(unit.clone()),
+                    Some(expected) if !expected.eq_ignore_ascii_case(&unit) => {
+                        let detail = format!("unit '{unit}' differs from '{expected}'");
+                        self.issue(block, RollupIssueKind::UnitMismatch, detail);
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+
+        let mut children = Vec::new();
+        let cyclic = !self.path.insert(block.id);
+        if cyclic {
+            self.issue(block, RollupIssueKind::Cycle, "composition cycle; subtree skipped".to_string());
+        } else {
+            let part_edges = edges
+                .iter()
+                .filter(|e| e.kind == EdgeKind::Composes && e.source_id == block.id);
+            for edge in part_edges {
+                if let Some(part) = nodes
+                    .iter()
+                    .find(|n| n.id == edge.target_id && n.kind == NodeKind::Block)
+                {
+                    children.push(self.visit(part, Some(edge)));
+                }
+            }
+            self.path.remove(&block.id);
+        }
+
+        if prop.is_none() && children.is_empty() && !cyclic {
+            self.issue(block, RollupIssueKind::Missing, format!("no '{property_name}' property"));
+        }
+
+        let own = own_value.unwrap_or(0.0);
+        let subtotal = match self.aggregate {
+            RollupAggregate::Sum => own + children.iter().map(|c| c.contribution).sum::<f64>(),
+            RollupAggregate::Max => children.iter().map(|c| c.contribution).fold(own, f64::max),
+        };
+        let contribution = match self.aggregate {
+            RollupAggregate::Sum => subtotal * multiplicity,
+            RollupAggregate::Max => subtotal,
+        };
+
+        RollupNode {
+            block_id: block.id,
+            block_name: block.name.clone(),
+            multiplicity,
+            multiplicity_raw,
+            own_value,
+            raw_value: prop.map(|p| p.value.clone()),
+            unit,
+            subtotal,
+            contribution,
+            missing: prop.is_none(),
+            children,
+        }
+    }
+
+    fn multiplicity(&mut self, block: &Node, via: Option<&Edge>) -> (f64, Option<String>) {
+        let from_edge = via.and_then(|e| e.meta.get("multiplicity")).and_then(|v| match v {
+            serde_json::Value::String(s) => Some(s.clone()),
+            serde_json::Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        });
+        let from_block = match &block.data {
+            NodeData::Block(b) => b.multiplicity.clone(),
+            _ => None,
+        };
+        let Some(raw) = from_edge.or(from_block).filter(|s| !s.trim().is_empty()) else {
+            return (1.0, None);
+        };
+        // The root's own multiplicity doesn't scale the total.
+        if via.is_none() {
+            return (1.0, Some(raw));
+        }
+        match parse_multiplicity(&raw) {
+            Some(m) => (m, Some(raw)),
+            None => {
+                self.issue(block, RollupIssueKind::NonNumericMultiplicity, format!("multiplicity '{raw}' counted as 1"));
+                (1.0, Some(raw))
+            }
+        }
+    }
+
+    fn issue(&mut self, block: &Node, kind: RollupIssueKind, detail: String) {
+        self.issues.push(RollupIssue {
+            block_id: block.id,
+            block_name: block.name.clone(),
+            kind,
+            detail,
+        });
+    }
+}
+
+/// "12.5", "12.5 kg", "1,200" → number; "TBD" → None.
+fn parse_number(s: &str) -> Option<f64> {
+    let token = s.split_whitespace().next()?.replace(',', "");
+    token.parse::<f64>().ok().filter(|v| v.is_finite())
+}
+
+/// "4" → 4, "2..2" → 2; open or ranged multiplicities ("0..*", "1..4") → None.
+fn parse_multiplicity(s: &str) -> Option<f64> {
+    let s = s.trim();
+    let (lo, hi) = s.split_once("..").unwrap_or((s, s));
+    let lo: f64 = lo.trim().parse().ok()?;
+    let hi: f64 = hi.trim().parse().ok()?;
+    (lo == hi && lo >= 0.0).then_some(lo)
+}
@@ -0,0 +1,256 @@
+/// Bulk generation of simulation scenario events from periodic or burst
+/// patterns, e.g. "a telemetry packet into the RF block every 100 ms for
+/// 60 s". Pure expansion; the commands layer checks block ids against the
+/// project and appends the result to the scenario.
+///
+/// A pattern fires at `start_ms`, then every `period_ms`, until `count`
+/// occurrences or `end_ms` (inclusive); with neither it runs to the end of
+/// the scenario. Each occurrence emits `burst_size` events spaced
+/// `burst_spacing_ms` apart. Times are rounded to the microsecond, so
+/// collisions are detected despite floating-point drift.
+use crate::core::model::SimulationScenarioEvent;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+/// Upper bound on the events one call may generate.
+pub const MAX_GENERATED_EVENTS: usize = 100_000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventPattern {
+    pub block_id: Uuid,
+    pub signal_type: String,
+    #[serde(default)]
+    pub start_ms: f64,
+    #[serde(default)]
+    pub period_ms: f64,
+    pub count: Option<u32>,
+    pub end_ms: Option<f64>,
+    /// Events per occurrence; more than one makes a burst.
+    pub burst_size: Option<u32>,
+    pub burst_spacing_ms: Option<f64>,
+    /// Template for each event's value. Inside strings, "{index}" is the
+    /// event's position in the pattern (from 0) and "{time_ms}" its time; a
+    /// string that is exactly one placeholder becomes that number.
+    #[serde(default)]
+    pub value: Value,
+}
+
+/// A pattern that can't be expanded.
+#[derive(Debug, Clone)]
+pub struct PatternError {
+    /// Index into the patterns passed in.
+    pub index: usize,
+    pub field: &'static str,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventGeneration {
+    /// Events appended (or, for a dry run, that would be), by time.
+    pub events: Vec<SimulationScenarioEvent>,
+    /// Generated events dropped because an event with the same time, block
+    /// and signal type already existed or was generated earlier.
+    pub duplicates: usize,
+    pub dry_run: bool,
+}
+
+/// Expand `patterns` against a scenario of `duration_ms` whose blocks are
+/// `block_ids`. Every error is reported, not just the first.
+pub fn expand(
+    patterns: &[EventPattern],
+    duration_ms: i64,
+    block_ids: &HashSet<Uuid>,
+) -> Result<Vec<SimulationScenarioEvent>, Vec<PatternError>> {
+    let duration = duration_ms as f64;
+    let mut errors = Vec::new();
+    let mut events = Vec::new();
+    for (index, pattern) in patterns.iter().enumerate() {
+        let mut error = |field: &'static str, message: String| {
+            errors.push(PatternError {
+                index,
+                field,
+                message,
+            });
+        };
+        if !block_ids.contains(&pattern.block_id) {
+            let message = format!("block {} is not in this project", pattern.block_id);
+            error("block_id", message);
+        }
+        if pattern.signal_type.trim().is_empty() {
+            error("signal_type", "the signal type must not be empty".into());
+        }
+        let times = match occurrence_times(pattern, duration) {
+            Ok(times) => times,
+            Err((field, message)) => {
+                error(field, message);
+                continue;
+            }
+        };
+        if events.len() + times.len() > MAX_GENERATED_EVENTS {
+            let message =
+                format!("patterns would generate more than {MAX_GENERATED_EVENTS} events");
+            error("count", message);
+            continue;
+        }
+        for (i, time_ms) in times.into_iter().enumerate() {
+            events.push(SimulationScenarioEvent {
+                time_ms,
+                block_id: pattern.block_id,
+                signal_type: pattern.signal_type.trim().to_string(),
+                value: fill_template(&pattern.value, i, time_ms),
+            });
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    events.sort_by(|a, b| a.time_ms.total_cmp(&b.time_ms));
+    Ok(events)
+}
+
+/// `generated` without events that collide with `existing` or with an
+/// earlier generated one, and how many were dropped.
+pub fn dedup_against(
+    existing: &[SimulationScenarioEvent],
+    generated: Vec<SimulationScenarioEvent>,
+) -> (Vec<SimulationScenarioEvent>, usize) {
+    let key = |e: &SimulationScenarioEvent| {
+        (
+            round_ms(e.time_ms).to_bits(),
+            e.block_id,
+            e.signal_type.clone(),
+        )
+    };
+    let mut seen: HashSet<_> = existing.iter().map(key).collect();
+    let total = generated.len();
+    let kept: Vec<_> = generated
+        .into_iter()
+        .filter(|e| seen.insert(key(e)))
+        .collect();
+    let dropped = total - kept.len();
+    (kept, dropped)
+}
+
+fn round_ms(ms: f64) -> f64 {
+    (ms * 1000.0).round() / 1000.0
+}
+
+/// The times a pattern fires at, or the offending field and why.
+fn occurrence_times(
+    pattern: &EventPattern,
+    duration: f64,
+) -> Result<Vec<f64>, (&'static str, String)> {
+    let finite = |v: f64| v.is_finite();
+    if !finite(pattern.start_ms) || pattern.start_ms < 0.0 || pattern.start_ms > duration {
+        return Err((
+            "start_ms",
+            format!("start must be within 0..={duration} ms"),
+        ));
+    }
+    if !finite(pattern.period_ms) || pattern.period_ms < 0.0 {
+        return Err(("period_ms", "the period must not be negative".into()));
+    }
+    let burst_size = pattern.burst_size.unwrap_or(1);
+    if burst_size == 0 {
+        return Err(("burst_size", "a burst needs at least one event".into()));
+    }
+    let spacing = pattern.burst_spacing_ms.unwrap_or(0.0);
+    if !finite(spacing) || spacing < 0.0 {
+        return Err((
+            "burst_spacing_ms",
+            "the burst spacing must not be negative".into(),
+        ));
+    }
+    if burst_size > 1 && spacing == 0.0 {
+        return Err((
+            "burst_spacing_ms",
+            "a burst needs a positive spacing".into(),
+        ));
+    }
+    let burst_length = spacing * f64::from(burst_size - 1);
+    if pattern.period_ms > 0.0 && burst_length >= pattern.period_ms {
+        return Err((
+            "burst_spacing_ms",
+            "a burst must end before the next one starts".into(),
+        ));
+    }
+
+    let occurrences = match (pattern.count, pattern.end_ms) {
+        (Some(_), Some(_)) => {
+            return Err(("end_ms", "give either a count or an end, not both".into()));
+        }
+        (Some(0), None) => return Err(("count", "count must be at least 1".into())),
+        (Some(count), None) => {
+            if count > 1 && pattern.period_ms == 0.0 {
+                return Err((
+                    "period_ms",
+                    "repeating events need a positive period".into(),
+                ));
+            }
+            count as usize
+        }
+        (None, end) => {
+            let end = end.unwrap_or(duration);
+            if !finite(end) || end < pattern.start_ms || end > duration {
+                let message = format!("end must be within {}..={duration} ms", pattern.start_ms);
+                return Err(("end_ms", message));
+            }
+            if pattern.period_ms == 0.0 {
+                if end > pattern.start_ms {
+                    return Err((
+                        "period_ms",
+                        "repeating events need a positive period".into(),
+                    ));
+                }
+                1
+            } else {
+                let span = (end - pattern.start_ms) / pattern.period_ms;
+                (span + 1e-9).floor() as usize + 1
+            }
+        }
+    };
+    if occurrences.saturating_mul(burst_size as usize) > MAX_GENERATED_EVENTS {
+        let message = format!("the pattern would generate more than {MAX_GENERATED_EVENTS} events");
+        return Err(("count", message));
+    }
+
+    let mut times = Vec::with_capacity(occurrences * burst_size as usize);
+    for n in 0..occurrences {
+        let at = pattern.start_ms + pattern.period_ms * n as f64;
+        for b in 0..burst_size {
+            times.push(round_ms(at + spacing * f64::from(b)));
+        }
+    }
+    if let Some(last) = times.last().filter(|&&t| t > duration) {
+        return Err((
+            "count",
+            format!("the last event, at {last} ms, is past {duration} ms"),
+        ));
+    }
+    Ok(times)
+}
+
+fn fill_template(template: &Value, index: usize, time_ms: f64) -> Value {
+    match template {
+        Value::String(s) if s == "{index}" => Value::from(index),
+        Value::String(s) if s == "{time_ms}" => Value::from(time_ms),
+        Value::String(s) => Value::String(
+            s.replace("{index}", &index.to_string())
+                .replace("{time_ms}", &time_ms.to_string()),
+        ),
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|v| fill_template(v, index, time_ms))
+                .collect(),
+        ),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| (k.clone(), fill_template(v, index, time_ms)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
@@ -0,0 +1,281 @@
+/// Ranked fuzzy node search for quick-open. Operates on a node slice so it
+/// has no store dependency; the commands layer loads the project's nodes.
+///
+/// Each query token must match some token of a field — exactly, as a
+/// prefix, as a substring, or within a small edit distance — so "latncy"
+/// still finds "Boot Latency Under 10s".
+use crate::core::model::{Node, NodeData, NodeKind};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchField {
+    Name,
+    ReqId,
+    Text,
+    Description,
+}
+
+impl SearchField {
+    /// Matches in the name outrank matches buried in body text.
+    fn weight(self) -> f64 {
+        match self {
+            SearchField::Name | SearchField::ReqId => 1.0,
+            SearchField::Text => 0.7,
+            SearchField::Description => 0.6,
+        }
+    }
+}
+
+/// Half-open character range `[start, end)` within the matched field.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MatchSpan {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FuzzyMatch {
+    pub node_id: Uuid,
+    pub kind: NodeKind,
+    pub name: String,
+    /// 0..=1, higher is better.
+    pub score: f64,
+    pub field: SearchField,
+    /// The full text of the matched field, for rendering with `offsets`.
+    pub field_text: String,
+    /// Character offsets into `field_text` to highlight.
+    pub offsets: Vec<MatchSpan>,
+}
+
+/// Search `nodes` for `query`, best matches first, at most `limit` results.
+pub fn fuzzy_search(nodes: &[Node], query: &str, limit: usize) -> Vec<FuzzyMatch> {
+    let query_tokens: Vec<String> = tokenize(query).into_iter().map(|t| t.text).collect();
+    if query_tokens.is_empty() {
+        return Vec::new();
+    }
+    let query_phrase = query.trim().to_lowercase();
+
+    let mut results: Vec<FuzzyMatch> = nodes
+        .iter()
+        .filter_map(|node| {
+            searchable_fields(node)
+                .into_iter()
+                .filter_map(|(field, text)| {
+                    score_field(&query_tokens, &query_phrase, &text).map(|(score, offsets)| FuzzyMatch {
+                        node_id: node.id,
+                        kind: node.kind.clone(),
+                        name: node.name.clone(),
+                        score: score * field.weight(),
+                        field,
+                        field_text: text,
+                        offsets,
+                    })
+                })
+                .max_by(|a, b| a.score.total_cmp(&b.score))
+        })
+        .collect();
+
+    results.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.name.cmp(&b.name)));
+    results.truncate(limit);
+    results
+}
+
+fn searchable_fields(node: &Node) -> Vec<(SearchField, String)> {
+    let mut fields = vec![(SearchField::Name, node.name.clone())];
+    if let NodeData::Requirement(r) = &node.data {
+        if let Some(req_id) = r.req_id.as_ref().filter(|s| !s.is_empty()) {
+            fields.push((SearchField::ReqId, req_id.clone()));
+        }
+        if let Some(text) = r.text.as_ref().filter(|s| !s.is_empty()) {
+            fields.push((SearchField::Text, text.clone()));
+        }
+    }
+    if !node.description.is_empty() {
+        fields.push((SearchField::Description, node.description.clone()));
+    }
+    fields
+}
+
+/// Score one field. Every query token must match; the field score is the
+/// mean token score plus a bonus when the whole query appears verbatim.
+fn score_field(query_tokens: &[String], query_phrase: &str, text: &str) -> Option<(f64, Vec<MatchSpan>)> {
+    let field_tokens = tokenize(text);
+    if field_tokens.is_empty() {
+        return None;
+    }
+
+    let mut total = 0.0;
+    let mut offsets = Vec::with_capacity(query_tokens.len());
+    for q in query_tokens {
+        let (score, span) = field_tokens
+            .iter()
+            .filter_map(|t| score_token(q, t))
+            .max_by(|a, b| a.0.total_cmp(&b.0))?;
+        total += score;
+        offsets.push(span);
+    }
+    let mut score = total / query_tokens.len() as f64;
+
+    let lower = text.to_lowercase();
+    if query_tokens.len() > 1 {
+        if let Some(byte_pos) = lower.find(query_phrase) {
+            let start = lower[..byte_pos].chars().count();
+            offsets = vec![original_span(text, start, query_phrase.chars().count())];
+            score = (score + 0.1).min(1.0);
+        }
+    }
+
+    offsets.sort_by_key(|s| s.start);
+    offsets.dedup();
+    Some((score, offsets))
+}
+
+fn score_token(query: &str, token: &Token) -> Option<(f64, MatchSpan)> {
+    let q_len = query.chars().count();
+    let t = token.text.as_str();
+    // `from` and `len` count characters of the lowercased token.
+    let span = |from: usize, len: usize| {
+        let within = original_span(&token.source, from, len);
+        MatchSpan {
+            start: token.start + within.start,
+            end: token.start + within.end,
+        }
+    };
+    let whole_token = MatchSpan {
+        start: token.start,
+        end: token.start + token.len,
+    };
+
+    if t == query {
+        return Some((1.0, span(0, q_len)));
+    }
+    if t.starts_with(query) {
+        return Some((0.9, span(0, q_len)));
+    }
+    if q_len >= 3 {
+        if let Some(byte_pos) = t.find(query) {
+            return Some((0.75, span(t[..byte_pos].chars().count(), q_len)));
+        }
+    }
+
+    let max = max_edits(q_len);
+    if max == 0 {
+        return None;
+    }
+    let whole = bounded_levenshtein(query, t, max).map(|d| (0.85 - 0.15 * d as f64, whole_token));
+    // A typo inside a partially typed word: compare against the token's prefix.
+    let prefix: String = t.chars().take(q_len).collect();
+    let partial = (token.len > q_len)
+        .then(|| bounded_levenshtein(query, &prefix, max))
+        .flatten()
+        .map(|d| (0.7 - 0.15 * d as f64, span(0, q_len)));
+
+    match (whole, partial) {
+        (Some(a), Some(b)) => Some(if a.0 >= b.0 { a } else { b }),
+        (a, b) => a.or(b),
+    }
+}
+
+/// The characters `[from, from + len)` of `text.to_lowercase()` as a span
+/// of `text` itself, widened to whole characters; lowercasing can turn one
+/// character into several ("İ").
+fn original_span(text: &str, from: usize, len: usize) -> MatchSpan {
+    let (mut start, mut end, mut lowered) = (None, 0, 0);
+    for (i, c) in text.chars().enumerate() {
+        if lowered >= from + len {
+            break;
+        }
+        lowered += c.to_lowercase().count();
+        if start.is_none() && lowered > from {
+            start = Some(i);
+        }
+        end = i + 1;
+    }
+    MatchSpan {
+        start: start.unwrap_or(end),
+        end,
+    }
+}
+
+/// Typos allowed per token: none for short tokens, where any edit would
+/// match half the model.
+fn max_edits(len: usize) -> usize {
+    match len {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+/// Levenshtein distance between `a` and `b`, or `None` if it exceeds `max`.
+fn bounded_levenshtein(a: &str, b: &str, max: usize) -> Option<usize> {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        curr[0] = i + 1;
+        let mut row_min = curr[0];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != cb);
+            curr[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1);
+            row_min = row_min.min(curr[j + 1]);
+        }
+        if row_min > max {
+            return None;
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    Some(prev[b.len()]).filter(|&d| d <= max)
+}
+
+struct Token {
+    /// Lowercased token text.
+    text: String,
+    /// The token as written.
+    source: String,
+    /// Character offset of the token in the original string.
+    start: usize,
+    /// Length in characters.
+    len: usize,
+}
+
+/// Split on anything that isn't alphanumeric, keeping character offsets.
+fn tokenize(s: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut source = String::new();
+    let mut start = 0;
+    let mut len = 0;
+    for (i, c) in s.chars().enumerate() {
+        if c.is_alphanumeric() {
+            if current.is_empty() {
+                start = i;
+            }
+            current.extend(c.to_lowercase());
+            source.push(c);
+            len += 1;
+        } else if !current.is_empty() {
+            tokens.push(Token {
+                text: std::mem::take(&mut current),
+                source: std::mem::take(&mut source),
+                start,
+                len,
+            });
+            len = 0;
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(Token { text: current, source, start, len });
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests;
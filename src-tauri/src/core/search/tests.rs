@@ -0,0 +1,48 @@
+use super::*;
+use crate::test_support as fixtures;
+
+fn highlighted(m: &FuzzyMatch) -> Vec<String> {
+    let chars: Vec<char> = m.field_text.chars().collect();
+    m.offsets
+        .iter()
+        .map(|s| chars[s.start..s.end].iter().collect())
+        .collect()
+}
+
+#[test]
+fn finds_a_misspelled_word() {
+    let project = Uuid::new_v4();
+    let nodes = [
+        fixtures::block(project, "Boot Latency Under 10s"),
+        fixtures::block(project, "Power Distribution"),
+    ];
+    let found = fuzzy_search(&nodes, "latncy", 10);
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].name, "Boot Latency Under 10s");
+    assert_eq!(highlighted(&found[0]), ["Latency"]);
+}
+
+#[test]
+fn offsets_count_characters_of_the_text_as_written() {
+    // "İ" lowercases to two characters, shifting everything after it.
+    let project = Uuid::new_v4();
+    let nodes = [
+        fixtures::block(project, "İzmir Boot Latency"),
+        fixtures::block(project, "İnterlatency Budget"),
+    ];
+
+    let phrase = fuzzy_search(&nodes, "boot latency", 10);
+    assert_eq!(phrase[0].name, "İzmir Boot Latency");
+    assert_eq!(phrase[0].offsets, [MatchSpan { start: 6, end: 18 }]);
+    assert_eq!(highlighted(&phrase[0]), ["Boot Latency"]);
+
+    let inside = fuzzy_search(&nodes, "latency", 10);
+    let inner = inside
+        .iter()
+        .find(|m| m.name == "İnterlatency Budget")
+        .unwrap();
+    assert_eq!(highlighted(inner), ["latency"]);
+
+    let prefix = fuzzy_search(&nodes, "İzm", 10);
+    assert_eq!(highlighted(&prefix[0]), ["İzm"]);
+}
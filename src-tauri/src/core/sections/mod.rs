@@ -0,0 +1,311 @@
+/// Split a document's text into sections with a heading hierarchy.
+///
+/// Headings are numbered ("3.1.2 Thermal Control", "4. Interfaces") or
+/// markdown-style ("## Interfaces"); their level is the number of
+/// numbering components or hashes. A numbered line only counts as a
+/// heading when its title is short, title-cased and has no modal verb —
+/// otherwise "3.2 The pump shall ..." would be one. Everything else is
+/// split into paragraphs at blank lines, and into list items at bullet and
+/// list markers ("- ", "a) ", "3. "); indented lines continue the list item
+/// above. A line starting with a part number ("PN-4001", "AB12345") is a
+/// BOM item referenced by that number. Paragraphs and list items the extraction rules see as obligations
+/// (a modal verb, not heading-like) become requirement sections.
+///
+/// Each heading's parent is the nearest heading above it with a lower
+/// level; every other section's parent is the heading it sits under.
+use crate::core::extraction::{
+    rule_flags, starts_with_list_marker, FLAG_LOOKS_LIKE_HEADING, FLAG_NO_MODAL_VERB,
+};
+use crate::core::model::{Document, DocumentSection, SectionType};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Numbered headings with longer titles are treated as text.
+const MAX_HEADING_TITLE_CHARS: usize = 80;
+const MAX_TITLE_CHARS: usize = 120;
+const MAX_LIST_TITLE_CHARS: usize = 80;
+
+/// A section with the sections under it, in document order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SectionTree {
+    #[serde(flatten)]
+    pub section: DocumentSection,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<SectionTree>,
+}
+
+struct Heading {
+    level: i64,
+    section_ref: String,
+    title: String,
+}
+
+enum Pending {
+    Paragraph(String),
+    ListItem(String),
+}
+
+/// Parse `document.text` into fresh sections, numbered from position 0.
+pub fn parse_sections(document: &Document, now: DateTime<Utc>) -> Vec<DocumentSection> {
+    let mut parser = Parser {
+        document,
+        now,
+        sections: Vec::new(),
+        headings: Vec::new(),
+    };
+    let mut pending: Option<Pending> = None;
+
+    for line in document.text.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            parser.flush(pending.take());
+            continue;
+        }
+        if let Some(heading) = parse_heading(trimmed) {
+            parser.flush(pending.take());
+            parser.push_heading(heading, trimmed);
+            continue;
+        }
+        if let Some(part_number) = leading_part_number(trimmed) {
+            parser.flush(pending.take());
+            parser.push_bom_item(part_number, trimmed);
+            continue;
+        }
+        if let Some(item) = strip_bullet(trimmed) {
+            parser.flush(pending.take());
+            pending = Some(Pending::ListItem(item.to_string()));
+            continue;
+        }
+        if starts_with_list_marker(trimmed) {
+            parser.flush(pending.take());
+            pending = Some(Pending::ListItem(trimmed.to_string()));
+            continue;
+        }
+        let indented = line.starts_with(char::is_whitespace);
+        match &mut pending {
+            Some(Pending::Paragraph(text)) => append(text, trimmed),
+            Some(Pending::ListItem(text)) if indented => append(text, trimmed),
+            _ => {
+                parser.flush(pending.take());
+                pending = Some(Pending::Paragraph(trimmed.to_string()));
+            }
+        }
+    }
+    parser.flush(pending);
+    parser.sections
+}
+
+struct Parser<'a> {
+    document: &'a Document,
+    now: DateTime<Utc>,
+    sections: Vec<DocumentSection>,
+    /// Open headings, outermost first: (level, id).
+    headings: Vec<(i64, Uuid)>,
+}
+
+impl Parser<'_> {
+    fn push_heading(&mut self, heading: Heading, line: &str) {
+        while self
+            .headings
+            .last()
+            .is_some_and(|(level, _)| *level >= heading.level)
+        {
+            self.headings.pop();
+        }
+        let section = self.section(
+            SectionType::Heading,
+            heading.section_ref,
+            truncate(&heading.title, MAX_TITLE_CHARS),
+            line.to_string(),
+            heading.level,
+        );
+        self.headings.push((heading.level, section.id));
+        self.sections.push(section);
+    }
+
+    fn push_bom_item(&mut self, part_number: &str, line: &str) {
+        let title = line
+            .split_whitespace()
+            .skip(1)
+            .take(7)
+            .collect::<Vec<_>>()
+            .join(" ");
+        let level = self.headings.last().map_or(0, |(level, _)| *level);
+        let section = self.section(
+            SectionType::BomItem,
+            part_number.to_string(),
+            title,
+            line.to_string(),
+            level,
+        );
+        self.sections.push(section);
+    }
+
+    fn flush(&mut self, pending: Option<Pending>) {
+        let Some(pending) = pending else {
+            return;
+        };
+        let (default_type, body, title_chars) = match pending {
+            Pending::Paragraph(body) => (SectionType::Paragraph, body, MAX_TITLE_CHARS),
+            Pending::ListItem(body) => (SectionType::ListItem, body, MAX_LIST_TITLE_CHARS),
+        };
+        let flags = rule_flags(&body);
+        let section_type =
+            if flags.contains(&FLAG_NO_MODAL_VERB) || flags.contains(&FLAG_LOOKS_LIKE_HEADING) {
+                default_type
+            } else {
+                SectionType::Requirement
+            };
+        let level = self.headings.last().map_or(0, |(level, _)| *level);
+        let title = truncate(&body, title_chars);
+        let section = self.section(section_type, String::new(), title, body, level);
+        self.sections.push(section);
+    }
+
+    fn section(
+        &self,
+        section_type: SectionType,
+        section_ref: String,
+        title: String,
+        body: String,
+        section_level: i64,
+    ) -> DocumentSection {
+        DocumentSection {
+            id: Uuid::new_v4(),
+            document_id: self.document.id,
+            project_id: self.document.project_id,
+            section_ref,
+            section_type,
+            title,
+            body,
+            part_number: None,
+            quantity: None,
+            unit: None,
+            position: self.sections.len() as i64,
+            created_at: self.now,
+            parent_section_id: self.headings.last().map(|(_, id)| *id),
+            section_level,
+        }
+    }
+}
+
+/// "## Title" or "3.1.2 Title". A numbering prefix of a markdown heading
+/// becomes its section_ref, but the hashes still set the level.
+fn parse_heading(line: &str) -> Option<Heading> {
+    let hashes = line.chars().take_while(|c| *c == '#').count();
+    if (1..=6).contains(&hashes) && line[hashes..].starts_with(char::is_whitespace) {
+        let text = line[hashes..].trim().trim_end_matches('#').trim();
+        if text.is_empty() {
+            return None;
+        }
+        let (section_ref, title) = match split_numbering(text) {
+            Some((parts, title)) if !title.is_empty() => (parts.join("."), title),
+            _ => (String::new(), text),
+        };
+        return Some(Heading {
+            level: hashes as i64,
+            section_ref,
+            title: title.to_string(),
+        });
+    }
+
+    let (parts, title) = split_numbering(line)?;
+    let flags = rule_flags(title);
+    let heading_like = title.chars().count() <= MAX_HEADING_TITLE_CHARS
+        && flags.contains(&FLAG_NO_MODAL_VERB)
+        && flags.contains(&FLAG_LOOKS_LIKE_HEADING);
+    heading_like.then(|| Heading {
+        level: parts.len() as i64,
+        section_ref: parts.join("."),
+        title: title.to_string(),
+    })
+}
+
+/// "3.1.2 Title" → (["3", "1", "2"], "Title"). A trailing period on the
+/// number ("4. Title") is allowed.
+fn split_numbering(line: &str) -> Option<(Vec<&str>, &str)> {
+    let (number, title) = line.split_once(char::is_whitespace)?;
+    let parts: Vec<&str> = number
+        .strip_suffix('.')
+        .unwrap_or(number)
+        .split('.')
+        .collect();
+    let numeric = parts
+        .iter()
+        .all(|p| !p.is_empty() && p.len() <= 3 && p.chars().all(|c| c.is_ascii_digit()));
+    numeric.then_some((parts, title.trim()))
+}
+
+/// One to four capitals, an optional hyphen and three to eight digits.
+fn leading_part_number(line: &str) -> Option<&str> {
+    let token = line.split_whitespace().next()?;
+    let letters = token.chars().take_while(char::is_ascii_uppercase).count();
+    let digits = token[letters..]
+        .strip_prefix('-')
+        .unwrap_or(&token[letters..]);
+    let is_part_number = (1..=4).contains(&letters)
+        && (3..=8).contains(&digits.len())
+        && digits.chars().all(|c| c.is_ascii_digit());
+    is_part_number.then_some(token)
+}
+
+/// The text of a "- ", "* " or "• " bullet line.
+fn strip_bullet(line: &str) -> Option<&str> {
+    let rest = line.strip_prefix(['-', '*', '•'])?;
+    rest.starts_with(char::is_whitespace)
+        .then_some(rest.trim())
+        .filter(|text| !text.is_empty())
+}
+
+fn append(text: &mut String, line: &str) {
+    text.push(' ');
+    text.push_str(line);
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    text.chars().take(max_chars).collect()
+}
+
+/// Nest `sections` under their parents, keeping the given order among
+/// siblings. Sections whose parent is missing from `sections` are roots,
+/// and so is anything only reachable through a parent cycle.
+pub fn build_tree(sections: Vec<DocumentSection>) -> Vec<SectionTree> {
+    let order: HashMap<Uuid, usize> = sections
+        .iter()
+        .enumerate()
+        .map(|(i, s)| (s.id, i))
+        .collect();
+    let mut by_parent: HashMap<Option<Uuid>, Vec<DocumentSection>> = HashMap::new();
+    for section in sections {
+        let parent = section
+            .parent_section_id
+            .filter(|p| order.contains_key(p) && *p != section.id);
+        by_parent.entry(parent).or_default().push(section);
+    }
+
+    let mut roots = take_children(&mut by_parent, None);
+    // Whatever is left only reaches itself through its parents.
+    let mut stranded: Vec<DocumentSection> = by_parent.into_values().flatten().collect();
+    stranded.sort_by_key(|s| order[&s.id]);
+    roots.extend(stranded.into_iter().map(|section| SectionTree {
+        section,
+        children: Vec::new(),
+    }));
+    roots
+}
+
+fn take_children(
+    by_parent: &mut HashMap<Option<Uuid>, Vec<DocumentSection>>,
+    parent: Option<Uuid>,
+) -> Vec<SectionTree> {
+    let children = by_parent.remove(&parent).unwrap_or_default();
+    children
+        .into_iter()
+        .map(|section| {
+            let children = take_children(by_parent, Some(section.id));
+            SectionTree { section, children }
+        })
+        .collect()
+}
@@ -0,0 +1,196 @@
+/// Splitting a compound requirement ("shall A and shall B") into atomic
+/// ones. Pure planning; the commands layer loads the parent and its edges
+/// and the store applies the plan in one transaction.
+///
+/// Each part becomes a Draft requirement with the parent's priority,
+/// allocations, source, verification method and classification. Its
+/// req_id is the parent's with "-1", "-2", ... appended, skipping ids
+/// already in use. A kept parent is marked Obsolete and refined by every
+/// child. Optionally, «satisfies» and «verifies» edges into the parent
+/// are copied to every child.
+use crate::core::model::{
+    Edge, EdgeKind, Node, NodeData, NodeKind, RequirementData, RequirementStatus,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// `change_source` recorded on nodes written by a split.
+pub const SPLIT_SOURCE: &str = "split";
+
+const MODAL_VERBS: [&str; 4] = ["shall", "must", "will", "should"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequirementSplit {
+    pub children: Vec<Node>,
+    /// «refines» edges to a kept parent and copied links.
+    pub edges: Vec<Edge>,
+    /// The parent marked Obsolete, or `None` when it is to be deleted.
+    pub parent: Option<Node>,
+}
+
+pub struct SplitOptions {
+    pub keep_parent: bool,
+    pub propagate_links: bool,
+    pub actor: String,
+    pub now: DateTime<Utc>,
+}
+
+/// Plan splitting `parent` into `parts`. `parent_edges` are the edges
+/// touching the parent; `taken_ids` are the project's req_ids in use.
+pub fn plan(
+    parent: &Node,
+    parts: &[String],
+    parent_edges: &[Edge],
+    taken_ids: &HashSet<String>,
+    options: &SplitOptions,
+) -> Result<RequirementSplit, String> {
+    let NodeData::Requirement(parent_req) = &parent.data else {
+        return Err(format!("'{}' is a {}, not a requirement", parent.name, parent.kind));
+    };
+    let parts: Vec<&str> = parts.iter().map(|p| p.trim()).filter(|p| !p.is_empty()).collect();
+    if parts.len() < 2 {
+        return Err("a split needs at least two non-empty parts".to_string());
+    }
+
+    let meta = |extra: Option<(&str, Value)>| {
+        let mut meta: HashMap<String, Value> = HashMap::new();
+        meta.insert("actor".to_string(), Value::from(options.actor.clone()));
+        meta.insert("change_source".to_string(), Value::from(SPLIT_SOURCE));
+        if let Some((key, value)) = extra {
+            meta.insert(key.to_string(), value);
+        }
+        meta
+    };
+
+    let base_id = parent_req.req_id.as_deref().map(str::trim).filter(|id| !id.is_empty());
+    let mut suffix = 0;
+    let mut next_req_id = || {
+        base_id.map(|base| loop {
+            suffix += 1;
+            let id = format!("{base}-{suffix}");
+            if !taken_ids.contains(&id) {
+                break id;
+            }
+        })
+    };
+
+    let children: Vec<Node> = parts
+        .iter()
+        .map(|text| Node {
+            id: Uuid::new_v4(),
+            project_id: parent.project_id,
+            kind: NodeKind::Requirement,
+            name: derive_name(text),
+            description: text.to_string(),
+            data: NodeData::Requirement(RequirementData {
+                req_id: next_req_id(),
+                text: Some(text.to_string()),
+                rationale: parent_req.rationale.clone(),
+                priority: parent_req.priority.clone(),
+                status: RequirementStatus::Draft,
+                source: parent_req.source.clone(),
+                allocations: parent_req.allocations.clone(),
+                verification_method: parent_req.verification_method.clone(),
+                classification: parent_req.classification.clone(),
+            }),
+            meta: meta(Some(("split_from", Value::from(parent.id.to_string())))),
+            archived: false,
+            created_at: options.now,
+            modified_at: options.now,
+        })
+        .collect();
+
+    let edge = |kind: EdgeKind, source_id: Uuid, target_id: Uuid, label: &str| Edge {
+        id: Uuid::new_v4(),
+        project_id: parent.project_id,
+        kind,
+        source_id,
+        target_id,
+        label: label.to_string(),
+        meta: meta(None),
+        created_at: options.now,
+        modified_at: options.now,
+    };
+
+    let mut edges = Vec::new();
+    if options.keep_parent {
+        edges.extend(children.iter().map(|c| edge(EdgeKind::Refines, c.id, parent.id, "")));
+    }
+    if options.propagate_links {
+        let incoming = parent_edges.iter().filter(|e| {
+            e.target_id == parent.id && matches!(e.kind, EdgeKind::Satisfies | EdgeKind::Verifies)
+        });
+        for link in incoming {
+            edges.extend(
+                children
+                    .iter()
+                    .map(|c| edge(link.kind.clone(), link.source_id, c.id, &link.label)),
+            );
+        }
+    }
+
+    let parent = options.keep_parent.then(|| {
+        let mut obsolete = parent.clone();
+        if let NodeData::Requirement(r) = &mut obsolete.data {
+            r.status = RequirementStatus::Obsolete;
+        }
+        obsolete.meta.extend(meta(None));
+        obsolete.modified_at = options.now;
+        obsolete
+    });
+
+    Ok(RequirementSplit { children, edges, parent })
+}
+
+/// A short Title Case name from a requirement sentence: up to seven words
+/// after the modal verb ("The pump shall deliver 5 L/min" → "Deliver 5
+/// L/Min"), or the first seven words when there is none.
+pub fn derive_name(text: &str) -> String {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let modal = words.iter().position(|w| {
+        let w = w.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase();
+        MODAL_VERBS.contains(&w.as_str())
+    });
+    let modal = modal.filter(|&i| i + 1 < words.len());
+    let mut rest = match modal {
+        Some(i) => &words[i + 1..],
+        None => &words[..],
+    };
+    if modal.is_some() {
+        // "not" stays: dropping it would invert the name.
+        let fillers: [&[&str]; 2] = [&["be"], &["able", "to"]];
+        for filler in fillers {
+            let strip = rest.len() > filler.len()
+                && rest.iter().zip(filler).all(|(w, f)| w.eq_ignore_ascii_case(f));
+            if strip {
+                rest = &rest[filler.len()..];
+            }
+        }
+    }
+
+    let name = rest
+        .iter()
+        .take(7)
+        .map(|w| title_case(w))
+        .collect::<Vec<_>>()
+        .join(" ");
+    name.trim_end_matches(|c: char| ".,;:!?".contains(c)).to_string()
+}
+
+/// Upper-case the first letter of each alphanumeric run ("l/min" → "L/Min").
+fn title_case(word: &str) -> String {
+    let mut out = String::with_capacity(word.len());
+    let mut at_start = true;
+    for c in word.chars() {
+        if at_start && c.is_alphanumeric() {
+            out.extend(c.to_uppercase());
+        } else {
+            out.push(c);
+        }
+        at_start = !c.is_alphanumeric();
+    }
+    out
+}
@@ -6,19 +6,104 @@ use sqlx::{
     Row,
 };
 use std::str::FromStr;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 use uuid::Uuid;
 
+/// Targets the `idx_settings_key_project` unique index, so concurrent
+/// writers of the same key and scope update one row instead of racing.
+const UPSERT_SETTING_SQL: &str = "INSERT INTO settings (key, project_id, value) VALUES (?, ?, ?)
+     ON CONFLICT(key, COALESCE(project_id, '')) DO UPDATE SET value = excluded.value";
+
+/// Actors that say nothing about who made a change; [`Store::stamp_actor`]
+/// replaces them with the configured identity.
+const PLACEHOLDER_ACTORS: [&str; 3] = ["", "system", "user"];
+
+/// Setting holding the newest app version that has opened this database.
+const APP_VERSION_KEY: &str = "app_version";
+
+fn migrator() -> sqlx::migrate::Migrator {
+    sqlx::migrate!("./migrations")
+}
+
+/// How long SQLite retries a locked database before failing with
+/// SQLITE_BUSY.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a user-initiated heavy write waits for a running one before
+/// giving up with [`HeavyWriteBusy`].
+pub const USER_HEAVY_WRITE_WAIT: Duration = Duration::from_secs(3);
+
+#[derive(Clone)]
 pub struct Store {
     pool: SqlitePool,
+    db_path: String,
+    /// Set when the database was last used by a newer app version.
+    version_warning: Option<String>,
+    write_queue: Arc<WriteQueue>,
+    /// This machine's identity, stamped onto audit, history and comment rows.
+    identity: Arc<RwLock<Option<String>>>,
+    read_only: bool,
+}
+
+/// Lets one heavy multi-statement write (bulk upserts, imports, baselines)
+/// run at a time. Single-row writes don't queue; `BUSY_TIMEOUT` covers them.
+struct WriteQueue {
+    permit: Arc<Semaphore>,
+    /// Name of the heavy write holding the permit.
+    running: Mutex<Option<String>>,
+}
+
+/// Held for the duration of a heavy write; see [`Store::begin_heavy_write`].
+pub struct HeavyWrite {
+    queue: Arc<WriteQueue>,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl Drop for HeavyWrite {
+    fn drop(&mut self) {
+        *self.queue.running.lock().unwrap() = None;
+    }
+}
+
+/// A user-initiated heavy write gave up waiting for another one.
+#[derive(Debug)]
+pub struct HeavyWriteBusy {
+    pub running: String,
+    pub requested: String,
 }
 
+impl std::fmt::Display for HeavyWriteBusy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Another large operation is in progress ({}). Try {} again when it finishes.",
+            self.running, self.requested
+        )
+    }
+}
+
+impl std::error::Error for HeavyWriteBusy {}
+
 impl Store {
     pub async fn open(db_path: &str) -> Result<Self> {
+        Self::open_with_progress(db_path, |_, _, _| {}).await
+    }
+
+    /// [`Store::open`], calling `on_migration(n, total, description)` before
+    /// each pending migration is applied. Nothing is called when the
+    /// database is already current.
+    pub async fn open_with_progress(
+        db_path: &str,
+        on_migration: impl Fn(usize, usize, &str),
+    ) -> Result<Self> {
         // WAL mode must be set via connect options, not a PRAGMA inside a
         // migration transaction — SQLite rejects that.
         let opts = SqliteConnectOptions::from_str(&format!("sqlite:{db_path}?mode=rwc"))?
             .journal_mode(SqliteJournalMode::Wal)
             .foreign_keys(true)
+            .busy_timeout(BUSY_TIMEOUT)
             .create_if_missing(true);
 
         let pool = SqlitePoolOptions::new()
@@ -26,13 +111,243 @@ impl Store {
             .connect_with(opts)
             .await?;
 
-        sqlx::migrate!("./migrations").run(&pool).await?;
-        Ok(Self { pool })
+        // A newer build may have applied migrations this one doesn't know.
+        // sqlx refuses to run in that case with an opaque "migration N was
+        // previously applied but is missing" error; tolerate it and warn.
+        let mut migrator = migrator();
+        let applied = applied_migrations(&pool).await?;
+        let unknown: Vec<i64> = applied
+            .iter()
+            .copied()
+            .filter(|v| !migrator.iter().any(|m| m.version == *v))
+            .collect();
+        if !unknown.is_empty() {
+            migrator.set_ignore_missing(true);
+        }
+
+        // Apply pending migrations one prefix at a time so progress can be
+        // reported between them. The full run afterwards is a no-op that
+        // still validates checksums of everything already applied.
+        let pending: Vec<usize> = migrator
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| {
+                !m.migration_type.is_down_migration() && !applied.contains(&m.version)
+            })
+            .map(|(i, _)| i)
+            .collect();
+        for (n, &i) in pending.iter().enumerate() {
+            on_migration(n + 1, pending.len(), &migrator.migrations[i].description);
+            let mut step = self::migrator();
+            step.migrations = std::borrow::Cow::Owned(migrator.migrations[..=i].to_vec());
+            step.set_ignore_missing(true);
+            step.run(&pool).await?;
+        }
+        migrator.run(&pool).await?;
+
+        let mut store = Self::with_pool(pool, db_path, false);
+        store.version_warning = store.check_app_version(&unknown).await?;
+        Ok(store)
+    }
+
+    /// A private in-memory database with every migration applied, for
+    /// tests. `sqlite::memory:` names a new shared-cache database on each
+    /// call, so the pool's connections see the same data; it goes away
+    /// with the last of them.
+    pub async fn open_in_memory() -> Result<Self> {
+        let opts = SqliteConnectOptions::from_str("sqlite::memory:")?
+            .foreign_keys(true)
+            .busy_timeout(BUSY_TIMEOUT);
+
+        let pool = SqlitePoolOptions::new()
+            .max_connections(5)
+            .min_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect_with(opts)
+            .await?;
+        migrator().run(&pool).await?;
+        Ok(Self::with_pool(pool, ":memory:", false))
+    }
+
+    /// Open an existing, already migrated database without writing to it,
+    /// for the shared-database fallback (see `crate::presence`). Every
+    /// write fails with SQLite's read-only error.
+    pub async fn open_read_only(db_path: &str) -> Result<Self> {
+        let opts = SqliteConnectOptions::from_str(&format!("sqlite:{db_path}?mode=ro"))?
+            .read_only(true)
+            .foreign_keys(true)
+            .busy_timeout(BUSY_TIMEOUT);
+
+        let pool = SqlitePoolOptions::new()
+            .max_connections(5)
+            .connect_with(opts)
+            .await?;
+        Ok(Self::with_pool(pool, db_path, true))
+    }
+
+    fn with_pool(pool: SqlitePool, db_path: &str, read_only: bool) -> Self {
+        Self {
+            pool,
+            db_path: db_path.to_string(),
+            version_warning: None,
+            write_queue: Arc::new(WriteQueue {
+                permit: Arc::new(Semaphore::new(1)),
+                running: Mutex::new(None),
+            }),
+            identity: Arc::new(RwLock::new(None)),
+            read_only,
+        }
+    }
+
+    /// Compare the stored `app_version` marker with this build and bump it
+    /// unless the database has already seen a newer version.
+    async fn check_app_version(&self, unknown_migrations: &[i64]) -> Result<Option<String>> {
+        let current = env!("CARGO_PKG_VERSION");
+        let stored = self.get_setting(APP_VERSION_KEY, None).await?;
+        let newer = stored
+            .as_deref()
+            .filter(|v| version_parts(v) > version_parts(current));
+
+        if newer.is_none() {
+            self.set_setting(APP_VERSION_KEY, None, current).await?;
+        }
+        if newer.is_none() && unknown_migrations.is_empty() {
+            return Ok(None);
+        }
+        let by = newer.map(|v| format!("version {v}")).unwrap_or_else(|| "a newer version".to_string());
+        Ok(Some(format!(
+            "This database was last used by SystemProduct {by}, but this is version {current}. \
+             Newer data may not display or save correctly; upgrade the app or restore a backup \
+             taken before the upgrade."
+        )))
+    }
+
+    pub fn db_path(&self) -> &str {
+        &self.db_path
+    }
+
+    pub fn version_warning(&self) -> Option<&str> {
+        self.version_warning.as_deref()
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    pub fn identity(&self) -> Option<String> {
+        self.identity.read().unwrap().clone()
+    }
+
+    /// Set the identity stamped onto new rows; blank clears it.
+    pub fn set_identity(&self, identity: Option<&str>) {
+        let identity = identity.map(str::trim).filter(|s| !s.is_empty());
+        *self.identity.write().unwrap() = identity.map(str::to_string);
+    }
+
+    /// `actor`, or the configured identity when `actor` is a placeholder
+    /// ("system", "user" or blank, in any case).
+    pub fn stamp_actor(&self, actor: &str) -> String {
+        let placeholder = PLACEHOLDER_ACTORS
+            .iter()
+            .any(|p| actor.trim().eq_ignore_ascii_case(p));
+        match self.identity() {
+            Some(identity) if placeholder => identity,
+            _ => actor.to_string(),
+        }
+    }
+
+    /// Wait for the heavy-write queue, named `operation` while it's held.
+    /// Background work passes `None` and waits as long as it takes;
+    /// user-initiated work passes a limit and gets a [`HeavyWriteBusy`]
+    /// naming the running operation instead of a long stall.
+    pub async fn begin_heavy_write(
+        &self,
+        operation: &str,
+        max_wait: Option<Duration>,
+    ) -> Result<HeavyWrite> {
+        let acquire = self.write_queue.permit.clone().acquire_owned();
+        let permit = match max_wait {
+            None => acquire.await?,
+            Some(wait) => match tokio::time::timeout(wait, acquire).await {
+                Ok(permit) => permit?,
+                Err(_) => {
+                    let running = self.write_queue.running.lock().unwrap().clone();
+                    return Err(HeavyWriteBusy {
+                        running: running.unwrap_or_else(|| "a bulk update".to_string()),
+                        requested: operation.to_string(),
+                    }
+                    .into());
+                }
+            },
+        };
+        *self.write_queue.running.lock().unwrap() = Some(operation.to_string());
+        Ok(HeavyWrite {
+            queue: self.write_queue.clone(),
+            _permit: permit,
+        })
+    }
+
+    /// Every migration this build ships, with its applied state, followed by
+    /// any applied migrations it doesn't know about.
+    pub async fn migration_status(&self) -> Result<Vec<MigrationStatus>> {
+        let rows = sqlx::query(
+            "SELECT version, description, CAST(installed_on AS TEXT) AS installed_on
+             FROM _sqlx_migrations WHERE success = 1 ORDER BY version",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        let mut applied: Vec<(i64, String, Option<String>)> = rows
+            .iter()
+            .map(|r| -> Result<(i64, String, Option<String>)> {
+                Ok((r.try_get("version")?, r.try_get("description")?, r.try_get("installed_on")?))
+            })
+            .collect::<Result<_>>()?;
+
+        let mut out: Vec<MigrationStatus> = migrator()
+            .iter()
+            .map(|m| {
+                let row = applied.iter().position(|(v, _, _)| *v == m.version);
+                let installed_on = row.and_then(|i| applied.remove(i).2);
+                MigrationStatus {
+                    version: m.version,
+                    description: m.description.to_string(),
+                    applied: row.is_some(),
+                    installed_on,
+                    known: true,
+                }
+            })
+            .collect();
+        out.extend(applied.into_iter().map(|(version, description, installed_on)| MigrationStatus {
+            version,
+            description,
+            applied: true,
+            installed_on,
+            known: false,
+        }));
+        Ok(out)
+    }
+
+    pub async fn journal_mode(&self) -> Result<String> {
+        Ok(sqlx::query_scalar("PRAGMA journal_mode").fetch_one(&self.pool).await?)
+    }
+
+    /// (projects, nodes, edges) across the whole database.
+    pub async fn model_totals(&self) -> Result<(i64, i64, i64)> {
+        let row = sqlx::query(
+            "SELECT (SELECT COUNT(*) FROM projects) AS projects,
+                    (SELECT COUNT(*) FROM nodes) AS nodes,
+                    (SELECT COUNT(*) FROM edges) AS edges",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok((row.try_get("projects")?, row.try_get("nodes")?, row.try_get("edges")?))
     }
 
     // ── Projects ──────────────────────────────────────────────────────────────
 
-    pub async fn create_project(&self, project: &Project) -> Result<()> {
+    pub async fn create_project(&self, project: &Project, actor: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
         sqlx::query(
             "INSERT INTO projects (id, name, description, created_at, modified_at)
              VALUES (?, ?, ?, ?, ?)",
@@ -42,8 +357,19 @@ impl Store {
         .bind(&project.description)
         .bind(project.created_at.to_rfc3339())
         .bind(project.modified_at.to_rfc3339())
-        .execute(&self.pool)
+        .execute(&mut *tx)
+        .await?;
+        self.record_audit(
+            &mut tx,
+            project.id,
+            "project",
+            project.id,
+            "create",
+            actor,
+            &format!("created project '{}'", project.name),
+        )
         .await?;
+        tx.commit().await?;
         Ok(())
     }
 
@@ -69,11 +395,111 @@ impl Store {
         row.as_ref().map(row_to_project).transpose()
     }
 
-    pub async fn delete_project(&self, id: Uuid) -> Result<()> {
+    pub async fn delete_project(&self, id: Uuid, actor: &str) -> Result<()> {
+        let name = self.get_project(id).await?.map(|p| p.name).unwrap_or_default();
+        let mut tx = self.pool.begin().await?;
         sqlx::query("DELETE FROM projects WHERE id = ?")
             .bind(id.to_string())
-            .execute(&self.pool)
+            .execute(&mut *tx)
+            .await?;
+        let summary = format!("deleted project '{name}'");
+        self.record_audit(&mut tx, id, "project", id, "delete", actor, &summary)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Which of `ids` a project, node or edge already uses.
+    pub async fn taken_ids(&self, ids: &[Uuid]) -> Result<std::collections::HashSet<Uuid>> {
+        let mut taken = std::collections::HashSet::new();
+        // Each id is bound three times; stay under SQLite's 999 variables.
+        for batch in ids.chunks(300) {
+            let placeholders = vec!["?"; batch.len()].join(", ");
+            let sql = format!(
+                "SELECT id FROM projects WHERE id IN ({placeholders})
+                 UNION SELECT id FROM nodes WHERE id IN ({placeholders})
+                 UNION SELECT id FROM edges WHERE id IN ({placeholders})"
+            );
+            let mut query = sqlx::query(&sql);
+            for _ in 0..3 {
+                for id in batch {
+                    query = query.bind(id.to_string());
+                }
+            }
+            for row in query.fetch_all(&self.pool).await? {
+                let id: String = row.try_get("id")?;
+                taken.insert(Uuid::parse_str(&id)?);
+            }
+        }
+        Ok(taken)
+    }
+
+    /// Write an imported model in one transaction: the project when
+    /// `create` is set, then `nodes` and `edges`, whose ids must be unused.
+    /// Audited as a single project entry by `actor`.
+    #[tracing::instrument(target = "query", skip_all, fields(project_id = %project.id, rows), err)]
+    pub async fn import_model(
+        &self,
+        project: &Project,
+        create: bool,
+        nodes: &[Node],
+        edges: &[Edge],
+        actor: &str,
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        if create {
+            sqlx::query(
+                "INSERT INTO projects (id, name, description, created_at, modified_at)
+                 VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(project.id.to_string())
+            .bind(&project.name)
+            .bind(&project.description)
+            .bind(project.created_at.to_rfc3339())
+            .bind(project.modified_at.to_rfc3339())
+            .execute(&mut *tx)
+            .await?;
+        }
+        for node in nodes {
+            let history_actor = self.stamp_actor(&extract_history_actor(node));
+            write_node(&mut tx, node, None, &history_actor).await?;
+        }
+        for edge in edges {
+            sqlx::query(
+                "INSERT INTO edges
+                 (id, project_id, kind, source_id, target_id, label, meta, created_at, modified_at)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(edge.id.to_string())
+            .bind(edge.project_id.to_string())
+            .bind(edge.kind.to_string())
+            .bind(edge.source_id.to_string())
+            .bind(edge.target_id.to_string())
+            .bind(&edge.label)
+            .bind(serde_json::to_string(&edge.meta)?)
+            .bind(edge.created_at.to_rfc3339())
+            .bind(edge.modified_at.to_rfc3339())
+            .execute(&mut *tx)
             .await?;
+        }
+
+        let (action, summary) = if create {
+            ("create", format!("imported project '{}'", project.name))
+        } else {
+            ("update", format!("imported into project '{}'", project.name))
+        };
+        self.record_audit(
+            &mut tx,
+            project.id,
+            "project",
+            project.id,
+            action,
+            actor,
+            &format!("{summary}: {} nodes, {} edges", nodes.len(), edges.len()),
+        )
+        .await?;
+        tx.commit().await?;
+        tracing::Span::current().record("rows", nodes.len() + edges.len());
         Ok(())
     }
 
@@ -83,7 +509,8 @@ impl Store {
         let prev_requirement_snapshot = if node.kind == NodeKind::Requirement {
             let row = sqlx::query(
                 "SELECT name, description, req_id, req_text, req_rationale, req_priority,
-                        req_status, req_source, req_allocations, req_verification_method
+                        req_status, req_source, req_allocations, req_verification_method,
+                        req_classification
                  FROM nodes
                  WHERE id = ? AND kind = 'requirement'",
             )
@@ -96,201 +523,246 @@ impl Store {
             None
         };
 
-        let next_requirement_snapshot = requirement_snapshot_from_node(node);
-
-        // Flatten kind-specific data for column storage
-        let (
-            req_id,
-            req_text,
-            req_rationale,
-            req_priority,
-            req_status,
-            req_source,
-            req_allocations,
-            req_verif,
-            block_abstract,
-            block_mult,
-            port_dir,
-            port_type,
-            port_type_name,
-            port_multiplicity,
-            uc_level,
-            tc_procedure,
-            tc_expected,
-            tc_status,
-            sim_params,
-            sim_script,
-            vt_base_type,
-            vt_unit,
-            vt_constraint,
-            cb_expression,
-            cb_parameters,
-            state_pseudo_kind,
-            state_entry,
-            state_exit,
-            state_do,
-        ) = flatten_node_data(&node.data);
+        let prev_name = self.node_name(node.id).await?;
 
         let mut tx = self.pool.begin().await?;
+        let history_actor = self.stamp_actor(&extract_history_actor(node));
+        write_node(&mut tx, node, prev_requirement_snapshot, &history_actor).await?;
 
-        sqlx::query(
-            "INSERT INTO nodes (
-                id, project_id, kind, name, description,
-                req_id, req_text, req_rationale, req_priority, req_status,
-                req_source, req_allocations, req_verification_method,
-                block_is_abstract, block_multiplicity,
-                port_direction, port_type_ref, port_type_name, port_multiplicity,
-                uc_level,
-                tc_procedure, tc_expected, tc_status,
-                sim_params, sim_script,
-                vt_base_type, vt_unit, vt_constraint,
-                cb_expression, cb_parameters,
-                state_pseudo_kind, state_entry, state_exit, state_do,
-                meta, created_at, modified_at
-             ) VALUES (
-                ?, ?, ?, ?, ?,
-                ?, ?, ?, ?, ?,
-                ?, ?, ?,
-                ?, ?,
-                ?, ?, ?, ?,
-                ?,
-                ?, ?, ?,
-                ?, ?,
-                ?, ?, ?,
-                ?, ?,
-                ?, ?, ?, ?,
-                ?, ?, ?
-             )
-             ON CONFLICT(id) DO UPDATE SET
-                name = excluded.name,
-                description = excluded.description,
-                req_id = excluded.req_id,
-                req_text = excluded.req_text,
-                req_rationale = excluded.req_rationale,
-                req_priority = excluded.req_priority,
-                req_status = excluded.req_status,
-                req_source = excluded.req_source,
-                req_allocations = excluded.req_allocations,
-                req_verification_method = excluded.req_verification_method,
-                block_is_abstract = excluded.block_is_abstract,
-                block_multiplicity = excluded.block_multiplicity,
-                port_direction = excluded.port_direction,
-                port_type_ref = excluded.port_type_ref,
-                port_type_name = excluded.port_type_name,
-                port_multiplicity = excluded.port_multiplicity,
-                uc_level = excluded.uc_level,
-                tc_procedure = excluded.tc_procedure,
-                tc_expected = excluded.tc_expected,
-                tc_status = excluded.tc_status,
-                sim_params = excluded.sim_params,
-                sim_script = excluded.sim_script,
-                vt_base_type = excluded.vt_base_type,
-                vt_unit = excluded.vt_unit,
-                vt_constraint = excluded.vt_constraint,
-                cb_expression = excluded.cb_expression,
-                cb_parameters = excluded.cb_parameters,
-                state_pseudo_kind = excluded.state_pseudo_kind,
-                state_entry = excluded.state_entry,
-                state_exit = excluded.state_exit,
-                state_do = excluded.state_do,
-                meta = excluded.meta,
-                modified_at = excluded.modified_at",
+        let (action, summary) = match prev_name {
+            None => ("create", format!("created {} '{}'", node.kind, node.name)),
+            Some(prev) if prev != node.name => {
+                ("update", format!("renamed '{}' → '{}'", prev, node.name))
+            }
+            Some(_) => ("update", format!("updated {} '{}'", node.kind, node.name)),
+        };
+        self.record_audit(
+            &mut tx,
+            node.project_id,
+            "node",
+            node.id,
+            action,
+            &history_actor,
+            &summary,
         )
-        .bind(node.id.to_string())
-        .bind(node.project_id.to_string())
-        .bind(node.kind.to_string())
-        .bind(&node.name)
-        .bind(&node.description)
-        .bind(req_id)
-        .bind(req_text)
-        .bind(req_rationale)
-        .bind(req_priority)
-        .bind(req_status)
-        .bind(req_source)
-        .bind(req_allocations)
-        .bind(req_verif)
-        .bind(block_abstract)
-        .bind(block_mult)
-        .bind(port_dir)
-        .bind(port_type)
-        .bind(port_type_name)
-        .bind(port_multiplicity)
-        .bind(uc_level)
-        .bind(tc_procedure)
-        .bind(tc_expected)
-        .bind(tc_status)
-        .bind(sim_params)
-        .bind(sim_script)
-        .bind(vt_base_type)
-        .bind(vt_unit)
-        .bind(vt_constraint)
-        .bind(cb_expression)
-        .bind(cb_parameters)
-        .bind(state_pseudo_kind)
-        .bind(state_entry)
-        .bind(state_exit)
-        .bind(state_do)
-        .bind(serde_json::to_string(&node.meta)?)
-        .bind(node.created_at.to_rfc3339())
-        .bind(node.modified_at.to_rfc3339())
-        .execute(&mut *tx)
         .await?;
+        tx.commit().await?;
 
-        if let Some(next) = next_requirement_snapshot {
-            if prev_requirement_snapshot.as_ref() != Some(&next) {
-                let prev = prev_requirement_snapshot.unwrap_or_default();
-                sqlx::query(
-                    "INSERT INTO requirement_history
-                     (id, project_id, node_id, actor, change_source, changed_at, prev_snapshot, next_snapshot)
-                     VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
-                )
-                .bind(Uuid::new_v4().to_string())
-                .bind(node.project_id.to_string())
-                .bind(node.id.to_string())
-                .bind(extract_history_actor(node))
-                .bind(extract_history_source(node))
-                .bind(node.modified_at.to_rfc3339())
-                .bind(serde_json::to_string(&prev)?)
-                .bind(serde_json::to_string(&next)?)
-                .execute(&mut *tx)
-                .await?;
+        Ok(())
+    }
+
+    /// Apply a planned requirement split in one transaction: create the
+    /// children and edges, then update the parent (`plan.parent`, marked
+    /// Obsolete) or delete it. Requirement history is written for every
+    /// child and for an updated parent.
+    pub async fn apply_requirement_split(
+        &self,
+        parent: &Node,
+        plan: &crate::core::split::RequirementSplit,
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for child in &plan.children {
+            let history_actor = self.stamp_actor(&extract_history_actor(child));
+            write_node(&mut tx, child, None, &history_actor).await?;
+        }
+        for edge in &plan.edges {
+            sqlx::query(
+                "INSERT INTO edges
+                 (id, project_id, kind, source_id, target_id, label, meta, created_at, modified_at)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(edge.id.to_string())
+            .bind(edge.project_id.to_string())
+            .bind(edge.kind.to_string())
+            .bind(edge.source_id.to_string())
+            .bind(edge.target_id.to_string())
+            .bind(&edge.label)
+            .bind(serde_json::to_string(&edge.meta)?)
+            .bind(edge.created_at.to_rfc3339())
+            .bind(edge.modified_at.to_rfc3339())
+            .execute(&mut *tx)
+            .await?;
+        }
+        match &plan.parent {
+            Some(updated) => {
+                let history_actor = self.stamp_actor(&extract_history_actor(updated));
+                let prev = requirement_snapshot_from_node(parent);
+                write_node(&mut tx, updated, prev, &history_actor).await?;
+            }
+            None => {
+                sqlx::query("DELETE FROM nodes WHERE id = ?")
+                    .bind(parent.id.to_string())
+                    .execute(&mut *tx)
+                    .await?;
             }
         }
 
+        let actor = plan
+            .children
+            .first()
+            .map(extract_history_actor)
+            .unwrap_or_else(|| "system".to_string());
+        for child in &plan.children {
+            self.record_audit(
+                &mut tx,
+                child.project_id,
+                "node",
+                child.id,
+                "create",
+                &actor,
+                &format!("created {} '{}' (split from '{}')", child.kind, child.name, parent.name),
+            )
+            .await?;
+        }
+        let (action, verb) = if plan.parent.is_some() {
+            ("update", "obsoleted")
+        } else {
+            ("delete", "deleted")
+        };
+        self.record_audit(
+            &mut tx,
+            parent.project_id,
+            "node",
+            parent.id,
+            action,
+            &actor,
+            &format!(
+                "{verb} {} '{}' after splitting it into {}",
+                parent.kind,
+                parent.name,
+                plan.children.len()
+            ),
+        )
+        .await?;
         tx.commit().await?;
-
         Ok(())
     }
 
-    pub async fn delete_node(&self, id: Uuid) -> Result<()> {
+    pub async fn delete_node(&self, id: Uuid, actor: &str) -> Result<()> {
+        let existing = self.get_node(id).await?;
+        let mut tx = self.pool.begin().await?;
         sqlx::query("DELETE FROM nodes WHERE id = ?")
             .bind(id.to_string())
-            .execute(&self.pool)
+            .execute(&mut *tx)
+            .await?;
+        if let Some(node) = existing {
+            self.record_audit(
+                &mut tx,
+                node.project_id,
+                "node",
+                id,
+                "delete",
+                actor,
+                &format!("deleted {} '{}'", node.kind, node.name),
+            )
             .await?;
+        }
+        tx.commit().await?;
         Ok(())
     }
 
+    /// Set the archived flag of `ids`, leaving their edges, history and
+    /// modification time alone. Returns the nodes whose flag changed, as
+    /// stored afterwards; ids that don't exist or already match are skipped.
+    pub async fn set_nodes_archived(
+        &self,
+        ids: &[Uuid],
+        archived: bool,
+        actor: &str,
+    ) -> Result<Vec<Node>> {
+        let mut changed = Vec::new();
+        let mut tx = self.pool.begin().await?;
+        for id in ids {
+            let row = sqlx::query("UPDATE nodes SET archived = ? WHERE id = ? AND archived != ?")
+                .bind(archived as i64)
+                .bind(id.to_string())
+                .bind(archived as i64)
+                .execute(&mut *tx)
+                .await?;
+            if row.rows_affected() > 0 {
+                changed.push(*id);
+            }
+        }
+
+        let mut nodes = Vec::with_capacity(changed.len());
+        for id in changed {
+            let row = sqlx::query("SELECT * FROM nodes WHERE id = ?")
+                .bind(id.to_string())
+                .fetch_optional(&mut *tx)
+                .await?;
+            let Some(node) = row.as_ref().map(row_to_node).transpose()? else {
+                continue;
+            };
+            self.record_audit(
+                &mut tx,
+                node.project_id,
+                "node",
+                node.id,
+                "update",
+                actor,
+                &format!(
+                    "{} {} '{}'",
+                    if archived { "archived" } else { "restored" },
+                    node.kind,
+                    node.name
+                ),
+            )
+            .await?;
+            nodes.push(node);
+        }
+        tx.commit().await?;
+        Ok(nodes)
+    }
+
+    #[tracing::instrument(target = "query", skip_all, fields(project_id = %project_id, rows), err)]
     pub async fn list_nodes(&self, project_id: Uuid) -> Result<Vec<Node>> {
-        let rows = sqlx::query("SELECT * FROM nodes WHERE project_id = ? ORDER BY created_at")
+        let rows = sqlx::query("SELECT * FROM nodes WHERE project_id = ? ORDER BY created_at, id")
             .bind(project_id.to_string())
             .fetch_all(&self.pool)
             .await?;
 
-        rows.iter().map(row_to_node).collect()
+        tracing::Span::current().record("rows", rows.len());
+        map_known_kinds(&rows, is_node_kind, row_to_node)
     }
 
+    #[tracing::instrument(target = "query", skip_all, fields(project_id = %project_id, rows), err)]
     pub async fn list_nodes_by_kind(&self, project_id: Uuid, kind: &NodeKind) -> Result<Vec<Node>> {
         let rows = sqlx::query(
-            "SELECT * FROM nodes WHERE project_id = ? AND kind = ? ORDER BY created_at",
+            "SELECT * FROM nodes WHERE project_id = ? AND kind = ? ORDER BY created_at, id",
         )
         .bind(project_id.to_string())
         .bind(kind.to_string())
         .fetch_all(&self.pool)
         .await?;
 
+        tracing::Span::current().record("rows", rows.len());
         rows.iter().map(row_to_node).collect()
     }
 
+    /// Id, kind, name, req_id and status of every node, optionally of one
+    /// kind and leaving out archived ones, in `list_nodes` order. Reads a
+    /// few columns and parses no JSON, for callers that only need labels.
+    pub async fn list_node_summaries(
+        &self,
+        project_id: Uuid,
+        kind: Option<&NodeKind>,
+        include_archived: bool,
+    ) -> Result<Vec<NodeSummary>> {
+        let rows = sqlx::query(
+            "SELECT id, kind, name, req_id, req_status, archived FROM nodes
+             WHERE project_id = ? AND (? IS NULL OR kind = ?) AND (? OR archived = 0)
+             ORDER BY created_at, id",
+        )
+        .bind(project_id.to_string())
+        .bind(kind.map(|k| k.to_string()))
+        .bind(kind.map(|k| k.to_string()))
+        .bind(include_archived)
+        .fetch_all(&self.pool)
+        .await?;
+
+        map_known_kinds(&rows, is_node_kind, row_to_node_summary)
+    }
+
     // ── Edges ─────────────────────────────────────────────────────────────────
 
     pub async fn list_requirement_history(
@@ -312,54 +784,344 @@ impl Store {
         rows.iter().map(row_to_requirement_history).collect()
     }
 
-    pub async fn upsert_edge(&self, edge: &Edge) -> Result<()> {
-        sqlx::query(
-            "INSERT INTO edges (id, project_id, kind, source_id, target_id, label, meta, created_at, modified_at)
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
-             ON CONFLICT(id) DO UPDATE SET
-                label = excluded.label,
-                meta = excluded.meta,
-                modified_at = excluded.modified_at",
+    pub async fn count_requirement_history(&self, node_id: Uuid) -> Result<i64> {
+        let row = sqlx::query("SELECT COUNT(*) AS n FROM requirement_history WHERE node_id = ?")
+            .bind(node_id.to_string())
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(row.try_get("n")?)
+    }
+
+    /// Every history entry for one node, oldest first.
+    pub async fn list_node_requirement_history(
+        &self,
+        node_id: Uuid,
+    ) -> Result<Vec<RequirementHistoryEntry>> {
+        let rows = sqlx::query(
+            "SELECT * FROM requirement_history WHERE node_id = ? ORDER BY changed_at, id",
         )
-        .bind(edge.id.to_string())
-        .bind(edge.project_id.to_string())
-        .bind(edge.kind.to_string())
-        .bind(edge.source_id.to_string())
-        .bind(edge.target_id.to_string())
-        .bind(&edge.label)
-        .bind(serde_json::to_string(&edge.meta)?)
-        .bind(edge.created_at.to_rfc3339())
-        .bind(edge.modified_at.to_rfc3339())
-        .execute(&self.pool)
+        .bind(node_id.to_string())
+        .fetch_all(&self.pool)
         .await?;
 
-        Ok(())
+        rows.iter().map(row_to_requirement_history).collect()
     }
 
-    pub async fn delete_edge(&self, id: Uuid) -> Result<()> {
-        sqlx::query("DELETE FROM edges WHERE id = ?")
-            .bind(id.to_string())
-            .execute(&self.pool)
-            .await?;
-        Ok(())
+    /// Every history entry in a project, for retention planning.
+    pub async fn list_all_requirement_history(
+        &self,
+        project_id: Uuid,
+    ) -> Result<Vec<RequirementHistoryEntry>> {
+        let rows = sqlx::query(
+            "SELECT * FROM requirement_history WHERE project_id = ? ORDER BY node_id, changed_at, id",
+        )
+        .bind(project_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(row_to_requirement_history).collect()
     }
 
-    pub async fn edges_for_node(&self, node_id: Uuid) -> Result<Vec<Edge>> {
-        let rows = sqlx::query("SELECT * FROM edges WHERE source_id = ? OR target_id = ?")
-            .bind(node_id.to_string())
-            .bind(node_id.to_string())
-            .fetch_all(&self.pool)
+    /// Rewrite collapsed survivors and delete pruned entries in one
+    /// transaction. Returns the number of rows deleted.
+    pub async fn apply_requirement_history_prune(
+        &self,
+        updated: &[RequirementHistoryEntry],
+        deleted: &[Uuid],
+    ) -> Result<u64> {
+        let mut tx = self.pool.begin().await?;
+        for entry in updated {
+            sqlx::query(
+                "UPDATE requirement_history SET next_snapshot = ?, changed_at = ? WHERE id = ?",
+            )
+            .bind(serde_json::to_string(&entry.next)?)
+            .bind(entry.ts.to_rfc3339())
+            .bind(entry.id.to_string())
+            .execute(&mut *tx)
             .await?;
-
-        rows.iter().map(row_to_edge).collect()
+        }
+        let mut removed = 0;
+        for chunk in deleted.chunks(500) {
+            let placeholders = vec!["?"; chunk.len()].join(", ");
+            let sql = format!("DELETE FROM requirement_history WHERE id IN ({placeholders})");
+            let mut query = sqlx::query(&sql);
+            for id in chunk {
+                query = query.bind(id.to_string());
+            }
+            removed += query.execute(&mut *tx).await?.rows_affected();
+        }
+        tx.commit().await?;
+        Ok(removed)
     }
 
-    // ── Diagrams ──────────────────────────────────────────────────────────────
-
-    pub async fn upsert_diagram(&self, diagram: &Diagram) -> Result<()> {
-        sqlx::query(
-            "INSERT INTO diagrams (id, project_id, kind, name, description, layout_options, created_at, modified_at)
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+    /// Requirement changes across a project, newest first, optionally
+    /// restricted by time, change source and actor.
+    pub async fn list_project_requirement_history(
+        &self,
+        project_id: Uuid,
+        since: Option<chrono::DateTime<Utc>>,
+        source: Option<&str>,
+        actor: Option<&str>,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<ProjectRequirementHistoryEntry>> {
+        let mut sql = String::from(
+            "SELECT h.*, n.req_id AS current_req_id, n.name AS current_name
+             FROM requirement_history h
+             JOIN nodes n ON n.id = h.node_id
+             WHERE h.project_id = ?",
+        );
+        if since.is_some() {
+            sql.push_str(" AND h.changed_at >= ?");
+        }
+        if source.is_some() {
+            sql.push_str(" AND h.change_source = ?");
+        }
+        if actor.is_some() {
+            sql.push_str(" AND h.actor = ?");
+        }
+        sql.push_str(" ORDER BY h.changed_at DESC LIMIT ? OFFSET ?");
+
+        let mut query = sqlx::query(&sql).bind(project_id.to_string());
+        if let Some(ts) = since {
+            query = query.bind(ts.to_rfc3339());
+        }
+        if let Some(source) = source {
+            query = query.bind(source.to_string());
+        }
+        if let Some(actor) = actor {
+            query = query.bind(actor.to_string());
+        }
+        let rows = query
+            .bind(limit as i64)
+            .bind(offset as i64)
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.iter()
+            .map(|row| {
+                Ok(ProjectRequirementHistoryEntry {
+                    entry: row_to_requirement_history(row)?,
+                    req_id: row.try_get("current_req_id")?,
+                    name: row.try_get("current_name")?,
+                })
+            })
+            .collect()
+    }
+
+    /// Requirement change counts per UTC day and per change source.
+    pub async fn requirement_history_stats(
+        &self,
+        project_id: Uuid,
+        since: Option<chrono::DateTime<Utc>>,
+    ) -> Result<RequirementHistoryStats> {
+        let since = since.map(|ts| ts.to_rfc3339()).unwrap_or_default();
+        let counts = |group: &'static str| {
+            let sql = format!(
+                "SELECT {group} AS key, COUNT(*) AS count FROM requirement_history
+                 WHERE project_id = ? AND changed_at >= ?
+                 GROUP BY key ORDER BY key"
+            );
+            let pool = &self.pool;
+            let since = since.clone();
+            async move {
+                let rows = sqlx::query(&sql)
+                    .bind(project_id.to_string())
+                    .bind(since)
+                    .fetch_all(pool)
+                    .await?;
+                rows.iter()
+                    .map(|r| Ok(HistoryCount { key: r.try_get("key")?, count: r.try_get("count")? }))
+                    .collect::<Result<Vec<_>>>()
+            }
+        };
+
+        let by_day = counts("substr(changed_at, 1, 10)").await?;
+        let by_source = counts("change_source").await?;
+        Ok(RequirementHistoryStats {
+            total: by_source.iter().map(|c| c.count).sum(),
+            by_day,
+            by_source,
+        })
+    }
+
+    pub async fn upsert_edge(&self, edge: &Edge) -> Result<()> {
+        let existed = sqlx::query("SELECT 1 FROM edges WHERE id = ?")
+            .bind(edge.id.to_string())
+            .fetch_optional(&self.pool)
+            .await?
+            .is_some();
+        let src = self.node_name(edge.source_id).await?.unwrap_or_else(|| "?".to_string());
+        let tgt = self.node_name(edge.target_id).await?.unwrap_or_else(|| "?".to_string());
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            "INSERT INTO edges (id, project_id, kind, source_id, target_id, label, meta, created_at, modified_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(id) DO UPDATE SET
+                label = excluded.label,
+                meta = excluded.meta,
+                modified_at = excluded.modified_at",
+        )
+        .bind(edge.id.to_string())
+        .bind(edge.project_id.to_string())
+        .bind(edge.kind.to_string())
+        .bind(edge.source_id.to_string())
+        .bind(edge.target_id.to_string())
+        .bind(&edge.label)
+        .bind(serde_json::to_string(&edge.meta)?)
+        .bind(edge.created_at.to_rfc3339())
+        .bind(edge.modified_at.to_rfc3339())
+        .execute(&mut *tx)
+        .await?;
+
+        let (action, verb) = if existed { ("update", "updated") } else { ("create", "linked") };
+        self.record_audit(
+            &mut tx,
+            edge.project_id,
+            "edge",
+            edge.id,
+            action,
+            &meta_actor(&edge.meta),
+            &format!("{verb} '{src}' «{}» '{tgt}'", edge.kind),
+        )
+        .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    pub async fn get_edge(&self, id: Uuid) -> Result<Option<Edge>> {
+        sqlx::query("SELECT * FROM edges WHERE id = ?")
+            .bind(id.to_string())
+            .fetch_optional(&self.pool)
+            .await?
+            .as_ref()
+            .map(row_to_edge)
+            .transpose()
+    }
+
+    pub async fn delete_edge(&self, id: Uuid, actor: &str) -> Result<()> {
+        let existing = match self.get_edge(id).await? {
+            Some(edge) => {
+                let src = self.node_name(edge.source_id).await?;
+                let tgt = self.node_name(edge.target_id).await?;
+                let unknown = || "?".to_string();
+                Some((edge, src.unwrap_or_else(unknown), tgt.unwrap_or_else(unknown)))
+            }
+            None => None,
+        };
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM edges WHERE id = ?")
+            .bind(id.to_string())
+            .execute(&mut *tx)
+            .await?;
+        if let Some((edge, src, tgt)) = existing {
+            self.record_audit(
+                &mut tx,
+                edge.project_id,
+                "edge",
+                id,
+                "delete",
+                actor,
+                &format!("unlinked '{src}' «{}» '{tgt}'", edge.kind),
+            )
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(target = "query", skip_all, fields(rows), err)]
+    pub async fn edges_for_node(&self, node_id: Uuid) -> Result<Vec<Edge>> {
+        let rows = sqlx::query(
+            "SELECT * FROM edges WHERE source_id = ? OR target_id = ? ORDER BY created_at, id",
+        )
+        .bind(node_id.to_string())
+        .bind(node_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        tracing::Span::current().record("rows", rows.len());
+        map_known_kinds(&rows, is_edge_kind, row_to_edge)
+    }
+
+    /// Every edge in a project, ordered by id.
+    #[tracing::instrument(target = "query", skip_all, fields(project_id = %project_id, rows), err)]
+    pub async fn list_edges(&self, project_id: Uuid) -> Result<Vec<Edge>> {
+        let rows = sqlx::query("SELECT * FROM edges WHERE project_id = ? ORDER BY id")
+            .bind(project_id.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        tracing::Span::current().record("rows", rows.len());
+        map_known_kinds(&rows, is_edge_kind, row_to_edge)
+    }
+
+    /// All edges in a project whose kind is one of `kinds`, in creation
+    /// order. An empty `kinds` slice matches every edge kind.
+    pub async fn list_edges_by_kind(&self, project_id: Uuid, kinds: &[EdgeKind]) -> Result<Vec<Edge>> {
+        self.list_edges_filtered(project_id, kinds, None, None).await
+    }
+
+    /// Like `list_edges_by_kind`, additionally restricted by the kind of the
+    /// source and/or target node (e.g. only «satisfies» edges from Blocks).
+    #[tracing::instrument(target = "query", skip_all, fields(project_id = %project_id, rows), err)]
+    pub async fn list_edges_filtered(
+        &self,
+        project_id: Uuid,
+        kinds: &[EdgeKind],
+        source_kind: Option<&NodeKind>,
+        target_kind: Option<&NodeKind>,
+    ) -> Result<Vec<Edge>> {
+        let mut sql = String::from(
+            "SELECT e.* FROM edges e
+             JOIN nodes s ON s.id = e.source_id
+             JOIN nodes t ON t.id = e.target_id
+             WHERE e.project_id = ?",
+        );
+        if !kinds.is_empty() {
+            let placeholders = vec!["?"; kinds.len()].join(", ");
+            sql.push_str(&format!(" AND e.kind IN ({placeholders})"));
+        }
+        if source_kind.is_some() {
+            sql.push_str(" AND s.kind = ?");
+        }
+        if target_kind.is_some() {
+            sql.push_str(" AND t.kind = ?");
+        }
+        sql.push_str(" ORDER BY e.created_at, e.id");
+
+        let mut query = sqlx::query(&sql).bind(project_id.to_string());
+        for kind in kinds {
+            query = query.bind(kind.to_string());
+        }
+        if let Some(kind) = source_kind {
+            query = query.bind(kind.to_string());
+        }
+        if let Some(kind) = target_kind {
+            query = query.bind(kind.to_string());
+        }
+
+        let rows = query.fetch_all(&self.pool).await?;
+        tracing::Span::current().record("rows", rows.len());
+        map_known_kinds(&rows, is_edge_kind, row_to_edge)
+    }
+
+    // ── Diagrams ──────────────────────────────────────────────────────────────
+
+    pub async fn upsert_diagram(&self, diagram: &Diagram, actor: &str) -> Result<()> {
+        let prev_name: Option<String> = sqlx::query("SELECT name FROM diagrams WHERE id = ?")
+            .bind(diagram.id.to_string())
+            .fetch_optional(&self.pool)
+            .await?
+            .map(|r| r.try_get("name"))
+            .transpose()?;
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            "INSERT INTO diagrams (id, project_id, kind, name, description, layout_options, created_at, modified_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)
              ON CONFLICT(id) DO UPDATE SET
                 name = excluded.name,
                 description = excluded.description,
@@ -374,47 +1136,129 @@ impl Store {
         .bind(serde_json::to_string(&diagram.layout_options)?)
         .bind(diagram.created_at.to_rfc3339())
         .bind(diagram.modified_at.to_rfc3339())
-        .execute(&self.pool)
+        .execute(&mut *tx)
+        .await?;
+
+        let (action, summary) = match prev_name {
+            None => ("create", format!("created diagram '{}'", diagram.name)),
+            Some(prev) if prev != diagram.name => {
+                ("update", format!("renamed diagram '{}' → '{}'", prev, diagram.name))
+            }
+            Some(_) => ("update", format!("updated diagram '{}'", diagram.name)),
+        };
+        self.record_audit(
+            &mut tx,
+            diagram.project_id,
+            "diagram",
+            diagram.id,
+            action,
+            actor,
+            &summary,
+        )
         .await?;
+        tx.commit().await?;
 
         Ok(())
     }
 
-    pub async fn delete_diagram(&self, diagram_id: Uuid) -> Result<()> {
+    pub async fn delete_diagram(&self, diagram_id: Uuid, actor: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        let existing = sqlx::query("SELECT project_id, name FROM diagrams WHERE id = ?")
+            .bind(diagram_id.to_string())
+            .fetch_optional(&mut *tx)
+            .await?;
         sqlx::query("DELETE FROM diagram_elements WHERE diagram_id = ?")
             .bind(diagram_id.to_string())
-            .execute(&self.pool)
+            .execute(&mut *tx)
             .await?;
         sqlx::query("DELETE FROM diagrams WHERE id = ?")
             .bind(diagram_id.to_string())
-            .execute(&self.pool)
+            .execute(&mut *tx)
+            .await?;
+        if let Some(row) = existing {
+            let project_id: Uuid = row.try_get::<String, _>("project_id")?.parse()?;
+            let name: String = row.try_get("name")?;
+            self.record_audit(
+                &mut tx,
+                project_id,
+                "diagram",
+                diagram_id,
+                "delete",
+                actor,
+                &format!("deleted diagram '{name}'"),
+            )
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn get_diagram(&self, diagram_id: Uuid) -> Result<Option<Diagram>> {
+        let row = sqlx::query("SELECT * FROM diagrams WHERE id = ?")
+            .bind(diagram_id.to_string())
+            .fetch_optional(&self.pool)
+            .await?;
+        row.as_ref().map(row_to_diagram).transpose()
+    }
+
+    pub async fn diagram_edge_routes(&self, diagram_id: Uuid) -> Result<Vec<DiagramEdgeRoute>> {
+        let rows = sqlx::query("SELECT * FROM diagram_edge_routes WHERE diagram_id = ?")
+            .bind(diagram_id.to_string())
+            .fetch_all(&self.pool)
             .await?;
+        rows.iter()
+            .map(|row| {
+                let waypoints: String = row.try_get("waypoints")?;
+                Ok(DiagramEdgeRoute {
+                    id: row.try_get::<String, _>("id")?.parse()?,
+                    diagram_id: row.try_get::<String, _>("diagram_id")?.parse()?,
+                    edge_id: row.try_get::<String, _>("edge_id")?.parse()?,
+                    waypoints: serde_json::from_str(&waypoints)?,
+                })
+            })
+            .collect()
+    }
+
+    /// Rewrites the waypoints of existing routes in one transaction.
+    pub async fn update_edge_route_waypoints(&self, routes: &[DiagramEdgeRoute]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for route in routes {
+            sqlx::query("UPDATE diagram_edge_routes SET waypoints = ? WHERE id = ?")
+                .bind(serde_json::to_string(&route.waypoints)?)
+                .bind(route.id.to_string())
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
         Ok(())
     }
 
     pub async fn list_diagrams(&self, project_id: Uuid) -> Result<Vec<Diagram>> {
-        let rows = sqlx::query("SELECT * FROM diagrams WHERE project_id = ? ORDER BY created_at")
+        let rows = sqlx::query("SELECT * FROM diagrams WHERE project_id = ? ORDER BY created_at, id")
             .bind(project_id.to_string())
             .fetch_all(&self.pool)
             .await?;
 
-        rows.iter().map(row_to_diagram).collect()
+        map_known_kinds(&rows, is_diagram_kind, row_to_diagram)
     }
 
     // ── Diagram elements ──────────────────────────────────────────────────────
 
-    pub async fn upsert_diagram_element(&self, el: &DiagramElement) -> Result<()> {
+    pub async fn upsert_diagram_element(&self, el: &DiagramElement, actor: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
         sqlx::query(
             "INSERT INTO diagram_elements
-                (id, diagram_id, node_id, x, y, width, height, collapsed, style_overrides)
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+                (id, diagram_id, node_id, x, y, width, height, collapsed, style_overrides,
+                 created_at, modified_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
              ON CONFLICT(diagram_id, node_id) DO UPDATE SET
                 x = excluded.x,
                 y = excluded.y,
                 width = excluded.width,
                 height = excluded.height,
                 collapsed = excluded.collapsed,
-                style_overrides = excluded.style_overrides",
+                style_overrides = excluded.style_overrides,
+                modified_at = excluded.modified_at",
         )
         .bind(el.id.to_string())
         .bind(el.diagram_id.to_string())
@@ -425,128 +1269,533 @@ impl Store {
         .bind(el.height)
         .bind(el.collapsed as i64)
         .bind(serde_json::to_string(&el.style_overrides)?)
-        .execute(&self.pool)
+        .bind(el.created_at.to_rfc3339())
+        .bind(Utc::now().to_rfc3339())
+        .execute(&mut *tx)
         .await?;
 
+        // Element drags fire many writes per second; fold them into one
+        // entry per diagram per window rather than one per write.
+        let project_id = sqlx::query("SELECT project_id FROM diagrams WHERE id = ?")
+            .bind(el.diagram_id.to_string())
+            .fetch_optional(&mut *tx)
+            .await?
+            .map(|r| r.try_get::<String, _>("project_id"))
+            .transpose()?;
+        if let Some(project_id) = project_id {
+            self.record_audit_coalesced(
+                &mut tx,
+                project_id.parse()?,
+                "diagram_element",
+                el.diagram_id,
+                "update",
+                actor,
+                "edited diagram layout",
+                300,
+            )
+            .await?;
+        }
+        tx.commit().await?;
+
         Ok(())
     }
 
     pub async fn diagram_elements(&self, diagram_id: Uuid) -> Result<Vec<DiagramElement>> {
-        let rows = sqlx::query("SELECT * FROM diagram_elements WHERE diagram_id = ?")
-            .bind(diagram_id.to_string())
-            .fetch_all(&self.pool)
-            .await?;
+        let rows = sqlx::query(
+            "SELECT * FROM diagram_elements WHERE diagram_id = ? ORDER BY created_at, id",
+        )
+        .bind(diagram_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
 
         rows.iter().map(row_to_diagram_element).collect()
     }
 
-    // -- Documents ----------------------------------------------------------
-
-    pub async fn list_documents(&self, project_id: Uuid) -> Result<Vec<Document>> {
-        let rows =
-            sqlx::query("SELECT * FROM documents WHERE project_id = ? ORDER BY added_at DESC")
-                .bind(project_id.to_string())
-                .fetch_all(&self.pool)
-                .await?;
-        rows.iter().map(row_to_document).collect()
-    }
-
-    pub async fn upsert_document(&self, doc: &Document) -> Result<()> {
-        sqlx::query(
-            "INSERT INTO documents (id, project_id, name, doc_type, size, added_at, text, source_base64, source_mime)
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
-             ON CONFLICT(id) DO UPDATE SET
-                name = excluded.name,
-                doc_type = excluded.doc_type,
-                size = excluded.size,
-                added_at = excluded.added_at,
-                text = excluded.text,
-                source_base64 = excluded.source_base64,
-                source_mime = excluded.source_mime",
+    pub async fn list_project_diagram_elements(&self, project_id: Uuid) -> Result<Vec<DiagramElement>> {
+        let rows = sqlx::query(
+            "SELECT de.* FROM diagram_elements de
+             JOIN diagrams d ON d.id = de.diagram_id
+             WHERE d.project_id = ?
+             ORDER BY de.diagram_id, de.created_at, de.id",
         )
-        .bind(doc.id.to_string())
-        .bind(doc.project_id.to_string())
-        .bind(&doc.name)
-        .bind(&doc.doc_type)
-        .bind(doc.size)
-        .bind(doc.added_at.to_rfc3339())
-        .bind(&doc.text)
-        .bind(&doc.source_base64)
-        .bind(&doc.source_mime)
-        .execute(&self.pool)
+        .bind(project_id.to_string())
+        .fetch_all(&self.pool)
         .await?;
-        Ok(())
+
+        rows.iter().map(row_to_diagram_element).collect()
     }
 
-    pub async fn delete_document(&self, id: Uuid) -> Result<()> {
-        sqlx::query("DELETE FROM documents WHERE id = ?")
-            .bind(id.to_string())
-            .execute(&self.pool)
+    /// Replace the `style_overrides` of `styles`' elements in one
+    /// transaction, recording a single audit entry for the diagram.
+    pub async fn set_element_styles(
+        &self,
+        diagram_id: Uuid,
+        styles: &[(Uuid, std::collections::HashMap<String, serde_json::Value>)],
+        summary: &str,
+        actor: &str,
+    ) -> Result<()> {
+        let now = Utc::now().to_rfc3339();
+        let diagram = self.get_diagram(diagram_id).await?;
+        let mut tx = self.pool.begin().await?;
+        for (element_id, style) in styles {
+            sqlx::query(
+                "UPDATE diagram_elements SET style_overrides = ?, modified_at = ?
+                 WHERE id = ? AND diagram_id = ?",
+            )
+            .bind(serde_json::to_string(style)?)
+            .bind(&now)
+            .bind(element_id.to_string())
+            .bind(diagram_id.to_string())
+            .execute(&mut *tx)
             .await?;
+        }
+
+        if let Some(diagram) = diagram {
+            let project_id = diagram.project_id;
+            self.record_audit(&mut tx, project_id, "diagram", diagram_id, "update", actor, summary)
+                .await?;
+        }
+        tx.commit().await?;
         Ok(())
     }
 
-    // -- Document sections -------------------------------------------------
+    // -- Layout sessions ------------------------------------------------------
 
-    pub async fn upsert_document_section(&self, s: &DocumentSection) -> Result<()> {
+    /// Opens a layout session on a diagram, capturing its element geometry.
+    pub async fn begin_layout_session(&self, diagram_id: Uuid) -> Result<LayoutSession> {
+        let rows = sqlx::query(
+            "SELECT node_id, x, y, width, height FROM diagram_elements
+             WHERE diagram_id = ? ORDER BY node_id",
+        )
+        .bind(diagram_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+        let snapshot = rows
+            .iter()
+            .map(|row| -> Result<ElementGeometry> {
+                Ok(ElementGeometry {
+                    node_id: row.try_get::<String, _>("node_id")?.parse()?,
+                    x: row.try_get("x")?,
+                    y: row.try_get("y")?,
+                    width: row.try_get("width")?,
+                    height: row.try_get("height")?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let session = LayoutSession {
+            id: Uuid::new_v4(),
+            diagram_id,
+            status: LayoutSessionStatus::Open,
+            element_count: snapshot.len() as i64,
+            started_at: Utc::now(),
+            ended_at: None,
+        };
         sqlx::query(
-            "INSERT INTO document_sections
-             (id, document_id, project_id, section_ref, section_type, title, body,
-              part_number, quantity, unit, position, created_at)
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-             ON CONFLICT(id) DO UPDATE SET
-               section_ref  = excluded.section_ref,
-               section_type = excluded.section_type,
-               title        = excluded.title,
-               body         = excluded.body,
-               part_number  = excluded.part_number,
-               quantity     = excluded.quantity,
-               unit         = excluded.unit,
-               position     = excluded.position",
+            "INSERT INTO layout_sessions (id, diagram_id, status, snapshot, started_at)
+             VALUES (?, ?, ?, ?, ?)",
         )
-        .bind(s.id.to_string())
-        .bind(s.document_id.to_string())
-        .bind(s.project_id.to_string())
-        .bind(&s.section_ref)
-        .bind(s.section_type.to_string())
-        .bind(&s.title)
-        .bind(&s.body)
-        .bind(&s.part_number)
-        .bind(&s.quantity)
-        .bind(&s.unit)
-        .bind(s.position)
-        .bind(s.created_at.to_rfc3339())
+        .bind(session.id.to_string())
+        .bind(diagram_id.to_string())
+        .bind(session.status.to_string())
+        .bind(serde_json::to_string(&snapshot)?)
+        .bind(session.started_at.to_rfc3339())
         .execute(&self.pool)
         .await?;
-        Ok(())
+        Ok(session)
     }
 
-    pub async fn list_document_sections(&self, document_id: Uuid) -> Result<Vec<DocumentSection>> {
-        let rows =
-            sqlx::query("SELECT * FROM document_sections WHERE document_id = ? ORDER BY position")
-                .bind(document_id.to_string())
-                .fetch_all(&self.pool)
-                .await?;
-        rows.iter().map(row_to_document_section).collect()
+    pub async fn get_layout_session(&self, id: Uuid) -> Result<Option<LayoutSession>> {
+        let row = sqlx::query(
+            "SELECT id, diagram_id, status, json_array_length(snapshot) AS element_count,
+                    started_at, ended_at
+             FROM layout_sessions WHERE id = ?",
+        )
+        .bind(id.to_string())
+        .fetch_optional(&self.pool)
+        .await?;
+        row.as_ref().map(row_to_layout_session).transpose()
     }
 
-    pub async fn list_project_document_sections(
-        &self,
-        project_id: Uuid,
-    ) -> Result<Vec<DocumentSection>> {
+    /// The diagram's sessions, newest first.
+    pub async fn list_layout_sessions(&self, diagram_id: Uuid) -> Result<Vec<LayoutSession>> {
         let rows = sqlx::query(
-            "SELECT * FROM document_sections WHERE project_id = ? ORDER BY document_id, position",
+            "SELECT id, diagram_id, status, json_array_length(snapshot) AS element_count,
+                    started_at, ended_at
+             FROM layout_sessions WHERE diagram_id = ?
+             ORDER BY started_at DESC, id",
         )
-        .bind(project_id.to_string())
+        .bind(diagram_id.to_string())
         .fetch_all(&self.pool)
         .await?;
-        rows.iter().map(row_to_document_section).collect()
+        rows.iter().map(row_to_layout_session).collect()
     }
 
-    pub async fn delete_document_sections(&self, document_id: Uuid) -> Result<()> {
-        sqlx::query("DELETE FROM document_sections WHERE document_id = ?")
-            .bind(document_id.to_string())
-            .execute(&self.pool)
+    pub async fn layout_session_snapshot(&self, id: Uuid) -> Result<Vec<ElementGeometry>> {
+        let snapshot: Option<String> =
+            sqlx::query_scalar("SELECT snapshot FROM layout_sessions WHERE id = ?")
+                .bind(id.to_string())
+                .fetch_optional(&self.pool)
+                .await?;
+        match snapshot {
+            Some(raw) => Ok(serde_json::from_str(&raw)?),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    pub async fn end_layout_session(&self, id: Uuid, status: LayoutSessionStatus) -> Result<()> {
+        sqlx::query("UPDATE layout_sessions SET status = ?, ended_at = ? WHERE id = ?")
+            .bind(status.to_string())
+            .bind(Utc::now().to_rfc3339())
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Moves the diagram's elements back to `geometry`. Elements added since
+    /// are left where they are.
+    pub async fn restore_element_geometry(
+        &self,
+        diagram_id: Uuid,
+        geometry: &[ElementGeometry],
+    ) -> Result<()> {
+        let now = Utc::now().to_rfc3339();
+        let mut tx = self.pool.begin().await?;
+        for g in geometry {
+            sqlx::query(
+                "UPDATE diagram_elements SET x = ?, y = ?, width = ?, height = ?, modified_at = ?
+                 WHERE diagram_id = ? AND node_id = ?",
+            )
+            .bind(g.x)
+            .bind(g.y)
+            .bind(g.width)
+            .bind(g.height)
+            .bind(&now)
+            .bind(diagram_id.to_string())
+            .bind(g.node_id.to_string())
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Deletes all but the `keep` newest ended sessions of a diagram.
+    pub async fn prune_layout_sessions(&self, diagram_id: Uuid, keep: i64) -> Result<u64> {
+        let result = sqlx::query(
+            "DELETE FROM layout_sessions
+             WHERE diagram_id = ? AND status <> 'open' AND id NOT IN (
+                 SELECT id FROM layout_sessions
+                 WHERE diagram_id = ? AND status <> 'open'
+                 ORDER BY started_at DESC, id LIMIT ?)",
+        )
+        .bind(diagram_id.to_string())
+        .bind(diagram_id.to_string())
+        .bind(keep)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    // -- Style presets --------------------------------------------------------
+
+    pub async fn list_style_presets(&self, project_id: Uuid) -> Result<Vec<StylePreset>> {
+        let rows = sqlx::query(
+            "SELECT * FROM style_presets WHERE project_id = ? ORDER BY name COLLATE NOCASE, id",
+        )
+        .bind(project_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter().map(row_to_style_preset).collect()
+    }
+
+    pub async fn get_style_preset(&self, id: Uuid) -> Result<Option<StylePreset>> {
+        let row = sqlx::query("SELECT * FROM style_presets WHERE id = ?")
+            .bind(id.to_string())
+            .fetch_optional(&self.pool)
+            .await?;
+        row.as_ref().map(row_to_style_preset).transpose()
+    }
+
+    pub async fn upsert_style_preset(&self, preset: &StylePreset) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO style_presets
+                (id, project_id, name, target_kinds, style, created_at, modified_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(id) DO UPDATE SET
+                name = excluded.name,
+                target_kinds = excluded.target_kinds,
+                style = excluded.style,
+                modified_at = excluded.modified_at",
+        )
+        .bind(preset.id.to_string())
+        .bind(preset.project_id.to_string())
+        .bind(&preset.name)
+        .bind(serde_json::to_string(&preset.target_kinds)?)
+        .bind(serde_json::to_string(&preset.style)?)
+        .bind(preset.created_at.to_rfc3339())
+        .bind(preset.modified_at.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// False when there was no such preset.
+    pub async fn delete_style_preset(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM style_presets WHERE id = ?")
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    // -- Documents ----------------------------------------------------------
+
+    pub async fn list_documents(&self, project_id: Uuid) -> Result<Vec<Document>> {
+        let rows =
+            sqlx::query("SELECT * FROM documents WHERE project_id = ? ORDER BY added_at DESC")
+                .bind(project_id.to_string())
+                .fetch_all(&self.pool)
+                .await?;
+        rows.iter().map(row_to_document).collect()
+    }
+
+    pub async fn get_document(&self, id: Uuid) -> Result<Option<Document>> {
+        let row = sqlx::query("SELECT * FROM documents WHERE id = ?")
+            .bind(id.to_string())
+            .fetch_optional(&self.pool)
+            .await?;
+        row.as_ref().map(row_to_document).transpose()
+    }
+
+    pub async fn upsert_document(&self, doc: &Document, actor: &str) -> Result<()> {
+        let existed = sqlx::query("SELECT 1 FROM documents WHERE id = ?")
+            .bind(doc.id.to_string())
+            .fetch_optional(&self.pool)
+            .await?
+            .is_some();
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            "INSERT INTO documents (id, project_id, name, doc_type, size, added_at, text, source_base64, source_mime)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(id) DO UPDATE SET
+                name = excluded.name,
+                doc_type = excluded.doc_type,
+                size = excluded.size,
+                added_at = excluded.added_at,
+                text = excluded.text,
+                source_base64 = excluded.source_base64,
+                source_mime = excluded.source_mime",
+        )
+        .bind(doc.id.to_string())
+        .bind(doc.project_id.to_string())
+        .bind(&doc.name)
+        .bind(&doc.doc_type)
+        .bind(doc.size)
+        .bind(doc.added_at.to_rfc3339())
+        .bind(&doc.text)
+        .bind(&doc.source_base64)
+        .bind(&doc.source_mime)
+        .execute(&mut *tx)
+        .await?;
+        let (action, verb) = if existed { ("update", "updated") } else { ("create", "added") };
+        self.record_audit(
+            &mut tx,
+            doc.project_id,
+            "document",
+            doc.id,
+            action,
+            actor,
+            &format!("{verb} document '{}'", doc.name),
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn delete_document(&self, id: Uuid, actor: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        let existing = sqlx::query("SELECT project_id, name FROM documents WHERE id = ?")
+            .bind(id.to_string())
+            .fetch_optional(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM documents WHERE id = ?")
+            .bind(id.to_string())
+            .execute(&mut *tx)
+            .await?;
+        if let Some(row) = existing {
+            let project_id: Uuid = row.try_get::<String, _>("project_id")?.parse()?;
+            let name: String = row.try_get("name")?;
+            let summary = format!("deleted document '{name}'");
+            self.record_audit(&mut tx, project_id, "document", id, "delete", actor, &summary)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // -- Document sections -------------------------------------------------
+
+    pub async fn upsert_document_section(&self, s: &DocumentSection) -> Result<()> {
+        let mut conn = self.pool.acquire().await?;
+        write_document_section(&mut conn, s).await
+    }
+
+    /// Swap a document's sections for `sections` in one transaction.
+    pub async fn replace_document_sections(
+        &self,
+        document_id: Uuid,
+        sections: &[DocumentSection],
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM document_sections WHERE document_id = ?")
+            .bind(document_id.to_string())
+            .execute(&mut *tx)
+            .await?;
+        for section in sections {
+            write_document_section(&mut tx, section).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Upsert `sections` in one transaction, several rows per statement.
+    /// Returns the number of rows written.
+    #[tracing::instrument(target = "query", skip_all, fields(rows), err)]
+    pub async fn bulk_upsert_document_sections(&self, sections: &[DocumentSection]) -> Result<u64> {
+        // 14 columns a row keeps a batch under SQLite's 999-parameter limit.
+        const BATCH_ROWS: usize = 64;
+        let mut tx = self.pool.begin().await?;
+        let mut written = 0;
+        for batch in sections.chunks(BATCH_ROWS) {
+            let values = vec!["(?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"; batch.len()].join(", ");
+            let sql = format!(
+                "INSERT INTO document_sections
+                 (id, document_id, project_id, section_ref, section_type, title, body,
+                  part_number, quantity, unit, position, created_at, parent_section_id, section_level)
+                 VALUES {values}
+                 ON CONFLICT(id) DO UPDATE SET
+                   section_ref       = excluded.section_ref,
+                   section_type      = excluded.section_type,
+                   title             = excluded.title,
+                   body              = excluded.body,
+                   part_number       = excluded.part_number,
+                   quantity          = excluded.quantity,
+                   unit              = excluded.unit,
+                   position          = excluded.position,
+                   parent_section_id = excluded.parent_section_id,
+                   section_level     = excluded.section_level"
+            );
+            let mut query = sqlx::query(&sql);
+            for s in batch {
+                query = query
+                    .bind(s.id.to_string())
+                    .bind(s.document_id.to_string())
+                    .bind(s.project_id.to_string())
+                    .bind(&s.section_ref)
+                    .bind(s.section_type.to_string())
+                    .bind(&s.title)
+                    .bind(&s.body)
+                    .bind(&s.part_number)
+                    .bind(&s.quantity)
+                    .bind(&s.unit)
+                    .bind(s.position)
+                    .bind(s.created_at.to_rfc3339())
+                    .bind(s.parent_section_id.map(|id| id.to_string()))
+                    .bind(s.section_level);
+            }
+            written += query.execute(&mut *tx).await?.rows_affected();
+        }
+        tx.commit().await?;
+        tracing::Span::current().record("rows", written);
+        Ok(written)
+    }
+
+    /// Set each section's position to its index in `ordered_ids`, in one
+    /// transaction. Ids not in `document_id` are left alone.
+    pub async fn reorder_document_sections(
+        &self,
+        document_id: Uuid,
+        ordered_ids: &[Uuid],
+    ) -> Result<u64> {
+        let document_id = document_id.to_string();
+        let mut tx = self.pool.begin().await?;
+        let mut updated = 0;
+        for (position, id) in ordered_ids.iter().enumerate() {
+            updated += sqlx::query(
+                "UPDATE document_sections SET position = ? WHERE id = ? AND document_id = ?",
+            )
+            .bind(position as i64)
+            .bind(id.to_string())
+            .bind(&document_id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+        tx.commit().await?;
+        Ok(updated)
+    }
+
+    /// The document each of `ids` belongs to, for those that exist.
+    pub async fn section_documents(
+        &self,
+        ids: &[Uuid],
+    ) -> Result<std::collections::HashMap<Uuid, Uuid>> {
+        let mut documents = std::collections::HashMap::new();
+        for batch in ids.chunks(500) {
+            let placeholders = vec!["?"; batch.len()].join(", ");
+            let sql = format!(
+                "SELECT id, document_id FROM document_sections WHERE id IN ({placeholders})"
+            );
+            let mut query = sqlx::query(&sql);
+            for id in batch {
+                query = query.bind(id.to_string());
+            }
+            for row in query.fetch_all(&self.pool).await? {
+                let id: String = row.try_get("id")?;
+                let document_id: String = row.try_get("document_id")?;
+                documents.insert(Uuid::parse_str(&id)?, Uuid::parse_str(&document_id)?);
+            }
+        }
+        Ok(documents)
+    }
+
+    pub async fn list_document_sections(&self, document_id: Uuid) -> Result<Vec<DocumentSection>> {
+        let rows =
+            sqlx::query("SELECT * FROM document_sections WHERE document_id = ? ORDER BY position, id")
+                .bind(document_id.to_string())
+                .fetch_all(&self.pool)
+                .await?;
+        rows.iter().map(row_to_document_section).collect()
+    }
+
+    pub async fn get_document_section(&self, id: Uuid) -> Result<Option<DocumentSection>> {
+        let row = sqlx::query("SELECT * FROM document_sections WHERE id = ?")
+            .bind(id.to_string())
+            .fetch_optional(&self.pool)
+            .await?;
+        row.as_ref().map(row_to_document_section).transpose()
+    }
+
+    pub async fn list_project_document_sections(
+        &self,
+        project_id: Uuid,
+    ) -> Result<Vec<DocumentSection>> {
+        let rows = sqlx::query(
+            "SELECT * FROM document_sections WHERE project_id = ? ORDER BY document_id, position, id",
+        )
+        .bind(project_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter().map(row_to_document_section).collect()
+    }
+
+    pub async fn delete_document_sections(&self, document_id: Uuid) -> Result<()> {
+        sqlx::query("DELETE FROM document_sections WHERE document_id = ?")
+            .bind(document_id.to_string())
+            .execute(&self.pool)
             .await?;
         Ok(())
     }
@@ -601,6 +1850,64 @@ impl Store {
         Ok(())
     }
 
+    pub async fn get_subsystem_knowledge(
+        &self,
+        id: Uuid,
+    ) -> Result<Option<SubsystemKnowledgePage>> {
+        let row = sqlx::query("SELECT * FROM subsystem_knowledge WHERE id = ?")
+            .bind(id.to_string())
+            .fetch_optional(&self.pool)
+            .await?;
+        row.as_ref().map(row_to_subsystem_knowledge).transpose()
+    }
+
+    /// Replaces a page's recorded links and clears its `stale_links` flag.
+    pub async fn replace_knowledge_links(
+        &self,
+        page_id: Uuid,
+        links: &[KnowledgeLink],
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM knowledge_links WHERE page_id = ?")
+            .bind(page_id.to_string())
+            .execute(&mut *tx)
+            .await?;
+        for link in links {
+            sqlx::query(
+                "INSERT OR IGNORE INTO knowledge_links
+                 (page_id, node_id, link_text, node_name, node_req_id)
+                 VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(page_id.to_string())
+            .bind(link.node_id.to_string())
+            .bind(&link.link_text)
+            .bind(&link.node_name)
+            .bind(&link.node_req_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+        sqlx::query("UPDATE subsystem_knowledge SET stale_links = 0 WHERE id = ?")
+            .bind(page_id.to_string())
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Knowledge pages whose recorded links point at `node_id`, most
+    /// recently updated first.
+    pub async fn pages_mentioning(&self, node_id: Uuid) -> Result<Vec<SubsystemKnowledgePage>> {
+        let rows = sqlx::query(
+            "SELECT * FROM subsystem_knowledge
+             WHERE id IN (SELECT page_id FROM knowledge_links WHERE node_id = ?)
+             ORDER BY updated_at DESC",
+        )
+        .bind(node_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter().map(row_to_subsystem_knowledge).collect()
+    }
+
     pub async fn delete_subsystem_knowledge(&self, id: Uuid) -> Result<()> {
         sqlx::query("DELETE FROM subsystem_knowledge WHERE id = ?")
             .bind(id.to_string())
@@ -609,70 +1916,198 @@ impl Store {
         Ok(())
     }
 
-    // -- Subsystem artifacts ------------------------------------------------
+    // -- Block value properties ---------------------------------------------
 
-    pub async fn list_subsystem_artifacts(
-        &self,
-        subsystem_id: Uuid,
-    ) -> Result<Vec<SubsystemArtifact>> {
+    pub async fn list_block_properties(&self, block_id: Uuid) -> Result<Vec<BlockProperty>> {
         let rows = sqlx::query(
-            "SELECT * FROM subsystem_artifacts WHERE subsystem_id = ? ORDER BY created_at DESC",
+            "SELECT * FROM block_properties WHERE block_node_id = ? ORDER BY created_at, name, id",
         )
-        .bind(subsystem_id.to_string())
+        .bind(block_id.to_string())
         .fetch_all(&self.pool)
         .await?;
-        rows.iter().map(row_to_subsystem_artifact).collect()
+        rows.iter().map(row_to_block_property).collect()
     }
 
-    pub async fn list_project_artifacts(&self, project_id: Uuid) -> Result<Vec<SubsystemArtifact>> {
+    pub async fn list_project_block_properties(&self, project_id: Uuid) -> Result<Vec<BlockProperty>> {
         let rows = sqlx::query(
-            "SELECT a.* FROM subsystem_artifacts a
-             JOIN nodes n ON n.id = a.subsystem_id
+            "SELECT bp.* FROM block_properties bp
+             JOIN nodes n ON n.id = bp.block_node_id
              WHERE n.project_id = ?
-             ORDER BY a.created_at DESC",
+             ORDER BY bp.created_at, bp.name, bp.id",
         )
         .bind(project_id.to_string())
         .fetch_all(&self.pool)
         .await?;
-        rows.iter().map(row_to_subsystem_artifact).collect()
+        rows.iter().map(row_to_block_property).collect()
     }
 
-    pub async fn upsert_subsystem_artifact(&self, artifact: &SubsystemArtifact) -> Result<()> {
+    pub async fn upsert_block_property(&self, property: &BlockProperty) -> Result<()> {
+        match self.get_node(property.block_node_id).await? {
+            Some(n) if n.kind == NodeKind::Block => {}
+            Some(n) => anyhow::bail!("'{}' is a {}, not a block", n.name, n.kind),
+            None => anyhow::bail!("block {} not found", property.block_node_id),
+        }
+        if let Some(vt_id) = property.value_type_node_id {
+            match self.get_node(vt_id).await? {
+                Some(n) if n.kind == NodeKind::ValueType => {}
+                Some(n) => anyhow::bail!("'{}' is a {}, not a value type", n.name, n.kind),
+                None => anyhow::bail!("value type {vt_id} not found"),
+            }
+        }
+
         sqlx::query(
-            "INSERT INTO subsystem_artifacts (id, subsystem_id, kind, title, link, notes, created_at)
-             VALUES (?, ?, ?, ?, ?, ?, ?)
+            "INSERT INTO block_properties
+                (id, block_node_id, name, value_type_node_id, type_name, value, unit, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)
              ON CONFLICT(id) DO UPDATE SET
-                kind = excluded.kind,
-                title = excluded.title,
-                link = excluded.link,
-                notes = excluded.notes",
+                name = excluded.name,
+                value_type_node_id = excluded.value_type_node_id,
+                type_name = excluded.type_name,
+                value = excluded.value,
+                unit = excluded.unit",
         )
-        .bind(artifact.id.to_string())
-        .bind(artifact.subsystem_id.to_string())
-        .bind(&artifact.kind)
-        .bind(&artifact.title)
-        .bind(&artifact.link)
-        .bind(&artifact.notes)
-        .bind(artifact.created_at.to_rfc3339())
+        .bind(property.id.to_string())
+        .bind(property.block_node_id.to_string())
+        .bind(&property.name)
+        .bind(property.value_type_node_id.map(|id| id.to_string()))
+        .bind(&property.type_name)
+        .bind(&property.value)
+        .bind(&property.unit)
+        .bind(property.created_at.to_rfc3339())
         .execute(&self.pool)
         .await?;
         Ok(())
     }
 
-    pub async fn delete_subsystem_artifact(&self, id: Uuid) -> Result<()> {
-        sqlx::query("DELETE FROM subsystem_artifacts WHERE id = ?")
+    pub async fn delete_block_property(&self, id: Uuid) -> Result<()> {
+        sqlx::query("DELETE FROM block_properties WHERE id = ?")
             .bind(id.to_string())
             .execute(&self.pool)
             .await?;
         Ok(())
     }
 
-    // -- Subsystem activity -------------------------------------------------
+    /// Number of block properties typed by the given ValueType node.
+    pub async fn count_value_type_references(&self, value_type_id: Uuid) -> Result<i64> {
+        let count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM block_properties WHERE value_type_node_id = ?",
+        )
+        .bind(value_type_id.to_string())
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(count)
+    }
 
-    pub async fn list_subsystem_activity(
-        &self,
-        subsystem_id: Uuid,
-    ) -> Result<Vec<SubsystemActivity>> {
+    // -- Glossary --------------------------------------------------------------
+
+    pub async fn list_glossary_terms(&self, project_id: Uuid) -> Result<Vec<GlossaryTerm>> {
+        let rows = sqlx::query(
+            "SELECT * FROM glossary_terms WHERE project_id = ? ORDER BY term COLLATE NOCASE, id",
+        )
+        .bind(project_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter().map(row_to_glossary_term).collect()
+    }
+
+    pub async fn upsert_glossary_term(&self, term: &GlossaryTerm) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO glossary_terms
+                (id, project_id, term, approved, variants, case_sensitive, created_at, modified_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(id) DO UPDATE SET
+                term = excluded.term,
+                approved = excluded.approved,
+                variants = excluded.variants,
+                case_sensitive = excluded.case_sensitive,
+                modified_at = excluded.modified_at",
+        )
+        .bind(term.id.to_string())
+        .bind(term.project_id.to_string())
+        .bind(&term.term)
+        .bind(&term.approved)
+        .bind(serde_json::to_string(&term.variants)?)
+        .bind(term.case_sensitive)
+        .bind(term.created_at.to_rfc3339())
+        .bind(term.modified_at.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// False when there was no such term.
+    pub async fn delete_glossary_term(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM glossary_terms WHERE id = ?")
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    // -- Subsystem artifacts ------------------------------------------------
+
+    pub async fn list_subsystem_artifacts(
+        &self,
+        subsystem_id: Uuid,
+    ) -> Result<Vec<SubsystemArtifact>> {
+        let rows = sqlx::query(
+            "SELECT * FROM subsystem_artifacts WHERE subsystem_id = ? ORDER BY created_at DESC",
+        )
+        .bind(subsystem_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter().map(row_to_subsystem_artifact).collect()
+    }
+
+    pub async fn list_project_artifacts(&self, project_id: Uuid) -> Result<Vec<SubsystemArtifact>> {
+        let rows = sqlx::query(
+            "SELECT a.* FROM subsystem_artifacts a
+             JOIN nodes n ON n.id = a.subsystem_id
+             WHERE n.project_id = ?
+             ORDER BY a.created_at DESC",
+        )
+        .bind(project_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter().map(row_to_subsystem_artifact).collect()
+    }
+
+    pub async fn upsert_subsystem_artifact(&self, artifact: &SubsystemArtifact) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO subsystem_artifacts (id, subsystem_id, kind, title, link, notes, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(id) DO UPDATE SET
+                kind = excluded.kind,
+                title = excluded.title,
+                link = excluded.link,
+                notes = excluded.notes",
+        )
+        .bind(artifact.id.to_string())
+        .bind(artifact.subsystem_id.to_string())
+        .bind(&artifact.kind)
+        .bind(&artifact.title)
+        .bind(&artifact.link)
+        .bind(&artifact.notes)
+        .bind(artifact.created_at.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn delete_subsystem_artifact(&self, id: Uuid) -> Result<()> {
+        sqlx::query("DELETE FROM subsystem_artifacts WHERE id = ?")
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    // -- Subsystem activity -------------------------------------------------
+
+    pub async fn list_subsystem_activity(
+        &self,
+        subsystem_id: Uuid,
+    ) -> Result<Vec<SubsystemActivity>> {
         let rows = sqlx::query(
             "SELECT * FROM subsystem_activity WHERE subsystem_id = ? ORDER BY created_at DESC",
         )
@@ -696,6 +2131,226 @@ impl Store {
         Ok(())
     }
 
+    // -- Block merge --------------------------------------------------------
+
+    /// Fold `removed` into `keep` in one transaction: edges, diagram
+    /// elements, knowledge pages and their links, artifacts, activity,
+    /// block properties and external links move to `keep`, requirement
+    /// allocation tags naming a removed block are rewritten to `keep`'s
+    /// name, and the removed blocks are deleted. The caller checks that all
+    /// of them are blocks in one project.
+    pub async fn merge_blocks(
+        &self,
+        keep: &Node,
+        removed: &[Node],
+        actor: &str,
+    ) -> Result<BlockMergeReport> {
+        let keep_id = keep.id.to_string();
+        let removed_ids: std::collections::HashSet<String> =
+            removed.iter().map(|n| n.id.to_string()).collect();
+        let removed_names: std::collections::HashSet<String> =
+            removed.iter().map(|n| n.name.trim().to_lowercase()).collect();
+        let mut report = BlockMergeReport {
+            kept_id: keep.id,
+            kept_name: keep.name.clone(),
+            removed: removed.iter().map(|n| (n.id, n.name.clone())).collect(),
+            ..Default::default()
+        };
+
+        let requirements = self.list_nodes_by_kind(keep.project_id, &NodeKind::Requirement).await?;
+        let history_actor = self.stamp_actor(actor);
+        let now = Utc::now();
+        let mut tx = self.pool.begin().await?;
+
+        // Edges: an edge that would duplicate one the kept block already
+        // has, or loop from the kept block to itself, is dropped.
+        let mut existing: std::collections::HashSet<(String, String, String)> = sqlx::query(
+            "SELECT kind, source_id, target_id FROM edges WHERE source_id = ? OR target_id = ?",
+        )
+        .bind(&keep_id)
+        .bind(&keep_id)
+        .fetch_all(&mut *tx)
+        .await?
+        .iter()
+        .map(|row| -> Result<(String, String, String)> {
+            Ok((row.try_get("kind")?, row.try_get("source_id")?, row.try_get("target_id")?))
+        })
+        .collect::<Result<_>>()?;
+        for id in &removed_ids {
+            let rows = sqlx::query(
+                "SELECT id, kind, source_id, target_id FROM edges
+                 WHERE source_id = ? OR target_id = ?",
+            )
+            .bind(id)
+            .bind(id)
+            .fetch_all(&mut *tx)
+            .await?;
+            for row in rows {
+                let edge_id: String = row.try_get("id")?;
+                let kind: String = row.try_get("kind")?;
+                let remap = |v: String| if removed_ids.contains(&v) { keep_id.clone() } else { v };
+                let source = remap(row.try_get("source_id")?);
+                let target = remap(row.try_get("target_id")?);
+                let key = (kind, source.clone(), target.clone());
+                if source == target || existing.contains(&key) {
+                    sqlx::query("DELETE FROM suspect_links WHERE edge_id = ?")
+                        .bind(&edge_id)
+                        .execute(&mut *tx)
+                        .await?;
+                    sqlx::query("DELETE FROM edges WHERE id = ?")
+                        .bind(&edge_id)
+                        .execute(&mut *tx)
+                        .await?;
+                    report.edges_dropped += 1;
+                    continue;
+                }
+                sqlx::query(
+                    "UPDATE edges SET source_id = ?, target_id = ?, modified_at = ? WHERE id = ?",
+                )
+                .bind(&source)
+                .bind(&target)
+                .bind(now.to_rfc3339())
+                .bind(&edge_id)
+                .execute(&mut *tx)
+                .await?;
+                existing.insert(key);
+                report.edges_moved += 1;
+            }
+        }
+
+        for id in &removed_ids {
+            for column in ["source_node_id", "target_node_id"] {
+                sqlx::query(&format!("UPDATE suspect_links SET {column} = ? WHERE {column} = ?"))
+                    .bind(&keep_id)
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+
+            // One element per node per diagram: where the kept block is
+            // already shown, the removed block's element goes.
+            report.diagram_elements_dropped += sqlx::query(
+                "DELETE FROM diagram_elements WHERE node_id = ? AND diagram_id IN (
+                     SELECT diagram_id FROM diagram_elements WHERE node_id = ?)",
+            )
+            .bind(id)
+            .bind(&keep_id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+            report.diagram_elements_moved +=
+                sqlx::query("UPDATE diagram_elements SET node_id = ? WHERE node_id = ?")
+                    .bind(&keep_id)
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?
+                    .rows_affected();
+
+            for (table, column) in [
+                ("subsystem_knowledge", "subsystem_id"),
+                ("subsystem_artifacts", "subsystem_id"),
+                ("subsystem_activity", "subsystem_id"),
+                ("knowledge_links", "node_id"),
+                ("block_properties", "block_node_id"),
+                ("external_item_links", "node_id"),
+            ] {
+                // OR IGNORE: a link the kept block already has stays put and
+                // goes with the removed block.
+                let n = sqlx::query(&format!(
+                    "UPDATE OR IGNORE {table} SET {column} = ? WHERE {column} = ?"
+                ))
+                .bind(&keep_id)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+                let counter = match table {
+                    "subsystem_knowledge" => &mut report.knowledge_pages_moved,
+                    "subsystem_artifacts" => &mut report.artifacts_moved,
+                    "subsystem_activity" => &mut report.activity_moved,
+                    "knowledge_links" => &mut report.knowledge_links_moved,
+                    "block_properties" => &mut report.block_properties_moved,
+                    _ => &mut report.external_links_moved,
+                };
+                *counter += n;
+            }
+        }
+
+        // Pages linking to a removed block by its old name now point at
+        // the kept block; flag them as a rename would.
+        sqlx::query(
+            "UPDATE subsystem_knowledge SET stale_links = 1
+             WHERE stale_links = 0 AND id IN (
+                 SELECT page_id FROM knowledge_links WHERE node_id = ? AND node_name <> ?)",
+        )
+        .bind(&keep_id)
+        .bind(&keep.name)
+        .execute(&mut *tx)
+        .await?;
+
+        for req in &requirements {
+            let NodeData::Requirement(r) = &req.data else { continue };
+            let Some(allocations) = &r.allocations else { continue };
+            if !allocations.iter().any(|a| removed_names.contains(&a.trim().to_lowercase())) {
+                continue;
+            }
+            let mut updated = req.clone();
+            if let NodeData::Requirement(r) = &mut updated.data {
+                r.allocations = Some(
+                    allocations
+                        .iter()
+                        .map(|a| {
+                            if removed_names.contains(&a.trim().to_lowercase()) {
+                                keep.name.clone()
+                            } else {
+                                a.clone()
+                            }
+                        })
+                        .collect(),
+                );
+            }
+            updated.meta.insert("change_source".to_string(), serde_json::json!("merge"));
+            updated.meta.insert("actor".to_string(), serde_json::json!(actor));
+            updated.modified_at = now;
+            write_node(&mut tx, &updated, requirement_snapshot_from_node(req), &history_actor)
+                .await?;
+            report.requirements_reallocated.push(req.id);
+        }
+
+        let names: Vec<String> = removed.iter().map(|n| format!("'{}'", n.name)).collect();
+        sqlx::query(
+            "INSERT INTO subsystem_activity (id, subsystem_id, text, created_at) VALUES (?, ?, ?, ?)",
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&keep_id)
+        .bind(format!("Merged {} into this block", names.join(", ")))
+        .bind(now.to_rfc3339())
+        .execute(&mut *tx)
+        .await?;
+
+        for id in &removed_ids {
+            sqlx::query("DELETE FROM nodes WHERE id = ?")
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        for node in removed {
+            self.record_audit(
+                &mut tx,
+                node.project_id,
+                "node",
+                node.id,
+                "delete",
+                actor,
+                &format!("merged {} '{}' into '{}'", node.kind, node.name, keep.name),
+            )
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(report)
+    }
+
     // -- Settings -----------------------------------------------------------
 
     pub async fn get_setting(&self, key: &str, project_id: Option<Uuid>) -> Result<Option<String>> {
@@ -717,7 +2372,91 @@ impl Store {
         project_id: Option<Uuid>,
         value: &str,
     ) -> Result<()> {
-        sqlx::query(
+        sqlx::query(UPSERT_SETTING_SQL)
+            .bind(key)
+            .bind(project_id.map(|id| id.to_string()))
+            .bind(value)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Several settings of one scope in a single query, so a concurrent
+    /// `set_settings` is seen either entirely or not at all. Keys that
+    /// aren't set are absent from the map.
+    pub async fn get_settings(
+        &self,
+        keys: &[&str],
+        project_id: Option<Uuid>,
+    ) -> Result<std::collections::HashMap<String, String>> {
+        let rows = sqlx::query(
+            "SELECT key, value FROM settings
+             WHERE key IN (SELECT value FROM json_each(?))
+               AND COALESCE(project_id, '') = COALESCE(?, '')",
+        )
+        .bind(serde_json::to_string(keys)?)
+        .bind(project_id.map(|id| id.to_string()))
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|r| Ok((r.try_get("key")?, r.try_get("value")?)))
+            .collect()
+    }
+
+    /// Write several settings in one transaction; either all land or none.
+    pub async fn set_settings(&self, entries: &[SettingEntry]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for entry in entries {
+            sqlx::query(UPSERT_SETTING_SQL)
+                .bind(&entry.key)
+                .bind(entry.project_id.map(|id| id.to_string()))
+                .bind(&entry.value)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Settings in one scope (global when `project_id` is `None`), optionally
+    /// restricted to keys starting with `prefix`, ordered by key.
+    pub async fn list_settings(
+        &self,
+        project_id: Option<Uuid>,
+        prefix: Option<&str>,
+    ) -> Result<Vec<SettingEntry>> {
+        let prefix = prefix.unwrap_or("");
+        let rows = sqlx::query(
+            "SELECT key, project_id, value FROM settings
+             WHERE COALESCE(project_id, '') = COALESCE(?, '')
+               AND substr(key, 1, length(?)) = ?
+             ORDER BY key",
+        )
+        .bind(project_id.map(|id| id.to_string()))
+        .bind(prefix)
+        .bind(prefix)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|r| {
+                Ok(SettingEntry {
+                    key: r.try_get("key")?,
+                    project_id: r
+                        .try_get::<Option<String>, _>("project_id")?
+                        .map(|id| id.parse())
+                        .transpose()?,
+                    value: r.try_get("value")?,
+                    masked: false,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns whether a setting was removed.
+    pub async fn delete_setting(&self, key: &str, project_id: Option<Uuid>) -> Result<bool> {
+        let result = sqlx::query(
             "DELETE FROM settings
              WHERE key = ? AND COALESCE(project_id, '') = COALESCE(?, '')",
         )
@@ -725,14 +2464,46 @@ impl Store {
         .bind(project_id.map(|id| id.to_string()))
         .execute(&self.pool)
         .await?;
+        Ok(result.rows_affected() > 0)
+    }
 
-        sqlx::query("INSERT INTO settings (key, project_id, value) VALUES (?, ?, ?)")
-            .bind(key)
-            .bind(project_id.map(|id| id.to_string()))
-            .bind(value)
-            .execute(&self.pool)
+    // -- Data health ---------------------------------------------------------
+
+    /// Node, edge and diagram rows whose kind this build doesn't know, with
+    /// every column. Listings skip these rows; see [`map_known_kinds`].
+    pub async fn list_unrecognized_rows(&self, project_id: Uuid) -> Result<Vec<UnrecognizedRow>> {
+        let tables: [(&str, fn(&str) -> bool); 3] = [
+            ("nodes", is_node_kind),
+            ("edges", is_edge_kind),
+            ("diagrams", is_diagram_kind),
+        ];
+        let mut out = Vec::new();
+        for (table, known) in tables {
+            let rows = sqlx::query(&format!(
+                "SELECT id, kind FROM {table} WHERE project_id = ? ORDER BY created_at, id"
+            ))
+            .bind(project_id.to_string())
+            .fetch_all(&self.pool)
             .await?;
-        Ok(())
+            for row in rows {
+                let kind: String = row.try_get("kind")?;
+                if known(&kind) {
+                    continue;
+                }
+                let id: String = row.try_get("id")?;
+                let full = sqlx::query(&format!("SELECT * FROM {table} WHERE id = ?"))
+                    .bind(&id)
+                    .fetch_one(&self.pool)
+                    .await?;
+                out.push(UnrecognizedRow {
+                    table: table.to_string(),
+                    id,
+                    kind,
+                    row: row_to_raw_json(&full),
+                });
+            }
+        }
+        Ok(out)
     }
 
     // -- Suspect links -------------------------------------------------------
@@ -782,117 +2553,431 @@ impl Store {
         .fetch_all(&self.pool)
         .await?;
 
-        rows.iter().map(|row| {
-            Ok(SuspectLink {
-                id: Uuid::parse_str(row.get("id"))?,
-                project_id: Uuid::parse_str(row.get("project_id"))?,
-                edge_id: Uuid::parse_str(row.get("edge_id"))?,
-                source_node_id: Uuid::parse_str(row.get("source_node_id"))?,
-                target_node_id: Uuid::parse_str(row.get("target_node_id"))?,
-                flagged_at: chrono::DateTime::parse_from_rfc3339(row.get("flagged_at"))?.with_timezone(&chrono::Utc),
-                flagged_reason: row.get("flagged_reason"),
-                resolved_at: row.get::<Option<String>, _>("resolved_at")
-                    .map(|s| chrono::DateTime::parse_from_rfc3339(&s).map(|d| d.with_timezone(&chrono::Utc)))
-                    .transpose()?,
-                resolved_by: row.get("resolved_by"),
-            })
-        }).collect()
-    }
-
-    pub async fn resolve_suspect_link(&self, id: Uuid, resolved_by: &str) -> Result<()> {
-        sqlx::query("UPDATE suspect_links SET resolved_at = ?, resolved_by = ? WHERE id = ?")
-            .bind(chrono::Utc::now().to_rfc3339())
-            .bind(resolved_by)
-            .bind(id.to_string())
-            .execute(&self.pool)
-            .await?;
-        Ok(())
+        rows.iter().map(row_to_suspect_link).collect()
     }
 
-    // -- Inline comments ---------------------------------------------------
-
-    pub async fn add_req_comment(&self, project_id: Uuid, node_id: Uuid, parent_id: Option<Uuid>, author: &str, body: &str) -> Result<ReqComment> {
-        let id = Uuid::new_v4();
-        let now = chrono::Utc::now().to_rfc3339();
-        sqlx::query(
-            "INSERT INTO req_comments (id, project_id, node_id, parent_id, author, body, created_at, updated_at)
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+    pub async fn get_suspect_link(&self, id: Uuid) -> Result<Option<SuspectLink>> {
+        let row = sqlx::query(
+            "SELECT id, project_id, edge_id, source_node_id, target_node_id, flagged_at,
+                    flagged_reason, resolved_at, resolved_by
+             FROM suspect_links WHERE id = ?",
         )
         .bind(id.to_string())
-        .bind(project_id.to_string())
-        .bind(node_id.to_string())
-        .bind(parent_id.map(|u| u.to_string()))
-        .bind(author)
-        .bind(body)
-        .bind(&now)
-        .bind(&now)
-        .execute(&self.pool)
+        .fetch_optional(&self.pool)
         .await?;
 
-        Ok(ReqComment {
-            id,
-            project_id,
-            node_id,
-            parent_id,
-            author: author.to_string(),
-            body: body.to_string(),
-            created_at: chrono::Utc::now(),
-            updated_at: chrono::Utc::now(),
-            resolved_at: None,
-            resolved_by: None,
-        })
+        row.as_ref().map(row_to_suspect_link).transpose()
     }
 
-    pub async fn get_req_comments(&self, node_id: Uuid) -> Result<Vec<ReqComment>> {
+    /// Every suspect link ever raised on an edge, resolved or not.
+    pub async fn list_edge_suspect_links(&self, edge_id: Uuid) -> Result<Vec<SuspectLink>> {
         let rows = sqlx::query(
-            "SELECT id, project_id, node_id, parent_id, author, body, created_at, updated_at, resolved_at, resolved_by
-             FROM req_comments WHERE node_id = ? ORDER BY created_at ASC"
+            "SELECT id, project_id, edge_id, source_node_id, target_node_id, flagged_at,
+                    flagged_reason, resolved_at, resolved_by
+             FROM suspect_links WHERE edge_id = ? ORDER BY flagged_at",
         )
-        .bind(node_id.to_string())
+        .bind(edge_id.to_string())
         .fetch_all(&self.pool)
         .await?;
 
-        rows.iter().map(|row| {
-            Ok(ReqComment {
-                id: Uuid::parse_str(row.get("id"))?,
-                project_id: Uuid::parse_str(row.get("project_id"))?,
-                node_id: Uuid::parse_str(row.get("node_id"))?,
-                parent_id: row.get::<Option<String>, _>("parent_id")
-                    .map(|s| Uuid::parse_str(&s))
-                    .transpose()?,
-                author: row.get("author"),
-                body: row.get("body"),
-                created_at: chrono::DateTime::parse_from_rfc3339(row.get("created_at"))?.with_timezone(&chrono::Utc),
-                updated_at: chrono::DateTime::parse_from_rfc3339(row.get("updated_at"))?.with_timezone(&chrono::Utc),
-                resolved_at: row.get::<Option<String>, _>("resolved_at")
-                    .map(|s| chrono::DateTime::parse_from_rfc3339(&s).map(|d| d.with_timezone(&chrono::Utc)))
-                    .transpose()?,
-                resolved_by: row.get("resolved_by"),
-            })
-        }).collect()
+        rows.iter().map(row_to_suspect_link).collect()
     }
 
-    pub async fn get_comment_counts_for_project(&self, project_id: Uuid) -> Result<std::collections::HashMap<String, i64>> {
+    /// Age buckets, the `oldest_limit` longest-unresolved links and the
+    /// distribution over the target requirements' allocations, all for
+    /// unresolved links only.
+    pub async fn suspect_link_summary(
+        &self,
+        project_id: Uuid,
+        oldest_limit: i64,
+    ) -> Result<SuspectLinkSummary> {
+        let pid = project_id.to_string();
+
+        let row = sqlx::query(
+            "SELECT COUNT(*) AS total,
+                    COALESCE(SUM(age < 7), 0) AS under_7,
+                    COALESCE(SUM(age >= 7 AND age <= 30), 0) AS from_7_to_30,
+                    COALESCE(SUM(age > 30), 0) AS over_30
+             FROM (SELECT julianday('now') - julianday(flagged_at) AS age
+                   FROM suspect_links WHERE project_id = ? AND resolved_at IS NULL)",
+        )
+        .bind(&pid)
+        .fetch_one(&self.pool)
+        .await?;
+        let unresolved: i64 = row.try_get("total")?;
+        let by_age = SuspectAgeBuckets {
+            under_7_days: row.try_get("under_7")?,
+            from_7_to_30_days: row.try_get("from_7_to_30")?,
+            over_30_days: row.try_get("over_30")?,
+        };
+
         let rows = sqlx::query(
-            "SELECT node_id, COUNT(*) as cnt FROM req_comments WHERE project_id = ? AND resolved_at IS NULL GROUP BY node_id"
+            "SELECT sl.id, sl.project_id, sl.edge_id, sl.source_node_id, sl.target_node_id,
+                    sl.flagged_at, sl.flagged_reason, sl.resolved_at, sl.resolved_by,
+                    COALESCE(s.name, '') AS source_name, COALESCE(t.name, '') AS target_name,
+                    julianday('now') - julianday(sl.flagged_at) AS age_days
+             FROM suspect_links sl
+             LEFT JOIN nodes s ON s.id = sl.source_node_id
+             LEFT JOIN nodes t ON t.id = sl.target_node_id
+             WHERE sl.project_id = ? AND sl.resolved_at IS NULL
+             ORDER BY julianday(sl.flagged_at) ASC
+             LIMIT ?",
         )
-        .bind(project_id.to_string())
+        .bind(&pid)
+        .bind(oldest_limit)
         .fetch_all(&self.pool)
         .await?;
+        let oldest = rows
+            .iter()
+            .map(|row| {
+                Ok(AgedSuspectLink {
+                    link: row_to_suspect_link(row)?,
+                    source_name: row.try_get("source_name")?,
+                    target_name: row.try_get("target_name")?,
+                    age_days: row.try_get::<f64, _>("age_days")?.floor() as i64,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
 
-        let mut map = std::collections::HashMap::new();
-        for row in rows {
-            let node_id: String = row.get("node_id");
-            let cnt: i64 = row.get("cnt");
-            map.insert(node_id, cnt);
-        }
-        Ok(map)
-    }
-
-    pub async fn resolve_req_comment(&self, id: Uuid, resolved_by: &str) -> Result<()> {
+        // A link counts once for each subsystem its target is allocated to;
+        // targets without allocations (or since deleted) are "Unallocated".
+        let rows = sqlx::query(
+            "SELECT COALESCE(a.value, 'Unallocated') AS subsystem,
+                    COUNT(DISTINCT sl.id) AS count
+             FROM suspect_links sl
+             LEFT JOIN nodes t ON t.id = sl.target_node_id
+             LEFT JOIN json_each(CASE WHEN json_valid(t.req_allocations)
+                                      THEN t.req_allocations ELSE '[]' END) a
+             WHERE sl.project_id = ? AND sl.resolved_at IS NULL
+             GROUP BY subsystem
+             ORDER BY count DESC, subsystem",
+        )
+        .bind(&pid)
+        .fetch_all(&self.pool)
+        .await?;
+        let by_subsystem = rows
+            .iter()
+            .map(|row| {
+                Ok(HistoryCount {
+                    key: row.try_get("subsystem")?,
+                    count: row.try_get("count")?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(SuspectLinkSummary {
+            project_id,
+            unresolved,
+            by_age,
+            oldest,
+            by_subsystem,
+        })
+    }
+
+    /// Unresolved links flagged more than `older_than_days` ago.
+    pub async fn count_stale_suspect_links(&self, project_id: Uuid, older_than_days: i64) -> Result<i64> {
+        let row = sqlx::query(
+            "SELECT COUNT(*) AS stale FROM suspect_links
+             WHERE project_id = ? AND resolved_at IS NULL
+               AND julianday('now') - julianday(flagged_at) > ?",
+        )
+        .bind(project_id.to_string())
+        .bind(older_than_days)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(row.try_get("stale")?)
+    }
+
+    pub async fn resolve_suspect_link(&self, id: Uuid, resolved_by: &str) -> Result<()> {
+        sqlx::query("UPDATE suspect_links SET resolved_at = ?, resolved_by = ? WHERE id = ?")
+            .bind(chrono::Utc::now().to_rfc3339())
+            .bind(self.stamp_actor(resolved_by))
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    // -- Attention digest --------------------------------------------------
+
+    /// Unresolved top-level comments created before `cutoff`.
+    pub async fn digest_open_comments(
+        &self,
+        project_id: Uuid,
+        cutoff: chrono::DateTime<Utc>,
+        samples: usize,
+    ) -> Result<DigestBucket> {
+        self.digest_bucket(
+            "FROM req_comments
+             WHERE project_id = ? AND resolved_at IS NULL AND created_at < ? AND parent_id IS NULL",
+            "created_at",
+            "id, node_id, substr(body, 1, 80) AS label",
+            project_id,
+            Some(cutoff),
+            samples,
+        )
+        .await
+    }
+
+    /// Review sessions still open or in progress, created before `cutoff`.
+    pub async fn digest_open_reviews(
+        &self,
+        project_id: Uuid,
+        cutoff: chrono::DateTime<Utc>,
+        samples: usize,
+    ) -> Result<DigestBucket> {
+        self.digest_bucket(
+            "FROM review_sessions
+             WHERE project_id = ? AND status IN ('open', 'in_progress') AND created_at < ?",
+            "created_at",
+            "id, NULL AS node_id, title AS label",
+            project_id,
+            Some(cutoff),
+            samples,
+        )
+        .await
+    }
+
+    /// Every unresolved suspect link.
+    pub async fn digest_suspect_links(
+        &self,
+        project_id: Uuid,
+        samples: usize,
+    ) -> Result<DigestBucket> {
+        self.digest_bucket(
+            "FROM suspect_links WHERE project_id = ? AND resolved_at IS NULL",
+            "flagged_at",
+            "id, target_node_id AS node_id, flagged_reason AS label",
+            project_id,
+            None,
+            samples,
+        )
+        .await
+    }
+
+    /// Active draft requirements last modified before `cutoff`.
+    pub async fn digest_stale_drafts(
+        &self,
+        project_id: Uuid,
+        cutoff: chrono::DateTime<Utc>,
+        samples: usize,
+    ) -> Result<DigestBucket> {
+        self.digest_bucket(
+            "FROM nodes
+             WHERE project_id = ? AND kind = 'requirement' AND req_status = 'draft'
+               AND modified_at < ? AND archived = 0",
+            "modified_at",
+            "id, NULL AS node_id, COALESCE(req_id || ' ', '') || name AS label",
+            project_id,
+            Some(cutoff),
+            samples,
+        )
+        .await
+    }
+
+    /// Count, oldest `date_column` and the oldest `samples` rows of
+    /// `from_where`, whose placeholders are the project id and, when
+    /// given, the cutoff. `columns` must yield id, node_id and label.
+    async fn digest_bucket(
+        &self,
+        from_where: &str,
+        date_column: &str,
+        columns: &str,
+        project_id: Uuid,
+        cutoff: Option<chrono::DateTime<Utc>>,
+        samples: usize,
+    ) -> Result<DigestBucket> {
+        let cutoff = cutoff.map(|c| c.to_rfc3339());
+        let summary_sql =
+            format!("SELECT COUNT(*) AS n, MIN({date_column}) AS oldest {from_where}");
+        let mut summary = sqlx::query(&summary_sql).bind(project_id.to_string());
+        if let Some(cutoff) = &cutoff {
+            summary = summary.bind(cutoff);
+        }
+        let row = summary.fetch_one(&self.pool).await?;
+        let count: i64 = row.try_get("n")?;
+        if count == 0 {
+            return Ok(DigestBucket::default());
+        }
+        let oldest = row
+            .try_get::<Option<String>, _>("oldest")?
+            .map(|t| parse_utc(&t))
+            .transpose()?;
+
+        let sample_sql = format!(
+            "SELECT {columns}, {date_column} AS since {from_where} ORDER BY {date_column}, id LIMIT ?"
+        );
+        let mut sample = sqlx::query(&sample_sql).bind(project_id.to_string());
+        if let Some(cutoff) = &cutoff {
+            sample = sample.bind(cutoff);
+        }
+        let rows = sample.bind(samples as i64).fetch_all(&self.pool).await?;
+        let samples = rows
+            .iter()
+            .map(|row| -> Result<DigestSample> {
+                Ok(DigestSample {
+                    id: row.try_get::<String, _>("id")?.parse()?,
+                    node_id: row
+                        .try_get::<Option<String>, _>("node_id")?
+                        .map(|id| id.parse())
+                        .transpose()?,
+                    label: row.try_get::<Option<String>, _>("label")?.unwrap_or_default(),
+                    since: parse_utc(&row.try_get::<String, _>("since")?)?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(DigestBucket {
+            count,
+            oldest,
+            samples,
+        })
+    }
+
+    // -- Presence ----------------------------------------------------------
+
+    pub async fn list_presence(&self) -> Result<Vec<Presence>> {
+        let rows = sqlx::query("SELECT * FROM presence ORDER BY machine")
+            .fetch_all(&self.pool)
+            .await?;
+        rows.iter().map(row_to_presence).collect()
+    }
+
+    pub async fn get_presence(&self, machine: &str) -> Result<Option<Presence>> {
+        let row = sqlx::query("SELECT * FROM presence WHERE machine = ?")
+            .bind(machine)
+            .fetch_optional(&self.pool)
+            .await?;
+        row.as_ref().map(row_to_presence).transpose()
+    }
+
+    /// Write `machine`'s heartbeat, creating its row if needed. The
+    /// identity and fallback switch are kept.
+    pub async fn touch_presence(&self, machine: &str, started_at: chrono::DateTime<Utc>) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO presence (machine, started_at, last_seen) VALUES (?, ?, ?)
+             ON CONFLICT(machine) DO UPDATE SET
+               started_at = excluded.started_at,
+               last_seen  = excluded.last_seen",
+        )
+        .bind(machine)
+        .bind(started_at.to_rfc3339())
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Store `machine`'s identity and make it the one stamped by this store.
+    pub async fn set_presence_user(&self, machine: &str, user: &str) -> Result<()> {
+        sqlx::query("UPDATE presence SET user = ? WHERE machine = ?")
+            .bind(user.trim())
+            .bind(machine)
+            .execute(&self.pool)
+            .await?;
+        self.set_identity(Some(user));
+        Ok(())
+    }
+
+    pub async fn set_presence_read_only_fallback(&self, machine: &str, enabled: bool) -> Result<()> {
+        sqlx::query("UPDATE presence SET read_only_fallback = ? WHERE machine = ?")
+            .bind(enabled as i64)
+            .bind(machine)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Mark `machine` as gone by resetting its heartbeat; the row keeps its
+    /// identity and fallback switch for the next start.
+    pub async fn clear_presence(&self, machine: &str) -> Result<()> {
+        sqlx::query("UPDATE presence SET last_seen = ? WHERE machine = ?")
+            .bind(chrono::DateTime::<Utc>::UNIX_EPOCH.to_rfc3339())
+            .bind(machine)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    // -- Inline comments ---------------------------------------------------
+
+    pub async fn add_req_comment(&self, project_id: Uuid, node_id: Uuid, parent_id: Option<Uuid>, author: &str, body: &str) -> Result<ReqComment> {
+        let author = self.stamp_actor(author);
+        let id = Uuid::new_v4();
+        let now = chrono::Utc::now().to_rfc3339();
+        sqlx::query(
+            "INSERT INTO req_comments (id, project_id, node_id, parent_id, author, body, created_at, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(id.to_string())
+        .bind(project_id.to_string())
+        .bind(node_id.to_string())
+        .bind(parent_id.map(|u| u.to_string()))
+        .bind(&author)
+        .bind(body)
+        .bind(&now)
+        .bind(&now)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(ReqComment {
+            id,
+            project_id,
+            node_id,
+            parent_id,
+            author,
+            body: body.to_string(),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            resolved_at: None,
+            resolved_by: None,
+        })
+    }
+
+    pub async fn get_req_comments(&self, node_id: Uuid) -> Result<Vec<ReqComment>> {
+        let rows = sqlx::query(
+            "SELECT id, project_id, node_id, parent_id, author, body, created_at, updated_at, resolved_at, resolved_by
+             FROM req_comments WHERE node_id = ? ORDER BY created_at ASC, id"
+        )
+        .bind(node_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(row_to_req_comment).collect()
+    }
+
+    pub async fn list_project_req_comments(&self, project_id: Uuid) -> Result<Vec<ReqComment>> {
+        let rows = sqlx::query(
+            "SELECT id, project_id, node_id, parent_id, author, body, created_at, updated_at, resolved_at, resolved_by
+             FROM req_comments WHERE project_id = ? ORDER BY created_at ASC, id"
+        )
+        .bind(project_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(row_to_req_comment).collect()
+    }
+
+    pub async fn get_comment_counts_for_project(&self, project_id: Uuid) -> Result<std::collections::HashMap<String, i64>> {
+        let rows = sqlx::query(
+            "SELECT node_id, COUNT(*) as cnt FROM req_comments WHERE project_id = ? AND resolved_at IS NULL GROUP BY node_id"
+        )
+        .bind(project_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut map = std::collections::HashMap::new();
+        for row in rows {
+            let node_id: String = row.get("node_id");
+            let cnt: i64 = row.get("cnt");
+            map.insert(node_id, cnt);
+        }
+        Ok(map)
+    }
+
+    pub async fn resolve_req_comment(&self, id: Uuid, resolved_by: &str) -> Result<()> {
         sqlx::query("UPDATE req_comments SET resolved_at = ?, resolved_by = ? WHERE id = ?")
             .bind(chrono::Utc::now().to_rfc3339())
-            .bind(resolved_by)
+            .bind(self.stamp_actor(resolved_by))
             .bind(id.to_string())
             .execute(&self.pool)
             .await?;
@@ -1039,6 +3124,268 @@ impl Store {
         row.as_ref().map(row_to_node).transpose()
     }
 
+    /// The project's requirement with `req_id` (trimmed, exact case), via
+    /// the (project_id, req_id) index. The oldest wins if several share it.
+    pub async fn find_node_by_req_id(
+        &self,
+        project_id: Uuid,
+        req_id: &str,
+    ) -> Result<Option<Node>> {
+        let row = sqlx::query(
+            "SELECT * FROM nodes
+             WHERE project_id = ? AND req_id = ? AND kind = 'requirement'
+             ORDER BY created_at, id
+             LIMIT 1",
+        )
+        .bind(project_id.to_string())
+        .bind(req_id.trim())
+        .fetch_optional(&self.pool)
+        .await?;
+        row.as_ref().map(row_to_node).transpose()
+    }
+
+    /// How many live requirements in the project have `req_id`, trimmed
+    /// and ignoring ASCII case.
+    pub async fn count_requirements_with_req_id(
+        &self,
+        project_id: Uuid,
+        req_id: &str,
+    ) -> Result<i64> {
+        let row = sqlx::query(
+            "SELECT COUNT(*) AS n FROM nodes
+             WHERE project_id = ? AND kind = 'requirement' AND archived = 0
+               AND lower(trim(req_id)) = lower(?)",
+        )
+        .bind(project_id.to_string())
+        .bind(req_id.trim())
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(row.try_get("n")?)
+    }
+
+    // ── Command palette ───────────────────────────────────────────────────────
+
+    /// Palette targets for a project: live nodes, diagrams, documents,
+    /// scenarios and review sessions, in that order, at most `limit` in
+    /// total. Each table is read by name columns only.
+    pub async fn palette_entries(
+        &self,
+        project_id: Uuid,
+        limit: usize,
+    ) -> Result<Vec<PaletteEntry>> {
+        const SOURCES: [(PaletteEntityType, &str); 5] = [
+            (
+                PaletteEntityType::Node,
+                "SELECT id, name AS label, COALESCE(NULLIF(req_id, ''), kind) AS sublabel
+                 FROM nodes WHERE project_id = ? AND archived = 0
+                 ORDER BY name, id LIMIT ?",
+            ),
+            (
+                PaletteEntityType::Diagram,
+                "SELECT id, name AS label, kind AS sublabel
+                 FROM diagrams WHERE project_id = ? ORDER BY name, id LIMIT ?",
+            ),
+            (
+                PaletteEntityType::Document,
+                "SELECT id, name AS label, NULLIF(doc_type, '') AS sublabel
+                 FROM documents WHERE project_id = ? ORDER BY name, id LIMIT ?",
+            ),
+            (
+                PaletteEntityType::Scenario,
+                "SELECT id, name AS label, NULL AS sublabel
+                 FROM simulation_scenarios WHERE project_id = ? ORDER BY name, id LIMIT ?",
+            ),
+            (
+                PaletteEntityType::ReviewSession,
+                "SELECT id, title AS label, status AS sublabel
+                 FROM review_sessions WHERE project_id = ? ORDER BY created_at, id LIMIT ?",
+            ),
+        ];
+
+        let mut entries = Vec::new();
+        for (entity_type, sql) in SOURCES {
+            let remaining = limit.saturating_sub(entries.len());
+            if remaining == 0 {
+                break;
+            }
+            let rows = sqlx::query(sql)
+                .bind(project_id.to_string())
+                .bind(remaining as i64)
+                .fetch_all(&self.pool)
+                .await?;
+            for row in &rows {
+                entries.push(PaletteEntry {
+                    id: row.try_get::<String, _>("id")?.parse()?,
+                    entity_type,
+                    label: row.try_get("label")?,
+                    sublabel: row.try_get("sublabel")?,
+                });
+            }
+        }
+        Ok(entries)
+    }
+
+    // ── Node usage ────────────────────────────────────────────────────────────
+
+    /// Everything that refers to `node`, one targeted query per referencing
+    /// table. Allocation tags match the node's name, case-insensitively.
+    pub async fn node_usage(&self, node: &Node) -> Result<NodeUsage> {
+        let id = node.id.to_string();
+
+        let rows = sqlx::query(
+            "SELECT e.id, e.kind, e.source_id, e.target_id, o.name AS other_name
+             FROM edges e
+             LEFT JOIN nodes o
+               ON o.id = CASE WHEN e.source_id = ? THEN e.target_id ELSE e.source_id END
+             WHERE e.source_id = ? OR e.target_id = ?
+             ORDER BY e.kind, e.created_at, e.id",
+        )
+        .bind(&id)
+        .bind(&id)
+        .bind(&id)
+        .fetch_all(&self.pool)
+        .await?;
+        let mut edges = Vec::with_capacity(rows.len());
+        for row in &rows {
+            let Ok(kind) = parse_edge_kind(row.try_get::<&str, _>("kind")?) else {
+                continue;
+            };
+            let source_id: Uuid = row.try_get::<String, _>("source_id")?.parse()?;
+            let target_id: Uuid = row.try_get::<String, _>("target_id")?.parse()?;
+            let outgoing = source_id == node.id;
+            edges.push(UsageEdge {
+                edge_id: row.try_get::<String, _>("id")?.parse()?,
+                kind,
+                other_id: if outgoing { target_id } else { source_id },
+                other_name: row.try_get::<Option<String>, _>("other_name")?.unwrap_or_default(),
+                outgoing,
+            });
+        }
+        let mut edges_by_kind: Vec<HistoryCount> = Vec::new();
+        for edge in &edges {
+            let key = edge.kind.to_string();
+            match edges_by_kind.iter_mut().find(|c| c.key == key) {
+                Some(c) => c.count += 1,
+                None => edges_by_kind.push(HistoryCount { key, count: 1 }),
+            }
+        }
+
+        let diagrams = sqlx::query(
+            "SELECT d.id, d.name FROM diagram_elements de
+             JOIN diagrams d ON d.id = de.diagram_id
+             WHERE de.node_id = ?
+             ORDER BY d.name, d.id",
+        )
+        .bind(&id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let allocated_requirements = sqlx::query(
+            "SELECT n.id,
+                    CASE WHEN COALESCE(n.req_id, '') = '' THEN n.name
+                         ELSE n.req_id || ' — ' || n.name END AS name
+             FROM nodes n
+             JOIN json_each(CASE WHEN json_valid(n.req_allocations)
+                                 THEN n.req_allocations ELSE '[]' END) a
+             WHERE n.project_id = ? AND n.kind = 'requirement'
+               AND lower(trim(a.value)) = lower(trim(?))
+             GROUP BY n.id
+             ORDER BY n.req_id, n.name",
+        )
+        .bind(node.project_id.to_string())
+        .bind(&node.name)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let comments = sqlx::query(
+            "SELECT COUNT(*) AS total,
+                    COALESCE(SUM(CASE WHEN resolved_at IS NULL THEN 1 ELSE 0 END), 0) AS open
+             FROM req_comments WHERE node_id = ?",
+        )
+        .bind(&id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let review_sessions = sqlx::query(
+            "SELECT DISTINCT s.id, s.title AS name, s.created_at FROM review_items i
+             JOIN review_sessions s ON s.id = i.session_id
+             WHERE i.node_id = ?
+             ORDER BY s.created_at, s.id",
+        )
+        .bind(&id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let suspect_links: Vec<String> = sqlx::query_scalar(
+            "SELECT id FROM suspect_links
+             WHERE resolved_at IS NULL AND (source_node_id = ? OR target_node_id = ?)
+             ORDER BY flagged_at",
+        )
+        .bind(&id)
+        .bind(&id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let knowledge_pages = sqlx::query(
+            "SELECT id, title AS name FROM subsystem_knowledge
+             WHERE subsystem_id = ? ORDER BY title, id",
+        )
+        .bind(&id)
+        .fetch_all(&self.pool)
+        .await?;
+        let artifacts = sqlx::query(
+            "SELECT id, title AS name FROM subsystem_artifacts
+             WHERE subsystem_id = ? ORDER BY title, id",
+        )
+        .bind(&id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        // Scenario events are a JSON array of { time_ms, block_id, ... }.
+        let sim_scenarios = sqlx::query(
+            "SELECT id, name FROM simulation_scenarios
+             WHERE project_id = ? AND events LIKE ?
+             ORDER BY name, id",
+        )
+        .bind(node.project_id.to_string())
+        .bind(format!("%{id}%"))
+        .fetch_all(&self.pool)
+        .await?;
+
+        let properties: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM block_properties WHERE block_node_id = ?")
+                .bind(&id)
+                .fetch_one(&self.pool)
+                .await?;
+        let typed_properties = self.count_value_type_references(node.id).await?;
+        let external_links: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM external_item_links WHERE node_id = ?")
+                .bind(&id)
+                .fetch_one(&self.pool)
+                .await?;
+
+        Ok(NodeUsage {
+            node_id: node.id,
+            edges,
+            edges_by_kind,
+            diagrams: rows_to_usage_refs(&diagrams)?,
+            allocated_requirements: rows_to_usage_refs(&allocated_requirements)?,
+            comments: comments.try_get("total")?,
+            open_comments: comments.try_get("open")?,
+            review_sessions: rows_to_usage_refs(&review_sessions)?,
+            suspect_links: suspect_links
+                .iter()
+                .map(|s| s.parse())
+                .collect::<std::result::Result<_, _>>()?,
+            knowledge_pages: rows_to_usage_refs(&knowledge_pages)?,
+            artifacts: rows_to_usage_refs(&artifacts)?,
+            sim_scenarios: rows_to_usage_refs(&sim_scenarios)?,
+            properties,
+            typed_properties,
+            external_links,
+        })
+    }
+
     // ── Simulation scenarios ──────────────────────────────────────────────────
 
     pub async fn upsert_simulation_scenario(&self, s: &SimulationScenario) -> Result<()> {
@@ -1120,78 +3467,1253 @@ impl Store {
         row.as_ref().map(row_to_simulation_result).transpose()
     }
 
-    pub async fn update_simulation_result_status(
+    pub async fn update_simulation_result_status(
+        &self,
+        id: Uuid,
+        status: &str,
+        metrics: serde_json::Value,
+        timeline: serde_json::Value,
+        errors: serde_json::Value,
+    ) -> Result<()> {
+        sqlx::query(
+            "UPDATE simulation_results SET status = ?, metrics = ?, timeline = ?, errors = ? WHERE id = ?",
+        )
+        .bind(status)
+        .bind(serde_json::to_string(&metrics)?)
+        .bind(serde_json::to_string(&timeline)?)
+        .bind(serde_json::to_string(&errors)?)
+        .bind(id.to_string())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Mark results still `running` that started before `before` as
+    /// `orphaned`: the app was closed or killed while they ran, so nothing
+    /// will ever finish them. Returns how many were marked.
+    pub async fn mark_orphaned_simulation_results(
+        &self,
+        before: chrono::DateTime<Utc>,
+    ) -> Result<u64> {
+        let errors = serde_json::json!(["The app exited before the simulation finished"]);
+        let result = sqlx::query(
+            "UPDATE simulation_results SET status = 'orphaned', errors = ?
+             WHERE status = 'running' AND ran_at < ?",
+        )
+        .bind(serde_json::to_string(&errors)?)
+        .bind(before.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    // ── Model baselines ───────────────────────────────────────────────────────
+
+    pub async fn create_baseline(&self, baseline: &ModelBaseline) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            "INSERT INTO model_baselines (id, project_id, name, description, created_by, created_at, snapshot)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(baseline.id.to_string())
+        .bind(baseline.project_id.to_string())
+        .bind(&baseline.name)
+        .bind(&baseline.description)
+        .bind(&baseline.created_by)
+        .bind(baseline.created_at.to_rfc3339())
+        .bind(serde_json::to_string(&baseline.snapshot)?)
+        .execute(&mut *tx)
+        .await?;
+        self.record_audit(
+            &mut tx,
+            baseline.project_id,
+            "baseline",
+            baseline.id,
+            "create",
+            &baseline.created_by,
+            &format!("created baseline '{}'", baseline.name),
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn list_baselines(&self, project_id: Uuid) -> Result<Vec<ModelBaseline>> {
+        let rows = sqlx::query(
+            "SELECT id, project_id, name, description, created_by, created_at, snapshot
+             FROM model_baselines WHERE project_id = ? ORDER BY created_at DESC",
+        )
+        .bind(project_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(row_to_baseline).collect()
+    }
+
+    pub async fn get_baseline(&self, id: Uuid) -> Result<Option<ModelBaseline>> {
+        let row = sqlx::query(
+            "SELECT id, project_id, name, description, created_by, created_at, snapshot
+             FROM model_baselines WHERE id = ?",
+        )
+        .bind(id.to_string())
+        .fetch_optional(&self.pool)
+        .await?;
+        row.as_ref().map(row_to_baseline).transpose()
+    }
+
+    pub async fn delete_baseline(&self, id: Uuid, actor: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        let existing = sqlx::query("SELECT project_id, name FROM model_baselines WHERE id = ?")
+            .bind(id.to_string())
+            .fetch_optional(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM model_baselines WHERE id = ?")
+            .bind(id.to_string())
+            .execute(&mut *tx)
+            .await?;
+        if let Some(row) = existing {
+            let project_id: Uuid = row.try_get::<String, _>("project_id")?.parse()?;
+            let name: String = row.try_get("name")?;
+            let summary = format!("deleted baseline '{name}'");
+            self.record_audit(&mut tx, project_id, "baseline", id, "delete", actor, &summary)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Requirement coverage of the live model, counted in SQL as
+    /// `core::metrics::model_coverage` counts it: archived nodes and
+    /// requirements marked Obsolete are left out, and an edge from an
+    /// archived node doesn't count. `core::baseline::coverage_metrics`
+    /// counts a snapshot the same way.
+    pub async fn coverage_metrics(&self, project_id: Uuid) -> Result<CoverageMetrics> {
+        let row = sqlx::query(
+            "SELECT COUNT(*) AS total,
+                    COALESCE(SUM(EXISTS (SELECT 1 FROM edges e
+                                         JOIN nodes s ON s.id = e.source_id AND s.archived = 0
+                                         WHERE e.target_id = n.id AND e.kind = 'satisfies')), 0)
+                        AS satisfied,
+                    COALESCE(SUM(EXISTS (SELECT 1 FROM edges e
+                                         JOIN nodes s ON s.id = e.source_id AND s.archived = 0
+                                         WHERE e.target_id = n.id AND e.kind = 'verifies')), 0)
+                        AS verified,
+                    COALESCE(SUM(n.req_status = 'approved'), 0) AS approved,
+                    COALESCE(SUM(COALESCE(n.req_verification_method, '') <> ''), 0)
+                        AS with_method,
+                    COALESCE(SUM(CASE WHEN json_valid(n.req_allocations)
+                                      THEN json_array_length(n.req_allocations) > 0
+                                      ELSE 0 END), 0) AS allocated
+             FROM nodes n
+             WHERE n.project_id = ? AND n.kind = 'requirement' AND n.archived = 0
+               AND COALESCE(n.req_status, '') <> 'obsolete'",
+        )
+        .bind(project_id.to_string())
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(CoverageMetrics::from_counts(
+            row.try_get("total")?,
+            row.try_get("satisfied")?,
+            row.try_get("verified")?,
+            row.try_get("approved")?,
+            row.try_get("with_method")?,
+            row.try_get("allocated")?,
+        ))
+    }
+
+    /// Coverage as of each baseline, oldest first. Metrics are cached in
+    /// `model_baselines.coverage_metrics`; a baseline without them (taken
+    /// before the column existed, or not yet charted) has its snapshot
+    /// evaluated once here and the result stored.
+    pub async fn baseline_coverage(&self, project_id: Uuid) -> Result<Vec<CoveragePoint>> {
+        let rows = sqlx::query(
+            "SELECT id, name, created_at, coverage_metrics
+             FROM model_baselines WHERE project_id = ? ORDER BY created_at, id",
+        )
+        .bind(project_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut points = Vec::with_capacity(rows.len());
+        for row in &rows {
+            let id: String = row.try_get("id")?;
+            let cached = row
+                .try_get::<Option<String>, _>("coverage_metrics")?
+                .and_then(|raw| serde_json::from_str::<CoverageMetrics>(&raw).ok());
+            let metrics = match cached {
+                Some(metrics) => metrics,
+                None => {
+                    let snapshot: String =
+                        sqlx::query_scalar("SELECT snapshot FROM model_baselines WHERE id = ?")
+                            .bind(&id)
+                            .fetch_one(&self.pool)
+                            .await?;
+                    let snapshot = serde_json::from_str(&snapshot).unwrap_or_default();
+                    let metrics = crate::core::baseline::coverage_metrics(&snapshot);
+                    sqlx::query("UPDATE model_baselines SET coverage_metrics = ? WHERE id = ?")
+                        .bind(serde_json::to_string(&metrics)?)
+                        .bind(&id)
+                        .execute(&self.pool)
+                        .await?;
+                    metrics
+                }
+            };
+            points.push(CoveragePoint {
+                baseline_id: Some(id.parse()?),
+                name: row.try_get("name")?,
+                at: chrono::DateTime::parse_from_rfc3339(
+                    row.try_get::<String, _>("created_at")?.as_str(),
+                )?
+                .with_timezone(&Utc),
+                metrics,
+            });
+        }
+        Ok(points)
+    }
+
+    // ── Portfolio ─────────────────────────────────────────────────────────────
+
+    /// Health of every project, by name. One grouped query per table, so
+    /// the cost doesn't grow with the number of projects. Status counts
+    /// cover every unarchived requirement; coverage is counted as in
+    /// `coverage_metrics`, leaving Obsolete ones out.
+    pub async fn portfolio_summary(&self) -> Result<Vec<ProjectHealth>> {
+        use std::collections::HashMap;
+
+        let mut health: Vec<ProjectHealth> = Vec::new();
+        for project in self.list_projects().await? {
+            health.push(ProjectHealth {
+                project_id: project.id,
+                name: project.name,
+                requirements: RequirementStatusCounts::default(),
+                coverage: CoverageMetrics::default(),
+                unresolved_suspect_links: 0,
+                open_reviews: 0,
+                last_baseline_at: None,
+                last_activity_at: project.modified_at,
+            });
+        }
+        health.sort_by_key(|h| h.name.to_lowercase());
+        let index: HashMap<String, usize> = health
+            .iter()
+            .enumerate()
+            .map(|(i, h)| (h.project_id.to_string(), i))
+            .collect();
+        let slot = |row: &sqlx::sqlite::SqliteRow| -> Result<Option<usize>> {
+            Ok(index.get(&row.try_get::<String, _>("project_id")?).copied())
+        };
+
+        let rows = sqlx::query(
+            "SELECT n.project_id,
+                    COALESCE(SUM(n.req_status = 'draft' OR n.req_status IS NULL), 0) AS draft,
+                    COALESCE(SUM(n.req_status = 'approved'), 0) AS approved,
+                    COALESCE(SUM(n.req_status = 'obsolete'), 0) AS obsolete,
+                    COALESCE(SUM(n.counted), 0) AS total,
+                    COALESCE(SUM(n.counted AND EXISTS (
+                        SELECT 1 FROM edges e
+                        JOIN nodes s ON s.id = e.source_id AND s.archived = 0
+                        WHERE e.target_id = n.id AND e.kind = 'satisfies')), 0) AS satisfied,
+                    COALESCE(SUM(n.counted AND EXISTS (
+                        SELECT 1 FROM edges e
+                        JOIN nodes s ON s.id = e.source_id AND s.archived = 0
+                        WHERE e.target_id = n.id AND e.kind = 'verifies')), 0) AS verified,
+                    COALESCE(SUM(n.counted AND COALESCE(n.req_verification_method, '') <> ''), 0)
+                        AS with_method,
+                    COALESCE(SUM(n.counted AND CASE WHEN json_valid(n.req_allocations)
+                                                    THEN json_array_length(n.req_allocations) > 0
+                                                    ELSE 0 END), 0) AS allocated
+             FROM (SELECT *, COALESCE(req_status, '') <> 'obsolete' AS counted
+                   FROM nodes WHERE kind = 'requirement' AND archived = 0) n
+             GROUP BY n.project_id",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        for row in &rows {
+            let Some(i) = slot(row)? else {
+                continue;
+            };
+            let approved: i64 = row.try_get("approved")?;
+            health[i].requirements = RequirementStatusCounts {
+                draft: row.try_get("draft")?,
+                approved,
+                obsolete: row.try_get("obsolete")?,
+            };
+            health[i].coverage = CoverageMetrics::from_counts(
+                row.try_get("total")?,
+                row.try_get("satisfied")?,
+                row.try_get("verified")?,
+                approved,
+                row.try_get("with_method")?,
+                row.try_get("allocated")?,
+            );
+        }
+
+        let rows = sqlx::query(
+            "SELECT project_id, COUNT(*) AS n FROM suspect_links
+             WHERE resolved_at IS NULL GROUP BY project_id",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        for row in &rows {
+            if let Some(i) = slot(row)? {
+                health[i].unresolved_suspect_links = row.try_get("n")?;
+            }
+        }
+
+        let rows = sqlx::query(
+            "SELECT project_id, COUNT(*) AS n FROM review_sessions
+             WHERE status IN ('open', 'in_progress') GROUP BY project_id",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        for row in &rows {
+            if let Some(i) = slot(row)? {
+                health[i].open_reviews = row.try_get("n")?;
+            }
+        }
+
+        let rows = sqlx::query(
+            "SELECT project_id, MAX(created_at) AS at FROM model_baselines GROUP BY project_id",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        for row in &rows {
+            if let Some(i) = slot(row)? {
+                health[i].last_baseline_at = Some(parse_utc(&row.try_get::<String, _>("at")?)?);
+            }
+        }
+
+        let rows =
+            sqlx::query("SELECT project_id, MAX(changed_at) AS at FROM audit_log GROUP BY project_id")
+                .fetch_all(&self.pool)
+                .await?;
+        for row in &rows {
+            if let Some(i) = slot(row)? {
+                let at = parse_utc(&row.try_get::<String, _>("at")?)?;
+                health[i].last_activity_at = health[i].last_activity_at.max(at);
+            }
+        }
+        Ok(health)
+    }
+
+    // ── Project templates ─────────────────────────────────────────────────────
+
+    pub async fn create_project_template(&self, template: &ProjectTemplate) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO project_templates (id, name, description, include, content, created_at)
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(template.id.to_string())
+        .bind(&template.name)
+        .bind(&template.description)
+        .bind(serde_json::to_string(&template.include)?)
+        .bind(serde_json::to_string(&template.content)?)
+        .bind(template.created_at.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Saved templates, newest first. Built-in templates are not included.
+    pub async fn list_project_templates(&self) -> Result<Vec<ProjectTemplate>> {
+        let rows = sqlx::query(
+            "SELECT id, name, description, include, content, created_at
+             FROM project_templates ORDER BY created_at DESC",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(row_to_project_template).collect()
+    }
+
+    pub async fn get_project_template(&self, id: Uuid) -> Result<Option<ProjectTemplate>> {
+        let row = sqlx::query(
+            "SELECT id, name, description, include, content, created_at
+             FROM project_templates WHERE id = ?",
+        )
+        .bind(id.to_string())
+        .fetch_optional(&self.pool)
+        .await?;
+        row.as_ref().map(row_to_project_template).transpose()
+    }
+
+    /// Write instantiated template content into its project, which must
+    /// already exist, through the ordinary writes. Value types go first so
+    /// typed block properties can reference them.
+    pub async fn write_template_content(
+        &self,
+        content: &ProjectTemplateContent,
+        actor: &str,
+    ) -> Result<()> {
+        let (value_types, others): (Vec<&Node>, Vec<&Node>) =
+            content.nodes.iter().partition(|n| n.kind == NodeKind::ValueType);
+        for node in value_types.into_iter().chain(others) {
+            self.upsert_node(node).await?;
+        }
+        for edge in &content.edges {
+            self.upsert_edge(edge).await?;
+        }
+        for property in &content.block_properties {
+            self.upsert_block_property(property).await?;
+        }
+        for diagram in &content.diagrams {
+            self.upsert_diagram(diagram, actor).await?;
+        }
+        for element in &content.diagram_elements {
+            self.upsert_diagram_element(element, actor).await?;
+        }
+        self.set_settings(&content.settings).await
+    }
+
+    /// Create the example project (see `core::example`) through the
+    /// ordinary writes. If any write fails the half-built project is
+    /// deleted again. Tests use it as a fully populated fixture.
+    pub async fn create_example_project(
+        &self,
+        example: &crate::core::example::ExampleProject,
+        actor: &str,
+    ) -> Result<()> {
+        self.create_project(&example.project, actor).await?;
+        let filled = async {
+            self.write_template_content(&example.content, actor).await?;
+            self.upsert_document(&example.document, actor).await?;
+            for section in &example.sections {
+                self.upsert_document_section(section).await?;
+            }
+            self.upsert_simulation_scenario(&example.scenario).await
+        }
+        .await;
+        if let Err(e) = filled {
+            let _ = self.delete_project(example.project.id, actor).await;
+            return Err(e.context("failed to create the example project"));
+        }
+        Ok(())
+    }
+
+    // ── External items ────────────────────────────────────────────────────────
+
+    /// Insert or update items by `(project_id, external_key)` in one
+    /// transaction. Updated items keep their id and links. Returns the
+    /// number created and updated.
+    pub async fn upsert_external_items(&self, items: &[ExternalItem]) -> Result<(usize, usize)> {
+        let mut tx = self.pool.begin().await?;
+        let (mut created, mut updated) = (0, 0);
+        for item in items {
+            let existing = sqlx::query(
+                "SELECT id FROM external_items WHERE project_id = ? AND external_key = ?",
+            )
+            .bind(item.project_id.to_string())
+            .bind(&item.external_key)
+            .fetch_optional(&mut *tx)
+            .await?;
+            match existing {
+                Some(row) => {
+                    sqlx::query(
+                        "UPDATE external_items
+                         SET source = ?, title = ?, url = ?, status = ?, synced_at = ?
+                         WHERE id = ?",
+                    )
+                    .bind(external_source_str(item.source))
+                    .bind(&item.title)
+                    .bind(&item.url)
+                    .bind(&item.status)
+                    .bind(item.synced_at.to_rfc3339())
+                    .bind(row.try_get::<String, _>("id")?)
+                    .execute(&mut *tx)
+                    .await?;
+                    updated += 1;
+                }
+                None => {
+                    sqlx::query(
+                        "INSERT INTO external_items
+                         (id, project_id, source, external_key, title, url, status, synced_at)
+                         VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+                    )
+                    .bind(item.id.to_string())
+                    .bind(item.project_id.to_string())
+                    .bind(external_source_str(item.source))
+                    .bind(&item.external_key)
+                    .bind(&item.title)
+                    .bind(&item.url)
+                    .bind(&item.status)
+                    .bind(item.synced_at.to_rfc3339())
+                    .execute(&mut *tx)
+                    .await?;
+                    created += 1;
+                }
+            }
+        }
+        tx.commit().await?;
+        Ok((created, updated))
+    }
+
+    /// A project's external items by key, optionally only those whose
+    /// status matches `status` (case-insensitive).
+    pub async fn list_external_items(
+        &self,
+        project_id: Uuid,
+        status: Option<&str>,
+    ) -> Result<Vec<ExternalItem>> {
+        let rows = sqlx::query(
+            "SELECT * FROM external_items
+             WHERE project_id = ? AND (? IS NULL OR status = ? COLLATE NOCASE)
+             ORDER BY external_key, id",
+        )
+        .bind(project_id.to_string())
+        .bind(status)
+        .bind(status)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(row_to_external_item).collect()
+    }
+
+    pub async fn get_external_item(&self, id: Uuid) -> Result<Option<ExternalItem>> {
+        let row = sqlx::query("SELECT * FROM external_items WHERE id = ?")
+            .bind(id.to_string())
+            .fetch_optional(&self.pool)
+            .await?;
+        row.as_ref().map(row_to_external_item).transpose()
+    }
+
+    /// Link an item to a node; linking twice is a no-op.
+    pub async fn link_external_item(&self, item_id: Uuid, node_id: Uuid) -> Result<()> {
+        sqlx::query(
+            "INSERT OR IGNORE INTO external_item_links (item_id, node_id, created_at)
+             VALUES (?, ?, ?)",
+        )
+        .bind(item_id.to_string())
+        .bind(node_id.to_string())
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn unlink_external_item(&self, item_id: Uuid, node_id: Uuid) -> Result<()> {
+        sqlx::query("DELETE FROM external_item_links WHERE item_id = ? AND node_id = ?")
+            .bind(item_id.to_string())
+            .bind(node_id.to_string())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Every item–node link in a project, ordered by node then item key.
+    pub async fn list_project_external_links(
+        &self,
+        project_id: Uuid,
+    ) -> Result<Vec<LinkedExternalItem>> {
+        let rows = sqlx::query(
+            "SELECT l.node_id, i.*
+             FROM external_item_links l
+             JOIN external_items i ON i.id = l.item_id
+             WHERE i.project_id = ?
+             ORDER BY l.node_id, i.external_key, i.id",
+        )
+        .bind(project_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(row_to_linked_external_item).collect()
+    }
+
+    /// Items linked to one node, by key.
+    pub async fn external_items_for_node(&self, node_id: Uuid) -> Result<Vec<ExternalItem>> {
+        let rows = sqlx::query(
+            "SELECT i.*
+             FROM external_item_links l
+             JOIN external_items i ON i.id = l.item_id
+             WHERE l.node_id = ?
+             ORDER BY i.external_key, i.id",
+        )
+        .bind(node_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(row_to_external_item).collect()
+    }
+
+    // ── Extraction review ─────────────────────────────────────────────────────
+
+    /// Insert or update a session's review. The raw results are only written
+    /// when the session is created; later saves replace the items.
+    pub async fn save_extraction_review_state(&self, review: &ExtractionReviewState) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO extraction_review_state
+                 (session_id, project_id, document_id, source_name, results, items,
+                  created_at, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(session_id) DO UPDATE SET
+                 items = excluded.items,
+                 source_name = excluded.source_name,
+                 updated_at = excluded.updated_at",
+        )
+        .bind(&review.session_id)
+        .bind(review.project_id.to_string())
+        .bind(review.document_id.map(|id| id.to_string()))
+        .bind(&review.source_name)
+        .bind(serde_json::to_string(&review.results)?)
+        .bind(serde_json::to_string(&review.items)?)
+        .bind(review.created_at.to_rfc3339())
+        .bind(review.updated_at.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn get_extraction_review_state(
+        &self,
+        session_id: &str,
+    ) -> Result<Option<ExtractionReviewState>> {
+        let row = sqlx::query(
+            "SELECT session_id, project_id, document_id, source_name, results, items,
+                    created_at, updated_at
+             FROM extraction_review_state WHERE session_id = ?",
+        )
+        .bind(session_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        row.as_ref().map(row_to_extraction_review_state).transpose()
+    }
+
+    // ── Requirement id counters ───────────────────────────────────────────────
+
+    /// Last sequence number per section-based id prefix; see
+    /// `core::numbering`.
+    pub async fn req_id_counters(
+        &self,
+        project_id: Uuid,
+    ) -> Result<std::collections::HashMap<String, i64>> {
+        let rows = sqlx::query("SELECT prefix, last_seq FROM req_id_counters WHERE project_id = ?")
+            .bind(project_id.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+        rows.iter()
+            .map(|r| Ok((r.try_get("prefix")?, r.try_get("last_seq")?)))
+            .collect()
+    }
+
+    /// Store counters after handing out ids. Each stored counter only moves
+    /// forward, unless `replace`, which first clears the project's counters
+    /// (renumbering starts every section again at 1).
+    pub async fn save_req_id_counters(
+        &self,
+        project_id: Uuid,
+        counters: &std::collections::HashMap<String, i64>,
+        replace: bool,
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        if replace {
+            sqlx::query("DELETE FROM req_id_counters WHERE project_id = ?")
+                .bind(project_id.to_string())
+                .execute(&mut *tx)
+                .await?;
+        }
+        for (prefix, last_seq) in counters {
+            sqlx::query(
+                "INSERT INTO req_id_counters (project_id, prefix, last_seq) VALUES (?, ?, ?)
+                 ON CONFLICT(project_id, prefix)
+                 DO UPDATE SET last_seq = MAX(last_seq, excluded.last_seq)",
+            )
+            .bind(project_id.to_string())
+            .bind(prefix)
+            .bind(last_seq)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // ── Audit log ─────────────────────────────────────────────────────────────
+
+    /// Append one audit entry on `conn`. The mutating store methods pass
+    /// their transaction, so a change and its entry commit or roll back
+    /// together.
+    #[allow(clippy::too_many_arguments)]
+    async fn record_audit(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+        project_id: Uuid,
+        entity_type: &str,
+        entity_id: Uuid,
+        action: &str,
+        actor: &str,
+        summary: &str,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO audit_log (id, project_id, entity_type, entity_id, action, actor, summary, changed_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(project_id.to_string())
+        .bind(entity_type)
+        .bind(entity_id.to_string())
+        .bind(action)
+        .bind(self.stamp_actor(actor))
+        .bind(summary)
+        .bind(Utc::now().to_rfc3339())
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn record_ai_usage(&self, usage: &AiUsage) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO ai_usage (id, provider, model, purpose, input_tokens, output_tokens,
+                                   cache_read_tokens, cache_write_tokens, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(usage.id.to_string())
+        .bind(&usage.provider)
+        .bind(&usage.model)
+        .bind(&usage.purpose)
+        .bind(usage.input_tokens.map(i64::from))
+        .bind(usage.output_tokens.map(i64::from))
+        .bind(usage.cache_read_tokens.map(i64::from))
+        .bind(usage.cache_write_tokens.map(i64::from))
+        .bind(usage.created_at.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// A cached extraction result; see `core::extraction::cache_key`.
+    pub async fn get_extraction_cache(
+        &self,
+        project_id: Uuid,
+        cache_key: &str,
+    ) -> Result<Option<serde_json::Value>> {
+        let row = sqlx::query(
+            "SELECT result FROM ai_extraction_cache WHERE project_id = ? AND cache_key = ?",
+        )
+        .bind(project_id.to_string())
+        .bind(cache_key)
+        .fetch_optional(&self.pool)
+        .await?;
+        match row {
+            Some(row) => Ok(Some(serde_json::from_str(&row.try_get::<String, _>("result")?)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Store (or replace) an extraction result, stamped with the current time.
+    pub async fn put_extraction_cache(
         &self,
-        id: Uuid,
-        status: &str,
-        metrics: serde_json::Value,
-        timeline: serde_json::Value,
-        errors: serde_json::Value,
+        project_id: Uuid,
+        cache_key: &str,
+        result: &serde_json::Value,
     ) -> Result<()> {
         sqlx::query(
-            "UPDATE simulation_results SET status = ?, metrics = ?, timeline = ?, errors = ? WHERE id = ?",
+            "INSERT INTO ai_extraction_cache (project_id, cache_key, result, created_at)
+             VALUES (?, ?, ?, ?)
+             ON CONFLICT (project_id, cache_key)
+             DO UPDATE SET result = excluded.result, created_at = excluded.created_at",
         )
-        .bind(status)
-        .bind(serde_json::to_string(&metrics)?)
-        .bind(serde_json::to_string(&timeline)?)
-        .bind(serde_json::to_string(&errors)?)
-        .bind(id.to_string())
+        .bind(project_id.to_string())
+        .bind(cache_key)
+        .bind(result.to_string())
+        .bind(chrono::Utc::now().to_rfc3339())
         .execute(&self.pool)
         .await?;
         Ok(())
     }
 
-    // ── Model baselines ───────────────────────────────────────────────────────
+    /// Drop the project's cached extraction results; returns how many.
+    pub async fn clear_extraction_cache(&self, project_id: Uuid) -> Result<u64> {
+        let done = sqlx::query("DELETE FROM ai_extraction_cache WHERE project_id = ?")
+            .bind(project_id.to_string())
+            .execute(&self.pool)
+            .await?;
+        Ok(done.rows_affected())
+    }
 
-    pub async fn create_baseline(&self, baseline: &ModelBaseline) -> Result<()> {
-        sqlx::query(
-            "INSERT INTO model_baselines (id, project_id, name, description, created_by, created_at, snapshot)
-             VALUES (?, ?, ?, ?, ?, ?, ?)",
+    /// Like `record_audit`, but folds repeated writes by the same actor to
+    /// the same entity within `window_secs` into the latest entry (bumping
+    /// its timestamp) instead of appending. Used for high-volume writes such
+    /// as element drags.
+    #[allow(clippy::too_many_arguments)]
+    async fn record_audit_coalesced(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+        project_id: Uuid,
+        entity_type: &str,
+        entity_id: Uuid,
+        action: &str,
+        actor: &str,
+        summary: &str,
+        window_secs: i64,
+    ) -> Result<()> {
+        let now = Utc::now();
+        let cutoff = now - chrono::Duration::seconds(window_secs);
+        let updated = sqlx::query(
+            "UPDATE audit_log SET changed_at = ?
+             WHERE id = (
+                 SELECT id FROM audit_log
+                 WHERE entity_id = ? AND entity_type = ? AND action = ? AND actor = ?
+                   AND changed_at >= ?
+                 ORDER BY changed_at DESC LIMIT 1
+             )",
         )
-        .bind(baseline.id.to_string())
-        .bind(baseline.project_id.to_string())
-        .bind(&baseline.name)
-        .bind(&baseline.description)
-        .bind(&baseline.created_by)
-        .bind(baseline.created_at.to_rfc3339())
-        .bind(serde_json::to_string(&baseline.snapshot)?)
-        .execute(&self.pool)
+        .bind(now.to_rfc3339())
+        .bind(entity_id.to_string())
+        .bind(entity_type)
+        .bind(action)
+        .bind(self.stamp_actor(actor))
+        .bind(cutoff.to_rfc3339())
+        .execute(&mut *conn)
         .await?;
+
+        if updated.rows_affected() == 0 {
+            self.record_audit(conn, project_id, entity_type, entity_id, action, actor, summary)
+                .await?;
+        }
         Ok(())
     }
 
-    pub async fn list_baselines(&self, project_id: Uuid) -> Result<Vec<ModelBaseline>> {
-        let rows = sqlx::query(
-            "SELECT id, project_id, name, description, created_by, created_at, snapshot
-             FROM model_baselines WHERE project_id = ? ORDER BY created_at DESC",
-        )
-        .bind(project_id.to_string())
-        .fetch_all(&self.pool)
-        .await?;
+    pub async fn list_audit_log(
+        &self,
+        project_id: Uuid,
+        entity_type: Option<&str>,
+        entity_id: Option<Uuid>,
+        since: Option<chrono::DateTime<Utc>>,
+        limit: usize,
+    ) -> Result<Vec<AuditEntry>> {
+        let mut sql = String::from("SELECT * FROM audit_log WHERE project_id = ?");
+        if entity_type.is_some() {
+            sql.push_str(" AND entity_type = ?");
+        }
+        if entity_id.is_some() {
+            sql.push_str(" AND entity_id = ?");
+        }
+        if since.is_some() {
+            sql.push_str(" AND changed_at >= ?");
+        }
+        sql.push_str(" ORDER BY changed_at DESC LIMIT ?");
 
-        rows.iter().map(row_to_baseline).collect()
+        let mut query = sqlx::query(&sql).bind(project_id.to_string());
+        if let Some(t) = entity_type {
+            query = query.bind(t.to_string());
+        }
+        if let Some(id) = entity_id {
+            query = query.bind(id.to_string());
+        }
+        if let Some(ts) = since {
+            query = query.bind(ts.to_rfc3339());
+        }
+        let rows = query.bind(limit as i64).fetch_all(&self.pool).await?;
+
+        rows.iter().map(row_to_audit_entry).collect()
     }
 
-    pub async fn get_baseline(&self, id: Uuid) -> Result<Option<ModelBaseline>> {
-        let row = sqlx::query(
-            "SELECT id, project_id, name, description, created_by, created_at, snapshot
-             FROM model_baselines WHERE id = ?",
-        )
-        .bind(id.to_string())
-        .fetch_optional(&self.pool)
-        .await?;
-        row.as_ref().map(row_to_baseline).transpose()
+    /// Delete audit entries older than `keep_days`. Returns the number removed.
+    pub async fn prune_audit_log(&self, project_id: Uuid, keep_days: i64) -> Result<u64> {
+        let cutoff = Utc::now() - chrono::Duration::days(keep_days.max(0));
+        let result = sqlx::query("DELETE FROM audit_log WHERE project_id = ? AND changed_at < ?")
+            .bind(project_id.to_string())
+            .bind(cutoff.to_rfc3339())
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
     }
 
-    pub async fn delete_baseline(&self, id: Uuid) -> Result<()> {
-        sqlx::query("DELETE FROM model_baselines WHERE id = ?")
+    async fn node_name(&self, id: Uuid) -> Result<Option<String>> {
+        let row = sqlx::query("SELECT name FROM nodes WHERE id = ?")
             .bind(id.to_string())
-            .execute(&self.pool)
+            .fetch_optional(&self.pool)
             .await?;
-        Ok(())
+        Ok(row.map(|r| r.try_get::<String, _>("name")).transpose()?)
+    }
+
+    // ── Maintenance ───────────────────────────────────────────────────────────
+
+    /// `PRAGMA integrity_check` — one line per problem, or `["ok"]`.
+    pub async fn integrity_check(&self) -> Result<Vec<String>> {
+        let rows = sqlx::query("PRAGMA integrity_check")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.iter().map(|r| r.get::<String, _>(0)).collect())
+    }
+
+    /// Size of the main database in bytes (page_count × page_size).
+    pub async fn database_size_bytes(&self) -> Result<i64> {
+        let pages: i64 = sqlx::query_scalar("PRAGMA page_count").fetch_one(&self.pool).await?;
+        let page_size: i64 = sqlx::query_scalar("PRAGMA page_size").fetch_one(&self.pool).await?;
+        Ok(pages * page_size)
+    }
+
+    /// Rebuild the database file. Fails instead of waiting when another
+    /// connection holds the write lock.
+    pub async fn vacuum(&self) -> Result<()> {
+        let mut conn = self.pool.acquire().await?;
+
+        let result = async {
+            // Probe for an active writer without queueing behind it.
+            sqlx::query("PRAGMA busy_timeout = 0").execute(&mut *conn).await?;
+            if let Err(e) = sqlx::query("BEGIN IMMEDIATE").execute(&mut *conn).await {
+                anyhow::bail!(
+                    "another write is in progress; try vacuum again once it finishes ({e})"
+                );
+            }
+            sqlx::query("COMMIT").execute(&mut *conn).await?;
+            sqlx::query("VACUUM").execute(&mut *conn).await?;
+            // Fold the WAL back so the file on disk actually shrinks.
+            sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)").execute(&mut *conn).await?;
+            Ok::<(), anyhow::Error>(())
+        }
+        .await;
+
+        // Whatever failed above, the connection goes back to the pool with
+        // the pool's timeout, or not at all.
+        let restore = format!("PRAGMA busy_timeout = {}", BUSY_TIMEOUT.as_millis());
+        if let Err(e) = sqlx::query(&restore).execute(&mut *conn).await {
+            drop(conn.detach());
+            return result.and(Err(e.into()));
+        }
+        result
+    }
+
+    /// Count rows whose parent reference no longer resolves.
+    pub async fn scan_orphans(&self) -> Result<Vec<OrphanCount>> {
+        let mut counts = Vec::new();
+        for &(table, column, parent) in ORPHAN_REFERENCES {
+            let sql = format!("SELECT COUNT(*) FROM {table} c WHERE {}", orphan_predicate(column, parent));
+            let count: i64 = sqlx::query_scalar(&sql).fetch_one(&self.pool).await?;
+            if count > 0 {
+                counts.push(orphan_count(table, column, parent, count));
+            }
+        }
+        Ok(counts)
+    }
+
+    /// Delete every orphaned row in one transaction. Repeats until nothing
+    /// is left, since removing an orphan can orphan its own children.
+    pub async fn cleanup_orphans(&self) -> Result<Vec<OrphanCount>> {
+        let mut removed: Vec<OrphanCount> = Vec::new();
+        let mut tx = self.pool.begin().await?;
+        loop {
+            let mut pass_total = 0u64;
+            for &(table, column, parent) in ORPHAN_REFERENCES {
+                let sql = format!("DELETE FROM {table} AS c WHERE {}", orphan_predicate(column, parent));
+                let n = sqlx::query(&sql).execute(&mut *tx).await?.rows_affected();
+                if n == 0 {
+                    continue;
+                }
+                pass_total += n;
+                match removed.iter_mut().find(|r| r.table == table && r.column == column) {
+                    Some(r) => r.count += n as i64,
+                    None => removed.push(orphan_count(table, column, parent, n as i64)),
+                }
+            }
+            if pass_total == 0 {
+                break;
+            }
+        }
+        tx.commit().await?;
+        Ok(removed)
     }
 }
 
+/// (table, column, parent table) for every parent reference in the schema,
+/// including the ones migrations added without a FOREIGN KEY clause.
+/// `audit_log` is deliberately absent — it outlives what it records — and
+/// `presence` rows belong to machines, not to anything in the model.
+const ORPHAN_REFERENCES: &[(&str, &str, &str)] = &[
+    ("nodes", "project_id", "projects"),
+    ("edges", "project_id", "projects"),
+    ("edges", "source_id", "nodes"),
+    ("edges", "target_id", "nodes"),
+    ("diagrams", "project_id", "projects"),
+    ("diagram_elements", "diagram_id", "diagrams"),
+    ("diagram_elements", "node_id", "nodes"),
+    ("diagram_edge_routes", "diagram_id", "diagrams"),
+    ("diagram_edge_routes", "edge_id", "edges"),
+    ("layout_sessions", "diagram_id", "diagrams"),
+    ("ai_suggestions", "project_id", "projects"),
+    ("ai_suggestions", "diagram_id", "diagrams"),
+    ("ai_suggestions", "target_node_id", "nodes"),
+    ("settings", "project_id", "projects"),
+    ("documents", "project_id", "projects"),
+    ("document_sections", "document_id", "documents"),
+    ("document_sections", "project_id", "projects"),
+    ("subsystem_knowledge", "subsystem_id", "nodes"),
+    ("subsystem_artifacts", "subsystem_id", "nodes"),
+    ("subsystem_activity", "subsystem_id", "nodes"),
+    ("knowledge_links", "page_id", "subsystem_knowledge"),
+    ("knowledge_links", "node_id", "nodes"),
+    ("requirement_history", "project_id", "projects"),
+    ("requirement_history", "node_id", "nodes"),
+    ("suspect_links", "project_id", "projects"),
+    ("suspect_links", "edge_id", "edges"),
+    ("suspect_links", "source_node_id", "nodes"),
+    ("suspect_links", "target_node_id", "nodes"),
+    ("req_comments", "project_id", "projects"),
+    ("req_comments", "node_id", "nodes"),
+    ("req_comments", "parent_id", "req_comments"),
+    ("review_sessions", "project_id", "projects"),
+    ("review_items", "session_id", "review_sessions"),
+    ("review_items", "node_id", "nodes"),
+    ("simulation_scenarios", "project_id", "projects"),
+    ("simulation_results", "scenario_id", "simulation_scenarios"),
+    ("model_baselines", "project_id", "projects"),
+    ("block_properties", "block_node_id", "nodes"),
+    ("external_items", "project_id", "projects"),
+    ("external_item_links", "item_id", "external_items"),
+    ("external_item_links", "node_id", "nodes"),
+    ("extraction_review_state", "project_id", "projects"),
+    ("req_id_counters", "project_id", "projects"),
+    ("glossary_terms", "project_id", "projects"),
+    ("style_presets", "project_id", "projects"),
+    ("ai_extraction_cache", "project_id", "projects"),
+];
+
+/// NULL references (global settings, top-level comments) are not orphans.
+fn orphan_predicate(column: &str, parent: &str) -> String {
+    format!("c.{column} IS NOT NULL AND NOT EXISTS (SELECT 1 FROM {parent} p WHERE p.id = c.{column})")
+}
+
+fn orphan_count(table: &str, column: &str, parent: &str, count: i64) -> OrphanCount {
+    OrphanCount {
+        table: table.to_string(),
+        column: column.to_string(),
+        parent_table: parent.to_string(),
+        count,
+    }
+}
+
+/// Insert or update one section row. Shared by `upsert_document_section`
+/// and `replace_document_sections`, which writes a whole document in one
+/// transaction.
+async fn write_document_section(
+    conn: &mut sqlx::SqliteConnection,
+    s: &DocumentSection,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO document_sections
+         (id, document_id, project_id, section_ref, section_type, title, body,
+          part_number, quantity, unit, position, created_at, parent_section_id, section_level)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT(id) DO UPDATE SET
+           section_ref       = excluded.section_ref,
+           section_type      = excluded.section_type,
+           title             = excluded.title,
+           body              = excluded.body,
+           part_number       = excluded.part_number,
+           quantity          = excluded.quantity,
+           unit              = excluded.unit,
+           position          = excluded.position,
+           parent_section_id = excluded.parent_section_id,
+           section_level     = excluded.section_level",
+    )
+    .bind(s.id.to_string())
+    .bind(s.document_id.to_string())
+    .bind(s.project_id.to_string())
+    .bind(&s.section_ref)
+    .bind(s.section_type.to_string())
+    .bind(&s.title)
+    .bind(&s.body)
+    .bind(&s.part_number)
+    .bind(&s.quantity)
+    .bind(&s.unit)
+    .bind(s.position)
+    .bind(s.created_at.to_rfc3339())
+    .bind(s.parent_section_id.map(|id| id.to_string()))
+    .bind(s.section_level)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Write `node` and, for a requirement whose snapshot differs from
+/// `prev_requirement_snapshot`, its history entry. Shared by `upsert_node`
+/// and multi-node operations that need one transaction. `archived` is only
+/// written on insert; see `set_nodes_archived`.
+/// `history_actor` is recorded on the requirement history row, if any.
+async fn write_node(
+    tx: &mut sqlx::SqliteConnection,
+    node: &Node,
+    prev_requirement_snapshot: Option<RequirementSnapshot>,
+    history_actor: &str,
+) -> Result<()> {
+    // Flatten kind-specific data for column storage
+    let (
+        req_id,
+        req_text,
+        req_rationale,
+        req_priority,
+        req_status,
+        req_source,
+        req_allocations,
+        req_verif,
+        req_classification,
+        block_abstract,
+        block_mult,
+        port_dir,
+        port_type,
+        port_type_name,
+        port_multiplicity,
+        uc_level,
+        tc_procedure,
+        tc_expected,
+        tc_status,
+        sim_params,
+        sim_script,
+        vt_base_type,
+        vt_unit,
+        vt_constraint,
+        cb_expression,
+        cb_parameters,
+        state_pseudo_kind,
+        state_entry,
+        state_exit,
+        state_do,
+    ) = flatten_node_data(&node.data);
+
+    sqlx::query(
+        "INSERT INTO nodes (
+            id, project_id, kind, name, description,
+            req_id, req_text, req_rationale, req_priority, req_status,
+            req_source, req_allocations, req_verification_method, req_classification,
+            block_is_abstract, block_multiplicity,
+            port_direction, port_type_ref, port_type_name, port_multiplicity,
+            uc_level,
+            tc_procedure, tc_expected, tc_status,
+            sim_params, sim_script,
+            vt_base_type, vt_unit, vt_constraint,
+            cb_expression, cb_parameters,
+            state_pseudo_kind, state_entry, state_exit, state_do,
+            meta, archived, created_at, modified_at
+         ) VALUES (
+            ?, ?, ?, ?, ?,
+            ?, ?, ?, ?, ?,
+            ?, ?, ?, ?,
+            ?, ?,
+            ?, ?, ?, ?,
+            ?,
+            ?, ?, ?,
+            ?, ?,
+            ?, ?, ?,
+            ?, ?,
+            ?, ?, ?, ?,
+            ?, ?, ?, ?
+         )
+         ON CONFLICT(id) DO UPDATE SET
+            name = excluded.name,
+            description = excluded.description,
+            req_id = excluded.req_id,
+            req_text = excluded.req_text,
+            req_rationale = excluded.req_rationale,
+            req_priority = excluded.req_priority,
+            req_status = excluded.req_status,
+            req_source = excluded.req_source,
+            req_allocations = excluded.req_allocations,
+            req_verification_method = excluded.req_verification_method,
+            req_classification = excluded.req_classification,
+            block_is_abstract = excluded.block_is_abstract,
+            block_multiplicity = excluded.block_multiplicity,
+            port_direction = excluded.port_direction,
+            port_type_ref = excluded.port_type_ref,
+            port_type_name = excluded.port_type_name,
+            port_multiplicity = excluded.port_multiplicity,
+            uc_level = excluded.uc_level,
+            tc_procedure = excluded.tc_procedure,
+            tc_expected = excluded.tc_expected,
+            tc_status = excluded.tc_status,
+            sim_params = excluded.sim_params,
+            sim_script = excluded.sim_script,
+            vt_base_type = excluded.vt_base_type,
+            vt_unit = excluded.vt_unit,
+            vt_constraint = excluded.vt_constraint,
+            cb_expression = excluded.cb_expression,
+            cb_parameters = excluded.cb_parameters,
+            state_pseudo_kind = excluded.state_pseudo_kind,
+            state_entry = excluded.state_entry,
+            state_exit = excluded.state_exit,
+            state_do = excluded.state_do,
+            meta = excluded.meta,
+            modified_at = excluded.modified_at",
+    )
+    .bind(node.id.to_string())
+    .bind(node.project_id.to_string())
+    .bind(node.kind.to_string())
+    .bind(&node.name)
+    .bind(&node.description)
+    .bind(req_id)
+    .bind(req_text)
+    .bind(req_rationale)
+    .bind(req_priority)
+    .bind(req_status)
+    .bind(req_source)
+    .bind(req_allocations)
+    .bind(req_verif)
+    .bind(req_classification)
+    .bind(block_abstract)
+    .bind(block_mult)
+    .bind(port_dir)
+    .bind(port_type)
+    .bind(port_type_name)
+    .bind(port_multiplicity)
+    .bind(uc_level)
+    .bind(tc_procedure)
+    .bind(tc_expected)
+    .bind(tc_status)
+    .bind(sim_params)
+    .bind(sim_script)
+    .bind(vt_base_type)
+    .bind(vt_unit)
+    .bind(vt_constraint)
+    .bind(cb_expression)
+    .bind(cb_parameters)
+    .bind(state_pseudo_kind)
+    .bind(state_entry)
+    .bind(state_exit)
+    .bind(state_do)
+    .bind(serde_json::to_string(&node.meta)?)
+    .bind(node.archived as i64)
+    .bind(node.created_at.to_rfc3339())
+    .bind(node.modified_at.to_rfc3339())
+    .execute(&mut *tx)
+    .await?;
+
+    // Knowledge pages keep their link text; a renamed target only flags
+    // them for the author to look at.
+    sqlx::query(
+        "UPDATE subsystem_knowledge SET stale_links = 1
+         WHERE stale_links = 0 AND id IN (
+             SELECT page_id FROM knowledge_links
+             WHERE node_id = ? AND (node_name <> ? OR node_req_id <> ?))",
+    )
+    .bind(node.id.to_string())
+    .bind(&node.name)
+    .bind(crate::core::references::own_req_id(node).unwrap_or(""))
+    .execute(&mut *tx)
+    .await?;
+
+    if let Some(next) = requirement_snapshot_from_node(node) {
+        if prev_requirement_snapshot.as_ref() != Some(&next) {
+            let prev = prev_requirement_snapshot.unwrap_or_default();
+            sqlx::query(
+                "INSERT INTO requirement_history
+                 (id, project_id, node_id, actor, change_source, changed_at, prev_snapshot, next_snapshot)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(node.project_id.to_string())
+            .bind(node.id.to_string())
+            .bind(history_actor)
+            .bind(extract_history_source(node))
+            .bind(node.modified_at.to_rfc3339())
+            .bind(serde_json::to_string(&prev)?)
+            .bind(serde_json::to_string(&next)?)
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+    Ok(())
+}
+
 // ── Row mapping helpers ───────────────────────────────────────────────────────
 
 fn row_to_project(row: &sqlx::sqlite::SqliteRow) -> Result<Project> {
@@ -1224,6 +4746,7 @@ fn row_to_node(row: &sqlx::sqlite::SqliteRow) -> Result<Node> {
         description: row.try_get("description")?,
         data,
         meta: serde_json::from_str(&meta_str)?,
+        archived: row.try_get::<i64, _>("archived")? != 0,
         created_at: chrono::DateTime::parse_from_rfc3339(
             row.try_get::<String, _>("created_at")?.as_str(),
         )?
@@ -1292,6 +4815,36 @@ fn row_to_diagram_element(row: &sqlx::sqlite::SqliteRow) -> Result<DiagramElemen
         height: row.try_get("height")?,
         collapsed: row.try_get::<i64, _>("collapsed")? != 0,
         style_overrides: serde_json::from_str(&style_str)?,
+        created_at: parse_optional_timestamp(row.try_get("created_at")?)?,
+        modified_at: parse_optional_timestamp(row.try_get("modified_at")?)?,
+    })
+}
+
+/// Timestamp columns added by later migrations are nullable; rows that
+/// predate them read back as the Unix epoch.
+fn parse_optional_timestamp(raw: Option<String>) -> Result<chrono::DateTime<Utc>> {
+    match raw.filter(|s| !s.is_empty()) {
+        Some(s) => Ok(chrono::DateTime::parse_from_rfc3339(&s)?.with_timezone(&Utc)),
+        None => Ok(chrono::DateTime::<Utc>::default()),
+    }
+}
+
+fn row_to_req_comment(row: &sqlx::sqlite::SqliteRow) -> Result<ReqComment> {
+    Ok(ReqComment {
+        id: Uuid::parse_str(row.get("id"))?,
+        project_id: Uuid::parse_str(row.get("project_id"))?,
+        node_id: Uuid::parse_str(row.get("node_id"))?,
+        parent_id: row.get::<Option<String>, _>("parent_id")
+            .map(|s| Uuid::parse_str(&s))
+            .transpose()?,
+        author: row.get("author"),
+        body: row.get("body"),
+        created_at: chrono::DateTime::parse_from_rfc3339(row.get("created_at"))?.with_timezone(&chrono::Utc),
+        updated_at: chrono::DateTime::parse_from_rfc3339(row.get("updated_at"))?.with_timezone(&chrono::Utc),
+        resolved_at: row.get::<Option<String>, _>("resolved_at")
+            .map(|s| chrono::DateTime::parse_from_rfc3339(&s).map(|d| d.with_timezone(&chrono::Utc)))
+            .transpose()?,
+        resolved_by: row.get("resolved_by"),
     })
 }
 
@@ -1316,6 +4869,62 @@ fn row_to_document(row: &sqlx::sqlite::SqliteRow) -> Result<Document> {
     })
 }
 
+fn row_to_block_property(row: &sqlx::sqlite::SqliteRow) -> Result<BlockProperty> {
+    Ok(BlockProperty {
+        id: row.try_get::<String, _>("id")?.parse()?,
+        block_node_id: row.try_get::<String, _>("block_node_id")?.parse()?,
+        name: row.try_get("name")?,
+        value_type_node_id: row
+            .try_get::<Option<String>, _>("value_type_node_id")?
+            .map(|s| s.parse())
+            .transpose()?,
+        type_name: row.try_get("type_name")?,
+        value: row.try_get("value")?,
+        unit: row.try_get("unit")?,
+        created_at: chrono::DateTime::parse_from_rfc3339(
+            row.try_get::<String, _>("created_at")?.as_str(),
+        )?
+        .with_timezone(&chrono::Utc),
+    })
+}
+
+fn row_to_style_preset(row: &sqlx::sqlite::SqliteRow) -> Result<StylePreset> {
+    Ok(StylePreset {
+        id: row.try_get::<String, _>("id")?.parse()?,
+        project_id: row.try_get::<String, _>("project_id")?.parse()?,
+        name: row.try_get("name")?,
+        target_kinds: serde_json::from_str(&row.try_get::<String, _>("target_kinds")?)?,
+        style: serde_json::from_str(&row.try_get::<String, _>("style")?)?,
+        created_at: chrono::DateTime::parse_from_rfc3339(
+            row.try_get::<String, _>("created_at")?.as_str(),
+        )?
+        .with_timezone(&chrono::Utc),
+        modified_at: chrono::DateTime::parse_from_rfc3339(
+            row.try_get::<String, _>("modified_at")?.as_str(),
+        )?
+        .with_timezone(&chrono::Utc),
+    })
+}
+
+fn row_to_glossary_term(row: &sqlx::sqlite::SqliteRow) -> Result<GlossaryTerm> {
+    Ok(GlossaryTerm {
+        id: row.try_get::<String, _>("id")?.parse()?,
+        project_id: row.try_get::<String, _>("project_id")?.parse()?,
+        term: row.try_get("term")?,
+        approved: row.try_get("approved")?,
+        variants: serde_json::from_str(&row.try_get::<String, _>("variants")?)?,
+        case_sensitive: row.try_get("case_sensitive")?,
+        created_at: chrono::DateTime::parse_from_rfc3339(
+            row.try_get::<String, _>("created_at")?.as_str(),
+        )?
+        .with_timezone(&chrono::Utc),
+        modified_at: chrono::DateTime::parse_from_rfc3339(
+            row.try_get::<String, _>("modified_at")?.as_str(),
+        )?
+        .with_timezone(&chrono::Utc),
+    })
+}
+
 fn row_to_subsystem_knowledge(row: &sqlx::sqlite::SqliteRow) -> Result<SubsystemKnowledgePage> {
     Ok(SubsystemKnowledgePage {
         id: row.try_get::<String, _>("id")?.parse()?,
@@ -1334,6 +4943,7 @@ fn row_to_subsystem_knowledge(row: &sqlx::sqlite::SqliteRow) -> Result<Subsystem
             row.try_get::<String, _>("updated_at")?.as_str(),
         )?
         .with_timezone(&chrono::Utc),
+        stale_links: row.try_get::<i64, _>("stale_links").unwrap_or(0) != 0,
     })
 }
 
@@ -1364,6 +4974,35 @@ fn row_to_subsystem_activity(row: &sqlx::sqlite::SqliteRow) -> Result<SubsystemA
     })
 }
 
+fn row_to_presence(row: &sqlx::sqlite::SqliteRow) -> Result<Presence> {
+    Ok(Presence {
+        machine: row.try_get("machine")?,
+        user: row.try_get("user")?,
+        read_only_fallback: row.try_get::<i64, _>("read_only_fallback")? != 0,
+        started_at: parse_utc(&row.try_get::<String, _>("started_at")?)?,
+        last_seen: parse_utc(&row.try_get::<String, _>("last_seen")?)?,
+    })
+}
+
+fn parse_utc(s: &str) -> Result<chrono::DateTime<Utc>> {
+    Ok(chrono::DateTime::parse_from_rfc3339(s)?.with_timezone(&Utc))
+}
+
+fn row_to_layout_session(row: &sqlx::sqlite::SqliteRow) -> Result<LayoutSession> {
+    Ok(LayoutSession {
+        id: row.try_get::<String, _>("id")?.parse()?,
+        diagram_id: row.try_get::<String, _>("diagram_id")?.parse()?,
+        status: row.try_get::<String, _>("status")?.parse()?,
+        element_count: row.try_get::<Option<i64>, _>("element_count")?.unwrap_or(0),
+        started_at: parse_utc(&row.try_get::<String, _>("started_at")?)?,
+        ended_at: row
+            .try_get::<Option<String>, _>("ended_at")?
+            .as_deref()
+            .map(parse_utc)
+            .transpose()?,
+    })
+}
+
 fn row_to_document_section(row: &sqlx::sqlite::SqliteRow) -> Result<DocumentSection> {
     let section_type_str: String = row.try_get("section_type")?;
     let section_type = section_type_str.parse::<SectionType>().unwrap_or_default();
@@ -1383,6 +5022,11 @@ fn row_to_document_section(row: &sqlx::sqlite::SqliteRow) -> Result<DocumentSect
             row.try_get::<String, _>("created_at")?.as_str(),
         )?
         .with_timezone(&chrono::Utc),
+        parent_section_id: row
+            .try_get::<Option<String>, _>("parent_section_id")?
+            .map(|id| id.parse())
+            .transpose()?,
+        section_level: row.try_get("section_level")?,
     })
 }
 
@@ -1394,12 +5038,12 @@ fn row_to_requirement_snapshot(row: &sqlx::sqlite::SqliteRow) -> Result<Requirem
             .try_get::<Option<String>, _>("req_id")?
             .unwrap_or_default(),
         name: row.try_get::<String, _>("name").unwrap_or_default(),
-        text: row
-            .try_get::<Option<String>, _>("req_text")?
-            .unwrap_or_default(),
-        rationale: row
-            .try_get::<Option<String>, _>("req_rationale")?
-            .unwrap_or_default(),
+        text: normalize_requirement_text(
+            row.try_get::<Option<String>, _>("req_text")?.as_deref(),
+        ),
+        rationale: normalize_requirement_text(
+            row.try_get::<Option<String>, _>("req_rationale")?.as_deref(),
+        ),
         priority: row
             .try_get::<Option<String>, _>("req_priority")?
             .unwrap_or_default(),
@@ -1409,6 +5053,9 @@ fn row_to_requirement_snapshot(row: &sqlx::sqlite::SqliteRow) -> Result<Requirem
         verification_method: row
             .try_get::<Option<String>, _>("req_verification_method")?
             .unwrap_or_default(),
+        classification: row
+            .try_get::<Option<String>, _>("req_classification")?
+            .unwrap_or_default(),
         source: row
             .try_get::<Option<String>, _>("req_source")?
             .unwrap_or_default(),
@@ -1416,6 +5063,7 @@ fn row_to_requirement_snapshot(row: &sqlx::sqlite::SqliteRow) -> Result<Requirem
             .try_get::<Option<String>, _>("req_allocations")?
             .as_deref()
             .and_then(|raw| serde_json::from_str::<Vec<String>>(raw).ok())
+            .map(normalize_allocations)
             .unwrap_or_default(),
         description: row.try_get::<String, _>("description").unwrap_or_default(),
     })
@@ -1438,42 +5086,76 @@ fn row_to_requirement_history(row: &sqlx::sqlite::SqliteRow) -> Result<Requireme
     })
 }
 
-fn parse_node_kind(s: &str) -> Result<NodeKind> {
-    match s {
-        "requirement" => Ok(NodeKind::Requirement),
-        "block" => Ok(NodeKind::Block),
-        "interface" => Ok(NodeKind::Interface),
-        "port" => Ok(NodeKind::Port),
-        "use_case" => Ok(NodeKind::UseCase),
-        "actor" => Ok(NodeKind::Actor),
-        "test_case" => Ok(NodeKind::TestCase),
-        "stakeholder" => Ok(NodeKind::Stakeholder),
-        "function" => Ok(NodeKind::Function),
-        "external" => Ok(NodeKind::External),
-        "value_type" => Ok(NodeKind::ValueType),
-        "constraint_block" => Ok(NodeKind::ConstraintBlock),
-        "state" => Ok(NodeKind::State),
-        other => anyhow::bail!("unknown node kind: {other}"),
-    }
-}
-
-fn parse_edge_kind(s: &str) -> Result<EdgeKind> {
-    match s {
-        "satisfies" => Ok(EdgeKind::Satisfies),
-        "refines" => Ok(EdgeKind::Refines),
-        "allocates" => Ok(EdgeKind::Allocates),
-        "realizes" => Ok(EdgeKind::Realizes),
-        "traces" => Ok(EdgeKind::Traces),
-        "verifies" => Ok(EdgeKind::Verifies),
-        "connects" => Ok(EdgeKind::Connects),
-        "composes" => Ok(EdgeKind::Composes),
-        "specializes" => Ok(EdgeKind::Specializes),
-        "derives" => Ok(EdgeKind::Derives),
-        "blocks" => Ok(EdgeKind::Blocks),
-        "transition" => Ok(EdgeKind::Transition),
-        "binding_connector" => Ok(EdgeKind::BindingConnector),
-        other => anyhow::bail!("unknown edge kind: {other}"),
+fn row_to_node_summary(row: &sqlx::sqlite::SqliteRow) -> Result<NodeSummary> {
+    let kind = parse_node_kind(&row.try_get::<String, _>("kind")?)?;
+    let is_requirement = kind == NodeKind::Requirement;
+    Ok(NodeSummary {
+        id: row.try_get::<String, _>("id")?.parse()?,
+        kind,
+        name: row.try_get("name")?,
+        req_id: row.try_get("req_id")?,
+        status: is_requirement
+            .then(|| row.try_get::<Option<String>, _>("req_status"))
+            .transpose()?
+            .map(|s| parse_req_status(s.as_deref())),
+        archived: row.try_get::<i64, _>("archived")? != 0,
+    })
+}
+
+fn is_node_kind(s: &str) -> bool {
+    parse_node_kind(s).is_ok()
+}
+
+fn is_edge_kind(s: &str) -> bool {
+    parse_edge_kind(s).is_ok()
+}
+
+fn is_diagram_kind(s: &str) -> bool {
+    parse_diagram_kind(s).is_ok()
+}
+
+/// Map listing rows, skipping any whose `kind` fails `known` — rows written
+/// by a newer app version — so one of them doesn't fail the whole listing.
+/// `list_unrecognized_rows` reports what was skipped.
+fn map_known_kinds<T>(
+    rows: &[sqlx::sqlite::SqliteRow],
+    known: fn(&str) -> bool,
+    map: fn(&sqlx::sqlite::SqliteRow) -> Result<T>,
+) -> Result<Vec<T>> {
+    rows.iter()
+        .filter(|row| !row.try_get::<String, _>("kind").is_ok_and(|k| !known(&k)))
+        .map(map)
+        .collect()
+}
+
+/// Every column of `row` as JSON, for passing through rows this build can't
+/// interpret. Blob columns come out as null.
+fn row_to_raw_json(row: &sqlx::sqlite::SqliteRow) -> serde_json::Value {
+    use sqlx::Column;
+
+    let mut map = serde_json::Map::new();
+    for col in row.columns() {
+        let i = col.ordinal();
+        let value = if let Ok(v) = row.try_get::<Option<String>, _>(i) {
+            serde_json::json!(v)
+        } else if let Ok(v) = row.try_get::<Option<i64>, _>(i) {
+            serde_json::json!(v)
+        } else if let Ok(v) = row.try_get::<Option<f64>, _>(i) {
+            serde_json::json!(v)
+        } else {
+            serde_json::Value::Null
+        };
+        map.insert(col.name().to_string(), value);
     }
+    serde_json::Value::Object(map)
+}
+
+pub(crate) fn parse_node_kind(s: &str) -> Result<NodeKind> {
+    s.parse().map_err(anyhow::Error::msg)
+}
+
+pub(crate) fn parse_edge_kind(s: &str) -> Result<EdgeKind> {
+    s.parse().map_err(anyhow::Error::msg)
 }
 
 fn parse_diagram_kind(s: &str) -> Result<DiagramKind> {
@@ -1501,7 +5183,7 @@ fn diagram_kind_str(k: &DiagramKind) -> &'static str {
 
 // ── Node data flatten/build ───────────────────────────────────────────────────
 
-fn requirement_snapshot_from_node(node: &Node) -> Option<RequirementSnapshot> {
+pub(crate) fn requirement_snapshot_from_node(node: &Node) -> Option<RequirementSnapshot> {
     let req = match &node.data {
         NodeData::Requirement(r) => r,
         _ => return None,
@@ -1510,8 +5192,8 @@ fn requirement_snapshot_from_node(node: &Node) -> Option<RequirementSnapshot> {
     Some(RequirementSnapshot {
         req_id: req.req_id.clone().unwrap_or_default(),
         name: node.name.clone(),
-        text: req.text.clone().unwrap_or_default(),
-        rationale: req.rationale.clone().unwrap_or_default(),
+        text: normalize_requirement_text(req.text.as_deref()),
+        rationale: normalize_requirement_text(req.rationale.as_deref()),
         priority: format!("{:?}", req.priority).to_lowercase(),
         status: format!("{:?}", req.status).to_lowercase(),
         verification_method: req
@@ -1519,15 +5201,40 @@ fn requirement_snapshot_from_node(node: &Node) -> Option<RequirementSnapshot> {
             .as_ref()
             .map(|v| format!("{v:?}").to_lowercase())
             .unwrap_or_default(),
+        classification: req.classification.clone().unwrap_or_default(),
         source: req.source.clone().unwrap_or_default(),
-        allocations: req.allocations.clone().unwrap_or_default(),
+        allocations: req.allocations.clone().map(normalize_allocations).unwrap_or_default(),
         description: node.description.clone(),
     })
 }
 
+/// Allocation order is cosmetic — trim, sort and dedup so reordering tags
+/// doesn't register as a requirement change in history. Also applied when
+/// writing the column, so stored allocations are already in this form.
+fn normalize_allocations(allocations: Vec<String>) -> Vec<String> {
+    let mut allocations: Vec<String> = allocations
+        .into_iter()
+        .map(|a| a.trim().to_string())
+        .filter(|a| !a.is_empty())
+        .collect();
+    allocations.sort();
+    allocations.dedup();
+    allocations
+}
+
+/// Leading and trailing whitespace in requirement text and rationale is an
+/// editor artifact, not a change worth a history row.
+fn normalize_requirement_text(text: Option<&str>) -> String {
+    text.map(str::trim).unwrap_or_default().to_string()
+}
+
 fn extract_history_actor(node: &Node) -> String {
-    node.meta
-        .get("actor")
+    meta_actor(&node.meta)
+}
+
+/// The "actor" a node or edge was written with, or "system".
+fn meta_actor(meta: &std::collections::HashMap<String, serde_json::Value>) -> String {
+    meta.get("actor")
         .and_then(|v| v.as_str())
         .filter(|v| !v.trim().is_empty())
         .unwrap_or("system")
@@ -1565,9 +5272,10 @@ fn extract_history_source(node: &Node) -> String {
 fn flatten_node_data(
     data: &NodeData,
 ) -> (
-    // req fields (8)
+    // req fields (9)
     Option<String>, Option<String>, Option<String>, Option<String>,
     Option<String>, Option<String>, Option<String>, Option<String>,
+    Option<String>,
     // block fields (2)
     Option<i64>, Option<String>,
     // port fields (4)
@@ -1585,8 +5293,8 @@ fn flatten_node_data(
     // state fields (4)
     Option<String>, Option<String>, Option<String>, Option<String>,
 ) {
-    let none29 = || (
-        None, None, None, None, None, None, None, None,
+    let none30 = || (
+        None, None, None, None, None, None, None, None, None,
         None, None,
         None, None, None, None,
         None,
@@ -1605,12 +5313,13 @@ fn flatten_node_data(
             Some(format!("{:?}", r.priority).to_lowercase()),
             Some(format!("{:?}", r.status).to_lowercase()),
             r.source.clone(),
-            r.allocations
-                .as_ref()
-                .map(|v| serde_json::to_string(v).unwrap_or_else(|_| "[]".to_string())),
+            r.allocations.clone().map(normalize_allocations).map(|v| {
+                serde_json::to_string(&v).unwrap_or_else(|_| "[]".to_string())
+            }),
             r.verification_method
                 .as_ref()
                 .map(|v| format!("{v:?}").to_lowercase()),
+            r.classification.clone(),
             None, None, None, None, None, None, None,
             None, None, None,
             None, None,
@@ -1619,7 +5328,7 @@ fn flatten_node_data(
             None, None, None, None,
         ),
         NodeData::Block(b) => (
-            None, None, None, None, None, None, None, None,
+            None, None, None, None, None, None, None, None, None,
             Some(b.is_abstract as i64),
             b.multiplicity.clone(),
             None, None, None, None,
@@ -1632,7 +5341,7 @@ fn flatten_node_data(
             None, None, None, None,
         ),
         NodeData::Port(p) => (
-            None, None, None, None, None, None, None, None,
+            None, None, None, None, None, None, None, None, None,
             None, None,
             Some(format!("{:?}", p.direction).to_lowercase()),
             p.type_ref.map(|u| u.to_string()),
@@ -1646,7 +5355,7 @@ fn flatten_node_data(
             None, None, None, None,
         ),
         NodeData::UseCase(u) => (
-            None, None, None, None, None, None, None, None,
+            None, None, None, None, None, None, None, None, None,
             None, None,
             None, None, None, None,
             Some(format!("{:?}", u.level).to_lowercase()),
@@ -1657,7 +5366,7 @@ fn flatten_node_data(
             None, None, None, None,
         ),
         NodeData::TestCase(t) => (
-            None, None, None, None, None, None, None, None,
+            None, None, None, None, None, None, None, None, None,
             None, None,
             None, None, None, None,
             None,
@@ -1670,7 +5379,7 @@ fn flatten_node_data(
             None, None, None, None,
         ),
         NodeData::ValueType(v) => (
-            None, None, None, None, None, None, None, None,
+            None, None, None, None, None, None, None, None, None,
             None, None,
             None, None, None, None,
             None,
@@ -1683,7 +5392,7 @@ fn flatten_node_data(
             None, None, None, None,
         ),
         NodeData::ConstraintBlock(c) => (
-            None, None, None, None, None, None, None, None,
+            None, None, None, None, None, None, None, None, None,
             None, None,
             None, None, None, None,
             None,
@@ -1695,7 +5404,7 @@ fn flatten_node_data(
             None, None, None, None,
         ),
         NodeData::State(s) => (
-            None, None, None, None, None, None, None, None,
+            None, None, None, None, None, None, None, None, None,
             None, None,
             None, None, None, None,
             None,
@@ -1708,7 +5417,7 @@ fn flatten_node_data(
             s.exit_action.clone(),
             s.do_activity.clone(),
         ),
-        _ => none29(),
+        _ => none30(),
     }
 }
 
@@ -1733,6 +5442,7 @@ fn build_node_data(kind: &NodeKind, row: &sqlx::sqlite::SqliteRow) -> Result<Nod
                 .as_deref()
                 .map(parse_verification_method)
                 .transpose()?,
+            classification: row.try_get("req_classification")?,
         })),
         NodeKind::Block => Ok(NodeData::Block(BlockData {
             is_abstract: row
@@ -1880,6 +5590,108 @@ fn row_to_baseline(row: &sqlx::sqlite::SqliteRow) -> Result<ModelBaseline> {
     })
 }
 
+/// Rows with `id` and `name` columns.
+fn rows_to_usage_refs(rows: &[sqlx::sqlite::SqliteRow]) -> Result<Vec<UsageRef>> {
+    rows.iter()
+        .map(|row| {
+            Ok(UsageRef {
+                id: row.try_get::<String, _>("id")?.parse()?,
+                name: row.try_get("name")?,
+            })
+        })
+        .collect()
+}
+
+fn row_to_suspect_link(row: &sqlx::sqlite::SqliteRow) -> Result<SuspectLink> {
+    Ok(SuspectLink {
+        id: Uuid::parse_str(row.try_get("id")?)?,
+        project_id: Uuid::parse_str(row.try_get("project_id")?)?,
+        edge_id: Uuid::parse_str(row.try_get("edge_id")?)?,
+        source_node_id: Uuid::parse_str(row.try_get("source_node_id")?)?,
+        target_node_id: Uuid::parse_str(row.try_get("target_node_id")?)?,
+        flagged_at: chrono::DateTime::parse_from_rfc3339(row.try_get("flagged_at")?)?
+            .with_timezone(&Utc),
+        flagged_reason: row.try_get("flagged_reason")?,
+        resolved_at: row
+            .try_get::<Option<String>, _>("resolved_at")?
+            .map(|s| chrono::DateTime::parse_from_rfc3339(&s).map(|d| d.with_timezone(&Utc)))
+            .transpose()?,
+        resolved_by: row.try_get("resolved_by")?,
+    })
+}
+
+fn row_to_project_template(row: &sqlx::sqlite::SqliteRow) -> Result<ProjectTemplate> {
+    Ok(ProjectTemplate {
+        id: Uuid::parse_str(row.try_get::<String, _>("id")?.as_str())?,
+        name: row.try_get("name")?,
+        description: row.try_get("description")?,
+        builtin: false,
+        include: serde_json::from_str(&row.try_get::<String, _>("include")?)?,
+        content: serde_json::from_str(&row.try_get::<String, _>("content")?)?,
+        created_at: chrono::DateTime::parse_from_rfc3339(
+            row.try_get::<String, _>("created_at")?.as_str(),
+        )?
+        .with_timezone(&Utc),
+    })
+}
+
+fn row_to_extraction_review_state(row: &sqlx::sqlite::SqliteRow) -> Result<ExtractionReviewState> {
+    let timestamp = |col: &str| -> Result<chrono::DateTime<Utc>> {
+        Ok(chrono::DateTime::parse_from_rfc3339(row.try_get::<String, _>(col)?.as_str())?
+            .with_timezone(&Utc))
+    };
+    Ok(ExtractionReviewState {
+        session_id: row.try_get("session_id")?,
+        project_id: Uuid::parse_str(row.try_get::<String, _>("project_id")?.as_str())?,
+        document_id: row
+            .try_get::<Option<String>, _>("document_id")?
+            .map(|id| Uuid::parse_str(&id))
+            .transpose()?,
+        source_name: row.try_get("source_name")?,
+        results: serde_json::from_str(&row.try_get::<String, _>("results")?)?,
+        items: serde_json::from_str(&row.try_get::<String, _>("items")?)?,
+        created_at: timestamp("created_at")?,
+        updated_at: timestamp("updated_at")?,
+    })
+}
+
+fn row_to_external_item(row: &sqlx::sqlite::SqliteRow) -> Result<ExternalItem> {
+    Ok(ExternalItem {
+        id: Uuid::parse_str(row.try_get::<String, _>("id")?.as_str())?,
+        project_id: Uuid::parse_str(row.try_get::<String, _>("project_id")?.as_str())?,
+        source: parse_external_source(row.try_get::<String, _>("source")?.as_str()),
+        external_key: row.try_get("external_key")?,
+        title: row.try_get("title")?,
+        url: row.try_get("url")?,
+        status: row.try_get("status")?,
+        synced_at: chrono::DateTime::parse_from_rfc3339(
+            row.try_get::<String, _>("synced_at")?.as_str(),
+        )?
+        .with_timezone(&Utc),
+    })
+}
+
+fn row_to_linked_external_item(row: &sqlx::sqlite::SqliteRow) -> Result<LinkedExternalItem> {
+    Ok(LinkedExternalItem {
+        node_id: Uuid::parse_str(row.try_get::<String, _>("node_id")?.as_str())?,
+        item: row_to_external_item(row)?,
+    })
+}
+
+fn parse_external_source(s: &str) -> ExternalSource {
+    match s {
+        "jira" => ExternalSource::Jira,
+        _ => ExternalSource::Generic,
+    }
+}
+
+fn external_source_str(source: ExternalSource) -> &'static str {
+    match source {
+        ExternalSource::Jira => "jira",
+        ExternalSource::Generic => "generic",
+    }
+}
+
 fn row_to_simulation_result(row: &sqlx::sqlite::SqliteRow) -> Result<SimulationResult> {
     Ok(SimulationResult {
         id: row.try_get::<String, _>("id")?.parse()?,
@@ -1903,3 +5715,47 @@ fn row_to_simulation_result(row: &sqlx::sqlite::SqliteRow) -> Result<SimulationR
         .unwrap_or_default(),
     })
 }
+
+fn row_to_audit_entry(row: &sqlx::sqlite::SqliteRow) -> Result<AuditEntry> {
+    Ok(AuditEntry {
+        id: row.try_get::<String, _>("id")?.parse()?,
+        project_id: row.try_get::<String, _>("project_id")?.parse()?,
+        entity_type: row.try_get("entity_type")?,
+        entity_id: row.try_get::<String, _>("entity_id")?.parse()?,
+        action: row.try_get("action")?,
+        actor: row.try_get("actor")?,
+        summary: row.try_get("summary")?,
+        changed_at: chrono::DateTime::parse_from_rfc3339(
+            row.try_get::<String, _>("changed_at")?.as_str(),
+        )?
+        .with_timezone(&Utc),
+    })
+}
+
+/// Versions recorded in `_sqlx_migrations`. Empty for a fresh database.
+async fn applied_migrations(pool: &SqlitePool) -> Result<Vec<i64>> {
+    let exists: Option<String> = sqlx::query_scalar(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations'",
+    )
+    .fetch_optional(pool)
+    .await?;
+    if exists.is_none() {
+        return Ok(Vec::new());
+    }
+    Ok(sqlx::query_scalar("SELECT version FROM _sqlx_migrations")
+        .fetch_all(pool)
+        .await?)
+}
+
+/// "0.10.2" → [0, 10, 2], for ordering versions; non-numeric parts count as 0.
+fn version_parts(v: &str) -> Vec<u64> {
+    v.trim()
+        .trim_start_matches('v')
+        .split(['.', '-', '+'])
+        .take(3)
+        .map(|p| p.parse().unwrap_or(0))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests;
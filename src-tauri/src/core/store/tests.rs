@@ -0,0 +1,886 @@
+use super::*;
+use crate::test_support::{self as fixtures, seed_example_project, seed_small_project};
+use serde_json::json;
+
+/// Data for `kind` with every field set away from its default, so a column
+/// the row mapping drops shows up as a difference.
+fn populated_data(kind: &NodeKind, type_ref: Uuid) -> NodeData {
+    match kind {
+        NodeKind::Requirement => NodeData::Requirement(RequirementData {
+            req_id: Some("REQ-042".into()),
+            text: Some("The unit shall survive a 15 g shock.".into()),
+            rationale: Some("Launch loads".into()),
+            priority: RequirementPriority::Shall,
+            status: RequirementStatus::Approved,
+            source: Some("SOW 3.2".into()),
+            // Stored sorted and de-duplicated.
+            allocations: Some(vec!["Avionics".into(), "Structure".into()]),
+            verification_method: Some(VerificationMethod::Test),
+            classification: Some("performance".into()),
+        }),
+        NodeKind::Block => NodeData::Block(BlockData {
+            is_abstract: true,
+            multiplicity: Some("0..2".into()),
+            sim_params: Some(SimParams {
+                processing_time_ms: Some(12.5),
+                failure_rate: Some(0.001),
+                queue_capacity: Some(8),
+                throughput_per_sec: Some(100.0),
+                input_signal_type: Some("power".into()),
+                output_signal_type: Some("data".into()),
+            }),
+            sim_script: Some("emit(input)".into()),
+        }),
+        NodeKind::Port => NodeData::Port(PortData {
+            direction: PortDirection::Out,
+            type_ref: Some(type_ref),
+            type_name: Some("Voltage".into()),
+            multiplicity: Some("1..*".into()),
+        }),
+        NodeKind::UseCase => NodeData::UseCase(UseCaseData {
+            level: UseCaseLevel::Subfunction,
+        }),
+        NodeKind::TestCase => NodeData::TestCase(TestCaseData {
+            procedure: Some("Apply power".into()),
+            expected: Some("28 V at the bus".into()),
+            status: TestStatus::Fail,
+        }),
+        NodeKind::ValueType => NodeData::ValueType(ValueTypeData {
+            base_type: Some("Real".into()),
+            unit: Some("V".into()),
+            constraint: Some("0 <= x <= 32".into()),
+        }),
+        NodeKind::ConstraintBlock => NodeData::ConstraintBlock(ConstraintBlockData {
+            expression: Some("P = V * I".into()),
+            parameters: Some(vec!["P".into(), "V".into(), "I".into()]),
+        }),
+        NodeKind::State => NodeData::State(StateData {
+            pseudo_kind: Some("choice".into()),
+            entry_action: Some("arm()".into()),
+            exit_action: Some("disarm()".into()),
+            do_activity: Some("monitor()".into()),
+        }),
+        other => fixtures::default_data(other),
+    }
+}
+
+fn as_json<T: serde::Serialize>(value: &T) -> serde_json::Value {
+    serde_json::to_value(value).unwrap()
+}
+
+#[tokio::test]
+async fn node_round_trips_for_every_kind() {
+    let store = fixtures::store().await;
+    let project = fixtures::project("Round trip");
+    store.create_project(&project, "tester").await.unwrap();
+    let type_ref = Uuid::new_v4();
+
+    for kind in NodeKind::ALL {
+        let mut node = fixtures::node(project.id, kind.clone(), &format!("A {kind}"));
+        node.description = format!("Every field of a {kind}");
+        node.data = populated_data(&kind, type_ref);
+        node.meta.insert("origin".into(), json!({ "sheet": 2 }));
+        store.upsert_node(&node).await.unwrap();
+
+        let stored = store.get_node(node.id).await.unwrap().expect("stored node");
+        assert_eq!(
+            as_json(&stored),
+            as_json(&node),
+            "{kind} did not round-trip"
+        );
+    }
+}
+
+#[tokio::test]
+async fn requirement_history_skips_no_op_saves() {
+    let store = fixtures::store().await;
+    let seed = seed_small_project(&store).await;
+    let mut req = seed.requirement.clone();
+    let history = |store: Store, id: Uuid| async move {
+        store.list_requirement_history(id, 50).await.unwrap()
+    };
+
+    assert_eq!(
+        history(store.clone(), req.id).await.len(),
+        1,
+        "creation is recorded"
+    );
+
+    req.modified_at = Utc::now();
+    store.upsert_node(&req).await.unwrap();
+    assert_eq!(
+        history(store.clone(), req.id).await.len(),
+        1,
+        "an unchanged save is not"
+    );
+
+    if let NodeData::Requirement(r) = &mut req.data {
+        r.text = Some("The system shall distribute 28 V and 5 V power.".into());
+    }
+    req.modified_at = Utc::now() + chrono::Duration::seconds(1);
+    store.upsert_node(&req).await.unwrap();
+    let entries = history(store.clone(), req.id).await;
+    assert_eq!(entries.len(), 2);
+    assert_eq!(
+        entries[0].prev.text,
+        "The system shall distribute 28 V power to every subsystem."
+    );
+    assert_eq!(
+        entries[0].next.text,
+        "The system shall distribute 28 V and 5 V power."
+    );
+}
+
+#[tokio::test]
+async fn requirement_history_ignores_allocation_order_and_trailing_whitespace() {
+    let store = fixtures::store().await;
+    let seed = seed_small_project(&store).await;
+    let mut req = seed.requirement.clone();
+    let mut save = |edit: fn(&mut RequirementData)| {
+        if let NodeData::Requirement(r) = &mut req.data {
+            edit(r);
+        }
+        req.modified_at += chrono::Duration::seconds(1);
+        let (store, req) = (store.clone(), req.clone());
+        async move {
+            store.upsert_node(&req).await.unwrap();
+            store
+                .list_requirement_history(req.id, 50)
+                .await
+                .unwrap()
+                .len()
+        }
+    };
+
+    let before = save(|r| r.allocations = Some(vec!["Power".into(), "Avionics".into()])).await;
+    assert_eq!(
+        save(|r| r.allocations = Some(vec!["Avionics".into(), " Power".into()])).await,
+        before,
+        "reordered allocations are not a change"
+    );
+    assert_eq!(
+        save(|r| {
+            r.allocations = Some(vec!["Avionics".into(), "Power".into(), "Thermal".into()])
+        })
+        .await,
+        before + 1,
+        "an added allocation is"
+    );
+    assert_eq!(
+        save(|r| r.text = r.text.take().map(|t| format!("{t}  \n"))).await,
+        before + 1,
+        "trailing whitespace is not"
+    );
+}
+
+#[tokio::test]
+async fn requirement_history_ignores_reordered_allocations_read_back_from_the_store() {
+    let store = fixtures::store().await;
+    let seed = seed_small_project(&store).await;
+    let mut req = seed.requirement.clone();
+    if let NodeData::Requirement(r) = &mut req.data {
+        r.allocations = Some(vec!["Structure".into(), "Avionics".into()]);
+    }
+    req.modified_at = Utc::now() + chrono::Duration::seconds(1);
+    store.upsert_node(&req).await.unwrap();
+    let recorded = store
+        .list_requirement_history(req.id, 50)
+        .await
+        .unwrap()
+        .len();
+
+    let mut saved = store.get_node(req.id).await.unwrap().unwrap();
+    if let NodeData::Requirement(r) = &mut saved.data {
+        r.allocations = Some(vec![
+            "Structure".into(),
+            "Avionics".into(),
+            "Avionics".into(),
+        ]);
+    }
+    saved.modified_at += chrono::Duration::seconds(1);
+    store.upsert_node(&saved).await.unwrap();
+    assert_eq!(
+        store
+            .list_requirement_history(req.id, 50)
+            .await
+            .unwrap()
+            .len(),
+        recorded
+    );
+}
+
+#[tokio::test]
+async fn suspect_links_flag_once_and_resolve() {
+    let store = fixtures::store().await;
+    let seed = seed_small_project(&store).await;
+    let (project_id, block) = (seed.project.id, &seed.blocks[0]);
+
+    store
+        .flag_suspect_links(project_id, block.id, "name")
+        .await
+        .unwrap();
+    store
+        .flag_suspect_links(project_id, block.id, "name")
+        .await
+        .unwrap();
+    let open = store.get_suspect_links(project_id).await.unwrap();
+    assert_eq!(open.len(), 1, "an unresolved flag is not repeated");
+    assert_eq!(open[0].edge_id, seed.edges[0].id);
+    assert_eq!(open[0].target_node_id, seed.requirement.id);
+
+    store
+        .resolve_suspect_link(open[0].id, "alice")
+        .await
+        .unwrap();
+    assert!(store
+        .get_suspect_links(project_id)
+        .await
+        .unwrap()
+        .is_empty());
+    let resolved = store.get_suspect_link(open[0].id).await.unwrap().unwrap();
+    assert!(resolved.resolved_at.is_some());
+    assert_eq!(resolved.resolved_by.as_deref(), Some("alice"));
+}
+
+#[tokio::test]
+async fn comment_threads_keep_replies_and_resolution() {
+    let store = fixtures::store().await;
+    let seed = seed_small_project(&store).await;
+    let (project_id, node_id) = (seed.project.id, seed.requirement.id);
+
+    let root = store
+        .add_req_comment(project_id, node_id, None, "alice", "Is 28 V right?")
+        .await
+        .unwrap();
+    let reply = store
+        .add_req_comment(
+            project_id,
+            node_id,
+            Some(root.id),
+            "bob",
+            "Yes, per the ICD.",
+        )
+        .await
+        .unwrap();
+    store.resolve_req_comment(root.id, "alice").await.unwrap();
+
+    let thread = store.get_req_comments(node_id).await.unwrap();
+    assert_eq!(thread.len(), 2);
+    let stored_root = thread.iter().find(|c| c.id == root.id).unwrap();
+    let stored_reply = thread.iter().find(|c| c.id == reply.id).unwrap();
+    assert_eq!(stored_reply.parent_id, Some(root.id));
+    assert_eq!(stored_reply.author, "bob");
+    assert!(stored_root.resolved_at.is_some());
+    assert!(stored_reply.resolved_at.is_none());
+
+    let open = store
+        .get_comment_counts_for_project(project_id)
+        .await
+        .unwrap();
+    assert_eq!(open.get(&node_id.to_string()), Some(&1));
+}
+
+#[tokio::test]
+async fn review_session_lifecycle() {
+    let store = fixtures::store().await;
+    let seed = seed_small_project(&store).await;
+    let project_id = seed.project.id;
+
+    let session = store
+        .create_review_session(project_id, "PDR", Some("Power"), vec![seed.requirement.id])
+        .await
+        .unwrap();
+    assert_eq!(session.status, ReviewStatus::Open);
+    assert_eq!(session.items.len(), 1);
+
+    store
+        .set_review_verdict(session.items[0].id, "approved", "alice", Some("Looks good"))
+        .await
+        .unwrap();
+    store
+        .close_review_session(session.id, "approved")
+        .await
+        .unwrap();
+
+    let sessions = store.list_review_sessions(project_id).await.unwrap();
+    assert_eq!(sessions.len(), 1);
+    let stored = &sessions[0];
+    assert_eq!(stored.status, ReviewStatus::Approved);
+    assert!(stored.closed_at.is_some());
+    assert_eq!(stored.items[0].node_id, seed.requirement.id);
+    assert_eq!(stored.items[0].verdict.as_deref(), Some("approved"));
+    assert_eq!(stored.items[0].verdict_by.as_deref(), Some("alice"));
+    assert_eq!(stored.items[0].verdict_note.as_deref(), Some("Looks good"));
+}
+
+#[tokio::test]
+async fn baseline_create_and_get() {
+    let store = fixtures::store().await;
+    let seed = seed_small_project(&store).await;
+    let baseline = ModelBaseline {
+        id: Uuid::new_v4(),
+        project_id: seed.project.id,
+        name: "PDR".into(),
+        description: "Before review".into(),
+        created_by: "alice".into(),
+        created_at: Utc::now(),
+        snapshot: json!({
+            "nodes": [as_json(&seed.requirement)],
+            "edges": [as_json(&seed.edges[0])],
+        }),
+    };
+    store.create_baseline(&baseline).await.unwrap();
+
+    let stored = store
+        .get_baseline(baseline.id)
+        .await
+        .unwrap()
+        .expect("stored baseline");
+    assert_eq!(as_json(&stored), as_json(&baseline));
+    let listed = store.list_baselines(seed.project.id).await.unwrap();
+    assert_eq!(
+        listed.iter().map(|b| b.id).collect::<Vec<_>>(),
+        vec![baseline.id]
+    );
+    assert!(store.get_baseline(Uuid::new_v4()).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn settings_upsert_per_scope() {
+    let store = fixtures::store().await;
+    let project = fixtures::project("Settings");
+    store.create_project(&project, "tester").await.unwrap();
+    let scope = Some(project.id);
+
+    store.set_setting("ai.model", None, "global").await.unwrap();
+    store.set_setting("ai.model", scope, "first").await.unwrap();
+    store
+        .set_setting("ai.model", scope, "second")
+        .await
+        .unwrap();
+    assert_eq!(
+        store
+            .get_setting("ai.model", None)
+            .await
+            .unwrap()
+            .as_deref(),
+        Some("global")
+    );
+    assert_eq!(
+        store
+            .get_setting("ai.model", scope)
+            .await
+            .unwrap()
+            .as_deref(),
+        Some("second")
+    );
+    let listed = store.list_settings(scope, Some("ai.")).await.unwrap();
+    assert_eq!(listed.len(), 1, "a second write updates the row");
+
+    store
+        .set_settings(&[SettingEntry {
+            key: "ai.provider".into(),
+            project_id: scope,
+            value: "ollama".into(),
+            masked: false,
+        }])
+        .await
+        .unwrap();
+    let values = store
+        .get_settings(&["ai.model", "ai.provider", "ai.unset"], scope)
+        .await
+        .unwrap();
+    assert_eq!(values.len(), 2);
+    assert_eq!(values["ai.provider"], "ollama");
+
+    assert!(store.delete_setting("ai.model", scope).await.unwrap());
+    assert!(!store.delete_setting("ai.model", scope).await.unwrap());
+    assert_eq!(
+        store
+            .get_setting("ai.model", None)
+            .await
+            .unwrap()
+            .as_deref(),
+        Some("global")
+    );
+}
+
+#[tokio::test]
+async fn list_edges_matches_per_node_aggregation() {
+    let store = fixtures::store().await;
+    let seed = seed_small_project(&store).await;
+    let other = seed_small_project(&store).await;
+
+    let mut aggregated = Vec::new();
+    for node in store.list_nodes(seed.project.id).await.unwrap() {
+        aggregated.extend(store.edges_for_node(node.id).await.unwrap());
+    }
+    aggregated.sort_by_key(|e| e.id);
+    aggregated.dedup_by_key(|e| e.id);
+
+    let listed = store.list_edges(seed.project.id).await.unwrap();
+    assert_eq!(as_json(&listed), as_json(&aggregated));
+    assert_eq!(listed.len(), seed.edges.len());
+    assert!(listed
+        .iter()
+        .all(|e| other.edges.iter().all(|o| o.id != e.id)));
+}
+
+#[tokio::test]
+async fn native_json_reimport_remaps_taken_ids() {
+    use crate::core::export::{from_native_json, to_native_json};
+
+    let store = fixtures::store().await;
+    let seed = seed_small_project(&store).await;
+    let mut port = fixtures::node(seed.project.id, NodeKind::Port, "Power In");
+    port.data = NodeData::Port(PortData {
+        type_ref: Some(seed.blocks[0].id),
+        ..Default::default()
+    });
+    port.meta.insert("origin".into(), json!({ "sheet": 2 }));
+    store.upsert_node(&port).await.unwrap();
+    let nodes = store.list_nodes(seed.project.id).await.unwrap();
+    let mut edges = store.list_edges(seed.project.id).await.unwrap();
+    let dangling = fixtures::edge(seed.project.id, EdgeKind::Traces, port.id, Uuid::new_v4());
+    edges.push(dangling.clone());
+    let text = to_native_json(&seed.project, &nodes, &edges, &[]).unwrap();
+
+    let mut doc = from_native_json(&text).unwrap();
+    assert_eq!(doc.warnings.len(), 1, "{:?}", doc.warnings);
+    assert!(doc.warnings[0].contains(&dangling.id.to_string()));
+    let taken = store.taken_ids(&doc.ids()).await.unwrap();
+    assert_eq!(taken.len(), 1 + nodes.len() + seed.edges.len());
+
+    let mut project = doc.project.clone();
+    project.id = Uuid::new_v4();
+    doc.remap(project.id, &taken);
+    store
+        .import_model(&project, true, &doc.nodes, &doc.edges, "tester")
+        .await
+        .unwrap();
+
+    let imported = store.list_nodes(project.id).await.unwrap();
+    assert_eq!(imported.len(), nodes.len());
+    assert!(imported.iter().all(|n| !taken.contains(&n.id)));
+    let names: std::collections::HashMap<Uuid, &str> =
+        imported.iter().map(|n| (n.id, n.name.as_str())).collect();
+    let copied_port = imported.iter().find(|n| n.name == "Power In").unwrap();
+    assert_eq!(copied_port.meta["origin"], json!({ "sheet": 2 }));
+    let NodeData::Port(p) = &copied_port.data else {
+        panic!("not a port")
+    };
+    assert_eq!(names[&p.type_ref.unwrap()], "Power Distribution");
+
+    let copied_edges = store.list_edges(project.id).await.unwrap();
+    assert_eq!(copied_edges.len(), seed.edges.len());
+    for edge in &copied_edges {
+        assert!(names.contains_key(&edge.source_id) && names.contains_key(&edge.target_id));
+    }
+    // The original project is untouched.
+    assert_eq!(
+        store.list_nodes(seed.project.id).await.unwrap().len(),
+        nodes.len()
+    );
+}
+
+#[tokio::test]
+async fn native_json_import_records_the_original_creation_time() {
+    use crate::core::export::{from_native_json, to_native_json};
+
+    let store = fixtures::store().await;
+    let seed = seed_small_project(&store).await;
+    let mut reimported = fixtures::block(seed.project.id, "Reimported");
+    reimported.meta.insert(
+        ORIGINAL_CREATED_AT_META.into(),
+        json!("2019-03-01T08:00:00+00:00"),
+    );
+    store.upsert_node(&reimported).await.unwrap();
+    let nodes = store.list_nodes(seed.project.id).await.unwrap();
+    let edges = store.list_edges(seed.project.id).await.unwrap();
+    let text = to_native_json(&seed.project, &nodes, &edges, &[]).unwrap();
+
+    let mut doc = from_native_json(&text).unwrap();
+    let taken = store.taken_ids(&doc.ids()).await.unwrap();
+    let mut project = doc.project.clone();
+    project.id = Uuid::new_v4();
+    doc.remap(project.id, &taken);
+    doc.mark_imported();
+    store
+        .import_model(&project, true, &doc.nodes, &doc.edges, "tester")
+        .await
+        .unwrap();
+
+    let imported = store.list_nodes(project.id).await.unwrap();
+    assert_eq!(imported.len(), nodes.len());
+    for copy in &imported {
+        let original = nodes.iter().find(|n| n.name == copy.name).unwrap();
+        assert_eq!(copy.created_at, original.created_at);
+        let expected = original
+            .original_created_at()
+            .unwrap_or(original.created_at);
+        assert_eq!(copy.original_created_at(), Some(expected), "{}", copy.name);
+        assert_eq!(copy.created_origin(), CreatedOrigin::Imported);
+    }
+    let copy = imported.iter().find(|n| n.name == "Reimported").unwrap();
+    assert_eq!(
+        copy.meta[ORIGINAL_CREATED_AT_META],
+        json!("2019-03-01T08:00:00+00:00")
+    );
+    let copied_edges = store.list_edges(project.id).await.unwrap();
+    assert_eq!(copied_edges.len(), edges.len());
+    for edge in &copied_edges {
+        assert_eq!(original_created_at(&edge.meta), Some(edge.created_at));
+    }
+}
+
+#[test]
+fn native_json_rejects_unknown_versions() {
+    use crate::core::export::from_native_json;
+
+    let project = as_json(&fixtures::project("Versions"));
+    for doc in [
+        json!({ "project": project, "nodes": [], "edges": [] }),
+        json!({ "version": 2, "project": project, "nodes": [], "edges": [] }),
+        json!({ "version": "1", "project": project, "nodes": [], "edges": [] }),
+    ] {
+        assert!(from_native_json(&doc.to_string()).is_err(), "{doc}");
+    }
+    let ok = json!({ "version": 1, "project": project, "nodes": [], "edges": [] });
+    assert!(from_native_json(&ok.to_string())
+        .unwrap()
+        .warnings
+        .is_empty());
+}
+
+#[test]
+fn native_json_gives_a_project_without_an_id_a_fresh_one() {
+    use crate::core::export::from_native_json;
+
+    let doc = json!({ "version": 1, "project": { "name": "Hand-written" } });
+    let a = from_native_json(&doc.to_string()).unwrap();
+    let b = from_native_json(&doc.to_string()).unwrap();
+    assert_eq!(a.project.name, "Hand-written");
+    assert_ne!(a.project.id, b.project.id);
+    assert!(a.nodes.is_empty() && a.edges.is_empty());
+
+    let err = from_native_json(&json!({ "version": 1, "project": [] }).to_string())
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("invalid \"project\""), "{err}");
+}
+
+#[tokio::test]
+async fn coverage_leaves_out_obsolete_and_archived_everywhere() {
+    let store = fixtures::store().await;
+    let project = fixtures::project("Coverage");
+    store.create_project(&project, "tester").await.unwrap();
+    let traced = fixtures::requirement(project.id, "REQ-001", "Traced");
+    let orphaned = fixtures::requirement(project.id, "REQ-002", "Only an archived block");
+    let mut obsolete = fixtures::requirement(project.id, "REQ-003", "Superseded");
+    if let NodeData::Requirement(r) = &mut obsolete.data {
+        r.status = RequirementStatus::Obsolete;
+    }
+    let shelved = fixtures::requirement(project.id, "REQ-004", "Archived");
+    let regulator = fixtures::block(project.id, "Regulator");
+    let retired = fixtures::block(project.id, "Retired");
+    for node in [
+        &traced, &orphaned, &obsolete, &shelved, &regulator, &retired,
+    ] {
+        store.upsert_node(node).await.unwrap();
+    }
+    for (src, tgt) in [
+        (regulator.id, traced.id),
+        (regulator.id, obsolete.id),
+        (regulator.id, shelved.id),
+        (retired.id, orphaned.id),
+    ] {
+        let edge = fixtures::edge(project.id, EdgeKind::Satisfies, src, tgt);
+        store.upsert_edge(&edge).await.unwrap();
+    }
+    store
+        .set_nodes_archived(&[shelved.id, retired.id], true, "tester")
+        .await
+        .unwrap();
+
+    let live = store.coverage_metrics(project.id).await.unwrap();
+    assert_eq!(live.total_requirements, 2);
+    assert!((live.satisfied_pct - 50.0).abs() < 1e-9);
+
+    let nodes = store.list_nodes(project.id).await.unwrap();
+    let edges = store.list_edges(project.id).await.unwrap();
+    let model = crate::core::metrics::model_coverage(&nodes, &edges);
+    assert_eq!(model.total_requirements as i64, live.total_requirements);
+    assert!((model.satisfied_pct - live.satisfied_pct).abs() < 1e-9);
+
+    let snapshot = json!({ "nodes": nodes, "edges": edges });
+    assert_eq!(crate::core::baseline::coverage_metrics(&snapshot), live);
+
+    let portfolio = store.portfolio_summary().await.unwrap();
+    let health = portfolio
+        .iter()
+        .find(|h| h.project_id == project.id)
+        .unwrap();
+    assert_eq!(health.coverage, live);
+    assert_eq!(health.requirements.obsolete, 1);
+}
+
+#[tokio::test]
+async fn audit_entries_name_the_actor_and_commit_with_the_change() {
+    let store = fixtures::store().await;
+    let seed = seed_small_project(&store).await;
+    let [kept, doomed] = [&seed.blocks[0], &seed.blocks[1]];
+
+    store.delete_node(doomed.id, "alice").await.unwrap();
+    let entries = store
+        .list_audit_log(seed.project.id, Some("node"), Some(doomed.id), None, 10)
+        .await
+        .unwrap();
+    assert_eq!(entries.len(), 2, "{entries:?}");
+    assert_eq!(entries[0].action, "delete");
+    assert_eq!(entries[0].actor, "alice");
+
+    // A failed audit write rolls the change back with it.
+    sqlx::query(
+        "CREATE TRIGGER audit_down BEFORE INSERT ON audit_log
+         BEGIN SELECT RAISE(ABORT, 'audit down'); END",
+    )
+    .execute(&store.pool)
+    .await
+    .unwrap();
+    assert!(store.delete_node(kept.id, "alice").await.is_err());
+    assert!(store.get_node(kept.id).await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn example_project_is_written_whole_or_not_at_all() {
+    let store = fixtures::store().await;
+    let example = seed_example_project(&store).await;
+    let id = example.project.id;
+
+    assert_eq!(
+        store.list_nodes(id).await.unwrap().len(),
+        example.content.nodes.len()
+    );
+    assert_eq!(
+        store.list_edges(id).await.unwrap().len(),
+        example.content.edges.len()
+    );
+    assert_eq!(
+        store.list_diagrams(id).await.unwrap().len(),
+        example.content.diagrams.len()
+    );
+    assert_eq!(
+        store
+            .list_document_sections(example.document.id)
+            .await
+            .unwrap()
+            .len(),
+        example.sections.len()
+    );
+    assert_eq!(store.list_simulation_scenarios(id).await.unwrap().len(), 1);
+    let coverage = store.coverage_metrics(id).await.unwrap();
+    assert!(coverage.total_requirements > 0);
+    assert!((coverage.verified_pct - 100.0).abs() < 1e-9);
+
+    // A write failing part-way removes the half-built project.
+    sqlx::query(
+        "CREATE TRIGGER sections_down BEFORE INSERT ON document_sections
+         BEGIN SELECT RAISE(ABORT, 'sections down'); END",
+    )
+    .execute(&store.pool)
+    .await
+    .unwrap();
+    let again = crate::core::example::build(Utc::now());
+    assert!(store
+        .create_example_project(&again, "system")
+        .await
+        .is_err());
+    assert!(store.get_project(again.project.id).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn a_node_kind_from_a_newer_version_does_not_stop_the_project_loading() {
+    let store = fixtures::store().await;
+    let seed = seed_small_project(&store).await;
+    let activity = fixtures::block(seed.project.id, "Charge battery");
+    store.upsert_node(&activity).await.unwrap();
+    sqlx::query("UPDATE nodes SET kind = 'activity' WHERE id = ?")
+        .bind(activity.id.to_string())
+        .execute(&store.pool)
+        .await
+        .unwrap();
+
+    let nodes = store.list_nodes(seed.project.id).await.unwrap();
+    assert_eq!(nodes.len(), 4);
+    assert!(nodes.iter().all(|n| n.id != activity.id));
+    let summaries = store
+        .list_node_summaries(seed.project.id, None, true)
+        .await
+        .unwrap();
+    assert_eq!(summaries.len(), 4);
+    assert_eq!(store.list_edges(seed.project.id).await.unwrap().len(), 3);
+    assert_eq!(store.list_diagrams(seed.project.id).await.unwrap().len(), 1);
+
+    let unrecognized = store.list_unrecognized_rows(seed.project.id).await.unwrap();
+    assert_eq!(unrecognized.len(), 1);
+    assert_eq!(unrecognized[0].table, "nodes");
+    assert_eq!(unrecognized[0].kind, "activity");
+    assert_eq!(unrecognized[0].id, activity.id.to_string());
+    assert_eq!(unrecognized[0].row["name"], "Charge battery");
+}
+
+#[tokio::test]
+async fn a_settings_read_during_a_settings_write_sees_the_old_values_whole() {
+    let db = fixtures::TempDb::default();
+    let store = db.open().await;
+    let setting = |key: &str, value: &str| SettingEntry {
+        key: key.to_string(),
+        project_id: None,
+        value: value.to_string(),
+        masked: false,
+    };
+    let keys = ["ai.ollama.model", "ai.ollama.base_url"];
+    store
+        .set_settings(&[
+            setting(keys[0], "old-model"),
+            setting(keys[1], "http://old:11434"),
+        ])
+        .await
+        .unwrap();
+
+    // What `set_settings` does, stopped after the first write.
+    let mut tx = store.pool.begin().await.unwrap();
+    sqlx::query(UPSERT_SETTING_SQL)
+        .bind(keys[0])
+        .bind(None::<String>)
+        .bind("new-model")
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+    let during = store.get_settings(&keys, None).await.unwrap();
+    assert_eq!(during[keys[0]], "old-model");
+    assert_eq!(during[keys[1]], "http://old:11434");
+
+    sqlx::query(UPSERT_SETTING_SQL)
+        .bind(keys[1])
+        .bind(None::<String>)
+        .bind("http://new:11434")
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+    let after = store.get_settings(&keys, None).await.unwrap();
+    assert_eq!(after[keys[0]], "new-model");
+    assert_eq!(after[keys[1]], "http://new:11434");
+}
+
+/// Markdown, native JSON and XMI of `project_id`, loaded the way the
+/// export commands load them.
+async fn exports(store: &Store, project_id: Uuid) -> [String; 3] {
+    use crate::core::export::{scope, to_markdown, to_native_json, to_xmi};
+
+    let project = store.get_project(project_id).await.unwrap().unwrap();
+    let nodes = store.list_nodes(project_id).await.unwrap();
+    let edges = store.list_edges(project_id).await.unwrap();
+    let properties = store
+        .list_project_block_properties(project_id)
+        .await
+        .unwrap();
+    let external = store.list_project_external_links(project_id).await.unwrap();
+    let unrecognized = store.list_unrecognized_rows(project_id).await.unwrap();
+    let markdown = to_markdown(
+        &project,
+        &nodes,
+        &edges,
+        &properties,
+        &external,
+        &unrecognized,
+        None,
+    );
+    let model = scope::resolve(&Default::default(), nodes, edges, properties);
+    let json = to_native_json(&project, &model.nodes, &model.edges, &unrecognized).unwrap();
+    let xmi = to_xmi(&project, &model.nodes, &model.edges, &model.properties);
+    [markdown, json, xmi]
+}
+
+#[tokio::test]
+async fn exports_are_byte_identical_across_a_store_reopen() {
+    let db = fixtures::TempDb::default();
+    let store = db.open().await;
+    let example = seed_example_project(&store).await;
+    let project_id = example.project.id;
+    // A bulk import stamps every row with the same time.
+    let imported_at = Utc::now();
+    for i in 0..20 {
+        let mut req = fixtures::requirement(project_id, &format!("IMP-{i:02}"), "Imported.");
+        (req.created_at, req.modified_at) = (imported_at, imported_at);
+        store.upsert_node(&req).await.unwrap();
+    }
+
+    let before = exports(&store, project_id).await;
+    assert_eq!(before, exports(&store, project_id).await);
+    drop(store);
+
+    let reopened = db.open().await;
+    let after = exports(&reopened, project_id).await;
+    for (format, (before, after)) in ["markdown", "json", "xmi"]
+        .into_iter()
+        .zip(before.iter().zip(&after))
+    {
+        assert!(before == after, "{format} export changed after reopening");
+    }
+}
+
+/// Benchmark, not a check: run with
+/// `cargo test --release bench_node_summaries -- --ignored --nocapture`.
+/// `list_node_summaries` reads five columns and parses no JSON, so on a
+/// 20k-node project it should beat `list_nodes` by a wide margin.
+#[tokio::test]
+#[ignore]
+async fn bench_node_summaries_against_list_nodes_on_20k_nodes() {
+    use std::time::{Duration, Instant};
+
+    let store = fixtures::store().await;
+    let project = fixtures::project("Bench");
+    let mut nodes = Vec::with_capacity(20_000);
+    for i in 0..10_000 {
+        let mut req = fixtures::requirement(
+            project.id,
+            &format!("REQ-{i:05}"),
+            "The unit shall report its health every second over the maintenance bus.",
+        );
+        req.meta.insert("actor".into(), json!("bench"));
+        req.meta.insert("change_source".into(), json!("import"));
+        nodes.push(req);
+        nodes.push(fixtures::block(project.id, &format!("Block {i}")));
+    }
+    store
+        .import_model(&project, true, &nodes, &[], "bench")
+        .await
+        .unwrap();
+
+    async fn median<F: std::future::Future<Output = usize>>(
+        mut run: impl FnMut() -> F,
+    ) -> Duration {
+        let mut samples = Vec::new();
+        for _ in 0..15 {
+            let start = Instant::now();
+            assert_eq!(run().await, 20_000);
+            samples.push(start.elapsed());
+        }
+        samples.sort();
+        samples[samples.len() / 2]
+    }
+    let (store, id) = (&store, project.id);
+    let full = median(|| async move { store.list_nodes(id).await.unwrap().len() }).await;
+    let slim = median(|| async move {
+        store
+            .list_node_summaries(id, None, true)
+            .await
+            .unwrap()
+            .len()
+    })
+    .await;
+    println!("20k nodes: list_nodes median {full:?}, list_node_summaries median {slim:?}");
+    assert!(slim < full, "summaries {slim:?}, full {full:?}");
+}
@@ -0,0 +1,380 @@
+/// Project templates: capturing a trimmed copy of a project and stamping
+/// out new projects from it. Pure functions over model values; the
+/// commands layer does the store reads and writes.
+///
+/// A template holds blocks, value types, diagrams and project settings —
+/// never requirements or documents. Edges, block properties and diagram
+/// elements come along only when both ends are part of the template.
+use crate::core::model::{
+    BlockData, BlockProperty, Diagram, DiagramElement, DiagramFilters, DiagramKind, Edge,
+    EdgeKind, Node, NodeData, NodeKind, ProjectTemplate, ProjectTemplateContent, SettingEntry,
+    ValueTypeData,
+};
+use chrono::Utc;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// Stable id of the built-in "Generic System" template.
+pub const GENERIC_SYSTEM_TEMPLATE_ID: Uuid =
+    Uuid::from_u128(0x5e6e_71c0_0000_4000_8000_0000_0000_0001);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemplatePart {
+    Blocks,
+    ValueTypes,
+    Diagrams,
+    Settings,
+}
+
+impl TemplatePart {
+    pub const ALL: [TemplatePart; 4] = [
+        TemplatePart::Blocks,
+        TemplatePart::ValueTypes,
+        TemplatePart::Diagrams,
+        TemplatePart::Settings,
+    ];
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "blocks" => Some(Self::Blocks),
+            "value_types" => Some(Self::ValueTypes),
+            "diagrams" => Some(Self::Diagrams),
+            "settings" => Some(Self::Settings),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Blocks => "blocks",
+            Self::ValueTypes => "value_types",
+            Self::Diagrams => "diagrams",
+            Self::Settings => "settings",
+        }
+    }
+}
+
+/// Parse an `include` list; an empty list means every part.
+pub fn parse_include(include: &[String]) -> Result<Vec<TemplatePart>, String> {
+    if include.is_empty() {
+        return Ok(TemplatePart::ALL.to_vec());
+    }
+    let mut parts = Vec::new();
+    for raw in include {
+        let part = TemplatePart::parse(raw).ok_or_else(|| {
+            format!(
+                "unknown template part '{raw}' (expected blocks, value_types, diagrams or settings)"
+            )
+        })?;
+        if !parts.contains(&part) {
+            parts.push(part);
+        }
+    }
+    Ok(parts)
+}
+
+/// Trim a project's model down to the requested parts. Archived nodes are
+/// left out.
+pub fn capture(
+    parts: &[TemplatePart],
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+    properties: Vec<BlockProperty>,
+    diagrams: Vec<Diagram>,
+    elements: Vec<DiagramElement>,
+    settings: Vec<SettingEntry>,
+) -> ProjectTemplateContent {
+    let wants = |part: TemplatePart| parts.contains(&part);
+
+    let nodes: Vec<Node> = nodes
+        .into_iter()
+        .filter(|n| !n.archived)
+        .filter(|n| match n.kind {
+            NodeKind::Block => wants(TemplatePart::Blocks),
+            NodeKind::ValueType => wants(TemplatePart::ValueTypes),
+            _ => false,
+        })
+        .collect();
+    let ids: HashSet<Uuid> = nodes.iter().map(|n| n.id).collect();
+
+    let edges = edges
+        .into_iter()
+        .filter(|e| ids.contains(&e.source_id) && ids.contains(&e.target_id))
+        .collect();
+    let block_properties = properties
+        .into_iter()
+        .filter(|p| ids.contains(&p.block_node_id))
+        .map(|mut p| {
+            // The display type name survives a dropped ValueType.
+            p.value_type_node_id = p.value_type_node_id.filter(|vt| ids.contains(vt));
+            p
+        })
+        .collect();
+
+    let (diagrams, diagram_elements) = if wants(TemplatePart::Diagrams) {
+        let elements = elements.into_iter().filter(|el| ids.contains(&el.node_id)).collect();
+        (diagrams, elements)
+    } else {
+        (Vec::new(), Vec::new())
+    };
+
+    let settings = if wants(TemplatePart::Settings) {
+        settings
+            .into_iter()
+            .map(|mut s| {
+                s.project_id = None;
+                s
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    ProjectTemplateContent {
+        nodes,
+        edges,
+        block_properties,
+        diagrams,
+        diagram_elements,
+        settings,
+    }
+}
+
+/// Copy a template's content into `project_id` with fresh ids and
+/// timestamps. References between elements follow the new ids; anything
+/// pointing outside the template is dropped.
+pub fn instantiate(content: &ProjectTemplateContent, project_id: Uuid) -> ProjectTemplateContent {
+    let now = Utc::now();
+    let fresh =
+        |ids: &mut HashMap<Uuid, Uuid>, old: Uuid| *ids.entry(old).or_insert_with(Uuid::new_v4);
+
+    let mut node_ids = HashMap::new();
+    let nodes = content
+        .nodes
+        .iter()
+        .map(|n| Node {
+            id: fresh(&mut node_ids, n.id),
+            project_id,
+            created_at: now,
+            modified_at: now,
+            ..n.clone()
+        })
+        .collect();
+
+    let edges = content
+        .edges
+        .iter()
+        .filter_map(|e| {
+            Some(Edge {
+                id: Uuid::new_v4(),
+                project_id,
+                source_id: *node_ids.get(&e.source_id)?,
+                target_id: *node_ids.get(&e.target_id)?,
+                created_at: now,
+                modified_at: now,
+                ..e.clone()
+            })
+        })
+        .collect();
+
+    let block_properties = content
+        .block_properties
+        .iter()
+        .filter_map(|p| {
+            Some(BlockProperty {
+                id: Uuid::new_v4(),
+                block_node_id: *node_ids.get(&p.block_node_id)?,
+                value_type_node_id: p.value_type_node_id.and_then(|vt| node_ids.get(&vt).copied()),
+                created_at: now,
+                ..p.clone()
+            })
+        })
+        .collect();
+
+    let mut diagram_ids = HashMap::new();
+    let diagrams = content
+        .diagrams
+        .iter()
+        .map(|d| {
+            let mut diagram = Diagram {
+                id: fresh(&mut diagram_ids, d.id),
+                project_id,
+                created_at: now,
+                modified_at: now,
+                ..d.clone()
+            };
+            let filters = d.filters();
+            if !filters.hidden_node_ids.is_empty() {
+                let remapped = DiagramFilters {
+                    hidden_node_ids: filters
+                        .hidden_node_ids
+                        .iter()
+                        .filter_map(|id| node_ids.get(id).copied())
+                        .collect(),
+                    ..filters
+                };
+                if let Ok(value) = serde_json::to_value(remapped) {
+                    diagram.layout_options.insert(DiagramFilters::LAYOUT_KEY.to_string(), value);
+                }
+            }
+            diagram
+        })
+        .collect();
+
+    let diagram_elements = content
+        .diagram_elements
+        .iter()
+        .filter_map(|el| {
+            Some(DiagramElement {
+                id: Uuid::new_v4(),
+                diagram_id: *diagram_ids.get(&el.diagram_id)?,
+                node_id: *node_ids.get(&el.node_id)?,
+                created_at: now,
+                modified_at: now,
+                ..el.clone()
+            })
+        })
+        .collect();
+
+    let settings = content
+        .settings
+        .iter()
+        .map(|s| SettingEntry {
+            project_id: Some(project_id),
+            ..s.clone()
+        })
+        .collect();
+
+    ProjectTemplateContent {
+        nodes,
+        edges,
+        block_properties,
+        diagrams,
+        diagram_elements,
+        settings,
+    }
+}
+
+/// Templates shipped with the app, listed before saved ones.
+pub fn builtin_templates() -> Vec<ProjectTemplate> {
+    vec![generic_system()]
+}
+
+pub fn builtin_template(id: Uuid) -> Option<ProjectTemplate> {
+    builtin_templates().into_iter().find(|t| t.id == id)
+}
+
+/// A System root block composed of five common subsystems, a BDD showing
+/// them, and a handful of physical value types.
+fn generic_system() -> ProjectTemplate {
+    let now = Utc::now();
+    let node = |kind: NodeKind, name: &str, description: &str, data: NodeData| Node {
+        id: Uuid::new_v4(),
+        project_id: Uuid::nil(),
+        kind,
+        name: name.to_string(),
+        description: description.to_string(),
+        data,
+        meta: HashMap::new(),
+        archived: false,
+        created_at: now,
+        modified_at: now,
+    };
+    let block = |name: &str, description: &str| {
+        node(NodeKind::Block, name, description, NodeData::Block(BlockData::default()))
+    };
+    let value_type = |name: &str, unit: &str| {
+        let data = ValueTypeData {
+            base_type: Some("Real".to_string()),
+            unit: Some(unit.to_string()),
+            constraint: None,
+        };
+        node(NodeKind::ValueType, name, "", NodeData::ValueType(data))
+    };
+
+    let system = block("System", "Top-level system of interest.");
+    let subsystems = vec![
+        block("Power Subsystem", "Generates, stores and distributes electrical power."),
+        block("Communications Subsystem", "External data and command links."),
+        block("Processing Subsystem", "Onboard computing, control and data handling."),
+        block("Structure", "Mechanical frame, enclosures and mounting."),
+        block("Thermal Management", "Keeps equipment within temperature limits."),
+    ];
+
+    let edges = subsystems
+        .iter()
+        .map(|part| Edge {
+            id: Uuid::new_v4(),
+            project_id: Uuid::nil(),
+            kind: EdgeKind::Composes,
+            source_id: system.id,
+            target_id: part.id,
+            label: String::new(),
+            meta: HashMap::new(),
+            created_at: now,
+            modified_at: now,
+        })
+        .collect();
+
+    let bdd = Diagram {
+        id: Uuid::new_v4(),
+        project_id: Uuid::nil(),
+        kind: DiagramKind::Bdd,
+        name: "System Overview".to_string(),
+        description: "System decomposition into subsystems.".to_string(),
+        layout_options: HashMap::new(),
+        created_at: now,
+        modified_at: now,
+    };
+    let element = |node_id: Uuid, x: f64, y: f64| DiagramElement {
+        id: Uuid::new_v4(),
+        diagram_id: bdd.id,
+        node_id,
+        x,
+        y,
+        width: 180.0,
+        height: 90.0,
+        collapsed: false,
+        style_overrides: HashMap::new(),
+        created_at: now,
+        modified_at: now,
+    };
+    // Subsystems in one row under the root, which sits centred above them.
+    let mut diagram_elements = vec![element(system.id, 520.0, 40.0)];
+    diagram_elements.extend(
+        subsystems
+            .iter()
+            .enumerate()
+            .map(|(i, part)| element(part.id, 40.0 + 240.0 * i as f64, 240.0)),
+    );
+
+    let mut nodes = vec![system];
+    nodes.extend(subsystems);
+    nodes.extend([
+        value_type("Mass", "kg"),
+        value_type("Power", "W"),
+        value_type("Voltage", "V"),
+        value_type("Current", "A"),
+        value_type("Length", "m"),
+        value_type("Temperature", "°C"),
+    ]);
+
+    ProjectTemplate {
+        id: GENERIC_SYSTEM_TEMPLATE_ID,
+        name: "Generic System".to_string(),
+        description: "System root block with power, communications, processing, structure and \
+                      thermal subsystems, an overview BDD and common value types."
+            .to_string(),
+        builtin: true,
+        include: TemplatePart::ALL.iter().map(|p| p.as_str().to_string()).collect(),
+        content: ProjectTemplateContent {
+            nodes,
+            edges,
+            block_properties: Vec::new(),
+            diagrams: vec![bdd],
+            diagram_elements,
+            settings: Vec::new(),
+        },
+        created_at: now,
+    }
+}
@@ -0,0 +1,219 @@
+/// Terminology check of requirement text against the project glossary.
+///
+/// A forbidden variant is reported wherever it occurs as whole words; a
+/// case-sensitive term's approved form is also reported wherever it is
+/// written with different capitalization. Whitespace inside a phrase
+/// matches any run of whitespace. Offsets and lengths count characters
+/// (not bytes) of the requirement text — `text`, or the description when
+/// there is none — and fixes are checked against the same text before
+/// being applied, so a stale fix is rejected rather than misapplied.
+use crate::core::model::{GlossaryTerm, Node, NodeData};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TerminologyIssueKind {
+    ForbiddenVariant,
+    Capitalization,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerminologyIssue {
+    pub node_id: Uuid,
+    pub req_id: Option<String>,
+    pub term_id: Uuid,
+    pub kind: TerminologyIssueKind,
+    /// Character offset into the requirement text.
+    pub offset: usize,
+    /// Length of `matched` in characters.
+    pub length: usize,
+    /// The text as found.
+    pub matched: String,
+    /// The approved form.
+    pub replacement: String,
+}
+
+/// One replacement to apply; usually an issue passed back unchanged.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerminologyFix {
+    pub node_id: Uuid,
+    pub offset: usize,
+    pub matched: String,
+    pub replacement: String,
+}
+
+/// The text terminology is checked in: the requirement text, or the
+/// description when the text is empty. `None` for other kinds.
+pub fn requirement_text(node: &Node) -> Option<&str> {
+    let NodeData::Requirement(req) = &node.data else {
+        return None;
+    };
+    Some(
+        req.text
+            .as_deref()
+            .filter(|t| !t.trim().is_empty())
+            .unwrap_or(&node.description),
+    )
+}
+
+/// Issues in every requirement of `nodes`, by node and then offset. Where
+/// matches overlap, the earliest (then longest) one is reported.
+pub fn check(nodes: &[Node], terms: &[GlossaryTerm]) -> Vec<TerminologyIssue> {
+    let mut issues = Vec::new();
+    for node in nodes {
+        let Some(text) = requirement_text(node) else {
+            continue;
+        };
+        let req_id = match &node.data {
+            NodeData::Requirement(r) => r.req_id.clone(),
+            _ => None,
+        };
+        let chars: Vec<char> = text.chars().collect();
+        let mut found = Vec::new();
+        for term in terms {
+            let approved = term.approved.trim();
+            if approved.is_empty() {
+                continue;
+            }
+            let approved_spans = find_phrase(&chars, approved, false);
+            for &(start, end) in &approved_spans {
+                if term.case_sensitive && !same_words(&chars[start..end], approved) {
+                    found.push((start, end, term, TerminologyIssueKind::Capitalization));
+                }
+            }
+            for variant in term
+                .variants
+                .iter()
+                .map(|v| v.trim())
+                .filter(|v| !v.is_empty())
+            {
+                for (start, end) in find_phrase(&chars, variant, term.case_sensitive) {
+                    let inside_approved = approved_spans.iter().any(|&(s, e)| start < e && s < end);
+                    if !inside_approved {
+                        found.push((start, end, term, TerminologyIssueKind::ForbiddenVariant));
+                    }
+                }
+            }
+        }
+
+        found.sort_by_key(|&(start, end, _, _)| (start, std::cmp::Reverse(end)));
+        let mut covered = 0;
+        for (start, end, term, kind) in found {
+            if start < covered {
+                continue;
+            }
+            covered = end;
+            issues.push(TerminologyIssue {
+                node_id: node.id,
+                req_id: req_id.clone(),
+                term_id: term.id,
+                kind,
+                offset: start,
+                length: end - start,
+                matched: chars[start..end].iter().collect(),
+                replacement: term.approved.trim().to_string(),
+            });
+        }
+    }
+    issues
+}
+
+/// `text` with `fixes` applied. Fails when a fix's `matched` is not at its
+/// offset (the text changed since the check) or two fixes overlap.
+pub fn apply(text: &str, fixes: &[&TerminologyFix]) -> Result<String, String> {
+    let mut chars: Vec<char> = text.chars().collect();
+    let mut ordered: Vec<&TerminologyFix> = fixes.to_vec();
+    ordered.sort_by_key(|f| std::cmp::Reverse(f.offset));
+    let mut limit = usize::MAX;
+    for fix in ordered {
+        let matched: Vec<char> = fix.matched.chars().collect();
+        let end = fix.offset + matched.len();
+        if end > limit {
+            return Err(format!("overlapping fixes at offset {}", fix.offset));
+        }
+        if chars.get(fix.offset..end) != Some(matched.as_slice()) {
+            return Err(format!(
+                "'{}' is no longer at offset {}; run the check again",
+                fix.matched, fix.offset
+            ));
+        }
+        chars.splice(fix.offset..end, fix.replacement.chars());
+        limit = fix.offset;
+    }
+    Ok(chars.into_iter().collect())
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn chars_eq(a: char, b: char, case_sensitive: bool) -> bool {
+    a == b || (!case_sensitive && a.to_lowercase().eq(b.to_lowercase()))
+}
+
+/// Whether `found` spells the words of `phrase` exactly, ignoring how
+/// much whitespace separates them.
+fn same_words(found: &[char], phrase: &str) -> bool {
+    let found: String = found.iter().collect();
+    found.split_whitespace().eq(phrase.split_whitespace())
+}
+
+/// Character ranges of `text` where `phrase` occurs as whole words.
+fn find_phrase(text: &[char], phrase: &str, case_sensitive: bool) -> Vec<(usize, usize)> {
+    let words: Vec<Vec<char>> = phrase
+        .split_whitespace()
+        .map(|w| w.chars().collect())
+        .collect();
+    let (Some(first), Some(last)) = (
+        words.first().and_then(|w| w.first()),
+        words.last().and_then(|w| w.last()),
+    ) else {
+        return Vec::new();
+    };
+    let check_start = is_word(*first);
+    let check_end = is_word(*last);
+
+    let mut spans = Vec::new();
+    for start in 0..text.len() {
+        if check_start && start > 0 && is_word(text[start - 1]) {
+            continue;
+        }
+        let Some(end) = match_words(text, start, &words, case_sensitive) else {
+            continue;
+        };
+        if check_end && end < text.len() && is_word(text[end]) {
+            continue;
+        }
+        spans.push((start, end));
+    }
+    spans
+}
+
+fn match_words(
+    text: &[char],
+    start: usize,
+    words: &[Vec<char>],
+    case_sensitive: bool,
+) -> Option<usize> {
+    let mut pos = start;
+    for (i, word) in words.iter().enumerate() {
+        if i > 0 {
+            let gap = text[pos..].iter().take_while(|c| c.is_whitespace()).count();
+            if gap == 0 {
+                return None;
+            }
+            pos += gap;
+        }
+        let candidate = text.get(pos..pos + word.len())?;
+        if !candidate
+            .iter()
+            .zip(word)
+            .all(|(&a, &b)| chars_eq(a, b, case_sensitive))
+        {
+            return None;
+        }
+        pos += word.len();
+    }
+    Some(pos)
+}
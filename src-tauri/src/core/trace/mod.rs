@@ -0,0 +1,153 @@
+/// Impact analysis: everything that transitively depends on a node
+/// (downstream), or that it depends on (upstream), through «derives»,
+/// «refines», «satisfies», «verifies» and «allocates» edges.
+///
+/// Each edge kind points its own way, so "downstream" is defined per kind:
+/// a derived requirement depends on the one it derives from, a refining
+/// requirement on the one it refines, a satisfying block or verifying test
+/// case on its requirement, and an allocation target on what is allocated
+/// to it. A requirement change thus reaches the requirements below it and
+/// the blocks and test cases tracing to any of them.
+use crate::core::model::{Edge, EdgeKind, Node, NodeKind};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// Default for `impact_analysis`'s `max_depth`.
+pub const DEFAULT_MAX_DEPTH: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceDirection {
+    /// What depends on the node.
+    Downstream,
+    /// What the node depends on.
+    Upstream,
+}
+
+impl TraceDirection {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "downstream" => Some(Self::Downstream),
+            "upstream" => Some(Self::Upstream),
+            _ => None,
+        }
+    }
+}
+
+/// One node of the impact tree. The root has depth 0 and no edge.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImpactNode {
+    pub node_id: Uuid,
+    pub name: String,
+    pub kind: NodeKind,
+    /// Kind of the edge joining this node to its parent in the tree.
+    pub edge_kind: Option<EdgeKind>,
+    pub edge_id: Option<Uuid>,
+    pub depth: usize,
+    /// By name.
+    pub children: Vec<ImpactNode>,
+}
+
+/// The (upstream, downstream) ends of `edge`, or `None` for a kind impact
+/// doesn't follow.
+fn ends(edge: &Edge) -> Option<(Uuid, Uuid)> {
+    match edge.kind {
+        EdgeKind::Derives | EdgeKind::Allocates => Some((edge.source_id, edge.target_id)),
+        EdgeKind::Refines | EdgeKind::Satisfies | EdgeKind::Verifies => {
+            Some((edge.target_id, edge.source_id))
+        }
+        _ => None,
+    }
+}
+
+/// The tree of nodes reachable from `root_id` in `direction`, at most
+/// `max_depth` edges away. Breadth-first, so each node appears once, at
+/// its shallowest depth, under the first node (by name) that reaches it;
+/// cycles end where they come back to a node already in the tree.
+/// Archived nodes other than the root are left out. `None` when `root_id`
+/// isn't in `nodes`.
+pub fn impact_tree(
+    root_id: Uuid,
+    nodes: &[Node],
+    edges: &[Edge],
+    direction: TraceDirection,
+    max_depth: usize,
+) -> Option<ImpactNode> {
+    let live: HashMap<Uuid, &Node> = nodes
+        .iter()
+        .filter(|n| !n.archived || n.id == root_id)
+        .map(|n| (n.id, n))
+        .collect();
+    live.get(&root_id)?;
+
+    let mut next: HashMap<Uuid, Vec<(Uuid, &Edge)>> = HashMap::new();
+    for edge in edges {
+        let Some((upstream, downstream)) = ends(edge) else {
+            continue;
+        };
+        let (from, to) = match direction {
+            TraceDirection::Downstream => (upstream, downstream),
+            TraceDirection::Upstream => (downstream, upstream),
+        };
+        if live.contains_key(&from) && live.contains_key(&to) {
+            next.entry(from).or_default().push((to, edge));
+        }
+    }
+    for list in next.values_mut() {
+        list.sort_by(|(a, x), (b, y)| {
+            live[a]
+                .name
+                .cmp(&live[b].name)
+                .then_with(|| a.cmp(b))
+                .then_with(|| x.id.cmp(&y.id))
+        });
+    }
+
+    // (node, (parent, edge), depth) in breadth-first order.
+    let mut seen: HashSet<Uuid> = HashSet::from([root_id]);
+    let mut order: Vec<(Uuid, Option<(Uuid, &Edge)>, usize)> = vec![(root_id, None, 0)];
+    let mut i = 0;
+    while i < order.len() {
+        let (id, _, depth) = order[i];
+        i += 1;
+        if depth >= max_depth {
+            continue;
+        }
+        for &(to, edge) in next.get(&id).into_iter().flatten() {
+            if seen.insert(to) {
+                order.push((to, Some((id, edge)), depth + 1));
+            }
+        }
+    }
+
+    // Deepest first, so every node's children are built before it.
+    let entry = |id: Uuid, via: Option<&Edge>, depth: usize, mut children: Vec<ImpactNode>| {
+        let node = live[&id];
+        children.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.node_id.cmp(&b.node_id)));
+        ImpactNode {
+            node_id: id,
+            name: node.name.clone(),
+            kind: node.kind.clone(),
+            edge_kind: via.map(|e| e.kind.clone()),
+            edge_id: via.map(|e| e.id),
+            depth,
+            children,
+        }
+    };
+    let mut built: HashMap<Uuid, Vec<ImpactNode>> = HashMap::new();
+    for &(id, via, depth) in order.iter().skip(1).rev() {
+        let Some((parent, edge)) = via else {
+            continue;
+        };
+        let children = built.remove(&id).unwrap_or_default();
+        built
+            .entry(parent)
+            .or_default()
+            .push(entry(id, Some(edge), depth, children));
+    }
+    let children = built.remove(&root_id).unwrap_or_default();
+    Some(entry(root_id, None, 0, children))
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,204 @@
+use super::*;
+use crate::test_support as fixtures;
+
+/// SYS-1 «derives» SUB-1 and SUB-2; CMP-1 «refines» SUB-1; Regulator
+/// «satisfies» CMP-1; the load test «verifies» CMP-1 and SUB-2; a function
+/// is «allocated» to the regulator; and SUB-2 «derives» SYS-1, closing a
+/// loop back to the root.
+struct Graph {
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+    sys: Uuid,
+    cmp: Uuid,
+    regulator: Uuid,
+    load_test: Uuid,
+}
+
+fn graph() -> Graph {
+    let project = fixtures::project("Trace");
+    let pid = project.id;
+    let sys = fixtures::requirement(pid, "SYS-1", "Supply power");
+    let sub1 = fixtures::requirement(pid, "SUB-1", "Regulate the bus");
+    let sub2 = fixtures::requirement(pid, "SUB-2", "Limit inrush");
+    let cmp = fixtures::requirement(pid, "CMP-1", "Hold 28 V");
+    let regulator = fixtures::block(pid, "Regulator");
+    let load_test = fixtures::test_case(pid, "Load test");
+    let regulate = fixtures::node(pid, NodeKind::Function, "Regulate");
+    let mut retired = fixtures::block(pid, "Retired");
+    retired.archived = true;
+    let edges = vec![
+        fixtures::edge(pid, EdgeKind::Derives, sys.id, sub1.id),
+        fixtures::edge(pid, EdgeKind::Derives, sys.id, sub2.id),
+        fixtures::edge(pid, EdgeKind::Refines, cmp.id, sub1.id),
+        fixtures::edge(pid, EdgeKind::Satisfies, regulator.id, cmp.id),
+        fixtures::edge(pid, EdgeKind::Verifies, load_test.id, cmp.id),
+        fixtures::edge(pid, EdgeKind::Verifies, load_test.id, sub2.id),
+        fixtures::edge(pid, EdgeKind::Allocates, regulate.id, regulator.id),
+        fixtures::edge(pid, EdgeKind::Derives, sub2.id, sys.id),
+        fixtures::edge(pid, EdgeKind::Satisfies, retired.id, sub1.id),
+        fixtures::edge(pid, EdgeKind::Traces, sys.id, regulate.id),
+    ];
+    Graph {
+        sys: sys.id,
+        cmp: cmp.id,
+        regulator: regulator.id,
+        load_test: load_test.id,
+        nodes: vec![
+            sys, sub1, sub2, cmp, regulator, load_test, regulate, retired,
+        ],
+        edges,
+    }
+}
+
+/// (name, edge kind, depth) in depth-first order.
+fn flatten(node: &ImpactNode, out: &mut Vec<(String, Option<EdgeKind>, usize)>) {
+    out.push((node.name.clone(), node.edge_kind.clone(), node.depth));
+    for child in &node.children {
+        flatten(child, out);
+    }
+}
+
+fn entries(node: &ImpactNode) -> Vec<(String, Option<EdgeKind>, usize)> {
+    let mut out = Vec::new();
+    flatten(node, &mut out);
+    out
+}
+
+fn entry(name: &str, kind: Option<EdgeKind>, depth: usize) -> (String, Option<EdgeKind>, usize) {
+    (name.to_string(), kind, depth)
+}
+
+#[test]
+fn downstream_reaches_derived_requirements_and_what_traces_to_them() {
+    let g = graph();
+    let tree = impact_tree(
+        g.sys,
+        &g.nodes,
+        &g.edges,
+        TraceDirection::Downstream,
+        DEFAULT_MAX_DEPTH,
+    )
+    .unwrap();
+
+    // The load test verifies both SUB-2 and CMP-1 but appears once, at its
+    // shallowest depth; SUB-2's «derives» back to SYS-1 ends there, and
+    // neither the archived block nor the «traces» edge is followed.
+    assert_eq!(
+        entries(&tree),
+        vec![
+            entry("SYS-1", None, 0),
+            entry("SUB-1", Some(EdgeKind::Derives), 1),
+            entry("CMP-1", Some(EdgeKind::Refines), 2),
+            entry("Regulator", Some(EdgeKind::Satisfies), 3),
+            entry("SUB-2", Some(EdgeKind::Derives), 1),
+            entry("Load test", Some(EdgeKind::Verifies), 2),
+        ]
+    );
+    let cmp = &tree.children[0].children[0];
+    assert_eq!(cmp.node_id, g.cmp);
+    assert_eq!(cmp.kind, NodeKind::Requirement);
+    assert!(cmp.edge_id.is_some());
+}
+
+#[test]
+fn allocation_targets_are_downstream_of_what_is_allocated() {
+    let g = graph();
+    let function = g.nodes.iter().find(|n| n.name == "Regulate").unwrap();
+    let tree = impact_tree(
+        function.id,
+        &g.nodes,
+        &g.edges,
+        TraceDirection::Downstream,
+        DEFAULT_MAX_DEPTH,
+    )
+    .unwrap();
+    assert_eq!(
+        entries(&tree),
+        vec![
+            entry("Regulate", None, 0),
+            entry("Regulator", Some(EdgeKind::Allocates), 1),
+        ]
+    );
+}
+
+#[test]
+fn upstream_walks_from_a_test_case_to_the_requirements_above_it() {
+    let g = graph();
+    let tree = impact_tree(
+        g.load_test,
+        &g.nodes,
+        &g.edges,
+        TraceDirection::Upstream,
+        DEFAULT_MAX_DEPTH,
+    )
+    .unwrap();
+    assert_eq!(
+        entries(&tree),
+        vec![
+            entry("Load test", None, 0),
+            entry("CMP-1", Some(EdgeKind::Verifies), 1),
+            entry("SUB-1", Some(EdgeKind::Refines), 2),
+            entry("SUB-2", Some(EdgeKind::Verifies), 1),
+            entry("SYS-1", Some(EdgeKind::Derives), 2),
+        ]
+    );
+}
+
+#[test]
+fn max_depth_cuts_the_tree_off() {
+    let g = graph();
+    let direct = impact_tree(g.sys, &g.nodes, &g.edges, TraceDirection::Downstream, 1).unwrap();
+    assert_eq!(
+        entries(&direct),
+        vec![
+            entry("SYS-1", None, 0),
+            entry("SUB-1", Some(EdgeKind::Derives), 1),
+            entry("SUB-2", Some(EdgeKind::Derives), 1),
+        ]
+    );
+
+    let root_only =
+        impact_tree(g.regulator, &g.nodes, &g.edges, TraceDirection::Upstream, 0).unwrap();
+    assert!(root_only.children.is_empty());
+}
+
+#[test]
+fn a_cycle_through_the_root_terminates() {
+    let project = fixtures::project("Loop");
+    let a = fixtures::requirement(project.id, "REQ-A", "A");
+    let b = fixtures::requirement(project.id, "REQ-B", "B");
+    let edges = vec![
+        fixtures::edge(project.id, EdgeKind::Derives, a.id, b.id),
+        fixtures::edge(project.id, EdgeKind::Derives, b.id, a.id),
+        fixtures::edge(project.id, EdgeKind::Refines, a.id, a.id),
+    ];
+    for direction in [TraceDirection::Downstream, TraceDirection::Upstream] {
+        let tree =
+            impact_tree(a.id, &[a.clone(), b.clone()], &edges, direction, usize::MAX).unwrap();
+        assert_eq!(
+            entries(&tree),
+            vec![
+                entry("REQ-A", None, 0),
+                entry("REQ-B", Some(EdgeKind::Derives), 1)
+            ]
+        );
+    }
+}
+
+#[test]
+fn unknown_root_and_direction() {
+    let g = graph();
+    assert!(impact_tree(
+        Uuid::new_v4(),
+        &g.nodes,
+        &g.edges,
+        TraceDirection::Downstream,
+        DEFAULT_MAX_DEPTH
+    )
+    .is_none());
+    assert_eq!(
+        TraceDirection::parse(" Upstream"),
+        Some(TraceDirection::Upstream)
+    );
+    assert_eq!(TraceDirection::parse("sideways"), None);
+}
@@ -0,0 +1,173 @@
+/// Per-project cache of validation issues so a single node or edge edit can
+/// be re-checked on its own and reported as a delta.
+///
+/// Every issue is owned by the entity whose rule produced it: edge rules
+/// own issues that carry an `edge_id`, node rules own the rest. Node rules
+/// only look at the node itself and edge rules only at the edge and its
+/// endpoints, so re-running the rules for the touched entities (a node plus
+/// its incident edges, or a single edge) reproduces exactly the issues the
+/// full `validate` would give for them.
+///
+/// The one node rule that looks further, `PORT_UNCONNECTED`, depends on the
+/// port's «connects» edges; callers touch both endpoints whenever such an
+/// edge changes, and a touched node always comes with all its edges. Port
+/// direction messages name the owning blocks, so callers also pass the
+/// «composes» edges and owners of «connects» endpoints.
+///
+/// `CYCLE_DETECTED` and `REQ_DUPLICATE_ID` look across the whole project,
+/// so they can't be re-checked here. Callers run a full validation instead
+/// while one is reported, or when a change could raise one: a touched
+/// hierarchy edge, or a touched requirement whose req_id another has.
+use super::{
+    assign_stable_ids, unconnected_ports, validate_edge, validate_node, ValidationIssue,
+    ValidationLimits,
+};
+use crate::core::model::{Edge, Node};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// Payload of `events::VALIDATION_UPDATED`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationDelta {
+    pub project_id: Uuid,
+    pub added_issues: Vec<ValidationIssue>,
+    pub removed_issue_ids: Vec<Uuid>,
+}
+
+impl ValidationDelta {
+    fn new(project_id: Uuid) -> Self {
+        Self {
+            project_id,
+            added_issues: Vec::new(),
+            removed_issue_ids: Vec::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added_issues.is_empty() && self.removed_issue_ids.is_empty()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ValidationCache {
+    /// project → owning entity id → issues.
+    projects: HashMap<Uuid, HashMap<Uuid, Vec<ValidationIssue>>>,
+}
+
+impl ValidationCache {
+    /// True once a full validation has seeded this project.
+    pub fn is_seeded(&self, project_id: Uuid) -> bool {
+        self.projects.contains_key(&project_id)
+    }
+
+    /// True when the cached issues for the project include one with any of
+    /// `codes`.
+    pub fn has_any_code(&self, project_id: Uuid, codes: &[&str]) -> bool {
+        self.projects
+            .get(&project_id)
+            .is_some_and(|issues| issues.values().flatten().any(|i| codes.contains(&i.code)))
+    }
+
+    /// Drop the project's cached issues, returning their ids.
+    pub fn invalidate(&mut self, project_id: Uuid) -> Vec<Uuid> {
+        self.projects
+            .remove(&project_id)
+            .unwrap_or_default()
+            .values()
+            .flatten()
+            .map(|i| i.id)
+            .collect()
+    }
+
+    /// Drop every project but `project_id` — called when the user switches projects.
+    pub fn retain_only(&mut self, project_id: Uuid) {
+        self.projects.retain(|id, _| *id == project_id);
+    }
+
+    /// Replace the project's cached issues with a full validation result and
+    /// return what changed. The first call for a project reports everything
+    /// as added.
+    pub fn reconcile(&mut self, project_id: Uuid, issues: &[ValidationIssue]) -> ValidationDelta {
+        let mut fresh: HashMap<Uuid, Vec<ValidationIssue>> = HashMap::new();
+        for issue in issues {
+            fresh.entry(owner(issue)).or_default().push(issue.clone());
+        }
+
+        let old = self.projects.remove(&project_id).unwrap_or_default();
+        let delta = diff(
+            project_id,
+            old.values().flatten(),
+            fresh.values().flatten(),
+        );
+        self.projects.insert(project_id, fresh);
+        delta
+    }
+
+    /// Re-run validation for the entities in `touched` and return the change
+    /// against the cache.
+    ///
+    /// `nodes` must hold the current state of every touched node that still
+    /// exists plus the endpoints of `edges`; `edges` holds every touched edge
+    /// that still exists. Touched ids missing from both were deleted, so
+    /// their cached issues are removed.
+    pub fn revalidate(
+        &mut self,
+        project_id: Uuid,
+        touched: &[Uuid],
+        nodes: &[Node],
+        edges: &[Edge],
+        limits: &ValidationLimits,
+    ) -> ValidationDelta {
+        let touched: HashSet<Uuid> = touched.iter().copied().collect();
+
+        let touched_nodes: Vec<Node> = nodes
+            .iter()
+            .filter(|n| touched.contains(&n.id))
+            .cloned()
+            .collect();
+        let mut fresh: Vec<ValidationIssue> = touched_nodes
+            .iter()
+            .flat_map(|n| validate_node(n, limits))
+            .collect();
+        for edge in edges.iter().filter(|e| touched.contains(&e.id)) {
+            fresh.extend(validate_edge(edge, nodes, edges));
+        }
+        fresh.extend(unconnected_ports(&touched_nodes, edges));
+        assign_stable_ids(&mut fresh);
+
+        let cached = self.projects.entry(project_id).or_default();
+        let old: Vec<ValidationIssue> = touched
+            .iter()
+            .filter_map(|id| cached.remove(id))
+            .flatten()
+            .collect();
+        for issue in &fresh {
+            cached.entry(owner(issue)).or_default().push(issue.clone());
+        }
+
+        diff(project_id, old.iter(), fresh.iter())
+    }
+}
+
+fn owner(issue: &ValidationIssue) -> Uuid {
+    issue.edge_id.or(issue.node_id).unwrap_or_default()
+}
+
+fn diff<'a>(
+    project_id: Uuid,
+    old: impl Iterator<Item = &'a ValidationIssue>,
+    new: impl Iterator<Item = &'a ValidationIssue>,
+) -> ValidationDelta {
+    let old: HashMap<Uuid, &ValidationIssue> = old.map(|i| (i.id, i)).collect();
+    let new: HashMap<Uuid, &ValidationIssue> = new.map(|i| (i.id, i)).collect();
+
+    let mut delta = ValidationDelta::new(project_id);
+    delta.added_issues = new
+        .iter()
+        .filter(|(id, _)| !old.contains_key(id))
+        .map(|(_, issue)| (*issue).clone())
+        .collect();
+    delta.removed_issue_ids = old.keys().filter(|id| !new.contains_key(id)).copied().collect();
+    delta
+}
@@ -1,7 +1,12 @@
-use crate::core::model::{Edge, EdgeKind, Node, NodeData, NodeKind};
+use crate::core::model::{
+    Diagram, DiagramElement, Edge, EdgeKind, Node, NodeData, NodeKind, PortDirection,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
 use uuid::Uuid;
 
+pub mod incremental;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ValidationIssue {
     pub id: Uuid,
@@ -20,24 +25,366 @@ pub enum IssueSeverity {
     Info,
 }
 
+/// Default for the `limits.req_text_chars` setting.
+pub const DEFAULT_REQ_TEXT_CHARS: usize = 2000;
+
+/// Default for the `validation.suspect_stale_days` setting.
+pub const DEFAULT_SUSPECT_STALE_DAYS: i64 = 30;
+
+/// Configurable thresholds used by the rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ValidationLimits {
+    pub req_text_chars: usize,
+    /// Unresolved suspect links older than this raise `SUSPECT_LINKS_STALE`.
+    pub suspect_stale_days: i64,
+}
+
+impl Default for ValidationLimits {
+    fn default() -> Self {
+        Self {
+            req_text_chars: DEFAULT_REQ_TEXT_CHARS,
+            suspect_stale_days: DEFAULT_SUSPECT_STALE_DAYS,
+        }
+    }
+}
+
 /// Run all structural validation rules against the current model.
 /// Returns an empty vec when the model is valid.
 pub fn validate(nodes: &[Node], edges: &[Edge]) -> Vec<ValidationIssue> {
+    validate_with_limits(nodes, edges, &ValidationLimits::default())
+}
+
+/// [`validate`] with project-specific limits.
+pub fn validate_with_limits(
+    nodes: &[Node],
+    edges: &[Edge],
+    limits: &ValidationLimits,
+) -> Vec<ValidationIssue> {
     let mut issues = Vec::new();
 
     for node in nodes {
-        issues.extend(validate_node(node));
+        issues.extend(validate_node(node, limits));
     }
 
     for edge in edges {
-        issues.extend(validate_edge(edge, nodes));
+        issues.extend(validate_edge(edge, nodes, edges));
     }
 
+    issues.extend(unconnected_ports(nodes, edges));
+    issues.extend(hierarchy_cycles(nodes, edges));
+    issues.extend(duplicate_req_ids(nodes));
+
+    assign_stable_ids(&mut issues);
     issues
 }
 
-fn validate_node(node: &Node) -> Vec<ValidationIssue> {
+/// Info issue for each Port in `nodes` that no «connects» edge touches.
+/// `edges` must include every edge incident to those ports.
+pub(crate) fn unconnected_ports(nodes: &[Node], edges: &[Edge]) -> Vec<ValidationIssue> {
+    let connected: HashSet<Uuid> = edges
+        .iter()
+        .filter(|e| e.kind == EdgeKind::Connects)
+        .flat_map(|e| [e.source_id, e.target_id])
+        .collect();
+    nodes
+        .iter()
+        .filter(|n| n.kind == NodeKind::Port && !n.archived && !connected.contains(&n.id))
+        .map(|n| ValidationIssue {
+            id: Uuid::new_v4(),
+            severity: IssueSeverity::Info,
+            code: "PORT_UNCONNECTED",
+            message: format!("Port '{}' is not connected", n.name),
+            node_id: Some(n.id),
+            edge_id: None,
+        })
+        .collect()
+}
+
+/// Codes whose rules span many nodes or edges. Incremental revalidation
+/// can't re-check them, so it defers to a full validation while the cache
+/// holds one.
+pub(crate) const FULL_VALIDATION_CODES: [&str; 2] = ["CYCLE_DETECTED", "REQ_DUPLICATE_ID"];
+
+/// Error issue for each live requirement whose req_id (trimmed, ignoring
+/// case) another live requirement also has. The message names every
+/// requirement sharing it. Requirements without a req_id are left out.
+pub(crate) fn duplicate_req_ids(nodes: &[Node]) -> Vec<ValidationIssue> {
+    let mut by_id: HashMap<String, Vec<(&Node, &str)>> = HashMap::new();
+    for node in nodes.iter().filter(|n| !n.archived) {
+        let NodeData::Requirement(r) = &node.data else {
+            continue;
+        };
+        let Some(req_id) = r.req_id.as_deref().map(str::trim).filter(|id| !id.is_empty()) else {
+            continue;
+        };
+        by_id.entry(req_id.to_lowercase()).or_default().push((node, req_id));
+    }
+
+    let mut groups: Vec<Vec<(&Node, &str)>> =
+        by_id.into_values().filter(|group| group.len() > 1).collect();
+    for group in &mut groups {
+        group.sort_by(|(a, _), (b, _)| a.name.cmp(&b.name).then_with(|| a.id.cmp(&b.id)));
+    }
+    groups.sort_by_key(|group| group[0].1.to_lowercase());
+
     let mut issues = Vec::new();
+    for group in groups {
+        let names = group
+            .iter()
+            .map(|(node, _)| format!("'{}'", node.name))
+            .collect::<Vec<_>>()
+            .join(", ");
+        for (node, req_id) in &group {
+            issues.push(ValidationIssue {
+                id: Uuid::new_v4(),
+                severity: IssueSeverity::Error,
+                code: "REQ_DUPLICATE_ID",
+                message: format!(
+                    "req_id '{req_id}' is used by {} requirements: {names}",
+                    group.len()
+                ),
+                node_id: Some(node.id),
+                edge_id: None,
+            });
+        }
+    }
+    issues
+}
+
+/// Edge kinds that build the requirement and composition hierarchies,
+/// which must not loop back on themselves.
+pub(crate) const HIERARCHY_EDGE_KINDS: [EdgeKind; 3] =
+    [EdgeKind::Refines, EdgeKind::Derives, EdgeKind::Composes];
+
+/// Error issue for each circular «refines» / «derives» / «composes» chain,
+/// one per strongly connected group of nodes, self-loops included. Edges
+/// are followed from parent to child: a «refines» edge points at its
+/// parent, «derives» and «composes» at the child. Archived nodes are left
+/// out.
+///
+/// The message walks the shortest loop through the group, starting from
+/// the first node by label; the issue carries the edge closing that loop.
+/// Spans many edges, so incremental revalidation doesn't re-check it; see
+/// [`FULL_VALIDATION_CODES`].
+pub(crate) fn hierarchy_cycles(nodes: &[Node], edges: &[Edge]) -> Vec<ValidationIssue> {
+    let live: HashMap<Uuid, &Node> = nodes
+        .iter()
+        .filter(|n| !n.archived)
+        .map(|n| (n.id, n))
+        .collect();
+    let order = |a: &Uuid, b: &Uuid| {
+        node_label(live[a])
+            .cmp(node_label(live[b]))
+            .then_with(|| a.cmp(b))
+    };
+
+    let mut children: HashMap<Uuid, Vec<(Uuid, &Edge)>> = HashMap::new();
+    for edge in edges.iter().filter(|e| HIERARCHY_EDGE_KINDS.contains(&e.kind)) {
+        if !live.contains_key(&edge.source_id) || !live.contains_key(&edge.target_id) {
+            continue;
+        }
+        let (parent, child) = if edge.kind == EdgeKind::Refines {
+            (edge.target_id, edge.source_id)
+        } else {
+            (edge.source_id, edge.target_id)
+        };
+        children.entry(parent).or_default().push((child, edge));
+    }
+    for list in children.values_mut() {
+        list.sort_by(|(a, x), (b, y)| order(a, b).then_with(|| x.id.cmp(&y.id)));
+    }
+    let mut roots: Vec<Uuid> = children.keys().copied().collect();
+    roots.sort_by(&order);
+
+    let mut issues = Vec::new();
+    for group in strongly_connected(&roots, &children) {
+        let looped = match group.as_slice() {
+            [only] => children
+                .get(only)
+                .is_some_and(|c| c.iter().any(|(child, _)| child == only)),
+            _ => true,
+        };
+        if !looped {
+            continue;
+        }
+        let Some(start) = group.iter().copied().min_by(&order) else {
+            continue;
+        };
+        let members: HashSet<Uuid> = group.into_iter().collect();
+        let steps = shortest_loop(start, &members, &children);
+        let Some((_, closing)) = steps.last() else {
+            continue;
+        };
+        let mut kinds: Vec<String> = Vec::new();
+        for (_, edge) in &steps {
+            let kind = edge.kind.to_string();
+            if !kinds.contains(&kind) {
+                kinds.push(kind);
+            }
+        }
+        let path = steps
+            .iter()
+            .map(|(node, _)| *node)
+            .chain([start])
+            .map(|id| format!("'{}'", node_label(live[&id])))
+            .collect::<Vec<_>>()
+            .join(" → ");
+        issues.push(ValidationIssue {
+            id: Uuid::new_v4(),
+            severity: IssueSeverity::Error,
+            code: "CYCLE_DETECTED",
+            message: format!("Circular «{}» chain: {path}", kinds.join("» / «")),
+            node_id: None,
+            edge_id: Some(closing.id),
+        });
+    }
+    issues
+}
+
+/// Tarjan's algorithm without recursion, so a deep hierarchy can't
+/// overflow the stack. Every node reachable from `roots` lands in exactly
+/// one group.
+fn strongly_connected(
+    roots: &[Uuid],
+    children: &HashMap<Uuid, Vec<(Uuid, &Edge)>>,
+) -> Vec<Vec<Uuid>> {
+    let mut index: HashMap<Uuid, usize> = HashMap::new();
+    let mut low: HashMap<Uuid, usize> = HashMap::new();
+    let mut on_stack: HashSet<Uuid> = HashSet::new();
+    let mut stack: Vec<Uuid> = Vec::new();
+    let mut groups = Vec::new();
+
+    for &root in roots {
+        if index.contains_key(&root) {
+            continue;
+        }
+        // (node, index of the next child to visit)
+        let mut work = vec![(root, 0)];
+        while let Some((node, next)) = work.pop() {
+            if next == 0 {
+                index.insert(node, index.len());
+                low.insert(node, index[&node]);
+                stack.push(node);
+                on_stack.insert(node);
+            }
+            if let Some((child, _)) = children.get(&node).and_then(|c| c.get(next)) {
+                work.push((node, next + 1));
+                if !index.contains_key(child) {
+                    work.push((*child, 0));
+                } else if on_stack.contains(child) {
+                    low.insert(node, low[&node].min(index[child]));
+                }
+                continue;
+            }
+            if low[&node] == index[&node] {
+                let mut group = Vec::new();
+                while let Some(member) = stack.pop() {
+                    on_stack.remove(&member);
+                    group.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                groups.push(group);
+            }
+            if let Some((parent, _)) = work.last() {
+                low.insert(*parent, low[parent].min(low[&node]));
+            }
+        }
+    }
+    groups
+}
+
+/// The fewest edges leading from `start` back to it within `members`, as
+/// (node, edge leaving it) steps in order. Empty if there is no such loop.
+fn shortest_loop<'a>(
+    start: Uuid,
+    members: &HashSet<Uuid>,
+    children: &HashMap<Uuid, Vec<(Uuid, &'a Edge)>>,
+) -> Vec<(Uuid, &'a Edge)> {
+    let mut reached: HashMap<Uuid, (Uuid, &Edge)> = HashMap::new();
+    let mut queue = VecDeque::from([start]);
+    while let Some(node) = queue.pop_front() {
+        for &(child, edge) in children.get(&node).into_iter().flatten() {
+            if child == start {
+                let mut steps = vec![(node, edge)];
+                let mut at = node;
+                while at != start {
+                    let (parent, edge) = reached[&at];
+                    steps.push((parent, edge));
+                    at = parent;
+                }
+                steps.reverse();
+                return steps;
+            }
+            if members.contains(&child) && !reached.contains_key(&child) {
+                reached.insert(child, (node, edge));
+                queue.push_back(child);
+            }
+        }
+    }
+    Vec::new()
+}
+
+/// Project-level Info issue for suspect links left unresolved past
+/// `limits.suspect_stale_days`. The count comes from the store, since
+/// suspect links aren't part of the node/edge model.
+pub fn stale_suspect_links_issue(stale: i64, limits: &ValidationLimits) -> Option<ValidationIssue> {
+    if stale <= 0 {
+        return None;
+    }
+    let mut issue = ValidationIssue {
+        id: Uuid::new_v4(),
+        severity: IssueSeverity::Info,
+        code: "SUSPECT_LINKS_STALE",
+        message: format!(
+            "{stale} suspect link{} unresolved for more than {} days",
+            if stale == 1 { " has been" } else { "s have been" },
+            limits.suspect_stale_days
+        ),
+        node_id: None,
+        edge_id: None,
+    };
+    assign_stable_ids(std::slice::from_mut(&mut issue));
+    Some(issue)
+}
+
+/// Replace each issue's id with one derived from its code, subject and
+/// message, so the same problem keeps the same id across validation runs
+/// and incremental deltas can refer to it.
+pub(crate) fn assign_stable_ids(issues: &mut [ValidationIssue]) {
+    fn fnv1a(seed: u64, parts: &[&[u8]]) -> u64 {
+        let mut hash = seed;
+        for part in parts {
+            for byte in part.iter().chain(&[0xff]) {
+                hash ^= u64::from(*byte);
+                hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+            }
+        }
+        hash
+    }
+
+    for issue in issues {
+        let node = issue.node_id.unwrap_or_default();
+        let edge = issue.edge_id.unwrap_or_default();
+        let parts: [&[u8]; 4] = [
+            issue.code.as_bytes(),
+            node.as_bytes(),
+            edge.as_bytes(),
+            issue.message.as_bytes(),
+        ];
+        let hi = fnv1a(0xcbf2_9ce4_8422_2325, &parts);
+        let lo = fnv1a(0x6c62_272e_07bb_0142, &parts);
+        issue.id = Uuid::from_u64_pair(hi, lo);
+    }
+}
+
+/// Archived nodes are skipped.
+pub(crate) fn validate_node(node: &Node, limits: &ValidationLimits) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    if node.archived {
+        return issues;
+    }
 
     if node.name.trim().is_empty() {
         issues.push(ValidationIssue {
@@ -65,6 +412,23 @@ fn validate_node(node: &Node) -> Vec<ValidationIssue> {
             });
         }
 
+        let text_chars = r.text.as_deref().map(|t| t.chars().count()).unwrap_or(0);
+        if text_chars > limits.req_text_chars {
+            issues.push(ValidationIssue {
+                id: Uuid::new_v4(),
+                severity: IssueSeverity::Warning,
+                code: "REQ_TEXT_TOO_LONG",
+                message: format!(
+                    "Requirement '{}' text is {} characters; the limit is {}",
+                    r.req_id.as_deref().unwrap_or(&node.name),
+                    text_chars,
+                    limits.req_text_chars
+                ),
+                node_id: Some(node.id),
+                edge_id: None,
+            });
+        }
+
         if r.verification_method.is_none() {
             issues.push(ValidationIssue {
                 id: Uuid::new_v4(),
@@ -83,7 +447,11 @@ fn validate_node(node: &Node) -> Vec<ValidationIssue> {
     issues
 }
 
-fn validate_edge(edge: &Edge, nodes: &[Node]) -> Vec<ValidationIssue> {
+/// `edges` is only used to name the blocks owning a «connects» edge's
+/// ports; a port whose «composes» edge isn't there is named on its own.
+/// Edges touching an archived node are skipped, except that an active node
+/// still satisfying or verifying an archived requirement is reported.
+pub(crate) fn validate_edge(edge: &Edge, nodes: &[Node], edges: &[Edge]) -> Vec<ValidationIssue> {
     let mut issues = Vec::new();
 
     let source = nodes.iter().find(|n| n.id == edge.source_id);
@@ -106,6 +474,26 @@ fn validate_edge(edge: &Edge, nodes: &[Node]) -> Vec<ValidationIssue> {
             node_id: None,
             edge_id: Some(edge.id),
         }),
+        (Some(src), Some(tgt)) if src.archived || tgt.archived => {
+            let still_linked = matches!(edge.kind, EdgeKind::Satisfies | EdgeKind::Verifies);
+            if still_linked && tgt.archived && !src.archived {
+                issues.push(ValidationIssue {
+                    id: Uuid::new_v4(),
+                    severity: IssueSeverity::Info,
+                    code: "ARCHIVED_STILL_LINKED",
+                    message: format!(
+                        "{} '{}' still {} archived {} '{}'",
+                        src.kind,
+                        src.name,
+                        if edge.kind == EdgeKind::Satisfies { "satisfies" } else { "verifies" },
+                        tgt.kind,
+                        node_label(tgt)
+                    ),
+                    node_id: Some(tgt.id),
+                    edge_id: Some(edge.id),
+                });
+            }
+        }
         (Some(src), Some(tgt)) => {
             // Semantic rules per edge kind
             match edge.kind {
@@ -192,6 +580,22 @@ fn validate_edge(edge: &Edge, nodes: &[Node]) -> Vec<ValidationIssue> {
                                     });
                                 }
                             }
+                            // InOut matches anything; two outs or two ins can't flow.
+                            if sp.direction != PortDirection::InOut && sp.direction == tp.direction {
+                                issues.push(ValidationIssue {
+                                    id: Uuid::new_v4(),
+                                    severity: IssueSeverity::Warning,
+                                    code: "PORT_DIRECTION_CONFLICT",
+                                    message: format!(
+                                        "Port direction conflict: {} and {} are both {} ports",
+                                        port_label(src, nodes, edges),
+                                        port_label(tgt, nodes, edges),
+                                        direction_name(&sp.direction)
+                                    ),
+                                    node_id: None,
+                                    edge_id: Some(edge.id),
+                                });
+                            }
                         }
                     }
                 }
@@ -227,5 +631,260 @@ fn validate_edge(edge: &Edge, nodes: &[Node]) -> Vec<ValidationIssue> {
         }
     }
 
+    // With dozens of edges of one kind, the label is what tells them apart.
+    let label = edge.label.trim();
+    if !label.is_empty() {
+        for issue in &mut issues {
+            issue.message = format!("{} (edge \"{}\")", issue.message, label);
+        }
+    }
+
+    issues
+}
+
+/// Info issue for each port-to-port «connects» edge with an InOut end on a
+/// diagram where other ports have an explicit direction — there the
+/// default InOut is more likely an omission than a choice.
+///
+/// Diagram-scoped, so it only runs in a full validation. Its issues carry
+/// no node or edge id and so are left alone by incremental revalidation;
+/// the message names both ends instead.
+pub fn port_direction_unspecified(
+    nodes: &[Node],
+    edges: &[Edge],
+    diagrams: &[Diagram],
+    elements: &[DiagramElement],
+) -> Vec<ValidationIssue> {
+    let direction = |id: Uuid| {
+        nodes.iter().find(|n| n.id == id && !n.archived).and_then(|n| match &n.data {
+            NodeData::Port(p) => Some((n, &p.direction)),
+            _ => None,
+        })
+    };
+
+    let mut issues = Vec::new();
+    let mut reported = HashSet::new();
+    for diagram in diagrams {
+        let shown: HashSet<Uuid> = elements
+            .iter()
+            .filter(|el| el.diagram_id == diagram.id)
+            .map(|el| el.node_id)
+            .collect();
+        let directions_set = shown
+            .iter()
+            .filter_map(|id| direction(*id))
+            .any(|(_, d)| *d != PortDirection::InOut);
+        if !directions_set {
+            continue;
+        }
+
+        for edge in edges.iter().filter(|e| e.kind == EdgeKind::Connects) {
+            if !shown.contains(&edge.source_id) || !shown.contains(&edge.target_id) {
+                continue;
+            }
+            let (Some((src, sd)), Some((tgt, td))) =
+                (direction(edge.source_id), direction(edge.target_id))
+            else {
+                continue;
+            };
+            let unspecified: Vec<&Node> = [(src, sd), (tgt, td)]
+                .into_iter()
+                .filter(|(_, d)| **d == PortDirection::InOut)
+                .map(|(n, _)| n)
+                .collect();
+            if unspecified.is_empty() || !reported.insert(edge.id) {
+                continue;
+            }
+            let which = unspecified
+                .iter()
+                .map(|n| port_label(n, nodes, edges))
+                .collect::<Vec<_>>()
+                .join(" and ");
+            issues.push(ValidationIssue {
+                id: Uuid::new_v4(),
+                severity: IssueSeverity::Info,
+                code: "PORT_DIRECTION_UNSPECIFIED",
+                message: format!(
+                    "«connects» between {} and {} on diagram '{}': {} {} no direction (InOut) \
+                     while other ports there do",
+                    port_label(src, nodes, edges),
+                    port_label(tgt, nodes, edges),
+                    diagram.name,
+                    which,
+                    if unspecified.len() == 1 { "has" } else { "have" }
+                ),
+                node_id: None,
+                edge_id: None,
+            });
+        }
+    }
+    assign_stable_ids(&mut issues);
+    issues
+}
+
+/// Info issue for each req_id mentioned in an active requirement's text
+/// ("see REQ-014") that no requirement in `nodes` has; see
+/// `core::references`. `nodes` must hold every requirement of the project.
+///
+/// Depends on other requirements' ids, so like
+/// [`port_direction_unspecified`] it only runs in a full validation and its
+/// issues carry no node id; the message names the requirement instead.
+pub fn broken_references(nodes: &[Node], patterns: &[String]) -> Vec<ValidationIssue> {
+    use crate::core::references;
+
+    let known: HashSet<&str> = nodes.iter().filter_map(references::own_req_id).collect();
+    let mut issues = Vec::new();
+    for node in nodes.iter().filter(|n| !n.archived) {
+        let Some(text) = references::requirement_text(node) else {
+            continue;
+        };
+        let mentions = references::find_mentions(text, patterns);
+        let mut reported = HashSet::new();
+        for mention in &mentions {
+            let req_id = mention.req_id.as_str();
+            if known.contains(req_id) || !reported.insert(req_id) {
+                continue;
+            }
+            issues.push(ValidationIssue {
+                id: Uuid::new_v4(),
+                severity: IssueSeverity::Info,
+                code: "REQ_BROKEN_REFERENCE",
+                message: format!(
+                    "Requirement '{}' mentions {}, which no requirement has",
+                    node_label(node),
+                    mention.req_id
+                ),
+                node_id: None,
+                edge_id: None,
+            });
+        }
+    }
+    assign_stable_ids(&mut issues);
+    issues
+}
+
+/// Warning for each port that redefines an inherited port of the same name
+/// with an incompatible direction; see `core::inheritance`. Reported once
+/// per pair of ports, however many blocks inherit both.
+///
+/// Spans several blocks, so like [`port_direction_unspecified`] it only
+/// runs in a full validation and its issues carry no node id.
+pub fn redefinition_conflicts(nodes: &[Node], edges: &[Edge]) -> Vec<ValidationIssue> {
+    use crate::core::inheritance;
+
+    let name = |id: Uuid| nodes.iter().find(|n| n.id == id).map_or("?", |n| n.name.as_str());
+    let mut issues = Vec::new();
+    let mut reported = HashSet::new();
+    for block in nodes.iter().filter(|n| n.kind == NodeKind::Block && !n.archived) {
+        let Ok(definition) = inheritance::effective_definition(block.id, nodes, edges, &[]) else {
+            continue;
+        };
+        for conflict in definition.conflicts {
+            if !reported.insert((conflict.port_id, conflict.redefined_port_id)) {
+                continue;
+            }
+            let port = match nodes.iter().find(|n| n.id == conflict.port_id) {
+                Some(p) => port_label(p, nodes, edges),
+                None => format!("'{}'", conflict.port_name),
+            };
+            issues.push(ValidationIssue {
+                id: Uuid::new_v4(),
+                severity: IssueSeverity::Warning,
+                code: "REDEFINITION_CONFLICT",
+                message: format!(
+                    "Port {port} ({}) redefines '{}' of '{}' ({}) with an incompatible direction",
+                    direction_name(&conflict.direction),
+                    conflict.port_name,
+                    name(conflict.ancestor_id),
+                    direction_name(&conflict.redefined_direction),
+                ),
+                node_id: None,
+                edge_id: None,
+            });
+        }
+    }
+    assign_stable_ids(&mut issues);
+    issues
+}
+
+/// A requirement's req_id, or the node's name.
+fn node_label(node: &Node) -> &str {
+    match &node.data {
+        NodeData::Requirement(r) => r.req_id.as_deref().unwrap_or(&node.name),
+        _ => &node.name,
+    }
+}
+
+/// "'name' on 'Owner'", the owner being the Block that «composes» the port.
+fn port_label(port: &Node, nodes: &[Node], edges: &[Edge]) -> String {
+    let owner = edges
+        .iter()
+        .filter(|e| e.kind == EdgeKind::Composes && e.target_id == port.id)
+        .filter_map(|e| nodes.iter().find(|n| n.id == e.source_id))
+        .find(|n| n.kind == NodeKind::Block);
+    match owner {
+        Some(block) => format!("'{}' on '{}'", port.name, block.name),
+        None => format!("'{}'", port.name),
+    }
+}
+
+fn direction_name(direction: &PortDirection) -> &'static str {
+    match direction {
+        PortDirection::In => "in",
+        PortDirection::Out => "out",
+        PortDirection::InOut => "inout",
+    }
+}
+
+/// Check that every block-to-block «connects» edge carries a compatible
+/// signal: the producer's `output_signal_type` must match the consumer's
+/// `input_signal_type`. Blocks without a declared type are not checked.
+/// Used by the simulation runner before handing the model to the engine.
+pub fn validate_signal_flow(nodes: &[Node], edges: &[Edge]) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+
+    for edge in edges.iter().filter(|e| e.kind == EdgeKind::Connects) {
+        let source = nodes.iter().find(|n| n.id == edge.source_id);
+        let target = nodes.iter().find(|n| n.id == edge.target_id);
+        let (Some(src), Some(tgt)) = (source, target) else {
+            continue;
+        };
+        let (NodeData::Block(sb), NodeData::Block(tb)) = (&src.data, &tgt.data) else {
+            continue;
+        };
+
+        let produced = sb
+            .sim_params
+            .as_ref()
+            .and_then(|p| p.output_signal_type.as_deref())
+            .map(str::trim)
+            .filter(|s| !s.is_empty());
+        let consumed = tb
+            .sim_params
+            .as_ref()
+            .and_then(|p| p.input_signal_type.as_deref())
+            .map(str::trim)
+            .filter(|s| !s.is_empty());
+
+        if let (Some(out_ty), Some(in_ty)) = (produced, consumed) {
+            if !out_ty.eq_ignore_ascii_case(in_ty) {
+                issues.push(ValidationIssue {
+                    id: Uuid::new_v4(),
+                    severity: IssueSeverity::Error,
+                    code: "SIGNAL_TYPE_MISMATCH",
+                    message: format!(
+                        "Signal type mismatch on «connects» {}: '{}' outputs «{}» but '{}' expects «{}»",
+                        edge.id, src.name, out_ty, tgt.name, in_ty
+                    ),
+                    node_id: Some(tgt.id),
+                    edge_id: Some(edge.id),
+                });
+            }
+        }
+    }
+
     issues
 }
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,308 @@
+use super::*;
+use crate::core::model::PortData;
+use crate::test_support as fixtures;
+
+fn cycles(issues: &[ValidationIssue]) -> Vec<&ValidationIssue> {
+    issues
+        .iter()
+        .filter(|i| i.code == "CYCLE_DETECTED")
+        .collect()
+}
+
+#[test]
+fn three_requirement_refines_cycle_is_one_error() {
+    let project = fixtures::project("Cycle");
+    let a = fixtures::requirement(project.id, "REQ-A", "A");
+    let b = fixtures::requirement(project.id, "REQ-B", "B");
+    let c = fixtures::requirement(project.id, "REQ-C", "C");
+    let edges = vec![
+        fixtures::edge(project.id, EdgeKind::Refines, a.id, b.id),
+        fixtures::edge(project.id, EdgeKind::Refines, b.id, c.id),
+        fixtures::edge(project.id, EdgeKind::Refines, c.id, a.id),
+    ];
+
+    let issues = validate(&[a, b, c], &edges);
+    let found = cycles(&issues);
+    assert_eq!(found.len(), 1);
+    assert!(matches!(found[0].severity, IssueSeverity::Error));
+    // Parent to child, so against the «refines» arrows.
+    assert_eq!(
+        found[0].message,
+        "Circular «refines» chain: 'REQ-A' → 'REQ-C' → 'REQ-B' → 'REQ-A'"
+    );
+    assert_eq!(found[0].edge_id, Some(edges[0].id));
+}
+
+#[test]
+fn self_loop_and_disjoint_cycles_are_each_reported() {
+    let project = fixtures::project("Cycles");
+    let lonely = fixtures::requirement(project.id, "REQ-1", "Refines itself");
+    let parent = fixtures::requirement(project.id, "REQ-2", "Parent");
+    let child = fixtures::requirement(project.id, "REQ-3", "Child");
+    let whole = fixtures::block(project.id, "Chassis");
+    let part = fixtures::block(project.id, "Frame");
+    let edges = vec![
+        fixtures::edge(project.id, EdgeKind::Refines, lonely.id, lonely.id),
+        // REQ-2 derives REQ-3, yet REQ-2 also refines REQ-3.
+        fixtures::edge(project.id, EdgeKind::Derives, parent.id, child.id),
+        fixtures::edge(project.id, EdgeKind::Refines, parent.id, child.id),
+        fixtures::edge(project.id, EdgeKind::Composes, whole.id, part.id),
+        fixtures::edge(project.id, EdgeKind::Composes, part.id, whole.id),
+    ];
+
+    let issues = validate(&[lonely, parent, child, whole, part], &edges);
+    let mut messages: Vec<&str> = cycles(&issues).iter().map(|i| i.message.as_str()).collect();
+    messages.sort();
+    assert_eq!(
+        messages,
+        [
+            "Circular «composes» chain: 'Chassis' → 'Frame' → 'Chassis'",
+            "Circular «derives» / «refines» chain: 'REQ-2' → 'REQ-3' → 'REQ-2'",
+            "Circular «refines» chain: 'REQ-1' → 'REQ-1'",
+        ]
+    );
+    let self_loop = cycles(&issues)
+        .into_iter()
+        .find(|i| i.message.contains("REQ-1"))
+        .unwrap();
+    assert_eq!(self_loop.edge_id, Some(edges[0].id));
+}
+
+#[test]
+fn diamond_hierarchy_is_not_a_cycle() {
+    let project = fixtures::project("Diamond");
+    let top = fixtures::requirement(project.id, "SYS-1", "Top");
+    let left = fixtures::requirement(project.id, "SUB-1", "Left");
+    let right = fixtures::requirement(project.id, "SUB-2", "Right");
+    let bottom = fixtures::requirement(project.id, "CMP-1", "Bottom");
+    let edges = vec![
+        fixtures::edge(project.id, EdgeKind::Derives, top.id, left.id),
+        fixtures::edge(project.id, EdgeKind::Derives, top.id, right.id),
+        fixtures::edge(project.id, EdgeKind::Refines, bottom.id, left.id),
+        fixtures::edge(project.id, EdgeKind::Refines, bottom.id, right.id),
+    ];
+
+    let issues = validate(&[top, left, right, bottom], &edges);
+    assert!(cycles(&issues).is_empty());
+}
+
+#[test]
+fn archived_node_breaks_the_cycle() {
+    let project = fixtures::project("Archived");
+    let a = fixtures::requirement(project.id, "REQ-A", "A");
+    let mut b = fixtures::requirement(project.id, "REQ-B", "B");
+    b.archived = true;
+    let edges = vec![
+        fixtures::edge(project.id, EdgeKind::Refines, a.id, b.id),
+        fixtures::edge(project.id, EdgeKind::Refines, b.id, a.id),
+    ];
+
+    assert!(cycles(&validate(&[a, b], &edges)).is_empty());
+}
+
+fn duplicates(issues: &[ValidationIssue]) -> Vec<&ValidationIssue> {
+    issues
+        .iter()
+        .filter(|i| i.code == "REQ_DUPLICATE_ID")
+        .collect()
+}
+
+fn renamed(mut node: Node, name: &str) -> Node {
+    node.name = name.to_string();
+    node
+}
+
+#[test]
+fn exact_duplicate_req_ids_flag_each_requirement() {
+    let project = fixtures::project("Duplicates");
+    let first = renamed(
+        fixtures::requirement(project.id, "REQ-014", "A"),
+        "Bus voltage",
+    );
+    let second = renamed(
+        fixtures::requirement(project.id, "REQ-014", "B"),
+        "Bus current",
+    );
+    let other = fixtures::requirement(project.id, "REQ-015", "C");
+
+    let issues = validate(&[first.clone(), second.clone(), other], &[]);
+    let found = duplicates(&issues);
+    assert_eq!(found.len(), 2);
+    for issue in &found {
+        assert!(matches!(issue.severity, IssueSeverity::Error));
+        assert_eq!(
+            issue.message,
+            "req_id 'REQ-014' is used by 2 requirements: 'Bus current', 'Bus voltage'"
+        );
+    }
+    let mut flagged: Vec<Uuid> = found.iter().filter_map(|i| i.node_id).collect();
+    let mut expected = vec![first.id, second.id];
+    flagged.sort();
+    expected.sort();
+    assert_eq!(flagged, expected);
+}
+
+#[test]
+fn req_ids_differing_in_case_or_padding_are_duplicates() {
+    let project = fixtures::project("Normalized");
+    let upper = fixtures::requirement(project.id, "REQ-014", "A");
+    let lower = fixtures::requirement(project.id, "req-014", "B");
+    let padded = fixtures::requirement(project.id, "  REQ-014 ", "C");
+    let mut archived = fixtures::requirement(project.id, "REQ-014", "Gone");
+    archived.archived = true;
+    // Blank and missing req_ids are never duplicates of each other.
+    let blank = [
+        fixtures::requirement(project.id, "  ", "D"),
+        fixtures::requirement(project.id, "", "E"),
+    ];
+    let mut unset = [
+        fixtures::requirement(project.id, "REQ-998", "F"),
+        fixtures::requirement(project.id, "REQ-999", "G"),
+    ];
+    for node in &mut unset {
+        if let NodeData::Requirement(r) = &mut node.data {
+            r.req_id = None;
+        }
+    }
+
+    let mut nodes = vec![upper, lower, padded, archived];
+    nodes.extend(blank);
+    nodes.extend(unset);
+    let issues = validate(&nodes, &[]);
+    let found = duplicates(&issues);
+    assert_eq!(found.len(), 3);
+    assert!(found[0]
+        .message
+        .ends_with("is used by 3 requirements: '  REQ-014 ', 'REQ-014', 'req-014'"));
+    // Each issue quotes its own requirement's req_id, trimmed.
+    let mut ids: Vec<&str> = found
+        .iter()
+        .map(|i| i.message.split('\'').nth(1).unwrap())
+        .collect();
+    ids.sort();
+    assert_eq!(ids, ["REQ-014", "REQ-014", "req-014"]);
+}
+
+/// PORT_DIRECTION_CONFLICT messages for a «connects» edge from an `out`
+/// port on "Source" to an `into` port on "Sink".
+fn direction_conflicts(out: PortDirection, into: PortDirection) -> Vec<String> {
+    let project = fixtures::project("Ports");
+    let port = |name: &str, direction: PortDirection| {
+        let mut node = fixtures::node(project.id, NodeKind::Port, name);
+        node.data = NodeData::Port(PortData {
+            direction,
+            ..Default::default()
+        });
+        node
+    };
+    let source = fixtures::block(project.id, "Source");
+    let sink = fixtures::block(project.id, "Sink");
+    let (tx, rx) = (port("tx", out), port("rx", into));
+    let edges = vec![
+        fixtures::edge(project.id, EdgeKind::Composes, source.id, tx.id),
+        fixtures::edge(project.id, EdgeKind::Composes, sink.id, rx.id),
+        fixtures::edge(project.id, EdgeKind::Connects, tx.id, rx.id),
+    ];
+
+    validate(&[source, sink, tx, rx], &edges)
+        .into_iter()
+        .filter(|i| i.code == "PORT_DIRECTION_CONFLICT")
+        .map(|i| i.message)
+        .collect()
+}
+
+#[test]
+fn out_port_to_in_port_is_fine() {
+    assert!(direction_conflicts(PortDirection::Out, PortDirection::In).is_empty());
+}
+
+#[test]
+fn out_port_to_out_port_warns_naming_both_ends() {
+    assert_eq!(
+        direction_conflicts(PortDirection::Out, PortDirection::Out),
+        ["Port direction conflict: 'tx' on 'Source' and 'rx' on 'Sink' are both out ports"]
+    );
+}
+
+#[test]
+fn inout_port_matches_an_out_port() {
+    assert!(direction_conflicts(PortDirection::InOut, PortDirection::Out).is_empty());
+}
+
+/// Benchmark, not a check: run with
+/// `cargo test --release bench_incremental -- --ignored --nocapture`.
+/// Re-validating one requirement and its edges in a 10k-node project should
+/// stay in the low milliseconds, as `revalidate_touched` does per edit.
+#[test]
+#[ignore]
+fn bench_incremental_revalidation_on_a_10k_node_project() {
+    use incremental::ValidationCache;
+    use std::time::{Duration, Instant};
+
+    let project = fixtures::project("Bench");
+    let mut nodes = Vec::new();
+    let mut edges = Vec::new();
+    for i in 0..2_000 {
+        let block = fixtures::block(project.id, &format!("Block {i}"));
+        let test = fixtures::test_case(project.id, &format!("Test {i}"));
+        for j in 0..3 {
+            let req = fixtures::requirement(
+                project.id,
+                &format!("REQ-{i:04}-{j}"),
+                "The unit shall operate.",
+            );
+            edges.push(fixtures::edge(
+                project.id,
+                EdgeKind::Satisfies,
+                block.id,
+                req.id,
+            ));
+            edges.push(fixtures::edge(
+                project.id,
+                EdgeKind::Verifies,
+                test.id,
+                req.id,
+            ));
+            nodes.push(req);
+        }
+        nodes.push(block);
+        nodes.push(test);
+    }
+    assert_eq!(nodes.len(), 10_000);
+    let limits = ValidationLimits::default();
+    let mut cache = ValidationCache::default();
+    cache.reconcile(project.id, &validate_with_limits(&nodes, &edges, &limits));
+
+    // What revalidate_touched passes for an edit of one requirement.
+    let target = nodes[0].id;
+    let incident: Vec<Edge> = edges
+        .iter()
+        .filter(|e| e.source_id == target || e.target_id == target)
+        .cloned()
+        .collect();
+    let mut touched = vec![target];
+    touched.extend(incident.iter().map(|e| e.id));
+    let around: Vec<Node> = nodes
+        .iter()
+        .filter(|n| {
+            n.id == target
+                || incident
+                    .iter()
+                    .any(|e| e.source_id == n.id || e.target_id == n.id)
+        })
+        .cloned()
+        .collect();
+
+    let mut samples: Vec<Duration> = (0..200)
+        .map(|_| {
+            let start = Instant::now();
+            cache.revalidate(project.id, &touched, &around, &incident, &limits);
+            start.elapsed()
+        })
+        .collect();
+    samples.sort();
+    let median = samples[samples.len() / 2];
+    let p99 = samples[samples.len() * 99 / 100];
+    println!("incremental revalidation, 10k nodes: median {median:?}, p99 {p99:?}");
+    assert!(median < Duration::from_millis(3), "median {median:?}");
+}
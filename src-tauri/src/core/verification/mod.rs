@@ -0,0 +1,198 @@
+/// Rule-based verification method inference.
+///
+/// Most requirements telegraph how they'll be verified ("shall be measured"
+/// → Test, "shall be documented" → Inspection). Rules are lowercase phrases
+/// matched as whole words against the requirement text; `*` matches any run
+/// of text within the requirement, so "analysis * show" covers "analysis
+/// shall show".
+/// Projects can add rules through the `verification.rules` setting.
+use crate::core::model::{Node, NodeData, VerificationMethod};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Proposals at or above this confidence are applied when not a dry run.
+pub const APPLY_THRESHOLD: f64 = 0.6;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerificationRule {
+    pub pattern: String,
+    pub method: VerificationMethod,
+    /// 0..=1 — how strongly a match implies `method`.
+    pub confidence: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerificationProposal {
+    pub node_id: Uuid,
+    pub req_id: Option<String>,
+    pub name: String,
+    pub current: Option<VerificationMethod>,
+    /// `None` when no rule matched.
+    pub proposed: Option<VerificationMethod>,
+    pub confidence: f64,
+    /// Patterns of the rules that voted for `proposed`.
+    pub matched_rules: Vec<String>,
+    pub applied: bool,
+}
+
+/// The built-in ruleset, one block per verification method.
+pub fn default_rules() -> Vec<VerificationRule> {
+    let rule = |pattern: &str, method: VerificationMethod, confidence: f64| VerificationRule {
+        pattern: pattern.to_string(),
+        method,
+        confidence,
+    };
+    use VerificationMethod::*;
+    vec![
+        // Test — measured or exercised against a pass/fail threshold.
+        rule("shall be measured", Test, 0.8),
+        rule("shall be tested", Test, 0.85),
+        rule("when tested", Test, 0.75),
+        rule("under test", Test, 0.6),
+        rule("test * shall", Test, 0.5),
+        rule("within * ms", Test, 0.45),
+        rule("within * seconds", Test, 0.45),
+        rule("at least * per second", Test, 0.45),
+        rule("latency", Test, 0.35),
+        rule("throughput", Test, 0.35),
+        // Inspection — documents, markings, physical attributes.
+        rule("shall be documented", Inspection, 0.8),
+        rule("shall be labeled", Inspection, 0.75),
+        rule("shall be labelled", Inspection, 0.75),
+        rule("shall be marked", Inspection, 0.7),
+        rule("shall be painted", Inspection, 0.7),
+        rule("shall be delivered", Inspection, 0.5),
+        rule("shall provide * documentation", Inspection, 0.6),
+        rule("shall comply with", Inspection, 0.45),
+        rule("drawing", Inspection, 0.35),
+        // Analysis — predicted rather than directly observed.
+        rule("analysis * show", Analysis, 0.85),
+        rule("shall be analyzed", Analysis, 0.8),
+        rule("shall be analysed", Analysis, 0.8),
+        rule("shall be calculated", Analysis, 0.75),
+        rule("shall be predicted", Analysis, 0.7),
+        rule("mtbf", Analysis, 0.6),
+        rule("reliability", Analysis, 0.45),
+        rule("over the lifetime", Analysis, 0.5),
+        rule("worst case", Analysis, 0.45),
+        // Demonstration — an operator or user performs a function.
+        rule("operator shall be able to", Demonstration, 0.8),
+        rule("user shall be able to", Demonstration, 0.8),
+        rule("shall be able to", Demonstration, 0.5),
+        rule("shall allow the", Demonstration, 0.5),
+        rule("shall display", Demonstration, 0.55),
+        rule("shall provide a * interface", Demonstration, 0.45),
+    ]
+}
+
+/// Score `text` against `rules` and return the winning method, its
+/// confidence and the patterns that matched it.
+///
+/// Matches for the same method combine as independent evidence
+/// (1 − Π(1 − c)); a competing method discounts the winner.
+pub fn infer_method(
+    text: &str,
+    rules: &[VerificationRule],
+) -> Option<(VerificationMethod, f64, Vec<String>)> {
+    let haystack = normalize(text);
+    if haystack.is_empty() {
+        return None;
+    }
+
+    let mut scores: Vec<(VerificationMethod, f64, Vec<String>)> = Vec::new();
+    for rule in rules {
+        if !pattern_matches(&haystack, &rule.pattern) {
+            continue;
+        }
+        let c = rule.confidence.clamp(0.0, 1.0);
+        match scores.iter_mut().find(|(m, _, _)| *m == rule.method) {
+            Some((_, miss, patterns)) => {
+                *miss *= 1.0 - c;
+                patterns.push(rule.pattern.clone());
+            }
+            None => scores.push((rule.method.clone(), 1.0 - c, vec![rule.pattern.clone()])),
+        }
+    }
+
+    let mut ranked: Vec<(VerificationMethod, f64, Vec<String>)> = scores
+        .into_iter()
+        .map(|(m, miss, patterns)| (m, 1.0 - miss, patterns))
+        .collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let runner_up = ranked.get(1).map(|r| r.1).unwrap_or(0.0);
+    let (method, score, patterns) = ranked.into_iter().next()?;
+    Some((method, score * (1.0 - runner_up / 2.0), patterns))
+}
+
+/// Build a proposal for every requirement in `nodes`. When `only_missing`
+/// is set, requirements that already have a method are skipped.
+pub fn propose(
+    nodes: &[Node],
+    rules: &[VerificationRule],
+    only_missing: bool,
+) -> Vec<VerificationProposal> {
+    nodes
+        .iter()
+        .filter_map(|node| {
+            let NodeData::Requirement(req) = &node.data else {
+                return None;
+            };
+            if only_missing && req.verification_method.is_some() {
+                return None;
+            }
+            let text = req.text.as_deref().unwrap_or(&node.description);
+            let inferred = infer_method(text, rules);
+            Some(VerificationProposal {
+                node_id: node.id,
+                req_id: req.req_id.clone(),
+                name: node.name.clone(),
+                current: req.verification_method.clone(),
+                proposed: inferred.as_ref().map(|(m, _, _)| m.clone()),
+                confidence: inferred.as_ref().map(|(_, c, _)| *c).unwrap_or(0.0),
+                matched_rules: inferred.map(|(_, _, p)| p).unwrap_or_default(),
+                applied: false,
+            })
+        })
+        .collect()
+}
+
+fn normalize(s: &str) -> String {
+    s.to_lowercase().split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Ordered match of whole words where `*` spans any text, so "test"
+/// matches neither "tested" nor "latest".
+fn pattern_matches(haystack: &str, pattern: &str) -> bool {
+    let pattern = normalize(pattern);
+    let mut rest = haystack;
+    for piece in pattern.split('*').map(str::trim).filter(|p| !p.is_empty()) {
+        match find_word(rest, piece) {
+            Some(pos) => rest = &rest[pos + piece.len()..],
+            None => return false,
+        }
+    }
+    !pattern.trim_matches(|c: char| c == '*' || c.is_whitespace()).is_empty()
+}
+
+/// First offset of `piece` in `haystack` not inside a longer word.
+fn find_word(haystack: &str, piece: &str) -> Option<usize> {
+    let is_word = |c: Option<char>| c.is_some_and(char::is_alphanumeric);
+    let mut from = 0;
+    while let Some(found) = haystack[from..].find(piece) {
+        let start = from + found;
+        let end = start + piece.len();
+        let before = haystack[..start].chars().next_back();
+        let after = haystack[end..].chars().next();
+        let starts_word = !(is_word(before) && is_word(piece.chars().next()));
+        let ends_word = !(is_word(after) && is_word(piece.chars().next_back()));
+        if starts_word && ends_word {
+            return Some(start);
+        }
+        from = start + haystack[start..].chars().next().map_or(1, char::len_utf8);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests;
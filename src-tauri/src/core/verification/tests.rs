@@ -0,0 +1,78 @@
+use super::*;
+
+fn inferred(text: &str) -> Option<VerificationMethod> {
+    infer_method(text, &default_rules()).map(|(method, _, _)| method)
+}
+
+#[test]
+fn test_rules_match_whole_words() {
+    assert_eq!(
+        inferred("The seal shall be tested at 10 bar."),
+        Some(VerificationMethod::Test)
+    );
+    assert_eq!(
+        inferred("Switchover shall complete within 50 ms."),
+        Some(VerificationMethod::Test)
+    );
+    // "test" inside "latest", "ms" inside "systems".
+    assert_eq!(
+        inferred("The latest firmware shall run within all systems."),
+        None
+    );
+}
+
+#[test]
+fn inspection_rules_match_whole_words() {
+    assert_eq!(
+        inferred("Each connector shall be labeled with its pin-out."),
+        Some(VerificationMethod::Inspection)
+    );
+    assert_eq!(
+        inferred("Interfaces are shown on the drawing."),
+        Some(VerificationMethod::Inspection)
+    );
+    assert_eq!(inferred("Withdrawing the probe is manual."), None);
+}
+
+#[test]
+fn analysis_rules_match_whole_words() {
+    assert_eq!(
+        inferred("Analysis shall show a margin of 20 %."),
+        Some(VerificationMethod::Analysis)
+    );
+    assert_eq!(
+        inferred("The MTBF shall exceed 10,000 hours."),
+        Some(VerificationMethod::Analysis)
+    );
+    assert_eq!(inferred("Reanalysis of the showroom is optional."), None);
+}
+
+#[test]
+fn demonstration_rules_match_whole_words() {
+    assert_eq!(
+        inferred("The operator shall be able to reset the alarm."),
+        Some(VerificationMethod::Demonstration)
+    );
+    assert_eq!(
+        inferred("The panel shall display the bus voltage."),
+        Some(VerificationMethod::Demonstration)
+    );
+    assert_eq!(inferred("The panel shall displayport-connect."), None);
+}
+
+#[test]
+fn wildcards_and_punctuation_still_match() {
+    assert!(pattern_matches(
+        &normalize("Shall be measured, then logged."),
+        "shall be measured"
+    ));
+    assert!(pattern_matches(
+        &normalize("analysis (FEA) shall show it"),
+        "analysis * show"
+    ));
+    assert!(!pattern_matches(
+        &normalize("analysis shall showcase"),
+        "analysis * show"
+    ));
+    assert!(!pattern_matches("anything", "*"));
+}
@@ -0,0 +1,164 @@
+/// `[[...]]` links in subsystem knowledge pages, e.g. `[[REQ-014]]`,
+/// `[[Block: Power Distribution]]` or `[[REQ-014|the bus voltage limit]]`.
+///
+/// A link names its target by req_id or by name, optionally behind a node
+/// kind ("Block:", "Use Case:"); text after `|` is only display text.
+/// Targets resolve by req_id, then exact name, then case-insensitive name,
+/// then fuzzy name. Offsets and lengths count characters, as in
+/// `core::references`.
+use crate::core::model::{Node, NodeKind};
+use crate::core::search::fuzzy_search;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Fuzzy matches below this score leave the link unresolved.
+pub const FUZZY_MIN_SCORE: f64 = 0.6;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WikiLink {
+    /// Character offset of the opening `[[`.
+    pub offset: usize,
+    /// Length in characters, brackets included.
+    pub length: usize,
+    /// Everything between the brackets.
+    pub text: String,
+    pub kind: Option<NodeKind>,
+    /// The req_id or name looked up.
+    pub target: String,
+    pub alias: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkMatch {
+    ReqId,
+    Name,
+    Fuzzy,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolvedLink {
+    #[serde(flatten)]
+    pub link: WikiLink,
+    /// `None` for a link nothing matched.
+    pub node_id: Option<Uuid>,
+    pub node_name: Option<String>,
+    pub matched_by: Option<LinkMatch>,
+}
+
+/// Links in `body`, in order. A link doesn't span lines or nest.
+pub fn parse_links(body: &str) -> Vec<WikiLink> {
+    let chars: Vec<char> = body.chars().collect();
+    let mut links = Vec::new();
+    let mut i = 0;
+    while i + 1 < chars.len() {
+        if chars[i] == '[' && chars[i + 1] == '[' {
+            let start = i + 2;
+            let mut end = start;
+            while end < chars.len() && !matches!(chars[end], '[' | ']' | '\n') {
+                end += 1;
+            }
+            let closed = chars.get(end) == Some(&']') && chars.get(end + 1) == Some(&']');
+            if closed {
+                let text: String = chars[start..end].iter().collect();
+                if let Some(link) = parse_link(i, end + 2 - i, text) {
+                    links.push(link);
+                    i = end + 2;
+                    continue;
+                }
+            }
+        }
+        i += 1;
+    }
+    links
+}
+
+fn parse_link(offset: usize, length: usize, text: String) -> Option<WikiLink> {
+    let (target, alias) = match text.split_once('|') {
+        Some((target, alias)) => (target, Some(alias.trim().to_string())),
+        None => (text.as_str(), None),
+    };
+    let (kind, target) = match target.split_once(':') {
+        Some((prefix, rest)) => match parse_kind(prefix) {
+            Some(kind) => (Some(kind), rest),
+            None => (None, target),
+        },
+        None => (None, target),
+    };
+    let target = target.trim().to_string();
+    if target.is_empty() {
+        return None;
+    }
+    Some(WikiLink {
+        offset,
+        length,
+        kind,
+        target,
+        alias: alias.filter(|a| !a.is_empty()),
+        text,
+    })
+}
+
+/// "Block", "use case" and "Test-Case" name kinds; anything else is part
+/// of the target ("Note: ...").
+fn parse_kind(prefix: &str) -> Option<NodeKind> {
+    let key: String = prefix
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c == ' ' || c == '-' { '_' } else { c })
+        .collect();
+    crate::core::store::parse_node_kind(&key).ok()
+}
+
+/// Each link with its target among `nodes`, which should be the project's
+/// live nodes.
+pub fn resolve_links(links: Vec<WikiLink>, nodes: &[Node]) -> Vec<ResolvedLink> {
+    links
+        .into_iter()
+        .map(|link| {
+            let candidates: Vec<&Node> = nodes
+                .iter()
+                .filter(|n| link.kind.is_none() || link.kind.as_ref() == Some(&n.kind))
+                .collect();
+            let found = find_target(&link.target, &candidates);
+            ResolvedLink {
+                node_id: found.map(|(n, _)| n.id),
+                node_name: found.map(|(n, _)| n.name.clone()),
+                matched_by: found.map(|(_, m)| m),
+                link,
+            }
+        })
+        .collect()
+}
+
+fn find_target<'a>(target: &str, candidates: &[&'a Node]) -> Option<(&'a Node, LinkMatch)> {
+    if let Some(n) = candidates
+        .iter()
+        .copied()
+        .find(|n| crate::core::references::own_req_id(n).is_some_and(|r| r == target))
+    {
+        return Some((n, LinkMatch::ReqId));
+    }
+    if let Some(n) = candidates.iter().copied().find(|n| n.name.trim() == target) {
+        return Some((n, LinkMatch::Name));
+    }
+    let lower = target.to_lowercase();
+    if let Some(n) = candidates
+        .iter()
+        .copied()
+        .find(|n| n.name.trim().to_lowercase() == lower)
+    {
+        return Some((n, LinkMatch::Name));
+    }
+    let pool: Vec<Node> = candidates.iter().copied().cloned().collect();
+    let best = fuzzy_search(&pool, target, 1).into_iter().next()?;
+    if best.score < FUZZY_MIN_SCORE {
+        return None;
+    }
+    candidates
+        .iter()
+        .copied()
+        .find(|n| n.id == best.node_id)
+        .map(|n| (n, LinkMatch::Fuzzy))
+}
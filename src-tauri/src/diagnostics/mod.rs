@@ -0,0 +1,292 @@
+/// Command and query timings for diagnosing "the app feels slow".
+///
+/// Every Tauri command is wrapped in a `tracing` span with target
+/// [`COMMAND_TARGET`], and the heavy store reads in one with target
+/// [`QUERY_TARGET`]. [`DiagnosticsLayer`] times those spans and keeps the
+/// results in a ring buffer; when the `diagnostics.enabled` setting is on
+/// it also appends each one as a JSON line to `diagnostics.log` in the app
+/// data directory.
+///
+/// Spans skip all arguments. Only the operation name, the project id,
+/// a row count where one is recorded and whether it failed are kept —
+/// never names, text or error messages.
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Global setting turning file logging on ("true") or off.
+pub const ENABLED_SETTING: &str = "diagnostics.enabled";
+
+/// Span target of Tauri commands.
+pub const COMMAND_TARGET: &str = "command";
+
+/// Span target of timed store queries.
+pub const QUERY_TARGET: &str = "query";
+
+/// File, in the app data directory, timings are appended to.
+pub const LOG_FILE: &str = "diagnostics.log";
+
+/// Timings kept in memory; older ones are dropped.
+const CAPACITY: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationKind {
+    Command,
+    Query,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OperationTiming {
+    pub kind: OperationKind,
+    /// Command or store method name.
+    pub name: String,
+    /// The project argument, or for a query the enclosing command's.
+    pub project_id: Option<String>,
+    /// For a query, the command it ran under.
+    pub command: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: f64,
+    pub ok: bool,
+    /// Rows returned, for queries that record it.
+    pub rows: Option<u64>,
+}
+
+pub struct Diagnostics {
+    recent: Mutex<VecDeque<OperationTiming>>,
+    log_path: PathBuf,
+    file_logging: AtomicBool,
+}
+
+impl Diagnostics {
+    pub fn new(log_path: PathBuf) -> Arc<Self> {
+        Arc::new(Self {
+            recent: Mutex::new(VecDeque::with_capacity(CAPACITY)),
+            log_path,
+            file_logging: AtomicBool::new(false),
+        })
+    }
+
+    pub fn log_path(&self) -> &Path {
+        &self.log_path
+    }
+
+    pub fn file_logging(&self) -> bool {
+        self.file_logging.load(Ordering::Relaxed)
+    }
+
+    pub fn set_file_logging(&self, enabled: bool) {
+        self.file_logging.store(enabled, Ordering::Relaxed);
+    }
+
+    /// The latest `limit` command timings, newest first.
+    pub fn recent_commands(&self, limit: usize) -> Vec<OperationTiming> {
+        let recent = self.recent.lock().unwrap();
+        recent
+            .iter()
+            .rev()
+            .filter(|t| t.kind == OperationKind::Command)
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    /// Commands and queries that took at least `threshold_ms`, slowest
+    /// first.
+    pub fn slow_operations(&self, threshold_ms: f64) -> Vec<OperationTiming> {
+        let mut slow: Vec<OperationTiming> = self
+            .recent
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|t| t.duration_ms >= threshold_ms)
+            .cloned()
+            .collect();
+        slow.sort_by(|a, b| b.duration_ms.total_cmp(&a.duration_ms));
+        slow
+    }
+
+    fn push(&self, timing: OperationTiming) {
+        if self.file_logging() {
+            // Losing a line is better than failing or slowing the command.
+            if let Ok(line) = serde_json::to_string(&timing) {
+                let _ = std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&self.log_path)
+                    .and_then(|mut file| writeln!(file, "{line}"));
+            }
+        }
+        let mut recent = self.recent.lock().unwrap();
+        if recent.len() == CAPACITY {
+            recent.pop_front();
+        }
+        recent.push_back(timing);
+    }
+}
+
+/// Whether a `diagnostics.enabled` value turns file logging on.
+pub fn enabled_from_setting(raw: Option<&str>) -> bool {
+    matches!(
+        raw.map(|v| v.trim().trim_matches('"').to_lowercase())
+            .as_deref(),
+        Some("true" | "1" | "yes" | "on")
+    )
+}
+
+/// Create the diagnostics sink logging to `data_dir` and install its layer
+/// as the global subscriber. File logging stays off until the setting is
+/// read.
+pub fn install(data_dir: &Path) -> Arc<Diagnostics> {
+    let diagnostics = Diagnostics::new(data_dir.join(LOG_FILE));
+    let subscriber = tracing_subscriber::registry().with(DiagnosticsLayer {
+        diagnostics: diagnostics.clone(),
+    });
+    if let Err(e) = tracing::subscriber::set_global_default(subscriber) {
+        eprintln!("warning: command timings disabled: {e}");
+    }
+    diagnostics
+}
+
+/// Times spans with [`COMMAND_TARGET`] or [`QUERY_TARGET`] and hands them
+/// to [`Diagnostics`] when they close.
+pub struct DiagnosticsLayer {
+    diagnostics: Arc<Diagnostics>,
+}
+
+/// Kept in the span's extensions while it is open.
+struct SpanTiming {
+    kind: OperationKind,
+    name: &'static str,
+    fields: SpanFields,
+    command: Option<String>,
+    started: Instant,
+    started_at: DateTime<Utc>,
+    failed: bool,
+}
+
+#[derive(Default)]
+struct SpanFields {
+    project_id: Option<String>,
+    rows: Option<u64>,
+}
+
+impl Visit for SpanFields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "project_id" {
+            self.project_id = Some(value.to_string());
+        }
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        if field.name() == "rows" {
+            self.rows = Some(value);
+        }
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.record_u64(field, value.max(0) as u64);
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        // `%project_id` arrives here, printing without quotes.
+        if field.name() == "project_id" {
+            self.project_id = Some(format!("{value:?}"));
+        }
+    }
+}
+
+impl<S> Layer<S> for DiagnosticsLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let metadata = attrs.metadata();
+        let kind = match metadata.target() {
+            COMMAND_TARGET => OperationKind::Command,
+            QUERY_TARGET => OperationKind::Query,
+            _ => return,
+        };
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut fields = SpanFields::default();
+        attrs.record(&mut fields);
+        let enclosing = span.scope().skip(1).find_map(|parent| {
+            let extensions = parent.extensions();
+            extensions
+                .get::<SpanTiming>()
+                .filter(|t| t.kind == OperationKind::Command)
+                .map(|t| (t.name.to_string(), t.fields.project_id.clone()))
+        });
+        let command = enclosing.map(|(name, project_id)| {
+            if fields.project_id.is_none() {
+                fields.project_id = project_id;
+            }
+            name
+        });
+        span.extensions_mut().insert(SpanTiming {
+            kind,
+            name: metadata.name(),
+            fields,
+            command,
+            started: Instant::now(),
+            started_at: Utc::now(),
+            failed: false,
+        });
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut extensions = span.extensions_mut();
+        if let Some(timing) = extensions.get_mut::<SpanTiming>() {
+            values.record(&mut timing.fields);
+        }
+    }
+
+    /// An error event inside a span (`instrument(err)` emits one for an
+    /// `Err` return) marks it failed. The message itself is not kept.
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        if *event.metadata().level() != Level::ERROR {
+            return;
+        }
+        let Some(span) = ctx.event_span(event) else {
+            return;
+        };
+        let mut extensions = span.extensions_mut();
+        if let Some(timing) = extensions.get_mut::<SpanTiming>() {
+            timing.failed = true;
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let Some(timing) = span.extensions_mut().remove::<SpanTiming>() else {
+            return;
+        };
+        self.diagnostics.push(OperationTiming {
+            kind: timing.kind,
+            name: timing.name.to_string(),
+            project_id: timing.fields.project_id,
+            command: timing.command,
+            started_at: timing.started_at,
+            duration_ms: timing.started.elapsed().as_secs_f64() * 1000.0,
+            ok: !timing.failed,
+            rows: timing.fields.rows,
+        });
+    }
+}
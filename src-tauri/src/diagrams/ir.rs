@@ -6,7 +6,9 @@
 /// pipeline's GROUP phase.  When `groups` is non-empty the frontend renders
 /// compound bounding boxes (labelled sections) around the member nodes,
 /// similar to the bracketed swim-lane sections in workflow studio tools.
-use crate::core::model::{DiagramEdgeRoute, DiagramElement, DiagramKind, Edge, Node};
+use crate::core::model::{
+    BlockProperty, DiagramEdgeRoute, DiagramElement, DiagramFilters, DiagramKind, Edge, Node,
+};
 use crate::diagrams::layout::{LayoutPhase, NodeGroup};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -25,6 +27,12 @@ pub struct DiagramIR {
     /// Lets the frontend show a progress state (e.g. skeleton nodes while ELK runs).
     #[serde(default)]
     pub layout_phase: Option<LayoutPhase>,
+    /// View filters applied to `nodes`/`edges`.
+    #[serde(default)]
+    pub filters: DiagramFilters,
+    /// Placed elements left out by `filters`.
+    #[serde(default)]
+    pub hidden_node_count: usize,
 }
 
 /// A logical grouping of nodes displayed as a labelled bounding box.
@@ -53,6 +61,12 @@ pub struct IRNode {
     pub style_overrides: serde_json::Value,
     /// True if this node has a pending AI suggestion ghost
     pub has_suggestion: bool,
+    /// When this node was placed on / last moved within the diagram.
+    pub element_created_at: chrono::DateTime<chrono::Utc>,
+    pub element_modified_at: chrono::DateTime<chrono::Utc>,
+    /// Value properties for the block's properties compartment. Empty for non-blocks.
+    #[serde(default)]
+    pub properties: Vec<BlockProperty>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -77,6 +91,8 @@ pub struct IRPoint {
 /// `groups` may be empty (flat layout) or populated from the GROUP phase.
 /// `layout_phase` records which pipeline phase produced this IR so the
 /// frontend can render an appropriate progress indicator.
+/// Nodes hidden by `filters` are dropped along with any edge touching them,
+/// so the frontend's auto-layout only ever sees visible elements.
 pub fn build_ir(
     diagram_id: Uuid,
     kind: DiagramKind,
@@ -85,35 +101,47 @@ pub fn build_ir(
     edges: &[Edge],
     elements: &[DiagramElement],
     routes: &[DiagramEdgeRoute],
+    properties: &[BlockProperty],
+    filters: &DiagramFilters,
     suggested_node_ids: &[Uuid],
     suggested_edge_ids: &[Uuid],
     groups: &[NodeGroup],
     layout_phase: Option<LayoutPhase>,
 ) -> DiagramIR {
-    let ir_nodes = elements
+    let (visible, hidden): (Vec<(&DiagramElement, &Node)>, Vec<_>) = elements
         .iter()
-        .filter_map(|el| {
-            let node = nodes.iter().find(|n| n.id == el.node_id)?;
-            Some(IRNode {
-                id: node.id,
-                kind: node.kind.to_string(),
-                name: node.name.clone(),
-                description: node.description.clone(),
-                data: serde_json::to_value(&node.data).unwrap_or(serde_json::Value::Null),
-                x: el.x,
-                y: el.y,
-                width: el.width,
-                height: el.height,
-                collapsed: el.collapsed,
-                style_overrides: serde_json::to_value(&el.style_overrides)
-                    .unwrap_or(serde_json::Value::Null),
-                has_suggestion: suggested_node_ids.contains(&node.id),
-            })
+        .filter_map(|el| Some((el, nodes.iter().find(|n| n.id == el.node_id)?)))
+        .partition(|(_, node)| !filters.is_hidden(node));
+    let hidden_node_count = hidden.len();
+
+    let ir_nodes = visible
+        .iter()
+        .map(|&(el, node)| IRNode {
+            id: node.id,
+            kind: node.kind.to_string(),
+            name: node.name.clone(),
+            description: node.description.clone(),
+            data: serde_json::to_value(&node.data).unwrap_or(serde_json::Value::Null),
+            x: el.x,
+            y: el.y,
+            width: el.width,
+            height: el.height,
+            collapsed: el.collapsed,
+            style_overrides: serde_json::to_value(&el.style_overrides)
+                .unwrap_or(serde_json::Value::Null),
+            has_suggestion: suggested_node_ids.contains(&node.id),
+            element_created_at: el.created_at,
+            element_modified_at: el.modified_at,
+            properties: properties
+                .iter()
+                .filter(|p| p.block_node_id == node.id)
+                .cloned()
+                .collect(),
         })
         .collect();
 
-    // Include only edges where both endpoints appear in this diagram
-    let element_node_ids: Vec<Uuid> = elements.iter().map(|e| e.node_id).collect();
+    // Include only edges where both endpoints are visible in this diagram
+    let element_node_ids: Vec<Uuid> = visible.iter().map(|(el, _)| el.node_id).collect();
 
     let ir_edges = edges
         .iter()
@@ -137,7 +165,7 @@ pub fn build_ir(
                 kind: edge.kind.to_string(),
                 source_id: edge.source_id,
                 target_id: edge.target_id,
-                label: edge.label.clone(),
+                label: if filters.show_edge_labels { edge.label.clone() } else { String::new() },
                 waypoints,
                 has_suggestion: suggested_edge_ids.contains(&edge.id),
             }
@@ -161,5 +189,7 @@ pub fn build_ir(
         edges: ir_edges,
         groups: ir_groups,
         layout_phase,
+        filters: filters.clone(),
+        hidden_node_count,
     }
 }
@@ -0,0 +1,112 @@
+/// In-memory side of layout sessions.
+///
+/// While a diagram has an open layout session, geometry writes for the same
+/// element are coalesced: the first write in a `COALESCE_WINDOW` goes
+/// through, later ones are held and only the newest is written when the
+/// window ends. The captured snapshot and session history live in the
+/// `layout_sessions` table; this only tracks the open sessions and the
+/// writes waiting for their window.
+use crate::core::model::DiagramElement;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+pub const COALESCE_WINDOW: Duration = Duration::from_millis(500);
+
+/// What to do with an element write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Throttle {
+    WriteNow,
+    /// Held; the newest held write is due after `flush_after`. `schedule`
+    /// is set for the write that started the wait, which should arrange
+    /// the flush; later ones just replace the held element.
+    Deferred {
+        flush_after: Duration,
+        schedule: bool,
+    },
+}
+
+#[derive(Debug, Default)]
+struct OpenSession {
+    session_id: Uuid,
+    last_write: HashMap<Uuid, Instant>,
+    pending: HashMap<Uuid, DiagramElement>,
+}
+
+/// Open layout sessions by diagram.
+#[derive(Debug, Default)]
+pub struct LayoutJournal {
+    sessions: HashMap<Uuid, OpenSession>,
+}
+
+impl LayoutJournal {
+    /// Starts tracking `session_id` for `diagram_id`, returning the writes
+    /// still held for a session it replaces.
+    pub fn open(&mut self, diagram_id: Uuid, session_id: Uuid) -> Vec<DiagramElement> {
+        let previous = self.sessions.insert(
+            diagram_id,
+            OpenSession {
+                session_id,
+                ..Default::default()
+            },
+        );
+        previous
+            .map(|s| s.pending.into_values().collect())
+            .unwrap_or_default()
+    }
+
+    /// The diagram's open session, if any.
+    pub fn session_for(&self, diagram_id: Uuid) -> Option<Uuid> {
+        self.sessions.get(&diagram_id).map(|s| s.session_id)
+    }
+
+    pub fn offer(&mut self, element: &DiagramElement, now: Instant) -> Throttle {
+        let Some(session) = self.sessions.get_mut(&element.diagram_id) else {
+            return Throttle::WriteNow;
+        };
+        let elapsed = session
+            .last_write
+            .get(&element.node_id)
+            .map(|at| now.saturating_duration_since(*at));
+        match elapsed {
+            Some(elapsed) if elapsed < COALESCE_WINDOW => {
+                let schedule = session
+                    .pending
+                    .insert(element.node_id, element.clone())
+                    .is_none();
+                Throttle::Deferred {
+                    flush_after: COALESCE_WINDOW - elapsed,
+                    schedule,
+                }
+            }
+            _ => {
+                session.last_write.insert(element.node_id, now);
+                session.pending.remove(&element.node_id);
+                Throttle::WriteNow
+            }
+        }
+    }
+
+    /// The held write for an element, now due; it counts as written at `now`.
+    pub fn take_pending(
+        &mut self,
+        diagram_id: Uuid,
+        node_id: Uuid,
+        now: Instant,
+    ) -> Option<DiagramElement> {
+        let session = self.sessions.get_mut(&diagram_id)?;
+        let element = session.pending.remove(&node_id)?;
+        session.last_write.insert(node_id, now);
+        Some(element)
+    }
+
+    /// Stops tracking the session, returning its held writes. `None` when
+    /// `session_id` isn't the diagram's open session.
+    pub fn close(&mut self, diagram_id: Uuid, session_id: Uuid) -> Option<Vec<DiagramElement>> {
+        if self.session_for(diagram_id) != Some(session_id) {
+            return None;
+        }
+        let session = self.sessions.remove(&diagram_id)?;
+        Some(session.pending.into_values().collect())
+    }
+}
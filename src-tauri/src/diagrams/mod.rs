@@ -1,3 +1,8 @@
 pub mod ir;
+pub mod journal;
 pub mod layout;
+pub mod routing;
 pub mod sysml;
+
+#[cfg(test)]
+mod tests;
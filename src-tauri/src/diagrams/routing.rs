@@ -0,0 +1,144 @@
+/// Edge route cleanup.
+///
+/// Stored routes are the bend points between an edge's two node anchors;
+/// ELK emits a lot of them and manual nudges add more. `simplify_route`
+/// drops the ones that don't change the path's shape (Ramer–Douglas–Peucker),
+/// and in orthogonal mode first straightens nearly horizontal or vertical
+/// segments so the points between them become exactly collinear.
+///
+/// The first and last waypoints are always kept: they decide where the edge
+/// leaves and enters its nodes.
+use crate::core::model::Point;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Segments within this many degrees of horizontal or vertical are snapped.
+pub const SNAP_DEGREES: f64 = 5.0;
+
+/// Per-edge outcome of `simplify_edge_routes`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteSimplification {
+    pub edge_id: Uuid,
+    pub points_before: usize,
+    pub points_after: usize,
+    pub points_removed: usize,
+}
+
+/// `points` simplified so no removed point was more than `tolerance` away
+/// from the path that replaced it.
+pub fn simplify_route(points: &[Point], tolerance: f64, orthogonal: bool) -> Vec<Point> {
+    if !orthogonal {
+        return rdp(points, tolerance);
+    }
+    // Simplify first so snapping sees the real segments rather than jitter,
+    // then again to drop the points snapping lined up.
+    let snapped = snap_orthogonal(&rdp(points, tolerance), SNAP_DEGREES);
+    rdp(&snapped, tolerance)
+}
+
+/// Ramer–Douglas–Peucker. Routes of two points or fewer come back as they are.
+pub fn rdp(points: &[Point], tolerance: f64) -> Vec<Point> {
+    if points.len() <= 2 {
+        return points.to_vec();
+    }
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+
+    let mut spans = vec![(0, points.len() - 1)];
+    while let Some((first, last)) = spans.pop() {
+        let mut farthest = None;
+        let mut max_distance = tolerance;
+        for (offset, p) in points[first + 1..last].iter().enumerate() {
+            let d = distance_to_segment(p, &points[first], &points[last]);
+            if d > max_distance {
+                max_distance = d;
+                farthest = Some(first + 1 + offset);
+            }
+        }
+        if let Some(i) = farthest {
+            keep[i] = true;
+            spans.push((first, i));
+            spans.push((i, last));
+        }
+    }
+
+    points
+        .iter()
+        .zip(keep)
+        .filter(|(_, kept)| *kept)
+        .map(|(p, _)| p.clone())
+        .collect()
+}
+
+/// Aligns each segment within `max_degrees` of an axis to that axis by
+/// moving interior bend points; the first and last points never move.
+///
+/// A run of consecutive near-horizontal segments shares one y (the
+/// first or last point's when the run reaches an end of the route, else
+/// its first point's), and likewise for x along near-vertical runs. A bend
+/// between a horizontal and a vertical run takes its y from one and its x
+/// from the other, so corners stay corners.
+pub fn snap_orthogonal(points: &[Point], max_degrees: f64) -> Vec<Point> {
+    let mut out = points.to_vec();
+    if points.len() <= 2 {
+        return out;
+    }
+    let last = points.len() - 1;
+    // Per segment i (points i → i+1): Some(true) horizontal, Some(false)
+    // vertical, None neither.
+    let axes: Vec<Option<bool>> = points
+        .windows(2)
+        .map(|w| {
+            let angle = (w[1].y - w[0].y).abs().atan2((w[1].x - w[0].x).abs()).to_degrees();
+            if angle <= max_degrees {
+                Some(true)
+            } else if angle >= 90.0 - max_degrees {
+                Some(false)
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    let mut start = 0;
+    while start < axes.len() {
+        let Some(horizontal) = axes[start] else {
+            start += 1;
+            continue;
+        };
+        let mut end = start;
+        while end + 1 < axes.len() && axes[end + 1] == Some(horizontal) {
+            end += 1;
+        }
+        // The run covers points start..=end + 1.
+        let anchor = if start == 0 {
+            &points[0]
+        } else if end + 1 == last {
+            &points[last]
+        } else {
+            &points[start]
+        };
+        let value = if horizontal { anchor.y } else { anchor.x };
+        for p in &mut out[start.max(1)..=(end + 1).min(last - 1)] {
+            if horizontal {
+                p.y = value;
+            } else {
+                p.x = value;
+            }
+        }
+        start = end + 1;
+    }
+    out
+}
+
+/// Distance from `p` to the segment `a`–`b`.
+fn distance_to_segment(p: &Point, a: &Point, b: &Point) -> f64 {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let len_sq = dx * dx + dy * dy;
+    if len_sq == 0.0 {
+        return (p.x - a.x).hypot(p.y - a.y);
+    }
+    let t = (((p.x - a.x) * dx + (p.y - a.y) * dy) / len_sq).clamp(0.0, 1.0);
+    (p.x - (a.x + t * dx)).hypot(p.y - (a.y + t * dy))
+}
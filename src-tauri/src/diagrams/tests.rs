@@ -0,0 +1,78 @@
+use super::routing::*;
+use crate::core::model::Point;
+
+fn route(points: &[(f64, f64)]) -> Vec<Point> {
+    points.iter().map(|&(x, y)| Point { x, y }).collect()
+}
+
+fn coords(points: &[Point]) -> Vec<(f64, f64)> {
+    points.iter().map(|p| (p.x, p.y)).collect()
+}
+
+#[test]
+fn rdp_drops_collinear_and_near_collinear_points() {
+    let points = route(&[
+        (0.0, 0.0),
+        (5.0, 0.2),
+        (10.0, 0.0),
+        (10.0, 5.0),
+        (10.0, 10.0),
+    ]);
+    assert_eq!(
+        coords(&rdp(&points, 1.0)),
+        vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0)]
+    );
+}
+
+#[test]
+fn a_two_point_route_is_left_alone() {
+    let points = route(&[(0.0, 0.0), (100.0, 3.0)]);
+    for orthogonal in [false, true] {
+        assert_eq!(
+            coords(&simplify_route(&points, 5.0, orthogonal)),
+            vec![(0.0, 0.0), (100.0, 3.0)]
+        );
+    }
+    assert_eq!(coords(&simplify_route(&[], 5.0, true)), vec![]);
+}
+
+#[test]
+fn a_zig_zag_does_not_collapse() {
+    let zig_zag = route(&[
+        (0.0, 0.0),
+        (20.0, 0.0),
+        (20.0, 20.0),
+        (40.0, 20.0),
+        (40.0, 40.0),
+        (60.0, 40.0),
+    ]);
+    for orthogonal in [false, true] {
+        assert_eq!(
+            coords(&simplify_route(&zig_zag, 2.0, orthogonal)),
+            coords(&zig_zag)
+        );
+    }
+}
+
+#[test]
+fn snapping_straightens_bends_but_never_moves_the_ends() {
+    // Nearly orthogonal, with both ends off the axis of their segment.
+    let points = route(&[(0.0, 0.0), (50.0, 1.0), (51.0, 50.0), (100.0, 52.0)]);
+    let snapped = snap_orthogonal(&points, SNAP_DEGREES);
+    assert_eq!(
+        coords(&snapped),
+        vec![(0.0, 0.0), (50.0, 0.0), (50.0, 52.0), (100.0, 52.0)]
+    );
+
+    let simplified = simplify_route(&points, 0.5, true);
+    assert_eq!(coords(&simplified), coords(&snapped));
+}
+
+#[test]
+fn a_horizontal_run_between_two_ends_keeps_the_first_ends_axis() {
+    let points = route(&[(0.0, 0.0), (30.0, 1.0), (60.0, 2.0)]);
+    assert_eq!(
+        coords(&snap_orthogonal(&points, SNAP_DEGREES)),
+        vec![(0.0, 0.0), (30.0, 0.0), (60.0, 2.0)]
+    );
+}
@@ -6,3 +6,12 @@ pub const VALIDATION_UPDATED: &str = "validation:updated";
 pub const AI_SUGGESTION_READY: &str = "ai:suggestion_ready";
 pub const AI_ANALYSIS_READY: &str = "ai:analysis_ready";
 pub const DIAGRAM_LAYOUT_READY: &str = "diagram:layout_ready";
+pub const DB_MAINTENANCE_PROGRESS: &str = "db:maintenance_progress";
+pub const LLM_EXTRACT_PROGRESS: &str = "llm:extract_progress";
+pub const JOB_PROGRESS: &str = "job:progress";
+pub const JOB_COMPLETE: &str = "job:complete";
+pub const PRESENCE_CONFLICT: &str = "presence:conflict";
+pub const DIGEST_READY: &str = "digest:ready";
+pub const STARTUP_PROGRESS: &str = "startup:progress";
+pub const STARTUP_AI_PROVIDER: &str = "startup:ai_provider";
+pub const GRAPHRAG_STATUS: &str = "graphrag:status";
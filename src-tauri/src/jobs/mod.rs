@@ -0,0 +1,150 @@
+/// Background jobs for commands too slow to run inside a single IPC call
+/// (exports of very large models). A job runs on a spawned task, reports
+/// progress on `events::JOB_PROGRESS` and finishes with `events::JOB_COMPLETE`;
+/// the registry in `AppState` lets the UI poll `get_job` after a reload.
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Finished jobs kept for `get_job`; older ones are dropped.
+const MAX_FINISHED_JOBS: usize = 50;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    Export,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn is_finished(self) -> bool {
+        self != JobStatus::Running
+    }
+}
+
+/// Formats the export job can stream to disk.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Json,
+    Xmi,
+}
+
+impl ExportFormat {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "xmi" => Some(Self::Xmi),
+            _ => None,
+        }
+    }
+}
+
+/// Payload of both job events and the return value of the job commands.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    pub id: Uuid,
+    pub kind: JobKind,
+    pub project_id: Uuid,
+    pub status: JobStatus,
+    /// Elements written so far (nodes, then edges).
+    pub done: usize,
+    pub total: usize,
+    /// Destination file; removed again if the job fails or is cancelled.
+    pub result_path: Option<String>,
+    pub error: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Default)]
+pub struct JobRegistry {
+    jobs: HashMap<Uuid, (Job, Arc<AtomicBool>)>,
+}
+
+impl JobRegistry {
+    /// Register a running job. The returned flag is set by [`Self::cancel`];
+    /// the job's task should poll it and stop.
+    pub fn start(
+        &mut self,
+        kind: JobKind,
+        project_id: Uuid,
+        result_path: Option<String>,
+    ) -> (Job, Arc<AtomicBool>) {
+        self.prune();
+        let job = Job {
+            id: Uuid::new_v4(),
+            kind,
+            project_id,
+            status: JobStatus::Running,
+            done: 0,
+            total: 0,
+            result_path,
+            error: None,
+            started_at: Utc::now(),
+            finished_at: None,
+        };
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.jobs.insert(job.id, (job.clone(), cancel.clone()));
+        (job, cancel)
+    }
+
+    pub fn get(&self, id: Uuid) -> Option<Job> {
+        self.jobs.get(&id).map(|(job, _)| job.clone())
+    }
+
+    pub fn set_progress(&mut self, id: Uuid, done: usize, total: usize) -> Option<Job> {
+        let (job, _) = self.jobs.get_mut(&id)?;
+        job.done = done;
+        job.total = total;
+        Some(job.clone())
+    }
+
+    pub fn finish(&mut self, id: Uuid, status: JobStatus, error: Option<String>) -> Option<Job> {
+        let (job, _) = self.jobs.get_mut(&id)?;
+        job.status = status;
+        job.error = error;
+        job.finished_at = Some(Utc::now());
+        Some(job.clone())
+    }
+
+    /// Ask a running job to stop. The job reports `Cancelled` once its task
+    /// has noticed and cleaned up.
+    pub fn cancel(&mut self, id: Uuid) -> Result<Job, String> {
+        let (job, flag) = self
+            .jobs
+            .get(&id)
+            .ok_or_else(|| format!("job {id} not found"))?;
+        if job.status.is_finished() {
+            return Err(format!("job {id} already finished"));
+        }
+        flag.store(true, Ordering::Relaxed);
+        Ok(job.clone())
+    }
+
+    fn prune(&mut self) {
+        let mut finished: Vec<(DateTime<Utc>, Uuid)> = self
+            .jobs
+            .values()
+            .filter_map(|(job, _)| job.finished_at.map(|at| (at, job.id)))
+            .collect();
+        if finished.len() <= MAX_FINISHED_JOBS {
+            return;
+        }
+        finished.sort();
+        for (_, id) in &finished[..finished.len() - MAX_FINISHED_JOBS] {
+            self.jobs.remove(id);
+        }
+    }
+}
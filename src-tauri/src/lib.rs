@@ -4,15 +4,36 @@ use tauri::Manager;
 pub mod ai;
 pub mod commands;
 pub mod core;
+pub mod diagnostics;
 pub mod diagrams;
 pub mod events;
+pub mod jobs;
+pub mod presence;
+pub mod processes;
+pub mod startup;
+#[cfg(test)]
+pub mod test_support;
 
-use ai::provider::{AIProvider, NullProvider};
+use ai::provider::AIProvider;
 use core::store::Store;
 
 pub struct AppState {
     pub store: Store,
     pub ai_provider: Mutex<Arc<dyn AIProvider>>,
+    /// Bundled llama.cpp probe result, filled by `local_llm_info`.
+    pub local_llm: Mutex<Option<commands::LocalLlmInfo>>,
+    /// Per-project validation issues backing incremental revalidation.
+    pub validation_cache: Mutex<core::validation::incremental::ValidationCache>,
+    /// Background jobs (large exports) started from commands.
+    pub jobs: Mutex<jobs::JobRegistry>,
+    /// Baseline snapshots indexed by node id, built on first lookup.
+    pub baseline_index: Mutex<core::baseline::BaselineIndexCache>,
+    /// Child processes spawned by commands, killed on exit.
+    pub processes: Mutex<processes::ProcessRegistry>,
+    /// This machine's presence on the (possibly shared) database.
+    pub presence: presence::LocalPresence,
+    /// Open layout sessions and the element writes they are holding back.
+    pub layout_journal: Mutex<diagrams::journal::LayoutJournal>,
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -26,97 +47,100 @@ pub fn run() {
                 .expect("failed to resolve app data dir");
             std::fs::create_dir_all(&data_dir)?;
 
+            // Command timings, before any command can run.
+            app.manage(diagnostics::install(&data_dir));
+
             let db_path = data_dir.join("systemproduct.db");
             let db_path_str = db_path.to_string_lossy().to_string();
 
-            // Bootstrap async runtime for store initialization
-            let store = tauri::async_runtime::block_on(async {
-                Store::open(&db_path_str)
-                    .await
-                    .expect("failed to open database")
-            });
-
-            // Resolve AI provider: env var → DB active provider → NullProvider.
-            let ai_provider: Arc<dyn AIProvider> = tauri::async_runtime::block_on(async {
-                // Env var always wins
-                let env_key = std::env::var("ANTHROPIC_API_KEY").unwrap_or_default();
-                if !env_key.is_empty() {
-                    return Arc::new(ai::anthropic::AnthropicProvider::new(env_key))
-                        as Arc<dyn AIProvider>;
-                }
-
-                // Check which provider was last saved
-                let saved_provider = store
-                    .get_setting("ai.provider", None)
-                    .await
-                    .unwrap_or(None)
-                    .unwrap_or_default();
-
-                match saved_provider.as_str() {
-                    "anthropic" => {
-                        let key = store
-                            .get_setting("ai.anthropic.api_key", None)
-                            .await
-                            .unwrap_or(None)
-                            .unwrap_or_default();
-                        if !key.is_empty() {
-                            return Arc::new(ai::anthropic::AnthropicProvider::new(key))
-                                as Arc<dyn AIProvider>;
-                        }
-                    }
-                    "ollama" => {
-                        let model = store
-                            .get_setting("ai.ollama.model", None)
-                            .await
-                            .unwrap_or(None)
-                            .unwrap_or_else(|| "qwen2.5:7b".to_string());
-                        let base_url = store
-                            .get_setting("ai.ollama.base_url", None)
-                            .await
-                            .unwrap_or(None);
-                        return Arc::new(ai::ollama::OllamaProvider::new(model, base_url))
-                            as Arc<dyn AIProvider>;
-                    }
-                    _ => {}
-                }
-
-                Arc::new(NullProvider) as Arc<dyn AIProvider>
-            });
-
-            app.manage(AppState {
-                store,
-                ai_provider: Mutex::new(ai_provider),
-            });
+            // Open the database and resolve the AI provider off the main
+            // thread so the window shows while migrations run; commands are
+            // gated until `AppState` is managed.
+            app.manage(startup::StartupState::default());
+            tauri::async_runtime::spawn(startup::initialize(app.handle().clone(), db_path_str));
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![
+        .invoke_handler(startup::gate(tauri::generate_handler![
+            commands::startup_status,
+            commands::get_recent_command_timings,
+            commands::get_slow_operations,
             commands::list_projects,
             commands::create_project,
             commands::get_project,
             commands::delete_project,
+            commands::save_project_as_template,
+            commands::list_project_templates,
+            commands::create_project_from_template,
+            commands::create_example_project,
             commands::list_nodes,
+            commands::list_node_summaries,
+            commands::search_nodes_fuzzy,
+            commands::find_node_by_req_id,
+            commands::resolve_references,
             commands::upsert_node,
             commands::list_requirement_history,
+            commands::list_project_requirement_history,
+            commands::requirement_history_stats,
+            commands::prune_requirement_history,
+            commands::infer_verification_methods,
+            commands::node_usage,
             commands::delete_node,
+            commands::split_requirement,
+            commands::archive_nodes,
+            commands::unarchive_nodes,
             commands::upsert_edge,
             commands::delete_edge,
+            commands::set_edge_rationale,
             commands::edges_for_node,
+            commands::list_project_edges_filtered,
             commands::list_diagrams,
             commands::upsert_diagram,
+            commands::set_diagram_filters,
+            commands::get_diagram_ir,
+            commands::simplify_edge_routes,
             commands::diagram_elements,
             commands::upsert_diagram_element,
+            commands::begin_layout_session,
+            commands::end_layout_session,
+            commands::list_layout_sessions,
+            commands::restore_layout_session,
             commands::delete_diagram,
+            commands::list_style_presets,
+            commands::upsert_style_preset,
+            commands::delete_style_preset,
+            commands::apply_style_preset,
+            commands::clear_styles,
             commands::list_documents,
             commands::upsert_document,
             commands::delete_document,
             commands::list_document_sections,
             commands::list_project_document_sections,
+            commands::parse_document_sections,
+            commands::resolve_requirement_source,
+            commands::requirements_for_section,
             commands::upsert_document_section,
+            commands::bulk_upsert_document_sections,
+            commands::reorder_document_sections,
             commands::delete_document_section,
             commands::delete_document_sections,
+            commands::list_block_properties,
+            commands::list_project_block_properties,
+            commands::upsert_block_property,
+            commands::delete_block_property,
+            commands::list_glossary_terms,
+            commands::upsert_glossary_term,
+            commands::delete_glossary_term,
+            commands::terminology_check,
+            commands::apply_terminology_fixes,
+            commands::rollup_property,
+            commands::export_property_rollup_csv,
+            commands::block_interface_summary,
+            commands::effective_block_definition,
             commands::list_subsystem_knowledge,
             commands::upsert_subsystem_knowledge,
             commands::delete_subsystem_knowledge,
+            commands::resolve_knowledge_links,
+            commands::pages_mentioning,
             commands::list_subsystem_artifacts,
             commands::list_project_artifacts,
             commands::upsert_subsystem_artifact,
@@ -125,25 +149,61 @@ pub fn run() {
             commands::add_subsystem_activity,
             commands::get_setting,
             commands::set_setting,
+            commands::list_settings,
+            commands::delete_setting,
+            commands::set_settings,
             commands::validate_model,
             commands::export_markdown,
+            commands::export_csv,
+            commands::export_requirements_csv,
+            commands::import_requirements_csv,
             commands::export_json,
+            commands::import_json,
             commands::export_xmi,
+            commands::export_reqif,
+            commands::import_reqif,
+            commands::export_preview,
+            commands::export_graphml,
+            commands::graph_metrics,
+            commands::start_export_job,
+            commands::get_job,
+            commands::cancel_job,
+            commands::list_background_processes,
+            commands::kill_background_process,
+            commands::export_json_ld,
+            commands::export_requirement_dossier,
+            commands::export_vcrm,
+            commands::export_traceability_matrix,
+            commands::generate_rtm,
+            commands::list_document_templates,
+            commands::generate_document_from_template,
             commands::ai_available,
             commands::ai_provider_name,
             commands::set_anthropic_key,
+            commands::set_openai_key,
             commands::ollama_status,
             commands::set_ollama_config,
             commands::parse_requirements,
             commands::local_llm_available,
+            commands::local_llm_info,
             commands::llm_extract_requirements,
             commands::ai_quality_pass_requirements,
+            commands::ai_propose_requirement_split,
             commands::ai_suggest_requirement_allocations,
+            commands::find_duplicate_blocks,
+            commands::merge_blocks,
             commands::ai_extract_requirements,
+            commands::save_extraction_review_state,
+            commands::get_extraction_review_state,
+            commands::commit_extraction_review,
+            commands::renumber_requirements_by_section,
             commands::graphrag_extract_requirements,
+            commands::clear_extraction_cache,
             commands::ai_generate_diagram,
             commands::get_suspect_links,
             commands::resolve_suspect_link,
+            commands::suspect_link_summary,
+            commands::suspect_link_context,
             commands::add_req_comment,
             commands::get_req_comments,
             commands::get_comment_counts,
@@ -157,13 +217,52 @@ pub fn run() {
             commands::get_sim_params,
             commands::save_scenario,
             commands::list_scenarios,
+            commands::generate_scenario_events,
             commands::run_simulation,
             commands::get_simulation_result,
+            commands::readiness_check,
             commands::create_baseline,
             commands::list_baselines,
             commands::get_baseline,
             commands::delete_baseline,
-        ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+            commands::requirement_at_baseline,
+            commands::diff_diagram_against_baseline,
+            commands::coverage_metrics,
+            commands::coverage_trend,
+            commands::model_coverage,
+            commands::impact_analysis,
+            commands::portfolio_summary,
+            commands::export_portfolio_csv,
+            commands::import_external_items_csv,
+            commands::list_external_items,
+            commands::link_external_item,
+            commands::unlink_external_item,
+            commands::palette_index,
+            commands::attention_digest,
+            commands::who_is_active,
+            commands::get_identity,
+            commands::set_identity,
+            commands::set_read_only_fallback,
+            commands::list_audit_log,
+            commands::prune_audit_log,
+            commands::export_audit_log,
+            commands::db_maintenance,
+            commands::app_info,
+            commands::data_health,
+        ]))
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // Don't leave sidecars or llama-cli running after the app.
+            if let tauri::RunEvent::Exit = event {
+                if let Some(state) = app.try_state::<AppState>() {
+                    state.processes.lock().unwrap().kill_all();
+                    if !state.presence.read_only {
+                        let _ = tauri::async_runtime::block_on(
+                            state.store.clear_presence(&state.presence.machine),
+                        );
+                    }
+                }
+            }
+        });
 }
@@ -0,0 +1,136 @@
+/// Presence on a shared database. Every machine running the app keeps a
+/// `presence` row (its host name, the configured identity and a heartbeat
+/// timestamp), so that:
+///
+/// - writes are attributed: the store stamps the identity onto audit log,
+///   requirement history and comment rows that would otherwise say
+///   "system" or "user" (see [`Store::stamp_actor`]);
+/// - a second machine with the database open is noticed. Its recent
+///   heartbeat raises [`events::PRESENCE_CONFLICT`] and, when this machine
+///   opted into the read-only fallback, the database is reopened read-only
+///   at startup to stay out of WAL contention.
+///
+/// The identity and the fallback switch live on the machine's own row, so
+/// two users sharing a database keep separate settings. A read-only
+/// instance can't write its heartbeat and is invisible to the others.
+///
+/// [`Store::stamp_actor`]: crate::core::store::Store::stamp_actor
+/// [`events::PRESENCE_CONFLICT`]: crate::events::PRESENCE_CONFLICT
+use crate::core::model::Presence;
+use crate::core::store::Store;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// How often the running app refreshes its presence row.
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A heartbeat newer than this means the instance is still running.
+pub const ACTIVE_WINDOW_SECS: i64 = 120;
+
+/// This instance's side of presence, kept in `AppState`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocalPresence {
+    pub machine: String,
+    /// The database was opened read-only because another machine had it
+    /// open and the fallback is on.
+    pub read_only: bool,
+    pub started_at: DateTime<Utc>,
+}
+
+/// An instance seen within [`ACTIVE_WINDOW_SECS`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveInstance {
+    pub machine: String,
+    pub user: String,
+    pub last_seen: DateTime<Utc>,
+    /// This machine.
+    pub is_self: bool,
+}
+
+/// Payload of [`crate::events::PRESENCE_CONFLICT`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PresenceConflict {
+    /// The other machines with a recent heartbeat.
+    pub others: Vec<ActiveInstance>,
+    pub read_only: bool,
+}
+
+/// The host name: `COMPUTERNAME` on Windows, otherwise `HOSTNAME` or
+/// /etc/hostname, falling back to "unknown".
+pub fn machine_name() -> String {
+    let from_env = std::env::var("COMPUTERNAME").or_else(|_| std::env::var("HOSTNAME"));
+    from_env
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Presence rows seen within the active window, this machine first.
+pub fn active_instances(
+    rows: &[Presence],
+    machine: &str,
+    now: DateTime<Utc>,
+) -> Vec<ActiveInstance> {
+    let cutoff = now - chrono::Duration::seconds(ACTIVE_WINDOW_SECS);
+    let mut active: Vec<ActiveInstance> = rows
+        .iter()
+        .filter(|p| p.last_seen >= cutoff)
+        .map(|p| ActiveInstance {
+            machine: p.machine.clone(),
+            user: p.user.clone(),
+            last_seen: p.last_seen,
+            is_self: p.machine == machine,
+        })
+        .collect();
+    active.sort_by(|a, b| {
+        b.is_self
+            .cmp(&a.is_self)
+            .then_with(|| a.machine.cmp(&b.machine))
+    });
+    active
+}
+
+/// Other machines active on the database.
+pub async fn other_instances(store: &Store, machine: &str) -> anyhow::Result<Vec<ActiveInstance>> {
+    let rows = store.list_presence().await?;
+    Ok(active_instances(&rows, machine, Utc::now())
+        .into_iter()
+        .filter(|a| !a.is_self)
+        .collect())
+}
+
+/// Refresh this machine's heartbeat every [`HEARTBEAT_INTERVAL`] and emit
+/// [`crate::events::PRESENCE_CONFLICT`] whenever the set of other active
+/// machines changes, including the first tick when there are any.
+pub async fn run_heartbeat(app: tauri::AppHandle, store: Store, local: LocalPresence) {
+    use tauri::Emitter;
+    let mut interval = tokio::time::interval(HEARTBEAT_INTERVAL);
+    let mut reported: Vec<String> = Vec::new();
+    loop {
+        interval.tick().await;
+        if !local.read_only {
+            if let Err(e) = store.touch_presence(&local.machine, local.started_at).await {
+                eprintln!("warning: presence heartbeat failed: {e}");
+            }
+        }
+        let others = match other_instances(&store, &local.machine).await {
+            Ok(others) => others,
+            Err(e) => {
+                eprintln!("warning: presence lookup failed: {e}");
+                continue;
+            }
+        };
+        let machines: Vec<String> = others.iter().map(|o| o.machine.clone()).collect();
+        if machines != reported {
+            reported = machines;
+            let payload = PresenceConflict {
+                others,
+                read_only: local.read_only,
+            };
+            let _ = app.emit(crate::events::PRESENCE_CONFLICT, &payload);
+        }
+    }
+}
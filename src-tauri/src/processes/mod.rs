@@ -0,0 +1,117 @@
+/// Child processes spawned by commands — the Python sidecars and
+/// llama-cli — tracked so they can be listed, stopped by the user, and
+/// killed when the app exits instead of outliving it and holding on to
+/// model files and ports. A spawn site registers the child's pid and keeps
+/// the returned [`Registration`] until the child has exited; dropping it
+/// removes the entry.
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+/// `running` simulation results older than this at startup belong to a
+/// previous run of the app and are marked `orphaned`.
+pub const ORPHANED_RESULT_MINUTES: i64 = 15;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessKind {
+    /// A Python sidecar script (requirement parser, simulation engine).
+    Sidecar,
+    /// The bundled llama-cli.
+    Llama,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackgroundProcess {
+    pub id: Uuid,
+    pub pid: u32,
+    pub kind: ProcessKind,
+    /// What the process is doing, e.g. "simulation_engine.py".
+    pub label: String,
+    pub started_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Default)]
+pub struct ProcessRegistry {
+    processes: HashMap<Uuid, BackgroundProcess>,
+}
+
+impl ProcessRegistry {
+    pub fn list(&self) -> Vec<BackgroundProcess> {
+        let mut list: Vec<_> = self.processes.values().cloned().collect();
+        list.sort_by_key(|p| (p.started_at, p.id));
+        list
+    }
+
+    pub fn get(&self, id: Uuid) -> Option<BackgroundProcess> {
+        self.processes.get(&id).cloned()
+    }
+
+    /// Kill every registered process, e.g. on app exit. Entries are removed
+    /// as their spawn sites drop their registrations.
+    pub fn kill_all(&self) {
+        for process in self.processes.values() {
+            if let Err(e) = kill_pid(process.pid) {
+                eprintln!(
+                    "warning: failed to kill {} (pid {}): {e}",
+                    process.label, process.pid
+                );
+            }
+        }
+    }
+}
+
+/// Keeps a process listed while alive; unregisters it when dropped.
+pub struct Registration<'a> {
+    registry: &'a Mutex<ProcessRegistry>,
+    pub id: Uuid,
+}
+
+impl Drop for Registration<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut registry) = self.registry.lock() {
+            registry.processes.remove(&self.id);
+        }
+    }
+}
+
+/// Register a spawned child. A child without a pid has already exited and
+/// is not listed.
+pub fn register<'a>(
+    registry: &'a Mutex<ProcessRegistry>,
+    kind: ProcessKind,
+    label: &str,
+    pid: Option<u32>,
+) -> Option<Registration<'a>> {
+    let pid = pid?;
+    let process = BackgroundProcess {
+        id: Uuid::new_v4(),
+        pid,
+        kind,
+        label: label.to_string(),
+        started_at: Utc::now(),
+    };
+    let id = process.id;
+    registry.lock().unwrap().processes.insert(id, process);
+    Some(Registration { registry, id })
+}
+
+/// Forcefully stop `pid` (and, on Windows, its children) through the
+/// platform's own tool, so this works from sync shutdown hooks too.
+pub fn kill_pid(pid: u32) -> std::io::Result<()> {
+    #[cfg(windows)]
+    let status = std::process::Command::new("taskkill")
+        .args(["/PID", &pid.to_string(), "/T", "/F"])
+        .status()?;
+    #[cfg(not(windows))]
+    let status = std::process::Command::new("kill")
+        .args(["-9", &pid.to_string()])
+        .status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(std::io::Error::other(format!("kill exited with {status}")))
+    }
+}
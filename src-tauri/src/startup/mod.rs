@@ -0,0 +1,347 @@
+/// Deferred startup. The window opens straight away while the database is
+/// opened, migrated and the AI provider resolved on a background task;
+/// `AppState` is only managed once that finishes. Until then the
+/// invoke handler wrapped by [`gate`] rejects commands with
+/// [`CommandError::NotReady`] (or the startup failure, once there is one),
+/// so the frontend can wait for [`events::STARTUP_PROGRESS`] to report
+/// `ready` and retry.
+///
+/// [`events::STARTUP_PROGRESS`]: crate::events::STARTUP_PROGRESS
+use crate::ai::provider::{AIProvider, NullProvider};
+use crate::commands::CommandError;
+use crate::core::store::Store;
+use crate::{ai, commands, diagnostics, events, presence, processes, AppState};
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use tauri::ipc::Invoke;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+/// Commands answered before `AppState` exists.
+const UNGATED_COMMANDS: &[&str] = &[
+    "startup_status",
+    "get_recent_command_timings",
+    "get_slow_operations",
+];
+
+/// Model used when Ollama is selected but no model was saved.
+const DEFAULT_OLLAMA_MODEL: &str = "qwen2.5:7b";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StartupStep {
+    OpeningDatabase,
+    Migrating,
+    ResolvingProvider,
+    Ready,
+    Failed,
+}
+
+/// Payload of [`events::STARTUP_PROGRESS`]. `current`/`total` count
+/// migrations during [`StartupStep::Migrating`] and are 0 otherwise.
+///
+/// [`events::STARTUP_PROGRESS`]: crate::events::STARTUP_PROGRESS
+#[derive(Debug, Clone, Serialize)]
+pub struct StartupProgress {
+    pub step: StartupStep,
+    pub current: usize,
+    pub total: usize,
+    pub message: String,
+}
+
+impl StartupProgress {
+    fn step(step: StartupStep, message: impl Into<String>) -> Self {
+        Self {
+            step,
+            current: 0,
+            total: 0,
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderSource {
+    /// `ANTHROPIC_API_KEY` was set.
+    Environment,
+    /// The saved `ai.provider` setting.
+    Settings,
+    /// Nothing usable was configured.
+    Default,
+}
+
+/// Payload of [`events::STARTUP_AI_PROVIDER`]: which provider startup
+/// picked, and why it fell back to "none" when it did.
+///
+/// [`events::STARTUP_AI_PROVIDER`]: crate::events::STARTUP_AI_PROVIDER
+#[derive(Debug, Clone, Serialize)]
+pub struct AiProviderResolution {
+    /// "anthropic", "openai", "ollama" or "none".
+    pub provider: String,
+    pub source: ProviderSource,
+    /// Set when a configured provider couldn't be used, or a setting
+    /// couldn't be read.
+    pub fallback_reason: Option<String>,
+}
+
+/// What `startup_status` returns: the latest progress, plus the provider
+/// resolution once known, for a window that missed the events.
+#[derive(Debug, Clone, Serialize)]
+pub struct StartupStatus {
+    pub ready: bool,
+    pub progress: StartupProgress,
+    pub ai_provider: Option<AiProviderResolution>,
+    /// Why startup failed; the app stays unusable until restarted.
+    pub error: Option<String>,
+}
+
+/// Managed from the start, before `AppState`.
+pub struct StartupState(pub Mutex<StartupStatus>);
+
+impl Default for StartupState {
+    fn default() -> Self {
+        StartupState(Mutex::new(StartupStatus {
+            ready: false,
+            progress: StartupProgress::step(StartupStep::OpeningDatabase, "Opening database"),
+            ai_provider: None,
+            error: None,
+        }))
+    }
+}
+
+/// Wrap the generated invoke handler so commands arriving before
+/// `AppState` is managed are rejected with a typed error instead of
+/// Tauri's "state not managed" string.
+pub fn gate<R: Runtime>(
+    handler: impl Fn(Invoke<R>) -> bool + Send + Sync + 'static,
+) -> impl Fn(Invoke<R>) -> bool + Send + Sync + 'static {
+    move |invoke| {
+        let webview = invoke.message.webview();
+        if webview.try_state::<AppState>().is_none()
+            && !UNGATED_COMMANDS.contains(&invoke.message.command())
+        {
+            let error = webview.try_state::<StartupState>().and_then(|state| {
+                let status = state.0.lock().unwrap();
+                status.error.clone()
+            });
+            match error {
+                Some(detail) => invoke.resolver.reject(CommandError::Internal { detail }),
+                None => invoke.resolver.reject(CommandError::NotReady),
+            }
+            return true;
+        }
+        handler(invoke)
+    }
+}
+
+fn report(app: &AppHandle, progress: StartupProgress) {
+    if let Some(state) = app.try_state::<StartupState>() {
+        let mut status = state.0.lock().unwrap();
+        status.ready = progress.step == StartupStep::Ready;
+        status.progress = progress.clone();
+    }
+    let _ = app.emit(events::STARTUP_PROGRESS, progress);
+}
+
+fn fail(app: &AppHandle, error: String) {
+    eprintln!("error: startup failed: {error}");
+    if let Some(state) = app.try_state::<StartupState>() {
+        state.0.lock().unwrap().error = Some(error.clone());
+    }
+    report(app, StartupProgress::step(StartupStep::Failed, error));
+}
+
+/// Open the database, settle presence, resolve the AI provider and manage
+/// `AppState`, reporting each step. Spawned from `setup`.
+pub async fn initialize(app: AppHandle, db_path: String) {
+    report(
+        &app,
+        StartupProgress::step(StartupStep::OpeningDatabase, "Opening database"),
+    );
+    let progress_app = app.clone();
+    let opened = Store::open_with_progress(&db_path, |current, total, description| {
+        report(
+            &progress_app,
+            StartupProgress {
+                step: StartupStep::Migrating,
+                current,
+                total,
+                message: format!("Applying migration {current} of {total}: {description}"),
+            },
+        );
+    })
+    .await;
+    let mut store = match opened {
+        Ok(store) => store,
+        Err(e) => return fail(&app, format!("failed to open database: {e:#}")),
+    };
+    if let Some(warning) = store.version_warning() {
+        eprintln!("warning: {warning}");
+    }
+    // Command timings go to the log file only when the user opted in.
+    if let Some(timings) = app.try_state::<Arc<diagnostics::Diagnostics>>() {
+        match store.get_setting(diagnostics::ENABLED_SETTING, None).await {
+            Ok(raw) => timings.set_file_logging(diagnostics::enabled_from_setting(raw.as_deref())),
+            Err(e) => eprintln!("warning: failed to read {}: {e}", diagnostics::ENABLED_SETTING),
+        }
+    }
+
+    // Attribute writes to this machine's identity, and step back to
+    // read-only when another machine has the database open and this
+    // one opted into that.
+    let machine = presence::machine_name();
+    let own = store.get_presence(&machine).await.unwrap_or_else(|e| {
+        eprintln!("warning: failed to read presence: {e}");
+        None
+    });
+    let others = presence::other_instances(&store, &machine)
+        .await
+        .unwrap_or_default();
+    if own.as_ref().is_some_and(|p| p.read_only_fallback) && !others.is_empty() {
+        match Store::open_read_only(&db_path).await {
+            Ok(read_only) => store = read_only,
+            Err(e) => eprintln!("warning: failed to reopen the database read-only: {e}"),
+        }
+    }
+    store.set_identity(own.as_ref().map(|p| p.user.as_str()));
+    let local_presence = presence::LocalPresence {
+        machine,
+        read_only: store.is_read_only(),
+        started_at: chrono::Utc::now(),
+    };
+    if !store.is_read_only() {
+        if let Err(e) = store
+            .touch_presence(&local_presence.machine, local_presence.started_at)
+            .await
+        {
+            eprintln!("warning: presence heartbeat failed: {e}");
+        }
+    }
+    tauri::async_runtime::spawn(presence::run_heartbeat(
+        app.clone(),
+        store.clone(),
+        local_presence.clone(),
+    ));
+
+    // Simulations left `running` by a killed app would look stuck forever.
+    let orphaned_before =
+        chrono::Utc::now() - chrono::Duration::minutes(processes::ORPHANED_RESULT_MINUTES);
+    if !store.is_read_only() {
+        if let Err(e) = store
+            .mark_orphaned_simulation_results(orphaned_before)
+            .await
+        {
+            eprintln!("warning: failed to mark orphaned simulation results: {e}");
+        }
+    }
+
+    report(
+        &app,
+        StartupProgress::step(StartupStep::ResolvingProvider, "Resolving AI provider"),
+    );
+    let (ai_provider, resolution) = resolve_ai_provider(&store).await;
+    if let Some(reason) = &resolution.fallback_reason {
+        eprintln!("warning: AI provider fell back to none: {reason}");
+    }
+    if let Some(state) = app.try_state::<StartupState>() {
+        state.0.lock().unwrap().ai_provider = Some(resolution.clone());
+    }
+    let _ = app.emit(events::STARTUP_AI_PROVIDER, resolution);
+
+    app.manage(AppState {
+        store: store.clone(),
+        ai_provider: Mutex::new(ai_provider),
+        local_llm: Mutex::new(None),
+        validation_cache: Mutex::new(Default::default()),
+        jobs: Mutex::new(Default::default()),
+        baseline_index: Mutex::new(Default::default()),
+        processes: Mutex::new(Default::default()),
+        presence: local_presence,
+        layout_journal: Mutex::new(Default::default()),
+    });
+    report(&app, StartupProgress::step(StartupStep::Ready, "Ready"));
+
+    tauri::async_runtime::spawn(commands::emit_startup_digest(app, store));
+}
+
+/// Env var → saved `ai.provider` → [`NullProvider`]. A setting that can't
+/// be read falls back to none with the error as the reason rather than
+/// being treated as unset.
+pub async fn resolve_ai_provider(store: &Store) -> (Arc<dyn AIProvider>, AiProviderResolution) {
+    // Env var always wins
+    let env_key = std::env::var("ANTHROPIC_API_KEY").unwrap_or_default();
+    if !env_key.is_empty() {
+        return (
+            Arc::new(ai::anthropic::AnthropicProvider::new(env_key)),
+            AiProviderResolution {
+                provider: "anthropic".to_string(),
+                source: ProviderSource::Environment,
+                fallback_reason: None,
+            },
+        );
+    }
+
+    let none = |reason: Option<String>| -> (Arc<dyn AIProvider>, AiProviderResolution) {
+        let source = if reason.is_some() {
+            ProviderSource::Settings
+        } else {
+            ProviderSource::Default
+        };
+        (
+            Arc::new(NullProvider),
+            AiProviderResolution {
+                provider: "none".to_string(),
+                source,
+                fallback_reason: reason,
+            },
+        )
+    };
+
+    let saved = match store.get_setting("ai.provider", None).await {
+        Ok(saved) => saved.unwrap_or_default(),
+        Err(e) => return none(Some(format!("failed to read ai.provider: {e}"))),
+    };
+    let provider: Arc<dyn AIProvider> = match saved.trim().trim_matches('"') {
+        "" => return none(None),
+        "anthropic" => match store.get_setting("ai.anthropic.api_key", None).await {
+            Ok(Some(key)) if !key.is_empty() => {
+                Arc::new(ai::anthropic::AnthropicProvider::new(key))
+            }
+            Ok(_) => {
+                return none(Some(
+                    "Anthropic is selected but no API key is saved".to_string(),
+                ))
+            }
+            Err(e) => return none(Some(format!("failed to read the Anthropic API key: {e}"))),
+        },
+        "openai" => match store.get_setting("ai.openai.api_key", None).await {
+            Ok(Some(key)) if !key.is_empty() => Arc::new(ai::openai::OpenAIProvider::new(key)),
+            Ok(_) => {
+                return none(Some(
+                    "OpenAI is selected but no API key is saved".to_string(),
+                ))
+            }
+            Err(e) => return none(Some(format!("failed to read the OpenAI API key: {e}"))),
+        },
+        "ollama" => {
+            let model = match store.get_setting("ai.ollama.model", None).await {
+                Ok(model) => model.unwrap_or_else(|| DEFAULT_OLLAMA_MODEL.to_string()),
+                Err(e) => return none(Some(format!("failed to read ai.ollama.model: {e}"))),
+            };
+            let base_url = match store.get_setting("ai.ollama.base_url", None).await {
+                Ok(base_url) => base_url,
+                Err(e) => return none(Some(format!("failed to read ai.ollama.base_url: {e}"))),
+            };
+            Arc::new(ai::ollama::OllamaProvider::new(model, base_url))
+        }
+        other => return none(Some(format!("unknown ai.provider \"{other}\""))),
+    };
+    (
+        provider,
+        AiProviderResolution {
+            provider: saved.trim().trim_matches('"').to_string(),
+            source: ProviderSource::Settings,
+            fallback_reason: None,
+        },
+    )
+}
@@ -0,0 +1,241 @@
+/// Fixtures for tests: an in-memory [`Store`] and model values with fresh
+/// ids and every required field filled. Builders return plain structs, so
+/// a test that needs something specific sets the field itself.
+use crate::core::model::*;
+use crate::core::store::Store;
+use chrono::Utc;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// An empty, fully migrated in-memory store.
+pub async fn store() -> Store {
+    Store::open_in_memory().await.expect("open in-memory store")
+}
+
+/// A database file in the system temp directory, deleted (with its WAL
+/// files) on drop. For tests that need a second connection to see
+/// committed data only, or the same data after reopening.
+pub struct TempDb {
+    pub path: String,
+}
+
+impl Default for TempDb {
+    fn default() -> Self {
+        let path = std::env::temp_dir().join(format!("systemproduct-test-{}.db", Uuid::new_v4()));
+        Self {
+            path: path.to_string_lossy().into_owned(),
+        }
+    }
+}
+
+impl TempDb {
+    /// Open (and on first use create and migrate) the database.
+    pub async fn open(&self) -> Store {
+        Store::open(&self.path).await.expect("open on-disk store")
+    }
+}
+
+impl Drop for TempDb {
+    fn drop(&mut self) {
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{suffix}", self.path));
+        }
+    }
+}
+
+pub fn project(name: &str) -> Project {
+    let now = Utc::now();
+    Project {
+        id: Uuid::new_v4(),
+        name: name.to_string(),
+        description: String::new(),
+        created_at: now,
+        modified_at: now,
+    }
+}
+
+/// A node of `kind` carrying that kind's default data.
+pub fn node(project_id: Uuid, kind: NodeKind, name: &str) -> Node {
+    let now = Utc::now();
+    Node {
+        id: Uuid::new_v4(),
+        project_id,
+        data: default_data(&kind),
+        kind,
+        name: name.to_string(),
+        description: String::new(),
+        meta: HashMap::new(),
+        archived: false,
+        created_at: now,
+        modified_at: now,
+    }
+}
+
+pub fn block(project_id: Uuid, name: &str) -> Node {
+    node(project_id, NodeKind::Block, name)
+}
+
+pub fn requirement(project_id: Uuid, req_id: &str, text: &str) -> Node {
+    let mut node = node(project_id, NodeKind::Requirement, req_id);
+    node.data = NodeData::Requirement(RequirementData {
+        req_id: Some(req_id.to_string()),
+        text: Some(text.to_string()),
+        priority: RequirementPriority::Shall,
+        ..Default::default()
+    });
+    node
+}
+
+pub fn test_case(project_id: Uuid, name: &str) -> Node {
+    node(project_id, NodeKind::TestCase, name)
+}
+
+/// The data a freshly created node of `kind` gets.
+pub fn default_data(kind: &NodeKind) -> NodeData {
+    match kind {
+        NodeKind::Requirement => NodeData::Requirement(Default::default()),
+        NodeKind::Block => NodeData::Block(Default::default()),
+        NodeKind::Interface => NodeData::Interface,
+        NodeKind::Port => NodeData::Port(Default::default()),
+        NodeKind::UseCase => NodeData::UseCase(Default::default()),
+        NodeKind::Actor => NodeData::Actor,
+        NodeKind::TestCase => NodeData::TestCase(Default::default()),
+        NodeKind::Stakeholder => NodeData::Stakeholder,
+        NodeKind::Function => NodeData::Function,
+        NodeKind::External => NodeData::External,
+        NodeKind::ValueType => NodeData::ValueType(Default::default()),
+        NodeKind::ConstraintBlock => NodeData::ConstraintBlock(Default::default()),
+        NodeKind::State => NodeData::State(Default::default()),
+    }
+}
+
+pub fn edge(project_id: Uuid, kind: EdgeKind, source_id: Uuid, target_id: Uuid) -> Edge {
+    let now = Utc::now();
+    Edge {
+        id: Uuid::new_v4(),
+        project_id,
+        kind,
+        source_id,
+        target_id,
+        label: String::new(),
+        meta: HashMap::new(),
+        created_at: now,
+        modified_at: now,
+    }
+}
+
+pub fn diagram(project_id: Uuid, kind: DiagramKind, name: &str) -> Diagram {
+    let now = Utc::now();
+    Diagram {
+        id: Uuid::new_v4(),
+        project_id,
+        kind,
+        name: name.to_string(),
+        description: String::new(),
+        layout_options: HashMap::new(),
+        created_at: now,
+        modified_at: now,
+    }
+}
+
+pub fn diagram_element(diagram_id: Uuid, node_id: Uuid, x: f64, y: f64) -> DiagramElement {
+    let now = Utc::now();
+    DiagramElement {
+        id: Uuid::new_v4(),
+        diagram_id,
+        node_id,
+        x,
+        y,
+        width: 160.0,
+        height: 80.0,
+        collapsed: false,
+        style_overrides: HashMap::new(),
+        created_at: now,
+        modified_at: now,
+    }
+}
+
+/// What [`seed_small_project`] wrote.
+pub struct SmallProject {
+    pub project: Project,
+    /// "Power Distribution" and "Flight Computer".
+    pub blocks: Vec<Node>,
+    /// REQ-001, satisfied by the first block and verified by `test_case`.
+    pub requirement: Node,
+    pub test_case: Node,
+    /// «satisfies», «verifies» and «composes» (flight computer → power).
+    pub edges: Vec<Edge>,
+    /// A BDD holding both blocks.
+    pub diagram: Diagram,
+}
+
+/// A project with two blocks, one requirement satisfied and verified, and
+/// a BDD showing the blocks.
+pub async fn seed_small_project(store: &Store) -> SmallProject {
+    let project = project("Small Project");
+    store
+        .create_project(&project, "system")
+        .await
+        .expect("create project");
+    let blocks = vec![
+        block(project.id, "Power Distribution"),
+        block(project.id, "Flight Computer"),
+    ];
+    let requirement = requirement(
+        project.id,
+        "REQ-001",
+        "The system shall distribute 28 V power to every subsystem.",
+    );
+    let test_case = test_case(project.id, "TC-001 Power-on test");
+    for node in blocks.iter().chain([&requirement, &test_case]) {
+        store.upsert_node(node).await.expect("upsert node");
+    }
+
+    let edges = vec![
+        edge(
+            project.id,
+            EdgeKind::Satisfies,
+            blocks[0].id,
+            requirement.id,
+        ),
+        edge(project.id, EdgeKind::Verifies, test_case.id, requirement.id),
+        edge(project.id, EdgeKind::Composes, blocks[1].id, blocks[0].id),
+    ];
+    for edge in &edges {
+        store.upsert_edge(edge).await.expect("upsert edge");
+    }
+
+    let diagram = diagram(project.id, DiagramKind::Bdd, "System BDD");
+    store
+        .upsert_diagram(&diagram, "system")
+        .await
+        .expect("upsert diagram");
+    for (i, block) in blocks.iter().enumerate() {
+        let element = diagram_element(diagram.id, block.id, 40.0 + 240.0 * i as f64, 40.0);
+        store
+            .upsert_diagram_element(&element, "system")
+            .await
+            .expect("place block");
+    }
+
+    SmallProject {
+        project,
+        blocks,
+        requirement,
+        test_case,
+        edges,
+        diagram,
+    }
+}
+
+/// The example UAV project (see `core::example`), written through
+/// `Store::create_example_project`. Larger than [`seed_small_project`],
+/// with documents, state machines and a simulation scenario.
+pub async fn seed_example_project(store: &Store) -> crate::core::example::ExampleProject {
+    let example = crate::core::example::build(Utc::now());
+    store
+        .create_example_project(&example, "system")
+        .await
+        .expect("create example project");
+    example
+}
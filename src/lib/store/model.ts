@@ -6,7 +6,22 @@
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { writable, derived, get } from "svelte/store";
-import type { Project, Node, Edge, Diagram, ValidationIssue } from "$lib/types";
+import type {
+  Project,
+  Node,
+  Edge,
+  Diagram,
+  ValidationDelta,
+  ValidationIssue,
+  ActiveInstance,
+  IdentityInfo,
+  PresenceConflict,
+  DigestReady,
+  StartupStatus,
+  StartupProgress,
+  AiProviderResolution,
+} from "$lib/types";
+import { isCommandError } from "$lib/types";
 
 // ── Raw stores ────────────────────────────────────────────────────────────────
 
@@ -17,6 +32,12 @@ export const diagrams = writable<Diagram[]>([]);
 export const validationIssues = writable<ValidationIssue[]>([]);
 export const aiAvailable = writable<boolean>(false);
 export const readOnly = writable<boolean>(false);
+/** Other machines with this database open (shared-drive setups). */
+export const otherInstances = writable<ActiveInstance[]>([]);
+/** Startup attention digest for the project opened last, if anything is overdue. */
+export const attentionDigest = writable<DigestReady | null>(null);
+/** Deferred startup: database migrations and AI provider resolution. */
+export const startupStatus = writable<StartupStatus | null>(null);
 const requirementHistoryByNode = writable<
   Map<string, RequirementHistoryEntry[]>
 >(new Map());
@@ -294,12 +315,18 @@ function canWriteBaseline(): boolean {
   return activeProfileFromState(acl).role !== "viewer";
 }
 
+/** Who the audit log records for a change made now: the active profile
+ *  when access control is on, else "system" (stamped by the backend with
+ *  the configured identity). */
+export function currentActor(): string {
+  const acl = get(accessControl);
+  return acl.enabled ? activeProfileFromState(acl).id : "system";
+}
+
 function stampNodeAuditMeta(node: Node): Node {
   const meta = { ...(node.meta ?? {}) } as Record<string, unknown>;
   if (!meta.actor) {
-    const acl = get(accessControl);
-    const profile = activeProfileFromState(acl);
-    meta.actor = acl.enabled ? profile.id : "system";
+    meta.actor = currentActor();
   }
   if (!meta.change_source) {
     meta.change_source = "manual";
@@ -641,7 +668,8 @@ export async function loadProject(projectId: string) {
   const [project, projectNodes, projectDiagrams, available] = await Promise.all(
     [
       invoke<Project>("get_project", { id: projectId }),
-      invoke<Node[]>("list_nodes", { projectId }),
+      // Archived nodes stay loaded so links through them still resolve.
+      invoke<Node[]>("list_nodes", { projectId, includeArchived: true }),
       invoke<Diagram[]>("list_diagrams", { projectId }),
       invoke<boolean>("ai_available"),
     ],
@@ -714,7 +742,7 @@ export async function removeNode(nodeId: string) {
       return next;
     });
   }
-  await invoke("delete_node", { id: nodeId });
+  await invoke("delete_node", { id: nodeId, actor: currentActor() });
 }
 
 export async function saveEdge(edge: Edge) {
@@ -729,7 +757,9 @@ export async function saveEdge(edge: Edge) {
     }
     return [...es, edge];
   });
-  await invoke("upsert_edge", { edge });
+  const meta = { ...(edge.meta ?? {}) };
+  if (!meta.actor) meta.actor = currentActor();
+  await invoke("upsert_edge", { edge: { ...edge, meta } });
 }
 
 export async function removeEdge(edgeId: string) {
@@ -737,7 +767,7 @@ export async function removeEdge(edgeId: string) {
   if (!edge || !canEditEdge(edge)) return;
   // Optimistic update
   edges.update((es) => es.filter((e) => e.id !== edgeId));
-  await invoke("delete_edge", { id: edgeId });
+  await invoke("delete_edge", { id: edgeId, actor: currentActor() });
 }
 
 export function getRequirementHistory(
@@ -770,15 +800,98 @@ async function refreshValidation(projectId: string) {
 
 // ── Backend event listeners ───────────────────────────────────────────────────
 
+/**
+ * Resolves once startup has managed the backend state. Commands invoked
+ * before then reject with `not_ready`.
+ */
+export function whenReady(): Promise<void> {
+  return new Promise((resolve) => {
+    const unsubscribe = startupStatus.subscribe((status) => {
+      if (!status?.ready) return;
+      queueMicrotask(() => unsubscribe());
+      resolve();
+    });
+  });
+}
+
+/** `invoke`, retried after startup finishes if it was too early. */
+export async function invokeWhenReady<T>(
+  command: string,
+  args?: Record<string, unknown>,
+): Promise<T> {
+  try {
+    return await invoke<T>(command, args);
+  } catch (e) {
+    if (!isCommandError(e) || e.code !== "not_ready") throw e;
+    await whenReady();
+    return invoke<T>(command, args);
+  }
+}
+
+async function initStartupListeners() {
+  const progress = listen<StartupProgress>("startup:progress", (event) => {
+    startupStatus.update((s) => ({
+      ready: event.payload.step === "ready",
+      progress: event.payload,
+      ai_provider: s?.ai_provider ?? null,
+      error: event.payload.step === "failed" ? event.payload.message : null,
+    }));
+  });
+
+  const provider = listen<AiProviderResolution>("startup:ai_provider", (event) => {
+    startupStatus.update((s) => (s ? { ...s, ai_provider: event.payload } : s));
+    if (event.payload.fallback_reason) {
+      console.warn(`AI provider unavailable: ${event.payload.fallback_reason}`);
+    }
+  });
+
+  // Startup may have progressed, or finished, before these listeners existed.
+  await Promise.all([progress, provider]);
+  invoke<StartupStatus>("startup_status")
+    .then((status) => {
+      startupStatus.update((s) => (s?.ready ? s : status));
+    })
+    .catch(() => {});
+}
+
 export function initEventListeners() {
+  void initStartupListeners();
+
   listen("model:changed", () => {
     const p = get(currentProject);
     if (p) refreshValidation(p.id);
   });
 
-  listen<ValidationIssue[]>("validation:updated", (event) => {
-    validationIssues.set(event.payload);
+  listen<ValidationDelta>("validation:updated", (event) => {
+    const delta = event.payload;
+    if (delta.project_id !== get(currentProject)?.id) return;
+    const replaced = new Set([
+      ...delta.removed_issue_ids,
+      ...delta.added_issues.map((i) => i.id),
+    ]);
+    validationIssues.update((issues) => [
+      ...issues.filter((i) => !replaced.has(i.id)),
+      ...delta.added_issues,
+    ]);
+  });
+
+  listen<PresenceConflict>("presence:conflict", (event) => {
+    otherInstances.set(event.payload.others);
   });
+
+  listen<DigestReady>("digest:ready", (event) => {
+    attentionDigest.set(event.payload);
+  });
+
+  // The first conflict event may fire before this listener exists.
+  invokeWhenReady<ActiveInstance[]>("who_is_active")
+    .then((active) => otherInstances.set(active.filter((a) => !a.is_self)))
+    .catch(() => {});
+  invokeWhenReady<IdentityInfo>("get_identity")
+    .then((info) => {
+      if (info.read_only) readOnly.set(true);
+    })
+    .catch(() => {});
 }
 
 // ── Helpers ───────────────────────────────────────────────────────────────────
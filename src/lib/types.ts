@@ -53,6 +53,8 @@ export interface RequirementData {
   source?: string;
   allocations?: string[];
   verification_method?: "analysis" | "test" | "inspection" | "demonstration";
+  /** A label from the project's classification taxonomy, or "unknown". */
+  classification?: string;
 }
 
 export interface BlockData {
@@ -125,10 +127,27 @@ export interface Node {
   description: string;
   data: NodeData;
   meta: Record<string, unknown>;
+  /** Obsolete but kept for traceability; changed only through
+   *  `archive_nodes` / `unarchive_nodes`. */
+  archived?: boolean;
   created_at: string;
   modified_at: string;
 }
 
+/** `list_nodes` filter; imported nodes may carry meta.original_created_at. */
+export type CreatedOrigin = "imported" | "authored";
+
+/** Slim node row from `list_node_summaries`; `req_id` and `status` are set
+ *  for requirements only. */
+export interface NodeSummary {
+  id: string;
+  kind: NodeKind;
+  name: string;
+  req_id: string | null;
+  status: RequirementData["status"] | null;
+  archived: boolean;
+}
+
 export interface Edge {
   id: string;
   project_id: string;
@@ -184,6 +203,53 @@ export interface DocumentSection {
   unit?: string;
   position: number;
   created_at: string;
+  parent_section_id?: string;
+  section_level?: number;
+  /** Only filled by list_document_sections with tree: true. */
+  children?: DocumentSection[];
+}
+
+/** Returned by bulk_upsert_document_sections and reorder_document_sections. */
+/** What `import_json` wrote. */
+export interface NativeImportSummary {
+  project_id: string;
+  nodes_imported: number;
+  edges_imported: number;
+  /** Nodes and edges left out of the file, and why. */
+  warnings: string[];
+}
+
+/** What `import_reqif` wrote. */
+export interface ReqIfImportSummary {
+  nodes_created: number;
+  edges_created: number;
+  /** Objects that matched an existing node instead of creating one. */
+  matched: number;
+  /** Objects and relations left out, with the reason. */
+  skipped: string[];
+}
+
+/** A CSV row that couldn't be imported; `row` counts the header as 1. */
+export interface SkippedRow {
+  row: number;
+  reason: string;
+}
+
+export type CsvImportMode = "create" | "upsert_by_req_id";
+
+export interface RequirementsCsvImportSummary {
+  created: number;
+  updated: number;
+  /** Rows matching a requirement that already had those values. */
+  unchanged: number;
+  errors: SkippedRow[];
+}
+
+export interface SectionWriteReport {
+  document_id: string;
+  /** Rows inserted, updated or repositioned. */
+  sections: number;
+  elapsed_ms: number;
 }
 
 export interface SubsystemKnowledgePage {
@@ -194,10 +260,26 @@ export interface SubsystemKnowledgePage {
   body_format?: KnowledgeBodyFormat;
   created_at: string;
   updated_at: string;
+  /** A [[link]] target was renamed since the links were last resolved. */
+  stale_links?: boolean;
 }
 
 export type KnowledgeBodyFormat = "plain" | "markdown" | "rich";
 
+/** A [[link]] in a knowledge page, from resolve_knowledge_links. */
+export interface ResolvedLink {
+  /** Character offset and length, brackets included. */
+  offset: number;
+  length: number;
+  text: string;
+  kind: NodeKind | null;
+  target: string;
+  alias: string | null;
+  node_id: string | null;
+  node_name: string | null;
+  matched_by: "req_id" | "name" | "fuzzy" | null;
+}
+
 export interface SubsystemArtifact {
   id: string;
   subsystem_id: string;
@@ -238,6 +320,18 @@ export interface DiagramElement {
   style_overrides: Record<string, unknown>;
 }
 
+export type LayoutSessionStatus = "open" | "committed" | "cancelled";
+
+export interface LayoutSession {
+  id: string;
+  diagram_id: string;
+  status: LayoutSessionStatus;
+  /** Elements captured when the session began. */
+  element_count: number;
+  started_at: string;
+  ended_at: string | null;
+}
+
 export interface ValidationIssue {
   id: string;
   severity: "error" | "warning" | "info";
@@ -247,6 +341,14 @@ export interface ValidationIssue {
   edge_id?: string;
 }
 
+/** Payload of the "validation:updated" event: the change to the
+ *  project's issues since the last one. */
+export interface ValidationDelta {
+  project_id: string;
+  added_issues: ValidationIssue[];
+  removed_issue_ids: string[];
+}
+
 export interface AiSuggestion {
   id: string;
   project_id: string;
@@ -294,6 +396,14 @@ export interface IREdge {
   has_suggestion: boolean;
 }
 
+/** Per-edge result of `simplify_edge_routes`. */
+export interface RouteSimplification {
+  edge_id: string;
+  points_before: number;
+  points_after: number;
+  points_removed: number;
+}
+
 export interface DiagramIR {
   diagram_id: string;
   kind: DiagramKind;
@@ -401,8 +511,288 @@ export interface SimulationResult {
   id: string;
   scenario_id: string;
   ran_at: string;
-  status: "pending" | "running" | "complete" | "error";
+  status: "pending" | "running" | "complete" | "error" | "orphaned";
   metrics: Record<string, BlockSimMetrics>;
   timeline: SimulationTimelineEvent[];
   errors: string[];
 }
+
+// ── Command errors ───────────────────────────────────────────────────────────
+
+export type CommandErrorCode =
+  | "not_found"
+  | "invalid_uuid"
+  | "conflict"
+  | "validation"
+  | "project_locked"
+  | "provider_unavailable"
+  | "not_ready"
+  | "io"
+  | "internal";
+
+export interface FieldIssue {
+  field: string | null;
+  code: string;
+  message: string;
+}
+
+/** Detail of an `invalid_uuid` error; `field` is the argument name. */
+export interface InvalidUuidDetail {
+  field: string | null;
+  value: string;
+}
+
+/** Rejection value of migrated commands; others still reject with a string. */
+export interface CommandError {
+  code: CommandErrorCode;
+  message: string;
+  detail: string | FieldIssue[] | InvalidUuidDetail | null;
+}
+
+export function isCommandError(e: unknown): e is CommandError {
+  return (
+    typeof e === "object" && e !== null && "code" in e && "message" in e
+  );
+}
+
+/** Human-readable text for any command rejection. */
+export function commandErrorMessage(e: unknown): string {
+  return isCommandError(e) ? e.message : String(e);
+}
+
+// ── Presence ──────────────────────────────────────────────────────────────────
+
+export interface ActiveInstance {
+  machine: string;
+  /** Empty when that machine has no identity set. */
+  user: string;
+  last_seen: string;
+  is_self: boolean;
+}
+
+/** Payload of the "presence:conflict" event. */
+export interface PresenceConflict {
+  others: ActiveInstance[];
+  read_only: boolean;
+}
+
+export type AttentionKind =
+  | "stale_comments"
+  | "open_reviews"
+  | "suspect_links"
+  | "stale_drafts";
+
+export interface DigestSample {
+  id: string;
+  node_id: string | null;
+  label: string;
+  since: string;
+}
+
+export interface AttentionItem {
+  kind: AttentionKind;
+  count: number;
+  worst_age_days: number;
+  sample_items: DigestSample[];
+}
+
+/** Payload of the "digest:ready" event. */
+export interface DigestReady {
+  project_id: string;
+  items: AttentionItem[];
+}
+
+export type ReadinessProfile = "baseline" | "delivery";
+
+export type ReadinessCriterion =
+  | "validation_errors"
+  | "suspect_links"
+  | "unapproved_requirements"
+  | "open_reviews"
+  | "verified_coverage";
+
+export interface CriterionResult {
+  criterion: ReadinessCriterion;
+  passed: boolean;
+  /** A count, or a percentage for verified_coverage. */
+  value: number;
+  threshold: number;
+  samples: { node_id: string | null; label: string }[];
+}
+
+export interface ReadinessReport {
+  profile: ReadinessProfile;
+  passed: boolean;
+  criteria: CriterionResult[];
+}
+
+export type PaletteEntityType =
+  | "node"
+  | "diagram"
+  | "document"
+  | "scenario"
+  | "review_session";
+
+export interface PaletteEntry {
+  id: string;
+  entity_type: PaletteEntityType;
+  label: string;
+  sublabel?: string;
+}
+
+/** Result of `palette_index`; cache until the next "model:changed". */
+export interface PaletteIndex {
+  entries: PaletteEntry[];
+  truncated: boolean;
+  palette_updated_at: string;
+}
+
+/** One project's row in `portfolio_summary`. */
+export interface ProjectHealth {
+  project_id: string;
+  name: string;
+  requirements: { draft: number; approved: number; obsolete: number };
+  coverage: {
+    total_requirements: number;
+    satisfied_pct: number;
+    verified_pct: number;
+    approved_pct: number;
+    verification_method_pct: number;
+    allocated_pct: number;
+  };
+  unresolved_suspect_links: number;
+  open_reviews: number;
+  last_baseline_at: string | null;
+  last_activity_at: string;
+}
+
+export type StartupStep =
+  | "opening_database"
+  | "migrating"
+  | "resolving_provider"
+  | "ready"
+  | "failed";
+
+/** Payload of `startup:progress`; current/total count migrations. */
+export interface StartupProgress {
+  step: StartupStep;
+  current: number;
+  total: number;
+  message: string;
+}
+
+/** Payload of `startup:ai_provider`. */
+export interface AiProviderResolution {
+  provider: "anthropic" | "ollama" | "none";
+  source: "environment" | "settings" | "default";
+  fallback_reason: string | null;
+}
+
+export interface StartupStatus {
+  ready: boolean;
+  progress: StartupProgress;
+  ai_provider: AiProviderResolution | null;
+  error: string | null;
+}
+
+export type VcrmFormat = "csv" | "markdown" | "xlsx";
+
+/** Result of `export_vcrm`: requirement–test case rows and requirements with no verifies link. */
+export interface VcrmExport {
+  rows: number;
+  unverified: number;
+}
+
+export interface DuplicatePair {
+  a_id: string;
+  a_name: string;
+  b_id: string;
+  b_name: string;
+  score: number;
+  reason: "same_tokens" | "initials" | "similar";
+}
+
+/** Result of `merge_blocks`. */
+export interface BlockMergeReport {
+  kept_id: string;
+  kept_name: string;
+  removed: [string, string][];
+  edges_moved: number;
+  edges_dropped: number;
+  diagram_elements_moved: number;
+  diagram_elements_dropped: number;
+  requirements_reallocated: string[];
+  knowledge_pages_moved: number;
+  knowledge_links_moved: number;
+  artifacts_moved: number;
+  activity_moved: number;
+  block_properties_moved: number;
+  external_links_moved: number;
+}
+
+export type OperationKind = "command" | "query";
+
+/** One timed command or store query (`get_recent_command_timings`,
+ *  `get_slow_operations`). Carries no names or text. */
+export interface OperationTiming {
+  kind: OperationKind;
+  name: string;
+  project_id: string | null;
+  /** For a query, the command it ran under. */
+  command: string | null;
+  started_at: string;
+  duration_ms: number;
+  ok: boolean;
+  rows: number | null;
+}
+
+/** `graphrag` in `graphrag_extract_requirements` output, and payload of
+ *  the "graphrag:status" event emitted before the graph is built. */
+export interface GraphRagStatus {
+  used: boolean;
+  entities: number;
+  relationships: number;
+  skipped_reason: string | null;
+  context_chars: number;
+}
+
+export interface IdentityInfo {
+  machine: string;
+  identity: string | null;
+  read_only_fallback: boolean;
+  read_only: boolean;
+}
+
+// ── Export preview ────────────────────────────────────────────────────────────
+
+/** Narrows json/xmi exports; omitted fields keep the whole project. */
+export interface ExportScope {
+  node_kinds?: NodeKind[];
+  exclude_archived?: boolean;
+}
+
+export interface ExportWarning {
+  code:
+    | "UNKNOWN_KINDS"
+    | "TEXT_OVER_LIMIT"
+    | "DANGLING_EDGES_DROPPED"
+    | "NOT_EXPORTED";
+  message: string;
+  count: number;
+}
+
+export interface ExportPreview {
+  format: "json" | "xmi";
+  counts: {
+    nodes: number;
+    edges: number;
+    block_properties: number;
+    unrecognized: number;
+    diagrams: number;
+    documents: number;
+  };
+  estimated_bytes: number;
+  /** True when the whole scope was rendered to measure it. */
+  exact: boolean;
+  warnings: ExportWarning[];
+}